@@ -0,0 +1,216 @@
+//! Structured architecture models.
+//!
+//! The architecture summary is prose for people. For tools (docs generators,
+//! onboarding bots) the same understanding is also requested as JSON: the
+//! project's components, what each is responsible for, where it lives and
+//! which other components it depends on. The response is constrained by a
+//! JSON schema, then validated so dependencies only name known components,
+//! and stored next to the summary.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+
+/// Maximum number of components kept in a model
+pub const MAX_COMPONENTS: usize = 40;
+
+/// A project's architecture as components and their dependencies
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchitectureModel {
+    /// One or two sentences on what the project does
+    pub summary: String,
+    pub components: Vec<Component>,
+}
+
+/// A module, service or layer of a project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Component {
+    pub name: String,
+    /// What the component is responsible for
+    pub responsibility: String,
+    /// Directories or files implementing the component, relative to the repository
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Names of the components this one uses
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+/// JSON schema for the architecture model response
+pub fn architecture_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "summary": {
+                "type": "string",
+                "description": "One or two sentences on what the project does"
+            },
+            "components": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "description": "Short unique name of the component, e.g. the module name"
+                        },
+                        "responsibility": {
+                            "type": "string",
+                            "description": "What the component is responsible for"
+                        },
+                        "paths": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Directories or files implementing the component, relative to the repository root"
+                        },
+                        "depends_on": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Names of other components in this list that the component uses"
+                        }
+                    },
+                    "required": ["name", "responsibility", "paths", "depends_on"]
+                }
+            }
+        },
+        "required": ["summary", "components"]
+    })
+}
+
+/// Prompt asking for the architecture model of `repo_name`, from its
+/// architecture summary and the analyses it was written from
+pub fn architecture_model_prompt(
+    repo_name: &str,
+    summary: &str,
+    analyses: &str,
+    output_language: &str,
+) -> String {
+    format!(
+        "You are describing the architecture of a codebase called '{}' for tools that \
+         generate documentation. Below are its architecture summary and the analyses it was \
+         written from.\n\n\
+         # Architecture Summary\n{}\n\n\
+         # Code Architecture Analyses\n{}\n\n\
+         List the project's main components (modules, services or layers), at most {}. For \
+         each give a short unique name, its responsibility, the directories or files that \
+         implement it and the names of the other listed components it depends on. Only list \
+         dependencies between components you listed.\n\n\
+         {}",
+        repo_name,
+        summary.trim(),
+        analyses.trim(),
+        MAX_COMPONENTS,
+        super::respond_in(output_language, false)
+    )
+}
+
+/// Trim a model from the LLM and drop what doesn't hold together: unnamed and
+/// duplicate components, and dependencies on itself or on unknown components.
+///
+/// Fails if no component is left.
+pub fn validate_model(model: ArchitectureModel) -> Result<ArchitectureModel, String> {
+    let mut seen = HashSet::new();
+    let mut components: Vec<Component> = model
+        .components
+        .into_iter()
+        .map(|c| Component {
+            name: c.name.trim().to_string(),
+            responsibility: c.responsibility.trim().to_string(),
+            paths: trimmed(c.paths),
+            depends_on: trimmed(c.depends_on),
+        })
+        .filter(|c| !c.name.is_empty() && seen.insert(c.name.to_lowercase()))
+        .take(MAX_COMPONENTS)
+        .collect();
+    if components.is_empty() {
+        return Err("The model lists no components".to_string());
+    }
+
+    // Match dependencies case-insensitively, and name them as the component is named
+    let names: Vec<String> = components.iter().map(|c| c.name.clone()).collect();
+    for component in &mut components {
+        let own = component.name.to_lowercase();
+        let mut depends_on = Vec::new();
+        for dependency in &component.depends_on {
+            let lower = dependency.to_lowercase();
+            if lower == own {
+                continue;
+            }
+            if let Some(name) = names.iter().find(|n| n.to_lowercase() == lower) {
+                if !depends_on.contains(name) {
+                    depends_on.push(name.clone());
+                }
+            }
+        }
+        component.depends_on = depends_on;
+    }
+
+    Ok(ArchitectureModel {
+        summary: model.summary.trim().to_string(),
+        components,
+    })
+}
+
+fn trimmed(values: Vec<String>) -> Vec<String> {
+    values
+        .into_iter()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(name: &str, depends_on: &[&str]) -> Component {
+        Component {
+            name: name.to_string(),
+            responsibility: " Does things ".to_string(),
+            paths: vec![" src/x ".to_string(), "".to_string()],
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_model() {
+        let model = validate_model(ArchitectureModel {
+            summary: " A daemon ".to_string(),
+            components: vec![
+                component(" Web ", &["db", "daemon", "web", "unknown", "DB"]),
+                component("DB", &[]),
+                component("", &["DB"]),
+                component("web", &[]),
+                component("Daemon", &["DB"]),
+            ],
+        })
+        .unwrap();
+
+        assert_eq!(model.summary, "A daemon");
+        let names: Vec<_> = model.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Web", "DB", "Daemon"]);
+        assert_eq!(model.components[0].depends_on, ["DB", "Daemon"]);
+        assert_eq!(model.components[0].responsibility, "Does things");
+        assert_eq!(model.components[0].paths, ["src/x"]);
+        assert_eq!(model.components[2].depends_on, ["DB"]);
+    }
+
+    #[test]
+    fn test_validate_model_without_components() {
+        let model = ArchitectureModel {
+            summary: "Empty".to_string(),
+            components: vec![component(" ", &[])],
+        };
+        assert!(validate_model(model).is_err());
+    }
+
+    #[test]
+    fn test_architecture_model_deserialization() {
+        let model: ArchitectureModel = serde_json::from_str(
+            r#"{"summary": "s", "components": [{"name": "n", "responsibility": "r"}]}"#,
+        )
+        .unwrap();
+        assert!(model.components[0].paths.is_empty());
+        assert!(model.components[0].depends_on.is_empty());
+    }
+}
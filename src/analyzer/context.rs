@@ -0,0 +1,539 @@
+//! Project context for file analysis.
+//!
+//! On its own a file says little about how it is used. With `[context]`
+//! enabled in `noctum.toml`, code understanding prompts also get the
+//! repository's architecture summary, the architecture analysis of the file's
+//! parent module and the public signatures of the project files it imports,
+//! trimmed to a token budget. Imports are resolved without a model: `use
+//! crate::` paths for Rust, relative specifiers for TypeScript/JavaScript and
+//! `import` statements for Java.
+
+use crate::daemon::truncate_at_char_boundary;
+use crate::language::Language;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Rough number of characters per token, for budgeting
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Maximum number of imported files whose signatures are included
+const MAX_IMPORTS: usize = 10;
+
+/// Maximum length of one signature
+const MAX_SIGNATURE_CHARS: usize = 200;
+
+/// Extensions tried for extensionless TypeScript/JavaScript imports
+const SCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+
+/// Context gathered for one file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileContext {
+    /// The repository's architecture summary
+    pub architecture: Option<String>,
+    /// Path (relative to the repository) and architecture analysis of the
+    /// file's parent module
+    pub module: Option<(String, String)>,
+    /// Paths (relative to the repository) and public signatures of the
+    /// project files the file imports
+    pub imports: Vec<(String, Vec<String>)>,
+}
+
+impl FileContext {
+    /// Render the context as a prompt section of at most about `max_tokens`,
+    /// or None if there is none. Each part gets an equal share of the budget;
+    /// what one leaves unused goes to the parts after it.
+    pub fn render(&self, max_tokens: usize) -> Option<String> {
+        let mut parts: Vec<(String, String)> = Vec::new();
+        if let Some(ref summary) = self.architecture {
+            parts.push((
+                "Architecture summary".to_string(),
+                summary.trim().to_string(),
+            ));
+        }
+        if let Some((ref path, ref summary)) = self.module {
+            parts.push((
+                format!("Parent module ({})", path),
+                summary.trim().to_string(),
+            ));
+        }
+        let signatures: String = self
+            .imports
+            .iter()
+            .filter(|(_, signatures)| !signatures.is_empty())
+            .map(|(path, signatures)| format!("{}:\n{}\n", path, signatures.join("\n")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !signatures.is_empty() {
+            parts.push(("Signatures of imported files".to_string(), signatures));
+        }
+        parts.retain(|(_, text)| !text.is_empty());
+        if parts.is_empty() {
+            return None;
+        }
+
+        let mut remaining = max_tokens * CHARS_PER_TOKEN;
+        let mut sections = Vec::new();
+        let count = parts.len();
+        for (index, (title, text)) in parts.into_iter().enumerate() {
+            let share = remaining / (count - index);
+            let text = if text.len() > share {
+                format!("{}\n(truncated)", truncate_at_char_boundary(&text, share))
+            } else {
+                text
+            };
+            remaining = remaining.saturating_sub(text.len());
+            sections.push(format!("## {}\n{}", title, text));
+        }
+
+        Some(format!(
+            "# Project Context\n\
+             The following describes the project the file belongs to. Use it to understand \
+             how the file is used, but only report on the file itself.\n\n{}",
+            sections.join("\n\n")
+        ))
+    }
+}
+
+/// What the contexts of a repository's files are gathered from, loaded once
+/// per analysis pass
+#[derive(Debug, Clone, Default)]
+pub struct ContextSources {
+    /// Repository root, which paths in the context are relative to
+    pub root: PathBuf,
+    /// The latest architecture summary
+    pub architecture: Option<String>,
+    /// Latest architecture analyses by file path
+    pub modules: HashMap<String, String>,
+}
+
+impl ContextSources {
+    /// Gather the context of a file. Imported files are read from disk,
+    /// except those `allowed` rejects by their path relative to the root.
+    pub fn context_for(
+        &self,
+        file: &Path,
+        content: &str,
+        language: Language,
+        allowed: impl Fn(&Path) -> bool,
+    ) -> FileContext {
+        let relative = |path: &Path| {
+            path.strip_prefix(&self.root)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+
+        let module = parent_module(file, language).and_then(|module| {
+            let summary = self.modules.get(&module.to_string_lossy().to_string())?;
+            Some((relative(&module), summary.clone()))
+        });
+        let imports = imported_files(file, content, language)
+            .into_iter()
+            .filter(|path| allowed(path.strip_prefix(&self.root).unwrap_or(path)))
+            .filter_map(|path| {
+                let content = std::fs::read_to_string(&path).ok()?;
+                Some((relative(&path), signatures(&content, language)))
+            })
+            .collect();
+
+        FileContext {
+            architecture: self.architecture.clone(),
+            module,
+            imports,
+        }
+    }
+}
+
+/// The module file a file belongs to: `mod.rs`, `lib.rs`, `main.rs` or the
+/// `<dir>.rs` of its directory for Rust, the directory's `index` file for
+/// TypeScript/JavaScript. Module files themselves belong to the module of the
+/// directory above. None for other languages or if there is no such file.
+pub fn parent_module(file: &Path, language: Language) -> Option<PathBuf> {
+    let dir = file.parent()?;
+    let stem = file.file_stem()?.to_str()?;
+    match language {
+        Language::Rust => {
+            let is_module_file = matches!(stem, "mod" | "lib" | "main");
+            let module_dir = if is_module_file { dir.parent()? } else { dir };
+            let mut candidates = vec![module_dir.join("mod.rs")];
+            if module_dir.file_name().is_some_and(|name| name == "src") {
+                candidates.push(module_dir.join("lib.rs"));
+                candidates.push(module_dir.join("main.rs"));
+            } else if let (Some(parent), Some(name)) = (module_dir.parent(), module_dir.file_name())
+            {
+                candidates.push(parent.join(format!("{}.rs", name.to_string_lossy())));
+            }
+            candidates
+                .into_iter()
+                .find(|candidate| candidate != file && candidate.is_file())
+        }
+        Language::TypeScript => {
+            let module_dir = if stem == "index" { dir.parent()? } else { dir };
+            SCRIPT_EXTENSIONS
+                .iter()
+                .map(|ext| module_dir.join(format!("index.{}", ext)))
+                .find(|candidate| candidate != file && candidate.is_file())
+        }
+        Language::Java | Language::Command(_) => None,
+    }
+}
+
+/// The project files a file imports directly, in the order of their imports
+pub fn imported_files(file: &Path, content: &str, language: Language) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let resolved = match language {
+        Language::Rust => rust_imports(file, content),
+        Language::TypeScript => script_imports(file, content),
+        Language::Java => java_imports(file, content),
+        Language::Command(_) => Vec::new(),
+    };
+    for path in resolved {
+        if path != file && !files.contains(&path) {
+            files.push(path);
+        }
+    }
+    files.truncate(MAX_IMPORTS);
+    files
+}
+
+/// Declarations of a file's public items, without their bodies
+pub fn signatures(content: &str, language: Language) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| match language {
+            Language::Rust => {
+                let item = strip_visibility(line);
+                item.len() < line.len()
+                    && !item.starts_with("use ")
+                    && !item.starts_with("mod ")
+                    && !item.starts_with("//")
+                    && item.split_whitespace().any(|word| {
+                        matches!(
+                            word,
+                            "fn" | "struct" | "enum" | "trait" | "type" | "const" | "static"
+                        )
+                    })
+            }
+            Language::TypeScript => {
+                line.starts_with("export ")
+                    && !line.starts_with("export {")
+                    && !line.starts_with("export *")
+                    && !line.contains(" from ")
+            }
+            Language::Java => {
+                line.starts_with("public ") && !line.contains('=') && !line.ends_with(';')
+            }
+            Language::Command(_) => false,
+        })
+        .map(|line| {
+            let declaration = line
+                .split(['{', ';'])
+                .next()
+                .and_then(|declaration| declaration.split(" = ").next())
+                .unwrap_or(line)
+                .trim_end()
+                .trim_end_matches("=>")
+                .trim_end_matches('=')
+                .trim_end();
+            truncate_at_char_boundary(declaration, MAX_SIGNATURE_CHARS).to_string()
+        })
+        .filter(|signature| !signature.is_empty())
+        .collect()
+}
+
+fn strip_visibility(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix("pub(") {
+        return rest
+            .split_once(')')
+            .map_or(line, |(_, rest)| rest.trim_start());
+    }
+    line.strip_prefix("pub ").unwrap_or(line)
+}
+
+/// Files of the modules named by `use crate::` statements: the longest
+/// prefix of each path that is a module file under the crate's `src`
+fn rust_imports(file: &Path, content: &str) -> Vec<PathBuf> {
+    let Some(src) = file.ancestors().find(|dir| dir.ends_with("src")) else {
+        return Vec::new();
+    };
+    crate::diagram::crate_use_paths(content)
+        .iter()
+        .filter_map(|import| {
+            let segments: Vec<&str> = import.split("::").skip(1).collect();
+            (1..=segments.len()).rev().find_map(|len| {
+                let module = segments[..len].join("/");
+                [
+                    src.join(format!("{}.rs", module)),
+                    src.join(&module).join("mod.rs"),
+                ]
+                .into_iter()
+                .find(|candidate| candidate.is_file())
+            })
+        })
+        .collect()
+}
+
+/// Files named by relative `import`/`export ... from` specifiers and
+/// `require` calls, with the extension or `index` file filled in
+fn script_imports(file: &Path, content: &str) -> Vec<PathBuf> {
+    let Some(dir) = file.parent() else {
+        return Vec::new();
+    };
+    let mut specifiers = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let quoted = if line.starts_with("import ") || line.starts_with("export ") {
+            line.rsplit_once(" from ")
+                .map(|(_, rest)| rest)
+                .or_else(|| line.strip_prefix("import "))
+        } else {
+            line.split_once("require(").map(|(_, rest)| rest)
+        };
+        let Some(quoted) = quoted.map(str::trim_start) else {
+            continue;
+        };
+        let Some(quote) = quoted.chars().next().filter(|c| matches!(c, '"' | '\'')) else {
+            continue;
+        };
+        if let Some(specifier) = quoted[1..].split(quote).next() {
+            if specifier.starts_with('.') {
+                specifiers.push(specifier.to_string());
+            }
+        }
+    }
+
+    specifiers
+        .iter()
+        .filter_map(|specifier| {
+            let base = dir.join(specifier);
+            // TypeScript ESM imports name the compiled `.js` file
+            let stem = base.with_extension("");
+            std::iter::once(base.clone())
+                .chain(
+                    SCRIPT_EXTENSIONS
+                        .iter()
+                        .map(|ext| PathBuf::from(format!("{}.{}", stem.display(), ext))),
+                )
+                .chain(
+                    SCRIPT_EXTENSIONS
+                        .iter()
+                        .map(|ext| base.join(format!("index.{}", ext))),
+                )
+                .find(|candidate| candidate.is_file())
+        })
+        .collect()
+}
+
+/// Files of the classes named by `import` statements, found under the source
+/// root implied by the file's `package` declaration
+fn java_imports(file: &Path, content: &str) -> Vec<PathBuf> {
+    let statement = |keyword: &str, line: &str| {
+        line.trim()
+            .strip_prefix(keyword)
+            .map(|rest| rest.trim().trim_end_matches(';').trim().to_string())
+    };
+    let package = content
+        .lines()
+        .find_map(|line| statement("package ", line))
+        .unwrap_or_default();
+    let mut root = file.parent().map(Path::to_path_buf).unwrap_or_default();
+    for _ in package.split('.').filter(|s| !s.is_empty()) {
+        if !root.pop() {
+            return Vec::new();
+        }
+    }
+
+    content
+        .lines()
+        .filter_map(|line| statement("import ", line))
+        .filter(|import| !import.starts_with("static ") && !import.ends_with('*'))
+        .filter_map(|import| {
+            let class = root.join(import.replace('.', "/"));
+            ["java", "kt"]
+                .iter()
+                .map(|ext| class.with_extension(ext))
+                .find(|candidate| candidate.is_file())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) -> PathBuf {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_rust_context() {
+        let repo = TempDir::new().unwrap();
+        let root = repo.path();
+        let main = write(root, "src/main.rs", "mod db;\nmod web;\n");
+        let db = write(
+            root,
+            "src/db/mod.rs",
+            "pub struct Database {\n    pool: Pool,\n}\n\nimpl Database {\n    pub async fn new(path: &Path) -> Result<Self> {\n        todo!()\n    }\n    fn private() {}\n}\n",
+        );
+        let models = write(root, "src/db/models.rs", "pub struct Repository;\n");
+        let handlers = write(
+            root,
+            "src/web/handlers.rs",
+            "use crate::db::{Database, models::Repository};\nuse std::sync::Arc;\n",
+        );
+        let web = write(root, "src/web.rs", "pub mod handlers;\n");
+
+        assert_eq!(
+            imported_files(
+                &handlers,
+                &std::fs::read_to_string(&handlers).unwrap(),
+                Language::Rust
+            ),
+            [db.clone(), models.clone()]
+        );
+        assert_eq!(parent_module(&handlers, Language::Rust), Some(web.clone()));
+        assert_eq!(parent_module(&models, Language::Rust), Some(db.clone()));
+        assert_eq!(parent_module(&db, Language::Rust), Some(main.clone()));
+        assert_eq!(parent_module(&main, Language::Rust), None);
+
+        assert_eq!(
+            signatures(&std::fs::read_to_string(&db).unwrap(), Language::Rust),
+            [
+                "pub struct Database",
+                "pub async fn new(path: &Path) -> Result<Self>"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_script_context() {
+        let repo = TempDir::new().unwrap();
+        let root = repo.path();
+        let index = write(root, "src/index.ts", "export * from './app';\n");
+        let util = write(
+            root,
+            "src/util/index.ts",
+            "export function slug(s: string): string {\n  return s;\n}\nexport const MAX = 3;\nexport { slug as default };\n",
+        );
+        let api = write(root, "src/api.ts", "export class Api {}\n");
+        let app = write(
+            root,
+            "src/app.ts",
+            "import { slug } from './util';\nimport { Api } from \"./api.js\";\nimport React from 'react';\nconst x = require('../missing');\n",
+        );
+
+        assert_eq!(
+            imported_files(
+                &app,
+                &std::fs::read_to_string(&app).unwrap(),
+                Language::TypeScript
+            ),
+            [util.clone(), api]
+        );
+        assert_eq!(parent_module(&app, Language::TypeScript), Some(index));
+        assert_eq!(
+            signatures(
+                &std::fs::read_to_string(&util).unwrap(),
+                Language::TypeScript
+            ),
+            [
+                "export function slug(s: string): string",
+                "export const MAX"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_java_imports() {
+        let repo = TempDir::new().unwrap();
+        let root = repo.path();
+        let user = write(
+            root,
+            "src/main/java/com/acme/model/User.java",
+            "package com.acme.model;\n\npublic class User {\n    public String name() {\n",
+        );
+        let service = write(
+            root,
+            "src/main/java/com/acme/service/UserService.java",
+            "package com.acme.service;\n\nimport com.acme.model.User;\nimport java.util.List;\n",
+        );
+
+        assert_eq!(
+            imported_files(
+                &service,
+                &std::fs::read_to_string(&service).unwrap(),
+                Language::Java
+            ),
+            std::slice::from_ref(&user)
+        );
+        assert_eq!(
+            signatures(&std::fs::read_to_string(&user).unwrap(), Language::Java),
+            ["public class User", "public String name()"]
+        );
+    }
+
+    #[test]
+    fn test_context_for() {
+        let repo = TempDir::new().unwrap();
+        let root = repo.path();
+        let db = write(root, "src/db/mod.rs", "pub struct Database;\n");
+        let secret = write(root, "src/secret.rs", "pub const KEY: &str = \"x\";\n");
+        let models = write(
+            root,
+            "src/db/models.rs",
+            "use crate::db::Database;\nuse crate::secret::KEY;\n",
+        );
+        let sources = ContextSources {
+            root: root.to_path_buf(),
+            architecture: Some("A layered app".to_string()),
+            modules: HashMap::from([(
+                db.to_string_lossy().to_string(),
+                "Stores results".to_string(),
+            )]),
+        };
+
+        let context = sources.context_for(
+            &models,
+            &std::fs::read_to_string(&models).unwrap(),
+            Language::Rust,
+            |path| path != secret.strip_prefix(root).unwrap(),
+        );
+        assert_eq!(
+            context,
+            FileContext {
+                architecture: Some("A layered app".to_string()),
+                module: Some(("src/db/mod.rs".to_string(), "Stores results".to_string())),
+                imports: vec![(
+                    "src/db/mod.rs".to_string(),
+                    vec!["pub struct Database".to_string()]
+                )],
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_under_budget() {
+        assert_eq!(FileContext::default().render(100), None);
+
+        let context = FileContext {
+            architecture: Some("~".repeat(1000)),
+            module: Some(("src/db/mod.rs".to_string(), "Stores results".to_string())),
+            imports: vec![(
+                "src/config/mod.rs".to_string(),
+                vec!["pub struct Config".to_string()],
+            )],
+        };
+        let rendered = context.render(100).unwrap();
+        assert!(rendered.contains("## Parent module (src/db/mod.rs)\nStores results"));
+        assert!(rendered.contains("src/config/mod.rs:\npub struct Config"));
+        assert!(rendered.contains("(truncated)"));
+        // The architecture summary gets its share of the 400 characters
+        let architecture = rendered.matches('~').count();
+        assert!((130..=140).contains(&architecture), "{}", architecture);
+    }
+}
@@ -0,0 +1,156 @@
+//! Delta analysis: update a file's previous summary from a diff.
+//!
+//! Re-analyzing a changed file from scratch resends the whole file to the LLM.
+//! In delta mode the prompt instead carries the previous summary and the
+//! unified diff since it was written, and the model returns an updated summary
+//! plus a short change-log entry. Large rewrites fall back to full analysis,
+//! since the diff would be no cheaper than the file itself.
+
+use similar::TextDiff;
+
+/// Marker opening the change-log section of a delta response
+pub(super) const CHANGES_MARKER: &str = "## Changes";
+
+/// Marker opening the updated summary section of a delta response
+pub(super) const SUMMARY_MARKER: &str = "## Summary";
+
+/// Maximum diff size (bytes) for delta analysis
+const MAX_DIFF_BYTES: usize = 20_000;
+
+/// Context for updating a previous analysis
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaContext {
+    /// The stored summary of the previous version
+    pub previous_summary: String,
+    /// Unified diff from the previous version to the current one
+    pub diff: String,
+}
+
+impl DeltaContext {
+    /// Build a delta context, or None when the change is too large for a diff
+    /// to be worth sending (the file is then analyzed from scratch).
+    pub fn new(file_path: &str, previous_summary: &str, old: &str, new: &str) -> Option<Self> {
+        let diff = unified_diff(file_path, old, new);
+        if diff.is_empty() || diff.len() > MAX_DIFF_BYTES || diff.len() > new.len() / 2 {
+            return None;
+        }
+        Some(Self {
+            previous_summary: previous_summary.to_string(),
+            diff,
+        })
+    }
+
+    /// Prompt asking the LLM to update the previous summary
+    pub fn prompt(&self, file_path: &str, output_language: &str) -> String {
+        format!(
+            "The file {} changed since it was last analyzed. Below is the previous analysis \
+             and the unified diff of the change.\n\n\
+             # Previous Analysis\n{}\n\n\
+             # Diff\n```diff\n{}\n```\n\n\
+             Respond with exactly two sections:\n\
+             {}\nA short bullet list describing what changed and why it matters.\n\n\
+             {}\nThe complete updated analysis of the file, in the same structure as the previous \
+             analysis (purpose, key functions/structs, potential issues, recommendations). Keep \
+             everything from the previous analysis that is still accurate.\n\n\
+             {}",
+            file_path,
+            self.previous_summary,
+            self.diff,
+            CHANGES_MARKER,
+            SUMMARY_MARKER,
+            super::respond_in(output_language, true)
+        )
+    }
+}
+
+/// An updated analysis parsed from a delta response
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaResult {
+    pub summary: String,
+    /// Change-log entry (None if the model omitted it)
+    pub changes: Option<String>,
+}
+
+/// Split a delta response into the change-log entry and the updated summary.
+///
+/// Responses that don't follow the requested format are used as the summary.
+pub fn parse_delta_response(response: &str) -> DeltaResult {
+    let Some(summary_start) = response.find(SUMMARY_MARKER) else {
+        return DeltaResult {
+            summary: response.trim().to_string(),
+            changes: None,
+        };
+    };
+
+    let summary = response[summary_start + SUMMARY_MARKER.len()..]
+        .trim()
+        .to_string();
+    let changes = response[..summary_start]
+        .find(CHANGES_MARKER)
+        .map(|start| response[start + CHANGES_MARKER.len()..summary_start].trim())
+        .filter(|changes| !changes.is_empty())
+        .map(str::to_string);
+
+    if summary.is_empty() {
+        return DeltaResult {
+            summary: response.trim().to_string(),
+            changes: None,
+        };
+    }
+    DeltaResult { summary, changes }
+}
+
+/// Unified diff between two versions of a file (empty when identical)
+pub fn unified_diff(file_path: &str, old: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", file_path), &format!("b/{}", file_path))
+        .to_string()
+}
+
+/// Unified diff between two texts without file headers (empty when identical)
+pub fn text_diff(old: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff("src/lib.rs", "a\nb\nc\n", "a\nB\nc\n");
+        assert!(diff.starts_with("--- a/src/lib.rs\n+++ b/src/lib.rs\n"));
+        assert!(diff.contains("-b\n+B\n"));
+        assert!(unified_diff("src/lib.rs", "a\n", "a\n").is_empty());
+    }
+
+    #[test]
+    fn test_delta_context_skips_large_changes() {
+        let old: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        let small = old.replace("line 50\n", "line fifty\n");
+        let context = DeltaContext::new("f.rs", "Summary", &old, &small).unwrap();
+        assert!(context.prompt("f.rs", "English").contains("+line fifty"));
+
+        let rewritten: String = (0..100).map(|i| format!("other {}\n", i)).collect();
+        assert!(DeltaContext::new("f.rs", "Summary", &old, &rewritten).is_none());
+        assert!(DeltaContext::new("f.rs", "Summary", &old, &old).is_none());
+    }
+
+    #[test]
+    fn test_parse_delta_response() {
+        let parsed = parse_delta_response(
+            "## Changes\n- Added retry logic\n\n## Summary\nHandles HTTP requests with retries.",
+        );
+        assert_eq!(parsed.changes.as_deref(), Some("- Added retry logic"));
+        assert_eq!(parsed.summary, "Handles HTTP requests with retries.");
+
+        let unstructured = parse_delta_response("Just a summary.");
+        assert_eq!(unstructured.summary, "Just a summary.");
+        assert_eq!(unstructured.changes, None);
+    }
+}
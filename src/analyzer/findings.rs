@@ -0,0 +1,308 @@
+//! Structured findings extracted from a file analysis.
+//!
+//! The code understanding pass produces free-form Markdown, which reads well
+//! but can't be filtered or counted. A second, schema-constrained request asks
+//! the model to list the concrete problems from that analysis as findings with
+//! a severity, category, line range and recommendation. The findings are
+//! stored alongside the analysis result, and the result's severity is the most
+//! severe finding rather than a keyword guess.
+//!
+//! Findings are deduplicated across scans by a [`fingerprint`] of the file,
+//! the normalized title and the code the finding points at, so a problem
+//! reported again updates the existing finding instead of adding another.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+/// Severities, from most to least severe
+pub const SEVERITIES: &[&str] = &["error", "warning", "info"];
+
+/// Categories a finding can be filed under
+pub const CATEGORIES: &[&str] = &[
+    "bug",
+    "security",
+    "performance",
+    "error_handling",
+    "maintainability",
+    "documentation",
+    "dead_code",
+];
+
+/// Maximum number of findings kept per file
+const MAX_FINDINGS: usize = 20;
+
+/// A problem found in a file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructuredFinding {
+    /// One-line summary
+    pub title: String,
+    /// One of [`SEVERITIES`]
+    pub severity: String,
+    /// One of [`CATEGORIES`]
+    pub category: String,
+    /// First line the finding refers to (1-based)
+    #[serde(default)]
+    pub line_start: Option<u32>,
+    /// Last line the finding refers to (1-based, inclusive)
+    #[serde(default)]
+    pub line_end: Option<u32>,
+    /// What to do about it
+    pub recommendation: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FindingsResponse {
+    pub findings: Vec<StructuredFinding>,
+}
+
+/// JSON schema for the findings response
+pub fn findings_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "findings": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "title": {
+                            "type": "string",
+                            "description": "One-line summary of the problem"
+                        },
+                        "severity": {
+                            "type": "string",
+                            "enum": SEVERITIES,
+                            "description": "'error' for bugs and vulnerabilities, 'warning' for likely problems, 'info' for minor improvements"
+                        },
+                        "category": {
+                            "type": "string",
+                            "enum": CATEGORIES
+                        },
+                        "line_start": {
+                            "type": ["integer", "null"],
+                            "description": "First line (1-based) the problem refers to, if it is localized"
+                        },
+                        "line_end": {
+                            "type": ["integer", "null"],
+                            "description": "Last line (1-based) the problem refers to"
+                        },
+                        "recommendation": {
+                            "type": "string",
+                            "description": "Concrete change that resolves the problem"
+                        }
+                    },
+                    "required": ["title", "severity", "category", "recommendation"]
+                }
+            }
+        },
+        "required": ["findings"]
+    })
+}
+
+/// Prompt asking for the findings of an analysis of `content`
+pub fn findings_prompt(
+    file_path: &str,
+    content: &str,
+    analysis: &str,
+    output_language: &str,
+) -> String {
+    let numbered: String = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:4} | {}\n", i + 1, line))
+        .collect();
+    format!(
+        "Below is a source file and an analysis of it. List the concrete problems the analysis \
+         identifies (bugs, security issues, missing error handling, performance problems, \
+         maintainability and documentation issues) as findings. Only include problems that \
+         are supported by the code; return an empty list if there are none. Refer to the line \
+         numbers shown in the code.\n\n\
+         File: {}\n\n```\n{}```\n\n# Analysis\n{}\n\n{}",
+        file_path,
+        numbered,
+        analysis,
+        super::respond_in(output_language, true)
+    )
+}
+
+/// Drop empty findings, coerce unknown severities and categories, and clamp
+/// line ranges to the file's `line_count`
+pub fn normalize_findings(
+    findings: Vec<StructuredFinding>,
+    line_count: usize,
+) -> Vec<StructuredFinding> {
+    let line_count = line_count as u32;
+    findings
+        .into_iter()
+        .filter(|f| !f.title.trim().is_empty())
+        .map(|mut f| {
+            f.title = f.title.trim().to_string();
+            f.recommendation = f.recommendation.trim().to_string();
+            f.severity = f.severity.trim().to_lowercase();
+            if !SEVERITIES.contains(&f.severity.as_str()) {
+                f.severity = "info".to_string();
+            }
+            f.category = f.category.trim().to_lowercase();
+            if !CATEGORIES.contains(&f.category.as_str()) {
+                f.category = "maintainability".to_string();
+            }
+
+            f.line_start = f.line_start.filter(|&l| l >= 1 && l <= line_count);
+            f.line_end = match f.line_start {
+                Some(start) => Some(f.line_end.unwrap_or(start).clamp(start, line_count)),
+                None => None,
+            };
+            f
+        })
+        .take(MAX_FINDINGS)
+        .collect()
+}
+
+/// Severity of an analysis with these findings (`info` when there are none)
+pub fn overall_severity(findings: &[StructuredFinding]) -> &'static str {
+    SEVERITIES
+        .iter()
+        .find(|severity| findings.iter().any(|f| f.severity == **severity))
+        .copied()
+        .unwrap_or("info")
+}
+
+/// Fingerprint identifying a finding across scans: a hash of the file path,
+/// the title with case, digits and punctuation removed, and a hash of the
+/// lines it refers to with whitespace collapsed. Line numbers themselves are
+/// left out, so edits elsewhere in the file don't change it.
+pub fn fingerprint(file_path: &str, finding: &StructuredFinding, content: &str) -> String {
+    let title: Vec<String> = finding
+        .title
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let snippet = match finding.line_start {
+        Some(start) => {
+            let start = start.max(1) as usize;
+            let end = finding
+                .line_end
+                .map_or(start, |end| end as usize)
+                .max(start);
+            content
+                .lines()
+                .skip(start - 1)
+                .take(end - start + 1)
+                .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        None => String::new(),
+    };
+    let snippet_hash = hex::encode(Sha256::digest(snippet.as_bytes()));
+
+    hex::encode(Sha256::digest(
+        format!("{}\0{}\0{}", file_path, title.join(" "), snippet_hash).as_bytes(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(
+        severity: &str,
+        category: &str,
+        lines: (Option<u32>, Option<u32>),
+    ) -> StructuredFinding {
+        StructuredFinding {
+            title: " Unchecked index ".to_string(),
+            severity: severity.to_string(),
+            category: category.to_string(),
+            line_start: lines.0,
+            line_end: lines.1,
+            recommendation: "Use get()".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_findings() {
+        let mut empty = finding("error", "bug", (None, None));
+        empty.title = "  ".to_string();
+
+        let findings = normalize_findings(
+            vec![
+                finding("Error", "bug", (Some(3), None)),
+                finding("critical", "style", (Some(8), Some(50))),
+                finding("info", "security", (Some(99), Some(100))),
+                finding("warning", "bug", (Some(5), Some(2))),
+                empty,
+            ],
+            10,
+        );
+
+        assert_eq!(findings.len(), 4);
+        assert_eq!(findings[0].title, "Unchecked index");
+        assert_eq!(findings[0].severity, "error");
+        assert_eq!(
+            (findings[0].line_start, findings[0].line_end),
+            (Some(3), Some(3))
+        );
+        assert_eq!(findings[1].severity, "info");
+        assert_eq!(findings[1].category, "maintainability");
+        assert_eq!(
+            (findings[1].line_start, findings[1].line_end),
+            (Some(8), Some(10))
+        );
+        assert_eq!((findings[2].line_start, findings[2].line_end), (None, None));
+        assert_eq!(
+            (findings[3].line_start, findings[3].line_end),
+            (Some(5), Some(5))
+        );
+    }
+
+    #[test]
+    fn test_overall_severity() {
+        assert_eq!(overall_severity(&[]), "info");
+        assert_eq!(
+            overall_severity(&[
+                finding("info", "bug", (None, None)),
+                finding("warning", "bug", (None, None)),
+            ]),
+            "warning"
+        );
+        assert_eq!(
+            overall_severity(&[
+                finding("error", "bug", (None, None)),
+                finding("warning", "bug", (None, None)),
+            ]),
+            "error"
+        );
+    }
+
+    #[test]
+    fn test_findings_response_deserialization() {
+        let response: FindingsResponse = serde_json::from_str(
+            r#"{"findings": [{"title": "t", "severity": "warning", "category": "bug", "recommendation": "r"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(response.findings[0].line_start, None);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let content = "fn main() {\n    let x = v[0];\n}\n";
+        let found = finding("error", "bug", (Some(2), Some(2)));
+        let print = fingerprint("src/main.rs", &found, content);
+
+        // Moving the code, reformatting it or rewording the title's case,
+        // numbers and punctuation keeps the fingerprint
+        let moved = "// header\nfn main() {\n  let  x = v[0];\n}\n";
+        let mut renamed = finding("warning", "bug", (Some(3), Some(3)));
+        renamed.title = "unchecked index (2)".to_string();
+        assert_eq!(fingerprint("src/main.rs", &renamed, moved), print);
+
+        let changed = "fn main() {\n    let x = v.get(0);\n}\n";
+        assert_ne!(fingerprint("src/main.rs", &found, changed), print);
+        assert_ne!(fingerprint("src/lib.rs", &found, content), print);
+    }
+}
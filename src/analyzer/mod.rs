@@ -1,6 +1,16 @@
+pub mod architecture;
+pub mod context;
+mod delta;
+pub mod findings;
 mod ollama;
+pub mod test_gaps;
+pub mod validation;
 
-pub use ollama::OllamaClient;
+pub use architecture::ArchitectureModel;
+pub use delta::{parse_delta_response, text_diff, DeltaContext};
+pub use findings::StructuredFinding;
+pub use ollama::{OllamaClient, TokenUsage};
+pub use test_gaps::TestSuggestion;
 
 use serde::{Deserialize, Serialize};
 
@@ -24,6 +34,10 @@ pub enum AnalysisType {
     Quality,
     /// Documentation analysis
     Documentation,
+    /// Missing-test suggestions for surviving mutations
+    TestGaps,
+    /// Unused dependencies and exports of a manifest or source file
+    DeadCode,
 }
 
 impl std::fmt::Display for AnalysisType {
@@ -37,10 +51,26 @@ impl std::fmt::Display for AnalysisType {
             AnalysisType::Security => write!(f, "security"),
             AnalysisType::Quality => write!(f, "quality"),
             AnalysisType::Documentation => write!(f, "documentation"),
+            AnalysisType::TestGaps => write!(f, "test_gaps"),
+            AnalysisType::DeadCode => write!(f, "dead_code"),
         }
     }
 }
 
+/// The closing line of a prompt, asking for a response in `language` (the
+/// `general.output_language` setting). With `or_code`, code is allowed too.
+pub fn respond_in(language: &str, or_code: bool) -> String {
+    let language = match language.trim() {
+        "" => "English",
+        language => language,
+    };
+    if or_code {
+        format!("IMPORTANT: Respond only in {} (or code)", language)
+    } else {
+        format!("IMPORTANT: Respond only in {}", language)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -67,5 +97,20 @@ mod tests {
             AnalysisType::MutationTesting.to_string(),
             "mutation_testing"
         );
+        assert_eq!(AnalysisType::TestGaps.to_string(), "test_gaps");
+        assert_eq!(AnalysisType::DeadCode.to_string(), "dead_code");
+    }
+
+    #[test]
+    fn test_respond_in() {
+        assert_eq!(
+            respond_in("English", true),
+            "IMPORTANT: Respond only in English (or code)"
+        );
+        assert_eq!(
+            respond_in(" German ", false),
+            "IMPORTANT: Respond only in German"
+        );
+        assert_eq!(respond_in("", false), "IMPORTANT: Respond only in English");
     }
 }
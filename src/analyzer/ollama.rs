@@ -1,13 +1,97 @@
+use crate::config::{GenerationOptions, OllamaEndpoint};
 use anyhow::{Context, Result};
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 /// Client for interacting with Ollama API
 pub struct OllamaClient {
     client: Client,
     base_url: String,
     model: String,
+    /// Sent as `keep_alive` with every generation (Ollama's default if None)
+    keep_alive: Option<String>,
+    /// Sent as `options` with every generation
+    options: GenerationOptions,
+    /// How failed generations are retried
+    retry: RetryPolicy,
+    /// Cancelling aborts in-flight generations (the HTTP request is dropped)
+    cancel: CancellationToken,
+    /// Tokens used by generations since the last `take_usage`
+    usage: Arc<Mutex<TokenUsage>>,
+}
+
+/// Token counts of one or more generations, as reported by Ollama
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TokenUsage {
+    pub requests: i64,
+    /// Tokens in the prompt (`prompt_eval_count`)
+    pub prompt_tokens: i64,
+    /// Tokens generated (`eval_count`)
+    pub completion_tokens: i64,
+    /// Generations retried after a transient error
+    pub retries: i64,
+    /// Responses rejected by validation (see the `validation` module)
+    pub rejected: i64,
+}
+
+impl TokenUsage {
+    pub fn is_empty(&self) -> bool {
+        self.requests == 0 && self.retries == 0 && self.rejected == 0
+    }
+}
+
+/// How generations are retried after transient errors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying)
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (starting at 0)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(1 << retry.min(16))
+            .min(self.max_backoff)
+    }
+}
+
+/// A non-success HTTP response from Ollama
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    body: String,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Ollama API error: {} - {}", self.status, self.body)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Whether a failed request may succeed when tried again: connection
+/// failures, timeouts, server errors and rate limiting
+fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return e.is_timeout() || e.is_connect() || e.is_request() || e.is_body();
+        }
+        if let Some(e) = cause.downcast_ref::<ApiError>() {
+            return e.status.is_server_error() || e.status == StatusCode::TOO_MANY_REQUESTS;
+        }
+        false
+    })
 }
 
 #[derive(Serialize)]
@@ -16,12 +100,34 @@ struct GenerateRequest<'a> {
     prompt: &'a str,
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
-    format: Option<Value>,
+    format: Option<&'a Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<&'a GenerationOptions>,
 }
 
 #[derive(Deserialize)]
 struct GenerateResponse {
     response: String,
+    #[serde(default)]
+    prompt_eval_count: i64,
+    #[serde(default)]
+    eval_count: i64,
+}
+
+/// One line of a streamed (NDJSON) generate response
+#[derive(Deserialize)]
+struct GenerateStreamChunk {
+    #[serde(default)]
+    response: String,
+    #[serde(default)]
+    done: bool,
+    /// Only present on the final chunk
+    #[serde(default)]
+    prompt_eval_count: i64,
+    #[serde(default)]
+    eval_count: i64,
 }
 
 impl OllamaClient {
@@ -31,6 +137,134 @@ impl OllamaClient {
             client: Client::new(),
             base_url: base_url.trim_end_matches('/').to_string(),
             model: model.to_string(),
+            keep_alive: None,
+            options: GenerationOptions::default(),
+            retry: RetryPolicy::default(),
+            cancel: CancellationToken::new(),
+            usage: Arc::default(),
+        }
+    }
+
+    /// Creates a client for an endpoint's model, with its keep-alive, model
+    /// options, request timeout and retry settings.
+    pub fn for_endpoint(endpoint: &OllamaEndpoint) -> Self {
+        let client = Self::new(&endpoint.url, &endpoint.model)
+            .with_keep_alive(endpoint.keep_alive.as_deref())
+            .with_options(endpoint.options)
+            .with_retry_policy(RetryPolicy {
+                max_retries: endpoint.max_retries,
+                initial_backoff: Duration::from_secs(endpoint.retry_backoff_seconds),
+                max_backoff: Duration::from_secs(endpoint.retry_backoff_max_seconds),
+            });
+        match endpoint.request_timeout_seconds {
+            0 => client,
+            seconds => client.with_timeout(Duration::from_secs(seconds)),
+        }
+    }
+
+    /// Abandon requests that take longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        self
+    }
+
+    /// Retry generations that fail with a transient error
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Tokens used since the last call, resetting the counters
+    pub fn take_usage(&self) -> TokenUsage {
+        std::mem::take(&mut *self.usage.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Count a response that was rejected by validation, reported with the
+    /// token usage
+    pub fn record_rejection(&self) {
+        self.usage
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .rejected += 1;
+    }
+
+    fn record_usage(&self, prompt_tokens: i64, completion_tokens: i64) {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        usage.requests += 1;
+        usage.prompt_tokens += prompt_tokens;
+        usage.completion_tokens += completion_tokens;
+    }
+
+    /// Run `request`, retrying it with exponential backoff while it fails with
+    /// a transient error
+    async fn with_retries<T, F, Fut>(&self, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut retry = 0;
+        loop {
+            match request().await {
+                Ok(value) => {
+                    if retry > 0 {
+                        tracing::info!(
+                            "Ollama request to {} succeeded after {} retries",
+                            self.base_url,
+                            retry
+                        );
+                    }
+                    return Ok(value);
+                }
+                Err(e) if retry < self.retry.max_retries && is_transient(&e) => {
+                    let delay = self.retry.backoff(retry);
+                    retry += 1;
+                    tracing::warn!(
+                        "Ollama request to {} failed ({:#}), retrying in {}ms ({}/{})",
+                        self.base_url,
+                        e,
+                        delay.as_millis(),
+                        retry,
+                        self.retry.max_retries
+                    );
+                    self.usage.lock().unwrap_or_else(|e| e.into_inner()).retries += 1;
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Abort generations when `cancel` is cancelled (e.g. on daemon shutdown).
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Keep the model loaded for `keep_alive` after each generation (e.g. `"30m"`)
+    pub fn with_keep_alive(mut self, keep_alive: Option<&str>) -> Self {
+        self.keep_alive = keep_alive.map(str::to_string);
+        self
+    }
+
+    /// Send `options` (temperature, context size, ...) with each generation
+    pub fn with_options(mut self, options: GenerationOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Model options to send, if any are set
+    fn request_options(&self) -> Option<&GenerationOptions> {
+        Some(&self.options).filter(|options| !options.is_empty())
+    }
+
+    /// Run a request, giving up as soon as the client is cancelled
+    async fn cancellable<T>(&self, request: impl Future<Output = Result<T>>) -> Result<T> {
+        tokio::select! {
+            result = request => result,
+            _ = self.cancel.cancelled() => anyhow::bail!("Ollama request cancelled"),
         }
     }
 
@@ -52,7 +286,89 @@ impl OllamaClient {
         serde_json::from_str(&response).context("Failed to parse structured response as JSON")
     }
 
+    /// Generate a response, streaming it from Ollama.
+    ///
+    /// `on_chunk` is called with each piece of text as it arrives (Ollama streams
+    /// roughly one token per chunk). Returns the full response once generation is done.
+    pub async fn generate_stream<F: FnMut(&str)>(
+        &self,
+        prompt: &str,
+        on_chunk: F,
+    ) -> Result<String> {
+        self.cancellable(self.generate_stream_uncancellable(prompt, on_chunk))
+            .await
+    }
+
+    async fn generate_stream_uncancellable<F: FnMut(&str)>(
+        &self,
+        prompt: &str,
+        mut on_chunk: F,
+    ) -> Result<String> {
+        let url = format!("{}/api/generate", self.base_url);
+
+        let request = GenerateRequest {
+            model: &self.model,
+            prompt,
+            stream: true,
+            format: None,
+            keep_alive: self.keep_alive.as_deref(),
+            options: self.request_options(),
+        };
+
+        let mut response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send request to Ollama")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError { status, body }.into());
+        }
+
+        let mut result = String::new();
+        let mut buffer: Vec<u8> = Vec::new();
+
+        while let Some(bytes) = response
+            .chunk()
+            .await
+            .context("Failed to read Ollama response stream")?
+        {
+            buffer.extend_from_slice(&bytes);
+
+            // Each complete line is one JSON chunk
+            while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                if let Some(chunk) = parse_stream_line(&line)? {
+                    on_chunk(&chunk.response);
+                    result.push_str(&chunk.response);
+                    if chunk.done {
+                        self.record_usage(chunk.prompt_eval_count, chunk.eval_count);
+                        return Ok(result);
+                    }
+                }
+            }
+        }
+
+        // The final chunk may not be newline-terminated
+        if let Some(chunk) = parse_stream_line(&buffer)? {
+            on_chunk(&chunk.response);
+            result.push_str(&chunk.response);
+            self.record_usage(chunk.prompt_eval_count, chunk.eval_count);
+        }
+
+        Ok(result)
+    }
+
     async fn generate_internal(&self, prompt: &str, format: Option<Value>) -> Result<String> {
+        self.cancellable(self.with_retries(|| self.generate_uncancellable(prompt, format.as_ref())))
+            .await
+    }
+
+    async fn generate_uncancellable(&self, prompt: &str, format: Option<&Value>) -> Result<String> {
         let url = format!("{}/api/generate", self.base_url);
 
         let request = GenerateRequest {
@@ -60,6 +376,8 @@ impl OllamaClient {
             prompt,
             stream: false,
             format,
+            keep_alive: self.keep_alive.as_deref(),
+            options: self.request_options(),
         };
 
         let response = self
@@ -73,17 +391,65 @@ impl OllamaClient {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            anyhow::bail!("Ollama API error: {} - {}", status, body);
+            return Err(ApiError { status, body }.into());
         }
 
         let result: GenerateResponse = response
             .json()
             .await
             .context("Failed to parse Ollama response")?;
+        self.record_usage(result.prompt_eval_count, result.eval_count);
 
         Ok(result.response)
     }
 
+    /// Embed `text` with the client's model (which must be an embedding model,
+    /// e.g. `nomic-embed-text`)
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.cancellable(self.embed_uncancellable(text)).await
+    }
+
+    async fn embed_uncancellable(&self, text: &str) -> Result<Vec<f32>> {
+        let url = format!("{}/api/embeddings", self.base_url);
+
+        #[derive(Serialize)]
+        struct EmbeddingRequest<'a> {
+            model: &'a str,
+            prompt: &'a str,
+        }
+
+        #[derive(Deserialize)]
+        struct EmbeddingResponse {
+            embedding: Vec<f32>,
+        }
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&EmbeddingRequest {
+                model: &self.model,
+                prompt: text,
+            })
+            .send()
+            .await
+            .context("Failed to send embedding request to Ollama")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError { status, body }.into());
+        }
+
+        let result: EmbeddingResponse = response
+            .json()
+            .await
+            .context("Failed to parse Ollama embedding response")?;
+        if result.embedding.is_empty() {
+            anyhow::bail!("Model {} returned an empty embedding", self.model);
+        }
+        Ok(result.embedding)
+    }
+
     pub async fn is_available(&self) -> bool {
         let url = format!("{}/api/tags", self.base_url);
         self.client.get(&url).send().await.is_ok()
@@ -116,6 +482,49 @@ impl OllamaClient {
 
         Ok(result.models.into_iter().map(|m| m.name).collect())
     }
+
+    /// Models currently loaded into memory (Ollama's `/api/ps`)
+    pub async fn loaded_models(&self) -> Result<Vec<String>> {
+        let url = format!("{}/api/ps", self.base_url);
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to connect to Ollama")?;
+
+        #[derive(Deserialize)]
+        struct Model {
+            name: String,
+        }
+
+        #[derive(Deserialize)]
+        struct PsResponse {
+            #[serde(default)]
+            models: Vec<Model>,
+        }
+
+        let result: PsResponse = response
+            .json()
+            .await
+            .context("Failed to parse Ollama running models response")?;
+
+        Ok(result.models.into_iter().map(|m| m.name).collect())
+    }
+}
+
+/// Parse one NDJSON line of a streamed response, ignoring blank lines
+fn parse_stream_line(line: &[u8]) -> Result<Option<GenerateStreamChunk>> {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+
+    serde_json::from_str(line)
+        .map(Some)
+        .context("Failed to parse Ollama stream chunk")
 }
 
 #[cfg(test)]
@@ -148,12 +557,15 @@ mod tests {
             prompt: "test prompt",
             stream: false,
             format: None,
+            keep_alive: None,
+            options: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
         assert!(json.contains("\"model\":\"llama2\""));
         assert!(json.contains("\"prompt\":\"test prompt\""));
         assert!(json.contains("\"stream\":false"));
+        assert!(!json.contains("keep_alive"));
     }
 
     #[test]
@@ -163,11 +575,48 @@ mod tests {
             model: "llama2",
             prompt: "test",
             stream: false,
-            format: Some(schema),
+            format: Some(&schema),
+            keep_alive: Some("-1"),
+            options: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
         assert!(json.contains("\"format\""));
+        assert!(json.contains("\"keep_alive\":\"-1\""));
+        assert!(!json.contains("options"));
+    }
+
+    #[test]
+    fn test_generate_request_with_options() {
+        let endpoint = OllamaEndpoint {
+            options: GenerationOptions {
+                temperature: Some(0.1),
+                num_ctx: Some(8192),
+                seed: Some(42),
+                ..GenerationOptions::default()
+            },
+            ..OllamaEndpoint::new(
+                "gpu".to_string(),
+                "http://localhost:11434".to_string(),
+                "llama2".to_string(),
+            )
+        };
+        let client = OllamaClient::for_endpoint(&endpoint);
+        let request = GenerateRequest {
+            model: &client.model,
+            prompt: "test",
+            stream: false,
+            format: None,
+            keep_alive: None,
+            options: client.request_options(),
+        };
+
+        let json: Value = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            json["options"],
+            serde_json::json!({ "temperature": 0.1, "num_ctx": 8192, "seed": 42 })
+        );
+        assert_eq!(OllamaClient::new("http://a", "m").request_options(), None);
     }
 
     #[test]
@@ -177,6 +626,112 @@ mod tests {
         assert_eq!(response.response, "test response");
     }
 
+    #[test]
+    fn test_parse_stream_line() {
+        let chunk = parse_stream_line(br#"{"response":"fn","done":false}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(chunk.response, "fn");
+        assert!(!chunk.done);
+
+        let chunk = parse_stream_line(b"{\"done\":true}\n").unwrap().unwrap();
+        assert!(chunk.response.is_empty());
+        assert!(chunk.done);
+
+        assert!(parse_stream_line(b"  \n").unwrap().is_none());
+        assert!(parse_stream_line(b"not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_aborts_generation() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"response": "late"}))
+                    .set_delay(std::time::Duration::from_secs(30)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let cancel = CancellationToken::new();
+        let client =
+            OllamaClient::new(&mock_server.uri(), "test-model").with_cancellation(cancel.clone());
+
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+        let started = std::time::Instant::now();
+        let result = client.generate("test prompt").await;
+        canceller.await.unwrap();
+
+        assert!(result.unwrap_err().to_string().contains("cancelled"));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_collects_chunks() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        let body = concat!(
+            r#"{"response":"Hello","done":false}"#,
+            "\n",
+            r#"{"response":", world","done":false}"#,
+            "\n",
+            r#"{"response":"","done":true,"prompt_eval_count":7,"eval_count":2}"#,
+            "\n"
+        );
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(serde_json::json!({"stream": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&mock_server)
+            .await;
+
+        let client = OllamaClient::new(&mock_server.uri(), "test-model");
+        let mut chunks = Vec::new();
+        let result = client
+            .generate_stream("test prompt", |chunk| chunks.push(chunk.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(result, "Hello, world");
+        assert_eq!(chunks, vec!["Hello", ", world", ""]);
+        assert_eq!(client.take_usage().completion_tokens, 2);
+    }
+
+    #[tokio::test]
+    async fn test_embed() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .and(body_partial_json(
+                serde_json::json!({"model": "nomic-embed-text", "prompt": "parser"}),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"embedding": [0.5, -1.0, 2.0]})),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = OllamaClient::new(&mock_server.uri(), "nomic-embed-text");
+        assert_eq!(client.embed("parser").await.unwrap(), vec![0.5, -1.0, 2.0]);
+        assert!(client.embed("other").await.is_err());
+    }
+
     #[tokio::test]
     async fn test_generate_handles_4xx_error() {
         use wiremock::matchers::{method, path};
@@ -246,4 +801,151 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "Hello world");
     }
+
+    #[tokio::test]
+    async fn test_generate_records_token_usage() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "response": "Hello",
+                "prompt_eval_count": 12,
+                "eval_count": 3
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = OllamaClient::new(&mock_server.uri(), "test-model");
+        client.generate("a").await.unwrap();
+        client.generate("b").await.unwrap();
+
+        assert_eq!(
+            client.take_usage(),
+            TokenUsage {
+                requests: 2,
+                prompt_tokens: 24,
+                completion_tokens: 6,
+                retries: 0,
+                rejected: 0,
+            }
+        );
+        assert!(client.take_usage().is_empty());
+
+        client.record_rejection();
+        assert_eq!(client.take_usage().rejected, 1);
+    }
+
+    fn quick_retries(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_retry_backoff_doubles_up_to_the_limit() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(10),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_secs(2));
+        assert_eq!(policy.backoff(1), Duration::from_secs(4));
+        assert_eq!(policy.backoff(2), Duration::from_secs(8));
+        assert_eq!(policy.backoff(3), Duration::from_secs(10));
+        assert_eq!(policy.backoff(40), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_generate_retries_transient_errors() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({"response": "ok"})),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client =
+            OllamaClient::new(&mock_server.uri(), "test-model").with_retry_policy(quick_retries(2));
+        assert_eq!(client.generate("test").await.unwrap(), "ok");
+        let usage = client.take_usage();
+        assert_eq!(usage.retries, 2);
+        assert_eq!(usage.requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_generate_gives_up_after_max_retries() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("model crashed"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let client =
+            OllamaClient::new(&mock_server.uri(), "test-model").with_retry_policy(quick_retries(1));
+        let error = client.generate("test").await.unwrap_err();
+        assert!(error.to_string().contains("model crashed"));
+        assert_eq!(client.take_usage().retries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_generate_does_not_retry_client_errors() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("model not found"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client =
+            OllamaClient::new(&mock_server.uri(), "test-model").with_retry_policy(quick_retries(3));
+        assert!(client.generate("test").await.is_err());
+        assert!(client.take_usage().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_generate_times_out() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"response": "late"}))
+                    .set_delay(Duration::from_secs(5)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = OllamaClient::new(&mock_server.uri(), "test-model")
+            .with_timeout(Duration::from_millis(100));
+        let error = client.generate("test").await.unwrap_err();
+        assert!(is_transient(&error));
+    }
 }
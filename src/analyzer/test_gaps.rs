@@ -0,0 +1,204 @@
+//! Missing-test suggestions for surviving mutations.
+//!
+//! A mutation that survives shows a behavior no test pins down. The test gaps
+//! pass shows the model the file, its code understanding analysis and the
+//! surviving mutation, and asks for concrete test cases (a name, the scenario
+//! and a sketch of the assertion) that would fail on the mutated code. The
+//! suggestions are stored with the mutation result.
+
+use crate::db::MutationResult;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Maximum number of test cases kept per surviving mutation
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// A test case that would kill a mutation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestSuggestion {
+    /// Test function name, e.g. `rejects_empty_input`
+    pub name: String,
+    /// What the test sets up and exercises
+    pub scenario: String,
+    /// Sketch of the assertion that fails on the mutated code
+    pub assertion: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TestGapsResponse {
+    pub tests: Vec<TestSuggestion>,
+}
+
+/// JSON schema for the test gaps response
+pub fn test_gaps_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "tests": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "description": "Name of the test function, in the file's naming style"
+                        },
+                        "scenario": {
+                            "type": "string",
+                            "description": "Inputs and setup of the test, and the behavior it exercises"
+                        },
+                        "assertion": {
+                            "type": "string",
+                            "description": "Code sketch of the assertion that passes on the original code and fails on the mutated code"
+                        }
+                    },
+                    "required": ["name", "scenario", "assertion"]
+                }
+            }
+        },
+        "required": ["tests"]
+    })
+}
+
+/// Prompt asking for tests that kill `mutation`, a surviving mutation of
+/// `content`
+pub fn test_gaps_prompt(
+    file_path: &str,
+    content: &str,
+    mutation: &MutationResult,
+    analysis: Option<&str>,
+    output_language: &str,
+) -> String {
+    let numbered: String = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:4} | {}\n", i + 1, line))
+        .collect();
+    let changes: String = mutation
+        .replacements
+        .iter()
+        .map(|r| {
+            format!(
+                "Line {}:\n- {}\n+ {}\n",
+                r.line_number,
+                r.original().trim(),
+                r.mutated().trim()
+            )
+        })
+        .collect();
+    let analysis = analysis
+        .map(|a| format!("# Analysis of the file\n{}\n\n", a.trim()))
+        .unwrap_or_default();
+
+    format!(
+        "The test suite of a project still passes after the change below was made to one of its \
+         source files, so no test checks the behavior the change breaks. Propose up to {} \
+         concrete test cases that pass on the original code and fail on the changed code. \
+         Use the project's language and test framework, and refer to real functions and types \
+         from the file.\n\n\
+         File: {}\n\n```\n{}```\n\n{}\
+         # Surviving change\n{}\n\nWhy it was made: {}\n\n{}\n{}",
+        MAX_SUGGESTIONS,
+        file_path,
+        numbered,
+        analysis,
+        mutation.description,
+        mutation.reasoning,
+        changes,
+        super::respond_in(output_language, true)
+    )
+}
+
+/// Drop incomplete suggestions, trim the rest and keep at most [`MAX_SUGGESTIONS`]
+pub fn normalize_suggestions(suggestions: Vec<TestSuggestion>) -> Vec<TestSuggestion> {
+    suggestions
+        .into_iter()
+        .map(|s| TestSuggestion {
+            name: s.name.trim().to_string(),
+            scenario: s.scenario.trim().to_string(),
+            assertion: s.assertion.trim().to_string(),
+        })
+        .filter(|s| !s.name.is_empty() && !s.scenario.is_empty())
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MutationReplacement;
+
+    fn suggestion(name: &str) -> TestSuggestion {
+        TestSuggestion {
+            name: name.to_string(),
+            scenario: " Call with an empty slice ".to_string(),
+            assertion: "assert!(parse(&[]).is_err());".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_suggestions() {
+        let suggestions = normalize_suggestions(vec![
+            suggestion(" rejects_empty "),
+            suggestion(""),
+            suggestion("b"),
+            suggestion("c"),
+            suggestion("d"),
+        ]);
+        let names: Vec<_> = suggestions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["rejects_empty", "b", "c"]);
+        assert_eq!(suggestions[0].scenario, "Call with an empty slice");
+    }
+
+    #[test]
+    fn test_prompt_includes_the_surviving_change() {
+        let mutation = MutationResult {
+            id: 1,
+            repository_id: 1,
+            file_path: "/repo/src/lib.rs".to_string(),
+            description: "Changed `>` to `>=`".to_string(),
+            reasoning: "Boundary not tested".to_string(),
+            replacements: vec![MutationReplacement {
+                line_number: 2,
+                find: ">".to_string(),
+                replace: ">=".to_string(),
+                original_line: "    if a > b {".to_string(),
+            }],
+            test_outcome: "survived".to_string(),
+            killing_test: None,
+            test_output: None,
+            execution_time_ms: None,
+            content_hash: None,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            toolchain: None,
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            covered: None,
+            project: None,
+            issue_url: None,
+            status: "new".to_string(),
+        };
+
+        let prompt = test_gaps_prompt(
+            "src/lib.rs",
+            "fn max(a: i32, b: i32) -> i32 {\n    if a > b {\n",
+            &mutation,
+            Some("Returns the larger value"),
+            "English",
+        );
+        assert!(prompt.contains("   2 |     if a > b {"));
+        assert!(prompt.contains("Line 2:\n- if a > b {\n+ if a >= b {\n"));
+        assert!(prompt.contains("Boundary not tested"));
+        assert!(prompt.contains("# Analysis of the file\nReturns the larger value"));
+    }
+
+    #[test]
+    fn test_test_gaps_response_deserialization() {
+        let response: TestGapsResponse = serde_json::from_str(
+            r#"{"tests": [{"name": "n", "scenario": "s", "assertion": "a"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(response.tests[0].name, "n");
+    }
+}
@@ -0,0 +1,251 @@
+//! Validation of analysis responses.
+//!
+//! Models sometimes answer with nothing useful: an empty or cut-off response,
+//! a reply in another language than the one asked for, or text that skips the
+//! sections the prompt requested. Responses are checked before they are
+//! stored; a failing one is asked for again once, with the problem spelled
+//! out, and dropped if the second answer fails too.
+
+use super::delta::{CHANGES_MARKER, SUMMARY_MARKER};
+use whatlang::Lang;
+
+/// What a response must look like to be stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Requirements {
+    /// Fewest characters of a useful response, ignoring surrounding whitespace
+    pub min_chars: usize,
+    /// Fewest headings or list items (a response answering a numbered list of
+    /// questions has one per answer)
+    pub min_sections: usize,
+    /// Markers the response must contain
+    pub markers: &'static [&'static str],
+}
+
+impl Requirements {
+    /// A code understanding or documentation analysis
+    pub const ANALYSIS: Requirements = Requirements {
+        min_chars: 80,
+        min_sections: 2,
+        markers: &[],
+    };
+
+    /// An architecture analysis or diagram extraction, which may just say that
+    /// the file has nothing to extract
+    pub const EXTRACTION: Requirements = Requirements {
+        min_chars: 1,
+        min_sections: 0,
+        markers: &[],
+    };
+
+    /// An analysis updated from a diff (see [`DeltaContext`](super::DeltaContext))
+    pub const DELTA: Requirements = Requirements {
+        min_chars: 80,
+        min_sections: 0,
+        markers: &[CHANGES_MARKER, SUMMARY_MARKER],
+    };
+}
+
+/// Check `response` against `requirements` and, when the language is
+/// recognized, that its prose is written in `output_language`. Returns what
+/// is wrong with it otherwise.
+pub fn validate(
+    response: &str,
+    requirements: &Requirements,
+    output_language: &str,
+) -> Result<(), String> {
+    let response = response.trim();
+    if response.is_empty() {
+        return Err("the response is empty".to_string());
+    }
+    let chars = response.chars().count();
+    if chars < requirements.min_chars {
+        return Err(format!(
+            "the response is too short ({} characters, at least {} expected)",
+            chars, requirements.min_chars
+        ));
+    }
+
+    let missing: Vec<&str> = requirements
+        .markers
+        .iter()
+        .copied()
+        .filter(|marker| !response.contains(marker))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "the response is missing the sections {}",
+            missing.join(", ")
+        ));
+    }
+
+    let sections = response.lines().filter(|line| is_section(line)).count();
+    if sections < requirements.min_sections {
+        return Err(format!(
+            "the response has {} headings or list items, at least {} expected",
+            sections, requirements.min_sections
+        ));
+    }
+
+    if let Some(detected) = detect_language(response) {
+        if let Some(expected) = known_language(output_language) {
+            if detected != expected {
+                return Err(format!(
+                    "the response is written in {} instead of {}",
+                    detected.eng_name(),
+                    expected.eng_name()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `prompt` again, followed by why the previous response was rejected
+pub fn corrective_prompt(prompt: &str, problem: &str) -> String {
+    format!(
+        "{}\n\nYour previous response to these instructions was rejected because {}. \
+         Answer again, following every instruction above.",
+        prompt, problem
+    )
+}
+
+/// Whether `line` opens a section: a Markdown heading, a list item or a bold label
+fn is_section(line: &str) -> bool {
+    let line = line.trim_start();
+    if line.starts_with('#') || line.starts_with("**") {
+        return true;
+    }
+    if ["- ", "* ", "+ "]
+        .iter()
+        .any(|bullet| line.starts_with(bullet))
+    {
+        return true;
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && matches!(line[digits..].chars().next(), Some('.') | Some(')'))
+}
+
+/// The language of the prose of `response` (code blocks and inline code
+/// removed), if it can be told reliably
+fn detect_language(response: &str) -> Option<Lang> {
+    let mut prose = String::new();
+    let mut in_code_block = false;
+    for line in response.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        // Drop inline code, the odd spans of a line split on backticks
+        for (i, part) in line.split('`').enumerate() {
+            if i % 2 == 0 {
+                prose.push_str(part);
+            }
+        }
+        prose.push('\n');
+    }
+
+    let info = whatlang::detect(&prose)?;
+    info.is_reliable().then(|| info.lang())
+}
+
+/// The language named by `general.output_language`, if it is one that can be
+/// detected
+fn known_language(name: &str) -> Option<Lang> {
+    let name = name.trim().to_lowercase();
+    Lang::all()
+        .iter()
+        .copied()
+        .find(|lang| lang.eng_name().to_lowercase() == name || lang.name().to_lowercase() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANALYSIS: &str = "This module parses the configuration file and validates it.\n\n\
+        1. **Purpose**: Loads the settings from disk and applies the defaults.\n\
+        2. **Key functions**: `load` reads the file, `validate` checks every value.\n\
+        3. **Issues**: Errors of nested tables are reported without their key path.\n";
+
+    #[test]
+    fn test_validate_accepts_a_structured_analysis() {
+        assert_eq!(
+            validate(ANALYSIS, &Requirements::ANALYSIS, "English"),
+            Ok(())
+        );
+        // Languages that can't be detected aren't checked
+        assert_eq!(
+            validate(ANALYSIS, &Requirements::ANALYSIS, "Klingon"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_useless_responses() {
+        assert!(validate("  \n", &Requirements::EXTRACTION, "English")
+            .unwrap_err()
+            .contains("empty"));
+        assert!(validate("Fine.", &Requirements::ANALYSIS, "English")
+            .unwrap_err()
+            .contains("too short"));
+        assert_eq!(
+            validate("No database access.", &Requirements::EXTRACTION, "English"),
+            Ok(())
+        );
+
+        let prose = "This module parses the configuration file and validates every value \
+                     it contains before the daemon starts.";
+        assert!(validate(prose, &Requirements::ANALYSIS, "English")
+            .unwrap_err()
+            .contains("headings or list items"));
+
+        let delta = format!("## Summary\n{}", ANALYSIS);
+        assert!(validate(&delta, &Requirements::DELTA, "English")
+            .unwrap_err()
+            .contains("## Changes"));
+        let delta = format!("## Changes\n- Added `validate`\n\n## Summary\n{}", ANALYSIS);
+        assert_eq!(validate(&delta, &Requirements::DELTA, "English"), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_checks_the_language() {
+        let german = "Dieses Modul liest die Konfigurationsdatei und prüft alle Werte.\n\n\
+            1. **Zweck**: Lädt die Einstellungen von der Festplatte und setzt die Standardwerte.\n\
+            2. **Funktionen**: `load` liest die Datei, `validate` prüft jeden einzelnen Wert.\n";
+        assert_eq!(
+            validate(german, &Requirements::ANALYSIS, "english").unwrap_err(),
+            "the response is written in German instead of English"
+        );
+        assert_eq!(validate(german, &Requirements::ANALYSIS, "German"), Ok(()));
+        assert_eq!(validate(german, &Requirements::ANALYSIS, "Deutsch"), Ok(()));
+    }
+
+    #[test]
+    fn test_is_section() {
+        assert!(is_section("## Purpose"));
+        assert!(is_section("  - item"));
+        assert!(is_section("12. item"));
+        assert!(is_section("1) item"));
+        assert!(is_section("**Purpose**: loads"));
+        assert!(!is_section("2024 was a year"));
+        assert!(!is_section("plain text"));
+    }
+
+    #[test]
+    fn test_detect_language_ignores_code() {
+        let response =
+            "```rust\nfn main() { println!(\"Hallo Welt, wie geht es dir heute\"); }\n```\n\
+                        The entry point prints a greeting and exits with a success status.";
+        assert_eq!(detect_language(response), Some(Lang::Eng));
+    }
+
+    #[test]
+    fn test_corrective_prompt() {
+        let prompt = corrective_prompt("Analyze this.", "the response is empty");
+        assert!(prompt.starts_with("Analyze this.\n\n"));
+        assert!(prompt.contains("rejected because the response is empty"));
+    }
+}
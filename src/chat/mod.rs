@@ -0,0 +1,470 @@
+//! Questions about a repository, answered from its stored analyses.
+//!
+//! A question ("where is auth handled?") is answered by the LLM from the
+//! repository's architecture summary and the code understanding summaries of
+//! the files most relevant to it. Relevant files are found by semantic search
+//! when `[search]` is enabled, and by matching the question's words against
+//! paths and summaries otherwise (or when the embedding model is missing). The
+//! conversation is stored per repository, and its recent turns go with each
+//! question so follow-ups ("and who calls it?") work.
+
+use crate::analyzer::{respond_in, AnalysisType};
+use crate::config::Config;
+use crate::daemon::{
+    endpoints_for, find_available_endpoint, routing_audit_detail, truncate_at_char_boundary,
+    HealthMonitor,
+};
+use crate::db::{AnalysisResult, ChatMessage, Database, Repository, AUDIT_SENSITIVE_ROUTING};
+use crate::search;
+use anyhow::{Context, Result};
+use std::path::Path;
+use tokio_util::sync::CancellationToken;
+
+/// Files whose summaries are given to the model with a question
+const CONTEXT_FILES: usize = 6;
+
+/// Maximum characters of each file summary in the prompt
+const MAX_SUMMARY_CHARS: usize = 4_000;
+
+/// Maximum characters of the architecture summary in the prompt
+const MAX_ARCHITECTURE_CHARS: usize = 8_000;
+
+/// Earlier messages sent with a question
+const HISTORY_MESSAGES: i64 = 10;
+
+/// Maximum characters of each earlier message in the prompt
+const MAX_HISTORY_CHARS: usize = 2_000;
+
+/// Words too common to say which files a question is about
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "are", "is", "was", "what", "where", "which", "who", "why", "how", "does",
+    "did", "can", "this", "that", "with", "from", "into", "there", "code", "file", "files", "any",
+    "all", "about", "when", "handled", "used", "you", "our", "its",
+];
+
+/// A file summary given to the model as context
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextFile {
+    /// Path relative to the repository root
+    pub file_path: String,
+    pub summary: String,
+}
+
+/// Ask a question about `repo`, storing it and the answer in the repository's chat
+pub async fn ask(
+    db: &Database,
+    config: &Config,
+    health: &HealthMonitor,
+    repo: &Repository,
+    question: &str,
+) -> Result<ChatMessage> {
+    let mut endpoints: Vec<_> = config
+        .endpoints
+        .iter()
+        .filter(|e| e.enabled)
+        .cloned()
+        .collect();
+
+    // A sensitive repository's summaries only go to local endpoints
+    if repo.sensitive {
+        let routed = endpoints_for(repo, &endpoints);
+        let detail = format!("Chat: {}", routing_audit_detail(&endpoints, &routed));
+        db.record_audit(Some(repo.id), AUDIT_SENSITIVE_ROUTING, &detail)
+            .await?;
+        if routed.is_empty() {
+            anyhow::bail!(
+                "{} is marked sensitive, but no local endpoint is enabled",
+                repo.name
+            );
+        }
+        endpoints = routed;
+    }
+
+    // The request is dropped if the browser goes away, so nothing needs cancelling
+    let (client, endpoint_name) =
+        find_available_endpoint(&endpoints, health, &CancellationToken::new())
+            .await
+            .context("No available Ollama endpoints configured")?;
+
+    let history = db.get_chat_messages(repo.id, HISTORY_MESSAGES).await?;
+    let architecture = db
+        .get_repository_results(repo.id, &AnalysisType::ArchitectureSummary.to_string())
+        .await?
+        .into_iter()
+        .next()
+        .map(|result| result.result);
+
+    let mut files = None;
+    if config.search.enabled {
+        let url = endpoints
+            .iter()
+            .find(|e| e.name == endpoint_name)
+            .map(|e| e.url.as_str())
+            .unwrap_or_default();
+        match semantic_context(db, config, url, repo, question).await {
+            Ok(found) if !found.is_empty() => files = Some(found),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Semantic search for chat failed, matching words: {}", e),
+        }
+    }
+    let files = match files {
+        Some(files) => files,
+        None => keyword_context(db, repo, question).await?,
+    };
+
+    let prompt = chat_prompt(
+        &repo.name,
+        architecture.as_deref(),
+        &files,
+        &history,
+        question,
+        &config.general.output_language,
+    );
+    let generated = client.generate(&prompt).await;
+    let usage = client.take_usage();
+    if !usage.is_empty() {
+        db.record_usage(repo.id, &endpoint_name, "chat", &usage)
+            .await?;
+    }
+    let answer = generated.context("Failed to generate an answer")?;
+
+    let sources: Vec<String> = files.into_iter().map(|file| file.file_path).collect();
+    db.save_chat_message(repo.id, "user", question, &[]).await?;
+    db.save_chat_message(repo.id, "assistant", answer.trim(), &sources)
+        .await
+}
+
+/// The files semantic search finds most relevant to the question
+async fn semantic_context(
+    db: &Database,
+    config: &Config,
+    url: &str,
+    repo: &Repository,
+    question: &str,
+) -> Result<Vec<ContextFile>> {
+    let model = &config.search.embedding_model;
+    let client = crate::analyzer::OllamaClient::new(url, model);
+    let hits = search::search(db, &client, model, question, Some(repo.id), CONTEXT_FILES).await?;
+
+    let mut files = Vec::with_capacity(hits.len());
+    for hit in hits {
+        // Hits carry a snippet, the prompt wants the whole summary
+        let summary = db
+            .get_latest_file_result(
+                repo.id,
+                &hit.file_path,
+                &AnalysisType::CodeUnderstanding.to_string(),
+            )
+            .await?
+            .map(|result| result.result)
+            .unwrap_or(hit.snippet);
+        files.push(ContextFile {
+            file_path: hit.relative_path,
+            summary,
+        });
+    }
+    Ok(files)
+}
+
+/// The files whose paths and summaries mention the question's words most
+async fn keyword_context(
+    db: &Database,
+    repo: &Repository,
+    question: &str,
+) -> Result<Vec<ContextFile>> {
+    let results = db
+        .get_repository_results(repo.id, &AnalysisType::CodeUnderstanding.to_string())
+        .await?;
+    Ok(
+        rank_by_keywords(question, &results, &repo.path, CONTEXT_FILES)
+            .into_iter()
+            .map(|result| ContextFile {
+                file_path: relative_path(&result.file_path, &repo.path),
+                summary: result.result.clone(),
+            })
+            .collect(),
+    )
+}
+
+/// The words of a question that can say which files it is about
+fn keywords(question: &str) -> Vec<String> {
+    let mut words: Vec<String> = question
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .map(str::to_lowercase)
+        .filter(|w| w.chars().count() >= 3 && !STOP_WORDS.contains(&w.as_str()))
+        .collect();
+    words.sort();
+    words.dedup();
+    words
+}
+
+/// The `limit` results mentioning the question's words most, weighing a word
+/// in the (relative) path above one in the summary. Results mentioning none
+/// are left out.
+fn rank_by_keywords<'a>(
+    question: &str,
+    results: &'a [AnalysisResult],
+    repo_path: &str,
+    limit: usize,
+) -> Vec<&'a AnalysisResult> {
+    let words = keywords(question);
+    let mut ranked: Vec<_> = results
+        .iter()
+        .map(|result| {
+            let path = relative_path(&result.file_path, repo_path).to_lowercase();
+            let summary = result.result.to_lowercase();
+            let score: usize = words
+                .iter()
+                .map(|word| {
+                    3 * path.matches(word.as_str()).count()
+                        + summary.matches(word.as_str()).count().min(5)
+                })
+                .sum();
+            (score, result)
+        })
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    // Stable, so ties keep the results' path order
+    ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(_, result)| result)
+        .collect()
+}
+
+/// Build the prompt answering `question` from the stored analyses and the
+/// conversation so far
+pub fn chat_prompt(
+    repo_name: &str,
+    architecture: Option<&str>,
+    files: &[ContextFile],
+    history: &[ChatMessage],
+    question: &str,
+    output_language: &str,
+) -> String {
+    let mut prompt = format!(
+        "You are answering questions about the '{}' code base. You can't see the code, only notes \
+         an analysis tool wrote about it.\n\n",
+        repo_name
+    );
+
+    if let Some(architecture) = architecture {
+        prompt.push_str(&format!(
+            "Architecture overview:\n\n{}\n\n",
+            truncate_at_char_boundary(architecture.trim(), MAX_ARCHITECTURE_CHARS)
+        ));
+    }
+
+    if !files.is_empty() {
+        prompt.push_str("Notes on the files most relevant to the question:\n\n");
+        for file in files {
+            prompt.push_str(&format!(
+                "### {}\n{}\n\n",
+                file.file_path,
+                truncate_at_char_boundary(file.summary.trim(), MAX_SUMMARY_CHARS)
+            ));
+        }
+    }
+
+    if !history.is_empty() {
+        prompt.push_str("Conversation so far:\n\n");
+        for message in history {
+            let speaker = if message.role == "user" {
+                "User"
+            } else {
+                "Assistant"
+            };
+            prompt.push_str(&format!(
+                "{}: {}\n\n",
+                speaker,
+                truncate_at_char_boundary(message.content.trim(), MAX_HISTORY_CHARS)
+            ));
+        }
+    }
+
+    prompt.push_str(&format!(
+        "Question: {}\n\n\
+         Answer from the notes above, naming the files involved. If the notes don't cover the \
+         question, say so rather than guessing. Keep the answer short and use Markdown.\n\n\
+         {}",
+        question.trim(),
+        respond_in(output_language, true)
+    ));
+    prompt
+}
+
+/// Strip the repository prefix from a stored (absolute) file path
+fn relative_path(file_path: &str, repo_path: &str) -> String {
+    Path::new(file_path)
+        .strip_prefix(repo_path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| file_path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OllamaEndpoint;
+    use crate::db::Attribution;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn result(file_path: &str, summary: &str) -> AnalysisResult {
+        AnalysisResult {
+            id: 1,
+            repository_id: 1,
+            file_path: file_path.to_string(),
+            analysis_type: "code_understanding".to_string(),
+            result: summary.to_string(),
+            severity: None,
+            content_hash: None,
+            created_at: String::new(),
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            issue_url: None,
+        }
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert_eq!(
+            keywords("Where is auth handled? And what talks to the database?"),
+            ["auth", "database", "talks"]
+        );
+    }
+
+    #[test]
+    fn test_rank_by_keywords() {
+        let results = [
+            result("/repo/src/db.rs", "Database access with sqlx"),
+            result("/repo/src/auth.rs", "Checks sessions"),
+            result(
+                "/repo/src/web.rs",
+                "Routes; calls auth for every request and auth again",
+            ),
+            result("/repo/src/util.rs", "Helpers"),
+        ];
+
+        // The repository's own path doesn't count
+        let ranked = rank_by_keywords("where is auth handled?", &results, "/repo", 5);
+        assert_eq!(
+            ranked
+                .iter()
+                .map(|r| r.file_path.as_str())
+                .collect::<Vec<_>>(),
+            ["/repo/src/auth.rs", "/repo/src/web.rs"]
+        );
+        assert_eq!(rank_by_keywords("auth", &results, "/repo", 1).len(), 1);
+        assert!(rank_by_keywords("what is this?", &results, "/repo", 5).is_empty());
+        assert!(rank_by_keywords("repo", &results, "/repo", 5).is_empty());
+    }
+
+    #[test]
+    fn test_chat_prompt() {
+        let history = [ChatMessage {
+            id: 1,
+            repository_id: 1,
+            role: "user".to_string(),
+            content: "Where is auth handled?".to_string(),
+            sources: Vec::new(),
+            created_at: String::new(),
+        }];
+        let files = [ContextFile {
+            file_path: "src/auth.rs".to_string(),
+            summary: "Checks sessions".to_string(),
+        }];
+
+        let prompt = chat_prompt(
+            "app",
+            Some("A web app"),
+            &files,
+            &history,
+            "Who calls it?",
+            "English",
+        );
+        assert!(prompt.contains("'app' code base"));
+        assert!(prompt.contains("Architecture overview:\n\nA web app"));
+        assert!(prompt.contains("### src/auth.rs\nChecks sessions"));
+        assert!(prompt.contains("User: Where is auth handled?"));
+        assert!(prompt.contains("Question: Who calls it?"));
+
+        let prompt = chat_prompt("app", None, &[], &[], "Who calls it?", "English");
+        assert!(!prompt.contains("Architecture overview"));
+        assert!(!prompt.contains("Conversation so far"));
+    }
+
+    #[tokio::test]
+    async fn test_ask() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+        let repo_dir = tempfile::TempDir::new().unwrap();
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+        std::fs::write(repo_dir.path().join("noctum.toml"), "").unwrap();
+        let repo_id = db.add_repository(&repo_path, "app").await.unwrap();
+        let repo = db.get_repository(repo_id).await.unwrap().unwrap();
+
+        let code_understanding = AnalysisType::CodeUnderstanding.to_string();
+        for (file, summary) in [("auth.rs", "Checks sessions"), ("db.rs", "Stores rows")] {
+            db.save_analysis_result(
+                repo_id,
+                &format!("{}/src/{}", repo_path, file),
+                &code_understanding,
+                summary,
+                None,
+                None,
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "models": [] })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_string_contains("### src/auth.rs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "response": "In `src/auth.rs`.\n", "done": true }),
+            ))
+            .mount(&server)
+            .await;
+
+        let endpoint: OllamaEndpoint = toml::from_str(&format!(
+            "name = \"local\"\nurl = \"{}\"\nmodel = \"llama3\"",
+            server.uri()
+        ))
+        .unwrap();
+        let config = Config {
+            endpoints: vec![endpoint],
+            ..Config::default()
+        };
+
+        let answer = ask(
+            &db,
+            &config,
+            &HealthMonitor::new(),
+            &repo,
+            "Where is auth handled?",
+        )
+        .await
+        .unwrap();
+        assert_eq!(answer.role, "assistant");
+        assert_eq!(answer.content, "In `src/auth.rs`.");
+        assert_eq!(answer.sources, ["src/auth.rs"]);
+
+        let messages = db.get_chat_messages(repo_id, 10).await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].content, "Where is auth handled?");
+    }
+}
@@ -0,0 +1,541 @@
+//! Editing and validating the config file from the command line.
+//!
+//! `noctum config set` and `noctum config add-endpoint` change the TOML
+//! document itself rather than a loaded [`Config`], so keys they don't touch
+//! are kept as written. Every change is validated before it is saved, and
+//! problems name the offending key (e.g. `endpoints[0].url`), including keys
+//! Noctum doesn't know, which serde would otherwise silently ignore.
+
+use super::{Config, OllamaEndpoint};
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Sections a profile can override, see [`super::ProfileConfig`]
+const PROFILE_KEYS: &[&str] = &["data_dir", "endpoints", "web", "schedule"];
+
+/// A problem with the value of a config key
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// Dotted path of the key, e.g. `web.port` or `endpoints[0].url`
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+impl Problem {
+    fn new(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Read the config file as a TOML document (empty if it does not exist)
+pub fn read_document(path: &Path) -> Result<Table> {
+    if !path.exists() {
+        return Ok(Table::new());
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config from {:?}", path))?;
+    contents
+        .parse()
+        .with_context(|| format!("Failed to parse config from {:?}", path))
+}
+
+/// Write a TOML document to the config file, creating its directory
+pub fn write_document(path: &Path, document: &Table) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory: {:?}", parent))?;
+    }
+    let contents = toml::to_string_pretty(document).context("Failed to serialize config")?;
+    std::fs::write(path, contents).with_context(|| format!("Failed to write config to {:?}", path))
+}
+
+/// Set a key to a value given on the command line.
+///
+/// The value is read as a TOML value (`9000`, `true`, `["a", "b"]`), or as a
+/// string if it isn't one or if only a string is valid for the key. With a
+/// profile, the key is set in that profile's section.
+pub fn set(document: &mut Table, profile: Option<&str>, key: &str, raw: &str) -> Result<()> {
+    let key = scoped_key(profile, key)?;
+    let path = parse_key(&key)?;
+
+    let mut candidates = Vec::new();
+    if let Some(value) = parse_value(raw) {
+        candidates.push(value);
+    }
+    if !candidates.iter().any(|v| v.as_str() == Some(raw)) {
+        candidates.push(Value::String(raw.to_string()));
+    }
+
+    let mut first_problems = None;
+    for value in candidates {
+        let mut edited = document.clone();
+        insert(&mut edited, &path, value).with_context(|| format!("Cannot set {}", key))?;
+        let problems = validate(&edited);
+        if problems.is_empty() {
+            *document = edited;
+            return Ok(());
+        }
+        first_problems.get_or_insert(problems);
+    }
+    Err(invalid(first_problems.unwrap_or_default()))
+}
+
+/// Append an endpoint, to a profile's endpoints if one is given. Names must
+/// be unique.
+pub fn add_endpoint(
+    document: &mut Table,
+    profile: Option<&str>,
+    endpoint: &OllamaEndpoint,
+) -> Result<()> {
+    let path = parse_key(&scoped_key(profile, "endpoints")?)?;
+    let value = Value::try_from(endpoint).context("Failed to serialize endpoint")?;
+
+    let mut edited = document.clone();
+    let mut endpoints = match lookup(&edited, &path) {
+        Some(Value::Array(endpoints)) => endpoints.clone(),
+        Some(_) => anyhow::bail!("{} is not a list of endpoints", path.join(".")),
+        None => Vec::new(),
+    };
+    if endpoints
+        .iter()
+        .any(|e| e.get("name").and_then(Value::as_str) == Some(endpoint.name.as_str()))
+    {
+        anyhow::bail!("An endpoint named '{}' already exists", endpoint.name);
+    }
+    endpoints.push(value);
+    insert(&mut edited, &path, Value::Array(endpoints))?;
+
+    let problems = validate(&edited);
+    if !problems.is_empty() {
+        return Err(invalid(problems));
+    }
+    *document = edited;
+    Ok(())
+}
+
+/// Check a config document: every value must have the right type, every key
+/// must be known, and endpoints, schedule hours and the log level must make
+/// sense. An empty list means the document is valid.
+pub fn validate(document: &Table) -> Vec<Problem> {
+    let config: Config = match serde_path_to_error::deserialize(Value::Table(document.clone())) {
+        Ok(config) => config,
+        Err(e) => {
+            let key = describe_path(e.path());
+            return vec![Problem::new(key, e.into_inner().message().to_string())];
+        }
+    };
+
+    let mut problems = Vec::new();
+    if let Ok(Value::Table(known)) = Value::try_from(&config) {
+        unknown_keys(document, &known, "", &mut problems);
+    }
+
+    if let Err(e) = crate::logs::parse_filter(&config.general.log_level) {
+        problems.push(Problem::new("general.log_level", format!("{:#}", e)));
+    }
+    if let Err(e) = config.check_database_url() {
+        problems.push(Problem::new("general.database_url", format!("{:#}", e)));
+    }
+    if config.general.output_language.trim().is_empty() {
+        problems.push(Problem::new("general.output_language", "must not be empty"));
+    }
+    check_endpoints(&config.endpoints, "endpoints", &mut problems);
+    check_schedule(&config.schedule, "schedule", &mut problems);
+    for (name, profile) in &config.profiles {
+        let prefix = format!("profiles.{}", name);
+        check_endpoints(
+            &profile.endpoints,
+            &format!("{}.endpoints", prefix),
+            &mut problems,
+        );
+        if let Some(ref schedule) = profile.schedule {
+            check_schedule(schedule, &format!("{}.schedule", prefix), &mut problems);
+        }
+    }
+    problems
+}
+
+/// An error listing the problems of a document
+fn invalid(problems: Vec<Problem>) -> anyhow::Error {
+    let lines: Vec<String> = problems.iter().map(|p| format!("  {}", p)).collect();
+    anyhow::anyhow!("Invalid configuration:\n{}", lines.join("\n"))
+}
+
+/// The key a command-line key refers to, inside the profile's section if one
+/// is given
+fn scoped_key(profile: Option<&str>, key: &str) -> Result<String> {
+    let Some(profile) = profile else {
+        return Ok(key.to_string());
+    };
+    let root = key.split(['.', '[']).next().unwrap_or_default();
+    if !PROFILE_KEYS.contains(&root) {
+        anyhow::bail!(
+            "{} can't be set per profile; profiles override {}",
+            key,
+            PROFILE_KEYS.join(", ")
+        );
+    }
+    Ok(format!("profiles.{}.{}", profile, key))
+}
+
+/// Split a key like `endpoints[0].url` or `endpoints.0.url` into its parts
+fn parse_key(key: &str) -> Result<Vec<String>> {
+    let parts: Vec<String> = key
+        .replace('[', ".")
+        .replace(']', "")
+        .split('.')
+        .map(|part| part.trim().to_string())
+        .collect();
+    if parts.iter().any(String::is_empty) {
+        anyhow::bail!(
+            "Invalid key '{}': expected a dotted path like web.port",
+            key
+        );
+    }
+    Ok(parts)
+}
+
+/// Read a command-line value as a TOML value, if it is one
+fn parse_value(raw: &str) -> Option<Value> {
+    format!("value = {}", raw)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+}
+
+fn lookup<'a>(table: &'a Table, path: &[String]) -> Option<&'a Value> {
+    let (first, rest) = path.split_first()?;
+    let mut value = table.get(first)?;
+    for part in rest {
+        value = match value {
+            Value::Table(table) => table.get(part)?,
+            Value::Array(items) => items.get(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+/// Set the value at a path, creating missing tables on the way. Array items
+/// are addressed by index and must exist.
+fn insert(table: &mut Table, path: &[String], value: Value) -> Result<()> {
+    let (first, rest) = path.split_first().context("Empty key")?;
+    if rest.is_empty() {
+        table.insert(first.clone(), value);
+        return Ok(());
+    }
+    let child = table
+        .entry(first.clone())
+        .or_insert_with(|| Value::Table(Table::new()));
+    insert_into(child, first, rest, value)
+}
+
+fn insert_into(parent: &mut Value, name: &str, path: &[String], value: Value) -> Result<()> {
+    match parent {
+        Value::Table(table) => insert(table, path, value),
+        Value::Array(items) => {
+            let (first, rest) = path.split_first().context("Empty key")?;
+            let index: usize = first
+                .parse()
+                .with_context(|| format!("{} is a list; address its items by index", name))?;
+            let len = items.len();
+            let item = items
+                .get_mut(index)
+                .with_context(|| format!("{} has {} items, no item {}", name, len, index))?;
+            if rest.is_empty() {
+                *item = value;
+                Ok(())
+            } else {
+                insert_into(item, first, rest, value)
+            }
+        }
+        _ => anyhow::bail!("{} is not a table", name),
+    }
+}
+
+/// A serde path as a config key, e.g. `endpoints[0].url`
+fn describe_path(path: &serde_path_to_error::Path) -> String {
+    let mut key = String::new();
+    for segment in path.iter() {
+        match segment {
+            serde_path_to_error::Segment::Seq { index } => key.push_str(&format!("[{}]", index)),
+            serde_path_to_error::Segment::Map { key: name }
+            | serde_path_to_error::Segment::Enum { variant: name } => {
+                if !key.is_empty() {
+                    key.push('.');
+                }
+                key.push_str(name);
+            }
+            serde_path_to_error::Segment::Unknown => {}
+        }
+    }
+    if key.is_empty() {
+        "(config)".to_string()
+    } else {
+        key
+    }
+}
+
+/// Report keys of `document` missing from `known`, the document as read back
+/// from a [`Config`]
+fn unknown_keys(document: &Table, known: &Table, prefix: &str, problems: &mut Vec<Problem>) {
+    for (name, value) in document {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match (value, known.get(name)) {
+            (_, None) => problems.push(Problem::new(key, "unknown key")),
+            (Value::Table(table), Some(Value::Table(known))) => {
+                unknown_keys(table, known, &key, problems)
+            }
+            (Value::Array(items), Some(Value::Array(known))) => {
+                for (index, (item, known)) in items.iter().zip(known).enumerate() {
+                    if let (Value::Table(item), Value::Table(known)) = (item, known) {
+                        unknown_keys(item, known, &format!("{}[{}]", key, index), problems);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_endpoints(endpoints: &[OllamaEndpoint], prefix: &str, problems: &mut Vec<Problem>) {
+    let mut names = HashSet::new();
+    for (index, endpoint) in endpoints.iter().enumerate() {
+        let key = |field: &str| format!("{}[{}].{}", prefix, index, field);
+        if endpoint.name.trim().is_empty() {
+            problems.push(Problem::new(key("name"), "must not be empty"));
+        } else if !names.insert(endpoint.name.as_str()) {
+            problems.push(Problem::new(
+                key("name"),
+                format!("another endpoint is already named '{}'", endpoint.name),
+            ));
+        }
+        if !endpoint.url.starts_with("http://") && !endpoint.url.starts_with("https://") {
+            problems.push(Problem::new(
+                key("url"),
+                format!("'{}' is not an http:// or https:// URL", endpoint.url),
+            ));
+        }
+        if endpoint.model.trim().is_empty() {
+            problems.push(Problem::new(key("model"), "must not be empty"));
+        }
+
+        let options = &endpoint.options;
+        if options.temperature.is_some_and(|t| t < 0.0) {
+            problems.push(Problem::new(
+                key("options.temperature"),
+                "must not be negative",
+            ));
+        }
+        if let Some(top_p) = options.top_p.filter(|p| !(0.0..=1.0).contains(p)) {
+            problems.push(Problem::new(
+                key("options.top_p"),
+                format!("{} is not between 0 and 1", top_p),
+            ));
+        }
+        if options.num_ctx == Some(0) {
+            problems.push(Problem::new(key("options.num_ctx"), "must be positive"));
+        }
+        if options.num_predict.is_some_and(|n| n < -2) {
+            problems.push(Problem::new(
+                key("options.num_predict"),
+                "must be -1 (no limit), -2 (fill the context) or a token count",
+            ));
+        }
+    }
+}
+
+fn check_schedule(schedule: &super::ScheduleConfig, prefix: &str, problems: &mut Vec<Problem>) {
+    for (field, hour) in [
+        ("start_hour", schedule.start_hour),
+        ("end_hour", schedule.end_hour),
+    ] {
+        if hour > 23 {
+            problems.push(Problem::new(
+                format!("{}.{}", prefix, field),
+                format!("{} is not an hour (0-23)", hour),
+            ));
+        }
+    }
+
+    let windows = schedule
+        .windows
+        .iter()
+        .enumerate()
+        .map(|(index, window)| (format!("{}.windows[{}]", prefix, index), window))
+        .chain(schedule.tags.iter().flat_map(|(tag, windows)| {
+            windows
+                .iter()
+                .enumerate()
+                .map(move |(index, window)| (format!("{}.tags.{}[{}]", prefix, tag, index), window))
+        }));
+    for (key, window) in windows {
+        if window.start_hour > 23 {
+            problems.push(Problem::new(
+                format!("{}.start_hour", key),
+                format!("{} is not an hour (0-23)", window.start_hour),
+            ));
+        }
+        if window.end_hour > 24 {
+            problems.push(Problem::new(
+                format!("{}.end_hour", key),
+                format!("{} is not an end hour (0-24)", window.end_hour),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    fn document(toml: &str) -> Table {
+        toml.parse().unwrap()
+    }
+
+    fn to_config(document: &Table) -> Result<Config> {
+        Ok(Config::deserialize(Value::Table(document.clone()))?)
+    }
+
+    fn keys(problems: &[Problem]) -> Vec<&str> {
+        problems.iter().map(|p| p.key.as_str()).collect()
+    }
+
+    #[test]
+    fn test_set_values() {
+        let mut doc = document("[web]\nport = 8420\n");
+
+        set(&mut doc, None, "web.port", "9000").unwrap();
+        set(&mut doc, None, "web.host", "0.0.0.0").unwrap();
+        set(&mut doc, None, "backup.enabled", "false").unwrap();
+        // Only a string is valid here, though 15 is a TOML integer
+        set(&mut doc, None, "search.embedding_model", "15").unwrap();
+
+        let config = to_config(&doc).unwrap();
+        assert_eq!(config.web.port, 9000);
+        assert_eq!(config.web.host, "0.0.0.0");
+        assert!(!config.backup.enabled);
+        assert_eq!(config.search.embedding_model, "15");
+
+        let error = set(&mut doc, None, "web.port", "eighty").unwrap_err();
+        assert!(
+            error.to_string().contains("web.port: invalid type"),
+            "{}",
+            error
+        );
+        let error = set(&mut doc, None, "web.prot", "80").unwrap_err();
+        assert!(
+            error.to_string().contains("web.prot: unknown key"),
+            "{}",
+            error
+        );
+        assert_eq!(to_config(&doc).unwrap().web.port, 9000);
+    }
+
+    #[test]
+    fn test_set_in_lists_and_profiles() {
+        let mut doc = document(
+            "[[endpoints]]\nname = \"local\"\nurl = \"http://localhost:11434\"\nmodel = \"llama3\"\n",
+        );
+
+        set(&mut doc, None, "endpoints[0].model", "qwen2.5-coder").unwrap();
+        set(&mut doc, None, "endpoints.0.context_window", "32768").unwrap();
+        let endpoint = &to_config(&doc).unwrap().endpoints[0];
+        assert_eq!(endpoint.model, "qwen2.5-coder");
+        assert_eq!(endpoint.context_window, Some(32768));
+        assert!(set(&mut doc, None, "endpoints[1].model", "llama3").is_err());
+
+        set(&mut doc, Some("work"), "web.port", "9001").unwrap();
+        let config = to_config(&doc).unwrap();
+        assert_eq!(config.profiles["work"].web.as_ref().unwrap().port, 9001);
+        assert!(set(&mut doc, Some("work"), "backup.keep", "3").is_err());
+    }
+
+    #[test]
+    fn test_add_endpoint() {
+        let mut doc = Table::new();
+        let endpoint = OllamaEndpoint::new(
+            "local".to_string(),
+            "http://localhost:11434".to_string(),
+            "llama3".to_string(),
+        );
+
+        add_endpoint(&mut doc, None, &endpoint).unwrap();
+        add_endpoint(&mut doc, Some("work"), &endpoint).unwrap();
+        let config = to_config(&doc).unwrap();
+        assert_eq!(config.endpoints[0].url, "http://localhost:11434");
+        assert_eq!(config.profiles["work"].endpoints[0].name, "local");
+
+        let error = add_endpoint(&mut doc, None, &endpoint).unwrap_err();
+        assert!(error.to_string().contains("already exists"));
+        let bad = OllamaEndpoint::new(
+            "remote".to_string(),
+            "gpu-box:11434".to_string(),
+            "llama3".to_string(),
+        );
+        let error = add_endpoint(&mut doc, None, &bad).unwrap_err();
+        assert!(error.to_string().contains("endpoints[1].url"), "{}", error);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&Table::new()).is_empty());
+
+        let problems = validate(&document("[web]\nport = \"x\"\n"));
+        assert_eq!(keys(&problems), ["web.port"]);
+
+        let problems = validate(&document(
+            "[general]\nlog_level = \"loud\"\ndatabase_url = \"mysql://db/noctum\"\noutput_language = \" \"\n\n[schedule]\nstart_hour = 25\nstrat_hour = 1\n\n\
+             [[schedule.windows]]\nstart_hour = 1\nend_hour = 30\n\n\
+             [[endpoints]]\nname = \"a\"\nurl = \"http://a\"\nmodel = \"\"\n\n\
+             [[endpoints]]\nname = \"a\"\nurl = \"ftp://b\"\nmodel = \"m\"\ncolour = \"red\"\n",
+        ));
+        let mut found = keys(&problems);
+        found.sort_unstable();
+        assert_eq!(
+            found,
+            [
+                "endpoints[0].model",
+                "endpoints[1].colour",
+                "endpoints[1].name",
+                "endpoints[1].url",
+                "general.database_url",
+                "general.log_level",
+                "general.output_language",
+                "schedule.start_hour",
+                "schedule.strat_hour",
+                "schedule.windows[0].end_hour",
+            ]
+        );
+
+        let problems = validate(&document(
+            "[[endpoints]]\nname = \"a\"\nurl = \"http://a\"\nmodel = \"m\"\n\n\
+             [endpoints.options]\ntemperature = -1.0\ntop_p = 1.5\nnum_ctx = 4096\ntop_k = 40\n",
+        ));
+        assert_eq!(
+            keys(&problems),
+            [
+                "endpoints[0].options.top_k",
+                "endpoints[0].options.temperature",
+                "endpoints[0].options.top_p",
+            ]
+        );
+    }
+}
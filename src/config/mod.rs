@@ -1,12 +1,16 @@
 //! Configuration management for Noctum.
 //!
 //! Handles loading, saving, and validating application configuration from TOML files.
-//! Supports schedule windows, multiple Ollama endpoints, and web server settings.
+//! Supports schedule windows, multiple Ollama endpoints, web server settings,
+//! and named profiles which isolate data directories and endpoints.
+
+pub mod edit;
 
 use anyhow::{Context, Result};
-use chrono::Timelike;
+use chrono::{Datelike, NaiveDateTime, Timelike, Weekday};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 /// Application configuration
@@ -31,13 +35,109 @@ pub struct Config {
     /// Data directory (where database and logs are stored)
     #[serde(default)]
     pub data_dir: Option<PathBuf>,
+
+    /// Database backup settings
+    #[serde(default)]
+    pub backup: BackupConfig,
+
+    /// Notification settings
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+
+    /// Issue tracker access for repositories that import issues
+    #[serde(default)]
+    pub issues: IssueTrackerConfig,
+
+    /// Offsite export of reports and backups
+    #[serde(default)]
+    pub export: ExportConfig,
+
+    /// Semantic search over file summaries
+    #[serde(default)]
+    pub search: SearchConfig,
+
+    /// How repositories are snapshotted before each scan
+    #[serde(default)]
+    pub snapshot: SnapshotConfig,
+
+    /// How diagrams are rendered to SVG
+    #[serde(default)]
+    pub diagram: DiagramConfig,
+
+    /// How work is routed to endpoints by size
+    #[serde(default)]
+    pub routing: RoutingConfig,
+
+    /// Languages supported through external commands, beyond the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<LanguageConfig>,
+
+    /// Named profiles, selected with `--profile <name>`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+
+    /// Name of the active profile (resolved at load time, never written to disk)
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+/// A named configuration profile.
+///
+/// A profile never shares its database or endpoints with the base configuration:
+/// endpoints are not inherited, and the data directory defaults to
+/// `<data_dir>/profiles/<name>`. Web and schedule settings are inherited unless overridden.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileConfig {
+    /// Data directory for this profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Ollama endpoints for this profile
+    #[serde(default)]
+    pub endpoints: Vec<OllamaEndpoint>,
+
+    /// Web server settings override
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web: Option<WebConfig>,
+
+    /// Schedule settings override
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<ScheduleConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GeneralConfig {
-    /// Log level (trace, debug, info, warn, error)
+    /// Log level (trace, debug, info, warn, error), optionally followed by
+    /// per-module levels, e.g. `info,noctum::daemon=debug`
     #[serde(default = "default_log_level")]
     pub log_level: String,
+
+    /// Append the daemon's log to `<data_dir>/logs/noctum.log` as JSON lines
+    #[serde(default = "default_enabled")]
+    pub log_file: bool,
+
+    /// Size in megabytes at which the log file is rotated
+    #[serde(default = "default_log_max_size_mb")]
+    pub log_max_size_mb: u64,
+
+    /// Number of rotated log files kept (`noctum.log.1`, `noctum.log.2`, ...)
+    #[serde(default = "default_log_max_files")]
+    pub log_max_files: u32,
+
+    /// Accept daemon commands on the Unix socket `<data_dir>/noctum.sock`
+    #[serde(default = "default_enabled")]
+    pub control_socket: bool,
+
+    /// Database to store analysis history in, as a `sqlite:` URL (relative
+    /// paths are resolved against the data directory) or a `postgres://` URL
+    /// of a server shared by a team. Defaults to `<data_dir>/noctum.db`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_url: Option<String>,
+
+    /// Natural language the LLM is asked to write analyses, summaries and
+    /// diagrams in, e.g. `German`
+    #[serde(default = "default_output_language")]
+    pub output_language: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +149,21 @@ pub struct WebConfig {
     /// Host to bind to
     #[serde(default = "default_host")]
     pub host: String,
+
+    /// Directory of templates replacing the built-in dashboard and report
+    /// templates of the same name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub templates_dir: Option<PathBuf>,
+
+    /// Requests per minute accepted by the expensive endpoints (scan trigger,
+    /// diagram regeneration, Ollama connection test), in bursts of up to as many
+    #[serde(default = "default_expensive_requests_per_minute")]
+    pub expensive_requests_per_minute: u32,
+
+    /// Expensive requests that may wait for the running one before further
+    /// requests are rejected
+    #[serde(default = "default_expensive_queue_size")]
+    pub expensive_queue_size: usize,
 }
 
 /// An Ollama endpoint configuration
@@ -66,14 +181,165 @@ pub struct OllamaEndpoint {
     /// Whether this endpoint is enabled
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+
+    /// Whether the endpoint runs on trusted hardware. Only `local` endpoints
+    /// receive the content of sensitive repositories.
+    #[serde(default)]
+    pub location: EndpointLocation,
+
+    /// How long Ollama keeps the model loaded after a request, e.g. `"30m"`,
+    /// or `"-1"` for as long as Ollama runs. Endpoints with this set are also
+    /// warmed up when a schedule window opens, so the model is loaded before
+    /// the first analysis.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<String>,
+
+    /// Seconds before a generation is abandoned (0 waits indefinitely)
+    #[serde(default = "default_request_timeout_seconds")]
+    pub request_timeout_seconds: u64,
+
+    /// Times a generation is retried after a transient error (connection
+    /// failure, timeout, 5xx or 429 response)
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry; doubles with each further retry
+    #[serde(default = "default_retry_backoff_seconds")]
+    pub retry_backoff_seconds: u64,
+
+    /// Upper bound for the doubling retry delay
+    #[serde(default = "default_retry_backoff_max_seconds")]
+    pub retry_backoff_max_seconds: u64,
+
+    /// Context window of the model in tokens, if known. Large files and
+    /// architecture summaries go to the endpoints with the largest window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+
+    /// Speed and size class of the model, used to route work (see [`RoutingConfig`])
+    #[serde(default)]
+    pub tier: EndpointTier,
+
+    /// Sampling and context settings sent as Ollama's `options` with every
+    /// generation (the model's defaults for those left out)
+    #[serde(default, skip_serializing_if = "GenerationOptions::is_empty")]
+    pub options: GenerationOptions,
+}
+
+/// Model parameters of an endpoint, named as in Ollama's request `options`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationOptions {
+    /// Higher values give more varied output; low values suit mutation
+    /// generation, higher ones architecture summaries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+
+    /// Nucleus sampling: only tokens within this cumulative probability are
+    /// considered (0-1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+
+    /// Context window Ollama loads the model with, in tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+
+    /// Maximum tokens to generate (-1 for no limit)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<i32>,
+
+    /// Seed for reproducible output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+}
+
+impl GenerationOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Where an endpoint runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EndpointLocation {
+    /// Trusted hardware, e.g. the machine Noctum runs on
+    Local,
+    /// Anything else; the default, so an endpoint must be labeled before it
+    /// sees sensitive code
+    #[default]
+    Remote,
+}
+
+/// Database named by `general.database_url`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DatabaseUrl {
+    /// A SQLite database file
+    Sqlite(PathBuf),
+    /// A PostgreSQL server, by its connection URL
+    Postgres(String),
+}
+
+/// Speed and size class of an endpoint's model
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EndpointTier {
+    /// A small, quick model, preferred for small files
+    Fast,
+    /// Neither preferred nor avoided
+    #[default]
+    Standard,
+    /// A big model with a large context window, preferred for large files and
+    /// architecture summaries
+    Large,
+}
+
+impl OllamaEndpoint {
+    /// An enabled, remote endpoint with default request settings
+    pub fn new(name: String, url: String, model: String) -> Self {
+        Self {
+            name,
+            url,
+            model,
+            enabled: default_enabled(),
+            location: EndpointLocation::default(),
+            keep_alive: None,
+            request_timeout_seconds: default_request_timeout_seconds(),
+            max_retries: default_max_retries(),
+            retry_backoff_seconds: default_retry_backoff_seconds(),
+            retry_backoff_max_seconds: default_retry_backoff_max_seconds(),
+            context_window: None,
+            tier: EndpointTier::default(),
+            options: GenerationOptions::default(),
+        }
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.location == EndpointLocation::Local
+    }
 }
 
 fn default_enabled() -> bool {
     true
 }
 
+fn default_request_timeout_seconds() -> u64 {
+    600
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff_seconds() -> u64 {
+    2
+}
+
+fn default_retry_backoff_max_seconds() -> u64 {
+    60
+}
+
 /// Schedule configuration for when analysis runs
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ScheduleConfig {
     /// Start hour (0-23) of the allowed window
     #[serde(default = "default_start_hour")]
@@ -86,56 +352,812 @@ pub struct ScheduleConfig {
     /// How often to check schedule (in seconds)
     #[serde(default = "default_check_interval")]
     pub check_interval_seconds: u64,
+
+    /// Quiet period (in seconds) after a manual trigger before the scan starts;
+    /// further triggers within the window restart it and coalesce into one scan
+    #[serde(default = "default_trigger_debounce")]
+    pub trigger_debounce_seconds: u64,
+
+    /// Named windows with day-of-week masks. When set, they replace the daily
+    /// `start_hour`-`end_hour` window.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<ScheduleWindow>,
+
+    /// Windows of repositories with a tag, e.g. `work` repositories only on
+    /// weeknights. They replace the windows above for repositories with the tag.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, Vec<ScheduleWindow>>,
 }
 
 impl ScheduleConfig {
     /// Check if the current time is within the scheduled window
     pub fn is_in_window(&self) -> bool {
-        let now = chrono::Local::now();
-        let current_hour = now.hour() as u8;
-        self.is_hour_in_window(current_hour)
+        self.is_in_window_at(chrono::Local::now().naive_local())
+    }
+
+    /// Check if a local time is within the scheduled window
+    pub fn is_in_window_at(&self, now: NaiveDateTime) -> bool {
+        if self.windows.is_empty() {
+            return self.is_hour_in_window(now.hour() as u8);
+        }
+        self.windows.iter().any(|window| window.contains(now))
+    }
+
+    /// Check if a local time is within the windows of a repository with
+    /// `tags`: those of its tags that have their own windows, or the schedule
+    /// above if none do
+    pub fn is_open_for_at(&self, tags: &[String], now: NaiveDateTime) -> bool {
+        let mut tag_windows = tags.iter().filter_map(|tag| self.tags.get(tag)).peekable();
+        if tag_windows.peek().is_none() {
+            return self.is_in_window_at(now);
+        }
+        tag_windows.flatten().any(|window| window.contains(now))
+    }
+
+    /// Check if the current time is within the windows of any repository
+    pub fn is_any_open(&self) -> bool {
+        self.is_any_open_at(chrono::Local::now().naive_local())
+    }
+
+    /// Check if a local time is within the schedule or the windows of any tag
+    pub fn is_any_open_at(&self, now: NaiveDateTime) -> bool {
+        self.is_in_window_at(now) || self.tags.values().flatten().any(|w| w.contains(now))
+    }
+
+    /// Time left in the current window of a repository with `tags`: zero
+    /// outside its windows, `None` if they never close (e.g. a schedule
+    /// covering every hour)
+    pub fn remaining_at(&self, tags: &[String], now: NaiveDateTime) -> Option<chrono::Duration> {
+        if !self.is_open_for_at(tags, now) {
+            return Some(chrono::Duration::zero());
+        }
+        // Windows are hour-aligned, so step from hour boundary to hour boundary
+        let mut end = now.date().and_hms_opt(now.hour(), 0, 0)? + chrono::Duration::hours(1);
+        for _ in 0..8 * 24 {
+            if !self.is_open_for_at(tags, end) {
+                return Some(end - now);
+            }
+            end += chrono::Duration::hours(1);
+        }
+        None
+    }
+
+    /// Human-readable description of the schedule, for logs
+    pub fn describe(&self) -> String {
+        let describe_windows = |windows: &[ScheduleWindow]| {
+            windows
+                .iter()
+                .map(ScheduleWindow::describe)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut description = if self.windows.is_empty() {
+            format!("{:02}:00 - {:02}:00", self.start_hour, self.end_hour)
+        } else {
+            describe_windows(&self.windows)
+        };
+        for (tag, windows) in &self.tags {
+            description.push_str(&format!("; {}: {}", tag, describe_windows(windows)));
+        }
+        description
+    }
+
+    /// Check if a specific hour is within the scheduled window (for testing)
+    pub fn is_hour_in_window(&self, hour: u8) -> bool {
+        if self.start_hour <= self.end_hour {
+            // Normal range: e.g., 9-17 (9am to 5pm)
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            // Overnight range: e.g., 22-6 (10pm to 6am)
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+/// A day of the week in a schedule window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Day {
+    #[serde(alias = "monday")]
+    Mon,
+    #[serde(alias = "tuesday")]
+    Tue,
+    #[serde(alias = "wednesday")]
+    Wed,
+    #[serde(alias = "thursday")]
+    Thu,
+    #[serde(alias = "friday")]
+    Fri,
+    #[serde(alias = "saturday")]
+    Sat,
+    #[serde(alias = "sunday")]
+    Sun,
+}
+
+impl From<Weekday> for Day {
+    fn from(day: Weekday) -> Self {
+        match day {
+            Weekday::Mon => Day::Mon,
+            Weekday::Tue => Day::Tue,
+            Weekday::Wed => Day::Wed,
+            Weekday::Thu => Day::Thu,
+            Weekday::Fri => Day::Fri,
+            Weekday::Sat => Day::Sat,
+            Weekday::Sun => Day::Sun,
+        }
+    }
+}
+
+/// A recurring time window in which background analysis runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleWindow {
+    /// Name shown in logs and on the dashboard
+    #[serde(default)]
+    pub name: String,
+
+    /// Days the window starts on (every day if empty). The part of an
+    /// overnight window after midnight belongs to the day it started on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Day>,
+
+    /// Start hour (0-23)
+    pub start_hour: u8,
+
+    /// End hour (0-24, exclusive). An end before the start continues into the
+    /// next day; `start_hour = 0, end_hour = 24` is the whole day.
+    pub end_hour: u8,
+}
+
+impl ScheduleWindow {
+    /// Check if a local time is within this window
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let hour = now.hour() as u8;
+        let today = now.weekday();
+        if self.start_hour <= self.end_hour {
+            self.starts_on(today) && hour >= self.start_hour && hour < self.end_hour
+        } else {
+            (self.starts_on(today) && hour >= self.start_hour)
+                || (self.starts_on(today.pred()) && hour < self.end_hour)
+        }
+    }
+
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&Day::from(day))
+    }
+
+    /// Human-readable description, e.g. `weekends Sat/Sun 00:00 - 24:00`
+    pub fn describe(&self) -> String {
+        let days = if self.days.is_empty() {
+            "daily".to_string()
+        } else {
+            self.days
+                .iter()
+                .map(|d| format!("{:?}", d))
+                .collect::<Vec<_>>()
+                .join("/")
+        };
+        let name = if self.name.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", self.name)
+        };
+        format!(
+            "{}{} {:02}:00 - {:02}:00",
+            name, days, self.start_hour, self.end_hour
+        )
+    }
+}
+
+/// Automatic database backup configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Whether the daemon takes automatic backups
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Minimum hours between automatic backups
+    #[serde(default = "default_backup_interval_hours")]
+    pub interval_hours: u64,
+
+    /// Number of backups to keep (older ones are deleted)
+    #[serde(default = "default_backup_keep")]
+    pub keep: usize,
+}
+
+/// Semantic search: after each scan, file summaries are embedded with an
+/// Ollama embedding model so they can be searched by meaning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchConfig {
+    /// Whether the daemon embeds file summaries
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Embedding model, which must be pulled on the endpoints
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+}
+
+/// Model routing: files of up to `large_file_chars` characters go to the
+/// `fast` endpoints, larger files and architecture summaries to the `large`
+/// endpoints or, without any, those with the largest `context_window`.
+/// Without tiers or context windows every endpoint takes every task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Size in characters above which a file counts as large
+    #[serde(default = "default_large_file_chars")]
+    pub large_file_chars: usize,
+}
+
+/// Repository snapshots: every scan works on a temporary copy of the
+/// repository, so mutation testing never touches the original
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    /// How files are placed in the snapshot
+    #[serde(default)]
+    pub strategy: SnapshotStrategy,
+
+    /// Leave out files and directories git ignores (build output, installed
+    /// dependencies), in addition to the repository's `copy_ignore` patterns
+    #[serde(default)]
+    pub respect_gitignore: bool,
+}
+
+/// How the files of a repository are placed in its snapshot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotStrategy {
+    /// Copy-on-write clones (reflinks) where the filesystem supports them
+    /// (Btrfs, XFS, APFS), full copies elsewhere
+    #[default]
+    Auto,
+    /// Full copies of every file
+    Copy,
+    /// Hard links to the repository's files, except in build and dependency
+    /// directories and manifests and lockfiles, which are copied. Fast and free
+    /// on any filesystem, but a tool that rewrites another file in place writes
+    /// through to the repository.
+    Hardlink,
+    /// Copies of source files, manifests and documentation only; build output,
+    /// dependencies and other files are left out
+    Sources,
+}
+
+/// Diagram generation and rendering
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiagramConfig {
+    /// The language diagrams are generated in
+    #[serde(default)]
+    pub format: DiagramFormat,
+
+    /// Which renderer turns the generated DOT into SVG
+    #[serde(default)]
+    pub renderer: DiagramRenderer,
+
+    /// The Graphviz `dot` binary, looked up on the `PATH` if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dot_path: Option<PathBuf>,
+}
+
+/// The language diagrams are generated in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagramFormat {
+    /// GraphViz DOT, rendered to SVG on the server
+    #[default]
+    Dot,
+    /// Mermaid, rendered in the browser, and rendered by GitHub, GitLab and
+    /// Notion when pasted into Markdown
+    Mermaid,
+}
+
+impl DiagramFormat {
+    /// The identifier stored with each diagram
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagramFormat::Dot => "dot",
+            DiagramFormat::Mermaid => "mermaid",
+        }
+    }
+}
+
+/// Which renderer turns DOT into SVG
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagramRenderer {
+    /// The built-in layout-rs renderer, which needs nothing installed but
+    /// doesn't support every DOT feature (HTML labels, some record shapes)
+    #[default]
+    Layout,
+    /// The system's Graphviz `dot -Tsvg`, falling back to layout-rs when it
+    /// isn't installed
+    Graphviz,
+}
+
+/// A language wired up through external commands, for stacks without
+/// built-in support (Elixir, Zig, Scala, ...).
+///
+/// Prompt templates may use `{language}`, `{file_path}` and `{content}`; the
+/// content of the mutation prompt is line-numbered. Languages without a
+/// template get generic prompts naming the language.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LanguageConfig {
+    /// Name shown in the dashboard and used in the generic prompts
+    pub name: String,
+
+    /// File extensions of source files, without the dot (e.g. `["ex", "exs"]`)
+    pub extensions: Vec<String>,
+
+    /// Files that mark a project root (e.g. `["mix.exs"]`)
+    #[serde(default)]
+    pub markers: Vec<String>,
+
+    /// Directories to skip when scanning for source files, besides hidden ones
+    #[serde(default)]
+    pub skip_dirs: Vec<String>,
+
+    /// Shell command that checks the project compiles, run in the project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compile_command: Option<String>,
+
+    /// Shell command that runs the project's tests, run in the project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_command: Option<String>,
+
+    /// Template of the file analysis prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis_prompt: Option<String>,
+
+    /// Template of the mutation generation prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mutation_prompt: Option<String>,
+
+    /// Template of the architecture-focused file analysis prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub architecture_prompt: Option<String>,
+}
+
+/// Notifications about scans, critical findings and survived mutants.
+///
+/// Each event is sent to every configured channel subscribed to it: the
+/// webhook, desktop and email channels follow `events`, and every entry of
+/// `webhooks` has its own list and payload format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// URL that receives a JSON POST for each notification
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+
+    /// Show desktop notifications (`notify-send` on Linux, `osascript` on macOS)
+    #[serde(default)]
+    pub desktop: bool,
+
+    /// Email recipients and delivery, and the nightly digest
+    #[serde(
+        default,
+        deserialize_with = "deserialize_email",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub email: Option<EmailConfig>,
+
+    /// Events sent to `webhook_url`, `desktop` and `email`
+    #[serde(default = "default_notification_events")]
+    pub events: Vec<NotificationEvent>,
+
+    /// Webhooks with their own payload format and events
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// Maximum number of mutants or findings listed in one notification
+    #[serde(default = "default_notification_max_mutants")]
+    pub max_mutants: usize,
+
+    /// Minimum number of new survived mutants in a run worth a notification
+    #[serde(default = "default_survived_threshold")]
+    pub survived_threshold: usize,
+
+    /// Maximum number of notifications about findings and mutants sent per 24 hours
+    #[serde(default = "default_notification_max_per_day")]
+    pub max_per_day: u32,
+
+    /// Base URL of the dashboard used for links (defaults to the web server address)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dashboard_url: Option<String>,
+}
+
+impl NotificationConfig {
+    /// Whether any notification channel is configured
+    pub fn is_enabled(&self) -> bool {
+        self.webhook_url.is_some()
+            || self.desktop
+            || self.email.as_ref().is_some_and(|email| email.notify)
+            || !self.webhooks.is_empty()
+    }
+
+    /// Whether any configured channel is subscribed to `event`
+    pub fn wants(&self, event: NotificationEvent) -> bool {
+        let basic = self.webhook_url.is_some()
+            || self.desktop
+            || self.email.as_ref().is_some_and(|email| email.notify);
+        (basic && self.events.contains(&event))
+            || self.webhooks.iter().any(|w| w.events.contains(&event))
+    }
+}
+
+/// Something worth a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    /// A processing cycle finished after analyzing or testing anything, or failed
+    ScanCompleted,
+    /// A scan found error-severity findings that weren't reported before
+    CriticalFindings,
+    /// Mutation testing found at least `survived_threshold` new survived mutants
+    SurvivedMutants,
+}
+
+/// A webhook and the events it receives
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+
+    /// Payload format
+    #[serde(default)]
+    pub format: WebhookFormat,
+
+    /// Events sent to the webhook (all by default)
+    #[serde(default = "default_notification_events")]
+    pub events: Vec<NotificationEvent>,
+}
+
+/// Payload format of a webhook
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// The full notification as JSON
+    #[default]
+    Generic,
+    /// A Slack incoming webhook message
+    Slack,
+    /// A Discord webhook message
+    Discord,
+}
+
+/// Email delivery: notifications about `events` and a morning digest of the
+/// night's scans.
+///
+/// Without `smtp_host`, mail is handed to the local `sendmail`. A plain
+/// address (`email = "me@example.com"`) is read as `to = ["me@example.com"]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailConfig {
+    /// Recipients
+    pub to: Vec<String>,
+
+    /// Sender address (the first recipient if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+
+    /// Whether notifications are emailed (the digest is sent either way)
+    #[serde(default = "default_enabled")]
+    pub notify: bool,
+
+    /// SMTP server to send through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smtp_host: Option<String>,
+
+    /// SMTP port (465 for `tls`, 25 for `none`, 587 for `starttls`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smtp_port: Option<u16>,
+
+    /// How the SMTP connection is encrypted
+    #[serde(default)]
+    pub smtp_tls: SmtpTls,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smtp_username: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smtp_password: Option<String>,
+
+    /// Send a digest of the scans since the previous one each morning
+    #[serde(default)]
+    pub digest: bool,
+
+    /// Local hour (0-23) the digest is sent at
+    #[serde(default = "default_digest_hour")]
+    pub digest_hour: u8,
+
+    /// Days the digest is sent on (every day if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub digest_days: Vec<Day>,
+}
+
+impl EmailConfig {
+    /// Settings for notifying a single address through `sendmail`
+    pub fn address(address: String) -> Self {
+        Self {
+            to: vec![address],
+            from: None,
+            notify: true,
+            smtp_host: None,
+            smtp_port: None,
+            smtp_tls: SmtpTls::default(),
+            smtp_username: None,
+            smtp_password: None,
+            digest: false,
+            digest_hour: default_digest_hour(),
+            digest_days: Vec::new(),
+        }
+    }
+
+    /// The configured SMTP port, or the usual one for `smtp_tls`
+    pub fn smtp_port(&self) -> u16 {
+        self.smtp_port.unwrap_or(match self.smtp_tls {
+            SmtpTls::Tls => 465,
+            SmtpTls::None => 25,
+            SmtpTls::Starttls => 587,
+        })
+    }
+
+    /// Whether a digest is due at local time `now`: `digest_hour` has passed
+    /// on a digest day and none was sent since
+    pub fn digest_due(&self, now: NaiveDateTime, last_sent: Option<NaiveDateTime>) -> bool {
+        let Some(slot) = now.date().and_hms_opt(self.digest_hour.into(), 0, 0) else {
+            return false;
+        };
+        let digest_day =
+            self.digest_days.is_empty() || self.digest_days.contains(&Day::from(now.weekday()));
+        self.digest && digest_day && now >= slot && last_sent.is_none_or(|sent| sent < slot)
+    }
+}
+
+/// Encryption of the SMTP connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// Upgrade a plain connection with `STARTTLS`
+    #[default]
+    Starttls,
+    /// Connect with TLS right away
+    Tls,
+    /// No encryption, e.g. for a relay on localhost
+    None,
+}
+
+/// Read `email` as either a plain address or a table of [`EmailConfig`]
+fn deserialize_email<'de, D>(deserializer: D) -> std::result::Result<Option<EmailConfig>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match toml::Value::deserialize(deserializer)? {
+        toml::Value::String(address) => Ok(Some(EmailConfig::address(address))),
+        value => value.try_into().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Issue tracker access.
+///
+/// Repositories opt in with `[issues] enabled = true` in their `noctum.toml`;
+/// tokens are only needed for private projects and higher rate limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueTrackerConfig {
+    /// GitHub token with read access to issues
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_token: Option<String>,
+
+    /// GitHub API URL (change for GitHub Enterprise)
+    #[serde(default = "default_github_api_url")]
+    pub github_api_url: String,
+
+    /// GitLab token with the `read_api` scope
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gitlab_token: Option<String>,
+
+    /// GitLab instance URL
+    #[serde(default = "default_gitlab_url")]
+    pub gitlab_url: String,
+}
+
+/// Offsite copies of reports and database backups.
+///
+/// After each scan the repository reports are uploaded, and every new
+/// automatic backup is uploaded once it is taken, to each configured target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportConfig {
+    /// Upload each repository's HTML report after a scan
+    #[serde(default = "default_enabled")]
+    pub reports: bool,
+
+    /// Upload new automatic database backups
+    #[serde(default = "default_enabled")]
+    pub backups: bool,
+
+    /// S3-compatible bucket (AWS S3, MinIO, Backblaze B2, Garage, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s3: Option<S3Target>,
+
+    /// WebDAV collection (Nextcloud, ownCloud, Apache mod_dav, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webdav: Option<WebDavTarget>,
+}
+
+impl ExportConfig {
+    /// Whether any export target is configured
+    pub fn is_enabled(&self) -> bool {
+        self.s3.is_some() || self.webdav.is_some()
     }
+}
+
+/// An S3-compatible export target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3Target {
+    /// Endpoint URL, e.g. `https://s3.eu-west-1.amazonaws.com` or `http://minio:9000`
+    pub endpoint: String,
+
+    pub bucket: String,
+
+    /// Region used for request signing
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+
+    pub access_key_id: String,
+
+    pub secret_access_key: String,
+
+    /// Key prefix for uploaded objects
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+
+    /// Address the bucket as `<endpoint>/<bucket>` instead of `<bucket>.<endpoint host>`
+    #[serde(default = "default_enabled")]
+    pub path_style: bool,
+}
+
+/// A WebDAV export target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebDavTarget {
+    /// URL of the collection files are uploaded into
+    pub url: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_output_language() -> String {
+    "English".to_string()
+}
+
+fn default_log_max_size_mb() -> u64 {
+    10
+}
+
+fn default_log_max_files() -> u32 {
+    5
+}
+
+/// Parse a `sqlite:` or `postgres://` database URL
+fn parse_database_url(url: &str) -> Result<DatabaseUrl> {
+    let url = url.trim();
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        return Ok(DatabaseUrl::Postgres(url.to_string()));
+    }
+    let Some(rest) = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))
+    else {
+        anyhow::bail!(
+            "Unsupported database URL '{}': expected a sqlite: or postgres:// URL",
+            url
+        );
+    };
+
+    let path = rest.split('?').next().unwrap_or_default();
+    if path.is_empty() || path == ":memory:" {
+        anyhow::bail!("The database URL '{}' must name a database file", url);
+    }
+    Ok(DatabaseUrl::Sqlite(PathBuf::from(path)))
+}
+
+fn default_port() -> u16 {
+    8420
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_expensive_requests_per_minute() -> u32 {
+    6
+}
+
+fn default_expensive_queue_size() -> usize {
+    4
+}
+
+fn default_start_hour() -> u8 {
+    22 // 10pm
+}
+
+fn default_end_hour() -> u8 {
+    6 // 6am
+}
+
+fn default_check_interval() -> u64 {
+    60 // Check every minute
+}
+
+fn default_trigger_debounce() -> u64 {
+    5
+}
+
+fn default_backup_interval_hours() -> u64 {
+    24 // Nightly
+}
+
+fn default_large_file_chars() -> usize {
+    24_000 // About 6k tokens
+}
+
+fn default_embedding_model() -> String {
+    "nomic-embed-text".to_string()
+}
+
+fn default_backup_keep() -> usize {
+    7 // One week of nightly backups
+}
 
-    /// Check if a specific hour is within the scheduled window (for testing)
-    pub fn is_hour_in_window(&self, hour: u8) -> bool {
-        if self.start_hour <= self.end_hour {
-            // Normal range: e.g., 9-17 (9am to 5pm)
-            hour >= self.start_hour && hour < self.end_hour
-        } else {
-            // Overnight range: e.g., 22-6 (10pm to 6am)
-            hour >= self.start_hour || hour < self.end_hour
-        }
-    }
+fn default_notification_max_mutants() -> usize {
+    20
 }
 
-fn default_log_level() -> String {
-    "info".to_string()
+fn default_notification_max_per_day() -> u32 {
+    5
 }
 
-fn default_port() -> u16 {
-    8420
+fn default_notification_events() -> Vec<NotificationEvent> {
+    vec![
+        NotificationEvent::ScanCompleted,
+        NotificationEvent::CriticalFindings,
+        NotificationEvent::SurvivedMutants,
+    ]
 }
 
-fn default_host() -> String {
-    "127.0.0.1".to_string()
+fn default_survived_threshold() -> usize {
+    1
 }
 
-fn default_start_hour() -> u8 {
-    22 // 10pm
+fn default_digest_hour() -> u8 {
+    7
 }
 
-fn default_end_hour() -> u8 {
-    6 // 6am
+fn default_github_api_url() -> String {
+    "https://api.github.com".to_string()
 }
 
-fn default_check_interval() -> u64 {
-    60 // Check every minute
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_gitlab_url() -> String {
+    "https://gitlab.com".to_string()
 }
 
 impl Default for GeneralConfig {
     fn default() -> Self {
         Self {
             log_level: default_log_level(),
+            log_file: default_enabled(),
+            log_max_size_mb: default_log_max_size_mb(),
+            log_max_files: default_log_max_files(),
+            control_socket: default_enabled(),
+            database_url: None,
+            output_language: default_output_language(),
         }
     }
 }
@@ -145,6 +1167,9 @@ impl Default for WebConfig {
         Self {
             port: default_port(),
             host: default_host(),
+            templates_dir: None,
+            expensive_requests_per_minute: default_expensive_requests_per_minute(),
+            expensive_queue_size: default_expensive_queue_size(),
         }
     }
 }
@@ -155,32 +1180,147 @@ impl Default for ScheduleConfig {
             start_hour: default_start_hour(),
             end_hour: default_end_hour(),
             check_interval_seconds: default_check_interval(),
+            trigger_debounce_seconds: default_trigger_debounce(),
+            windows: Vec::new(),
+            tags: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_hours: default_backup_interval_hours(),
+            keep: default_backup_keep(),
+        }
+    }
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            large_file_chars: default_large_file_chars(),
+        }
+    }
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            embedding_model: default_embedding_model(),
+        }
+    }
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            desktop: false,
+            email: None,
+            events: default_notification_events(),
+            webhooks: Vec::new(),
+            max_mutants: default_notification_max_mutants(),
+            survived_threshold: default_survived_threshold(),
+            max_per_day: default_notification_max_per_day(),
+            dashboard_url: None,
+        }
+    }
+}
+
+impl Default for IssueTrackerConfig {
+    fn default() -> Self {
+        Self {
+            github_token: None,
+            github_api_url: default_github_api_url(),
+            gitlab_token: None,
+            gitlab_url: default_gitlab_url(),
+        }
+    }
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            reports: default_enabled(),
+            backups: default_enabled(),
+            s3: None,
+            webdav: None,
         }
     }
 }
 
 impl Config {
-    /// Load configuration from file, or create default if not found
-    pub fn load(path: Option<&Path>) -> Result<Self> {
+    /// Load configuration from file, or create default if not found.
+    ///
+    /// When a profile name is given, the profile is resolved on top of the base configuration.
+    pub fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Self> {
         let config_path = path.map(PathBuf::from).or_else(Self::default_config_path);
 
-        let config = if let Some(ref path) = config_path {
-            if path.exists() {
-                let contents = std::fs::read_to_string(path)
-                    .with_context(|| format!("Failed to read config from {:?}", path))?;
-                toml::from_str(&contents)
-                    .with_context(|| format!("Failed to parse config from {:?}", path))?
-            } else {
-                Config::default()
-            }
-        } else {
-            Config::default()
+        let config = match config_path {
+            Some(ref path) => Self::load_file(path)?,
+            None => Config::default(),
         };
 
-        Ok(config)
+        match profile {
+            Some(name) => config.resolve_profile(name),
+            None => Ok(config),
+        }
+    }
+
+    /// Read the unresolved configuration from a file, or the defaults if it does not exist
+    fn load_file(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config from {:?}", path))?;
+        toml::from_str(&contents).with_context(|| format!("Failed to parse config from {:?}", path))
+    }
+
+    /// Apply the named profile on top of this configuration
+    fn resolve_profile(mut self, name: &str) -> Result<Self> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            anyhow::bail!(
+                "Invalid profile name '{}': use only letters, digits, '-' and '_'",
+                name
+            );
+        }
+
+        let profile = self.profiles.get(name).cloned().with_context(|| {
+            format!(
+                "Unknown profile '{}'. Define it under [profiles.{}] in the config file",
+                name, name
+            )
+        })?;
+
+        let data_dir = profile
+            .data_dir
+            .unwrap_or_else(|| self.data_dir().join("profiles").join(name));
+        self.data_dir = Some(data_dir);
+        self.endpoints = profile.endpoints;
+        if let Some(web) = profile.web {
+            self.web = web;
+        }
+        if let Some(schedule) = profile.schedule {
+            self.schedule = schedule;
+        }
+        self.profile = Some(name.to_string());
+
+        Ok(self)
     }
 
-    /// Save configuration to file
+    /// Save configuration to file.
+    ///
+    /// When a profile is active, only that profile's section is updated and the
+    /// base configuration on disk is left untouched.
     pub fn save(&self, path: Option<&Path>) -> Result<()> {
         let config_path = path
             .map(PathBuf::from)
@@ -193,7 +1333,29 @@ impl Config {
                 .with_context(|| format!("Failed to create config directory: {:?}", parent))?;
         }
 
-        let contents = toml::to_string_pretty(self).context("Failed to serialize config")?;
+        let to_write = match self.profile {
+            Some(ref name) => {
+                let mut base = Self::load_file(&config_path)?;
+                let existing = base.profiles.remove(name).unwrap_or_default();
+                // A profile inheriting the base schedule keeps inheriting it
+                // unless its schedule was changed
+                let schedule = (existing.schedule.is_some() || self.schedule != base.schedule)
+                    .then(|| self.schedule.clone());
+                base.profiles.insert(
+                    name.clone(),
+                    ProfileConfig {
+                        data_dir: existing.data_dir,
+                        endpoints: self.endpoints.clone(),
+                        web: existing.web,
+                        schedule,
+                    },
+                );
+                base
+            }
+            None => self.clone(),
+        };
+
+        let contents = toml::to_string_pretty(&to_write).context("Failed to serialize config")?;
         std::fs::write(&config_path, contents)
             .with_context(|| format!("Failed to write config to {:?}", config_path))?;
 
@@ -215,9 +1377,75 @@ impl Config {
         })
     }
 
-    /// Get the database file path
-    pub fn database_path(&self) -> PathBuf {
-        self.data_dir().join("noctum.db")
+    /// Get the database to store analysis history in. An invalid
+    /// `general.database_url` is ignored here; [`Config::check_database_url`]
+    /// rejects it at startup.
+    pub fn database(&self) -> DatabaseUrl {
+        let configured = self
+            .general
+            .database_url
+            .as_deref()
+            .and_then(|url| parse_database_url(url).ok());
+        match configured {
+            Some(DatabaseUrl::Sqlite(path)) if path.is_relative() => {
+                DatabaseUrl::Sqlite(self.data_dir().join(path))
+            }
+            Some(database) => database,
+            None => DatabaseUrl::Sqlite(self.data_dir().join("noctum.db")),
+        }
+    }
+
+    /// Get the database file path (None when the database is a PostgreSQL
+    /// server)
+    pub fn database_path(&self) -> Option<PathBuf> {
+        match self.database() {
+            DatabaseUrl::Sqlite(path) => Some(path),
+            DatabaseUrl::Postgres(_) => None,
+        }
+    }
+
+    /// Check that `general.database_url` names a database Noctum can store
+    /// its history in
+    pub fn check_database_url(&self) -> Result<()> {
+        match self.general.database_url {
+            Some(ref url) => parse_database_url(url).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the directory the daemon's log files are written to
+    pub fn log_dir(&self) -> PathBuf {
+        self.data_dir().join("logs")
+    }
+
+    /// Get the path of the daemon's control socket
+    pub fn control_socket_path(&self) -> PathBuf {
+        self.data_dir().join("noctum.sock")
+    }
+
+    /// Get the database backup directory path
+    pub fn backup_dir(&self) -> PathBuf {
+        self.data_dir().join("backups")
+    }
+
+    /// Base URL of the web dashboard, for links in notifications
+    pub fn dashboard_url(&self) -> String {
+        match &self.notifications.dashboard_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => {
+                // A wildcard bind address is not reachable as a link target
+                let host = match self.web.host.as_str() {
+                    "0.0.0.0" | "::" => "localhost",
+                    host => host,
+                };
+                format!("http://{}:{}", host, self.web.port)
+            }
+        }
+    }
+
+    /// Get the directory caching installed JavaScript dependencies
+    pub fn dependency_cache_dir(&self) -> PathBuf {
+        self.data_dir().join("cache").join("node_modules")
     }
 }
 
@@ -236,6 +1464,9 @@ mod tests {
             start_hour: 9,
             end_hour: 17,
             check_interval_seconds: 60,
+            trigger_debounce_seconds: 5,
+            windows: Vec::new(),
+            tags: BTreeMap::new(),
         };
 
         assert!(config.is_hour_in_window(9)); // Start hour is included
@@ -250,6 +1481,9 @@ mod tests {
             start_hour: 9,
             end_hour: 17,
             check_interval_seconds: 60,
+            trigger_debounce_seconds: 5,
+            windows: Vec::new(),
+            tags: BTreeMap::new(),
         };
 
         assert!(!config.is_hour_in_window(8)); // Before start
@@ -265,6 +1499,9 @@ mod tests {
             start_hour: 22,
             end_hour: 6,
             check_interval_seconds: 60,
+            trigger_debounce_seconds: 5,
+            windows: Vec::new(),
+            tags: BTreeMap::new(),
         };
 
         assert!(config.is_hour_in_window(22)); // Start hour
@@ -281,6 +1518,9 @@ mod tests {
             start_hour: 22,
             end_hour: 6,
             check_interval_seconds: 60,
+            trigger_debounce_seconds: 5,
+            windows: Vec::new(),
+            tags: BTreeMap::new(),
         };
 
         assert!(!config.is_hour_in_window(6)); // End hour is excluded
@@ -296,6 +1536,9 @@ mod tests {
             start_hour: 12,
             end_hour: 12,
             check_interval_seconds: 60,
+            trigger_debounce_seconds: 5,
+            windows: Vec::new(),
+            tags: BTreeMap::new(),
         };
 
         // With current implementation, this means empty window
@@ -309,6 +1552,9 @@ mod tests {
             start_hour: 0,
             end_hour: 23,
             check_interval_seconds: 60,
+            trigger_debounce_seconds: 5,
+            windows: Vec::new(),
+            tags: BTreeMap::new(),
         };
 
         assert!(config.is_hour_in_window(0)); // Start at midnight
@@ -317,6 +1563,158 @@ mod tests {
         assert!(!config.is_hour_in_window(23)); // End hour excluded
     }
 
+    fn at(date: &str, hour: u32) -> NaiveDateTime {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(hour, 30, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_schedule_windows() {
+        let config: ScheduleConfig = toml::from_str(
+            r#"
+start_hour = 1
+end_hour = 2
+
+[[windows]]
+name = "weeknights"
+days = ["mon", "tue", "wed", "thu", "friday"]
+start_hour = 22
+end_hour = 6
+
+[[windows]]
+name = "weekends"
+days = ["sat", "sun"]
+start_hour = 0
+end_hour = 24
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.windows[0].days[4], Day::Fri);
+
+        // 2025-01-06 is a Monday
+        assert!(config.is_in_window_at(at("2025-01-06", 23)));
+        assert!(!config.is_in_window_at(at("2025-01-06", 12)));
+        // The daily window is replaced by the named windows
+        assert!(!config.is_in_window_at(at("2025-01-06", 1)));
+        // Monday morning belongs to Sunday's all-day window, not to a weeknight
+        assert!(!config.is_in_window_at(at("2025-01-06", 3)));
+        // Friday night continues into Saturday, which is in the weekend window anyway
+        assert!(config.is_in_window_at(at("2025-01-11", 3)));
+        assert!(config.is_in_window_at(at("2025-01-12", 12)));
+        // Tuesday morning continues Monday night's window
+        assert!(config.is_in_window_at(at("2025-01-07", 5)));
+        assert!(!config.is_in_window_at(at("2025-01-07", 6)));
+
+        assert_eq!(
+            config.describe(),
+            "weeknights Mon/Tue/Wed/Thu/Fri 22:00 - 06:00, weekends Sat/Sun 00:00 - 24:00"
+        );
+    }
+
+    #[test]
+    fn test_overnight_window_belongs_to_start_day() {
+        let window = ScheduleWindow {
+            name: String::new(),
+            days: vec![Day::Fri],
+            start_hour: 22,
+            end_hour: 6,
+        };
+        assert!(window.contains(at("2025-01-10", 22))); // Friday night
+        assert!(window.contains(at("2025-01-11", 5))); // Saturday morning
+        assert!(!window.contains(at("2025-01-10", 5))); // Friday morning
+        assert!(!window.contains(at("2025-01-11", 22))); // Saturday night
+
+        let config = ScheduleConfig::default();
+        assert!(config.is_in_window_at(at("2025-01-10", 23)));
+        assert_eq!(config.describe(), "22:00 - 06:00");
+    }
+
+    #[test]
+    fn test_remaining_window_time() {
+        let config = ScheduleConfig::default();
+        // 23:30 in a 22-6 window leaves 6.5 hours
+        assert_eq!(
+            config.remaining_at(&[], at("2025-01-10", 23)),
+            Some(chrono::Duration::minutes(390))
+        );
+        assert_eq!(
+            config.remaining_at(&[], at("2025-01-10", 12)),
+            Some(chrono::Duration::zero())
+        );
+
+        // Adjacent named windows count as one
+        let config = ScheduleConfig {
+            windows: vec![
+                ScheduleWindow {
+                    name: String::new(),
+                    days: vec![Day::Fri],
+                    start_hour: 22,
+                    end_hour: 24,
+                },
+                ScheduleWindow {
+                    name: String::new(),
+                    days: vec![Day::Sat],
+                    start_hour: 0,
+                    end_hour: 8,
+                },
+            ],
+            ..ScheduleConfig::default()
+        };
+        assert_eq!(
+            config.remaining_at(&[], at("2025-01-10", 22)),
+            Some(chrono::Duration::minutes(570))
+        );
+
+        let always = ScheduleConfig {
+            start_hour: 0,
+            end_hour: 24,
+            ..ScheduleConfig::default()
+        };
+        assert_eq!(always.remaining_at(&[], at("2025-01-10", 12)), None);
+    }
+
+    #[test]
+    fn test_tag_schedules() {
+        let config: ScheduleConfig = toml::from_str(
+            r#"
+start_hour = 22
+end_hour = 6
+
+[[tags.work]]
+name = "weeknights"
+days = ["mon", "tue", "wed", "thu", "fri"]
+start_hour = 20
+end_hour = 23
+"#,
+        )
+        .unwrap();
+        let work = ["work".to_string()];
+        let personal = ["personal".to_string()];
+
+        // 2025-01-06 is a Monday
+        assert!(config.is_open_for_at(&work, at("2025-01-06", 20)));
+        assert!(!config.is_open_for_at(&personal, at("2025-01-06", 20)));
+        assert!(config.is_any_open_at(at("2025-01-06", 20)));
+        // Tagged repositories don't follow the schedule above
+        assert!(!config.is_open_for_at(&work, at("2025-01-06", 23)));
+        assert!(config.is_open_for_at(&personal, at("2025-01-06", 23)));
+        assert!(config.is_open_for_at(&[], at("2025-01-06", 23)));
+        // Work repositories only run on weekdays
+        assert!(!config.is_open_for_at(&work, at("2025-01-11", 20)));
+        assert!(!config.is_any_open_at(at("2025-01-11", 20)));
+
+        assert_eq!(
+            config.remaining_at(&work, at("2025-01-06", 20)),
+            Some(chrono::Duration::minutes(150))
+        );
+        assert_eq!(
+            config.describe(),
+            "22:00 - 06:00; work: weeknights Mon/Tue/Wed/Thu/Fri 20:00 - 23:00"
+        );
+    }
+
     // =========================================================================
     // Default value tests
     // =========================================================================
@@ -334,6 +1732,214 @@ mod tests {
         let config = WebConfig::default();
         assert_eq!(config.port, 8420);
         assert_eq!(config.host, "127.0.0.1");
+        assert_eq!(config.expensive_requests_per_minute, 6);
+        assert_eq!(config.expensive_queue_size, 4);
+    }
+
+    #[test]
+    fn test_default_backup_config() {
+        let config = BackupConfig::default();
+        assert!(config.enabled);
+        assert_eq!(config.interval_hours, 24);
+        assert_eq!(config.keep, 7);
+    }
+
+    #[test]
+    fn test_parse_backup_config() {
+        let toml = r#"
+[backup]
+enabled = false
+keep = 3
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert!(!config.backup.enabled);
+        assert_eq!(config.backup.keep, 3);
+        assert_eq!(config.backup.interval_hours, 24);
+    }
+
+    #[test]
+    fn test_parse_export_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(!config.export.is_enabled());
+
+        let toml = r#"
+[export]
+backups = false
+
+[export.s3]
+endpoint = "http://minio:9000"
+bucket = "noctum"
+access_key_id = "key"
+secret_access_key = "secret"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert!(config.export.is_enabled());
+        assert!(config.export.reports);
+        assert!(!config.export.backups);
+        let s3 = config.export.s3.unwrap();
+        assert_eq!(s3.region, "us-east-1");
+        assert!(s3.path_style);
+        assert!(config.export.webdav.is_none());
+    }
+
+    #[test]
+    fn test_parse_snapshot_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.snapshot.strategy, SnapshotStrategy::Auto);
+        assert!(!config.snapshot.respect_gitignore);
+
+        let toml = r#"
+[snapshot]
+strategy = "hardlink"
+respect_gitignore = true
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.snapshot.strategy, SnapshotStrategy::Hardlink);
+        assert!(config.snapshot.respect_gitignore);
+
+        assert!(toml::from_str::<Config>("[snapshot]\nstrategy = \"rsync\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_diagram_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.diagram.format, DiagramFormat::Dot);
+        assert_eq!(config.diagram.renderer, DiagramRenderer::Layout);
+        assert!(config.diagram.dot_path.is_none());
+
+        let toml = r#"
+[diagram]
+format = "mermaid"
+renderer = "graphviz"
+dot_path = "/opt/graphviz/bin/dot"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.diagram.format, DiagramFormat::Mermaid);
+        assert_eq!(config.diagram.format.as_str(), "mermaid");
+        assert_eq!(config.diagram.renderer, DiagramRenderer::Graphviz);
+        assert_eq!(
+            config.diagram.dot_path,
+            Some(PathBuf::from("/opt/graphviz/bin/dot"))
+        );
+
+        assert!(toml::from_str::<Config>("[diagram]\nrenderer = \"mermaid\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_notification_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(!config.notifications.is_enabled());
+        assert_eq!(config.notifications.max_per_day, 5);
+        assert_eq!(config.dashboard_url(), "http://127.0.0.1:8420");
+
+        let toml = r#"
+[notifications]
+webhook_url = "https://hooks.example.com/noctum"
+max_mutants = 5
+dashboard_url = "https://noctum.example.com/"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert!(config.notifications.is_enabled());
+        assert_eq!(config.notifications.max_mutants, 5);
+        assert_eq!(config.dashboard_url(), "https://noctum.example.com");
+        assert!(config.notifications.wants(NotificationEvent::ScanCompleted));
+    }
+
+    #[test]
+    fn test_parse_email_config() {
+        // A plain address keeps working
+        let config: Config = toml::from_str("[notifications]\nemail = \"me@example.com\"").unwrap();
+        let email = config.notifications.email.as_ref().unwrap();
+        assert_eq!(email.to, ["me@example.com"]);
+        assert!(email.notify && !email.digest && email.smtp_host.is_none());
+        assert!(config.notifications.is_enabled());
+
+        let toml = r#"
+[notifications.email]
+to = ["me@example.com", "team@example.com"]
+from = "noctum@example.com"
+notify = false
+smtp_host = "smtp.example.com"
+smtp_tls = "tls"
+smtp_username = "noctum"
+smtp_password = "secret"
+digest = true
+digest_hour = 8
+digest_days = ["mon", "fri"]
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let email = config.notifications.email.as_ref().unwrap();
+        assert_eq!(email.to.len(), 2);
+        assert_eq!(email.smtp_port(), 465);
+        assert_eq!(email.digest_days, [Day::Mon, Day::Fri]);
+        // Only the digest is emailed
+        assert!(!config.notifications.is_enabled());
+
+        assert!(
+            toml::from_str::<Config>("[notifications.email]\nto = \"me@example.com\"").is_err()
+        );
+    }
+
+    #[test]
+    fn test_digest_due() {
+        let email = EmailConfig {
+            digest: true,
+            digest_days: vec![Day::Mon],
+            ..EmailConfig::address("me@example.com".to_string())
+        };
+        let at = |day: u32, hour: u32| {
+            chrono::NaiveDate::from_ymd_opt(2024, 1, day)
+                .unwrap()
+                .and_hms_opt(hour, 30, 0)
+                .unwrap()
+        };
+
+        // 2024-01-01 is a Monday
+        assert!(!email.digest_due(at(1, 6), None));
+        assert!(email.digest_due(at(1, 7), None));
+        assert!(email.digest_due(at(1, 9), Some(at(1, 6))));
+        assert!(!email.digest_due(at(1, 9), Some(at(1, 7))));
+        assert!(!email.digest_due(at(2, 9), None));
+        assert!(!EmailConfig {
+            digest: false,
+            ..email.clone()
+        }
+        .digest_due(at(1, 9), None));
+    }
+
+    #[test]
+    fn test_parse_notification_webhooks() {
+        let toml = r#"
+[notifications]
+events = ["survived_mutants"]
+survived_threshold = 3
+
+[[notifications.webhooks]]
+url = "https://hooks.slack.com/services/T/B/X"
+format = "slack"
+events = ["critical_findings"]
+
+[[notifications.webhooks]]
+url = "https://discord.com/api/webhooks/1/abc"
+format = "discord"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let notifications = &config.notifications;
+        assert!(notifications.is_enabled());
+        assert_eq!(notifications.survived_threshold, 3);
+        assert_eq!(notifications.webhooks[0].format, WebhookFormat::Slack);
+        assert_eq!(
+            notifications.webhooks[0].events,
+            [NotificationEvent::CriticalFindings]
+        );
+        assert_eq!(notifications.webhooks[1].events.len(), 3);
+
+        // `events` only applies to webhook_url, desktop and email, none of
+        // which is configured here
+        let mut only_slack = notifications.clone();
+        only_slack.webhooks.truncate(1);
+        assert!(only_slack.wants(NotificationEvent::CriticalFindings));
+        assert!(!only_slack.wants(NotificationEvent::SurvivedMutants));
     }
 
     #[test]
@@ -371,19 +1977,44 @@ name = "Local"
 url = "http://localhost:11434"
 model = "llama2"
 enabled = true
+location = "local"
 
 [[endpoints]]
 name = "Remote"
 url = "http://remote:11434"
 model = "codellama"
 enabled = false
+max_retries = 0
+request_timeout_seconds = 30
+
+[endpoints.options]
+temperature = 0.2
+num_ctx = 16384
+seed = 7
 "#;
         let config: Config = toml::from_str(toml).unwrap();
         assert_eq!(config.endpoints.len(), 2);
         assert_eq!(config.endpoints[0].name, "Local");
         assert!(config.endpoints[0].enabled);
+        assert!(config.endpoints[0].is_local());
         assert_eq!(config.endpoints[1].name, "Remote");
         assert!(!config.endpoints[1].enabled);
+        // Endpoints are untrusted unless labeled
+        assert_eq!(config.endpoints[1].location, EndpointLocation::Remote);
+        assert_eq!(config.endpoints[0].max_retries, 3);
+        assert_eq!(config.endpoints[0].request_timeout_seconds, 600);
+        assert_eq!(config.endpoints[1].max_retries, 0);
+        assert_eq!(config.endpoints[1].request_timeout_seconds, 30);
+        assert!(config.endpoints[0].options.is_empty());
+        assert_eq!(
+            config.endpoints[1].options,
+            GenerationOptions {
+                temperature: Some(0.2),
+                num_ctx: Some(16384),
+                seed: Some(7),
+                ..GenerationOptions::default()
+            }
+        );
     }
 
     #[test]
@@ -410,6 +2041,28 @@ check_interval_seconds = 120
         assert!(config.endpoints.is_empty());
     }
 
+    #[test]
+    fn test_command_languages() {
+        let toml = r#"
+[[languages]]
+name = "Elixir"
+extensions = ["ex", "exs"]
+markers = ["mix.exs"]
+skip_dirs = ["_build", "deps"]
+compile_command = "mix compile --warnings-as-errors"
+test_command = "mix test"
+analysis_prompt = "Summarize this {language} module: {file_path}\n{content}"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.languages.len(), 1);
+        let elixir = &config.languages[0];
+        assert_eq!(elixir.name, "Elixir");
+        assert_eq!(elixir.extensions, ["ex", "exs"]);
+        assert_eq!(elixir.markers, ["mix.exs"]);
+        assert_eq!(elixir.test_command.as_deref(), Some("mix test"));
+        assert!(elixir.mutation_prompt.is_none());
+    }
+
     // =========================================================================
     // File I/O tests
     // =========================================================================
@@ -419,7 +2072,7 @@ check_interval_seconds = 120
         let temp_file = tempfile::NamedTempFile::new().unwrap();
         std::fs::remove_file(temp_file.path()).unwrap();
 
-        let config = Config::load(Some(temp_file.path())).unwrap();
+        let config = Config::load(Some(temp_file.path()), None).unwrap();
         assert_eq!(config.schedule.start_hour, 22);
         assert_eq!(config.endpoints.len(), 0);
     }
@@ -442,7 +2095,7 @@ end_hour = 18
 
         std::fs::write(temp_file.path(), toml_content).unwrap();
 
-        let config = Config::load(Some(temp_file.path())).unwrap();
+        let config = Config::load(Some(temp_file.path()), None).unwrap();
         assert_eq!(config.general.log_level, "debug");
         assert_eq!(config.web.port, 9000);
         assert_eq!(config.schedule.start_hour, 8);
@@ -455,7 +2108,7 @@ end_hour = 18
 
         std::fs::write(temp_file.path(), "invalid {{{{ toml").unwrap();
 
-        let result = Config::load(Some(temp_file.path()));
+        let result = Config::load(Some(temp_file.path()), None);
         assert!(result.is_err());
     }
 
@@ -466,18 +2119,34 @@ end_hour = 18
         let config = Config {
             general: GeneralConfig {
                 log_level: "debug".to_string(),
+                ..GeneralConfig::default()
             },
             web: WebConfig {
                 port: 9000,
                 host: "0.0.0.0".to_string(),
+                ..WebConfig::default()
             },
             endpoints: vec![],
             schedule: ScheduleConfig {
                 start_hour: 8,
                 end_hour: 18,
                 check_interval_seconds: 120,
+                trigger_debounce_seconds: 5,
+                windows: Vec::new(),
+                tags: BTreeMap::new(),
             },
             data_dir: None,
+            backup: BackupConfig::default(),
+            notifications: NotificationConfig::default(),
+            issues: IssueTrackerConfig::default(),
+            export: ExportConfig::default(),
+            search: SearchConfig::default(),
+            snapshot: SnapshotConfig::default(),
+            diagram: DiagramConfig::default(),
+            routing: RoutingConfig::default(),
+            languages: Vec::new(),
+            profiles: BTreeMap::new(),
+            profile: None,
         };
 
         config.save(Some(temp_file.path())).unwrap();
@@ -534,7 +2203,178 @@ end_hour = 18
             ..Default::default()
         };
 
-        let db_path = config.database_path();
+        let db_path = config.database_path().unwrap();
         assert_eq!(db_path, PathBuf::from("/test/data/noctum.db"));
+
+        let with_url = |url: &str| {
+            let mut config = config.clone();
+            config.general.database_url = Some(url.to_string());
+            config
+        };
+        let config_for = with_url("sqlite:///srv/noctum/team.db?mode=rwc");
+        assert!(config_for.check_database_url().is_ok());
+        assert_eq!(
+            config_for.database_path(),
+            Some(PathBuf::from("/srv/noctum/team.db"))
+        );
+        assert_eq!(
+            with_url("sqlite:history.db").database_path(),
+            Some(PathBuf::from("/test/data/history.db"))
+        );
+
+        let config_for = with_url("postgres://noctum@db/noctum");
+        assert!(config_for.check_database_url().is_ok());
+        assert_eq!(
+            config_for.database(),
+            DatabaseUrl::Postgres("postgres://noctum@db/noctum".to_string())
+        );
+        assert_eq!(config_for.database_path(), None);
+
+        for url in ["mysql://db/noctum", "sqlite::memory:", "sqlite://"] {
+            let config_for = with_url(url);
+            assert!(config_for.check_database_url().is_err(), "{}", url);
+            assert_eq!(config_for.database_path(), Some(db_path.clone()));
+        }
+    }
+
+    #[test]
+    fn test_backup_dir() {
+        let config = Config {
+            data_dir: Some("/test/data".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(config.backup_dir(), PathBuf::from("/test/data/backups"));
+    }
+
+    // =========================================================================
+    // Profile tests
+    // =========================================================================
+
+    const PROFILE_CONFIG: &str = r#"
+data_dir = "/data/noctum"
+
+[web]
+port = 9000
+
+[[endpoints]]
+name = "Personal"
+url = "http://localhost:11434"
+model = "qwen2.5-coder"
+
+[profiles.work]
+data_dir = "/data/work"
+
+[[profiles.work.endpoints]]
+name = "Work GPU"
+url = "http://gpu.work:11434"
+model = "codellama"
+
+[profiles.work.schedule]
+start_hour = 1
+end_hour = 5
+
+[profiles.side]
+"#;
+
+    fn write_profile_config() -> tempfile::NamedTempFile {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(temp_file.path(), PROFILE_CONFIG).unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn test_load_without_profile_uses_base() {
+        let temp_file = write_profile_config();
+        let config = Config::load(Some(temp_file.path()), None).unwrap();
+
+        assert!(config.profile.is_none());
+        assert_eq!(config.endpoints.len(), 1);
+        assert_eq!(config.endpoints[0].name, "Personal");
+        assert_eq!(
+            config.database_path(),
+            Some(PathBuf::from("/data/noctum/noctum.db"))
+        );
+        assert_eq!(config.profiles.len(), 2);
+    }
+
+    #[test]
+    fn test_load_profile_overrides() {
+        let temp_file = write_profile_config();
+        let config = Config::load(Some(temp_file.path()), Some("work")).unwrap();
+
+        assert_eq!(config.profile.as_deref(), Some("work"));
+        assert_eq!(config.endpoints.len(), 1);
+        assert_eq!(config.endpoints[0].name, "Work GPU");
+        assert_eq!(
+            config.database_path(),
+            Some(PathBuf::from("/data/work/noctum.db"))
+        );
+        assert_eq!(config.schedule.start_hour, 1);
+        assert_eq!(config.schedule.end_hour, 5);
+        // Web settings are inherited from the base config
+        assert_eq!(config.web.port, 9000);
+    }
+
+    #[test]
+    fn test_load_profile_isolated_by_default() {
+        let temp_file = write_profile_config();
+        let config = Config::load(Some(temp_file.path()), Some("side")).unwrap();
+
+        // Endpoints are never inherited, and the data dir is nested under the base one
+        assert!(config.endpoints.is_empty());
+        assert_eq!(
+            config.data_dir(),
+            PathBuf::from("/data/noctum/profiles/side")
+        );
+        assert_eq!(config.schedule.start_hour, 22);
+    }
+
+    #[test]
+    fn test_load_unknown_profile() {
+        let temp_file = write_profile_config();
+        let result = Config::load(Some(temp_file.path()), Some("missing"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_load_invalid_profile_name() {
+        let temp_file = write_profile_config();
+        assert!(Config::load(Some(temp_file.path()), Some("../work")).is_err());
+        assert!(Config::load(Some(temp_file.path()), Some("")).is_err());
+    }
+
+    #[test]
+    fn test_save_profile_preserves_base() {
+        let temp_file = write_profile_config();
+        let mut config = Config::load(Some(temp_file.path()), Some("work")).unwrap();
+        config.endpoints[0].model = "qwen2.5-coder".to_string();
+        config.schedule.start_hour = 2;
+        config.save(Some(temp_file.path())).unwrap();
+
+        let base = Config::load(Some(temp_file.path()), None).unwrap();
+        assert_eq!(base.endpoints[0].name, "Personal");
+        assert_eq!(base.schedule.start_hour, 22);
+
+        let work = Config::load(Some(temp_file.path()), Some("work")).unwrap();
+        assert_eq!(work.endpoints[0].model, "qwen2.5-coder");
+        assert_eq!(work.schedule.start_hour, 2);
+        assert_eq!(work.data_dir(), PathBuf::from("/data/work"));
+    }
+
+    #[test]
+    fn test_save_profile_keeps_inherited_schedule() {
+        let temp_file = write_profile_config();
+        let mut config = Config::load(Some(temp_file.path()), Some("side")).unwrap();
+        config.endpoints.push(OllamaEndpoint::new(
+            "Side".to_string(),
+            "http://localhost:11434".to_string(),
+            "codellama".to_string(),
+        ));
+        config.save(Some(temp_file.path())).unwrap();
+
+        let base = Config::load(Some(temp_file.path()), None).unwrap();
+        assert_eq!(base.profiles["side"].endpoints.len(), 1);
+        assert!(base.profiles["side"].schedule.is_none());
     }
 }
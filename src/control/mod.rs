@@ -0,0 +1,278 @@
+//! Control socket.
+//!
+//! The daemon listens on a Unix domain socket at `<data_dir>/noctum.sock`, so
+//! the `noctum daemon` subcommands and editor plugins can control it without
+//! going through the web server and its host checks. A client writes one
+//! command per line (`status`, `trigger-scan`, `pause`, `resume` or `stop`)
+//! and gets one line of JSON back for each: `{"ok": true, ...}`, or
+//! `{"ok": false, "error": "..."}`. The socket is only accessible to the user
+//! running the daemon.
+
+use crate::daemon::DaemonHandle;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A command accepted on the control socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Report the daemon's state
+    Status,
+    /// Start a scan now, regardless of the schedule
+    TriggerScan,
+    /// Stop processing until resumed
+    Pause,
+    /// Resume processing after a pause
+    Resume,
+    /// Shut the daemon down
+    Stop,
+}
+
+impl Command {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Command::Status => "status",
+            Command::TriggerScan => "trigger-scan",
+            Command::Pause => "pause",
+            Command::Resume => "resume",
+            Command::Stop => "stop",
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "status" => Ok(Command::Status),
+            "trigger-scan" | "scan" => Ok(Command::TriggerScan),
+            "pause" => Ok(Command::Pause),
+            "resume" => Ok(Command::Resume),
+            "stop" => Ok(Command::Stop),
+            other => Err(format!(
+                "Unknown command '{}': expected status, trigger-scan, pause, resume or stop",
+                other
+            )),
+        }
+    }
+}
+
+/// Run `command` against the daemon and build its reply
+pub fn execute(command: Command, daemon: &DaemonHandle) -> Value {
+    let message = match command {
+        Command::Status => {
+            return serde_json::json!({
+                "ok": true,
+                "state": daemon.status(),
+                "paused": daemon.is_paused(),
+                "last_error": daemon.last_error(),
+                "version": env!("CARGO_PKG_VERSION"),
+            })
+        }
+        Command::TriggerScan => {
+            daemon.trigger_scan();
+            "Scan triggered"
+        }
+        Command::Pause => {
+            daemon.pause();
+            "Daemon paused"
+        }
+        Command::Resume => {
+            daemon.resume();
+            "Daemon resumed"
+        }
+        Command::Stop => {
+            daemon.stop();
+            "Daemon stopping"
+        }
+    };
+    serde_json::json!({ "ok": true, "message": message })
+}
+
+/// Listen for commands on the socket at `path` until the task is dropped. A
+/// stale socket left by a daemon that didn't shut down cleanly is replaced,
+/// but one another daemon is listening on is not.
+#[cfg(unix)]
+pub async fn serve(path: &Path, daemon: DaemonHandle) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::{UnixListener, UnixStream};
+
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            anyhow::bail!("Another daemon is listening on {}", path.display());
+        }
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to bind control socket {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("Failed to restrict access to {}", path.display()))?;
+    tracing::info!("Control socket listening on {}", path.display());
+
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .context("Failed to accept control connection")?;
+        let daemon = daemon.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &daemon).await {
+                tracing::debug!("Control connection failed: {:#}", e);
+            }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn serve(_path: &Path, _daemon: DaemonHandle) -> Result<()> {
+    anyhow::bail!("The control socket is only available on Unix systems")
+}
+
+/// Answer each command line of a connection until the client hangs up
+#[cfg(unix)]
+async fn handle_connection(stream: tokio::net::UnixStream, daemon: &DaemonHandle) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match line.parse::<Command>() {
+            Ok(command) => {
+                tracing::debug!("Control command: {}", command.as_str());
+                execute(command, daemon)
+            }
+            Err(e) => serde_json::json!({ "ok": false, "error": e }),
+        };
+        write.write_all(format!("{}\n", reply).as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Send `command` to the daemon listening on `path` and return its reply,
+/// failing if the daemon reports an error
+#[cfg(unix)]
+pub async fn send(path: &Path, command: Command) -> Result<Value> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+
+    let mut stream = UnixStream::connect(path).await.with_context(|| {
+        format!(
+            "Failed to connect to {}; is the daemon running with general.control_socket enabled?",
+            path.display()
+        )
+    })?;
+    stream
+        .write_all(format!("{}\n", command.as_str()).as_bytes())
+        .await
+        .context("Failed to send the command")?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .await
+        .context("Failed to read the reply")?;
+    let reply: Value = serde_json::from_str(&line).context("Invalid reply from the daemon")?;
+    if reply["ok"] != Value::Bool(true) {
+        anyhow::bail!(
+            "{}",
+            reply["error"]
+                .as_str()
+                .unwrap_or("The daemon rejected the command")
+        );
+    }
+    Ok(reply)
+}
+
+#[cfg(not(unix))]
+pub async fn send(_path: &Path, _command: Command) -> Result<Value> {
+    anyhow::bail!("The control socket is only available on Unix systems")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::daemon::Daemon;
+    use crate::db::Database;
+    use std::sync::Arc;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+    use tokio::sync::RwLock;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!("status".parse(), Ok(Command::Status));
+        assert_eq!(" Trigger-Scan\n".parse(), Ok(Command::TriggerScan));
+        assert_eq!("scan".parse(), Ok(Command::TriggerScan));
+        assert!("restart".parse::<Command>().is_err());
+        for command in [
+            Command::Status,
+            Command::TriggerScan,
+            Command::Pause,
+            Command::Resume,
+            Command::Stop,
+        ] {
+            assert_eq!(command.as_str().parse(), Ok(command));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_control_socket() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+        let daemon = Daemon::new(Arc::new(RwLock::new(Config::default())), db).handle();
+
+        let path = temp_dir.path().join("noctum.sock");
+        // A stale socket file is replaced
+        std::fs::write(&path, "").unwrap();
+        let server = tokio::spawn({
+            let path = path.clone();
+            let daemon = daemon.clone();
+            async move { serve(&path, daemon).await }
+        });
+        while UnixStream::connect(&path).await.is_err() {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let status = send(&path, Command::Status).await.unwrap();
+        assert_eq!(status["state"], "waiting");
+        assert_eq!(status["paused"], false);
+
+        let reply = send(&path, Command::Pause).await.unwrap();
+        assert_eq!(reply["message"], "Daemon paused");
+        assert!(daemon.is_paused());
+        assert_eq!(send(&path, Command::Status).await.unwrap()["paused"], true);
+        send(&path, Command::Resume).await.unwrap();
+        assert!(!daemon.is_paused());
+
+        // A second daemon doesn't take over the socket
+        assert!(serve(&path, daemon.clone()).await.is_err());
+
+        // Unknown commands are rejected without closing the connection
+        let mut stream = UnixStream::connect(&path).await.unwrap();
+        stream.write_all(b"restart\nstatus\n").await.unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        let reply: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(reply["ok"], false);
+        let reply: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(reply["ok"], true);
+
+        server.abort();
+    }
+}
@@ -0,0 +1,111 @@
+//! Fitting mutation testing into the schedule window.
+//!
+//! Before the mutants of a file are executed, their execution time is
+//! estimated from the average time of the repository's earlier mutants (or,
+//! without history, from the baseline build and test run of the matching rule)
+//! and compared against the time left in the window. The first file that would
+//! not finish in time starts the deferral: it and every remaining file are left
+//! for the next window, which picks them up through the content-hash skip. The
+//! first file of a run is always tested, so progress is made every window.
+
+use std::time::Instant;
+use tokio::time::Duration;
+
+/// Time budget of one mutation testing run
+#[derive(Debug, Clone)]
+pub struct MutationBudget {
+    /// When the schedule window closes (None if the run is not bound to it)
+    deadline: Option<Instant>,
+    /// Mutants executed concurrently
+    parallelism: usize,
+    /// Average execution time of the repository's earlier mutants
+    history_ms: Option<u64>,
+}
+
+impl MutationBudget {
+    pub fn new(window_left: Option<Duration>, parallelism: usize, history_ms: Option<u64>) -> Self {
+        Self {
+            deadline: window_left.map(|left| Instant::now() + left),
+            parallelism: parallelism.max(1),
+            history_ms,
+        }
+    }
+
+    /// Estimated time to execute `mutants` mutants of a file whose rule's
+    /// baseline build and test run took `baseline_ms`
+    pub fn estimate(&self, mutants: usize, baseline_ms: u64) -> Duration {
+        let per_mutant_ms = self.history_ms.unwrap_or(baseline_ms);
+        let rounds = mutants.div_ceil(self.parallelism) as u64;
+        Duration::from_millis(rounds.saturating_mul(per_mutant_ms))
+    }
+
+    /// Time left before the window closes (None if unbounded)
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether work taking `estimate` finishes before the window closes
+    pub fn fits(&self, estimate: Duration) -> bool {
+        self.remaining().is_none_or(|left| estimate <= left)
+    }
+}
+
+/// Mutation testing left over for the next window
+#[derive(Debug, Default)]
+pub struct Deferred {
+    pub files: usize,
+    pub estimate: Duration,
+}
+
+impl Deferred {
+    pub fn add(&mut self, estimate: Duration) {
+        self.files += 1;
+        self.estimate += estimate;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_uses_history_and_parallelism() {
+        let budget = MutationBudget::new(None, 4, Some(2_000));
+        // 10 mutants on 4 workers take 3 rounds
+        assert_eq!(budget.estimate(10, 500), Duration::from_secs(6));
+        assert_eq!(budget.estimate(0, 500), Duration::ZERO);
+
+        // Without history the baseline run is the best guess
+        let budget = MutationBudget::new(None, 1, None);
+        assert_eq!(budget.estimate(3, 500), Duration::from_millis(1_500));
+    }
+
+    #[test]
+    fn test_fits_the_window() {
+        let unbounded = MutationBudget::new(None, 1, None);
+        assert!(unbounded.fits(Duration::from_secs(24 * 3600)));
+        assert_eq!(unbounded.remaining(), None);
+
+        let budget = MutationBudget::new(Some(Duration::from_secs(3600)), 1, None);
+        assert!(budget.fits(Duration::from_secs(60)));
+        assert!(!budget.fits(Duration::from_secs(2 * 3600)));
+
+        let closed = MutationBudget::new(Some(Duration::ZERO), 1, None);
+        assert!(!closed.fits(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_deferred_totals() {
+        let mut deferred = Deferred::default();
+        assert!(deferred.is_empty());
+        deferred.add(Duration::from_secs(60));
+        deferred.add(Duration::from_secs(30));
+        assert_eq!(deferred.files, 2);
+        assert_eq!(deferred.estimate, Duration::from_secs(90));
+    }
+}
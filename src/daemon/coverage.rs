@@ -0,0 +1,235 @@
+//! Analysis coverage of a repository.
+//!
+//! Compares the source files the daemon would analyze today with the stored
+//! results of one per-file analysis type, so it's visible how complete the
+//! picture of a repository is: which files have a result for their current
+//! content, which results are stale, which files are skipped (and why), and
+//! which were never analyzed.
+
+use super::compute_hash;
+use super::source::{read_source, SkipReason, SourceText};
+use crate::db::{Database, Repository};
+use crate::project::discover_projects;
+use crate::repo_config::RepoConfig;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Coverage state of a single source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageStatus {
+    /// Latest result was produced from the current content
+    Current,
+    /// Latest result was produced from older content
+    Stale,
+    /// The daemon doesn't analyze the file (see the reason)
+    Skipped,
+    /// No result yet
+    NeverAnalyzed,
+}
+
+/// Coverage of one source file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileCoverage {
+    /// Relative to the repository root
+    pub file_path: String,
+    pub status: CoverageStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Coverage of a repository for one analysis type
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisCoverage {
+    pub repository_id: i64,
+    pub analysis_type: String,
+    pub total: usize,
+    pub current: usize,
+    pub stale: usize,
+    pub skipped: usize,
+    pub never_analyzed: usize,
+    pub files: Vec<FileCoverage>,
+}
+
+/// Compute the coverage of `analysis_type` results for a repository
+pub async fn analysis_coverage(
+    db: &Database,
+    repo: &Repository,
+    analysis_type: &str,
+) -> Result<AnalysisCoverage> {
+    let repo_path = Path::new(&repo.path);
+    anyhow::ensure!(
+        repo_path.exists(),
+        "Repository path does not exist: {}",
+        repo.path
+    );
+
+    let repo_config = RepoConfig::load(repo_path).unwrap_or_default();
+    let file_filter = repo_config
+        .file_filter()
+        .with_context(|| format!("Invalid include/exclude globs for {}", repo.name))?;
+
+    let latest: HashMap<String, Option<String>> = db
+        .get_all_repository_results(repo.id)
+        .await?
+        .into_iter()
+        .filter(|r| r.analysis_type == analysis_type)
+        .map(|r| (r.file_path, r.content_hash))
+        .collect();
+    let failed: HashMap<String, Option<String>> = db
+        .get_failed_file_jobs(repo.id, analysis_type)
+        .await?
+        .into_iter()
+        .filter_map(|job| Some((job.file_path?, job.error)))
+        .collect();
+
+    let mut files = Vec::new();
+    for project in discover_projects(repo_path)? {
+        let language = project.language;
+        for file_path in language.find_source_files(&project.root)? {
+            let relative = file_path.strip_prefix(repo_path).unwrap_or(&file_path);
+            let stored_path = file_path.to_string_lossy().to_string();
+
+            let skipped = |reason: &str| (CoverageStatus::Skipped, Some(reason.to_string()));
+            let (status, reason) = if !file_filter.matches(relative) {
+                skipped("excluded by include/exclude globs")
+            } else {
+                match read_source(&file_path).await {
+                    Err(e) => skipped(&format!("unreadable: {}", e)),
+                    Ok(SourceText::Skipped(reason)) => skipped(reason.status()),
+                    Ok(SourceText::Text(content) | SourceText::Lossy(content)) => {
+                        if let Some(detected) = file_filter.detect(relative, &content) {
+                            skipped(SkipReason::from(detected).status())
+                        } else if content.len() < language.min_file_size() {
+                            skipped("too small")
+                        } else if content.len() > language.max_file_size() {
+                            skipped("too large")
+                        } else {
+                            let hash = compute_hash(&content);
+                            match (latest.get(&stored_path), failed.get(&stored_path)) {
+                                (Some(stored), _) if stored.as_deref() == Some(hash.as_str()) => {
+                                    (CoverageStatus::Current, None)
+                                }
+                                (_, Some(error)) => skipped(&format!(
+                                    "failed: {}",
+                                    error.as_deref().unwrap_or("unknown error")
+                                )),
+                                (Some(_), None) => (CoverageStatus::Stale, None),
+                                (None, None) => (CoverageStatus::NeverAnalyzed, None),
+                            }
+                        }
+                    }
+                }
+            };
+
+            files.push(FileCoverage {
+                file_path: relative.to_string_lossy().to_string(),
+                status,
+                reason,
+            });
+        }
+    }
+    files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    files.dedup_by(|a, b| a.file_path == b.file_path);
+
+    let count = |status| files.iter().filter(|f| f.status == status).count();
+    Ok(AnalysisCoverage {
+        repository_id: repo.id,
+        analysis_type: analysis_type.to_string(),
+        total: files.len(),
+        current: count(CoverageStatus::Current),
+        stale: count(CoverageStatus::Stale),
+        skipped: count(CoverageStatus::Skipped),
+        never_analyzed: count(CoverageStatus::NeverAnalyzed),
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Attribution;
+
+    #[tokio::test]
+    async fn test_analysis_coverage_classifies_files() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&data_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        let root = repo_dir.path();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("noctum.toml"), "").unwrap();
+        std::fs::create_dir(root.join("src")).unwrap();
+        let body = |name: &str| format!("pub fn {}() -> u32 {{\n    42\n}}\n", name).repeat(4);
+        for name in ["current", "stale", "never", "failed"] {
+            std::fs::write(root.join(format!("src/{}.rs", name)), body(name)).unwrap();
+        }
+        std::fs::write(root.join("src/tiny.rs"), "").unwrap();
+
+        let repo_id = db
+            .add_repository(&root.to_string_lossy(), "demo")
+            .await
+            .unwrap();
+        let repo = db.get_repository(repo_id).await.unwrap().unwrap();
+        let path = |name: &str| {
+            root.join(format!("src/{}.rs", name))
+                .to_string_lossy()
+                .to_string()
+        };
+
+        for (name, hash) in [
+            ("current", compute_hash(&body("current"))),
+            ("stale", "old".to_string()),
+        ] {
+            db.save_analysis_result(
+                repo_id,
+                &path(name),
+                "code_understanding",
+                "ok",
+                None,
+                Some(&hash),
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+        }
+        let job = db
+            .enqueue_job(repo_id, "code_understanding", Some(&path("failed")))
+            .await
+            .unwrap();
+        db.update_job_status(job, "failed", Some("timed out"))
+            .await
+            .unwrap();
+
+        let coverage = analysis_coverage(&db, &repo, "code_understanding")
+            .await
+            .unwrap();
+        assert_eq!(coverage.total, 5);
+        assert_eq!(coverage.current, 1);
+        assert_eq!(coverage.stale, 1);
+        assert_eq!(coverage.skipped, 2);
+        assert_eq!(coverage.never_analyzed, 1);
+
+        let reason = |file: &str| {
+            coverage
+                .files
+                .iter()
+                .find(|f| f.file_path == file)
+                .and_then(|f| f.reason.clone())
+        };
+        assert_eq!(
+            reason("src/failed.rs").as_deref(),
+            Some("failed: timed out")
+        );
+        assert_eq!(reason("src/tiny.rs").as_deref(), Some("too small"));
+    }
+}
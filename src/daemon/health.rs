@@ -0,0 +1,413 @@
+//! Endpoint health tracking and circuit breaking.
+//!
+//! Every LLM call and a periodic background probe report their outcome here.
+//! After `FAILURE_THRESHOLD` consecutive failures an endpoint's circuit opens
+//! and it is taken out of the pool for a cooldown that doubles with each trip
+//! (up to `MAX_COOLDOWN`). Once the cooldown expires the endpoint is allowed
+//! again (half-open): the next success closes the circuit, the next failure
+//! reopens it. The dashboard shows the tracked state on the settings page and
+//! at `/api/endpoints/health`.
+//!
+//! When a schedule window opens, endpoints with `keep_alive` set are warmed
+//! up with a tiny prompt, so a large model is loaded (and kept loaded) before
+//! the first analysis instead of timing it out.
+
+use crate::analyzer::OllamaClient;
+use crate::config::{Config, OllamaEndpoint};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Consecutive failures that open an endpoint's circuit
+const FAILURE_THRESHOLD: u32 = 3;
+
+/// Cooldown after the first trip of a circuit
+const BASE_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Upper bound for the doubling cooldown
+const MAX_COOLDOWN: Duration = Duration::from_secs(15 * 60);
+
+/// How often endpoints are re-probed in the background
+const PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Prompt sent to load a model; the answer is discarded
+const WARM_UP_PROMPT: &str = "Reply with OK.";
+
+/// Tracked state of one endpoint
+#[derive(Debug, Clone, Default)]
+struct EndpointState {
+    consecutive_failures: u32,
+    /// Number of times the circuit opened without a success in between
+    trips: u32,
+    /// Set while the circuit is open
+    open_until: Option<Instant>,
+    /// Round-trip time of the last successful probe
+    latency_ms: Option<u64>,
+    /// Whether the configured model is loaded in memory (None if unknown)
+    model_loaded: Option<bool>,
+    last_error: Option<String>,
+    /// When the endpoint last succeeded or failed (RFC 3339)
+    last_checked: Option<String>,
+}
+
+/// Health of an endpoint, as shown on the dashboard
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EndpointHealth {
+    pub name: String,
+    pub url: String,
+    pub model: String,
+    /// 'unknown', 'healthy', 'degraded' (failing, circuit still closed),
+    /// 'open' (removed from the pool) or 'half_open' (cooldown over, awaiting a result)
+    pub status: String,
+    pub consecutive_failures: u32,
+    pub latency_ms: Option<u64>,
+    pub model_loaded: Option<bool>,
+    pub last_error: Option<String>,
+    pub last_checked: Option<String>,
+    /// Seconds until the endpoint is tried again (only while open)
+    pub retry_in_seconds: Option<u64>,
+}
+
+/// Shared endpoint health state, keyed by endpoint name
+#[derive(Clone, Default)]
+pub struct HealthMonitor {
+    states: Arc<Mutex<HashMap<String, EndpointState>>>,
+}
+
+impl HealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether an endpoint may be used (its circuit is closed or half-open)
+    pub fn is_allowed(&self, endpoint: &str) -> bool {
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        states
+            .get(endpoint)
+            .and_then(|state| state.open_until)
+            .is_none_or(|until| Instant::now() >= until)
+    }
+
+    /// Record a successful call, closing the endpoint's circuit
+    pub fn record_success(&self, endpoint: &str) {
+        self.update(endpoint, |state| {
+            if state.open_until.is_some() {
+                tracing::info!(
+                    "Endpoint '{}' recovered, returning it to the pool",
+                    endpoint
+                );
+            }
+            state.consecutive_failures = 0;
+            state.trips = 0;
+            state.open_until = None;
+            state.last_error = None;
+        });
+    }
+
+    /// Record a failed call, opening the endpoint's circuit once failures pile up
+    pub fn record_failure(&self, endpoint: &str, error: &str) {
+        self.update(endpoint, |state| {
+            state.consecutive_failures += 1;
+            state.last_error = Some(error.to_string());
+            if state.consecutive_failures >= FAILURE_THRESHOLD {
+                state.trips += 1;
+                let cooldown = cooldown_for(state.trips);
+                state.open_until = Some(Instant::now() + cooldown);
+                tracing::warn!(
+                    "Endpoint '{}' failed {} times in a row, removing it from the pool for {}s: {}",
+                    endpoint,
+                    state.consecutive_failures,
+                    cooldown.as_secs(),
+                    error
+                );
+            }
+        });
+    }
+
+    /// Probe an endpoint's availability, latency and model load state
+    pub async fn probe(&self, endpoint: &OllamaEndpoint, cancel: &CancellationToken) -> bool {
+        let client =
+            OllamaClient::new(&endpoint.url, &endpoint.model).with_cancellation(cancel.clone());
+
+        let started = Instant::now();
+        if let Err(e) = client.list_models().await {
+            self.record_failure(&endpoint.name, &format!("{:#}", e));
+            return false;
+        }
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        // Older Ollama versions have no /api/ps; the load state is then unknown
+        let model_loaded = client
+            .loaded_models()
+            .await
+            .ok()
+            .map(|models| models.iter().any(|m| model_matches(&endpoint.model, m)));
+
+        self.update(&endpoint.name, |state| {
+            state.latency_ms = Some(latency_ms);
+            state.model_loaded = model_loaded;
+        });
+        self.record_success(&endpoint.name);
+        true
+    }
+
+    /// Load the model of every allowed endpoint that has `keep_alive` set,
+    /// all at once. Returns the number of endpoints warmed up.
+    pub async fn warm_up(&self, endpoints: &[OllamaEndpoint], cancel: &CancellationToken) -> usize {
+        let warm_ups = endpoints
+            .iter()
+            .filter(|e| e.enabled && e.keep_alive.is_some() && self.is_allowed(&e.name))
+            .map(|endpoint| async move {
+                let client = OllamaClient::for_endpoint(endpoint).with_cancellation(cancel.clone());
+                let started = Instant::now();
+                match client.generate(WARM_UP_PROMPT).await {
+                    Ok(_) => {
+                        tracing::info!(
+                            "Warmed up {} on endpoint '{}' in {}s",
+                            endpoint.model,
+                            endpoint.name,
+                            started.elapsed().as_secs()
+                        );
+                        self.update(&endpoint.name, |state| state.model_loaded = Some(true));
+                        self.record_success(&endpoint.name);
+                        true
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to warm up endpoint '{}': {:#}", endpoint.name, e);
+                        self.record_failure(&endpoint.name, &format!("{:#}", e));
+                        false
+                    }
+                }
+            });
+        futures::future::join_all(warm_ups)
+            .await
+            .into_iter()
+            .filter(|warmed| *warmed)
+            .count()
+    }
+
+    /// Health of the given endpoints, in order
+    pub fn snapshot(&self, endpoints: &[OllamaEndpoint]) -> Vec<EndpointHealth> {
+        let states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        endpoints
+            .iter()
+            .map(|endpoint| {
+                let state = states.get(&endpoint.name).cloned().unwrap_or_default();
+                let status = match state.open_until {
+                    Some(until) if now < until => "open",
+                    Some(_) => "half_open",
+                    None if state.last_checked.is_none() => "unknown",
+                    None if state.consecutive_failures > 0 => "degraded",
+                    None => "healthy",
+                };
+                EndpointHealth {
+                    name: endpoint.name.clone(),
+                    url: endpoint.url.clone(),
+                    model: endpoint.model.clone(),
+                    status: status.to_string(),
+                    consecutive_failures: state.consecutive_failures,
+                    latency_ms: state.latency_ms,
+                    model_loaded: state.model_loaded,
+                    last_error: state.last_error,
+                    last_checked: state.last_checked,
+                    retry_in_seconds: state
+                        .open_until
+                        .filter(|until| now < *until)
+                        .map(|until| (until - now).as_secs()),
+                }
+            })
+            .collect()
+    }
+
+    fn update(&self, endpoint: &str, f: impl FnOnce(&mut EndpointState)) {
+        let mut states = self.states.lock().unwrap_or_else(|e| e.into_inner());
+        let state = states.entry(endpoint.to_string()).or_default();
+        f(state);
+        state.last_checked =
+            Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+    }
+}
+
+/// Re-probe all enabled endpoints until cancelled
+pub async fn run_probes(
+    monitor: HealthMonitor,
+    config: Arc<RwLock<Config>>,
+    cancel: CancellationToken,
+) {
+    let mut ticker = tokio::time::interval(PROBE_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = cancel.cancelled() => break,
+        }
+
+        let endpoints: Vec<OllamaEndpoint> = config
+            .read()
+            .await
+            .endpoints
+            .iter()
+            .filter(|e| e.enabled)
+            .cloned()
+            .collect();
+        for endpoint in &endpoints {
+            if cancel.is_cancelled() {
+                return;
+            }
+            // Open circuits are left alone until their cooldown expires
+            if monitor.is_allowed(&endpoint.name) {
+                monitor.probe(endpoint, &cancel).await;
+            }
+        }
+    }
+}
+
+/// Cooldown for the given number of consecutive trips
+fn cooldown_for(trips: u32) -> Duration {
+    let factor = 1u32 << trips.saturating_sub(1).min(16);
+    (BASE_COOLDOWN * factor).min(MAX_COOLDOWN)
+}
+
+/// Whether a model name reported by Ollama is the configured model
+/// (`llama3` is reported as `llama3:latest`)
+fn model_matches(configured: &str, reported: &str) -> bool {
+    reported == configured
+        || (!configured.contains(':') && reported.strip_suffix(":latest") == Some(configured))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(name: &str) -> OllamaEndpoint {
+        OllamaEndpoint::new(
+            name.to_string(),
+            "http://localhost:11434".to_string(),
+            "llama3".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_circuit_opens_after_consecutive_failures() {
+        let monitor = HealthMonitor::new();
+        assert!(monitor.is_allowed("gpu"));
+
+        monitor.record_failure("gpu", "connection refused");
+        monitor.record_failure("gpu", "connection refused");
+        assert!(monitor.is_allowed("gpu"));
+        assert_eq!(monitor.snapshot(&[endpoint("gpu")])[0].status, "degraded");
+
+        monitor.record_failure("gpu", "connection refused");
+        assert!(!monitor.is_allowed("gpu"));
+        let health = &monitor.snapshot(&[endpoint("gpu")])[0];
+        assert_eq!(health.status, "open");
+        assert_eq!(health.consecutive_failures, 3);
+        assert_eq!(health.last_error.as_deref(), Some("connection refused"));
+        assert!(health.retry_in_seconds.is_some());
+
+        monitor.record_success("gpu");
+        assert!(monitor.is_allowed("gpu"));
+        assert_eq!(monitor.snapshot(&[endpoint("gpu")])[0].status, "healthy");
+    }
+
+    #[test]
+    fn test_cooldown_doubles_up_to_max() {
+        assert_eq!(cooldown_for(1), BASE_COOLDOWN);
+        assert_eq!(cooldown_for(2), BASE_COOLDOWN * 2);
+        assert_eq!(cooldown_for(100), MAX_COOLDOWN);
+    }
+
+    #[test]
+    fn test_model_matches() {
+        assert!(model_matches("llama3", "llama3:latest"));
+        assert!(model_matches("llama3:8b", "llama3:8b"));
+        assert!(!model_matches("llama3:8b", "llama3:latest"));
+        assert!(!model_matches("llama3", "llama3.1:latest"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_records_latency_and_model_state() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "models": [{ "name": "llama3:latest" }] })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/ps"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "models": [{ "name": "llama3:latest" }] })),
+            )
+            .mount(&server)
+            .await;
+
+        let monitor = HealthMonitor::new();
+        let endpoint = OllamaEndpoint {
+            url: server.uri(),
+            ..endpoint("local")
+        };
+        assert!(monitor.probe(&endpoint, &CancellationToken::new()).await);
+
+        let health = &monitor.snapshot(&[endpoint])[0];
+        assert_eq!(health.status, "healthy");
+        assert!(health.latency_ms.is_some());
+        assert_eq!(health.model_loaded, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_loads_models_with_keep_alive() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .and(body_partial_json(
+                serde_json::json!({ "keep_alive": "30m" }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "response": "OK" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let monitor = HealthMonitor::new();
+        let endpoints = [
+            OllamaEndpoint {
+                url: server.uri(),
+                keep_alive: Some("30m".to_string()),
+                ..endpoint("gpu")
+            },
+            // Without keep_alive the endpoint is left alone
+            OllamaEndpoint {
+                url: server.uri(),
+                ..endpoint("cpu")
+            },
+        ];
+        assert_eq!(
+            monitor.warm_up(&endpoints, &CancellationToken::new()).await,
+            1
+        );
+        assert_eq!(monitor.snapshot(&endpoints)[0].model_loaded, Some(true));
+    }
+
+    #[test]
+    fn test_unknown_endpoint_snapshot() {
+        let health = &HealthMonitor::new().snapshot(&[endpoint("cpu")])[0];
+        assert_eq!(health.status, "unknown");
+        assert_eq!(health.latency_ms, None);
+    }
+}
@@ -1,30 +1,108 @@
-use crate::analyzer::{AnalysisType, OllamaClient};
-use crate::config::{Config, OllamaEndpoint};
-use crate::db::Database;
+use crate::analyzer::architecture::{self, ArchitectureModel};
+use crate::analyzer::context::ContextSources;
+use crate::analyzer::findings::{self, FindingsResponse};
+use crate::analyzer::test_gaps::{self, TestGapsResponse};
+use crate::analyzer::validation::{self, Requirements};
+use crate::analyzer::{
+    parse_delta_response, respond_in, AnalysisType, DeltaContext, OllamaClient, StructuredFinding,
+};
+use crate::config::{
+    Config, DiagramFormat, NotificationConfig, NotificationEvent, OllamaEndpoint, RoutingConfig,
+    SnapshotConfig,
+};
+use crate::db::{
+    backup, Attribution, Database, MutationReplacement, NewAnalysisResult, Repository,
+    AUDIT_SENSITIVE_ROUTING,
+};
+use crate::dead_code::{self, ReviewResponse};
+use crate::deps::{DependencyCache, PackageManager};
 use crate::diagram::{
-    clean_dot_output, render_dot_to_svg, validate_dot_syntax, DiagramExtractor, DiagramGenerator,
-    DiagramType,
+    clean_dot_output, clean_mermaid_output, module_dependency_dot, module_dependency_mermaid,
+    validate_mermaid_syntax, DiagramExtractor, DiagramGenerator, DiagramType, DotRenderer,
+    MermaidGenerator,
 };
+use crate::duplication;
+use crate::export::{self, Exporter};
+use crate::issues::{self, IssueClient, IssueSource};
 use crate::language::Language;
 use crate::mutation::{
     analyze_and_generate_mutations,
-    executor::{execute_mutation_test, truncate_output_tail},
-    MutationConfig,
+    changes::{self, ChangedLines},
+    coverage::CoverageReport,
+    executor::{execute_mutation_tests, truncate_output_tail},
+    filter::rejection_reason,
+    operators,
+    workspace::WorkspacePool,
+    GeneratedMutation, MutationConfig, MutationStrategy, MutationTestResult, TestOutcome,
+};
+use crate::notify::{CriticalFinding, DigestEmail, Notification, Notifier, SurvivedMutant};
+use crate::plugin::{overall_severity, render_findings, PluginHost};
+use crate::project::{
+    discover_projects, is_noctum_temp_dir, is_running_binary_source, Project, TEMP_DIR_PREFIX,
 };
-use crate::project::discover_projects;
-use crate::repo_config::RepoConfig;
+use crate::repo_config::{FileFilter, MutationRule, RepoConfig};
+use crate::report::{Report, ReportFormat};
+use crate::search;
+use crate::snapshot::SnapshotPlan;
+use crate::theme::Templates;
+use crate::toolchain::{wait_with_output, CommandRunner, CommandWait};
+use anyhow::Context;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex as TokioMutex;
 use tokio::sync::RwLock;
 use tokio::time::{interval, Duration};
+use tokio_util::sync::CancellationToken;
+
+mod budget;
+mod coverage;
+mod health;
+mod pause;
+mod progress;
+mod queue;
+mod routing;
+mod source;
+mod summary;
+mod trigger;
+mod watch;
+
+use budget::{Deferred, MutationBudget};
+pub use coverage::analysis_coverage;
+pub use health::{EndpointHealth, HealthMonitor};
+use pause::PauseSwitch;
+pub use progress::{DashboardUpdate, ProgressBroadcaster, ProgressEvent};
+use queue::JobQueue;
+use routing::FileSize;
+use source::{read_source, SourceText};
+pub use source::{
+    FILE_STATUS_BINARY, FILE_STATUS_GENERATED, FILE_STATUS_LOSSY, FILE_STATUS_MINIFIED,
+    FILE_STATUS_VENDORED,
+};
+use trigger::ScanTrigger;
+use watch::{ChangedFiles, RepoWatcher};
 
 /// Maximum number of retries for DOT diagram generation when syntax errors occur
 const DOT_MAX_RETRIES: usize = 3;
 
+/// Plugin results saved per transaction
+const RESULT_BATCH_SIZE: usize = 50;
+
+/// Surviving mutations that get test suggestions per scan
+const TEST_GAPS_PER_SCAN: u32 = 20;
+
+/// Timeout for installing JavaScript dependencies (10 minutes)
+const DEPENDENCY_INSTALL_TIMEOUT_SECS: u64 = 600;
+
+/// Coverage command used when `[mutation.coverage]` names neither a command nor an lcov file
+const DEFAULT_COVERAGE_COMMAND: &str = "cargo llvm-cov --lcov --output-path lcov.info";
+
 /// Compute a SHA256 hash of the content
 fn compute_hash(content: &str) -> String {
     let mut hasher = Sha256::new();
@@ -32,8 +110,53 @@ fn compute_hash(content: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// How a file's content relates to its latest result of one analysis type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Freshness {
+    /// Never analyzed (or the result has no content hash)
+    New,
+    /// Analyzed, but the content changed since
+    Changed,
+    /// The latest result was computed from this content
+    Unchanged,
+}
+
+/// Compare a file's content hash with the one its latest `analysis_type` result
+/// was computed from. Every per-file pass skips `Unchanged` files, so unchanged
+/// files cost no LLM calls. Files with a pending reanalysis request are `New`.
+async fn freshness(
+    db: &Database,
+    repository_id: i64,
+    file_path: &str,
+    analysis_type: &str,
+    content_hash: &str,
+) -> Freshness {
+    match db.is_reanalysis_requested(repository_id, file_path).await {
+        Ok(true) => return Freshness::New,
+        Ok(false) => {}
+        Err(e) => tracing::warn!("Failed to look up reanalysis of {}: {}", file_path, e),
+    }
+    match db
+        .get_latest_file_hash(repository_id, file_path, analysis_type)
+        .await
+    {
+        Ok(Some(hash)) if hash == content_hash => Freshness::Unchanged,
+        Ok(Some(_)) => Freshness::Changed,
+        Ok(None) => Freshness::New,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to look up previous {} of {}: {}",
+                analysis_type,
+                file_path,
+                e
+            );
+            Freshness::New
+        }
+    }
+}
+
 /// Truncate a string at a valid UTF-8 char boundary
-fn truncate_at_char_boundary(s: &str, max_len: usize) -> &str {
+pub(crate) fn truncate_at_char_boundary(s: &str, max_len: usize) -> &str {
     if s.len() <= max_len {
         s
     } else {
@@ -51,19 +174,24 @@ fn truncate_at_char_boundary(s: &str, max_len: usize) -> &str {
 /// copied repository within it.
 ///
 /// The `ignore_patterns` parameter allows excluding files/directories matching
-/// glob patterns (e.g., `["node_modules", "target", ".git"]`).
+/// glob patterns (e.g., `["node_modules", "target", ".git"]`). The snapshot
+/// settings choose how files are placed in the copy and whether the files git
+/// ignores are left out as well.
 async fn copy_repo_to_temp(
     repo_path: &Path,
     ignore_patterns: &[String],
+    snapshot: &SnapshotConfig,
 ) -> anyhow::Result<tempfile::TempDir> {
     let repo_path = repo_path.to_path_buf();
     let ignore_patterns = ignore_patterns.to_vec();
+    let snapshot = snapshot.clone();
 
     // Use spawn_blocking since file I/O is synchronous
     let temp_dir = tokio::task::spawn_blocking(move || -> anyhow::Result<tempfile::TempDir> {
-        let temp_dir = tempfile::TempDir::with_prefix("noctum-")?;
+        let temp_dir = tempfile::TempDir::with_prefix(TEMP_DIR_PREFIX)?;
 
-        copy_dir_with_ignore(&repo_path, temp_dir.path(), &ignore_patterns)?;
+        let plan = SnapshotPlan::new(&repo_path, &snapshot);
+        copy_dir_with_ignore(&repo_path, temp_dir.path(), &ignore_patterns, &plan)?;
 
         Ok(temp_dir)
     })
@@ -76,11 +204,30 @@ async fn copy_repo_to_temp(
 ///
 /// Ignore patterns are matched against the relative path from the source root.
 /// Patterns like `node_modules` will match any path component named `node_modules`.
-fn copy_dir_with_ignore(src: &Path, dest: &Path, ignore_patterns: &[String]) -> anyhow::Result<()> {
+/// Files are placed in `dest` as the snapshot plan says, and the paths it
+/// skips are left out.
+fn copy_dir_with_ignore(
+    src: &Path,
+    dest: &Path,
+    ignore_patterns: &[String],
+    plan: &SnapshotPlan,
+) -> anyhow::Result<()> {
     use std::fs;
     use walkdir::WalkDir;
 
-    for entry in WalkDir::new(src).min_depth(1) {
+    // Never copy Noctum's own temp copies (or the destination itself) into the copy,
+    // which would recurse when a repository contains the system temp directory
+    let walker = WalkDir::new(src)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|e| {
+            !(e.file_type().is_dir()
+                && (e.path() == dest
+                    || is_noctum_temp_dir(e.path())
+                    || e.path().strip_prefix(src).is_ok_and(|r| plan.skips_dir(r))))
+        });
+
+    for entry in walker {
         let entry = entry.map_err(|e| anyhow::anyhow!("Failed to read directory entry: {}", e))?;
         let src_path = entry.path();
 
@@ -147,16 +294,23 @@ fn copy_dir_with_ignore(src: &Path, dest: &Path, ignore_patterns: &[String]) ->
                 anyhow::anyhow!("Failed to create directory {:?}: {}", dest_path, e)
             })?;
         } else if entry.file_type().is_file() {
+            if plan.skips_file(relative_path) {
+                continue;
+            }
             // Ensure parent directory exists
             if let Some(parent) = dest_path.parent() {
                 fs::create_dir_all(parent).map_err(|e| {
                     anyhow::anyhow!("Failed to create parent directory {:?}: {}", parent, e)
                 })?;
             }
-            fs::copy(src_path, &dest_path).map_err(|e| {
-                anyhow::anyhow!("Failed to copy {:?} to {:?}: {}", src_path, dest_path, e)
-            })?;
+            plan.place(src_path, &dest_path, relative_path)
+                .map_err(|e| {
+                    anyhow::anyhow!("Failed to copy {:?} to {:?}: {}", src_path, dest_path, e)
+                })?;
         } else if entry.file_type().is_symlink() {
+            if plan.skips_file(relative_path) {
+                continue;
+            }
             // For symlinks, copy the target file/directory content instead of the symlink
             // This fixes issues with broken symlinks in node_modules/.bin/
             if let Some(parent) = dest_path.parent() {
@@ -207,6 +361,21 @@ fn copy_dir_with_ignore(src: &Path, dest: &Path, ignore_patterns: &[String]) ->
                 continue;
             }
 
+            // A link to one of its own ancestors would copy the tree into itself
+            if canonical_target.is_dir()
+                && src_path
+                    .parent()
+                    .and_then(|parent| parent.canonicalize().ok())
+                    .is_some_and(|parent| parent.starts_with(&canonical_target))
+            {
+                tracing::warn!(
+                    "Skipping symlink {:?}: target {:?} is an ancestor of the link",
+                    src_path,
+                    canonical_target
+                );
+                continue;
+            }
+
             if resolved_target.is_file() {
                 fs::copy(&resolved_target, &dest_path).map_err(|e| {
                     anyhow::anyhow!(
@@ -298,21 +467,22 @@ pub struct CommandResult {
 
 /// Run a shell command with a timeout.
 ///
-/// The command is executed via `sh -c` to support shell features like pipes.
+/// The command is executed via `sh -c` to support shell features like pipes, using
+/// the toolchains pinned by the repository. The command is killed if `cancel` fires.
 /// Returns a `CommandResult` with success status, output, and duration.
 async fn run_command_with_timeout(
+    runner: &CommandRunner,
     working_dir: &Path,
     command: &str,
     timeout_seconds: u64,
+    cancel: &CancellationToken,
 ) -> CommandResult {
     use std::process::Stdio;
-    use std::time::Instant;
 
     let start = Instant::now();
 
-    let child = tokio::process::Command::new("sh")
-        .arg("-c")
-        .arg(command)
+    let child = runner
+        .command(command)
         .current_dir(working_dir)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -330,12 +500,12 @@ async fn run_command_with_timeout(
     };
 
     let timeout = Duration::from_secs(timeout_seconds);
-    let result = tokio::time::timeout(timeout, child.wait_with_output()).await;
+    let result = wait_with_output(child, timeout, cancel).await;
 
     let duration_ms = start.elapsed().as_millis() as u64;
 
     match result {
-        Ok(Ok(output)) => {
+        CommandWait::Finished(Ok(output)) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
             let stderr = String::from_utf8_lossy(&output.stderr);
             let combined = format!("{}{}", stdout, stderr);
@@ -346,21 +516,35 @@ async fn run_command_with_timeout(
                 duration_ms,
             }
         }
-        Ok(Err(e)) => CommandResult {
+        CommandWait::Finished(Err(e)) => CommandResult {
             success: false,
             output: format!("Command execution error: {}", e),
             duration_ms,
         },
-        Err(_) => CommandResult {
+        CommandWait::TimedOut => CommandResult {
             success: false,
             output: format!("Command timed out after {} seconds", timeout_seconds),
             duration_ms,
         },
+        CommandWait::Cancelled => CommandResult {
+            success: false,
+            output: "Command cancelled".to_string(),
+            duration_ms,
+        },
     }
 }
 
+/// The time scheduled processing is paused until, if that is still in the future
+pub async fn schedule_paused_until(db: &Database) -> Option<chrono::DateTime<chrono::Utc>> {
+    let state = db.get_daemon_status().await.ok()?;
+    let until = chrono::DateTime::parse_from_rfc3339(state.paused_until.as_deref()?).ok()?;
+    let until = until.with_timezone(&chrono::Utc);
+    (until > chrono::Utc::now()).then_some(until)
+}
+
 /// Daemon status
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DaemonStatus {
     /// Daemon is waiting for scheduled window
     Waiting,
@@ -368,6 +552,8 @@ pub enum DaemonStatus {
     Processing,
     /// Daemon is stopping
     Stopping,
+    /// The last processing cycle failed; retried on the next tick in the window
+    Error,
 }
 
 impl DaemonStatus {
@@ -376,6 +562,7 @@ impl DaemonStatus {
             DaemonStatus::Waiting => 0,
             DaemonStatus::Processing => 1,
             DaemonStatus::Stopping => 2,
+            DaemonStatus::Error => 3,
         }
     }
 
@@ -383,11 +570,29 @@ impl DaemonStatus {
         match v {
             0 => DaemonStatus::Waiting,
             1 => DaemonStatus::Processing,
+            3 => DaemonStatus::Error,
             _ => DaemonStatus::Stopping,
         }
     }
 }
 
+/// The error that ended a processing cycle
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DaemonError {
+    pub message: String,
+    /// When the cycle failed (RFC 3339)
+    pub occurred_at: String,
+}
+
+/// Counts of a processing cycle kept by the daemon; the results it saved are
+/// counted by the database (see `Database::finish_scan_run`)
+#[derive(Debug, Default)]
+struct CycleStats {
+    repositories_processed: i64,
+    errors: i64,
+    last_error: Option<String>,
+}
+
 /// The type of analysis to perform for a task
 #[derive(Debug, Clone, Copy)]
 enum AnalysisTaskType {
@@ -401,6 +606,36 @@ enum AnalysisTaskType {
     DocumentationAnalysis,
 }
 
+impl AnalysisTaskType {
+    /// What a response must look like to be stored; `delta` for analyses
+    /// updated from a diff
+    fn requirements(self, delta: bool) -> Requirements {
+        match self {
+            AnalysisTaskType::CodeUnderstanding if delta => Requirements::DELTA,
+            AnalysisTaskType::CodeUnderstanding | AnalysisTaskType::DocumentationAnalysis => {
+                Requirements::ANALYSIS
+            }
+            AnalysisTaskType::ArchitectureFileAnalysis | AnalysisTaskType::DiagramExtraction(_) => {
+                Requirements::EXTRACTION
+            }
+        }
+    }
+
+    /// The analysis type name under which results (and queue jobs) are stored
+    fn analysis_type(self) -> String {
+        match self {
+            AnalysisTaskType::CodeUnderstanding => AnalysisType::CodeUnderstanding.to_string(),
+            AnalysisTaskType::ArchitectureFileAnalysis => {
+                AnalysisType::ArchitectureFileAnalysis.to_string()
+            }
+            AnalysisTaskType::DiagramExtraction(diagram_type) => {
+                format!("diagram_extraction_{}", diagram_type.as_str())
+            }
+            AnalysisTaskType::DocumentationAnalysis => AnalysisType::Documentation.to_string(),
+        }
+    }
+}
+
 /// An analysis task to be processed by a worker
 struct AnalysisTask {
     repository_id: i64,
@@ -410,43 +645,268 @@ struct AnalysisTask {
     task_type: AnalysisTaskType,
     /// The programming language of the file being analyzed.
     language: Language,
+    /// Queue entry tracking this task (None if it could not be recorded)
+    job_id: Option<i64>,
+    /// Previous analysis to update instead of analyzing from scratch
+    delta: Option<DeltaContext>,
+    /// Keep the analyzed content so the next change can be analyzed as a delta
+    save_snapshot: bool,
+    /// Known open issues, appended to code understanding prompts
+    issue_context: Option<Arc<str>>,
+    /// Project context of the file, appended to code understanding prompts
+    file_context: Option<String>,
+    /// Keep security findings (off when the repository deselects the security pass)
+    security: bool,
+}
+
+/// The analysis workers of a pass: one pool for small files and, when routing
+/// sends large files to other endpoints, one for large files
+struct WorkerPools {
+    small: mpsc::Sender<AnalysisTask>,
+    large: Option<mpsc::Sender<AnalysisTask>>,
+    routing: RoutingConfig,
+    handles: Vec<tokio::task::JoinHandle<()>>,
+    results: ResultCollector,
+}
+
+impl WorkerPools {
+    /// Send a task to the pool for its size, or to the other pool once every
+    /// worker of that one has stopped. Returns false if no worker is left.
+    async fn send(&self, task: AnalysisTask) -> bool {
+        let Some(large) = &self.large else {
+            return self.small.send(task).await.is_ok();
+        };
+        let (preferred, other) = match FileSize::of(&task.content, &self.routing) {
+            FileSize::Small => (&self.small, large),
+            FileSize::Large => (large, &self.small),
+        };
+        match preferred.send(task).await {
+            Ok(()) => true,
+            Err(mpsc::error::SendError(task)) => other.send(task).await.is_ok(),
+        }
+    }
+
+    /// Close the pools, wait for their workers to finish and save the results
+    /// still waiting for a full batch
+    async fn finish(self, pass: &str) {
+        let WorkerPools {
+            small,
+            large,
+            handles,
+            results,
+            ..
+        } = self;
+        drop(small);
+        drop(large);
+
+        for handle in handles {
+            if let Err(e) = handle.await {
+                tracing::warn!("{} worker failed: {}", pass, e);
+            }
+        }
+        results.flush().await;
+    }
+}
+
+/// An analysis completed by a worker, waiting in a [`ResultCollector`] to be saved
+struct CompletedAnalysis {
+    task: AnalysisTask,
+    result: NewAnalysisResult,
+    /// Change-log entry of a delta analysis
+    changes: Option<String>,
+    /// Structured findings of a code understanding result
+    findings: Option<Vec<StructuredFinding>>,
+}
+
+/// Collects the results of a pass's analysis workers and saves them in
+/// batches, one transaction each, so a cycle doesn't hold up the web server
+/// with a write per analyzed file. Jobs are completed once their batch is saved.
+#[derive(Clone)]
+struct ResultCollector {
+    pending: Arc<TokioMutex<Vec<CompletedAnalysis>>>,
+    db: Database,
+    queue: JobQueue,
+    progress: ProgressBroadcaster,
+}
+
+impl ResultCollector {
+    fn new(db: Database, queue: JobQueue, progress: ProgressBroadcaster) -> Self {
+        Self {
+            pending: Arc::new(TokioMutex::new(Vec::new())),
+            db,
+            queue,
+            progress,
+        }
+    }
+
+    /// Add a result, saving the batch once it is full
+    async fn push(&self, completed: CompletedAnalysis) {
+        let batch = {
+            let mut pending = self.pending.lock().await;
+            pending.push(completed);
+            if pending.len() < RESULT_BATCH_SIZE {
+                return;
+            }
+            std::mem::take(&mut *pending)
+        };
+        self.save(batch).await;
+    }
+
+    /// Save the results waiting for a full batch
+    async fn flush(&self) {
+        let batch = std::mem::take(&mut *self.pending.lock().await);
+        self.save(batch).await;
+    }
+
+    async fn save(&self, batch: Vec<CompletedAnalysis>) {
+        // A pass analyzes one repository, but nothing stops a batch from mixing them
+        let mut by_repository: HashMap<i64, Vec<CompletedAnalysis>> = HashMap::new();
+        for completed in batch {
+            by_repository
+                .entry(completed.task.repository_id)
+                .or_default()
+                .push(completed);
+        }
+
+        for (repository_id, completed) in by_repository {
+            let results: Vec<NewAnalysisResult> =
+                completed.iter().map(|c| c.result.clone()).collect();
+            let ids = match self.db.save_analysis_results(repository_id, &results).await {
+                Ok(ids) => ids,
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to save {} analysis result(s): {}",
+                        completed.len(),
+                        e
+                    );
+                    for c in &completed {
+                        self.queue.fail(c.task.job_id, &e.to_string()).await;
+                    }
+                    continue;
+                }
+            };
+
+            for (c, result_id) in completed.into_iter().zip(ids) {
+                let file_path = &c.result.file_path;
+                save_delta_history(&self.db, &c.task, file_path, c.changes.as_deref()).await;
+                self.progress.publish(DashboardUpdate::AnalysisResult {
+                    repository_id,
+                    file_path: file_path.clone(),
+                    analysis_type: c.result.analysis_type.clone(),
+                    severity: c.result.severity.clone(),
+                });
+                if let Some(found) = &c.findings {
+                    if let Err(e) = self
+                        .db
+                        .save_findings(result_id, repository_id, file_path, &c.task.content, found)
+                        .await
+                    {
+                        tracing::warn!("Failed to save findings of {}: {}", file_path, e);
+                    }
+                }
+                self.queue.complete(c.task.job_id).await;
+            }
+        }
+    }
 }
 
 /// Handle for controlling the daemon from outside (e.g., web handlers).
 /// This is cheap to clone and doesn't require any locks.
 #[derive(Clone)]
 pub struct DaemonHandle {
-    should_stop: Arc<AtomicBool>,
-    trigger_scan: Arc<AtomicBool>,
+    cancel: CancellationToken,
+    trigger_scan: ScanTrigger,
+    pause: PauseSwitch,
     status: Arc<AtomicU8>,
+    last_error: Arc<std::sync::Mutex<Option<DaemonError>>>,
+    progress: ProgressBroadcaster,
+    health: HealthMonitor,
 }
 
 impl DaemonHandle {
-    /// Trigger an immediate scan (works anytime, ignores schedule)
+    /// Trigger a scan (works anytime, ignores schedule). The scan starts once no
+    /// further trigger has arrived for `schedule.trigger_debounce_seconds`.
     pub fn trigger_scan(&self) {
-        self.trigger_scan.store(true, Ordering::SeqCst);
+        self.trigger_scan.trigger();
         tracing::info!("Scan triggered manually");
     }
 
+    /// Stop all processing, even inside the schedule window, until resumed
+    pub fn pause(&self) {
+        if self.pause.pause() {
+            tracing::info!("Daemon paused");
+            self.progress
+                .publish(DashboardUpdate::Paused { paused: true });
+        }
+    }
+
+    /// Resume processing after [`DaemonHandle::pause`]
+    pub fn resume(&self) {
+        if self.pause.resume() {
+            tracing::info!("Daemon resumed");
+            self.progress
+                .publish(DashboardUpdate::Paused { paused: false });
+        }
+    }
+
+    /// Whether processing is paused
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_paused()
+    }
+
     /// Signal the daemon to stop gracefully
     pub fn stop(&self) {
         tracing::info!("Shutdown requested, stopping daemon...");
-        self.should_stop.store(true, Ordering::SeqCst);
+        self.cancel.cancel();
     }
 
     /// Get current daemon status
     pub fn status(&self) -> DaemonStatus {
         DaemonStatus::from_u8(self.status.load(Ordering::SeqCst))
     }
+
+    /// The error that ended the most recent failed cycle, if any
+    pub fn last_error(&self) -> Option<DaemonError> {
+        self.last_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Subscribe to live progress of LLM generations
+    pub fn subscribe_progress(&self) -> tokio::sync::broadcast::Receiver<ProgressEvent> {
+        self.progress.subscribe()
+    }
+
+    /// Subscribe to daemon state changes and newly saved results
+    pub fn subscribe_updates(&self) -> tokio::sync::broadcast::Receiver<DashboardUpdate> {
+        self.progress.subscribe_updates()
+    }
+
+    /// Current health of the given endpoints
+    pub fn endpoint_health(&self, endpoints: &[OllamaEndpoint]) -> Vec<EndpointHealth> {
+        self.health.snapshot(endpoints)
+    }
+
+    /// The daemon's endpoint health, for generating outside a scan
+    pub fn health(&self) -> &HealthMonitor {
+        &self.health
+    }
 }
 
 /// The background daemon that manages analysis tasks
 pub struct Daemon {
     config: Arc<RwLock<Config>>,
     status: Arc<AtomicU8>,
-    should_stop: Arc<AtomicBool>,
-    trigger_scan: Arc<AtomicBool>,
+    last_error: Arc<std::sync::Mutex<Option<DaemonError>>>,
+    cancel: CancellationToken,
+    trigger_scan: ScanTrigger,
+    pause: PauseSwitch,
     db: Database,
+    queue: JobQueue,
+    progress: ProgressBroadcaster,
+    health: HealthMonitor,
+    watcher: RepoWatcher,
 }
 
 impl Daemon {
@@ -455,8 +915,14 @@ impl Daemon {
         Self {
             config,
             status: Arc::new(AtomicU8::new(DaemonStatus::Waiting.as_u8())),
-            should_stop: Arc::new(AtomicBool::new(false)),
-            trigger_scan: Arc::new(AtomicBool::new(false)),
+            last_error: Arc::new(std::sync::Mutex::new(None)),
+            cancel: CancellationToken::new(),
+            trigger_scan: ScanTrigger::new(),
+            pause: PauseSwitch::new(),
+            queue: JobQueue::new(db.clone()),
+            progress: ProgressBroadcaster::new(),
+            health: HealthMonitor::new(),
+            watcher: RepoWatcher::new(),
             db,
         }
     }
@@ -465,15 +931,41 @@ impl Daemon {
     /// The handle is cheap to clone and doesn't require locks.
     pub fn handle(&self) -> DaemonHandle {
         DaemonHandle {
-            should_stop: Arc::clone(&self.should_stop),
-            trigger_scan: Arc::clone(&self.trigger_scan),
+            cancel: self.cancel.clone(),
+            trigger_scan: self.trigger_scan.clone(),
+            pause: self.pause.clone(),
             status: Arc::clone(&self.status),
+            last_error: Arc::clone(&self.last_error),
+            progress: self.progress.clone(),
+            health: self.health.clone(),
         }
     }
 
-    /// Check if we're in the scheduled window
+    /// Check if we're in a scheduled window (of any repository) and the
+    /// schedule isn't paused
     async fn is_in_schedule(&self) -> bool {
-        self.config.read().await.schedule.is_in_window()
+        if let Some(until) = schedule_paused_until(&self.db).await {
+            tracing::debug!("Schedule paused until {}", until.to_rfc3339());
+            return false;
+        }
+        self.config.read().await.schedule.is_any_open()
+    }
+
+    /// Time left in the current schedule window of `repo`, or None when a run
+    /// isn't bound to it: scans triggered outside the window and schedules that
+    /// never close
+    async fn schedule_time_left(&self, repo: &Repository) -> Option<Duration> {
+        if !self.is_in_schedule().await {
+            return None;
+        }
+        let now = chrono::Local::now().naive_local();
+        let remaining = self
+            .config
+            .read()
+            .await
+            .schedule
+            .remaining_at(&repo.tags, now)?;
+        remaining.to_std().ok()
     }
 
     /// Get current daemon status
@@ -483,43 +975,131 @@ impl Daemon {
 
     /// Set daemon status
     fn set_status(&self, status: DaemonStatus) {
-        self.status.store(status.as_u8(), Ordering::SeqCst);
+        let previous = self.status.swap(status.as_u8(), Ordering::SeqCst);
+        if previous != status.as_u8() {
+            self.progress.publish(DashboardUpdate::Status {
+                state: status,
+                task: None,
+            });
+        }
+    }
+
+    /// Record the daemon's current task and show it on the dashboard
+    async fn set_task(&self, status: &str, task: Option<&str>) -> anyhow::Result<()> {
+        self.db.update_daemon_status(status, task).await?;
+        self.progress.publish(DashboardUpdate::Status {
+            state: self.status(),
+            task: task.map(str::to_string),
+        });
+        Ok(())
+    }
+
+    /// Run one processing cycle. A failure puts the daemon in the error state
+    /// (recorded in the database) instead of ending the daemon loop, and the
+    /// cycle is retried on a later tick. `trigger` (`scheduled` or `manual`) is
+    /// recorded in the run history.
+    async fn run_cycle(&mut self, trigger: &str) {
+        let Err(e) = self.process_tasks(trigger).await else {
+            return;
+        };
+
+        let message = format!("{:#}", e);
+        tracing::error!("Processing cycle failed: {}", message);
+        *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(DaemonError {
+            message: message.clone(),
+            occurred_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        });
+        self.set_status(DaemonStatus::Error);
+        if let Err(e) = self.db.record_daemon_error(&message).await {
+            tracing::warn!("Failed to record daemon error: {}", e);
+        }
     }
 
     /// Run the daemon loop
     pub async fn run(&mut self) -> anyhow::Result<()> {
         let config = self.config.read().await;
         tracing::info!(
-            "Daemon started (scheduled window: {:02}:00 - {:02}:00)",
-            config.schedule.start_hour,
-            config.schedule.end_hour
+            "Daemon started (scheduled window: {})",
+            config.schedule.describe()
         );
         let check_interval = Duration::from_secs(config.schedule.check_interval_seconds);
         drop(config);
 
+        // Resume work interrupted by a previous shutdown or crash
+        if let Err(e) = self.queue.recover().await {
+            tracing::warn!("Failed to recover job queue: {}", e);
+        }
+        match self.db.interrupt_scan_runs().await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("Marked {} unfinished scan run(s) as interrupted", n),
+            Err(e) => tracing::warn!("Failed to mark interrupted scan runs: {}", e),
+        }
+
+        // Track endpoint health in the background, so failing endpoints rejoin the
+        // pool once they recover
+        tokio::spawn(health::run_probes(
+            self.health.clone(),
+            Arc::clone(&self.config),
+            self.cancel.clone(),
+        ));
+
         let mut ticker = interval(check_interval);
 
         loop {
-            // Wait for either the next tick or a stop signal
+            self.sync_watchers().await;
+
+            // Wait for the next tick, a manual trigger, a resume, watched file
+            // changes or a stop signal
             tokio::select! {
                 _ = ticker.tick() => {}
-                _ = self.wait_for_stop() => {
+                _ = self.trigger_scan.notified() => {}
+                _ = self.pause.resumed() => {}
+                changes = self.watcher.changes() => {
+                    if self.pause.is_paused() {
+                        tracing::debug!("Paused, ignoring file changes");
+                    } else {
+                        self.analyze_changes(changes).await;
+                    }
+                    continue;
+                }
+                _ = self.cancel.cancelled() => {
                     break;
                 }
             }
 
             // Check stop flag after waking up
-            if self.should_stop.load(Ordering::SeqCst) {
+            if self.cancel.is_cancelled() {
                 break;
             }
 
-            // Check if a scan was triggered manually
-            let scan_triggered = self.trigger_scan.swap(false, Ordering::SeqCst);
-            if scan_triggered {
+            // Take an automatic backup if one is due (independent of the schedule window)
+            if let Err(e) = self.run_scheduled_backup().await {
+                tracing::warn!("Automatic database backup failed: {}", e);
+            }
+            if let Err(e) = self.send_scheduled_digest().await {
+                tracing::warn!("Failed to send the digest email: {:#}", e);
+            }
+
+            // Skip all processing while paused; pending triggers run after resuming
+            if self.pause.is_paused() {
+                tracing::debug!("Paused, skipping processing");
+                if self.status() == DaemonStatus::Processing {
+                    self.set_status(DaemonStatus::Waiting);
+                }
+                continue;
+            }
+
+            // Check if a scan was triggered manually, letting rapid triggers settle first
+            if self.trigger_scan.is_pending() && !self.wait_for_quiet_trigger().await {
+                break;
+            }
+            if self.trigger_scan.take() {
                 tracing::info!("Running manually triggered scan");
                 self.set_status(DaemonStatus::Processing);
-                self.process_tasks().await?;
-                self.set_status(DaemonStatus::Waiting);
+                self.run_cycle("manual").await;
+                if self.status() == DaemonStatus::Processing {
+                    self.set_status(DaemonStatus::Waiting);
+                }
                 continue;
             }
 
@@ -530,11 +1110,21 @@ impl Daemon {
                 (DaemonStatus::Waiting, true) => {
                     tracing::info!("Entering scheduled window, starting processing");
                     self.set_status(DaemonStatus::Processing);
-                    self.process_tasks().await?;
+                    self.warm_up_endpoints().await;
+                    self.run_cycle("scheduled").await;
                 }
                 (DaemonStatus::Processing, true) => {
                     // Continue processing
-                    self.process_tasks().await?;
+                    self.run_cycle("scheduled").await;
+                }
+                (DaemonStatus::Error, true) => {
+                    tracing::info!("Retrying processing after a failed cycle");
+                    self.set_status(DaemonStatus::Processing);
+                    self.run_cycle("scheduled").await;
+                }
+                (DaemonStatus::Error, false) => {
+                    // Stay in the error state until the next cycle succeeds
+                    tracing::debug!("Outside scheduled window after a failed cycle, waiting");
                 }
                 (DaemonStatus::Processing, false) => {
                     tracing::info!("Exiting scheduled window, pausing");
@@ -555,35 +1145,91 @@ impl Daemon {
         Ok(())
     }
 
-    /// Wait until the stop flag is set (used for select!)
-    async fn wait_for_stop(&self) {
-        // Poll the stop flag periodically
-        while !self.should_stop.load(Ordering::SeqCst) {
-            tokio::time::sleep(Duration::from_millis(100)).await;
+    /// Load the models of endpoints with `keep_alive` set before the window's
+    /// first analysis
+    async fn warm_up_endpoints(&self) {
+        let endpoints = self.config.read().await.endpoints.clone();
+        if endpoints
+            .iter()
+            .any(|e| e.enabled && e.keep_alive.is_some())
+        {
+            tracing::info!("Warming up endpoints");
+            self.health.warm_up(&endpoints, &self.cancel).await;
         }
     }
 
-    /// Sleep for a duration, but wake up early if shutdown is requested.
-    /// Checks the stop flag every second.
-    async fn interruptible_sleep(&self, seconds: u64) {
-        for _ in 0..seconds {
-            if self.should_stop.load(Ordering::SeqCst) {
+    /// Watch the enabled, unarchived repositories that have `repository.watch`
+    /// set, and stop watching the rest
+    async fn sync_watchers(&mut self) {
+        let repositories = match self.db.get_repositories().await {
+            Ok(repos) => repos,
+            Err(e) => {
+                tracing::warn!("Failed to fetch repositories for watch mode: {}", e);
                 return;
             }
-            tokio::time::sleep(Duration::from_secs(1)).await;
+        };
+
+        let mut watched = HashSet::new();
+        for repo in repositories.iter().filter(|r| r.enabled && !r.archived()) {
+            let path = Path::new(&repo.path);
+            if !RepoConfig::load(path).is_some_and(|c| c.repository.watch) {
+                continue;
+            }
+            watched.insert(repo.id);
+            if self.watcher.is_watching(repo.id) {
+                continue;
+            }
+            match self.watcher.watch(repo.id, path) {
+                Ok(()) => tracing::info!("Watching {} for changes", repo.name),
+                Err(e) => tracing::warn!("Failed to watch {}: {:#}", repo.name, e),
+            }
         }
+        self.watcher.retain(&watched);
     }
 
-    /// Process background analysis tasks
-    async fn process_tasks(&mut self) -> anyhow::Result<()> {
-        tracing::debug!("Processing tasks");
+    /// Run code analysis on files changed in watched repositories. Aggregation and
+    /// mutation testing are left to the next scheduled scan.
+    async fn analyze_changes(&self, changes: ChangedFiles) {
+        let previous_status = self.status();
+        self.set_status(DaemonStatus::Processing);
 
-        // Update daemon state in database
-        self.db
-            .update_daemon_status("processing", Some("scanning repositories"))
-            .await?;
+        for (repository_id, changed) in changes {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+            if let Err(e) = self.analyze_changed_files(repository_id, &changed).await {
+                tracing::warn!(
+                    "Failed to analyze changed files in repository {}: {:#}",
+                    repository_id,
+                    e
+                );
+            }
+        }
+
+        if self.status() == DaemonStatus::Processing {
+            self.set_status(previous_status);
+        }
+    }
+
+    /// Analyze the given files (relative to the repository root) in place
+    async fn analyze_changed_files(
+        &self,
+        repository_id: i64,
+        changed: &HashSet<PathBuf>,
+    ) -> anyhow::Result<()> {
+        let Some(repo) = self.db.get_repository(repository_id).await? else {
+            return Ok(());
+        };
+        let repo_path = Path::new(&repo.path);
+        let repo_config = RepoConfig::load(repo_path).unwrap_or_default();
+        if !repo.enabled
+            || !repo_config.repository.watch
+            || !repo_config.enable_code_analysis
+            || !repo.runs("code_understanding")
+        {
+            return Ok(());
+        }
 
-        // Get enabled endpoints from config (read fresh each cycle)
         let endpoints: Vec<_> = self
             .config
             .read()
@@ -593,52 +1239,339 @@ impl Daemon {
             .filter(|e| e.enabled)
             .cloned()
             .collect();
-
         if endpoints.is_empty() {
-            tracing::debug!("No Ollama endpoints configured, waiting...");
-            self.db
-                .update_daemon_status("idle", Some("no endpoints configured"))
-                .await?;
-            tokio::time::sleep(Duration::from_secs(5)).await;
+            tracing::debug!("No Ollama endpoints configured, ignoring file changes");
             return Ok(());
         }
-
-        // Get all enabled repositories
-        let repositories = match self.db.get_repositories().await {
-            Ok(repos) => repos,
-            Err(e) => {
-                tracing::error!("Failed to fetch repositories: {}", e);
-                return Ok(());
-            }
+        let Some(endpoints) = self.route_endpoints(&repo, &endpoints).await else {
+            return Ok(());
         };
 
-        let enabled_repos: Vec<_> = repositories.into_iter().filter(|r| r.enabled).collect();
+        let file_filter = repo_config
+            .file_filter()
+            .with_context(|| format!("Invalid include/exclude globs for {}", repo.name))?;
+
+        // Files are read from the repository itself: nothing is modified, so no
+        // temp copy is needed
+        let mut file_data = Vec::new();
+        for project in discover_projects(repo_path)? {
+            for file_path in project.language.find_included_source_files(
+                &project.root,
+                repo_path,
+                &file_filter,
+            )? {
+                let relative_path = file_path.strip_prefix(repo_path).unwrap_or(&file_path);
+                if !changed.contains(relative_path) {
+                    continue;
+                }
+                if let Some((content, content_hash)) = self
+                    .load_source_file(
+                        repo.id,
+                        &file_path,
+                        &file_path,
+                        project.language,
+                        &file_filter,
+                        relative_path,
+                    )
+                    .await
+                {
+                    file_data.push((file_path, content, content_hash, project.language));
+                }
+            }
+        }
 
-        if enabled_repos.is_empty() {
-            tracing::debug!("No enabled repositories to analyze");
-            self.db.update_daemon_status("idle", None).await?;
-            tokio::time::sleep(Duration::from_secs(5)).await;
+        if file_data.is_empty() {
             return Ok(());
         }
+        tracing::info!(
+            "Analyzing {} changed file(s) in {}",
+            file_data.len(),
+            repo.name
+        );
+        self.run_code_understanding_analysis(&repo, &file_data, &endpoints, &repo_config)
+            .await?;
+        Ok(())
+    }
 
-        // Process each repository with parallel workers
-        for repo in enabled_repos {
-            // Check if we should stop before processing each repo
-            if self.should_stop.load(Ordering::SeqCst) {
+    /// Wait until no manual trigger has arrived for the debounce window.
+    /// Returns false if the daemon was stopped while waiting.
+    async fn wait_for_quiet_trigger(&self) -> bool {
+        let debounce =
+            Duration::from_secs(self.config.read().await.schedule.trigger_debounce_seconds);
+
+        while let Some(remaining) = self.trigger_scan.remaining(debounce) {
+            if remaining.is_zero() {
                 break;
             }
+            tokio::select! {
+                _ = tokio::time::sleep(remaining) => {}
+                _ = self.cancel.cancelled() => return false,
+            }
+        }
+        true
+    }
 
-            tracing::info!("Analyzing repository: {} ({})", repo.name, repo.path);
-            self.db
-                .update_daemon_status("processing", Some(&format!("analyzing {}", repo.name)))
+    /// Snapshot the database if the newest backup is older than the configured interval,
+    /// then rotate old backups.
+    async fn run_scheduled_backup(&self) -> anyhow::Result<()> {
+        let (backup_config, backup_dir, db_path) = {
+            let config = self.config.read().await;
+            (
+                config.backup.clone(),
+                config.backup_dir(),
+                config.database_path(),
+            )
+        };
+
+        // A PostgreSQL server is backed up with its own tools
+        let Some(db_path) = db_path.filter(|_| backup_config.enabled) else {
+            return Ok(());
+        };
+
+        let interval = Duration::from_secs(backup_config.interval_hours * 3600);
+        if !backup::is_backup_due(&backup_dir, interval)? {
+            return Ok(());
+        }
+
+        let path = backup::create_backup(&db_path, &backup_dir).await?;
+        tracing::info!("Database backed up to {}", path.display());
+
+        if let Err(e) = self.export_backup(&path).await {
+            tracing::warn!("Failed to export backup {}: {:#}", path.display(), e);
+        }
+
+        for removed in backup::rotate_backups(&backup_dir, backup_config.keep.max(1))? {
+            tracing::debug!("Removed old backup {}", removed.display());
+        }
+
+        Ok(())
+    }
+
+    /// Email the digest of the scans since the previous one if it is due.
+    /// A digest that fails to send is skipped; the next one covers its scans.
+    async fn send_scheduled_digest(&self) -> anyhow::Result<()> {
+        let (email, dashboard_url) = {
+            let config = self.config.read().await;
+            (config.notifications.email.clone(), config.dashboard_url())
+        };
+        let Some(email) = email.filter(|email| email.digest) else {
+            return Ok(());
+        };
+
+        let last = self.db.last_digest().await?;
+        let last_sent = last
+            .as_ref()
+            .and_then(|digest| chrono::DateTime::parse_from_rfc3339(&digest.sent_at).ok())
+            .map(|sent| sent.with_timezone(&chrono::Local).naive_local());
+        if !email.digest_due(chrono::Local::now().naive_local(), last_sent) {
+            return Ok(());
+        }
+
+        // The first digest covers the past day
+        let (after_run_id, since) = match &last {
+            Some(digest) => (digest.last_run_id, String::new()),
+            None => (
+                0,
+                (chrono::Utc::now() - chrono::Duration::days(1))
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            ),
+        };
+        let runs = self.db.get_finished_scan_runs(after_run_id, &since).await?;
+        let (Some(first), Some(newest)) = (runs.first(), runs.last()) else {
+            tracing::info!("No scans since the last digest, skipping it");
+            return self.db.record_digest(after_run_id).await;
+        };
+
+        let repositories = self.db.get_repository_digests(first.id).await?;
+        let digest = DigestEmail::new(&runs, &repositories, &dashboard_url);
+        if let Err(e) = digest.send(&email).await {
+            self.db.record_digest(after_run_id).await?;
+            return Err(e);
+        }
+        tracing::info!("Sent digest email: {}", digest.subject);
+        self.db.record_digest(newest.id).await
+    }
+
+    /// Upload a new backup to the configured export targets
+    async fn export_backup(&self, path: &Path) -> anyhow::Result<()> {
+        let export_config = self.config.read().await.export.clone();
+        if !export_config.is_enabled() || !export_config.backups {
+            return Ok(());
+        }
+
+        let file_name = path
+            .file_name()
+            .context("Backup path has no file name")?
+            .to_string_lossy()
+            .into_owned();
+        let body = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read backup {}", path.display()))?;
+        Exporter::new(export_config)
+            .upload(
+                &export::backup_key(&file_name),
+                body,
+                "application/vnd.sqlite3",
+            )
+            .await?;
+        tracing::info!("Exported backup {}", file_name);
+        Ok(())
+    }
+
+    /// Upload the HTML report of each repository to the configured export targets
+    async fn export_reports(&self, repositories: &[crate::db::Repository]) {
+        let (export_config, templates_dir) = {
+            let config = self.config.read().await;
+            (config.export.clone(), config.web.templates_dir.clone())
+        };
+        if !export_config.is_enabled() || !export_config.reports {
+            return;
+        }
+
+        let templates = Templates::new(templates_dir.as_deref());
+        let exporter = Exporter::new(export_config);
+        let format = ReportFormat::Html;
+        for repo in repositories {
+            let result = async {
+                let report = Report::load(&self.db, repo.id)
+                    .await?
+                    .render(format, &templates)?;
+                let key = export::report_key(&repo.name, format.extension());
+                exporter
+                    .upload(&key, report.into_bytes(), format.content_type())
+                    .await
+            }
+            .await;
+            match result {
+                Ok(()) => tracing::debug!("Exported report of {}", repo.name),
+                Err(e) => tracing::warn!("Failed to export report of {}: {:#}", repo.name, e),
+            }
+        }
+    }
+
+    /// Sleep for a duration, but wake up early if shutdown is requested.
+    async fn interruptible_sleep(&self, seconds: u64) {
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(seconds)) => {}
+            _ = self.cancel.cancelled() => {}
+        }
+    }
+
+    /// Process background analysis tasks
+    async fn process_tasks(&mut self, trigger: &str) -> anyhow::Result<()> {
+        tracing::debug!("Processing tasks");
+
+        // Update daemon state in database
+        self.set_task("processing", Some("scanning repositories"))
+            .await?;
+
+        // Get enabled endpoints from config (read fresh each cycle)
+        let endpoints: Vec<_> = self
+            .config
+            .read()
+            .await
+            .endpoints
+            .iter()
+            .filter(|e| e.enabled)
+            .cloned()
+            .collect();
+
+        if endpoints.is_empty() {
+            tracing::debug!("No Ollama endpoints configured, waiting...");
+            self.set_task("idle", Some("no endpoints configured"))
                 .await?;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            return Ok(());
+        }
 
-            if let Err(e) = self.analyze_repository_parallel(&repo, &endpoints).await {
-                tracing::warn!("Failed to analyze repository {}: {}", repo.name, e);
+        // Get all enabled repositories
+        let repositories = match self.db.get_repositories().await {
+            Ok(repos) => repos,
+            Err(e) => {
+                tracing::error!("Failed to fetch repositories: {}", e);
+                return Ok(());
+            }
+        };
+
+        let mut enabled_repos: Vec<_> = repositories
+            .into_iter()
+            .filter(|r| r.enabled && !r.archived())
+            .collect();
+
+        // Repositories added from the dashboard wait for their quick scan to be
+        // confirmed
+        match self.db.get_unconfirmed_repositories().await {
+            Ok(unconfirmed) => enabled_repos.retain(|repo| {
+                let confirmed = !unconfirmed.contains(&repo.id);
+                if !confirmed {
+                    tracing::debug!("{} awaits confirmation, skipping", repo.name);
+                }
+                confirmed
+            }),
+            Err(e) => tracing::warn!("Failed to read unconfirmed repositories: {}", e),
+        }
+
+        // Scheduled scans only cover repositories whose window is open, since
+        // tags can give repositories windows of their own
+        if trigger == "scheduled" {
+            let schedule = self.config.read().await.schedule.clone();
+            let now = chrono::Local::now().naive_local();
+            enabled_repos.retain(|repo| {
+                let open = schedule.is_open_for_at(&repo.tags, now);
+                if !open {
+                    tracing::debug!("Outside the scheduled window of {}, skipping", repo.name);
+                }
+                open
+            });
+        }
+
+        // Repositories with unfinished jobs from an interrupted run go first
+        match self.queue.repositories_with_pending_jobs().await {
+            Ok(pending) => queue::prioritize_pending(&mut enabled_repos, &pending, |r| r.id),
+            Err(e) => tracing::warn!("Failed to read pending jobs: {}", e),
+        }
+
+        if enabled_repos.is_empty() {
+            tracing::debug!("No enabled repositories to analyze");
+            self.set_task("idle", None).await?;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            return Ok(());
+        }
+
+        // Process each repository with parallel workers, recording the cycle in
+        // the run history
+        let run_id = self.db.start_scan_run(trigger).await?;
+        let mut stats = CycleStats::default();
+        let outcome = self
+            .process_repositories(&enabled_repos, &endpoints, &mut stats)
+            .await;
+        let status = match &outcome {
+            Err(e) => {
+                stats.errors += 1;
+                stats.last_error = Some(format!("{:#}", e));
+                "failed"
             }
+            Ok(()) if self.cancel.is_cancelled() || self.pause.is_paused() => "stopped",
+            Ok(()) => "completed",
+        };
+        if let Err(e) = self
+            .db
+            .finish_scan_run(
+                run_id,
+                status,
+                stats.repositories_processed,
+                stats.errors,
+                stats.last_error.as_deref(),
+            )
+            .await
+        {
+            tracing::warn!("Failed to record the end of scan run {}: {}", run_id, e);
         }
+        self.record_scores(run_id, &enabled_repos).await;
+        self.notify_scan_completed(run_id).await;
+        outcome?;
 
-        self.db.update_daemon_status("idle", None).await?;
+        self.set_task("idle", None).await?;
 
         // Wait before next cycle to avoid excessive resource usage
         // (especially since we copy the entire repo to temp each cycle)
@@ -653,6 +1586,157 @@ impl Daemon {
         Ok(())
     }
 
+    /// Analyze each repository in turn, then export reports unless the cycle
+    /// was stopped
+    async fn process_repositories(
+        &self,
+        enabled_repos: &[crate::db::Repository],
+        endpoints: &[OllamaEndpoint],
+        stats: &mut CycleStats,
+    ) -> anyhow::Result<()> {
+        for repo in enabled_repos {
+            // Check if we should stop before processing each repo
+            if self.cancel.is_cancelled() {
+                break;
+            }
+            if self.pause.is_paused() {
+                tracing::info!("Paused, stopping the processing cycle");
+                break;
+            }
+
+            tracing::info!("Analyzing repository: {} ({})", repo.name, repo.path);
+            self.set_task("processing", Some(&format!("analyzing {}", repo.name)))
+                .await?;
+
+            let Some(repo_endpoints) = self.route_endpoints(repo, endpoints).await else {
+                continue;
+            };
+            if let Err(e) = self.pull_remote_repository(repo).await {
+                tracing::warn!("Failed to update {}: {:#}", repo.name, e);
+                stats.errors += 1;
+                stats.last_error = Some(format!("{}: {:#}", repo.name, e));
+                continue;
+            }
+            self.reconcile_stale_results(repo).await;
+            let started_at = crate::db::now_millis();
+            let first_result_id = self.db.latest_analysis_result_id().await?;
+            if let Err(e) = self
+                .analyze_repository_parallel(repo, &repo_endpoints)
+                .await
+            {
+                tracing::warn!("Failed to analyze repository {}: {}", repo.name, e);
+                stats.errors += 1;
+                stats.last_error = Some(format!("{}: {:#}", repo.name, e));
+                continue;
+            }
+
+            stats.repositories_processed += 1;
+            self.notify_critical_findings(repo, first_result_id).await;
+            if !self.cancel.is_cancelled() {
+                // A complete pass re-enqueues everything still needed, so leftovers are stale
+                if let Err(e) = self.queue.discard_pending(repo.id).await {
+                    tracing::warn!("Failed to discard stale jobs for {}: {}", repo.name, e);
+                }
+                // Files requested during the pass are analyzed again by the next one
+                if let Err(e) = self
+                    .db
+                    .clear_reanalysis_requests(repo.id, &started_at)
+                    .await
+                {
+                    tracing::warn!(
+                        "Failed to clear reanalysis requests of {}: {}",
+                        repo.name,
+                        e
+                    );
+                }
+            }
+        }
+
+        if !self.cancel.is_cancelled() && !self.pause.is_paused() {
+            self.export_reports(enabled_repos).await;
+        }
+
+        Ok(())
+    }
+
+    /// Bring the checkout of a repository added by Git URL up to date. If the
+    /// remote can't be reached, the last fetched state is scanned instead;
+    /// only a missing checkout that can't be cloned again is an error.
+    async fn pull_remote_repository(&self, repo: &crate::db::Repository) -> anyhow::Result<()> {
+        let Some(url) = &repo.remote_url else {
+            return Ok(());
+        };
+        self.set_task("processing", Some(&format!("pulling {}", repo.name)))
+            .await?;
+
+        let path = Path::new(&repo.path);
+        match crate::remote::update(path, url, repo.branch.as_deref()).await {
+            Err(e) if !path.join(".git").exists() => return Err(e),
+            Err(e) => tracing::warn!(
+                "Failed to pull {}, scanning the last fetched state: {:#}",
+                repo.name,
+                e
+            ),
+            Ok(()) => tracing::info!("Pulled {}", repo.name),
+        }
+        self.set_task("processing", Some(&format!("analyzing {}", repo.name)))
+            .await
+    }
+
+    /// Mark the results of files deleted from a repository stale, which hides
+    /// them until they are purged, and those of files that came back current
+    /// again. Nothing is marked if the repository itself is missing (e.g. on
+    /// an unmounted drive).
+    async fn reconcile_stale_results(&self, repo: &crate::db::Repository) {
+        if !Path::new(&repo.path).is_dir() {
+            return;
+        }
+        let files = match self.db.get_result_files(repo.id).await {
+            Ok(files) => files,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to check results of {} for deleted files: {}",
+                    repo.name,
+                    e
+                );
+                return;
+            }
+        };
+
+        let (mut deleted, mut restored) = (Vec::new(), Vec::new());
+        for (file_path, stale) in files {
+            // Repository-wide results (e.g. the architecture summary) have no file
+            let path = Path::new(&file_path);
+            if !path.is_absolute() {
+                continue;
+            }
+            match (path.exists(), stale) {
+                (false, false) => deleted.push(file_path),
+                (true, true) => restored.push(file_path),
+                _ => {}
+            }
+        }
+
+        for (files, stale) in [(deleted, true), (restored, false)] {
+            if files.is_empty() {
+                continue;
+            }
+            match self.db.set_results_stale(repo.id, &files, stale).await {
+                Ok(_) if stale => tracing::info!(
+                    "Marked the results of {} deleted files of {} stale",
+                    files.len(),
+                    repo.name
+                ),
+                Ok(_) => tracing::info!(
+                    "{} files of {} are back; their results are current again",
+                    files.len(),
+                    repo.name
+                ),
+                Err(e) => tracing::warn!("Failed to update stale results of {}: {}", repo.name, e),
+            }
+        }
+    }
+
     /// Analyze a repository using parallel workers (one per endpoint)
     /// Returns true if any files were analyzed (i.e., had changes)
     async fn analyze_repository_parallel(
@@ -686,7 +1770,13 @@ impl Daemon {
             "Copying repository {} to temp directory for analysis",
             repo.name
         );
-        let temp_dir = match copy_repo_to_temp(original_repo_path, &repo_config.copy_ignore).await {
+        let mut copy_ignore = repo_config.copy_ignore.clone();
+        if let Some(data_dir) = self.data_dir_within(original_repo_path).await {
+            // Keep Noctum's own database and caches out of the copy
+            copy_ignore.push(data_dir);
+        }
+        let snapshot = self.config.read().await.snapshot.clone();
+        let temp_dir = match copy_repo_to_temp(original_repo_path, &copy_ignore, &snapshot).await {
             Ok(dir) => dir,
             Err(e) => {
                 tracing::error!("Failed to copy repository to temp: {}", e);
@@ -699,27 +1789,46 @@ impl Daemon {
             temp_repo_path.display()
         );
 
+        // A pass runs if noctum.toml enables it and it's selected for the repository
+        let run_code = repo_config.enable_code_analysis && repo.runs("code_understanding");
+        let run_arch = repo_config.enable_architecture_analysis && repo.runs("architecture");
+        let run_diagrams = repo_config.enable_diagram_creation && repo.runs("diagrams");
+        let run_mutation = repo_config.enable_mutation_testing && repo.runs("mutation_testing");
+        let run_duplication = repo_config.enable_duplication_detection && repo.runs("duplication");
+        let run_dead_code = repo_config.enable_dead_code_detection && repo.runs("dead_code");
+        let run_test_gaps = run_mutation && repo.runs("test_gaps");
+
         // Log which features are enabled
         tracing::info!(
-            "Repository {} config: code_analysis={}, architecture_analysis={}, diagram_creation={}, mutation_testing={}",
+            "Repository {} config: code_analysis={}, architecture_analysis={}, diagram_creation={}, mutation_testing={}, duplication={}, dead_code={}, security={}",
             repo.name,
-            repo_config.enable_code_analysis,
-            repo_config.enable_architecture_analysis,
-            repo_config.enable_diagram_creation,
-            repo_config.enable_mutation_testing
+            run_code,
+            run_arch,
+            run_diagrams,
+            run_mutation,
+            run_duplication,
+            run_dead_code,
+            repo.runs("security")
         );
 
         // Check if any analysis is enabled
-        let any_analysis_enabled = repo_config.enable_code_analysis
-            || repo_config.enable_architecture_analysis
-            || repo_config.enable_diagram_creation
-            || repo_config.enable_mutation_testing;
+        let any_analysis_enabled = run_code
+            || run_arch
+            || run_diagrams
+            || run_mutation
+            || run_duplication
+            || run_dead_code
+            || !repo_config.plugins.is_empty();
 
         if !any_analysis_enabled {
             tracing::info!("No analysis features enabled for {}, skipping", repo.name);
             return Ok(false);
         }
 
+        let file_filter = repo_config
+            .file_filter()
+            .with_context(|| format!("Invalid include/exclude globs for {}", repo.name))?;
+
         // Discover projects in the repository
         let projects = discover_projects(temp_repo_path)?;
 
@@ -742,38 +1851,42 @@ impl Daemon {
 
         for project in &projects {
             // Find source files for this project
-            let source_files = project.language.find_source_files(&project.root)?;
+            let source_files = project.language.find_included_source_files(
+                &project.root,
+                temp_repo_path,
+                &file_filter,
+            )?;
 
             for file_path in source_files {
-                let content = match tokio::fs::read_to_string(&file_path).await {
-                    Ok(c) => c,
-                    Err(e) => {
-                        tracing::warn!("Failed to read file {:?}: {}", file_path, e);
-                        continue;
-                    }
-                };
-
-                // Use language-specific size limits
-                let min_size = project.language.min_file_size();
-                let max_size = project.language.max_file_size();
-                if content.len() > max_size || content.len() < min_size {
-                    tracing::debug!("Skipping file due to size: {:?}", file_path);
-                    continue;
-                }
+                let relative_path = file_path.strip_prefix(temp_repo_path).unwrap_or(&file_path);
 
                 let original_file_path =
                     translate_temp_to_original(temp_repo_path, original_repo_path, &file_path);
-                let content_hash = compute_hash(&content);
-
-                file_data.push((original_file_path, content, content_hash, project.language));
+                if let Some((content, content_hash)) = self
+                    .load_source_file(
+                        repo.id,
+                        &file_path,
+                        &original_file_path,
+                        project.language,
+                        &file_filter,
+                        relative_path,
+                    )
+                    .await
+                {
+                    file_data.push((original_file_path, content, content_hash, project.language));
+                }
             }
 
             // Find context files for this project
             let ctx_files = project.language.find_context_files(&project.root)?;
 
             for file_path in ctx_files {
-                let content = match tokio::fs::read_to_string(&file_path).await {
-                    Ok(c) => c,
+                let content = match read_source(&file_path).await {
+                    Ok(SourceText::Text(content) | SourceText::Lossy(content)) => content,
+                    Ok(SourceText::Skipped(reason)) => {
+                        tracing::debug!("Skipping {:?} context file: {:?}", reason, file_path);
+                        continue;
+                    }
                     Err(e) => {
                         tracing::warn!("Failed to read context file {:?}: {}", file_path, e);
                         continue;
@@ -807,6 +1920,13 @@ impl Daemon {
             return Ok(false);
         }
 
+        // Files whose pending jobs were reprioritized go first
+        let priorities = self.queue.file_priorities(repo.id).await;
+        let path_of =
+            |(path, ..): &(PathBuf, String, String, Language)| path.to_string_lossy().to_string();
+        queue::prioritize_files(&mut file_data, &priorities, path_of);
+        queue::prioritize_files(&mut context_file_data, &priorities, path_of);
+
         tracing::info!(
             "Found {} source files and {} context files in {}, distributing across {} endpoint(s)",
             file_data.len(),
@@ -815,14 +1935,12 @@ impl Daemon {
             endpoints.len()
         );
 
-        // Compute combined hash for diagram change detection
-        let combined_hash = {
-            let mut hasher = Sha256::new();
-            for (_, _, hash, _) in &file_data {
-                hasher.update(hash.as_bytes());
-            }
-            format!("{:x}", hasher.finalize())
-        };
+        self.refresh_issues(repo, &repo_config, original_repo_path)
+            .await;
+
+        if run_duplication {
+            self.detect_duplicates(repo, &file_data).await;
+        }
 
         // =========================================================================
         // PHASE 1: PARALLEL ANALYSIS
@@ -834,19 +1952,28 @@ impl Daemon {
         let mut diagrams_changed = false;
         let mut docs_changed = false;
 
-        // Only run analyses that are enabled
-        let run_code = repo_config.enable_code_analysis;
-        let run_arch = repo_config.enable_architecture_analysis;
-        let run_diagrams = repo_config.enable_diagram_creation;
+        // Plugins are loaded from the original repository, since the temp copy may
+        // exclude them via copy_ignore
+        let plugin_host = if repo_config.plugins.is_empty() {
+            None
+        } else {
+            match PluginHost::load(original_repo_path, &repo_config.plugins) {
+                Ok(host) => Some(host),
+                Err(e) => {
+                    tracing::warn!("Failed to initialize plugins for {}: {}", repo.name, e);
+                    None
+                }
+            }
+        };
 
-        if run_code || run_arch || run_diagrams {
+        if run_code || run_arch || run_diagrams || plugin_host.is_some() {
             tracing::info!("Starting parallel analysis phase for {}", repo.name);
 
             // Run enabled analysis types in parallel
             // We use Option futures to conditionally include each analysis
             let code_future = async {
                 if run_code {
-                    self.run_code_understanding_analysis(repo, &file_data, endpoints)
+                    self.run_code_understanding_analysis(repo, &file_data, endpoints, &repo_config)
                         .await
                 } else {
                     Ok(false)
@@ -881,8 +2008,24 @@ impl Daemon {
                 }
             };
 
-            let (code_result, arch_result, diagram_result, doc_result) =
-                tokio::join!(code_future, arch_future, diagram_future, doc_future);
+            // Plugin findings are standalone, so they don't trigger aggregation
+            let plugin_future = async {
+                match &plugin_host {
+                    Some(host) => {
+                        self.run_plugin_analysis(repo, &file_data, original_repo_path, host)
+                            .await
+                    }
+                    None => Ok(false),
+                }
+            };
+
+            let (code_result, arch_result, diagram_result, doc_result, plugin_result) = tokio::join!(
+                code_future,
+                arch_future,
+                diagram_future,
+                doc_future,
+                plugin_future
+            );
 
             code_changed = code_result.unwrap_or_else(|e| {
                 tracing::warn!("Code understanding analysis failed: {}", e);
@@ -903,12 +2046,16 @@ impl Daemon {
                 tracing::warn!("Documentation analysis failed: {}", e);
                 false
             });
+
+            if let Err(e) = plugin_result {
+                tracing::warn!("Plugin analysis failed: {}", e);
+            }
         }
 
         let any_changed = code_changed || arch_changed || diagrams_changed || docs_changed;
 
         // Check if we should continue
-        if self.should_stop.load(Ordering::SeqCst) {
+        if self.cancel.is_cancelled() {
             return Ok(any_changed);
         }
 
@@ -918,13 +2065,34 @@ impl Daemon {
         // Only run if the corresponding features are enabled.
         // =========================================================================
 
-        let should_aggregate = any_changed && (run_arch || run_diagrams);
+        // Diagrams requested from the dashboard are generated even without changes
+        let diagram_requests = if run_diagrams {
+            self.db
+                .get_diagram_requests(repo.id)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to read diagram requests of {}: {}", repo.name, e);
+                    Vec::new()
+                })
+        } else {
+            Vec::new()
+        };
+
+        let should_aggregate =
+            (any_changed && (run_arch || run_diagrams)) || !diagram_requests.is_empty();
         if should_aggregate {
             tracing::info!("Starting aggregation phase for {}", repo.name);
 
             let arch_summary_future = async {
-                if run_arch {
-                    self.generate_architecture_summary(repo, endpoints).await
+                if run_arch && any_changed {
+                    let job_id = self
+                        .queue
+                        .enqueue(repo.id, queue::JOB_ARCHITECTURE_SUMMARY, None)
+                        .await;
+                    self.queue.start(job_id).await;
+                    let result = self.generate_architecture_summary(repo, endpoints).await;
+                    self.queue.finish(job_id, &result).await;
+                    result
                 } else {
                     Ok(())
                 }
@@ -932,8 +2100,13 @@ impl Daemon {
 
             let diagrams_future = async {
                 if run_diagrams {
-                    self.generate_diagrams(repo, endpoints, &combined_hash)
-                        .await
+                    let job_id = self.queue.enqueue(repo.id, queue::JOB_DIAGRAMS, None).await;
+                    self.queue.start(job_id).await;
+                    let result = self
+                        .generate_diagrams(repo, endpoints, &file_data, &diagram_requests)
+                        .await;
+                    self.queue.finish(job_id, &result).await;
+                    result
                 } else {
                     Ok(())
                 }
@@ -961,18 +2134,34 @@ impl Daemon {
         }
 
         // Check if we should continue
-        if self.should_stop.load(Ordering::SeqCst) {
+        if self.cancel.is_cancelled() {
             return Ok(any_changed);
         }
 
+        // Keep the semantic search index in step with the file summaries
+        if run_code {
+            self.index_embeddings(repo, endpoints).await;
+        }
+
         // =========================================================================
         // PHASE 3: MUTATION TESTING
         // This must be sequential as it modifies files in the temp directory.
-        // Only run if mutation testing is enabled in the repo config.
+        // Only run if mutation testing is enabled for the repository.
         // =========================================================================
 
-        if repo_config.enable_mutation_testing {
-            if let Err(e) = self
+        if run_mutation && is_running_binary_source(original_repo_path) {
+            // Mutating and rebuilding the running daemon's own source could clobber its binary
+            tracing::warn!(
+                "Skipping mutation testing for {}: it contains the running Noctum binary's source",
+                repo.name
+            );
+        } else if run_mutation {
+            let job_id = self
+                .queue
+                .enqueue(repo.id, queue::JOB_MUTATION_TESTING, None)
+                .await;
+            self.queue.start(job_id).await;
+            let result = self
                 .run_mutation_testing(
                     repo,
                     endpoints,
@@ -980,12 +2169,29 @@ impl Daemon {
                     original_repo_path,
                     &repo_config,
                 )
-                .await
-            {
+                .await;
+            self.queue.finish(job_id, &result).await;
+            if let Err(e) = result {
                 tracing::warn!("Failed to run mutation testing for {}: {}", repo.name, e);
             }
         }
 
+        if run_test_gaps && !self.cancel.is_cancelled() {
+            self.suggest_missing_tests(repo, endpoints).await;
+        }
+
+        if run_dead_code && !self.cancel.is_cancelled() {
+            self.detect_dead_code(
+                repo,
+                &projects,
+                &file_filter,
+                temp_repo_path,
+                original_repo_path,
+                endpoints,
+            )
+            .await;
+        }
+
         // temp_dir is dropped here, cleaning up the temp copy
         tracing::debug!("Cleaning up temp directory for {}", repo.name);
         drop(temp_dir);
@@ -993,79 +2199,866 @@ impl Daemon {
         Ok(any_changed)
     }
 
-    /// Run code understanding analysis on files (for File Analysis tab)
-    async fn run_code_understanding_analysis(
-        &self,
-        repo: &crate::db::Repository,
-        file_data: &[(PathBuf, String, String, Language)],
-        endpoints: &[OllamaEndpoint],
-    ) -> anyhow::Result<bool> {
-        let (tx, rx) = mpsc::channel::<AnalysisTask>(100);
-        let rx = Arc::new(TokioMutex::new(rx));
-
-        let mut worker_handles = Vec::new();
-        for endpoint in endpoints {
+    /// Ask for test cases that would kill the repository's surviving mutations.
+    /// At most [`TEST_GAPS_PER_SCAN`] mutations are handled per scan; mutations of
+    /// files that changed since they were tested are skipped.
+    async fn suggest_missing_tests(&self, repo: &Repository, endpoints: &[OllamaEndpoint]) {
+        let survivors = match self
+            .db
+            .get_survivors_without_suggestions(repo.id, TEST_GAPS_PER_SCAN)
+            .await
+        {
+            Ok(survivors) => survivors,
+            Err(e) => {
+                tracing::warn!("Failed to load surviving mutations of {}: {}", repo.name, e);
+                return;
+            }
+        };
+        if survivors.is_empty() {
+            return;
+        }
+        let output_language = self.config.read().await.general.output_language.clone();
+        if let Err(e) = self
+            .set_task(
+                "processing",
+                Some(&format!("suggesting tests for {}", repo.name)),
+            )
+            .await
+        {
+            tracing::warn!("Failed to update daemon status: {}", e);
+        }
+
+        let analysis_type = AnalysisType::TestGaps.to_string();
+        let mut suggested = 0;
+        for mutation in survivors {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+            let content = match read_source(Path::new(&mutation.file_path)).await {
+                Ok(SourceText::Text(content)) => content,
+                _ => continue,
+            };
+            if mutation
+                .content_hash
+                .as_deref()
+                .is_some_and(|hash| hash != compute_hash(&content))
+            {
+                continue;
+            }
+            let analysis = self
+                .db
+                .get_latest_file_result(
+                    repo.id,
+                    &mutation.file_path,
+                    &AnalysisType::CodeUnderstanding.to_string(),
+                )
+                .await
+                .ok()
+                .flatten()
+                .map(|result| result.result);
+
+            let Some((client, endpoint_name)) =
+                find_available_endpoint(endpoints, &self.health, &self.cancel).await
+            else {
+                tracing::debug!("No endpoint available for test suggestions");
+                break;
+            };
+            let relative_path = Path::new(&mutation.file_path)
+                .strip_prefix(&repo.path)
+                .unwrap_or(Path::new(&mutation.file_path))
+                .to_string_lossy()
+                .to_string();
+            let prompt = test_gaps::test_gaps_prompt(
+                &relative_path,
+                &content,
+                &mutation,
+                analysis.as_deref(),
+                &output_language,
+            );
+            let response = client
+                .generate_structured::<TestGapsResponse>(&prompt, test_gaps::test_gaps_schema())
+                .await;
+            record_usage(&self.db, &client, &endpoint_name, repo.id, &analysis_type).await;
+
+            let suggestions = match response {
+                Ok(response) => test_gaps::normalize_suggestions(response.tests),
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to suggest tests for mutation {} of {}: {}",
+                        mutation.id,
+                        relative_path,
+                        e
+                    );
+                    continue;
+                }
+            };
+            match self
+                .db
+                .save_test_suggestions(mutation.id, repo.id, &suggestions)
+                .await
+            {
+                Ok(()) => suggested += usize::from(!suggestions.is_empty()),
+                Err(e) => tracing::warn!("Failed to save test suggestions: {}", e),
+            }
+        }
+        if suggested > 0 {
+            tracing::info!(
+                "Suggested tests for {} surviving mutation(s) in {}",
+                suggested,
+                repo.name
+            );
+        }
+    }
+
+    /// Find code copied across the repository's source files and replace the
+    /// stored duplicate clusters
+    async fn detect_duplicates(
+        &self,
+        repo: &Repository,
+        file_data: &[(PathBuf, String, String, Language)],
+    ) {
+        let files: Vec<(String, &str)> = file_data
+            .iter()
+            .map(|(path, content, ..)| (path.to_string_lossy().to_string(), content.as_str()))
+            .collect();
+        let clusters = duplication::detect(&files);
+        match self.db.replace_duplicate_clusters(repo.id, &clusters).await {
+            Ok(()) => tracing::info!(
+                "Found {} duplicated block(s) in {}",
+                clusters.len(),
+                repo.name
+            ),
+            Err(e) => tracing::warn!("Failed to save duplicates for {}: {}", repo.name, e),
+        }
+    }
+
+    /// Find unused dependencies and exports, have a model confirm them and
+    /// store them as findings per file. Files whose content and candidates are
+    /// unchanged since their last result are skipped; files that no longer
+    /// have candidates get a clean result, resolving their old findings.
+    async fn detect_dead_code(
+        &self,
+        repo: &Repository,
+        projects: &[Project],
+        file_filter: &FileFilter,
+        temp_repo_path: &Path,
+        original_repo_path: &Path,
+        endpoints: &[OllamaEndpoint],
+    ) {
+        let output_language = self.config.read().await.general.output_language.clone();
+        let scanned = projects.to_vec();
+        let candidates =
+            match tokio::task::spawn_blocking(move || dead_code::find_candidates(&scanned)).await {
+                Ok(candidates) => candidates,
+                Err(e) => {
+                    tracing::warn!("Dead code detection panicked for {}: {}", repo.name, e);
+                    return;
+                }
+            };
+
+        // Candidates by the original path of the file declaring them
+        let mut by_file: Vec<(PathBuf, PathBuf, Vec<dead_code::Candidate>)> = Vec::new();
+        for candidate in candidates {
+            let relative = candidate
+                .file
+                .strip_prefix(temp_repo_path)
+                .unwrap_or(&candidate.file)
+                .to_path_buf();
+            if candidate.kind == dead_code::CandidateKind::Export && !file_filter.matches(&relative)
+            {
+                continue;
+            }
+            match by_file.last_mut() {
+                Some((temp_path, _, file_candidates)) if *temp_path == candidate.file => {
+                    if file_candidates.len() < dead_code::MAX_CANDIDATES_PER_FILE {
+                        file_candidates.push(candidate);
+                    }
+                }
+                _ => by_file.push((
+                    candidate.file.clone(),
+                    translate_temp_to_original(temp_repo_path, original_repo_path, &candidate.file),
+                    vec![candidate],
+                )),
+            }
+        }
+
+        let analysis_type = AnalysisType::DeadCode.to_string();
+        let previous = match self
+            .db
+            .get_repository_results(repo.id, &analysis_type)
+            .await
+        {
+            Ok(previous) => previous,
+            Err(e) => {
+                tracing::warn!("Failed to load dead code results of {}: {}", repo.name, e);
+                return;
+            }
+        };
+
+        if !by_file.is_empty() {
+            if let Err(e) = self
+                .set_task(
+                    "processing",
+                    Some(&format!("looking for dead code in {}", repo.name)),
+                )
+                .await
+            {
+                tracing::warn!("Failed to update daemon status: {}", e);
+            }
+        }
+
+        let mut found = 0;
+        for (temp_path, original_path, candidates) in &by_file {
+            if self.cancel.is_cancelled() {
+                return;
+            }
+            let Ok(content) = tokio::fs::read_to_string(temp_path).await else {
+                continue;
+            };
+            let file_path = original_path.to_string_lossy().to_string();
+            let names: Vec<&str> = candidates.iter().map(|c| c.name.as_str()).collect();
+            let hash = compute_hash(&format!("{}\0{}", content, names.join("\0")));
+            if let Some(last) = previous.iter().find(|r| r.file_path == file_path) {
+                if last.content_hash.as_deref() == Some(hash.as_str()) {
+                    found += usize::from(last.severity.is_some());
+                    continue;
+                }
+            }
+
+            let started = Instant::now();
+            let mut attribution = Attribution::default();
+            let mut verdicts = None;
+            if let Some((client, endpoint_name)) =
+                find_available_endpoint(endpoints, &self.health, &self.cancel).await
+            {
+                let relative_path = temp_path
+                    .strip_prefix(temp_repo_path)
+                    .unwrap_or(temp_path)
+                    .to_string_lossy()
+                    .to_string();
+                let prompt = dead_code::review_prompt(
+                    &relative_path,
+                    &content,
+                    candidates,
+                    &output_language,
+                );
+                let response = client
+                    .generate_structured::<ReviewResponse>(&prompt, dead_code::review_schema())
+                    .await;
+                record_usage(&self.db, &client, &endpoint_name, repo.id, &analysis_type).await;
+                match response {
+                    Ok(response) => {
+                        verdicts = Some(response.candidates);
+                        if let Some(endpoint) = endpoints.iter().find(|e| e.name == endpoint_name) {
+                            attribution = llm_attribution(endpoint, started);
+                        }
+                    }
+                    Err(e) => tracing::warn!(
+                        "Failed to review dead code candidates of {}: {}",
+                        relative_path,
+                        e
+                    ),
+                }
+            }
+
+            let findings = dead_code::findings(candidates, verdicts.as_deref());
+            let severity = (!findings.is_empty()).then(|| findings::overall_severity(&findings));
+            found += usize::from(severity.is_some());
+            self.save_dead_code(
+                repo,
+                &file_path,
+                &content,
+                &dead_code::render(&findings, verdicts.is_some()),
+                severity,
+                &hash,
+                &attribution,
+                &findings,
+            )
+            .await;
+        }
+
+        // Files that had dead code but no longer have candidates
+        for last in previous.iter().filter(|r| r.severity.is_some()) {
+            if self.cancel.is_cancelled() {
+                return;
+            }
+            if by_file
+                .iter()
+                .any(|(_, original, _)| original.to_string_lossy() == last.file_path)
+            {
+                continue;
+            }
+            let content = tokio::fs::read_to_string(&last.file_path)
+                .await
+                .unwrap_or_default();
+            self.save_dead_code(
+                repo,
+                &last.file_path,
+                &content,
+                &dead_code::render(&[], true),
+                None,
+                &compute_hash(&content),
+                &Attribution::default(),
+                &[],
+            )
+            .await;
+        }
+
+        tracing::info!("Found dead code in {} file(s) of {}", found, repo.name);
+    }
+
+    /// Save a file's dead code result and its findings
+    #[allow(clippy::too_many_arguments)]
+    async fn save_dead_code(
+        &self,
+        repo: &Repository,
+        file_path: &str,
+        content: &str,
+        report: &str,
+        severity: Option<&str>,
+        hash: &str,
+        attribution: &Attribution,
+        findings: &[StructuredFinding],
+    ) {
+        let saved = self
+            .db
+            .save_analysis_result(
+                repo.id,
+                file_path,
+                &AnalysisType::DeadCode.to_string(),
+                report,
+                severity,
+                Some(hash),
+                attribution,
+            )
+            .await;
+        let saved = match saved {
+            Ok(result_id) => {
+                self.db
+                    .save_findings(result_id, repo.id, file_path, content, findings)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = saved {
+            tracing::warn!("Failed to save dead code of {}: {}", file_path, e);
+        }
+    }
+
+    /// Embed the repository's new file summaries for semantic search
+    async fn index_embeddings(&self, repo: &Repository, endpoints: &[OllamaEndpoint]) {
+        let search = self.config.read().await.search.clone();
+        if !search.enabled {
+            return;
+        }
+        let Some(endpoint) = endpoints.iter().find(|e| self.health.is_allowed(&e.name)) else {
+            return;
+        };
+
+        let client = OllamaClient::new(&endpoint.url, &search.embedding_model)
+            .with_cancellation(self.cancel.clone());
+        match search::index_repository(&self.db, &client, repo.id, &search.embedding_model).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Embedded {} file summaries of {}", count, repo.name),
+            Err(e) => tracing::warn!("Failed to embed file summaries of {}: {}", repo.name, e),
+        }
+    }
+
+    /// Restrict the endpoints of a repository to those allowed to see its
+    /// content, recording the enforcement in the audit log for sensitive
+    /// repositories. Returns None if no endpoint may analyze the repository.
+    async fn route_endpoints(
+        &self,
+        repo: &Repository,
+        endpoints: &[OllamaEndpoint],
+    ) -> Option<Vec<OllamaEndpoint>> {
+        let routed = endpoints_for(repo, endpoints);
+        if repo.sensitive {
+            let detail = routing_audit_detail(endpoints, &routed);
+            if routed.is_empty() {
+                tracing::warn!(
+                    "Not analyzing sensitive repository {}: {}",
+                    repo.name,
+                    detail
+                );
+            } else {
+                tracing::info!("Sensitive repository {}: {}", repo.name, detail);
+            }
+            if let Err(e) = self
+                .db
+                .record_audit(Some(repo.id), AUDIT_SENSITIVE_ROUTING, &detail)
+                .await
+            {
+                tracing::warn!("Failed to record audit entry for {}: {}", repo.name, e);
+            }
+        }
+        (!routed.is_empty()).then_some(routed)
+    }
+
+    /// Read a source file for analysis and record its inventory status. Returns
+    /// the content and its hash, or None if the file should not be analyzed.
+    async fn load_source_file(
+        &self,
+        repository_id: i64,
+        file_path: &Path,
+        original_file_path: &Path,
+        language: Language,
+        file_filter: &FileFilter,
+        relative_path: &Path,
+    ) -> Option<(String, String)> {
+        let source = match read_source(file_path).await {
+            Ok(source) => source,
+            Err(e) => {
+                tracing::warn!("Failed to read file {:?}: {}", file_path, e);
+                return None;
+            }
+        };
+        let detected = match &source {
+            SourceText::Text(content) | SourceText::Lossy(content) => {
+                file_filter.detect(relative_path, content)
+            }
+            SourceText::Skipped(_) => None,
+        };
+        let source = match detected {
+            Some(detected) => SourceText::Skipped(detected.into()),
+            None => source,
+        };
+
+        if let Err(e) = self
+            .db
+            .set_file_status(
+                repository_id,
+                &original_file_path.to_string_lossy(),
+                source.status(),
+            )
+            .await
+        {
+            tracing::warn!("Failed to record status of {:?}: {}", file_path, e);
+        }
+
+        let content = match source {
+            SourceText::Text(content) => content,
+            SourceText::Lossy(content) => {
+                tracing::debug!("Decoded non-UTF-8 file lossily: {:?}", file_path);
+                content
+            }
+            SourceText::Skipped(reason) => {
+                tracing::debug!("Skipping {:?} file: {:?}", reason, file_path);
+                return None;
+            }
+        };
+
+        // Use language-specific size limits
+        if content.len() > language.max_file_size() || content.len() < language.min_file_size() {
+            tracing::debug!("Skipping file due to size: {:?}", file_path);
+            return None;
+        }
+
+        let content_hash = compute_hash(&content);
+        Some((content, content_hash))
+    }
+
+    /// Path of Noctum's data directory relative to `repo_path`, if it lies inside it
+    async fn data_dir_within(&self, repo_path: &Path) -> Option<String> {
+        let data_dir = self.config.read().await.data_dir().canonicalize().ok()?;
+        let repo_path = repo_path.canonicalize().ok()?;
+        let relative = data_dir.strip_prefix(&repo_path).ok()?;
+        Some(relative.to_string_lossy().to_string()).filter(|r| !r.is_empty())
+    }
+
+    /// Import the open issues of a repository with `[issues] enabled = true`, or
+    /// forget previously imported ones when it is disabled. On failure the last
+    /// imported issues are kept.
+    async fn refresh_issues(
+        &self,
+        repo: &crate::db::Repository,
+        repo_config: &RepoConfig,
+        repo_path: &Path,
+    ) {
+        let settings = &repo_config.issues;
+        if !settings.enabled {
+            if let Err(e) = self.db.replace_issues(repo.id, &[]).await {
+                tracing::warn!("Failed to clear issues of {}: {}", repo.name, e);
+            }
+            return;
+        }
+
+        let tracker = self.config.read().await.issues.clone();
+        let Some(source) = IssueSource::resolve(settings, &tracker, repo_path).await else {
+            tracing::warn!(
+                "Cannot import issues for {}: set [issues] provider and project in noctum.toml",
+                repo.name
+            );
+            return;
+        };
+
+        match IssueClient::new(tracker).fetch_open_issues(&source).await {
+            Ok(fetched) => {
+                tracing::info!(
+                    "Imported {} open issue(s) of {} for {}",
+                    fetched.len(),
+                    source.project,
+                    repo.name
+                );
+                if let Err(e) = self.db.replace_issues(repo.id, &fetched).await {
+                    tracing::warn!("Failed to save issues of {}: {}", repo.name, e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to import issues for {}: {:#}", repo.name, e),
+        }
+    }
+
+    /// Run WASM analyzer plugins on files (results shown in the File Analysis tab).
+    ///
+    /// Plugins are CPU-bound, so each invocation runs on the blocking thread pool.
+    async fn run_plugin_analysis(
+        &self,
+        repo: &crate::db::Repository,
+        file_data: &[(PathBuf, String, String, Language)],
+        original_repo_path: &Path,
+        host: &PluginHost,
+    ) -> anyhow::Result<bool> {
+        let mut changed = false;
+        // Results are saved in batches, so a cycle over many files doesn't hold
+        // up the web server with a write per file
+        let mut pending = Vec::new();
+
+        for plugin in host.plugins() {
+            let analysis_type = plugin.analysis_type();
+
+            for (file_path, content, content_hash, _) in file_data {
+                if self.cancel.is_cancelled() {
+                    self.save_plugin_results(repo.id, &mut pending).await?;
+                    return Ok(changed);
+                }
+
+                let file_path_str = file_path.to_string_lossy().to_string();
+
+                // Include the plugin hash so results are recomputed when the plugin changes
+                let hash = compute_hash(&format!("{}{}", content_hash, plugin.hash));
+                if freshness(&self.db, repo.id, &file_path_str, &analysis_type, &hash).await
+                    == Freshness::Unchanged
+                {
+                    continue;
+                }
+
+                let job_id = self
+                    .queue
+                    .enqueue(repo.id, &analysis_type, Some(&file_path_str))
+                    .await;
+                if !self.queue.claim(job_id, None).await {
+                    tracing::info!(
+                        "Skipping {} for {}: removed from the queue",
+                        analysis_type,
+                        file_path_str
+                    );
+                    continue;
+                }
+
+                // Plugins see the path relative to the repository root
+                let relative_path = file_path
+                    .strip_prefix(original_repo_path)
+                    .unwrap_or(file_path)
+                    .to_string_lossy()
+                    .to_string();
+                let task_host = host.clone();
+                let task_plugin = plugin.clone();
+                let task_content = content.clone();
+                let started = Instant::now();
+                let result = tokio::task::spawn_blocking(move || {
+                    task_host.analyze(&task_plugin, &relative_path, &task_content)
+                })
+                .await
+                .map_err(|e| anyhow::anyhow!("Plugin task panicked: {}", e))
+                .and_then(|result| result);
+
+                self.queue.finish(job_id, &result).await;
+
+                match result {
+                    Ok(findings) => {
+                        let severity = (!findings.is_empty()).then(|| overall_severity(&findings));
+                        pending.push(NewAnalysisResult {
+                            file_path: file_path_str.clone(),
+                            analysis_type: analysis_type.clone(),
+                            result: render_findings(&findings),
+                            severity: severity.map(str::to_string),
+                            content_hash: Some(hash),
+                            attribution: Attribution {
+                                duration_ms: Some(started.elapsed().as_millis() as i64),
+                                ..Attribution::default()
+                            },
+                        });
+                        if pending.len() >= RESULT_BATCH_SIZE {
+                            self.save_plugin_results(repo.id, &mut pending).await?;
+                        }
+                        changed = true;
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Plugin '{}' failed on {}: {:#}",
+                            plugin.name,
+                            file_path_str,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+
+        self.save_plugin_results(repo.id, &mut pending).await?;
+        Ok(changed)
+    }
+
+    /// Save a batch of plugin results and announce them on the dashboard
+    async fn save_plugin_results(
+        &self,
+        repository_id: i64,
+        pending: &mut Vec<NewAnalysisResult>,
+    ) -> anyhow::Result<()> {
+        self.db
+            .save_analysis_results(repository_id, pending)
+            .await?;
+        for result in pending.drain(..) {
+            self.progress.publish(DashboardUpdate::AnalysisResult {
+                repository_id,
+                file_path: result.file_path,
+                analysis_type: result.analysis_type,
+                severity: result.severity,
+            });
+        }
+        Ok(())
+    }
+
+    /// Spawn analysis workers on `endpoints`, split into pools for small and
+    /// large files by the routing policy
+    async fn spawn_workers(&self, endpoints: &[OllamaEndpoint]) -> WorkerPools {
+        let (routing, output_language) = {
+            let config = self.config.read().await;
+            (
+                config.routing.clone(),
+                config.general.output_language.clone(),
+            )
+        };
+        let (small_endpoints, large_endpoints) = routing::split_by_size(endpoints);
+
+        let mut handles = Vec::new();
+        let results =
+            ResultCollector::new(self.db.clone(), self.queue.clone(), self.progress.clone());
+        let small = self.spawn_pool(&small_endpoints, &output_language, &results, &mut handles);
+        // Without a split both get every endpoint, so one pool does
+        let large = (large_endpoints.len() < endpoints.len())
+            .then(|| self.spawn_pool(&large_endpoints, &output_language, &results, &mut handles));
+
+        WorkerPools {
+            small,
+            large,
+            routing,
+            handles,
+            results,
+        }
+    }
+
+    /// Spawn a worker per endpoint, sharing one task channel. Workers ask for
+    /// responses in `output_language` and hand their results to `results`.
+    fn spawn_pool(
+        &self,
+        endpoints: &[OllamaEndpoint],
+        output_language: &str,
+        results: &ResultCollector,
+        handles: &mut Vec<tokio::task::JoinHandle<()>>,
+    ) -> mpsc::Sender<AnalysisTask> {
+        let (tx, rx) = mpsc::channel::<AnalysisTask>(100);
+        let rx = Arc::new(TokioMutex::new(rx));
+
+        for endpoint in endpoints {
             let worker_rx = Arc::clone(&rx);
             let db = self.db.clone();
-            let should_stop = Arc::clone(&self.should_stop);
+            let queue = self.queue.clone();
+            let progress = self.progress.clone();
+            let health = self.health.clone();
+            let cancel = self.cancel.clone();
             let endpoint = endpoint.clone();
-
-            let handle =
-                tokio::spawn(
-                    async move { analysis_worker(endpoint, worker_rx, db, should_stop).await },
-                );
-            worker_handles.push(handle);
+            let output_language = output_language.to_string();
+            let results = results.clone();
+
+            handles.push(tokio::spawn(async move {
+                analysis_worker(
+                    endpoint,
+                    worker_rx,
+                    db,
+                    queue,
+                    progress,
+                    health,
+                    cancel,
+                    output_language,
+                    results,
+                )
+                .await
+            }));
         }
 
+        tx
+    }
+
+    /// Run code understanding analysis on files (for File Analysis tab).
+    ///
+    /// With `delta_analysis`, changed files whose previous version was snapshotted
+    /// are analyzed by updating the previous result from the diff. With
+    /// `[context]` enabled, prompts get the file's project context.
+    async fn run_code_understanding_analysis(
+        &self,
+        repo: &crate::db::Repository,
+        file_data: &[(PathBuf, String, String, Language)],
+        endpoints: &[OllamaEndpoint],
+        repo_config: &RepoConfig,
+    ) -> anyhow::Result<bool> {
+        let delta_analysis = repo_config.delta_analysis;
+        let file_filter = repo_config
+            .file_filter()
+            .with_context(|| format!("Invalid include/exclude globs for {}", repo.name))?;
+        let workers = self.spawn_workers(endpoints).await;
+
         let repository_id = repo.id;
         let mut tasks_sent = 0;
 
+        let issue_context: Option<Arc<str>> = match self.db.get_issues(repository_id).await {
+            Ok(known) if !known.is_empty() => Some(issues::prompt_context(&known).into()),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("Failed to load issues of {}: {}", repo.name, e);
+                None
+            }
+        };
+
+        let context_sources = if repo_config.context.enabled {
+            Some(self.context_sources(repo).await)
+        } else {
+            None
+        };
         for (file_path, content, content_hash, language) in file_data {
-            if self.should_stop.load(Ordering::SeqCst) {
+            if self.cancel.is_cancelled() {
                 break;
             }
 
             let file_path_str = file_path.to_string_lossy().to_string();
 
-            // Check if file has changed since last code understanding analysis
-            let existing_hash = self
-                .db
-                .get_latest_file_hash(
+            let task_type = AnalysisTaskType::CodeUnderstanding;
+            let previous = freshness(
+                &self.db,
+                repository_id,
+                &file_path_str,
+                &task_type.analysis_type(),
+                content_hash,
+            )
+            .await;
+            if previous == Freshness::Unchanged {
+                continue;
+            }
+
+            let delta = if delta_analysis && previous == Freshness::Changed {
+                self.delta_context(repository_id, &file_path_str, content)
+                    .await
+            } else {
+                None
+            };
+
+            let job_id = self
+                .queue
+                .enqueue(
                     repository_id,
-                    &file_path_str,
-                    &AnalysisType::CodeUnderstanding.to_string(),
+                    &task_type.analysis_type(),
+                    Some(&file_path_str),
                 )
-                .await
-                .unwrap_or(None);
-
-            if existing_hash.as_ref() == Some(content_hash) {
-                continue; // Skip unchanged file
-            }
+                .await;
 
             let task = AnalysisTask {
                 repository_id,
                 file_path: file_path.clone(),
                 content: content.clone(),
                 content_hash: content_hash.clone(),
-                task_type: AnalysisTaskType::CodeUnderstanding,
+                task_type,
                 language: *language,
+                job_id,
+                delta,
+                save_snapshot: delta_analysis,
+                issue_context: issue_context.clone(),
+                file_context: context_sources.as_ref().and_then(|sources| {
+                    sources
+                        .context_for(file_path, content, *language, |path| {
+                            file_filter.matches(path)
+                        })
+                        .render(repo_config.context.max_tokens)
+                }),
+                security: repo.runs("security"),
             };
 
-            if tx.send(task).await.is_err() {
+            if !workers.send(task).await {
                 break;
             }
             tasks_sent += 1;
         }
 
-        drop(tx);
+        workers.finish("Code understanding").await;
 
-        for handle in worker_handles {
-            if let Err(e) = handle.await {
-                tracing::warn!("Code understanding worker failed: {}", e);
+        Ok(tasks_sent > 0)
+    }
+
+    /// Load the architecture summary and architecture file analyses that file
+    /// contexts are gathered from
+    async fn context_sources(&self, repo: &crate::db::Repository) -> ContextSources {
+        let latest = |analysis_type: AnalysisType| async move {
+            match self
+                .db
+                .get_repository_results(repo.id, &analysis_type.to_string())
+                .await
+            {
+                Ok(results) => results,
+                Err(e) => {
+                    tracing::warn!("Failed to load context of {}: {}", repo.name, e);
+                    Vec::new()
+                }
             }
+        };
+        ContextSources {
+            root: PathBuf::from(&repo.path),
+            architecture: latest(AnalysisType::ArchitectureSummary)
+                .await
+                .into_iter()
+                .next()
+                .map(|result| result.result),
+            modules: latest(AnalysisType::ArchitectureFileAnalysis)
+                .await
+                .into_iter()
+                .map(|result| (result.file_path, result.result))
+                .collect(),
         }
+    }
 
-        Ok(tasks_sent > 0)
+    /// Build the delta context for a changed file from its latest code understanding
+    /// result and the snapshot of the content that result was produced from.
+    async fn delta_context(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        content: &str,
+    ) -> Option<DeltaContext> {
+        let analysis_type = AnalysisType::CodeUnderstanding.to_string();
+        let previous = self
+            .db
+            .get_latest_file_result(repository_id, file_path, &analysis_type)
+            .await
+            .ok()??;
+        let (snapshot_hash, snapshot) = self
+            .db
+            .get_file_snapshot(repository_id, file_path)
+            .await
+            .ok()??;
+
+        // The snapshot must be the content the previous result describes
+        if previous.content_hash.as_deref() != Some(snapshot_hash.as_str()) {
+            return None;
+        }
+        DeltaContext::new(file_path, &previous.result, &snapshot, content)
     }
 
     /// Run architecture-focused file analysis (for Architecture summary aggregation)
@@ -1075,70 +3068,63 @@ impl Daemon {
         file_data: &[(PathBuf, String, String, Language)],
         endpoints: &[OllamaEndpoint],
     ) -> anyhow::Result<bool> {
-        let (tx, rx) = mpsc::channel::<AnalysisTask>(100);
-        let rx = Arc::new(TokioMutex::new(rx));
-
-        let mut worker_handles = Vec::new();
-        for endpoint in endpoints {
-            let worker_rx = Arc::clone(&rx);
-            let db = self.db.clone();
-            let should_stop = Arc::clone(&self.should_stop);
-            let endpoint = endpoint.clone();
-
-            let handle =
-                tokio::spawn(
-                    async move { analysis_worker(endpoint, worker_rx, db, should_stop).await },
-                );
-            worker_handles.push(handle);
-        }
+        let workers = self.spawn_workers(endpoints).await;
 
         let repository_id = repo.id;
         let mut tasks_sent = 0;
 
         for (file_path, content, content_hash, language) in file_data {
-            if self.should_stop.load(Ordering::SeqCst) {
+            if self.cancel.is_cancelled() {
                 break;
             }
 
             let file_path_str = file_path.to_string_lossy().to_string();
 
-            // Check if file has changed since last architecture file analysis
-            let existing_hash = self
-                .db
-                .get_latest_file_hash(
-                    repository_id,
-                    &file_path_str,
-                    &AnalysisType::ArchitectureFileAnalysis.to_string(),
-                )
-                .await
-                .unwrap_or(None);
-
-            if existing_hash.as_ref() == Some(content_hash) {
+            let task_type = AnalysisTaskType::ArchitectureFileAnalysis;
+            if freshness(
+                &self.db,
+                repository_id,
+                &file_path_str,
+                &task_type.analysis_type(),
+                content_hash,
+            )
+            .await
+                == Freshness::Unchanged
+            {
                 continue;
             }
 
+            let job_id = self
+                .queue
+                .enqueue(
+                    repository_id,
+                    &task_type.analysis_type(),
+                    Some(&file_path_str),
+                )
+                .await;
+
             let task = AnalysisTask {
                 repository_id,
                 file_path: file_path.clone(),
                 content: content.clone(),
                 content_hash: content_hash.clone(),
-                task_type: AnalysisTaskType::ArchitectureFileAnalysis,
+                task_type,
                 language: *language,
+                job_id,
+                delta: None,
+                save_snapshot: false,
+                issue_context: None,
+                file_context: None,
+                security: repo.runs("security"),
             };
 
-            if tx.send(task).await.is_err() {
+            if !workers.send(task).await {
                 break;
             }
             tasks_sent += 1;
         }
 
-        drop(tx);
-
-        for handle in worker_handles {
-            if let Err(e) = handle.await {
-                tracing::warn!("Architecture file analysis worker failed: {}", e);
-            }
-        }
+        workers.finish("Architecture file analysis").await;
 
         Ok(tasks_sent > 0)
     }
@@ -1150,71 +3136,66 @@ impl Daemon {
         file_data: &[(PathBuf, String, String, Language)],
         endpoints: &[OllamaEndpoint],
     ) -> anyhow::Result<bool> {
-        let (tx, rx) = mpsc::channel::<AnalysisTask>(100);
-        let rx = Arc::new(TokioMutex::new(rx));
-
-        let mut worker_handles = Vec::new();
-        for endpoint in endpoints {
-            let worker_rx = Arc::clone(&rx);
-            let db = self.db.clone();
-            let should_stop = Arc::clone(&self.should_stop);
-            let endpoint = endpoint.clone();
-
-            let handle =
-                tokio::spawn(
-                    async move { analysis_worker(endpoint, worker_rx, db, should_stop).await },
-                );
-            worker_handles.push(handle);
-        }
+        let workers = self.spawn_workers(endpoints).await;
 
         let repository_id = repo.id;
         let mut tasks_sent = 0;
 
-        // For each diagram type, check if we need to extract for each file
-        for diagram_type in DiagramType::all() {
-            let analysis_type_str = format!("diagram_extraction_{}", diagram_type.as_str());
-
+        // For each extracted diagram type, check if we need to extract for each file
+        for diagram_type in DiagramType::all().iter().filter(|t| t.is_extracted()) {
             for (file_path, content, content_hash, language) in file_data {
-                if self.should_stop.load(Ordering::SeqCst) {
+                if self.cancel.is_cancelled() {
                     break;
                 }
 
                 let file_path_str = file_path.to_string_lossy().to_string();
 
-                // Check if file has changed since last extraction for this diagram type
-                let existing_hash = self
-                    .db
-                    .get_latest_file_hash(repository_id, &file_path_str, &analysis_type_str)
-                    .await
-                    .unwrap_or(None);
-
-                if existing_hash.as_ref() == Some(content_hash) {
+                let task_type = AnalysisTaskType::DiagramExtraction(*diagram_type);
+                if freshness(
+                    &self.db,
+                    repository_id,
+                    &file_path_str,
+                    &task_type.analysis_type(),
+                    content_hash,
+                )
+                .await
+                    == Freshness::Unchanged
+                {
                     continue;
                 }
 
+                let job_id = self
+                    .queue
+                    .enqueue(
+                        repository_id,
+                        &task_type.analysis_type(),
+                        Some(&file_path_str),
+                    )
+                    .await;
+
                 let task = AnalysisTask {
                     repository_id,
                     file_path: file_path.clone(),
                     content: content.clone(),
                     content_hash: content_hash.clone(),
-                    task_type: AnalysisTaskType::DiagramExtraction(*diagram_type),
+                    task_type,
                     language: *language,
+                    job_id,
+                    delta: None,
+                    save_snapshot: false,
+                    issue_context: None,
+                    file_context: None,
+                    security: repo.runs("security"),
                 };
 
-                if tx.send(task).await.is_err() {
+                if !workers.send(task).await {
                     break;
                 }
                 tasks_sent += 1;
             }
         }
 
-        drop(tx);
-
-        for handle in worker_handles {
-            if let Err(e) = handle.await {
-                tracing::warn!("Diagram extraction worker failed: {}", e);
-            }
-        }
+        workers.finish("Diagram extraction").await;
 
         Ok(tasks_sent > 0)
     }
@@ -1230,115 +3211,115 @@ impl Daemon {
             return Ok(false);
         }
 
-        let (tx, rx) = mpsc::channel::<AnalysisTask>(100);
-        let rx = Arc::new(TokioMutex::new(rx));
-
-        let mut worker_handles = Vec::new();
-        for endpoint in endpoints {
-            let worker_rx = Arc::clone(&rx);
-            let db = self.db.clone();
-            let should_stop = Arc::clone(&self.should_stop);
-            let endpoint = endpoint.clone();
-
-            let handle =
-                tokio::spawn(
-                    async move { analysis_worker(endpoint, worker_rx, db, should_stop).await },
-                );
-            worker_handles.push(handle);
-        }
+        let workers = self.spawn_workers(endpoints).await;
 
         let repository_id = repo.id;
         let mut tasks_sent = 0;
 
         for (file_path, content, content_hash, language) in context_file_data {
-            if self.should_stop.load(Ordering::SeqCst) {
+            if self.cancel.is_cancelled() {
                 break;
             }
 
             let file_path_str = file_path.to_string_lossy().to_string();
 
-            // Check if file has changed since last documentation analysis
-            let existing_hash = self
-                .db
-                .get_latest_file_hash(
-                    repository_id,
-                    &file_path_str,
-                    &AnalysisType::Documentation.to_string(),
-                )
-                .await
-                .unwrap_or(None);
-
-            if existing_hash.as_ref() == Some(content_hash) {
+            let task_type = AnalysisTaskType::DocumentationAnalysis;
+            if freshness(
+                &self.db,
+                repository_id,
+                &file_path_str,
+                &task_type.analysis_type(),
+                content_hash,
+            )
+            .await
+                == Freshness::Unchanged
+            {
                 continue;
             }
 
+            let job_id = self
+                .queue
+                .enqueue(
+                    repository_id,
+                    &task_type.analysis_type(),
+                    Some(&file_path_str),
+                )
+                .await;
+
             let task = AnalysisTask {
                 repository_id,
                 file_path: file_path.clone(),
                 content: content.clone(),
                 content_hash: content_hash.clone(),
-                task_type: AnalysisTaskType::DocumentationAnalysis,
+                task_type,
                 language: *language,
+                job_id,
+                delta: None,
+                save_snapshot: false,
+                issue_context: None,
+                file_context: None,
+                security: repo.runs("security"),
             };
 
-            if tx.send(task).await.is_err() {
+            if !workers.send(task).await {
                 break;
             }
             tasks_sent += 1;
         }
 
-        drop(tx);
-
-        for handle in worker_handles {
-            if let Err(e) = handle.await {
-                tracing::warn!("Documentation analysis worker failed: {}", e);
-            }
-        }
+        workers.finish("Documentation analysis").await;
 
         Ok(tasks_sent > 0)
     }
 
-    /// Generate D2 diagrams from extracted data
+    /// Generate D2 diagrams from extracted data. Diagrams whose sources are
+    /// unchanged are skipped, unless their type is in `requested`.
     async fn generate_diagrams(
         &self,
         repo: &crate::db::Repository,
         endpoints: &[OllamaEndpoint],
-        combined_hash: &str,
+        file_data: &[(PathBuf, String, String, Language)],
+        requested: &[String],
     ) -> anyhow::Result<()> {
         tracing::info!("Generating D2 diagrams for {}", repo.name);
-
-        self.db
-            .update_daemon_status(
-                "processing",
-                Some(&format!("generating diagrams for {}", repo.name)),
-            )
-            .await?;
+        let started_at = crate::db::now_millis();
+        let diagram_config = self.config.read().await.diagram.clone();
+        let renderer = DotRenderer::new(&diagram_config).await;
+        let format = diagram_config.format;
+
+        self.set_task(
+            "processing",
+            Some(&format!("generating diagrams for {}", repo.name)),
+        )
+        .await?;
 
         for diagram_type in DiagramType::all() {
-            if self.should_stop.load(Ordering::SeqCst) {
+            if self.cancel.is_cancelled() {
                 break;
             }
 
-            // Check if diagrams need regeneration based on combined hash
-            let existing_hash = self
-                .db
-                .get_latest_diagram_hash(repo.id, diagram_type.as_str())
+            let is_requested = requested.iter().any(|t| t == diagram_type.as_str());
+            let generated = if diagram_type.is_extracted() {
+                self.generate_single_diagram(
+                    repo,
+                    endpoints,
+                    &renderer,
+                    format,
+                    *diagram_type,
+                    is_requested,
+                )
                 .await
-                .unwrap_or(None);
-
-            if existing_hash.as_ref() == Some(&combined_hash.to_string()) {
-                tracing::debug!(
-                    "Skipping {} diagram for {} - no changes",
-                    diagram_type.title(),
-                    repo.name
-                );
-                continue;
-            }
-
-            if let Err(e) = self
-                .generate_single_diagram(repo, endpoints, *diagram_type, combined_hash)
+            } else {
+                self.generate_module_dependency_graph(
+                    repo,
+                    &renderer,
+                    format,
+                    file_data,
+                    is_requested,
+                )
                 .await
-            {
+            };
+            if let Err(e) = generated {
                 tracing::warn!(
                     "Failed to generate {} diagram for {}: {}",
                     diagram_type.title(),
@@ -1346,18 +3327,121 @@ impl Daemon {
                     e
                 );
             }
+            // Requests made during the generation are served by the next scan
+            if is_requested {
+                if let Err(e) = self
+                    .db
+                    .clear_diagram_request(repo.id, diagram_type.as_str(), &started_at)
+                    .await
+                {
+                    tracing::warn!("Failed to clear diagram request: {}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the latest diagram of `diagram_type` was generated from sources
+    /// with `source_hash`, in which case it needn't be regenerated
+    async fn diagram_is_current(
+        &self,
+        repo: &crate::db::Repository,
+        diagram_type: DiagramType,
+        source_hash: &str,
+    ) -> bool {
+        let existing_hash = self
+            .db
+            .get_latest_diagram_hash(repo.id, diagram_type.as_str())
+            .await
+            .unwrap_or(None);
+        let current = existing_hash.as_deref() == Some(source_hash);
+        if current {
+            tracing::debug!(
+                "Skipping {} diagram for {} - no changes",
+                diagram_type.title(),
+                repo.name
+            );
+        }
+        current
+    }
+
+    /// Build the module dependency graph from the Rust files' imports. It is
+    /// regenerated when a Rust file changes, or when `requested`.
+    async fn generate_module_dependency_graph(
+        &self,
+        repo: &crate::db::Repository,
+        renderer: &DotRenderer,
+        format: DiagramFormat,
+        file_data: &[(PathBuf, String, String, Language)],
+        requested: bool,
+    ) -> anyhow::Result<()> {
+        let diagram_type = DiagramType::ModuleDependencyGraph;
+        let rust_files: Vec<_> = file_data
+            .iter()
+            .filter(|(_, _, _, language)| *language == Language::Rust)
+            .collect();
+        let file_hashes: Vec<String> = rust_files
+            .iter()
+            .map(|(path, _, content_hash, _)| {
+                compute_hash(&format!("{}\0{}", path.display(), content_hash))
+            })
+            .collect();
+        let source_hash = diagram_source_hash(format, &file_hashes);
+        if !requested
+            && self
+                .diagram_is_current(repo, diagram_type, &source_hash)
+                .await
+        {
+            return Ok(());
         }
 
+        let rust_files = rust_files
+            .into_iter()
+            .map(|(path, content, _, _)| (path.as_path(), content.as_str()));
+        let code = match format {
+            DiagramFormat::Dot => module_dependency_dot(rust_files),
+            DiagramFormat::Mermaid => module_dependency_mermaid(rust_files),
+        };
+        let Some(code) = code else {
+            tracing::debug!("No module imports found for {}", repo.name);
+            return Ok(());
+        };
+
+        let svg_content = render_diagram(renderer, format, diagram_type, &code)
+            .await
+            .map_err(anyhow::Error::msg)?;
+        tracing::info!(
+            "Generated {} diagram for {}",
+            diagram_type.title(),
+            repo.name
+        );
+        self.db
+            .save_diagram(
+                repo.id,
+                diagram_type.as_str(),
+                diagram_type.title(),
+                diagram_type.description(),
+                format.as_str(),
+                &code,
+                &svg_content,
+                Some(&source_hash),
+            )
+            .await?;
         Ok(())
     }
 
-    /// Generate a single D2 diagram with retry logic for syntax errors
+    /// Generate a single D2 diagram with retry logic for syntax errors. Only
+    /// the extractions of changed files are redone before this, and the
+    /// diagram is regenerated only if one of them changed, or when `requested`.
     async fn generate_single_diagram(
         &self,
         repo: &crate::db::Repository,
         endpoints: &[OllamaEndpoint],
+        renderer: &DotRenderer,
+        format: DiagramFormat,
         diagram_type: DiagramType,
-        combined_hash: &str,
+        requested: bool,
     ) -> anyhow::Result<()> {
         let analysis_type_str = format!("diagram_extraction_{}", diagram_type.as_str());
 
@@ -1378,6 +3462,7 @@ impl Daemon {
 
         // Build aggregated extractions, filtering out deleted files and empty results
         let mut extractions = String::new();
+        let mut extraction_hashes = Vec::new();
         let mut included_count = 0;
         for result in &results {
             let file_path = std::path::Path::new(&result.file_path);
@@ -1393,6 +3478,10 @@ impl Daemon {
                 continue;
             }
             extractions.push_str(&format!("\n## {}\n{}\n", result.file_path, result.result));
+            extraction_hashes.push(compute_hash(&format!(
+                "{}\0{}",
+                result.file_path, result.result
+            )));
             included_count += 1;
         }
 
@@ -1405,6 +3494,16 @@ impl Daemon {
             return Ok(());
         }
 
+        // A changed file whose extraction came out the same doesn't change the diagram
+        let source_hash = diagram_source_hash(format, &extraction_hashes);
+        if !requested
+            && self
+                .diagram_is_current(repo, diagram_type, &source_hash)
+                .await
+        {
+            return Ok(());
+        }
+
         // Truncate if too long
         let truncated = if extractions.len() > 50000 {
             format!(
@@ -1417,9 +3516,27 @@ impl Daemon {
         };
 
         // Generate the diagram with retry logic
-        let prompt = DiagramGenerator::prompt_for_type(diagram_type, &repo.name, &truncated);
+        let output_language = self.config.read().await.general.output_language.clone();
+        let prompt = match format {
+            DiagramFormat::Dot => DiagramGenerator::prompt_for_type(
+                diagram_type,
+                &repo.name,
+                &truncated,
+                &output_language,
+            ),
+            DiagramFormat::Mermaid => MermaidGenerator::prompt_for_type(
+                diagram_type,
+                &repo.name,
+                &truncated,
+                &output_language,
+            ),
+        };
+        let Some(prompt) = prompt else {
+            return Ok(());
+        };
 
         let mut dot_code: Option<String> = None;
+        let mut svg_content: Option<String> = None;
         let mut last_error: Option<String> = None;
 
         for attempt in 0..=DOT_MAX_RETRIES {
@@ -1427,32 +3544,52 @@ impl Daemon {
                 prompt.clone()
             } else {
                 // Use fix prompt for retries
-                DiagramGenerator::fix_dot_prompt(
-                    dot_code.as_deref().unwrap_or(""),
-                    last_error.as_deref().unwrap_or("Unknown error"),
-                )
+                let code = dot_code.as_deref().unwrap_or("");
+                let error = last_error.as_deref().unwrap_or("Unknown error");
+                match format {
+                    DiagramFormat::Dot => DiagramGenerator::fix_dot_prompt(code, error),
+                    DiagramFormat::Mermaid => MermaidGenerator::fix_mermaid_prompt(code, error),
+                }
             };
 
             // Try each endpoint
             for endpoint in endpoints {
-                let client = OllamaClient::new(&endpoint.url, &endpoint.model);
+                let client =
+                    OllamaClient::for_endpoint(endpoint).with_cancellation(self.cancel.clone());
 
-                if !client.is_available().await {
+                if !self.health.is_allowed(&endpoint.name) || !client.is_available().await {
                     continue;
                 }
 
-                match client.generate(&current_prompt).await {
+                let generated = client.generate(&current_prompt).await;
+                record_usage(
+                    &self.db,
+                    &client,
+                    &endpoint.name,
+                    repo.id,
+                    "diagram_generation",
+                )
+                .await;
+                match generated {
                     Ok(raw_output) => {
-                        let cleaned = clean_dot_output(&raw_output);
-
-                        match validate_dot_syntax(&cleaned) {
-                            Ok(()) => {
+                        self.health.record_success(&endpoint.name);
+                        let cleaned = match format {
+                            DiagramFormat::Dot => clean_dot_output(&raw_output),
+                            DiagramFormat::Mermaid => clean_mermaid_output(&raw_output),
+                        };
+
+                        // Rendering validates DOT with the renderer that will draw it
+                        match render_diagram(renderer, format, diagram_type, &cleaned).await {
+                            Ok(svg) => {
                                 dot_code = Some(cleaned);
+                                svg_content = Some(svg);
+                                last_error = None;
                                 break;
                             }
                             Err(e) => {
                                 tracing::debug!(
-                                    "DOT validation failed for {} (attempt {}): {}",
+                                    "{} validation failed for {} (attempt {}): {}",
+                                    format.as_str(),
                                     diagram_type.title(),
                                     attempt + 1,
                                     e
@@ -1469,6 +3606,7 @@ impl Daemon {
                             diagram_type.title(),
                             e
                         );
+                        self.health.record_failure(&endpoint.name, &e.to_string());
                     }
                 }
             }
@@ -1487,25 +3625,11 @@ impl Daemon {
                     DOT_MAX_RETRIES + 1
                 );
             }
-        }
-
-        // Render and save diagram if we got valid DOT
-        match (dot_code, last_error) {
-            (Some(code), None) => {
-                // Render DOT to SVG
-                let svg_content = match render_dot_to_svg(&code) {
-                    Ok(svg) => svg,
-                    Err(e) => {
-                        tracing::warn!(
-                            "Failed to render {} diagram to SVG for {}: {}",
-                            diagram_type.title(),
-                            repo.name,
-                            e
-                        );
-                        return Ok(());
-                    }
-                };
+        }
 
+        // Save the diagram if we got DOT that rendered
+        match (dot_code, svg_content, last_error) {
+            (Some(code), Some(svg_content), _) => {
                 tracing::info!(
                     "Generated {} diagram for {}",
                     diagram_type.title(),
@@ -1518,13 +3642,14 @@ impl Daemon {
                         diagram_type.as_str(),
                         diagram_type.title(),
                         diagram_type.description(),
+                        format.as_str(),
                         &code,
                         &svg_content,
-                        Some(combined_hash),
+                        Some(&source_hash),
                     )
                     .await?;
             }
-            (Some(_), Some(e)) => {
+            (Some(_), None, Some(e)) => {
                 tracing::warn!(
                     "Failed to generate valid {} diagram for {} after {} retries: {}",
                     diagram_type.title(),
@@ -1533,7 +3658,7 @@ impl Daemon {
                     e
                 );
             }
-            (None, _) => {
+            _ => {
                 tracing::warn!(
                     "No endpoints available for {} diagram generation",
                     diagram_type.title()
@@ -1551,9 +3676,9 @@ impl Daemon {
         endpoints: &[OllamaEndpoint],
     ) -> anyhow::Result<()> {
         tracing::info!("Generating architecture summary for {}", repo.name);
+        let output_language = self.config.read().await.general.output_language.clone();
 
-        self.db
-            .update_daemon_status("processing", Some(&format!("summarizing {}", repo.name)))
+        self.set_task("processing", Some(&format!("summarizing {}", repo.name)))
             .await?;
 
         // Get documentation analysis results first (READMEs, Cargo.toml, etc.)
@@ -1588,9 +3713,8 @@ impl Daemon {
             doc_context.push_str(&format!("\n## {}\n{}\n", result.file_path, result.result));
         }
 
-        // Build a summary of all code file analyses, filtering out deleted files
-        let mut file_summaries = String::new();
-        let mut included_count = 0;
+        // Collect the code file analyses, filtering out deleted files
+        let mut analyses = Vec::new();
         for result in &results {
             // Skip results for files that no longer exist
             let file_path = std::path::Path::new(&result.file_path);
@@ -1598,25 +3722,18 @@ impl Daemon {
                 tracing::debug!("Skipping deleted file in summary: {}", result.file_path);
                 continue;
             }
-            file_summaries.push_str(&format!("\n## {}\n{}\n", result.file_path, result.result));
-            included_count += 1;
+            analyses.push((result.file_path.clone(), result.result.clone()));
         }
 
-        if included_count == 0 && doc_context.is_empty() {
+        if analyses.is_empty() && doc_context.is_empty() {
             tracing::debug!("No existing files to summarize for {}", repo.name);
             return Ok(());
         }
 
-        // Truncate code summaries if too long (keep under ~45k chars to leave room for docs)
-        let truncated_code = if file_summaries.len() > 45000 {
-            format!(
-                "{}...\n\n(truncated, {} files total)",
-                truncate_at_char_boundary(&file_summaries, 45000),
-                included_count
-            )
-        } else {
-            file_summaries
-        };
+        // Condense the code analyses until they fit (leaving room for docs)
+        let code_section = self
+            .condense_file_analyses(repo, endpoints, &analyses)
+            .await?;
 
         // Truncate doc context if needed (keep under ~5k chars)
         let truncated_docs = if doc_context.len() > 5000 {
@@ -1639,11 +3756,19 @@ impl Daemon {
             String::new()
         };
 
+        let issue_section = match self.db.get_issues(repo.id).await {
+            Ok(known) => issues::prompt_context(&known),
+            Err(e) => {
+                tracing::warn!("Failed to load issues of {}: {}", repo.name, e);
+                String::new()
+            }
+        };
+
         let prompt = format!(
             "You are analyzing a Rust codebase called '{}'.\n\n\
-             {}\
+             {}{}\
              # Code Architecture Analyses\n\
-             Below are architecture-focused analyses of individual source files:\n{}\n\n\
+             {}\n\n\
              Based on ALL the information above (documentation AND code analyses), \
              provide a high-level architectural overview including:\n\
              1. **Purpose**: What is this project/application about?\n\
@@ -1652,59 +3777,346 @@ impl Daemon {
              4. **Data Flow**: How does data flow through the system?\n\
              5. **Dependencies**: What external dependencies or integrations exist?\n\
              6. **Suggestions**: Any architectural improvements or concerns?\n\n\
-             IMPORTANT: Respond only in English (or code)",
-            repo.name, doc_section, truncated_code
+             {}",
+            repo.name,
+            doc_section,
+            issue_section,
+            code_section,
+            respond_in(&output_language, true)
         );
 
-        // Try each endpoint until one succeeds
+        let Some((summary, endpoint, started)) = self
+            .generate_summary_step(repo, endpoints, &prompt, "architecture summary")
+            .await
+        else {
+            tracing::warn!(
+                "All endpoints failed for architecture summary of {}",
+                repo.name
+            );
+            return Ok(());
+        };
+
+        // Save the summary
+        self.db
+            .save_analysis_result(
+                repo.id,
+                &format!("[{}] Architecture Summary", repo.name),
+                &AnalysisType::ArchitectureSummary.to_string(),
+                &summary,
+                Some("info"),
+                None, // No content hash for architecture summaries
+                &llm_attribution(endpoint, started),
+            )
+            .await?;
+        self.progress.publish(DashboardUpdate::AnalysisResult {
+            repository_id: repo.id,
+            file_path: format!("[{}] Architecture Summary", repo.name),
+            analysis_type: AnalysisType::ArchitectureSummary.to_string(),
+            severity: Some("info".to_string()),
+        });
+
+        if let Err(e) = self
+            .generate_architecture_model(repo, endpoints, &summary, &code_section)
+            .await
+        {
+            tracing::warn!(
+                "Failed to generate architecture model for {}: {}",
+                repo.name,
+                e
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Generate the structured architecture model of a repository from its
+    /// architecture summary, trying each endpoint until one returns a valid model
+    async fn generate_architecture_model(
+        &self,
+        repo: &crate::db::Repository,
+        endpoints: &[OllamaEndpoint],
+        summary: &str,
+        code_section: &str,
+    ) -> anyhow::Result<()> {
+        let output_language = self.config.read().await.general.output_language.clone();
+        let prompt = architecture::architecture_model_prompt(
+            &repo.name,
+            summary,
+            code_section,
+            &output_language,
+        );
         for endpoint in endpoints {
-            let client = OllamaClient::new(&endpoint.url, &endpoint.model);
+            if self.cancel.is_cancelled() {
+                anyhow::bail!("Cancelled while generating the architecture model");
+            }
+            let client =
+                OllamaClient::for_endpoint(endpoint).with_cancellation(self.cancel.clone());
+            if !self.health.is_allowed(&endpoint.name) || !client.is_available().await {
+                continue;
+            }
+
+            let started = Instant::now();
+            let generated = client
+                .generate_structured::<ArchitectureModel>(
+                    &prompt,
+                    architecture::architecture_schema(),
+                )
+                .await;
+            record_usage(
+                &self.db,
+                &client,
+                &endpoint.name,
+                repo.id,
+                &AnalysisType::ArchitectureSummary.to_string(),
+            )
+            .await;
+            let model = match generated
+                .and_then(|model| architecture::validate_model(model).map_err(anyhow::Error::msg))
+            {
+                Ok(model) => model,
+                Err(e) => {
+                    tracing::warn!(
+                        "Endpoint {} returned no usable architecture model for {}: {}",
+                        endpoint.name,
+                        repo.name,
+                        e
+                    );
+                    continue;
+                }
+            };
+            self.db
+                .save_architecture_model(repo.id, &model, &llm_attribution(endpoint, started))
+                .await?;
+            tracing::info!(
+                "Generated architecture model of {} with {} components",
+                repo.name,
+                model.components.len()
+            );
+            return Ok(());
+        }
+        anyhow::bail!("All endpoints failed")
+    }
+
+    /// The code section of the architecture summary prompt. Analyses that don't
+    /// fit into one prompt are summarized per group of directories, and those
+    /// summaries again until they fit (see the `summary` module).
+    async fn condense_file_analyses(
+        &self,
+        repo: &crate::db::Repository,
+        endpoints: &[OllamaEndpoint],
+        analyses: &[(String, String)],
+    ) -> anyhow::Result<String> {
+        let output_language = self.config.read().await.general.output_language.clone();
+        let sections = summary::file_sections(Path::new(&repo.path), analyses);
+        let joined = |sections: &[summary::Section]| {
+            sections.iter().map(|s| s.text.as_str()).collect::<String>()
+        };
+        if summary::total_len(&sections) <= summary::CHUNK_CHARS {
+            return Ok(format!(
+                "Below are architecture-focused analyses of individual source files:\n{}",
+                joined(&sections)
+            ));
+        }
+
+        // Map: summarize the analyses of each group of directories
+        let chunks = summary::pack(&sections, summary::CHUNK_CHARS);
+        tracing::info!(
+            "Analyses of {} files in {} exceed one prompt, summarizing them in {} groups",
+            analyses.len(),
+            repo.name,
+            chunks.len()
+        );
+        let mut summaries = Vec::with_capacity(chunks.len());
+        for chunk in &chunks {
+            if self.cancel.is_cancelled() {
+                anyhow::bail!("Cancelled while summarizing {}", repo.name);
+            }
+            let prompt = summary::module_summary_prompt(&repo.name, chunk, &output_language);
+            let Some((text, ..)) = self
+                .generate_summary_step(repo, endpoints, &prompt, &chunk.name())
+                .await
+            else {
+                anyhow::bail!("All endpoints failed to summarize {}", chunk.name());
+            };
+            summaries.push(summary::summary_section(chunk, &text));
+        }
+
+        // Reduce: combine the summaries until they fit
+        let mut round = 0;
+        while summary::total_len(&summaries) > summary::CHUNK_CHARS
+            && round < summary::MAX_REDUCE_ROUNDS
+        {
+            round += 1;
+            let mut combined = Vec::new();
+            for chunk in summary::pack(&summaries, summary::CHUNK_CHARS) {
+                if self.cancel.is_cancelled() {
+                    anyhow::bail!("Cancelled while summarizing {}", repo.name);
+                }
+                let prompt =
+                    summary::combine_summaries_prompt(&repo.name, &chunk, &output_language);
+                let Some((text, ..)) = self
+                    .generate_summary_step(repo, endpoints, &prompt, &chunk.name())
+                    .await
+                else {
+                    anyhow::bail!(
+                        "All endpoints failed to combine summaries of {}",
+                        chunk.name()
+                    );
+                };
+                combined.push(summary::summary_section(&chunk, &text));
+            }
+            summaries = combined;
+        }
+
+        Ok(format!(
+            "Below are architecture summaries of the project's modules, condensed from \
+             analyses of {} source files:\n{}",
+            analyses.len(),
+            truncate_at_char_boundary(&joined(&summaries), summary::CHUNK_CHARS)
+        ))
+    }
 
-            if !client.is_available().await {
+    /// Generate one step of an architecture summary, trying each endpoint until
+    /// one succeeds. Returns the response, its endpoint and when it started.
+    async fn generate_summary_step<'a>(
+        &self,
+        repo: &crate::db::Repository,
+        endpoints: &'a [OllamaEndpoint],
+        prompt: &str,
+        step: &str,
+    ) -> Option<(String, &'a OllamaEndpoint, Instant)> {
+        // Summaries go to the largest models first
+        for endpoint in routing::by_capacity(endpoints) {
+            let client =
+                OllamaClient::for_endpoint(endpoint).with_cancellation(self.cancel.clone());
+
+            if !self.health.is_allowed(&endpoint.name) || !client.is_available().await {
                 tracing::debug!(
-                    "Endpoint {} not available for architecture summary, trying next",
-                    endpoint.name
+                    "Endpoint {} not available for {}, trying next",
+                    endpoint.name,
+                    step
                 );
                 continue;
             }
 
-            match client.generate(&prompt).await {
-                Ok(summary) => {
+            let started = Instant::now();
+            let generated = client.generate(prompt).await;
+            record_usage(
+                &self.db,
+                &client,
+                &endpoint.name,
+                repo.id,
+                &AnalysisType::ArchitectureSummary.to_string(),
+            )
+            .await;
+            match generated {
+                Ok(text) => {
+                    self.health.record_success(&endpoint.name);
                     tracing::info!(
-                        "Generated architecture summary for {} using endpoint {}",
+                        "Generated {} for {} using endpoint {}",
+                        step,
                         repo.name,
                         endpoint.name
                     );
-
-                    // Save the summary
-                    self.db
-                        .save_analysis_result(
-                            repo.id,
-                            &format!("[{}] Architecture Summary", repo.name),
-                            &AnalysisType::ArchitectureSummary.to_string(),
-                            &summary,
-                            Some("info"),
-                            None, // No content hash for architecture summaries
-                        )
-                        .await?;
-
-                    return Ok(());
+                    return Some((text, endpoint, started));
                 }
                 Err(e) => {
                     tracing::warn!(
-                        "Endpoint {} failed for architecture summary: {}, trying next",
+                        "Endpoint {} failed for {}: {}, trying next",
                         endpoint.name,
+                        step,
                         e
                     );
+                    self.health.record_failure(&endpoint.name, &e.to_string());
                 }
             }
         }
+        None
+    }
 
-        tracing::warn!(
-            "All endpoints failed for architecture summary of {}",
-            repo.name
-        );
-        Ok(())
+    /// Install dependencies for every JavaScript/TypeScript project with a lockfile,
+    /// restoring from the dependency cache when the lockfile is unchanged.
+    ///
+    /// Returns false if an install failed, in which case mutation testing is skipped.
+    async fn install_dependencies(
+        &self,
+        repo_name: &str,
+        temp_repo_path: &Path,
+    ) -> anyhow::Result<bool> {
+        let cache = DependencyCache::new(self.config.read().await.dependency_cache_dir());
+
+        for project in discover_projects(temp_repo_path)? {
+            // Already present when `node_modules` is not in `copy_ignore`
+            if project.language != Language::TypeScript
+                || project.root.join("node_modules").exists()
+            {
+                continue;
+            }
+            let Some(manager) = PackageManager::detect(&project.root) else {
+                tracing::debug!(
+                    "No lockfile in {}, skipping dependency install",
+                    project.root.display()
+                );
+                continue;
+            };
+
+            let key = DependencyCache::key(&project.root, manager)?;
+            let root = project.root.clone();
+            let restore_cache = cache.clone();
+            let restore_key = key.clone();
+            let restored =
+                tokio::task::spawn_blocking(move || restore_cache.restore(&restore_key, &root))
+                    .await??;
+            if restored {
+                tracing::info!(
+                    "Restored cached dependencies for {} ({})",
+                    repo_name,
+                    project.relative_path
+                );
+                continue;
+            }
+
+            let runner = CommandRunner::detect(&project.root, temp_repo_path);
+            let command = manager.install_command();
+            tracing::info!(
+                "Installing dependencies for {} ({}): '{}'",
+                repo_name,
+                project.relative_path,
+                command
+            );
+            let result = run_command_with_timeout(
+                &runner,
+                &project.root,
+                command,
+                DEPENDENCY_INSTALL_TIMEOUT_SECS,
+                &self.cancel,
+            )
+            .await;
+            if !result.success {
+                tracing::warn!(
+                    "Dependency install '{}' failed for {}, skipping mutation testing\nOutput:\n{}",
+                    command,
+                    repo_name,
+                    result.output
+                );
+                return Ok(false);
+            }
+            tracing::info!(
+                "Installed dependencies for {} ({}ms)",
+                repo_name,
+                result.duration_ms
+            );
+
+            let root = project.root.clone();
+            let store_cache = cache.clone();
+            if let Err(e) =
+                tokio::task::spawn_blocking(move || store_cache.store(&key, &root)).await?
+            {
+                tracing::warn!("Failed to cache dependencies for {}: {}", repo_name, e);
+            }
+        }
+
+        Ok(true)
     }
 
     /// Run LLM-driven mutation testing on a repository using a temp copy.
@@ -1724,12 +4136,11 @@ impl Daemon {
     ) -> anyhow::Result<()> {
         tracing::info!("Starting mutation testing for {}", repo.name);
 
-        self.db
-            .update_daemon_status(
-                "processing",
-                Some(&format!("mutation testing {}", repo.name)),
-            )
-            .await?;
+        self.set_task(
+            "processing",
+            Some(&format!("mutation testing {}", repo.name)),
+        )
+        .await?;
 
         if repo_config.mutation.rules.is_empty() {
             tracing::info!(
@@ -1738,6 +4149,13 @@ impl Daemon {
             );
             return Ok(());
         }
+        // Commands set in the repository's settings replace those of every rule
+        let rules: Vec<MutationRule> = repo_config
+            .mutation
+            .rules
+            .iter()
+            .map(|rule| rule.with_overrides(&repo.commands))
+            .collect();
 
         // Security notice: Log that we're about to execute user-defined commands
         // The noctum.toml file has already passed ownership/permission checks in RepoConfig::load()
@@ -1745,9 +4163,21 @@ impl Daemon {
             "Executing shell commands from noctum.toml for {} ({} rule(s)). \
              Commands are executed in an isolated temp directory copy of the repository.",
             repo.name,
-            repo_config.mutation.rules.len()
+            rules.len()
         );
-        for (i, rule) in repo_config.mutation.rules.iter().enumerate() {
+        if !repo.commands.is_empty() {
+            tracing::info!(
+                "Using the command overrides of {}: {:?}",
+                repo.name,
+                repo.commands
+            );
+        }
+        // Commands run in the project's root unless the settings name a directory
+        let command_dir = |project: &Project| match &repo.commands.working_dir {
+            Some(dir) => temp_repo_path.join(dir),
+            None => project.root.clone(),
+        };
+        for (i, rule) in rules.iter().enumerate() {
             tracing::debug!(
                 "  Rule {}: glob='{}', build='{}', test='{}'",
                 i + 1,
@@ -1757,11 +4187,33 @@ impl Daemon {
             );
         }
 
+        // Setup and baseline run from the repository root with its pinned toolchain
+        let root_runner = CommandRunner::detect(temp_repo_path, temp_repo_path);
+        if let Some(toolchain) = root_runner.toolchain() {
+            tracing::info!("Using pinned toolchain for {}: {}", repo.name, toolchain);
+        }
+
+        // Install JavaScript dependencies into the temp copy (if enabled)
+        if repo_config.install_dependencies
+            && !self
+                .install_dependencies(&repo.name, temp_repo_path)
+                .await?
+        {
+            return Ok(());
+        }
+
         // Run setup command once before baseline verification (if specified)
         if let Some(setup_cmd) = &repo_config.setup_command {
             tracing::info!("Running setup command for {}: '{}'", repo.name, setup_cmd);
             // Use a reasonable default timeout for setup (5 minutes)
-            let setup_result = run_command_with_timeout(temp_repo_path, setup_cmd, 300).await;
+            let setup_result = run_command_with_timeout(
+                &root_runner,
+                temp_repo_path,
+                setup_cmd,
+                300,
+                &self.cancel,
+            )
+            .await;
             if !setup_result.success {
                 tracing::warn!(
                     "Setup command '{}' failed for {}, skipping mutation testing\nOutput:\n{}",
@@ -1778,67 +4230,122 @@ impl Daemon {
             );
         }
 
-        // Run baseline verification for each rule (both build and test commands)
-        // Rules that fail baseline are excluded from mutation testing
-        let mut valid_rules: Vec<&crate::repo_config::MutationRule> = Vec::new();
+        // Each project's mutants are tested with its own tests: inside the
+        // project's directory, with cargo commands scoped to workspace members
+        let file_filter = repo_config
+            .file_filter()
+            .with_context(|| format!("Invalid include/exclude globs for {}", repo.name))?;
+        let projects = discover_projects(temp_repo_path)?;
+        let mut project_files = Vec::with_capacity(projects.len());
+        for project in &projects {
+            project_files.push(project.language.find_included_source_files(
+                &project.root,
+                temp_repo_path,
+                &file_filter,
+            )?);
+        }
+
+        // Run baseline verification of each rule in each project with files it
+        // matches (both build and test commands). Rules that fail a project's
+        // baseline are excluded from mutation testing in that project.
+        // Baseline build and test time per (rule, project), which also estimates
+        // mutants without history
+        let mut baseline_ms: HashMap<(usize, usize), u64> = HashMap::new();
+        let mut baselines_run = 0;
 
         tracing::info!(
-            "Running baseline verification for {} mutation rule(s) in {}",
-            repo_config.mutation.rules.len(),
+            "Running baseline verification for {} mutation rule(s) in {} project(s) of {}",
+            rules.len(),
+            projects.len(),
             repo.name
         );
 
-        for rule in &repo_config.mutation.rules {
-            tracing::info!(
-                "Verifying baseline for rule '{}': build='{}', test='{}'",
-                rule.glob,
-                rule.build_command,
-                rule.test_command
-            );
+        for (rule_index, rule) in rules.iter().enumerate() {
+            for (project_index, project) in projects.iter().enumerate() {
+                let matches_files = project_files[project_index].iter().any(|file| {
+                    let relative = file.strip_prefix(temp_repo_path).unwrap_or(file);
+                    rule.matches(&relative.to_string_lossy())
+                });
+                if !matches_files {
+                    continue;
+                }
+                if self.cancel.is_cancelled() {
+                    return Ok(());
+                }
+                baselines_run += 1;
 
-            // Run build command
-            let build_result =
-                run_command_with_timeout(temp_repo_path, &rule.build_command, rule.timeout_seconds)
-                    .await;
-            if !build_result.success {
-                tracing::warn!(
-                    "Excluding rule '{}' from mutation testing: baseline build '{}' failed\nOutput:\n{}",
+                let command_dir = command_dir(project);
+                let runner = CommandRunner::detect(&command_dir, temp_repo_path);
+                let build_command = project.scope_build_command(&rule.build_command);
+                let test_command = project.scope_test_command(&rule.test_command);
+                tracing::info!(
+                    "Verifying baseline for rule '{}' in project '{}': build='{}', test='{}'",
                     rule.glob,
-                    rule.build_command,
-                    build_result.output
+                    project.name,
+                    build_command,
+                    test_command
                 );
-                continue;
-            }
-            tracing::info!(
-                "Baseline build passed for rule '{}' ({}ms)",
-                rule.glob,
-                build_result.duration_ms
-            );
 
-            // Run test command
-            let test_result =
-                run_command_with_timeout(temp_repo_path, &rule.test_command, rule.timeout_seconds)
-                    .await;
-            if !test_result.success {
-                tracing::warn!(
-                    "Excluding rule '{}' from mutation testing: baseline test '{}' failed\nOutput:\n{}",
+                // Run build command
+                let build_result = run_command_with_timeout(
+                    &runner,
+                    &command_dir,
+                    &build_command,
+                    rule.timeout_seconds,
+                    &self.cancel,
+                )
+                .await;
+                if !build_result.success {
+                    tracing::warn!(
+                        "Excluding rule '{}' from mutation testing in project '{}': baseline build '{}' failed\nOutput:\n{}",
+                        rule.glob,
+                        project.name,
+                        build_command,
+                        build_result.output
+                    );
+                    continue;
+                }
+                tracing::info!(
+                    "Baseline build passed for rule '{}' in project '{}' ({}ms)",
                     rule.glob,
-                    rule.test_command,
-                    test_result.output
+                    project.name,
+                    build_result.duration_ms
+                );
+
+                // Run test command
+                let test_result = run_command_with_timeout(
+                    &runner,
+                    &command_dir,
+                    &test_command,
+                    rule.timeout_seconds,
+                    &self.cancel,
+                )
+                .await;
+                if !test_result.success {
+                    tracing::warn!(
+                        "Excluding rule '{}' from mutation testing in project '{}': baseline test '{}' failed\nOutput:\n{}",
+                        rule.glob,
+                        project.name,
+                        test_command,
+                        test_result.output
+                    );
+                    continue;
+                }
+                tracing::info!(
+                    "Baseline test passed for rule '{}' in project '{}' ({}ms)",
+                    rule.glob,
+                    project.name,
+                    test_result.duration_ms
                 );
-                continue;
-            }
-            tracing::info!(
-                "Baseline test passed for rule '{}' ({}ms)",
-                rule.glob,
-                test_result.duration_ms
-            );
 
-            tracing::info!("Baseline passed for rule '{}'", rule.glob);
-            valid_rules.push(rule);
+                baseline_ms.insert(
+                    (rule_index, project_index),
+                    build_result.duration_ms + test_result.duration_ms,
+                );
+            }
         }
 
-        if valid_rules.is_empty() {
+        if baseline_ms.is_empty() {
             tracing::warn!(
                 "No mutation rules passed baseline verification for {}, skipping mutation testing",
                 repo.name
@@ -1847,66 +4354,140 @@ impl Daemon {
         }
 
         tracing::info!(
-            "{}/{} mutation rules passed baseline verification for {}",
-            valid_rules.len(),
-            repo_config.mutation.rules.len(),
+            "{}/{} mutation rule baselines passed for {}",
+            baseline_ms.len(),
+            baselines_run,
             repo.name
         );
 
-        let config = MutationConfig::default();
+        let config = MutationConfig {
+            strategy: repo_config.mutation.strategy,
+            compile_fix: repo_config.mutation.compile_fix.clone(),
+            ..MutationConfig::default()
+        };
 
-        // Find first available endpoint
-        let (client, endpoint_name) = match find_available_endpoint(endpoints).await {
-            Some((c, name)) => (c, name),
-            None => {
+        // Find first available endpoint (deterministic mutations don't need one)
+        let available = match config.strategy {
+            MutationStrategy::Deterministic => None,
+            MutationStrategy::Llm | MutationStrategy::Hybrid => {
+                find_available_endpoint(endpoints, &self.health, &self.cancel).await
+            }
+        };
+        let (mut current_client, endpoint_name) = match available {
+            Some((c, name)) => (Some(c), Some(name)),
+            None if config.strategy == MutationStrategy::Llm => {
                 tracing::warn!("No endpoints available for mutation testing");
                 return Ok(());
             }
+            None => {
+                if config.strategy == MutationStrategy::Hybrid {
+                    tracing::info!(
+                        "No endpoints available, using deterministic mutations for {}",
+                        repo.name
+                    );
+                }
+                (None, None)
+            }
         };
 
-        // Discover projects to run mutation testing per-project
-        let projects = discover_projects(temp_repo_path)?;
+        let coverage = if repo_config.mutation.coverage.enabled {
+            self.collect_coverage(
+                repo,
+                repo_config,
+                &root_runner,
+                temp_repo_path,
+                original_repo_path,
+            )
+            .await
+        } else {
+            None
+        };
+
+        // Extra copies are made after the baseline run so they start with warm builds
+        let parallelism = repo_config.mutation.parallelism.max(1);
+        if parallelism > 1 {
+            tracing::info!(
+                "Creating {} workspaces for parallel mutation testing of {}",
+                parallelism,
+                repo.name
+            );
+        }
+        let workspaces = WorkspacePool::create(temp_repo_path, parallelism)
+            .await
+            .context("Failed to create mutation workspaces")?;
 
         let mut total_mutations = 0;
-        let mut current_client = client;
+        let mut survived = Vec::new();
         let mut current_endpoint_idx = endpoints
             .iter()
-            .position(|e| e.name == endpoint_name)
+            .position(|e| Some(&e.name) == endpoint_name.as_ref())
             .unwrap_or(0);
 
-        for project in projects {
-            if self.should_stop.load(Ordering::SeqCst) {
+        // Files whose mutants won't finish before the window closes are left for
+        // the next one, which skips everything already tested at the same hash
+        let history_ms = self
+            .db
+            .get_average_mutation_time_ms(repo.id)
+            .await
+            .unwrap_or(None)
+            .map(|ms| ms.max(0) as u64);
+        let budget =
+            MutationBudget::new(self.schedule_time_left(repo).await, parallelism, history_ms);
+        let mut deferred = Deferred::default();
+        let mut tested_files = 0;
+        let mut tested_mutants = 0;
+
+        // Files tested at an earlier commit only have their changed functions mutated
+        let head = if repo_config.mutation.changed_only {
+            changes::head_commit(original_repo_path).await
+        } else {
+            None
+        };
+
+        for (project_index, (project, source_files)) in
+            projects.iter().zip(project_files).enumerate()
+        {
+            if self.cancel.is_cancelled() {
                 break;
             }
 
-            // Find source files for this project
-            let source_files = project.language.find_source_files(&project.root)?;
+            let command_dir = command_dir(project);
+            let runner = CommandRunner::detect(&command_dir, temp_repo_path);
+            let toolchain = runner.toolchain();
 
             for file_path in source_files {
-                if self.should_stop.load(Ordering::SeqCst) {
+                if self.cancel.is_cancelled() {
                     break;
                 }
 
                 // Get relative path for glob matching
-                let relative_path = file_path
-                    .strip_prefix(temp_repo_path)
-                    .unwrap_or(&file_path)
-                    .to_string_lossy();
-
-                // Find matching rule from validated rules only - skip file if no rule matches
-                let rule = match valid_rules.iter().find(|r| r.matches(&relative_path)) {
-                    Some(r) => *r,
-                    None => {
-                        tracing::debug!("Skipping {}: no matching mutation rule", relative_path);
-                        continue;
-                    }
+                let relative = file_path.strip_prefix(temp_repo_path).unwrap_or(&file_path);
+                let relative_path = relative.to_string_lossy();
+
+                // Find the first matching rule that passed this project's baseline -
+                // skip file if no rule matches
+                let matching_rule = rules.iter().enumerate().find(|(rule_index, rule)| {
+                    rule.matches(&relative_path)
+                        && baseline_ms.contains_key(&(*rule_index, project_index))
+                });
+                let Some((rule_index, rule)) = matching_rule else {
+                    tracing::debug!("Skipping {}: no matching mutation rule", relative_path);
+                    continue;
                 };
-
-                // Read file from temp copy
-                let content = match tokio::fs::read_to_string(&file_path).await {
-                    Ok(c) => c,
-                    Err(_) => continue,
+                // Workspace members only need their own package recompiled and tested
+                let build_command = project.scope_build_command(&rule.build_command);
+                let test_command = project.scope_test_command(&rule.test_command);
+
+                // Read file from temp copy. Lossily decoded files can't be mutated, since
+                // writing the mutant back would corrupt the invalid bytes
+                let content = match read_source(&file_path).await {
+                    Ok(SourceText::Text(c)) => c,
+                    _ => continue,
                 };
+                if let Some(detected) = file_filter.detect(relative, &content) {
+                    tracing::debug!("Skipping {:?} file {}", detected, relative_path);
+                    continue;
+                }
 
                 // Use language-specific size limits for mutations
                 let min_size = project.language.min_mutation_file_size();
@@ -1925,12 +4506,31 @@ impl Daemon {
                     translate_temp_to_original(temp_repo_path, original_repo_path, &file_path);
                 let original_file_path_str = original_file_path.to_string_lossy().to_string();
 
-                // Check if already tested with this hash (using original path for DB lookup)
-                if self
+                // A run interrupted at the same content continues with its remaining
+                // mutations, which already saved results don't count as finishing
+                let interrupted = match self
                     .db
-                    .has_mutation_results_for_hash(repo.id, &original_file_path_str, &content_hash)
+                    .get_mutation_run(repo.id, &original_file_path_str)
                     .await
-                    .unwrap_or(false)
+                {
+                    Ok(run) => run.filter(|run| run.content_hash == content_hash),
+                    Err(e) => {
+                        tracing::warn!("Failed to read mutation run state: {}", e);
+                        None
+                    }
+                };
+
+                // Check if already tested with this hash (using original path for DB lookup)
+                if interrupted.is_none()
+                    && self
+                        .db
+                        .has_mutation_results_for_hash(
+                            repo.id,
+                            &original_file_path_str,
+                            &content_hash,
+                        )
+                        .await
+                        .unwrap_or(false)
                 {
                     tracing::debug!(
                         "Skipping mutation testing for unchanged file: {}",
@@ -1939,61 +4539,154 @@ impl Daemon {
                     continue;
                 }
 
+                let rule_baseline_ms = baseline_ms
+                    .get(&(rule_index, project_index))
+                    .copied()
+                    .unwrap_or(0);
+                if !deferred.is_empty() {
+                    // Expect as many mutants as the files tested so far had
+                    let expected = match tested_files {
+                        0 => config.max_mutations_per_file,
+                        n => tested_mutants / n,
+                    };
+                    deferred.add(budget.estimate(expected, rule_baseline_ms));
+                    continue;
+                }
+
+                let changed = match (&head, &interrupted) {
+                    (Some(_), None) => {
+                        self.changed_since_last_mutation_run(
+                            repo.id,
+                            original_repo_path,
+                            &relative_path,
+                            &original_file_path_str,
+                            &content,
+                        )
+                        .await
+                    }
+                    _ => None,
+                };
+
                 // Analyze and generate mutations, with endpoint fallback
                 // Pass temp path so mutations store temp paths for executor to use
-                tracing::debug!("Analyzing mutations for {}", original_file_path_str);
-                let mutations = match analyze_and_generate_mutations(
-                    &current_client,
-                    &temp_file_path_str,
-                    &content,
-                    config.max_mutations_per_file,
+                let started = Instant::now();
+                let llm_mutations = match &current_client {
+                    Some(client) if interrupted.is_none() => {
+                        tracing::debug!("Analyzing mutations for {}", original_file_path_str);
+                        match analyze_and_generate_mutations(
+                            client,
+                            &temp_file_path_str,
+                            &content,
+                            config.max_mutations_per_file,
+                            changed.as_ref(),
+                        )
+                        .await
+                        {
+                            Ok(m) => {
+                                self.health
+                                    .record_success(&endpoints[current_endpoint_idx].name);
+                                Some(m)
+                            }
+                            Err(e) => {
+                                tracing::warn!(
+                                    "Failed to analyze mutations in {} with current endpoint: {}",
+                                    original_file_path_str,
+                                    e
+                                );
+                                self.health.record_failure(
+                                    &endpoints[current_endpoint_idx].name,
+                                    &e.to_string(),
+                                );
+
+                                // Try to find another endpoint
+                                let remaining = &endpoints[current_endpoint_idx + 1..];
+                                if let Some((new_client, new_name)) =
+                                    find_available_endpoint(remaining, &self.health, &self.cancel)
+                                        .await
+                                {
+                                    tracing::info!(
+                                        "Switching to endpoint {} for mutation analysis",
+                                        new_name
+                                    );
+                                    current_endpoint_idx = endpoints
+                                        .iter()
+                                        .position(|ep| ep.name == new_name)
+                                        .unwrap_or(current_endpoint_idx);
+
+                                    // Retry with new endpoint
+                                    let retry = analyze_and_generate_mutations(
+                                        &new_client,
+                                        &temp_file_path_str,
+                                        &content,
+                                        config.max_mutations_per_file,
+                                        changed.as_ref(),
+                                    )
+                                    .await;
+                                    current_client = Some(new_client);
+                                    let current_name = &endpoints[current_endpoint_idx].name;
+                                    match retry {
+                                        Ok(m) => {
+                                            self.health.record_success(current_name);
+                                            Some(m)
+                                        }
+                                        Err(e2) => {
+                                            tracing::warn!(
+                                                "Retry also failed for {}: {}",
+                                                original_file_path_str,
+                                                e2
+                                            );
+                                            self.health
+                                                .record_failure(current_name, &e2.to_string());
+                                            None
+                                        }
+                                    }
+                                } else {
+                                    None
+                                }
+                            }
+                        }
+                    }
+                    _ => None,
+                };
+                self.record_mutation_usage(
+                    current_client.as_ref(),
+                    endpoints.get(current_endpoint_idx),
+                    repo.id,
                 )
-                .await
-                {
-                    Ok(m) => m,
-                    Err(e) => {
-                        tracing::warn!(
-                            "Failed to analyze mutations in {} with current endpoint: {}",
-                            original_file_path_str,
-                            e
-                        );
+                .await;
 
-                        // Try to find another endpoint
-                        let remaining = &endpoints[current_endpoint_idx + 1..];
-                        if let Some((new_client, new_name)) =
-                            find_available_endpoint(remaining).await
-                        {
-                            tracing::info!(
-                                "Switching to endpoint {} for mutation analysis",
-                                new_name
-                            );
-                            current_client = new_client;
-                            current_endpoint_idx = endpoints
-                                .iter()
-                                .position(|ep| ep.name == new_name)
-                                .unwrap_or(current_endpoint_idx);
-
-                            // Retry with new endpoint
-                            match analyze_and_generate_mutations(
-                                &current_client,
+                // Hybrid and deterministic strategies fall back to the operator table
+                let (mutations, attribution) = if let Some(run) = &interrupted {
+                    // Stored with the temp path of the interrupted run
+                    let mutations = run
+                        .mutations
+                        .iter()
+                        .cloned()
+                        .map(|mutation| GeneratedMutation {
+                            file_path: temp_file_path_str.clone(),
+                            ..mutation
+                        })
+                        .collect();
+                    (mutations, run.attribution())
+                } else {
+                    match llm_mutations {
+                        Some(m) if !m.is_empty() || config.strategy == MutationStrategy::Llm => (
+                            m,
+                            llm_attribution(&endpoints[current_endpoint_idx], started),
+                        ),
+                        None if config.strategy == MutationStrategy::Llm => continue,
+                        _ => {
+                            let mutations = operators::generate_mutations(
                                 &temp_file_path_str,
                                 &content,
                                 config.max_mutations_per_file,
-                            )
-                            .await
-                            {
-                                Ok(m) => m,
-                                Err(e2) => {
-                                    tracing::warn!(
-                                        "Retry also failed for {}: {}",
-                                        original_file_path_str,
-                                        e2
-                                    );
-                                    continue;
-                                }
-                            }
-                        } else {
-                            continue;
+                                changed.as_ref(),
+                            );
+                            let attribution = Attribution {
+                                duration_ms: Some(started.elapsed().as_millis() as i64),
+                                ..Attribution::default()
+                            };
+                            (mutations, attribution)
                         }
                     }
                 };
@@ -2003,33 +4696,118 @@ impl Daemon {
                     continue;
                 }
 
-                tracing::info!(
-                    "Generated {} mutations for {}",
-                    mutations.len(),
-                    original_file_path_str
-                );
+                // The run state lets a restart continue with the next untested mutation
+                let executed: HashSet<usize> = match &interrupted {
+                    Some(run) => {
+                        let executed: HashSet<usize> = run.executed.iter().copied().collect();
+                        tracing::info!(
+                            "Resuming mutation testing of {}: {} of {} mutations left",
+                            original_file_path_str,
+                            mutations.len().saturating_sub(executed.len()),
+                            mutations.len()
+                        );
+                        executed
+                    }
+                    None => {
+                        tracing::info!(
+                            "Generated {} mutations for {}",
+                            mutations.len(),
+                            original_file_path_str
+                        );
+                        if let Err(e) = self
+                            .db
+                            .start_mutation_run(
+                                repo.id,
+                                &original_file_path_str,
+                                &content_hash,
+                                &mutations,
+                                &attribution,
+                            )
+                            .await
+                        {
+                            tracing::warn!("Failed to save mutation run state: {}", e);
+                        }
+                        HashSet::new()
+                    }
+                };
 
                 // Pre-compute original lines for building replacement details
                 let original_lines: Vec<&str> = content.lines().collect();
 
-                for mutation in mutations {
-                    if self.should_stop.load(Ordering::SeqCst) {
-                        break;
+                // Mutations on lines no test executes would survive, so they aren't tested
+                let file_coverage = coverage.as_ref().and_then(|c| c.file(relative));
+                let covered = |mutation: &GeneratedMutation| -> Option<bool> {
+                    file_coverage?.mutation_covered(&content, mutation)
+                };
+                let mutations: Vec<(usize, GeneratedMutation)> = mutations
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(index, _)| !executed.contains(index))
+                    .collect();
+                let generated = mutations.len();
+                let mutations: Vec<(usize, GeneratedMutation)> = mutations
+                    .into_iter()
+                    .filter(|(_, mutation)| covered(mutation) != Some(false))
+                    .collect();
+                if mutations.len() < generated {
+                    tracing::info!(
+                        "Skipping {} mutation(s) on lines not covered by tests in {}",
+                        generated - mutations.len(),
+                        original_file_path_str
+                    );
+                }
+
+                // Mutations that can't change behavior are recorded without running them
+                let mut accepted = Vec::with_capacity(mutations.len());
+                let mut rejected = Vec::new();
+                for (index, mutation) in mutations {
+                    match rejection_reason(&content, &mutation) {
+                        Some(reason) => rejected.push((
+                            index,
+                            Ok(MutationTestResult {
+                                mutation,
+                                outcome: TestOutcome::Rejected,
+                                killing_test: None,
+                                test_output: Some(reason),
+                                execution_time_ms: 0,
+                            }),
+                        )),
+                        None => accepted.push((index, mutation)),
                     }
+                }
 
-                    // Execute the mutation test using configured commands
-                    let result = match execute_mutation_test(
-                        &current_client,
-                        &project.root,
-                        mutation,
+                // The first file always runs, so files too big for any window
+                // still get tested eventually
+                let estimate = budget.estimate(accepted.len(), rule_baseline_ms);
+                if tested_files > 0 && !budget.fits(estimate) {
+                    deferred.add(estimate);
+                    continue;
+                }
+                tested_files += 1;
+                tested_mutants += accepted.len();
+
+                // Execute the mutation tests using configured commands
+                let mut results =
+                    std::pin::pin!(stream::iter(rejected).chain(execute_mutation_tests(
+                        current_client.as_ref(),
+                        &runner,
+                        &workspaces,
+                        &command_dir,
+                        accepted,
                         &content,
                         &config,
-                        &rule.build_command,
-                        &rule.test_command,
+                        &build_command,
+                        &test_command,
                         rule.timeout_seconds,
-                    )
-                    .await
-                    {
+                        &self.cancel,
+                    )));
+
+                while let Some((index, result)) = results.next().await {
+                    if self.cancel.is_cancelled() {
+                        break;
+                    }
+
+                    let result = match result {
                         Ok(r) => r,
                         Err(e) => {
                             tracing::warn!("Failed to execute mutation test: {}", e);
@@ -2045,6 +4823,8 @@ impl Daemon {
                             original_file_path_str,
                             result.mutation.description
                         );
+                        self.mark_mutation_executed(repo.id, &original_file_path_str, index)
+                            .await;
                         continue;
                     }
 
@@ -2071,33 +4851,33 @@ impl Daemon {
                                 result.mutation.description,
                             );
                         }
+                        crate::mutation::TestOutcome::Rejected => {
+                            tracing::info!(
+                                "Mutation REJECTED in {}: {} ({})",
+                                original_file_path_str,
+                                result.mutation.description,
+                                result.test_output.as_deref().unwrap_or("unknown reason"),
+                            );
+                        }
                         _ => {}
                     }
 
-                    // Build replacements JSON with all replacement info
-                    // Each replacement has: line_number, find, replace
-                    // We also include the original lines for context
-                    let replacements_with_context: Vec<serde_json::Value> = result
+                    // Keep the original line of each replacement for context
+                    let replacements: Vec<MutationReplacement> = result
                         .mutation
                         .replacements
                         .iter()
-                        .map(|r| {
-                            let original_line = original_lines
+                        .map(|r| MutationReplacement {
+                            line_number: r.line_number as i64,
+                            find: r.find.clone(),
+                            replace: r.replace.clone(),
+                            original_line: original_lines
                                 .get(r.line_number.saturating_sub(1))
                                 .unwrap_or(&"")
-                                .to_string();
-                            serde_json::json!({
-                                "line_number": r.line_number,
-                                "find": r.find,
-                                "replace": r.replace,
-                                "original_line": original_line
-                            })
+                                .to_string(),
                         })
                         .collect();
 
-                    let replacements_json = serde_json::to_string(&replacements_with_context)
-                        .unwrap_or_else(|_| "[]".to_string());
-
                     // Save result with original path (not temp path) for UI display
                     if let Err(e) = self
                         .db
@@ -2106,20 +4886,75 @@ impl Daemon {
                             &original_file_path_str,
                             &result.mutation.description,
                             &result.mutation.reasoning,
-                            &replacements_json,
+                            &replacements,
                             &result.outcome.to_string(),
                             result.killing_test.as_deref(),
                             result.test_output.as_deref(),
                             Some(result.execution_time_ms as i32),
                             Some(&content_hash),
+                            toolchain.as_deref(),
+                            covered(&result.mutation),
+                            Some(&project.name),
+                            &attribution,
                         )
                         .await
                     {
                         tracing::warn!("Failed to save mutation result: {}", e);
+                    } else {
+                        self.progress.publish(DashboardUpdate::MutationResult {
+                            repository_id: repo.id,
+                            file_path: original_file_path_str.clone(),
+                            description: result.mutation.description.clone(),
+                            test_outcome: result.outcome.to_string(),
+                        });
+                    }
+
+                    self.mark_mutation_executed(repo.id, &original_file_path_str, index)
+                        .await;
+
+                    if result.outcome == crate::mutation::TestOutcome::Survived {
+                        survived.push(SurvivedMutant {
+                            file_path: relative_path.to_string(),
+                            description: result.mutation.description.clone(),
+                        });
                     }
 
                     total_mutations += 1;
                 }
+
+                // An interrupted run keeps its state to be resumed
+                if !self.cancel.is_cancelled() {
+                    if let Err(e) = self
+                        .db
+                        .finish_mutation_run(repo.id, &original_file_path_str)
+                        .await
+                    {
+                        tracing::warn!("Failed to clear mutation run state: {}", e);
+                    }
+
+                    // The next run diffs against this commit, unless the file had
+                    // uncommitted changes and has to be mutated in full
+                    if let Some(head) = &head {
+                        let base = changes::is_committed(original_repo_path, &relative_path)
+                            .await
+                            .then_some(head.as_str());
+                        if let Err(e) = self
+                            .db
+                            .set_mutation_base(repo.id, &original_file_path_str, base)
+                            .await
+                        {
+                            tracing::warn!("Failed to save mutation base: {}", e);
+                        }
+                    }
+                }
+
+                // Fixes of mutations that failed to compile
+                self.record_mutation_usage(
+                    current_client.as_ref(),
+                    endpoints.get(current_endpoint_idx),
+                    repo.id,
+                )
+                .await;
             }
         }
 
@@ -2128,20 +4963,381 @@ impl Daemon {
             repo.name,
             total_mutations
         );
+
+        if !deferred.is_empty() {
+            let remaining = budget.remaining().unwrap_or_default();
+            tracing::info!(
+                "Deferring mutation testing of {} file(s) in {} (~{} min) to the next window, \
+                 {} min left in this one",
+                deferred.files,
+                repo.name,
+                deferred.estimate.as_secs().div_ceil(60),
+                remaining.as_secs() / 60
+            );
+            if let Err(e) = self
+                .db
+                .save_mutation_deferral(
+                    repo.id,
+                    deferred.files as i64,
+                    deferred.estimate.as_secs() as i64,
+                    remaining.as_secs() as i64,
+                )
+                .await
+            {
+                tracing::warn!("Failed to save mutation deferral: {}", e);
+            }
+        } else if !self.cancel.is_cancelled() {
+            if let Err(e) = self.db.clear_mutation_deferral(repo.id).await {
+                tracing::warn!("Failed to clear mutation deferral: {}", e);
+            }
+        }
+
+        self.notify_survived_mutants(repo, survived).await;
         Ok(())
     }
+
+    /// The functions of a file changed since the commit its mutations were last
+    /// tested at, None to mutate the whole file (never tested at a commit, the
+    /// commit is gone, or nothing changed although the content hash did)
+    async fn changed_since_last_mutation_run(
+        &self,
+        repository_id: i64,
+        original_repo_path: &Path,
+        relative_path: &str,
+        file_path: &str,
+        content: &str,
+    ) -> Option<ChangedLines> {
+        let base = match self.db.get_mutation_base(repository_id, file_path).await {
+            Ok(base) => base?,
+            Err(e) => {
+                tracing::warn!("Failed to read mutation base: {}", e);
+                return None;
+            }
+        };
+        let previous = match changes::file_at_commit(original_repo_path, &base, relative_path).await
+        {
+            Ok(previous) => previous,
+            Err(e) => {
+                tracing::debug!("Mutating all of {}: {}", file_path, e);
+                return None;
+            }
+        };
+
+        let changed = ChangedLines::between(&previous, content)?;
+        tracing::info!(
+            "Mutating lines {} of {} ({} of {} lines, in functions changed since {})",
+            changed,
+            file_path,
+            changed.line_count(),
+            content.lines().count(),
+            &base[..base.len().min(12)]
+        );
+        Some(changed)
+    }
+
+    /// Load line coverage for mutation testing as configured in `[mutation.coverage]`:
+    /// run the coverage command in the temp copy and read the lcov file it writes,
+    /// or read an existing lcov file from the repository. Failures only disable
+    /// coverage-based skipping.
+    async fn collect_coverage(
+        &self,
+        repo: &crate::db::Repository,
+        repo_config: &RepoConfig,
+        runner: &CommandRunner,
+        temp_repo_path: &Path,
+        original_repo_path: &Path,
+    ) -> Option<CoverageReport> {
+        let settings = &repo_config.mutation.coverage;
+        let lcov_path = match (&settings.command, &settings.lcov_path) {
+            (None, Some(lcov_path)) => original_repo_path.join(lcov_path),
+            (command, lcov_path) => {
+                let command = command.as_deref().unwrap_or(DEFAULT_COVERAGE_COMMAND);
+                tracing::info!("Running coverage command for {}: '{}'", repo.name, command);
+                let result = run_command_with_timeout(
+                    runner,
+                    temp_repo_path,
+                    command,
+                    settings.timeout_seconds,
+                    &self.cancel,
+                )
+                .await;
+                if !result.success {
+                    tracing::warn!(
+                        "Coverage command '{}' failed for {}, testing all mutations\nOutput:\n{}",
+                        command,
+                        repo.name,
+                        truncate_output_tail(&result.output, 2000)
+                    );
+                    return None;
+                }
+                temp_repo_path.join(lcov_path.as_deref().unwrap_or(Path::new("lcov.info")))
+            }
+        };
+
+        match CoverageReport::load(&lcov_path, &[temp_repo_path, original_repo_path]) {
+            Ok(report) => {
+                tracing::info!(
+                    "Loaded line coverage of {} file(s) for {}",
+                    report.len(),
+                    repo.name
+                );
+                Some(report)
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load coverage for {}, testing all mutations: {:#}",
+                    repo.name,
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Record that a mutation of a file's run was executed, so it isn't run again
+    /// when the run is resumed
+    async fn mark_mutation_executed(&self, repository_id: i64, file_path: &str, index: usize) {
+        if let Err(e) = self
+            .db
+            .mark_mutation_executed(repository_id, file_path, index)
+            .await
+        {
+            tracing::warn!("Failed to update mutation run state: {}", e);
+        }
+    }
+
+    /// Record the tokens used for mutation testing since the last call
+    async fn record_mutation_usage(
+        &self,
+        client: Option<&OllamaClient>,
+        endpoint: Option<&OllamaEndpoint>,
+        repository_id: i64,
+    ) {
+        if let (Some(client), Some(endpoint)) = (client, endpoint) {
+            record_usage(
+                &self.db,
+                client,
+                &endpoint.name,
+                repository_id,
+                &AnalysisType::MutationTesting.to_string(),
+            )
+            .await;
+        }
+    }
+
+    /// Send one consolidated notification listing the survived mutants of a run,
+    /// unless there are fewer than `survived_threshold` of them.
+    async fn notify_survived_mutants(
+        &self,
+        repo: &crate::db::Repository,
+        survived: Vec<SurvivedMutant>,
+    ) {
+        let (notifications, dashboard_url) = {
+            let config = self.config.read().await;
+            (config.notifications.clone(), config.dashboard_url())
+        };
+        if survived.is_empty()
+            || survived.len() < notifications.survived_threshold
+            || !notifications.wants(NotificationEvent::SurvivedMutants)
+        {
+            return;
+        }
+
+        let notification = Notification::survived_mutants(
+            &repo.name,
+            repo.id,
+            survived,
+            notifications.max_mutants,
+            &dashboard_url,
+        );
+        self.send_repository_notification(repo, notifications, notification)
+            .await;
+    }
+
+    /// Send one consolidated notification listing the error-severity findings
+    /// of the results saved after `since_result_id` that weren't reported before
+    async fn notify_critical_findings(&self, repo: &crate::db::Repository, since_result_id: i64) {
+        let (notifications, dashboard_url) = {
+            let config = self.config.read().await;
+            (config.notifications.clone(), config.dashboard_url())
+        };
+        if !notifications.wants(NotificationEvent::CriticalFindings) {
+            return;
+        }
+
+        let findings = match self
+            .db
+            .get_new_critical_findings(repo.id, since_result_id)
+            .await
+        {
+            Ok(findings) if findings.is_empty() => return,
+            Ok(findings) => findings,
+            Err(e) => {
+                tracing::warn!("Failed to fetch new critical findings: {}", e);
+                return;
+            }
+        };
+        let findings = findings
+            .into_iter()
+            .map(|finding| CriticalFinding {
+                file_path: Path::new(&finding.file_path)
+                    .strip_prefix(&repo.path)
+                    .unwrap_or(Path::new(&finding.file_path))
+                    .to_string_lossy()
+                    .to_string(),
+                title: finding.title,
+                line_start: finding.line_start,
+            })
+            .collect();
+
+        let notification = Notification::critical_findings(
+            &repo.name,
+            repo.id,
+            findings,
+            notifications.max_mutants,
+            &dashboard_url,
+        );
+        self.send_repository_notification(repo, notifications, notification)
+            .await;
+    }
+
+    /// Send a notification about a repository and record it, unless the daily
+    /// cap has been reached
+    async fn send_repository_notification(
+        &self,
+        repo: &crate::db::Repository,
+        notifications: NotificationConfig,
+        notification: Notification,
+    ) {
+        match self.db.count_recent_notifications().await {
+            Ok(sent) if sent >= i64::from(notifications.max_per_day) => {
+                tracing::info!(
+                    "Daily notification cap ({}) reached, not sending: {}",
+                    notifications.max_per_day,
+                    notification.title
+                );
+                return;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("Failed to check notification cap: {}", e);
+                return;
+            }
+        }
+
+        match Notifier::new(notifications).send(&notification).await {
+            Ok(()) => {
+                tracing::info!("Sent notification: {}", notification.title);
+                if let Err(e) = self
+                    .db
+                    .record_notification(repo.id, &notification.title)
+                    .await
+                {
+                    tracing::warn!("Failed to record notification: {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to send notification for {}: {}", repo.name, e),
+        }
+    }
+
+    /// Record the health score of each repository, for the trend on the
+    /// repositories page
+    async fn record_scores(&self, run_id: i64, repositories: &[Repository]) {
+        for repo in repositories {
+            let recorded = match self.db.get_score_inputs(repo.id).await {
+                Ok(inputs) => self.db.record_score(repo.id, Some(run_id), &inputs).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = recorded {
+                tracing::warn!("Failed to record the score of {}: {}", repo.name, e);
+            }
+        }
+    }
+
+    /// Send a summary of a finished processing cycle, unless it did nothing.
+    /// Summaries are sent at most once per cycle and don't count against the
+    /// daily cap.
+    async fn notify_scan_completed(&self, run_id: i64) {
+        let (notifications, dashboard_url) = {
+            let config = self.config.read().await;
+            (config.notifications.clone(), config.dashboard_url())
+        };
+        if !notifications.wants(NotificationEvent::ScanCompleted) {
+            return;
+        }
+
+        let run = match self.db.get_scan_run(run_id).await {
+            Ok(Some(run)) => run,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Failed to fetch scan run {}: {}", run_id, e);
+                return;
+            }
+        };
+        let idle = run.status == "completed"
+            && run.files_analyzed == 0
+            && run.mutations_run == 0
+            && run.errors == 0;
+        if idle {
+            return;
+        }
+
+        let notification = Notification::scan_completed(run, &dashboard_url);
+        match Notifier::new(notifications).send(&notification).await {
+            Ok(()) => tracing::info!("Sent notification: {}", notification.title),
+            Err(e) => tracing::warn!("Failed to send scan notification: {}", e),
+        }
+    }
+}
+
+/// Hash identifying the sources a diagram is generated from, given the hashes
+/// of its extractions (or files) in order. Switching formats changes it, so
+/// every diagram is regenerated in the new format.
+fn diagram_source_hash(format: DiagramFormat, hashes: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    for hash in hashes {
+        hasher.update(hash.as_bytes());
+        hasher.update(b"\0");
+    }
+    let hash = format!("{:x}", hasher.finalize());
+    match format {
+        DiagramFormat::Dot => hash,
+        DiagramFormat::Mermaid => format!("mermaid:{}", hash),
+    }
+}
+
+/// Validate diagram source in `format`, returning the SVG to store. Mermaid
+/// is rendered in the browser, so it has none.
+async fn render_diagram(
+    renderer: &DotRenderer,
+    format: DiagramFormat,
+    diagram_type: DiagramType,
+    code: &str,
+) -> Result<String, String> {
+    match format {
+        DiagramFormat::Dot => renderer.render(code).await,
+        DiagramFormat::Mermaid => {
+            validate_mermaid_syntax(code, diagram_type).map(|()| String::new())
+        }
+    }
 }
 
 /// Worker function for analysis tasks
+#[allow(clippy::too_many_arguments)]
 async fn analysis_worker(
     endpoint: OllamaEndpoint,
     receiver: Arc<TokioMutex<mpsc::Receiver<AnalysisTask>>>,
     db: Database,
-    should_stop: Arc<AtomicBool>,
+    queue: JobQueue,
+    progress: ProgressBroadcaster,
+    health: HealthMonitor,
+    cancel: CancellationToken,
+    output_language: String,
+    results: ResultCollector,
 ) {
-    let client = OllamaClient::new(&endpoint.url, &endpoint.model);
+    let client = OllamaClient::for_endpoint(&endpoint).with_cancellation(cancel.clone());
 
-    if !client.is_available().await {
+    if !health.is_allowed(&endpoint.name) || !health.probe(&endpoint, &cancel).await {
         tracing::warn!(
             "Ollama endpoint '{}' at {} is not available for generic analysis, skipping",
             endpoint.name,
@@ -2157,7 +5353,7 @@ async fn analysis_worker(
     );
 
     loop {
-        if should_stop.load(Ordering::SeqCst) {
+        if cancel.is_cancelled() {
             tracing::debug!(
                 "Generic worker for '{}' stopping due to shutdown signal",
                 endpoint.name
@@ -2165,11 +5361,21 @@ async fn analysis_worker(
             break;
         }
 
+        // Leave the pool while the circuit is open; the remaining tasks go to the
+        // other workers
+        if !health.is_allowed(&endpoint.name) {
+            tracing::warn!(
+                "Generic worker for '{}' stopping, endpoint is unhealthy",
+                endpoint.name
+            );
+            break;
+        }
+
         let task = {
             let mut rx = receiver.lock().await;
             tokio::select! {
                 task = rx.recv() => task,
-                _ = wait_for_stop_signal(&should_stop) => {
+                _ = cancel.cancelled() => {
                     tracing::debug!(
                         "Generic worker for '{}' stopping due to shutdown signal",
                         endpoint.name
@@ -2191,39 +5397,69 @@ async fn analysis_worker(
         };
 
         let file_path_str = task.file_path.to_string_lossy().to_string();
+        let analysis_type_str = task.task_type.analysis_type();
+
+        if !queue.claim(task.job_id, Some(&endpoint.name)).await {
+            tracing::info!(
+                "Skipping {} for {}: removed from the queue",
+                analysis_type_str,
+                file_path_str
+            );
+            continue;
+        }
 
         // Build the appropriate prompt based on task type and language
-        let (prompt, analysis_type_str) = match task.task_type {
+        let prompt = match task.task_type {
             AnalysisTaskType::ArchitectureFileAnalysis => {
-                let prompt = DiagramExtractor::architecture_file_analysis_prompt(
+                DiagramExtractor::architecture_file_analysis_prompt(
                     &file_path_str,
                     &task.content,
                     task.language,
-                );
-                (prompt, AnalysisType::ArchitectureFileAnalysis.to_string())
+                    &output_language,
+                )
             }
             AnalysisTaskType::DiagramExtraction(diagram_type) => {
-                let prompt = DiagramExtractor::prompt_for_type(
+                match DiagramExtractor::prompt_for_type(
                     diagram_type,
                     &file_path_str,
                     &task.content,
                     task.language,
-                );
-                let analysis_type = format!("diagram_extraction_{}", diagram_type.as_str());
-                (prompt, analysis_type)
+                    &output_language,
+                ) {
+                    Some(prompt) => prompt,
+                    None => {
+                        let error = format!("{} diagrams have no extraction", diagram_type.title());
+                        queue.fail(task.job_id, &error).await;
+                        continue;
+                    }
+                }
             }
             AnalysisTaskType::CodeUnderstanding => {
-                // Use language-specific analysis prompt
-                let prompt = task.language.analysis_prompt(&file_path_str, &task.content);
-                (prompt, AnalysisType::CodeUnderstanding.to_string())
+                let prompt = match &task.delta {
+                    Some(delta) => delta.prompt(&file_path_str, &output_language),
+                    // Use language-specific analysis prompt
+                    None => task.language.analysis_prompt(
+                        &file_path_str,
+                        &task.content,
+                        &output_language,
+                    ),
+                };
+                let prompt = match &task.file_context {
+                    Some(context) => format!("{}\n\n{}", prompt, context),
+                    None => prompt,
+                };
+                match &task.issue_context {
+                    Some(context) => format!("{}\n\n{}", prompt, context),
+                    None => prompt,
+                }
             }
             AnalysisTaskType::DocumentationAnalysis => {
-                let prompt = DiagramExtractor::documentation_analysis_prompt(
+                DiagramExtractor::documentation_analysis_prompt(
                     &file_path_str,
                     &task.content,
                     task.language,
-                );
-                (prompt, AnalysisType::Documentation.to_string())
+                    &output_language,
+                )
             }
         };
 
@@ -2234,25 +5470,125 @@ async fn analysis_worker(
             endpoint.name
         );
 
-        match client.generate(&prompt).await {
-            Ok(result) => {
+        let mut tracker = progress.tracker(
+            &endpoint.name,
+            task.repository_id,
+            &file_path_str,
+            &analysis_type_str,
+        );
+        let started = Instant::now();
+        let requirements = task.task_type.requirements(task.delta.is_some());
+        let generated = match client.generate_stream(&prompt, |_| tracker.token()).await {
+            Ok(response) => {
+                match validation::validate(&response, &requirements, &output_language) {
+                    Ok(()) => Ok(Ok(response)),
+                    Err(problem) => {
+                        tracing::warn!(
+                            "Rejected {} for {}: {}; asking again",
+                            analysis_type_str,
+                            file_path_str,
+                            problem
+                        );
+                        client.record_rejection();
+                        let retry = validation::corrective_prompt(&prompt, &problem);
+                        client
+                            .generate_stream(&retry, |_| tracker.token())
+                            .await
+                            .map(|response| {
+                                validation::validate(&response, &requirements, &output_language)
+                                    .map(|()| response)
+                            })
+                    }
+                }
+            }
+            Err(e) => Err(e),
+        };
+        tracker.finish();
+        if matches!(generated, Ok(Err(_))) {
+            client.record_rejection();
+        }
+        record_usage(
+            &db,
+            &client,
+            &endpoint.name,
+            task.repository_id,
+            &analysis_type_str,
+        )
+        .await;
+
+        match generated {
+            Ok(Err(problem)) => {
+                // The endpoint works, the model just didn't deliver; nothing is
+                // stored, so the next scan tries again
+                tracing::warn!(
+                    "Dropped {} for {} after a second invalid response: {}",
+                    analysis_type_str,
+                    file_path_str,
+                    problem
+                );
+                health.record_success(&endpoint.name);
+                queue
+                    .fail(task.job_id, &format!("Invalid response: {}", problem))
+                    .await;
+            }
+            Ok(Ok(mut result)) => {
                 tracing::info!("Completed {} for: {}", analysis_type_str, file_path_str);
+                health.record_success(&endpoint.name);
 
-                let severity = determine_severity(&result);
+                let mut changes = None;
+                if task.delta.is_some() {
+                    let delta = parse_delta_response(&result);
+                    result = delta.summary;
+                    changes = delta.changes;
+                }
 
-                if let Err(e) = db
-                    .save_analysis_result(
-                        task.repository_id,
+                // Code understanding results get structured findings, which also
+                // determine their severity
+                let mut structured = None;
+                if matches!(task.task_type, AnalysisTaskType::CodeUnderstanding) {
+                    structured = extract_findings(
+                        &client,
                         &file_path_str,
-                        &analysis_type_str,
+                        &task.content,
                         &result,
-                        severity.as_deref(),
-                        Some(&task.content_hash),
+                        &output_language,
                     )
-                    .await
-                {
-                    tracing::warn!("Failed to save {} result: {}", analysis_type_str, e);
+                    .await;
+                    if !task.security {
+                        if let Some(found) = structured.as_mut() {
+                            found.retain(|f| f.category != "security");
+                        }
+                    }
+                    record_usage(
+                        &db,
+                        &client,
+                        &endpoint.name,
+                        task.repository_id,
+                        &analysis_type_str,
+                    )
+                    .await;
                 }
+                let severity = match &structured {
+                    Some(found) => Some(findings::overall_severity(found).to_string()),
+                    None => determine_severity(&result),
+                };
+
+                // Saved with the rest of its batch, which completes the job
+                results
+                    .push(CompletedAnalysis {
+                        result: NewAnalysisResult {
+                            file_path: file_path_str,
+                            analysis_type: analysis_type_str,
+                            result,
+                            severity,
+                            content_hash: Some(task.content_hash.clone()),
+                            attribution: llm_attribution(&endpoint, started),
+                        },
+                        task,
+                        changes,
+                        findings: structured,
+                    })
+                    .await;
             }
             Err(e) => {
                 tracing::warn!(
@@ -2262,6 +5598,10 @@ async fn analysis_worker(
                     file_path_str,
                     e
                 );
+                if !cancel.is_cancelled() {
+                    health.record_failure(&endpoint.name, &e.to_string());
+                }
+                queue.fail(task.job_id, &e.to_string()).await;
             }
         }
     }
@@ -2272,12 +5612,59 @@ async fn analysis_worker(
     );
 }
 
-/// Find the first available endpoint from a list.
-/// Returns the client and endpoint name if found.
-async fn find_available_endpoint(endpoints: &[OllamaEndpoint]) -> Option<(OllamaClient, String)> {
+/// The endpoints allowed to see a repository's content: sensitive repositories
+/// are only sent to local endpoints
+pub(crate) fn endpoints_for(
+    repo: &Repository,
+    endpoints: &[OllamaEndpoint],
+) -> Vec<OllamaEndpoint> {
+    endpoints
+        .iter()
+        .filter(|endpoint| !repo.sensitive || endpoint.is_local())
+        .cloned()
+        .collect()
+}
+
+/// Audit log detail of routing a sensitive repository to `routed` out of `endpoints`
+pub(crate) fn routing_audit_detail(
+    endpoints: &[OllamaEndpoint],
+    routed: &[OllamaEndpoint],
+) -> String {
+    let names = |filter: &dyn Fn(&OllamaEndpoint) -> bool| {
+        endpoints
+            .iter()
+            .filter(|e| filter(e))
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let withheld = names(&|e| !e.is_local());
+    if routed.is_empty() {
+        format!(
+            "Skipped: no local endpoint enabled (withheld from: {})",
+            withheld
+        )
+    } else if withheld.is_empty() {
+        format!("Routed to local endpoints: {}", names(&|e| e.is_local()))
+    } else {
+        format!(
+            "Routed to local endpoints: {} (withheld from: {})",
+            names(&|e| e.is_local()),
+            withheld
+        )
+    }
+}
+
+/// Find the first available endpoint from a list, skipping endpoints whose
+/// circuit is open. Returns the client and endpoint name if found.
+pub(crate) async fn find_available_endpoint(
+    endpoints: &[OllamaEndpoint],
+    health: &HealthMonitor,
+    cancel: &CancellationToken,
+) -> Option<(OllamaClient, String)> {
     for endpoint in endpoints {
-        let client = OllamaClient::new(&endpoint.url, &endpoint.model);
-        if client.is_available().await {
+        if health.is_allowed(&endpoint.name) && health.probe(endpoint, cancel).await {
+            let client = OllamaClient::for_endpoint(endpoint).with_cancellation(cancel.clone());
             return Some((client, endpoint.name.clone()));
         }
         tracing::debug!("Endpoint {} not available, trying next", endpoint.name);
@@ -2285,6 +5672,97 @@ async fn find_available_endpoint(endpoints: &[OllamaEndpoint]) -> Option<(Ollama
     None
 }
 
+/// Ask the model for the structured findings of a code understanding result.
+///
+/// Returns None if the model's response couldn't be used, in which case the
+/// result's severity falls back to the keyword heuristic.
+async fn extract_findings(
+    client: &OllamaClient,
+    file_path: &str,
+    content: &str,
+    analysis: &str,
+    output_language: &str,
+) -> Option<Vec<StructuredFinding>> {
+    let prompt = findings::findings_prompt(file_path, content, analysis, output_language);
+    match client
+        .generate_structured::<FindingsResponse>(&prompt, findings::findings_schema())
+        .await
+    {
+        Ok(response) => Some(findings::normalize_findings(
+            response.findings,
+            content.lines().count(),
+        )),
+        Err(e) => {
+            tracing::warn!("Failed to extract findings for {}: {}", file_path, e);
+            None
+        }
+    }
+}
+
+/// Record the change-log entry and content snapshot of a delta-enabled analysis
+async fn save_delta_history(
+    db: &Database,
+    task: &AnalysisTask,
+    file_path: &str,
+    changes: Option<&str>,
+) {
+    if let Some(entry) = changes {
+        if let Err(e) = db
+            .save_changelog_entry(
+                task.repository_id,
+                file_path,
+                entry,
+                Some(&task.content_hash),
+            )
+            .await
+        {
+            tracing::warn!("Failed to save change log for {}: {}", file_path, e);
+        }
+    }
+    if task.save_snapshot {
+        if let Err(e) = db
+            .save_file_snapshot(
+                task.repository_id,
+                file_path,
+                &task.content_hash,
+                &task.content,
+            )
+            .await
+        {
+            tracing::warn!("Failed to save snapshot of {}: {}", file_path, e);
+        }
+    }
+}
+
+/// Add the tokens `client` used since the last call to the usage table
+async fn record_usage(
+    db: &Database,
+    client: &OllamaClient,
+    endpoint: &str,
+    repository_id: i64,
+    analysis_type: &str,
+) {
+    let usage = client.take_usage();
+    if usage.is_empty() {
+        return;
+    }
+    if let Err(e) = db
+        .record_usage(repository_id, endpoint, analysis_type, &usage)
+        .await
+    {
+        tracing::warn!("Failed to record token usage: {}", e);
+    }
+}
+
+/// Attribute a result to an endpoint's model, timed from `started`
+fn llm_attribution(endpoint: &OllamaEndpoint, started: Instant) -> Attribution {
+    Attribution {
+        endpoint: Some(endpoint.name.clone()),
+        model: Some(endpoint.model.clone()),
+        duration_ms: Some(started.elapsed().as_millis() as i64),
+    }
+}
+
 /// Map keywords in analysis results to severity levels.
 ///
 /// - "critical", "vulnerability", "unsafe" → "warning"
@@ -2303,13 +5781,6 @@ fn determine_severity(result: &str) -> Option<String> {
     }
 }
 
-/// Helper function to wait for shutdown signal (for use in tokio::select!)
-async fn wait_for_stop_signal(should_stop: &AtomicBool) {
-    while !should_stop.load(Ordering::SeqCst) {
-        tokio::time::sleep(Duration::from_millis(100)).await;
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2359,6 +5830,29 @@ mod tests {
         assert_eq!(hash.len(), 64); // SHA256 produces 64 hex chars
     }
 
+    #[test]
+    fn test_diagram_source_hash() {
+        let hashes = |items: &[&str]| items.iter().map(|s| compute_hash(s)).collect::<Vec<_>>();
+        let dot = diagram_source_hash(DiagramFormat::Dot, &hashes(&["a", "b"]));
+
+        assert_eq!(
+            dot,
+            diagram_source_hash(DiagramFormat::Dot, &hashes(&["a", "b"]))
+        );
+        assert_ne!(
+            dot,
+            diagram_source_hash(DiagramFormat::Dot, &hashes(&["a", "c"]))
+        );
+        assert_ne!(
+            dot,
+            diagram_source_hash(DiagramFormat::Dot, &hashes(&["a"]))
+        );
+        assert_eq!(
+            diagram_source_hash(DiagramFormat::Mermaid, &hashes(&["a", "b"])),
+            format!("mermaid:{}", dot)
+        );
+    }
+
     #[test]
     fn test_determine_severity_critical() {
         assert_eq!(
@@ -2444,25 +5938,93 @@ mod tests {
         (Daemon::new(config, db), temp_dir)
     }
 
+    #[tokio::test]
+    async fn test_sensitive_repositories_only_use_local_endpoints() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+        let repo_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(repo_dir.path().join("noctum.toml"), "").unwrap();
+        let id = db
+            .add_repository(&repo_dir.path().to_string_lossy(), "secret")
+            .await
+            .unwrap();
+        let daemon = Daemon::new(Arc::new(RwLock::new(Config::default())), db.clone());
+
+        let endpoint = |name: &str, location| OllamaEndpoint {
+            location,
+            ..OllamaEndpoint::new(
+                name.to_string(),
+                "http://localhost:11434".to_string(),
+                "llama3".to_string(),
+            )
+        };
+        let local = endpoint("desk", crate::config::EndpointLocation::Local);
+        let remote = endpoint("cloud", crate::config::EndpointLocation::Remote);
+        let both = [local.clone(), remote.clone()];
+
+        // Ordinary repositories use every endpoint without audit entries
+        let repo = db.get_repository(id).await.unwrap().unwrap();
+        let routed = daemon.route_endpoints(&repo, &both).await.unwrap();
+        assert_eq!(routed.len(), 2);
+        assert!(db.get_audit_log(10).await.unwrap().is_empty());
+
+        db.set_repository_sensitive(id, true).await.unwrap();
+        let repo = db.get_repository(id).await.unwrap().unwrap();
+        let routed = daemon.route_endpoints(&repo, &both).await.unwrap();
+        assert_eq!(routed.len(), 1);
+        assert_eq!(routed[0].name, "desk");
+        assert!(daemon.route_endpoints(&repo, &[remote]).await.is_none());
+
+        let audit = db.get_audit_log(10).await.unwrap();
+        assert_eq!(audit.len(), 2);
+        assert!(audit.iter().all(|e| e.action == AUDIT_SENSITIVE_ROUTING));
+        assert_eq!(audit[0].repository_name.as_deref(), Some("secret"));
+        assert_eq!(
+            audit[0].detail,
+            "Skipped: no local endpoint enabled (withheld from: cloud)"
+        );
+        assert_eq!(
+            audit[1].detail,
+            "Routed to local endpoints: desk (withheld from: cloud)"
+        );
+    }
+
     #[test]
     fn test_daemon_new() {
         let (daemon, _temp_dir) = create_test_daemon();
         assert_eq!(daemon.status(), DaemonStatus::Waiting);
     }
 
+    #[test]
+    fn test_daemon_status_round_trips() {
+        for status in [
+            DaemonStatus::Waiting,
+            DaemonStatus::Processing,
+            DaemonStatus::Stopping,
+            DaemonStatus::Error,
+        ] {
+            assert_eq!(DaemonStatus::from_u8(status.as_u8()), status);
+        }
+
+        let (daemon, _temp_dir) = create_test_daemon();
+        assert_eq!(daemon.handle().last_error(), None);
+    }
+
     #[test]
     fn test_daemon_trigger_scan() {
         let (daemon, _temp_dir) = create_test_daemon();
         let handle = daemon.handle();
 
-        assert!(!daemon
-            .trigger_scan
-            .load(std::sync::atomic::Ordering::SeqCst));
+        assert!(!daemon.trigger_scan.is_pending());
 
         handle.trigger_scan();
-        assert!(daemon
-            .trigger_scan
-            .load(std::sync::atomic::Ordering::SeqCst));
+        handle.trigger_scan();
+        assert!(daemon.trigger_scan.is_pending());
+        assert!(daemon.trigger_scan.take());
+        assert!(!daemon.trigger_scan.is_pending());
     }
 
     #[test]
@@ -2470,10 +6032,10 @@ mod tests {
         let (daemon, _temp_dir) = create_test_daemon();
         let handle = daemon.handle();
 
-        assert!(!daemon.should_stop.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(!daemon.cancel.is_cancelled());
 
         handle.stop();
-        assert!(daemon.should_stop.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(daemon.cancel.is_cancelled());
     }
 
     #[tokio::test]
@@ -2498,6 +6060,269 @@ mod tests {
         assert_eq!(cfg.schedule.end_hour, 20);
     }
 
+    #[tokio::test]
+    async fn test_result_collector_saves_in_batches() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = Database::new(temp_file.path()).await.unwrap();
+        db.run_migrations().await.unwrap();
+        let repo_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(repo_dir.path().join("noctum.toml"), "").unwrap();
+        let repo_id = db
+            .add_repository(&repo_dir.path().to_string_lossy(), "repo")
+            .await
+            .unwrap();
+        let queue = JobQueue::new(db.clone());
+        let results = ResultCollector::new(db.clone(), queue.clone(), ProgressBroadcaster::new());
+
+        let mut jobs = Vec::new();
+        for i in 0..RESULT_BATCH_SIZE + 2 {
+            let file_path = format!("src/file{}.rs", i);
+            let job_id = queue
+                .enqueue(repo_id, "code_understanding", Some(&file_path))
+                .await;
+            jobs.push((file_path.clone(), job_id.unwrap()));
+            results
+                .push(CompletedAnalysis {
+                    task: AnalysisTask {
+                        repository_id: repo_id,
+                        file_path: PathBuf::from(&file_path),
+                        content: "fn f() {}".to_string(),
+                        content_hash: format!("hash{}", i),
+                        task_type: AnalysisTaskType::CodeUnderstanding,
+                        language: Language::Rust,
+                        job_id,
+                        delta: None,
+                        save_snapshot: false,
+                        issue_context: None,
+                        file_context: None,
+                        security: true,
+                    },
+                    result: NewAnalysisResult {
+                        file_path,
+                        analysis_type: "code_understanding".to_string(),
+                        result: "Fine".to_string(),
+                        severity: None,
+                        content_hash: Some(format!("hash{}", i)),
+                        attribution: Attribution::default(),
+                    },
+                    changes: None,
+                    findings: None,
+                })
+                .await;
+        }
+
+        let saved = |db: Database, file_path: String| async move {
+            db.get_latest_file_hash(repo_id, &file_path, "code_understanding")
+                .await
+                .unwrap()
+        };
+        let status =
+            |db: Database, id: i64| async move { db.get_job(id).await.unwrap().unwrap().status };
+
+        // The first full batch is saved and its jobs completed, the rest waits
+        let (first, _) = &jobs[0];
+        assert_eq!(
+            saved(db.clone(), first.clone()).await,
+            Some("hash0".to_string())
+        );
+        assert_eq!(status(db.clone(), jobs[0].1).await, "done");
+        let (last, last_job) = jobs.last().unwrap();
+        assert_eq!(saved(db.clone(), last.clone()).await, None);
+        assert_eq!(status(db.clone(), *last_job).await, "pending");
+
+        results.flush().await;
+        assert!(saved(db.clone(), last.clone()).await.is_some());
+        assert_eq!(status(db.clone(), *last_job).await, "done");
+    }
+
+    #[tokio::test]
+    async fn test_freshness_of_every_analysis_type() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = Database::new(temp_file.path()).await.unwrap();
+        db.run_migrations().await.unwrap();
+        let repo_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(repo_dir.path().join("noctum.toml"), "").unwrap();
+        let repo_id = db
+            .add_repository(&repo_dir.path().to_string_lossy(), "repo")
+            .await
+            .unwrap();
+
+        for task_type in [
+            AnalysisTaskType::CodeUnderstanding,
+            AnalysisTaskType::ArchitectureFileAnalysis,
+            AnalysisTaskType::DiagramExtraction(DiagramType::DataFlow),
+            AnalysisTaskType::DocumentationAnalysis,
+        ] {
+            let analysis_type = task_type.analysis_type();
+            assert_eq!(
+                freshness(&db, repo_id, "src/lib.rs", &analysis_type, "v1").await,
+                Freshness::New
+            );
+
+            db.save_analysis_result(
+                repo_id,
+                "src/lib.rs",
+                &analysis_type,
+                "result",
+                None,
+                Some("v1"),
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                freshness(&db, repo_id, "src/lib.rs", &analysis_type, "v1").await,
+                Freshness::Unchanged
+            );
+            assert_eq!(
+                freshness(&db, repo_id, "src/lib.rs", &analysis_type, "v2").await,
+                Freshness::Changed
+            );
+        }
+
+        // A requested reanalysis overrides the hash until a scan clears it
+        db.request_reanalysis(repo_id, "src/lib.rs").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let analysis_type = AnalysisTaskType::CodeUnderstanding.analysis_type();
+        assert_eq!(
+            freshness(&db, repo_id, "src/lib.rs", &analysis_type, "v1").await,
+            Freshness::New
+        );
+        // Requests made after the scan started are kept for the next one
+        let started_at = crate::db::now_millis();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        db.request_reanalysis(repo_id, "src/main.rs").await.unwrap();
+        assert_eq!(
+            db.clear_reanalysis_requests(repo_id, &started_at)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            db.get_reanalysis_requests(repo_id).await.unwrap(),
+            ["src/main.rs"]
+        );
+        assert_eq!(
+            freshness(&db, repo_id, "src/lib.rs", &analysis_type, "v1").await,
+            Freshness::Unchanged
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_stale_results() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = Database::new(temp_file.path()).await.unwrap();
+        db.run_migrations().await.unwrap();
+        let repo_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(repo_dir.path().join("noctum.toml"), "").unwrap();
+        let kept = repo_dir.path().join("kept.rs");
+        let deleted = repo_dir.path().join("deleted.rs");
+        std::fs::write(&kept, "fn kept() {}").unwrap();
+        let repo_id = db
+            .add_repository(&repo_dir.path().to_string_lossy(), "repo")
+            .await
+            .unwrap();
+        for file in [
+            kept.to_string_lossy().to_string(),
+            deleted.to_string_lossy().to_string(),
+            "[repo] Architecture Summary".to_string(),
+        ] {
+            db.save_analysis_result(
+                repo_id,
+                &file,
+                "code_understanding",
+                "result",
+                None,
+                None,
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let daemon = Daemon::new(Arc::new(RwLock::new(Config::default())), db.clone());
+        let repo = db.get_repository(repo_id).await.unwrap().unwrap();
+        daemon.reconcile_stale_results(&repo).await;
+        let stale: Vec<_> = db
+            .get_result_files(repo_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|(_, stale)| *stale)
+            .map(|(file, _)| file)
+            .collect();
+        assert_eq!(stale, [deleted.to_string_lossy().to_string()]);
+
+        // A file that comes back is current again
+        std::fs::write(&deleted, "fn back() {}").unwrap();
+        daemon.reconcile_stale_results(&repo).await;
+        assert_eq!(db.count_stale_files(repo_id).await.unwrap(), 0);
+
+        // Nothing is marked while the repository itself is missing
+        let missing = crate::db::Repository {
+            path: repo_dir.path().join("gone").to_string_lossy().to_string(),
+            ..repo
+        };
+        std::fs::remove_file(&kept).unwrap();
+        daemon.reconcile_stale_results(&missing).await;
+        assert_eq!(db.count_stale_files(repo_id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_pause_overrides_schedule() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = Database::new(temp_file.path()).await.unwrap();
+        db.run_migrations().await.unwrap();
+
+        let mut config = Config::default();
+        config.schedule.start_hour = 0;
+        config.schedule.end_hour = 24;
+        let daemon = Daemon::new(Arc::new(RwLock::new(config)), db.clone());
+        assert!(daemon.is_in_schedule().await);
+
+        let tomorrow = chrono::Utc::now() + chrono::Duration::days(1);
+        db.set_paused_until(Some(&tomorrow.to_rfc3339()))
+            .await
+            .unwrap();
+        assert!(schedule_paused_until(&db).await.is_some());
+        assert!(!daemon.is_in_schedule().await);
+
+        // An expired pause no longer applies
+        let yesterday = chrono::Utc::now() - chrono::Duration::days(1);
+        db.set_paused_until(Some(&yesterday.to_rfc3339()))
+            .await
+            .unwrap();
+        assert!(schedule_paused_until(&db).await.is_none());
+        assert!(daemon.is_in_schedule().await);
+    }
+
+    #[tokio::test]
+    async fn test_handle_pauses_and_resumes() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = Database::new(temp_file.path()).await.unwrap();
+        let daemon = Daemon::new(Arc::new(RwLock::new(Config::default())), db);
+        let handle = daemon.handle();
+        let mut updates = handle.subscribe_updates();
+
+        handle.pause();
+        handle.pause();
+        assert!(handle.is_paused());
+        assert!(daemon.pause.is_paused());
+        handle.resume();
+        assert!(!handle.is_paused());
+
+        // Repeated calls don't announce anything
+        assert_eq!(
+            updates.recv().await.unwrap(),
+            DashboardUpdate::Paused { paused: true }
+        );
+        assert_eq!(
+            updates.recv().await.unwrap(),
+            DashboardUpdate::Paused { paused: false }
+        );
+        assert!(updates.try_recv().is_err());
+    }
+
     // =========================================================================
     // Language::Rust.find_source_files tests
     // =========================================================================
@@ -2563,10 +6388,37 @@ mod tests {
             .any(|f| f.to_string_lossy().contains(".hidden")));
     }
 
+    #[test]
+    fn test_find_included_source_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let crate_dir = temp_dir.path().join("core");
+        std::fs::create_dir_all(crate_dir.join("src")).unwrap();
+        std::fs::create_dir_all(crate_dir.join("tests/fixtures")).unwrap();
+        std::fs::write(crate_dir.join("src/lib.rs"), "pub fn lib() {}").unwrap();
+        std::fs::write(crate_dir.join("src/api_generated.rs"), "pub fn api() {}").unwrap();
+        std::fs::write(crate_dir.join("tests/fixtures/input.rs"), "fn f() {}").unwrap();
+
+        // Globs are relative to the repository root, not the project
+        let filter = FileFilter::new(
+            &["core/**".to_string()],
+            &["**/fixtures/**".to_string(), "*_generated.rs".to_string()],
+        )
+        .unwrap();
+        let files = Language::Rust
+            .find_included_source_files(&crate_dir, temp_dir.path(), &filter)
+            .unwrap();
+
+        assert_eq!(files, vec![crate_dir.join("src/lib.rs")]);
+    }
+
     // =========================================================================
     // copy_dir_with_ignore tests
     // =========================================================================
 
+    fn default_plan(src: &Path) -> SnapshotPlan {
+        SnapshotPlan::new(src, &SnapshotConfig::default())
+    }
+
     #[test]
     fn test_copy_dir_with_ignore_no_patterns() {
         let src = tempfile::TempDir::new().unwrap();
@@ -2577,7 +6429,7 @@ mod tests {
         std::fs::create_dir_all(src.path().join("subdir")).unwrap();
         std::fs::write(src.path().join("subdir/nested.txt"), "nested").unwrap();
 
-        copy_dir_with_ignore(src.path(), dest.path(), &[]).unwrap();
+        copy_dir_with_ignore(src.path(), dest.path(), &[], &default_plan(src.path())).unwrap();
 
         // Verify files were copied
         assert!(dest.path().join("file.txt").exists());
@@ -2599,7 +6451,13 @@ mod tests {
         .unwrap();
 
         let ignore_patterns = vec!["node_modules".to_string()];
-        copy_dir_with_ignore(src.path(), dest.path(), &ignore_patterns).unwrap();
+        copy_dir_with_ignore(
+            src.path(),
+            dest.path(),
+            &ignore_patterns,
+            &default_plan(src.path()),
+        )
+        .unwrap();
 
         // Verify index.js was copied but node_modules was not
         assert!(dest.path().join("index.js").exists());
@@ -2626,7 +6484,13 @@ mod tests {
         .unwrap();
 
         let ignore_patterns = vec!["node_modules".to_string()];
-        copy_dir_with_ignore(src.path(), dest.path(), &ignore_patterns).unwrap();
+        copy_dir_with_ignore(
+            src.path(),
+            dest.path(),
+            &ignore_patterns,
+            &default_plan(src.path()),
+        )
+        .unwrap();
 
         // Verify structure without node_modules
         assert!(dest.path().join("package.json").exists());
@@ -2653,7 +6517,13 @@ mod tests {
             "node_modules".to_string(),
             ".git".to_string(),
         ];
-        copy_dir_with_ignore(src.path(), dest.path(), &ignore_patterns).unwrap();
+        copy_dir_with_ignore(
+            src.path(),
+            dest.path(),
+            &ignore_patterns,
+            &default_plan(src.path()),
+        )
+        .unwrap();
 
         // Verify only main.rs was copied
         assert!(dest.path().join("main.rs").exists());
@@ -2674,7 +6544,13 @@ mod tests {
         std::fs::write(src.path().join("dist/main.js"), "compiled").unwrap();
 
         let ignore_patterns = vec!["dist".to_string()];
-        copy_dir_with_ignore(src.path(), dest.path(), &ignore_patterns).unwrap();
+        copy_dir_with_ignore(
+            src.path(),
+            dest.path(),
+            &ignore_patterns,
+            &default_plan(src.path()),
+        )
+        .unwrap();
 
         assert!(dest.path().join("src/main.ts").exists());
         assert!(!dest.path().join("dist").exists());
@@ -2690,7 +6566,7 @@ mod tests {
         std::fs::write(src.path().join("target/binary"), "binary data").unwrap();
 
         let ignore_patterns = vec!["target".to_string()];
-        let temp_dir = copy_repo_to_temp(src.path(), &ignore_patterns)
+        let temp_dir = copy_repo_to_temp(src.path(), &ignore_patterns, &SnapshotConfig::default())
             .await
             .unwrap();
 
@@ -2699,6 +6575,82 @@ mod tests {
         assert!(!temp_dir.path().join("target").exists());
     }
 
+    #[tokio::test]
+    async fn test_copy_repo_to_temp_sources_only() {
+        let src = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(src.path().join("src")).unwrap();
+        std::fs::write(src.path().join("Cargo.toml"), "[package]").unwrap();
+        std::fs::write(src.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(src.path().join("logo.png"), "png").unwrap();
+        std::fs::create_dir_all(src.path().join("target/debug")).unwrap();
+        std::fs::write(src.path().join("target/debug/app.rs"), "").unwrap();
+
+        let snapshot = SnapshotConfig {
+            strategy: crate::config::SnapshotStrategy::Sources,
+            respect_gitignore: false,
+        };
+        let temp_dir = copy_repo_to_temp(src.path(), &[], &snapshot).await.unwrap();
+
+        assert!(temp_dir.path().join("Cargo.toml").exists());
+        assert!(temp_dir.path().join("src/main.rs").exists());
+        assert!(!temp_dir.path().join("logo.png").exists());
+        assert!(!temp_dir.path().join("target").exists());
+    }
+
+    #[tokio::test]
+    async fn test_hardlink_snapshot_baseline_leaves_repository_untouched() {
+        let src = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(src.path().join("src")).unwrap();
+        std::fs::write(
+            src.path().join("Cargo.toml"),
+            "[package]\nname = \"snap\"\nversion = \"0.2.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        std::fs::write(src.path().join("src/lib.rs"), "pub fn f() -> u8 { 1 }\n").unwrap();
+        // Stale: cargo rewrites it with the package's current version
+        std::fs::write(
+            src.path().join("Cargo.lock"),
+            "version = 3\n\n[[package]]\nname = \"snap\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        let contents = |root: &Path| -> Vec<(PathBuf, Vec<u8>)> {
+            let mut files: Vec<(PathBuf, Vec<u8>)> = walkdir::WalkDir::new(root)
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_file())
+                .map(|entry| {
+                    let relative = entry.path().strip_prefix(root).unwrap().to_path_buf();
+                    (relative, std::fs::read(entry.path()).unwrap())
+                })
+                .collect();
+            files.sort();
+            files
+        };
+        let before = contents(src.path());
+
+        let snapshot = SnapshotConfig {
+            strategy: crate::config::SnapshotStrategy::Hardlink,
+            respect_gitignore: false,
+        };
+        let temp_dir = copy_repo_to_temp(src.path(), &[], &snapshot).await.unwrap();
+        let runner = CommandRunner::detect(temp_dir.path(), temp_dir.path());
+        let result = run_command_with_timeout(
+            &runner,
+            temp_dir.path(),
+            "cargo check --offline --quiet",
+            300,
+            &CancellationToken::new(),
+        )
+        .await;
+        assert!(result.success, "{}", result.output);
+        assert_ne!(
+            std::fs::read(temp_dir.path().join("Cargo.lock")).unwrap(),
+            std::fs::read(src.path().join("Cargo.lock")).unwrap()
+        );
+
+        assert_eq!(contents(src.path()), before);
+    }
+
     #[tokio::test]
     async fn test_copy_repo_to_temp_empty_ignore() {
         let src = tempfile::TempDir::new().unwrap();
@@ -2708,10 +6660,31 @@ mod tests {
         std::fs::create_dir_all(src.path().join("subdir")).unwrap();
         std::fs::write(src.path().join("subdir/nested.txt"), "nested").unwrap();
 
-        let temp_dir = copy_repo_to_temp(src.path(), &[]).await.unwrap();
+        let temp_dir = copy_repo_to_temp(src.path(), &[], &SnapshotConfig::default())
+            .await
+            .unwrap();
 
         // Verify all files were copied
         assert!(temp_dir.path().join("file.txt").exists());
         assert!(temp_dir.path().join("subdir/nested.txt").exists());
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_dir_skips_ancestor_symlinks() {
+        let src = tempfile::TempDir::new().unwrap();
+        let dest = tempfile::TempDir::new().unwrap();
+
+        std::fs::create_dir_all(src.path().join("a/b")).unwrap();
+        std::fs::write(src.path().join("a/b/file.txt"), "content").unwrap();
+        std::os::unix::fs::symlink("..", src.path().join("a/b/loop")).unwrap();
+        std::os::unix::fs::symlink("a/b", src.path().join("shortcut")).unwrap();
+
+        copy_dir_with_ignore(src.path(), dest.path(), &[], &default_plan(src.path())).unwrap();
+
+        assert!(dest.path().join("a/b/file.txt").exists());
+        assert!(!dest.path().join("a/b/loop").exists());
+        // Links to non-ancestor directories are still copied
+        assert!(dest.path().join("shortcut/file.txt").exists());
+    }
 }
@@ -0,0 +1,73 @@
+//! Pausing the daemon from the dashboard or API.
+//!
+//! Unlike a schedule pause, which only suspends scheduled processing and is
+//! stored in the database, this switch stops all processing (scheduled scans,
+//! manual triggers and watched file changes) until it is flipped back. Triggers
+//! arriving while paused stay pending and run after resuming. A running cycle
+//! stops before its next repository. The switch is kept in memory, so a
+//! restarted daemon starts unpaused.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Shared pause flag of the daemon
+#[derive(Clone, Default)]
+pub struct PauseSwitch {
+    paused: Arc<AtomicBool>,
+    /// Wakes the daemon loop when processing is resumed
+    resumed: Arc<Notify>,
+}
+
+impl PauseSwitch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pause processing. Returns whether the daemon was running before.
+    pub fn pause(&self) -> bool {
+        !self.paused.swap(true, Ordering::SeqCst)
+    }
+
+    /// Resume processing. Returns whether the daemon was paused before.
+    pub fn resume(&self) -> bool {
+        let was_paused = self.paused.swap(false, Ordering::SeqCst);
+        if was_paused {
+            self.resumed.notify_one();
+        }
+        was_paused
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Wait until processing is resumed
+    pub async fn resumed(&self) {
+        self.resumed.notified().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::{timeout, Duration};
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let switch = PauseSwitch::new();
+        assert!(!switch.is_paused());
+        assert!(!switch.resume());
+
+        assert!(switch.pause());
+        assert!(!switch.pause());
+        assert!(switch.clone().is_paused());
+
+        assert!(switch.resume());
+        assert!(!switch.is_paused());
+        // Resuming wakes the daemon loop even if it wasn't waiting yet
+        timeout(Duration::from_secs(1), switch.resumed())
+            .await
+            .unwrap();
+    }
+}
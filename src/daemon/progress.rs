@@ -0,0 +1,212 @@
+//! Live analysis progress and dashboard updates.
+//!
+//! Workers publish progress while an LLM response is streaming in, and the web
+//! server forwards the events to the dashboard over Server-Sent Events
+//! (`/api/events`). The daemon also publishes [`DashboardUpdate`]s when its
+//! state changes or a result is saved, which the web server forwards over
+//! WebSocket (`/ws`) so open pages update without a refresh. Events are
+//! best-effort: nobody listening is not an error, and slow listeners miss
+//! events rather than slowing down analysis.
+
+use super::DaemonStatus;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Number of buffered events per subscriber before old events are dropped
+const EVENT_BUFFER: usize = 256;
+
+/// Publish a progress update every this many tokens
+const TOKENS_PER_UPDATE: usize = 25;
+
+/// Progress of a single LLM generation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressEvent {
+    pub endpoint: String,
+    pub repository_id: i64,
+    pub file_path: String,
+    pub analysis_type: String,
+    /// Tokens received so far
+    pub tokens: usize,
+    /// True once the generation has finished (successfully or not)
+    pub done: bool,
+}
+
+/// A change the dashboard shows live
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DashboardUpdate {
+    /// The daemon's state or current task changed
+    Status {
+        state: DaemonStatus,
+        task: Option<String>,
+    },
+    /// Processing was paused or resumed from the dashboard or API
+    Paused { paused: bool },
+    /// An analysis result was saved
+    AnalysisResult {
+        repository_id: i64,
+        file_path: String,
+        analysis_type: String,
+        severity: Option<String>,
+    },
+    /// A mutation was tested
+    MutationResult {
+        repository_id: i64,
+        file_path: String,
+        description: String,
+        test_outcome: String,
+    },
+}
+
+/// Broadcasts progress events and dashboard updates to any number of subscribers
+#[derive(Clone)]
+pub struct ProgressBroadcaster {
+    sender: broadcast::Sender<ProgressEvent>,
+    updates: broadcast::Sender<DashboardUpdate>,
+}
+
+impl Default for ProgressBroadcaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressBroadcaster {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        let (updates, _) = broadcast::channel(EVENT_BUFFER);
+        Self { sender, updates }
+    }
+
+    /// Subscribe to progress events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Subscribe to dashboard updates published from now on
+    pub fn subscribe_updates(&self) -> broadcast::Receiver<DashboardUpdate> {
+        self.updates.subscribe()
+    }
+
+    /// Publish a dashboard update
+    pub fn publish(&self, update: DashboardUpdate) {
+        // Sending only fails when there are no subscribers
+        let _ = self.updates.send(update);
+    }
+
+    /// Start tracking a generation
+    pub fn tracker(
+        &self,
+        endpoint: &str,
+        repository_id: i64,
+        file_path: &str,
+        analysis_type: &str,
+    ) -> ProgressTracker {
+        let tracker = ProgressTracker {
+            sender: self.sender.clone(),
+            event: ProgressEvent {
+                endpoint: endpoint.to_string(),
+                repository_id,
+                file_path: file_path.to_string(),
+                analysis_type: analysis_type.to_string(),
+                tokens: 0,
+                done: false,
+            },
+        };
+        tracker.publish();
+        tracker
+    }
+}
+
+/// Tracks the token count of one generation and publishes throttled updates
+pub struct ProgressTracker {
+    sender: broadcast::Sender<ProgressEvent>,
+    event: ProgressEvent,
+}
+
+impl ProgressTracker {
+    /// Record a streamed token
+    pub fn token(&mut self) {
+        self.event.tokens += 1;
+        if self.event.tokens.is_multiple_of(TOKENS_PER_UPDATE) {
+            self.publish();
+        }
+    }
+
+    /// Mark the generation as finished
+    pub fn finish(mut self) {
+        self.event.done = true;
+        self.publish();
+    }
+
+    fn publish(&self) {
+        // Sending only fails when there are no subscribers
+        let _ = self.sender.send(self.event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tracker_publishes_throttled_updates() {
+        let broadcaster = ProgressBroadcaster::new();
+        let mut rx = broadcaster.subscribe();
+
+        let mut tracker = broadcaster.tracker("local", 1, "src/main.rs", "code_understanding");
+        for _ in 0..(TOKENS_PER_UPDATE * 2 + 3) {
+            tracker.token();
+        }
+        tracker.finish();
+
+        let started = rx.recv().await.unwrap();
+        assert_eq!(started.tokens, 0);
+        assert_eq!(started.file_path, "src/main.rs");
+        assert!(!started.done);
+
+        assert_eq!(rx.recv().await.unwrap().tokens, TOKENS_PER_UPDATE);
+        assert_eq!(rx.recv().await.unwrap().tokens, TOKENS_PER_UPDATE * 2);
+
+        let finished = rx.recv().await.unwrap();
+        assert_eq!(finished.tokens, TOKENS_PER_UPDATE * 2 + 3);
+        assert!(finished.done);
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_updates_are_tagged() {
+        let broadcaster = ProgressBroadcaster::new();
+        let mut rx = broadcaster.subscribe_updates();
+
+        broadcaster.publish(DashboardUpdate::Status {
+            state: DaemonStatus::Processing,
+            task: Some("analyzing app".to_string()),
+        });
+        broadcaster.publish(DashboardUpdate::MutationResult {
+            repository_id: 1,
+            file_path: "src/lib.rs".to_string(),
+            description: "Changed > to >=".to_string(),
+            test_outcome: "killed".to_string(),
+        });
+
+        assert_eq!(
+            serde_json::to_value(rx.recv().await.unwrap()).unwrap(),
+            serde_json::json!({"type": "status", "state": "processing", "task": "analyzing app"})
+        );
+        let mutation = serde_json::to_value(rx.recv().await.unwrap()).unwrap();
+        assert_eq!(mutation["type"], "mutation_result");
+        assert_eq!(mutation["test_outcome"], "killed");
+    }
+
+    #[test]
+    fn test_publish_without_subscribers() {
+        let broadcaster = ProgressBroadcaster::new();
+        let mut tracker = broadcaster.tracker("local", 1, "a.rs", "documentation");
+        tracker.token();
+        tracker.finish();
+        broadcaster.publish(DashboardUpdate::Status {
+            state: DaemonStatus::Waiting,
+            task: None,
+        });
+    }
+}
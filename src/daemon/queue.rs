@@ -0,0 +1,434 @@
+//! Persistent job queue for daemon work.
+//!
+//! Every per-file analysis and repository-level phase (summary, diagrams, mutation
+//! testing) is recorded as a job in the `jobs` table before it runs. Jobs that were
+//! running when the daemon stopped are reset to pending on the next start, and
+//! repositories with pending jobs are processed first so interrupted work resumes.
+//!
+//! Pending jobs can be removed or reprioritized through the API (`/api/queue`).
+//! A removed job is skipped when a worker picks it up (the work is enqueued
+//! again by a later scan if it is still needed), and priorities order the
+//! repositories and files at the start of the next pass.
+
+use crate::db::Database;
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// How long finished jobs are kept for display before being deleted
+const FINISHED_JOB_RETENTION_DAYS: u32 = 7;
+
+/// Lifecycle status of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Set by the database default when a job is enqueued
+    #[allow(dead_code)]
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Repository-level job types (per-file jobs use the analysis type name)
+pub const JOB_ARCHITECTURE_SUMMARY: &str = "architecture_summary";
+pub const JOB_DIAGRAMS: &str = "diagrams";
+pub const JOB_MUTATION_TESTING: &str = "mutation_testing";
+
+/// Handle to the persistent job queue.
+///
+/// Queue bookkeeping must never break analysis, so failures to record a status
+/// change are logged rather than returned.
+#[derive(Clone)]
+pub struct JobQueue {
+    db: Database,
+}
+
+impl JobQueue {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Record a job as pending. Returns None if the job could not be recorded.
+    pub async fn enqueue(
+        &self,
+        repository_id: i64,
+        job_type: &str,
+        file_path: Option<&str>,
+    ) -> Option<i64> {
+        match self
+            .db
+            .enqueue_job(repository_id, job_type, file_path)
+            .await
+        {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::warn!("Failed to enqueue {} job: {}", job_type, e);
+                None
+            }
+        }
+    }
+
+    /// Mark a job as running
+    pub async fn start(&self, job_id: Option<i64>) {
+        self.set_status(job_id, JobStatus::Running, None).await;
+    }
+
+    /// Mark a pending job as running on `endpoint`. Returns false if the job was
+    /// removed from the queue, in which case its work should be skipped.
+    pub async fn claim(&self, job_id: Option<i64>, endpoint: Option<&str>) -> bool {
+        let Some(id) = job_id else {
+            return true;
+        };
+
+        match self.db.claim_job(id, endpoint).await {
+            Ok(claimed) => claimed,
+            Err(e) => {
+                tracing::warn!("Failed to mark job {} as running: {}", id, e);
+                true
+            }
+        }
+    }
+
+    /// Mark a job as done
+    pub async fn complete(&self, job_id: Option<i64>) {
+        self.set_status(job_id, JobStatus::Done, None).await;
+    }
+
+    /// Mark a job as failed with an error message
+    pub async fn fail(&self, job_id: Option<i64>, error: &str) {
+        self.set_status(job_id, JobStatus::Failed, Some(error))
+            .await;
+    }
+
+    /// Mark a job as done or failed based on the outcome of its work
+    pub async fn finish<T>(&self, job_id: Option<i64>, result: &Result<T>) {
+        match result {
+            Ok(_) => self.complete(job_id).await,
+            Err(e) => self.fail(job_id, &e.to_string()).await,
+        }
+    }
+
+    async fn set_status(&self, job_id: Option<i64>, status: JobStatus, error: Option<&str>) {
+        let Some(id) = job_id else {
+            return;
+        };
+
+        if let Err(e) = self.db.update_job_status(id, status.as_str(), error).await {
+            tracing::warn!("Failed to mark job {} as {}: {}", id, status, e);
+        }
+    }
+
+    /// Prepare the queue after a daemon start: reset interrupted jobs to pending
+    /// and drop old finished jobs.
+    pub async fn recover(&self) -> Result<u64> {
+        let reset = self.db.reset_running_jobs().await?;
+        if reset > 0 {
+            tracing::info!("Resuming {} interrupted job(s) from previous run", reset);
+        }
+
+        let purged = self
+            .db
+            .delete_finished_jobs(FINISHED_JOB_RETENTION_DAYS)
+            .await?;
+        tracing::debug!("Purged {} finished job(s)", purged);
+
+        Ok(reset)
+    }
+
+    /// Repository IDs with unfinished work, to be processed first
+    pub async fn repositories_with_pending_jobs(&self) -> Result<Vec<i64>> {
+        self.db.get_repositories_with_pending_jobs().await
+    }
+
+    /// Priorities of files with pending jobs in a repository
+    pub async fn file_priorities(&self, repository_id: i64) -> HashMap<String, i64> {
+        match self.db.get_pending_file_priorities(repository_id).await {
+            Ok(priorities) => priorities.into_iter().collect(),
+            Err(e) => {
+                tracing::warn!("Failed to read job priorities: {}", e);
+                HashMap::new()
+            }
+        }
+    }
+
+    /// Discard pending jobs for a repository after a complete pass, since anything
+    /// left over was superseded (e.g. the file no longer exists or is unchanged)
+    pub async fn discard_pending(&self, repository_id: i64) -> Result<u64> {
+        self.db.delete_pending_jobs(repository_id).await
+    }
+}
+
+/// Order repositories so those with pending jobs come first (in the order of
+/// `pending`), preserving the original order otherwise.
+pub fn prioritize_pending<T>(repos: &mut [T], pending: &[i64], id: impl Fn(&T) -> i64) {
+    repos.sort_by_key(|repo| {
+        pending
+            .iter()
+            .position(|&p| p == id(repo))
+            .unwrap_or(usize::MAX)
+    });
+}
+
+/// Order files by the priority of their pending jobs (highest first; files
+/// without pending jobs count as priority 0), preserving the original order
+/// otherwise.
+pub fn prioritize_files<T>(
+    files: &mut [T],
+    priorities: &HashMap<String, i64>,
+    path: impl Fn(&T) -> String,
+) {
+    if priorities.is_empty() {
+        return;
+    }
+    files.sort_by_key(|file| std::cmp::Reverse(priorities.get(&path(file)).copied().unwrap_or(0)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn create_test_queue() -> (JobQueue, Database, i64, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::fs::write(repo_dir.join("noctum.toml"), "").unwrap();
+        let repo_id = db
+            .add_repository(&repo_dir.to_string_lossy(), "repo")
+            .await
+            .unwrap();
+
+        (JobQueue::new(db.clone()), db, repo_id, temp_dir)
+    }
+
+    #[test]
+    fn test_job_status_as_str() {
+        assert_eq!(JobStatus::Pending.as_str(), "pending");
+        assert_eq!(JobStatus::Running.as_str(), "running");
+        assert_eq!(JobStatus::Done.as_str(), "done");
+        assert_eq!(JobStatus::Failed.to_string(), "failed");
+    }
+
+    #[test]
+    fn test_prioritize_pending() {
+        let mut repos = vec![1, 2, 3, 4];
+        prioritize_pending(&mut repos, &[3, 4], |r| *r);
+        assert_eq!(repos, vec![3, 4, 1, 2]);
+    }
+
+    #[test]
+    fn test_prioritize_pending_keeps_pending_order() {
+        let mut repos = vec![1, 2, 3, 4];
+        prioritize_pending(&mut repos, &[4, 2], |r| *r);
+        assert_eq!(repos, vec![4, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_prioritize_files() {
+        let mut files = vec!["a.rs", "b.rs", "c.rs", "d.rs"];
+        let priorities = HashMap::from([
+            ("c.rs".to_string(), 5),
+            ("a.rs".to_string(), -1),
+            ("d.rs".to_string(), 0),
+        ]);
+        prioritize_files(&mut files, &priorities, |f| f.to_string());
+        assert_eq!(files, vec!["c.rs", "b.rs", "d.rs", "a.rs"]);
+    }
+
+    #[tokio::test]
+    async fn test_removed_jobs_are_not_claimed() {
+        let (queue, db, repo_id, _temp) = create_test_queue().await;
+
+        let kept = queue
+            .enqueue(repo_id, "code_understanding", Some("a.rs"))
+            .await;
+        let removed = queue
+            .enqueue(repo_id, "code_understanding", Some("b.rs"))
+            .await;
+        assert!(db.set_job_priority(kept.unwrap(), 3).await.unwrap());
+        assert!(db.delete_pending_job(removed.unwrap()).await.unwrap());
+
+        assert!(!queue.claim(removed, Some("gpu")).await);
+        assert!(queue.claim(kept, Some("gpu")).await);
+        // Jobs that failed to be recorded still run
+        assert!(queue.claim(None, None).await);
+
+        let job = db.get_job(kept.unwrap()).await.unwrap().unwrap();
+        assert_eq!(job.status, "running");
+        assert_eq!(job.priority, 3);
+        assert_eq!(job.endpoint.as_deref(), Some("gpu"));
+
+        // Running jobs can't be removed or reprioritized
+        assert!(!db.delete_pending_job(kept.unwrap()).await.unwrap());
+        assert!(!db.set_job_priority(kept.unwrap(), 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_priorities_order_queue() {
+        let (queue, db, repo_id, temp) = create_test_queue().await;
+        let other_dir = temp.path().join("other");
+        std::fs::create_dir_all(&other_dir).unwrap();
+        std::fs::write(other_dir.join("noctum.toml"), "").unwrap();
+        let other_id = db
+            .add_repository(&other_dir.to_string_lossy(), "other")
+            .await
+            .unwrap();
+
+        let first = queue
+            .enqueue(repo_id, "code_understanding", Some("a.rs"))
+            .await;
+        let urgent = queue
+            .enqueue(other_id, "code_understanding", Some("b.rs"))
+            .await;
+        queue.enqueue(other_id, "documentation", Some("b.rs")).await;
+        db.set_job_priority(urgent.unwrap(), 10).await.unwrap();
+
+        assert_eq!(
+            queue.repositories_with_pending_jobs().await.unwrap(),
+            vec![other_id, repo_id]
+        );
+        assert_eq!(
+            queue.file_priorities(other_id).await,
+            HashMap::from([("b.rs".to_string(), 10)])
+        );
+
+        let queued = db.get_queued_jobs().await.unwrap();
+        assert_eq!(queued.len(), 3);
+        assert_eq!(queued[0].id, urgent.unwrap());
+        assert_eq!(queued[1].id, first.unwrap());
+        assert_eq!(queued[0].repository_name.as_deref(), Some("other"));
+    }
+
+    #[tokio::test]
+    async fn test_job_lifecycle() {
+        let (queue, db, repo_id, _temp) = create_test_queue().await;
+
+        let id = queue
+            .enqueue(repo_id, "code_understanding", Some("/repo/src/main.rs"))
+            .await;
+        let job = db.get_job(id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(job.status, "pending");
+        assert_eq!(job.repository_name.as_deref(), Some("repo"));
+
+        queue.start(id).await;
+        let job = db.get_job(id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(job.status, "running");
+        assert!(job.started_at.is_some());
+
+        queue.complete(id).await;
+        let job = db.get_job(id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(job.status, "done");
+        assert!(job.finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_finish_records_error() {
+        let (queue, db, repo_id, _temp) = create_test_queue().await;
+
+        let id = queue.enqueue(repo_id, JOB_MUTATION_TESTING, None).await;
+        queue.start(id).await;
+        queue
+            .finish::<()>(id, &Err(anyhow::anyhow!("baseline failed")))
+            .await;
+
+        let job = db.get_job(id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(job.status, "failed");
+        assert_eq!(job.error.as_deref(), Some("baseline failed"));
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_reuses_pending_job() {
+        let (queue, _db, repo_id, _temp) = create_test_queue().await;
+
+        let first = queue
+            .enqueue(repo_id, "documentation", Some("README.md"))
+            .await;
+        let second = queue
+            .enqueue(repo_id, "documentation", Some("README.md"))
+            .await;
+        assert_eq!(first, second);
+
+        let summary = queue.enqueue(repo_id, JOB_DIAGRAMS, None).await;
+        let summary_again = queue.enqueue(repo_id, JOB_DIAGRAMS, None).await;
+        assert_eq!(summary, summary_again);
+        assert_ne!(first, summary);
+
+        // Once a job has started, new work gets a new job
+        queue.start(first).await;
+        let third = queue
+            .enqueue(repo_id, "documentation", Some("README.md"))
+            .await;
+        assert_ne!(first, third);
+    }
+
+    #[tokio::test]
+    async fn test_recover_resets_running_jobs() {
+        let (queue, db, repo_id, _temp) = create_test_queue().await;
+
+        let running = queue
+            .enqueue(repo_id, "code_understanding", Some("a.rs"))
+            .await;
+        queue.start(running).await;
+        let done = queue
+            .enqueue(repo_id, "code_understanding", Some("b.rs"))
+            .await;
+        queue.start(done).await;
+        queue.complete(done).await;
+
+        assert_eq!(queue.recover().await.unwrap(), 1);
+
+        let job = db.get_job(running.unwrap()).await.unwrap().unwrap();
+        assert_eq!(job.status, "pending");
+        assert!(job.started_at.is_none());
+        assert_eq!(
+            queue.repositories_with_pending_jobs().await.unwrap(),
+            vec![repo_id]
+        );
+
+        let counts = db.get_job_counts().await.unwrap();
+        assert_eq!(counts.pending, 1);
+        assert_eq!(counts.done, 1);
+    }
+
+    #[tokio::test]
+    async fn test_discard_pending() {
+        let (queue, db, repo_id, _temp) = create_test_queue().await;
+
+        queue
+            .enqueue(repo_id, "code_understanding", Some("a.rs"))
+            .await;
+        let done = queue
+            .enqueue(repo_id, "code_understanding", Some("b.rs"))
+            .await;
+        queue.complete(done).await;
+
+        assert_eq!(queue.discard_pending(repo_id).await.unwrap(), 1);
+        assert!(queue
+            .repositories_with_pending_jobs()
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(db.get_jobs(10).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,170 @@
+//! Model routing.
+//!
+//! Picks the endpoints for a piece of work from their `tier` and
+//! `context_window`: large files and architecture summaries go to the large
+//! endpoints, or to those with the largest context window if no endpoint is
+//! marked large, and small files to the others, fast ones included. Without
+//! tiers or context windows every endpoint takes every task.
+
+use crate::config::{EndpointTier, OllamaEndpoint, RoutingConfig};
+use std::cmp::Reverse;
+
+/// Size class of a file for routing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileSize {
+    Small,
+    Large,
+}
+
+impl FileSize {
+    pub fn of(content: &str, routing: &RoutingConfig) -> Self {
+        if content.chars().count() > routing.large_file_chars {
+            FileSize::Large
+        } else {
+            FileSize::Small
+        }
+    }
+}
+
+/// Endpoints for small and large files.
+///
+/// Large files go to the large endpoints or, without any, to those sharing the
+/// largest known context window; fast endpoints only get them if every
+/// endpoint is fast. Small files go to the remaining endpoints. When that
+/// leaves nothing to tell apart, both get every endpoint.
+pub fn split_by_size(endpoints: &[OllamaEndpoint]) -> (Vec<OllamaEndpoint>, Vec<OllamaEndpoint>) {
+    let candidates: Vec<&OllamaEndpoint> = if endpoints.iter().all(|e| e.tier == EndpointTier::Fast)
+    {
+        endpoints.iter().collect()
+    } else {
+        endpoints
+            .iter()
+            .filter(|e| e.tier != EndpointTier::Fast)
+            .collect()
+    };
+
+    let large: Vec<&OllamaEndpoint> = if candidates.iter().any(|e| e.tier == EndpointTier::Large) {
+        candidates
+            .into_iter()
+            .filter(|e| e.tier == EndpointTier::Large)
+            .collect()
+    } else {
+        match candidates.iter().filter_map(|e| e.context_window).max() {
+            Some(largest) => candidates
+                .into_iter()
+                .filter(|e| e.context_window == Some(largest))
+                .collect(),
+            None => candidates,
+        }
+    };
+
+    let small: Vec<OllamaEndpoint> = endpoints
+        .iter()
+        .filter(|e| !large.iter().any(|l| l.name == e.name))
+        .cloned()
+        .collect();
+    if small.is_empty() {
+        (endpoints.to_vec(), endpoints.to_vec())
+    } else {
+        (small, large.into_iter().cloned().collect())
+    }
+}
+
+/// All endpoints, largest first: by tier, then by context window (unknown
+/// last), keeping the configured order otherwise. Used for architecture
+/// summaries, which try each endpoint in turn.
+pub fn by_capacity(endpoints: &[OllamaEndpoint]) -> Vec<&OllamaEndpoint> {
+    let mut ordered: Vec<&OllamaEndpoint> = endpoints.iter().collect();
+    ordered.sort_by_key(|e| (Reverse(e.tier), Reverse(e.context_window)));
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(name: &str, tier: EndpointTier, context_window: Option<u32>) -> OllamaEndpoint {
+        OllamaEndpoint {
+            tier,
+            context_window,
+            ..OllamaEndpoint::new(
+                name.to_string(),
+                "http://localhost:11434".to_string(),
+                "llama3".to_string(),
+            )
+        }
+    }
+
+    fn names(endpoints: &[OllamaEndpoint]) -> Vec<&str> {
+        endpoints.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_file_size() {
+        let routing = RoutingConfig {
+            large_file_chars: 5,
+        };
+        assert_eq!(FileSize::of("fn ()", &routing), FileSize::Small);
+        assert_eq!(FileSize::of("fn f()", &routing), FileSize::Large);
+    }
+
+    #[test]
+    fn test_routing_by_tier() {
+        let endpoints = [
+            endpoint("laptop", EndpointTier::Fast, Some(8_192)),
+            endpoint("desktop", EndpointTier::Standard, Some(32_768)),
+            endpoint("server", EndpointTier::Large, Some(16_384)),
+        ];
+
+        let (small, large) = split_by_size(&endpoints);
+        assert_eq!(names(&small), ["laptop", "desktop"]);
+        assert_eq!(names(&large), ["server"]);
+        let ordered: Vec<&str> = by_capacity(&endpoints)
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(ordered, ["server", "desktop", "laptop"]);
+
+        // Fast endpoints only get large files when there is nothing else
+        let (small, large) = split_by_size(&endpoints[..2]);
+        assert_eq!(names(&small), ["laptop"]);
+        assert_eq!(names(&large), ["desktop"]);
+    }
+
+    #[test]
+    fn test_routing_by_context_window() {
+        let endpoints = [
+            endpoint("a", EndpointTier::Standard, None),
+            endpoint("b", EndpointTier::Standard, Some(32_768)),
+            endpoint("c", EndpointTier::Standard, Some(8_192)),
+        ];
+
+        let (small, large) = split_by_size(&endpoints);
+        assert_eq!(names(&small), ["a", "c"]);
+        assert_eq!(names(&large), ["b"]);
+        let ordered: Vec<&str> = by_capacity(&endpoints)
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(ordered, ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn test_unconfigured_endpoints_take_everything() {
+        let endpoints = [
+            endpoint("a", EndpointTier::Standard, None),
+            endpoint("b", EndpointTier::Standard, None),
+        ];
+
+        let (small, large) = split_by_size(&endpoints);
+        assert_eq!(names(&small), ["a", "b"]);
+        assert_eq!(names(&large), ["a", "b"]);
+
+        let fast = [
+            endpoint("a", EndpointTier::Fast, None),
+            endpoint("b", EndpointTier::Fast, None),
+        ];
+        let (small, large) = split_by_size(&fast);
+        assert_eq!((small.len(), large.len()), (2, 2));
+    }
+}
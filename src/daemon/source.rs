@@ -0,0 +1,147 @@
+//! Reading source files for analysis.
+//!
+//! Files that aren't valid UTF-8 are decoded lossily rather than dropped, and
+//! the result is flagged so the dashboard can say the analysis saw replacement
+//! characters. Binary files and minified bundles (a few enormous lines) would
+//! only waste prompt space, so they are skipped and recorded in the file
+//! inventory with the reason, as are vendored and generated files.
+
+use crate::file_filter::Detected;
+use std::path::Path;
+
+/// Bytes inspected for NUL when detecting binary files (same heuristic as git)
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+/// Files with a line longer than this (in bytes) are treated as minified
+pub const MAX_LINE_LENGTH: usize = 1_000;
+
+/// Why a file was not analyzed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    Binary,
+    Minified,
+    Vendored,
+    Generated,
+}
+
+impl SkipReason {
+    /// Status stored in the file inventory
+    pub fn status(self) -> &'static str {
+        match self {
+            Self::Binary => FILE_STATUS_BINARY,
+            Self::Minified => FILE_STATUS_MINIFIED,
+            Self::Vendored => FILE_STATUS_VENDORED,
+            Self::Generated => FILE_STATUS_GENERATED,
+        }
+    }
+}
+
+impl From<Detected> for SkipReason {
+    fn from(detected: Detected) -> Self {
+        match detected {
+            Detected::Vendored => Self::Vendored,
+            Detected::Generated => Self::Generated,
+        }
+    }
+}
+
+/// File inventory status of a file that was skipped as binary
+pub const FILE_STATUS_BINARY: &str = "skipped_binary";
+
+/// File inventory status of a file that was skipped as minified
+pub const FILE_STATUS_MINIFIED: &str = "skipped_minified";
+
+/// File inventory status of a file that was skipped as third-party code
+pub const FILE_STATUS_VENDORED: &str = "skipped_vendored";
+
+/// File inventory status of a file that was skipped as generator output
+pub const FILE_STATUS_GENERATED: &str = "skipped_generated";
+
+/// File inventory status of a file that was analyzed after lossy UTF-8 decoding
+pub const FILE_STATUS_LOSSY: &str = "lossy_utf8";
+
+/// The decoded contents of a source file
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceText {
+    /// Valid UTF-8
+    Text(String),
+    /// Invalid UTF-8 sequences were replaced with U+FFFD
+    Lossy(String),
+    /// Not worth analyzing
+    Skipped(SkipReason),
+}
+
+impl SourceText {
+    /// Decode file contents, classifying binary and minified files
+    pub fn decode(bytes: Vec<u8>) -> Self {
+        let sniff = &bytes[..bytes.len().min(BINARY_SNIFF_BYTES)];
+        if sniff.contains(&0) {
+            return Self::Skipped(SkipReason::Binary);
+        }
+        if bytes
+            .split(|&b| b == b'\n')
+            .any(|line| line.len() > MAX_LINE_LENGTH)
+        {
+            return Self::Skipped(SkipReason::Minified);
+        }
+
+        match String::from_utf8(bytes) {
+            Ok(text) => Self::Text(text),
+            Err(e) => Self::Lossy(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+        }
+    }
+
+    /// Status to record in the file inventory (None for clean text)
+    pub fn status(&self) -> Option<&'static str> {
+        match self {
+            Self::Text(_) => None,
+            Self::Lossy(_) => Some(FILE_STATUS_LOSSY),
+            Self::Skipped(reason) => Some(reason.status()),
+        }
+    }
+}
+
+/// Read and decode a source file
+pub async fn read_source(path: &Path) -> std::io::Result<SourceText> {
+    Ok(SourceText::decode(tokio::fs::read(path).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_text_and_lossy() {
+        assert_eq!(
+            SourceText::decode(b"fn main() {}\n".to_vec()),
+            SourceText::Text("fn main() {}\n".to_string())
+        );
+
+        let decoded = SourceText::decode(b"// caf\xe9\nfn main() {}\n".to_vec());
+        assert_eq!(
+            decoded,
+            SourceText::Lossy("// caf\u{fffd}\nfn main() {}\n".to_string())
+        );
+        assert_eq!(decoded.status(), Some(FILE_STATUS_LOSSY));
+    }
+
+    #[test]
+    fn test_decode_skips_binary_and_minified() {
+        assert_eq!(
+            SourceText::decode(b"\x7fELF\x00\x01".to_vec()),
+            SourceText::Skipped(SkipReason::Binary)
+        );
+
+        let minified = format!("var a={};\n", "1+".repeat(MAX_LINE_LENGTH));
+        assert_eq!(
+            SourceText::decode(minified.into_bytes()),
+            SourceText::Skipped(SkipReason::Minified)
+        );
+
+        let long_but_fine = "x\n".repeat(MAX_LINE_LENGTH * 10);
+        assert!(matches!(
+            SourceText::decode(long_but_fine.into_bytes()),
+            SourceText::Text(_)
+        ));
+    }
+}
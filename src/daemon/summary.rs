@@ -0,0 +1,204 @@
+//! Map-reduce aggregation for architecture summaries.
+//!
+//! A repository's file analyses often don't fit into one prompt. Instead of
+//! truncating them, they are packed in path order (so files of the same
+//! directory stay together) into chunks that do fit, and each chunk is
+//! summarized on its own (map). The chunk summaries are packed and summarized
+//! again until everything fits into the final architecture summary prompt
+//! (reduce).
+
+use super::truncate_at_char_boundary;
+use crate::analyzer::respond_in;
+use std::path::Path;
+
+/// Characters of code analyses (or module summaries) per prompt
+pub const CHUNK_CHARS: usize = 45_000;
+
+/// Reduce rounds before the remaining summaries are truncated instead
+pub const MAX_REDUCE_ROUNDS: usize = 3;
+
+/// A named piece of text to summarize
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Directory (or group of directories) the text covers
+    pub name: String,
+    pub text: String,
+}
+
+/// Sections packed into one prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// Names of the sections, without duplicates, in order
+    pub names: Vec<String>,
+    pub text: String,
+}
+
+impl Chunk {
+    pub fn name(&self) -> String {
+        self.names.join(", ")
+    }
+}
+
+/// One section per file analysis, named by the file's directory relative to
+/// `root` and sorted by path
+pub fn file_sections(root: &Path, analyses: &[(String, String)]) -> Vec<Section> {
+    let mut analyses: Vec<_> = analyses.iter().collect();
+    analyses.sort_by(|a, b| a.0.cmp(&b.0));
+    analyses
+        .into_iter()
+        .map(|(path, analysis)| {
+            let relative = Path::new(path)
+                .strip_prefix(root)
+                .unwrap_or(Path::new(path));
+            let directory = relative
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .filter(|p| !p.is_empty())
+                .unwrap_or_else(|| ".".to_string());
+            Section {
+                name: directory,
+                text: format!("\n## {}\n{}\n", relative.display(), analysis),
+            }
+        })
+        .collect()
+}
+
+/// Total length of the sections' text
+pub fn total_len(sections: &[Section]) -> usize {
+    sections.iter().map(|s| s.text.len()).sum()
+}
+
+/// Pack sections in order into chunks of at most `max_chars`, truncating
+/// sections that are longer on their own
+pub fn pack(sections: &[Section], max_chars: usize) -> Vec<Chunk> {
+    let mut chunks: Vec<Chunk> = Vec::new();
+    for section in sections {
+        let text = truncate_at_char_boundary(&section.text, max_chars);
+        let fits = chunks
+            .last()
+            .is_some_and(|chunk| chunk.text.len() + text.len() <= max_chars);
+        if !fits {
+            chunks.push(Chunk {
+                names: Vec::new(),
+                text: String::new(),
+            });
+        }
+        let chunk = chunks.last_mut().expect("a chunk was just pushed");
+        if !chunk.names.contains(&section.name) {
+            chunk.names.push(section.name.clone());
+        }
+        chunk.text.push_str(text);
+    }
+    chunks
+}
+
+/// Prompt summarizing the file analyses of one chunk (map)
+pub fn module_summary_prompt(repo_name: &str, chunk: &Chunk, output_language: &str) -> String {
+    format!(
+        "You are analyzing part of a codebase called '{}': {}.\n\n\
+         Below are architecture-focused analyses of its source files:\n{}\n\n\
+         Summarize the architecture of this part of the codebase in at most 300 words:\n\
+         1. **Responsibilities**: What do these modules do?\n\
+         2. **Key Components**: The main types, functions and their roles\n\
+         3. **Dependencies**: Which other parts of the codebase and external services they use\n\n\
+         {}",
+        repo_name,
+        chunk.name(),
+        chunk.text,
+        respond_in(output_language, true)
+    )
+}
+
+/// Prompt combining module summaries into a shorter summary (reduce)
+pub fn combine_summaries_prompt(repo_name: &str, chunk: &Chunk, output_language: &str) -> String {
+    format!(
+        "You are analyzing a codebase called '{}'.\n\n\
+         Below are architecture summaries of some of its modules ({}):\n{}\n\n\
+         Combine them into one architecture summary of these modules in at most 400 words, \
+         keeping each module's responsibilities and how the modules depend on each other.\n\n\
+         {}",
+        repo_name,
+        chunk.name(),
+        chunk.text,
+        respond_in(output_language, true)
+    )
+}
+
+/// Section holding the summary of a chunk
+pub fn summary_section(chunk: &Chunk, summary: &str) -> Section {
+    Section {
+        name: chunk.name(),
+        text: format!("\n## {}\n{}\n", chunk.name(), summary.trim()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(name: &str, len: usize) -> Section {
+        Section {
+            name: name.to_string(),
+            text: "x".repeat(len),
+        }
+    }
+
+    #[test]
+    fn test_file_sections_are_grouped_by_directory() {
+        let analyses = vec![
+            ("/repo/src/web/mod.rs".to_string(), "web".to_string()),
+            ("/repo/build.rs".to_string(), "build".to_string()),
+            ("/repo/src/db/mod.rs".to_string(), "db".to_string()),
+        ];
+        let sections = file_sections(Path::new("/repo"), &analyses);
+        let names: Vec<_> = sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, [".", "src/db", "src/web"]);
+        assert_eq!(sections[1].text, "\n## src/db/mod.rs\ndb\n");
+    }
+
+    #[test]
+    fn test_pack_fills_chunks_in_order() {
+        let sections = [
+            section("src/db", 40),
+            section("src/db", 40),
+            section("src/web", 30),
+            section("src/web", 50),
+        ];
+        let chunks = pack(&sections, 100);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].names, ["src/db"]);
+        assert_eq!(chunks[0].text.len(), 80);
+        assert_eq!(chunks[1].name(), "src/web");
+        assert_eq!(chunks[1].text.len(), 80);
+
+        // Everything is kept
+        let total: usize = chunks.iter().map(|c| c.text.len()).sum();
+        assert_eq!(total, total_len(&sections));
+    }
+
+    #[test]
+    fn test_pack_truncates_oversized_sections() {
+        let chunks = pack(&[section("a", 10), section("b", 500)], 100);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].text.len(), 100);
+        assert!(pack(&[], 100).is_empty());
+    }
+
+    #[test]
+    fn test_prompts_name_the_chunk() {
+        let chunk = Chunk {
+            names: vec!["src/db".to_string(), "src/web".to_string()],
+            text: "\n## src/db/mod.rs\nStores results\n".to_string(),
+        };
+        let prompt = module_summary_prompt("noctum", &chunk, "English");
+        assert!(prompt.contains("src/db, src/web"));
+        assert!(prompt.contains("Stores results"));
+
+        let section = summary_section(&chunk, " Database and web layers \n");
+        assert_eq!(
+            section.text,
+            "\n## src/db, src/web\nDatabase and web layers\n"
+        );
+        assert!(combine_summaries_prompt("noctum", &chunk, "English").contains("Combine"));
+    }
+}
@@ -0,0 +1,85 @@
+//! Debounced manual scan triggers.
+//!
+//! Save hooks and scripts may trigger scans in quick succession. Rather than
+//! queueing a full pass per trigger, the daemon waits until no new trigger has
+//! arrived for the configured quiet period and then runs a single scan.
+//! Triggers that arrive while a scan is running coalesce into one follow-up scan.
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::Notify;
+use tokio::time::Duration;
+
+/// Shared state of pending manual triggers
+#[derive(Clone, Default)]
+pub struct ScanTrigger {
+    /// Time of the most recent trigger not yet consumed by a scan
+    last: Arc<Mutex<Option<Instant>>>,
+    /// Wakes the daemon loop when a trigger arrives
+    notify: Arc<Notify>,
+}
+
+impl ScanTrigger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request a scan (restarts the quiet period if one is pending)
+    pub fn trigger(&self) {
+        *self.last.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        self.notify.notify_one();
+    }
+
+    /// Whether a trigger is waiting to be consumed
+    pub fn is_pending(&self) -> bool {
+        self.last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    /// Time left until the pending trigger has been quiet for `debounce`
+    /// (None when nothing is pending)
+    pub fn remaining(&self, debounce: Duration) -> Option<Duration> {
+        self.last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .map(|last| debounce.saturating_sub(last.elapsed()))
+    }
+
+    /// Consume the pending trigger
+    pub fn take(&self) -> bool {
+        self.last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .is_some()
+    }
+
+    /// Wait until a trigger arrives
+    pub async fn notified(&self) {
+        self.notify.notified().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triggers_coalesce() {
+        let trigger = ScanTrigger::new();
+        assert!(!trigger.is_pending());
+        assert_eq!(trigger.remaining(Duration::from_secs(5)), None);
+
+        for _ in 0..5 {
+            trigger.trigger();
+        }
+        assert!(trigger.is_pending());
+        assert!(trigger.remaining(Duration::from_secs(5)).unwrap() > Duration::ZERO);
+        assert_eq!(trigger.remaining(Duration::ZERO), Some(Duration::ZERO));
+
+        assert!(trigger.take());
+        assert!(!trigger.take());
+    }
+}
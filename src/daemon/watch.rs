@@ -0,0 +1,185 @@
+//! Watch mode: analyze changed files as they are saved.
+//!
+//! Repositories with `repository.watch = true` in their `noctum.toml` get a
+//! filesystem watcher. Change events for source files are collected per
+//! repository until no further change has arrived for `DEBOUNCE`, so an editor
+//! save or a branch checkout produces a single batch. The daemon then analyzes
+//! just those files, regardless of the scheduled window.
+
+use crate::language::Language;
+use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+/// Quiet period after the last change before a batch is handed out
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Changed files per repository ID, relative to the repository root
+pub type ChangedFiles = HashMap<i64, HashSet<PathBuf>>;
+
+/// Filesystem watchers for all watched repositories
+pub struct RepoWatcher {
+    watchers: HashMap<i64, RecommendedWatcher>,
+    tx: mpsc::UnboundedSender<(i64, PathBuf)>,
+    rx: mpsc::UnboundedReceiver<(i64, PathBuf)>,
+    /// Changes received but not yet handed out
+    pending: ChangedFiles,
+}
+
+impl RepoWatcher {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            watchers: HashMap::new(),
+            tx,
+            rx,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Whether a repository is being watched
+    pub fn is_watching(&self, repository_id: i64) -> bool {
+        self.watchers.contains_key(&repository_id)
+    }
+
+    /// Start watching a repository
+    pub fn watch(&mut self, repository_id: i64, root: &Path) -> Result<()> {
+        let tx = self.tx.clone();
+        let event_root = root.to_path_buf();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                for path in event.paths {
+                    if let Some(relative) = watched_source(&event_root, &path) {
+                        let _ = tx.send((repository_id, relative));
+                    }
+                }
+            })
+            .context("Failed to create file watcher")?;
+        watcher
+            .watch(root, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", root.display()))?;
+
+        self.watchers.insert(repository_id, watcher);
+        Ok(())
+    }
+
+    /// Stop watching every repository not in `keep`
+    pub fn retain(&mut self, keep: &HashSet<i64>) {
+        self.watchers.retain(|id, _| keep.contains(id));
+        self.pending.retain(|id, _| keep.contains(id));
+    }
+
+    /// Wait for changes and return them once no further change has arrived for
+    /// the debounce period. Cancel safe: changes received before the future is
+    /// dropped are returned by the next call.
+    pub async fn changes(&mut self) -> ChangedFiles {
+        if self.pending.is_empty() {
+            let Some(change) = self.rx.recv().await else {
+                return std::future::pending().await;
+            };
+            self.record(change);
+        }
+
+        while let Ok(Some(change)) = tokio::time::timeout(DEBOUNCE, self.rx.recv()).await {
+            self.record(change);
+        }
+
+        // Changes from repositories that are no longer watched may still be queued
+        let watchers = &self.watchers;
+        self.pending.retain(|id, _| watchers.contains_key(id));
+        std::mem::take(&mut self.pending)
+    }
+
+    fn record(&mut self, (repository_id, path): (i64, PathBuf)) {
+        self.pending.entry(repository_id).or_default().insert(path);
+    }
+}
+
+/// The path of a changed file relative to the repository root, if it is a
+/// source file outside hidden, build and dependency directories
+fn watched_source(root: &Path, path: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(root).ok()?;
+
+    let extension = relative.extension()?.to_str()?;
+    if !Language::all()
+        .iter()
+        .any(|language| language.file_extensions().contains(&extension))
+    {
+        return None;
+    }
+
+    let skipped = relative.components().any(|component| match component {
+        Component::Normal(name) => {
+            let name = name.to_string_lossy();
+            name.starts_with('.')
+                || Language::all()
+                    .iter()
+                    .any(|language| language.skip_directories().contains(&name.as_ref()))
+        }
+        _ => true,
+    });
+    (!skipped).then(|| relative.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watched_source() {
+        let root = Path::new("/repo");
+        assert_eq!(
+            watched_source(root, Path::new("/repo/src/main.rs")),
+            Some(PathBuf::from("src/main.rs"))
+        );
+        assert_eq!(
+            watched_source(root, Path::new("/repo/web/app.tsx")),
+            Some(PathBuf::from("web/app.tsx"))
+        );
+
+        assert_eq!(watched_source(root, Path::new("/repo/README.md")), None);
+        assert_eq!(
+            watched_source(root, Path::new("/repo/target/debug/build.rs")),
+            None
+        );
+        assert_eq!(
+            watched_source(root, Path::new("/repo/node_modules/x/index.js")),
+            None
+        );
+        assert_eq!(watched_source(root, Path::new("/repo/.git/hook.rs")), None);
+        assert_eq!(watched_source(root, Path::new("/other/main.rs")), None);
+    }
+
+    #[tokio::test]
+    async fn test_changes_are_batched_per_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let mut watcher = RepoWatcher::new();
+        watcher.watch(1, dir.path()).unwrap();
+        assert!(watcher.is_watching(1));
+
+        std::fs::write(dir.path().join("src/lib.rs"), "fn a() {}").unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "fn b() {}").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let changes = tokio::time::timeout(Duration::from_secs(10), watcher.changes())
+            .await
+            .expect("file changes should be reported");
+        assert_eq!(
+            changes,
+            HashMap::from([(1, HashSet::from([PathBuf::from("src/lib.rs")]))])
+        );
+
+        watcher.retain(&HashSet::new());
+        assert!(!watcher.is_watching(1));
+    }
+}
@@ -0,0 +1,355 @@
+//! Database backups.
+//!
+//! Snapshots are taken with SQLite's online backup API, so they are consistent even
+//! while the daemon is writing. Backups are stored as `noctum-<timestamp>.db` files
+//! and rotated so only the newest ones are kept. Snapshots can also be written
+//! to any path on demand (`noctum db backup`, `POST /api/admin/backup`).
+//! Before a restore overwrites the database, it is saved as a
+//! `pre-restore-<timestamp>.db` file, which is neither listed with the
+//! backups nor rotated, so restoring again picks the same backup.
+//!
+//! Only SQLite database files are backed up; a PostgreSQL database is backed up
+//! with the server's own tools.
+
+use crate::config::Config;
+use anyhow::{Context, Result};
+use rusqlite::{backup::Progress, Connection, DatabaseName, OpenFlags};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// File name prefix for backup files
+const BACKUP_PREFIX: &str = "noctum-";
+
+/// File name prefix for the snapshots taken before a restore
+const SAFETY_PREFIX: &str = "pre-restore-";
+
+/// File name extension for backup files
+const BACKUP_EXTENSION: &str = ".db";
+
+/// Returns true if the file name looks like a backup created by Noctum
+fn is_backup_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(BACKUP_PREFIX) && name.ends_with(BACKUP_EXTENSION))
+}
+
+/// The SQLite database file backups are taken of
+pub fn database_file(config: &Config) -> Result<PathBuf> {
+    config
+        .database_path()
+        .context("The database is on a PostgreSQL server; back it up with pg_dump instead")
+}
+
+/// List backups in a directory, newest first.
+///
+/// Returns an empty list if the directory does not exist.
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(backup_dir)
+        .with_context(|| format!("Failed to read backup directory: {:?}", backup_dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && is_backup_file(path))
+        .collect();
+
+    // Timestamps in file names sort chronologically
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Check whether a new backup is due, based on the age of the newest backup.
+pub fn is_backup_due(backup_dir: &Path, interval: Duration) -> Result<bool> {
+    let Some(latest) = list_backups(backup_dir)?.into_iter().next() else {
+        return Ok(true);
+    };
+
+    let modified = std::fs::metadata(&latest)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("Failed to read backup metadata: {:?}", latest))?;
+
+    Ok(SystemTime::now()
+        .duration_since(modified)
+        .map(|age| age >= interval)
+        .unwrap_or(false))
+}
+
+/// Path of a new backup file in `backup_dir`, named by the current time
+pub fn new_backup_path(backup_dir: &Path) -> PathBuf {
+    timestamped_path(backup_dir, BACKUP_PREFIX)
+}
+
+fn timestamped_path(dir: &Path, prefix: &str) -> PathBuf {
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f");
+    dir.join(format!("{}{}{}", prefix, timestamp, BACKUP_EXTENSION))
+}
+
+/// Snapshot the database into the backup directory.
+///
+/// Returns the path of the new backup file.
+pub async fn create_backup(db_path: &Path, backup_dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(backup_dir)
+        .with_context(|| format!("Failed to create backup directory: {:?}", backup_dir))?;
+
+    let backup_path = new_backup_path(backup_dir);
+    backup_to(db_path, &backup_path).await?;
+    Ok(backup_path)
+}
+
+/// Snapshot the database to `destination`, which must not exist yet.
+pub async fn backup_to(db_path: &Path, destination: &Path) -> Result<()> {
+    if destination.exists() {
+        anyhow::bail!("{:?} already exists", destination);
+    }
+
+    let source = db_path.to_path_buf();
+    let destination = destination.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let conn = Connection::open_with_flags(&source, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open database for backup: {:?}", source))?;
+        conn.backup(DatabaseName::Main, &destination, None::<fn(Progress)>)
+            .with_context(|| format!("Failed to write backup to {:?}", destination))?;
+        Ok(())
+    })
+    .await
+    .context("Backup task panicked")?
+}
+
+/// Delete the oldest backups so that at most `keep` remain.
+///
+/// Returns the paths that were removed.
+pub fn rotate_backups(backup_dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let removed: Vec<PathBuf> = list_backups(backup_dir)?.into_iter().skip(keep).collect();
+
+    for path in &removed {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove old backup: {:?}", path))?;
+    }
+
+    Ok(removed)
+}
+
+/// Restore the database from `backup`, or the newest backup in `backup_dir`.
+///
+/// The current database, if any, is first saved into `backup_dir` as a
+/// pre-restore snapshot so the restore can be undone. Returns the restored
+/// backup and the snapshot.
+pub async fn restore(
+    db_path: &Path,
+    backup_dir: &Path,
+    backup: Option<PathBuf>,
+) -> Result<(PathBuf, Option<PathBuf>)> {
+    let backup_path = match backup {
+        Some(path) => path,
+        None => list_backups(backup_dir)?
+            .into_iter()
+            .next()
+            .with_context(|| format!("No backups found in {}", backup_dir.display()))?,
+    };
+
+    let safety = if db_path.exists() {
+        std::fs::create_dir_all(backup_dir)
+            .with_context(|| format!("Failed to create backup directory: {:?}", backup_dir))?;
+        let safety = timestamped_path(backup_dir, SAFETY_PREFIX);
+        backup_to(db_path, &safety).await?;
+        Some(safety)
+    } else {
+        None
+    };
+
+    restore_backup(&backup_path, db_path).await?;
+    Ok((backup_path, safety))
+}
+
+/// Restore the database from a backup file.
+///
+/// The backup is integrity-checked before anything is overwritten. Noctum must not be
+/// running against the same database while restoring.
+pub async fn restore_backup(backup_path: &Path, db_path: &Path) -> Result<()> {
+    let source = backup_path.to_path_buf();
+    let destination = db_path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let backup = Connection::open_with_flags(&source, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open backup: {:?}", source))?;
+        let check: String = backup
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .with_context(|| format!("Failed to verify backup: {:?}", source))?;
+        if check != "ok" {
+            anyhow::bail!("Backup {:?} failed integrity check: {}", source, check);
+        }
+        drop(backup);
+
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create database directory: {:?}", parent))?;
+        }
+
+        let mut conn = Connection::open(&destination)
+            .with_context(|| format!("Failed to open database: {:?}", destination))?;
+        conn.restore(DatabaseName::Main, &source, None::<fn(Progress)>)
+            .with_context(|| format!("Failed to restore database from {:?}", source))?;
+        Ok(())
+    })
+    .await
+    .context("Restore task panicked")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use tempfile::TempDir;
+
+    async fn create_db_with_repo(dir: &Path, name: &str) -> PathBuf {
+        let db_path = dir.join("noctum.db");
+        let db = Database::new(&db_path).await.unwrap();
+        db.run_migrations().await.unwrap();
+
+        let repo_dir = dir.join(name);
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::fs::write(repo_dir.join("noctum.toml"), "").unwrap();
+        db.add_repository(&repo_dir.to_string_lossy(), name)
+            .await
+            .unwrap();
+        db_path
+    }
+
+    async fn repository_names(db_path: &Path) -> Vec<String> {
+        let db = Database::new(db_path).await.unwrap();
+        db.get_repositories()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_list_backups_missing_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let backups = list_backups(&temp_dir.path().join("missing")).unwrap();
+        assert!(backups.is_empty());
+    }
+
+    #[test]
+    fn test_list_backups_ignores_other_files() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("noctum-20240101-000000.000.db"), "").unwrap();
+        std::fs::write(temp_dir.path().join("notes.txt"), "").unwrap();
+        std::fs::write(temp_dir.path().join("other.db"), "").unwrap();
+
+        let backups = list_backups(temp_dir.path()).unwrap();
+        assert_eq!(backups.len(), 1);
+    }
+
+    #[test]
+    fn test_rotate_backups_keeps_newest() {
+        let temp_dir = TempDir::new().unwrap();
+        for day in 1..=5 {
+            let name = format!("noctum-2024010{}-000000.000.db", day);
+            std::fs::write(temp_dir.path().join(name), "").unwrap();
+        }
+
+        let removed = rotate_backups(temp_dir.path(), 2).unwrap();
+        assert_eq!(removed.len(), 3);
+
+        let remaining = list_backups(temp_dir.path()).unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining[0].ends_with("noctum-20240105-000000.000.db"));
+        assert!(remaining[1].ends_with("noctum-20240104-000000.000.db"));
+    }
+
+    #[test]
+    fn test_is_backup_due() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(is_backup_due(temp_dir.path(), Duration::from_secs(3600)).unwrap());
+
+        std::fs::write(temp_dir.path().join("noctum-20240101-000000.000.db"), "").unwrap();
+        assert!(!is_backup_due(temp_dir.path(), Duration::from_secs(3600)).unwrap());
+        assert!(is_backup_due(temp_dir.path(), Duration::ZERO).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = create_db_with_repo(temp_dir.path(), "original").await;
+        let backup_dir = temp_dir.path().join("backups");
+
+        let backup_path = create_backup(&db_path, &backup_dir).await.unwrap();
+        assert!(backup_path.exists());
+        assert_eq!(
+            list_backups(&backup_dir).unwrap(),
+            vec![backup_path.clone()]
+        );
+
+        // Modify the live database after the backup
+        let db = Database::new(&db_path).await.unwrap();
+        let repo_id = db.get_repositories().await.unwrap()[0].id;
+        db.delete_repository(repo_id).await.unwrap();
+        drop(db);
+        assert!(repository_names(&db_path).await.is_empty());
+
+        restore_backup(&backup_path, &db_path).await.unwrap();
+        assert_eq!(repository_names(&db_path).await, vec!["original"]);
+    }
+
+    #[tokio::test]
+    async fn test_backup_to_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = create_db_with_repo(temp_dir.path(), "original").await;
+
+        // The daemon may hold the database open while it is snapshotted
+        let db = Database::new(&db_path).await.unwrap();
+        let snapshot = temp_dir.path().join("snapshot.db");
+        backup_to(&db_path, &snapshot).await.unwrap();
+        assert_eq!(repository_names(&snapshot).await, vec!["original"]);
+        drop(db);
+
+        // Existing files are not overwritten
+        assert!(backup_to(&db_path, &snapshot).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restore_twice_keeps_picking_the_newest_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = create_db_with_repo(temp_dir.path(), "original").await;
+        let backup_dir = temp_dir.path().join("backups");
+        let backup_path = create_backup(&db_path, &backup_dir).await.unwrap();
+
+        let db = Database::new(&db_path).await.unwrap();
+        let repo_id = db.get_repositories().await.unwrap()[0].id;
+        db.delete_repository(repo_id).await.unwrap();
+        drop(db);
+
+        let (restored, first_safety) = restore(&db_path, &backup_dir, None).await.unwrap();
+        assert_eq!(restored, backup_path);
+        let first_safety = first_safety.unwrap();
+        assert!(repository_names(&first_safety).await.is_empty());
+        assert_eq!(repository_names(&db_path).await, vec!["original"]);
+
+        // The snapshot taken by the first restore isn't a candidate for the second
+        let (restored, second_safety) = restore(&db_path, &backup_dir, None).await.unwrap();
+        assert_eq!(restored, backup_path);
+        assert_eq!(repository_names(&db_path).await, vec!["original"]);
+        assert_eq!(list_backups(&backup_dir).unwrap(), vec![backup_path]);
+
+        // Nor are snapshots rotated away
+        assert!(rotate_backups(&backup_dir, 1).unwrap().is_empty());
+        assert!(first_safety.exists());
+        assert!(second_safety.unwrap().exists());
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_invalid_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = create_db_with_repo(temp_dir.path(), "original").await;
+        let bogus = temp_dir.path().join("noctum-bogus.db");
+        std::fs::write(&bogus, "not a database").unwrap();
+
+        assert!(restore_backup(&bogus, &db_path).await.is_err());
+        assert_eq!(repository_names(&db_path).await, vec!["original"]);
+    }
+}
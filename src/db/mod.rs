@@ -1,24 +1,89 @@
-//! SQLite database layer for persistent storage.
+//! Database layer for persistent storage.
 //!
 //! Manages repositories, analysis results, mutation testing results, and daemon state.
-//! Handles migrations and provides async CRUD operations via sqlx.
+//! Handles migrations and provides async CRUD operations via sqlx. The data lives
+//! in a SQLite file by default, or on a PostgreSQL server shared by a team (with
+//! the `postgres` feature). Queries are written in the SQL both accept; the few
+//! that can't be are chosen per [`Backend`].
 
+pub mod backup;
 mod models;
 
 pub use models::*;
 
+use crate::analyzer::{findings, ArchitectureModel, StructuredFinding, TestSuggestion, TokenUsage};
+use crate::config::DatabaseUrl;
+use crate::issues::TrackerIssue;
+use crate::mutation::GeneratedMutation;
+use crate::onboarding::{self, QuickScan};
 use anyhow::{Context, Result};
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use sqlx::any::AnyPoolOptions;
+use sqlx::{Any, Executor, Pool, Transaction};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard};
 
-/// Database wrapper for SQLite operations
+/// Connections in the pool, shared by the web server and the daemon
+const MAX_CONNECTIONS: u32 = 16;
+
+/// How long a connection waits for a lock held by another writer
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The current time as an RFC 3339 UTC timestamp with milliseconds, which
+/// sorts chronologically as text
+pub fn now_millis() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// The current UTC time as `YYYY-MM-DD HH:MM:SS`, the format of the columns
+/// that default to the time a row was added
+fn current_timestamp() -> String {
+    timestamp(chrono::Utc::now())
+}
+
+/// The UTC time `days` days ago, in the format of [`current_timestamp`]
+fn days_ago(days: i64) -> String {
+    timestamp(chrono::Utc::now() - chrono::Duration::days(days))
+}
+
+/// The UTC date `days` days ago as `YYYY-MM-DD`, the format of usage days
+fn date_days_ago(days: i64) -> String {
+    (chrono::Utc::now() - chrono::Duration::days(days))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+fn timestamp(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Database server a [`Database`] stores its data in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+/// Database wrapper for SQLite and PostgreSQL operations
 #[derive(Clone)]
 pub struct Database {
-    pool: Pool<Sqlite>,
+    pool: Pool<Any>,
+    backend: Backend,
+    /// Serializes multi-statement writes. SQLite allows a single writer, and
+    /// transactions started as readers fail to upgrade with `SQLITE_BUSY`
+    /// instead of waiting, so bulk writes take this lock first.
+    writes: Arc<Mutex<()>>,
 }
 
 impl Database {
-    /// Create a new database connection
+    /// Create a new connection to a SQLite database file
+    ///
+    /// The database uses write-ahead logging, so the web server keeps reading
+    /// while the daemon writes, and connections wait for locks instead of
+    /// failing with "database is locked".
     pub async fn new(path: &Path) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
@@ -26,20 +91,110 @@ impl Database {
                 .with_context(|| format!("Failed to create database directory: {:?}", parent))?;
         }
 
+        sqlx::any::install_default_drivers();
         let database_url = format!("sqlite:{}?mode=rwc", path.display());
-
-        let pool = SqlitePoolOptions::new()
-            .max_connections(5)
+        let pool = AnyPoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .after_connect(|conn, _| {
+                Box::pin(async move {
+                    // The busy timeout comes first, so switching to WAL waits
+                    // for other connections too
+                    conn.execute(
+                        format!(
+                            "PRAGMA busy_timeout = {}; \
+                             PRAGMA journal_mode = WAL; \
+                             PRAGMA synchronous = NORMAL",
+                            BUSY_TIMEOUT.as_millis()
+                        )
+                        .as_str(),
+                    )
+                    .await?;
+                    Ok(())
+                })
+            })
             .connect(&database_url)
             .await
             .with_context(|| format!("Failed to connect to database: {}", database_url))?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            backend: Backend::Sqlite,
+            writes: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// Connect to the database named by `general.database_url`
+    pub async fn open(url: &DatabaseUrl) -> Result<Self> {
+        match url {
+            DatabaseUrl::Sqlite(path) => Self::new(path).await,
+            DatabaseUrl::Postgres(url) => Self::connect_postgres(url).await,
+        }
+    }
+
+    /// Connect to a PostgreSQL server
+    #[cfg(feature = "postgres")]
+    async fn connect_postgres(url: &str) -> Result<Self> {
+        sqlx::any::install_default_drivers();
+        let pool = AnyPoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .after_connect(|conn, _| {
+                Box::pin(async move {
+                    // Migrations skip existing tables and columns with a notice
+                    conn.execute("SET client_min_messages = warning").await?;
+                    Ok(())
+                })
+            })
+            .connect(url)
+            .await
+            .context("Failed to connect to the PostgreSQL database")?;
+
+        Ok(Self {
+            pool,
+            backend: Backend::Postgres,
+            writes: Arc::new(Mutex::new(())),
+        })
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    async fn connect_postgres(_url: &str) -> Result<Self> {
+        anyhow::bail!("This build of Noctum has no PostgreSQL support (the `postgres` feature)")
+    }
+
+    /// Adapt a statement of the schema, which is written for SQLite, to the
+    /// backend. PostgreSQL gets 64-bit integers and sequences for the integer
+    /// columns, and columns are only added if they are missing.
+    fn schema<'a>(&self, sql: &'a str) -> Cow<'a, str> {
+        match self.backend {
+            Backend::Sqlite => Cow::Borrowed(sql),
+            Backend::Postgres => Cow::Owned(
+                sql.replace("INTEGER PRIMARY KEY AUTOINCREMENT", "BIGSERIAL PRIMARY KEY")
+                    .replace("INTEGER", "BIGINT")
+                    .replace("REAL", "DOUBLE PRECISION")
+                    .replace("BLOB", "BYTEA")
+                    .replace(
+                        "DEFAULT CURRENT_TIMESTAMP",
+                        "DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')",
+                    )
+                    .replace("ADD COLUMN", "ADD COLUMN IF NOT EXISTS"),
+            ),
+        }
+    }
+
+    /// Begin a transaction holding the write lock, which is released when the
+    /// returned guard is dropped
+    async fn begin_write(&self) -> Result<(MutexGuard<'_, ()>, Transaction<'_, Any>)> {
+        let guard = self.writes.lock().await;
+        let tx = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to start transaction")?;
+        Ok((guard, tx))
     }
 
     /// Run database migrations
     pub async fn run_migrations(&self) -> Result<()> {
-        sqlx::query(
+        sqlx::query(&self.schema(
             r#"
             CREATE TABLE IF NOT EXISTS repositories (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -50,12 +205,48 @@ impl Database {
                 updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
             )
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await
         .context("Failed to create repositories table")?;
 
-        sqlx::query(
+        // Analysis passes selected per repository (NULL runs all of them)
+        let _ =
+            sqlx::query(&self.schema("ALTER TABLE repositories ADD COLUMN analysis_types TEXT"))
+                .execute(&self.pool)
+                .await;
+        // Sensitive repositories are only analyzed by local endpoints
+        let _ = sqlx::query(
+            &self
+                .schema("ALTER TABLE repositories ADD COLUMN sensitive INTEGER NOT NULL DEFAULT 0"),
+        )
+        .execute(&self.pool)
+        .await;
+        // Repositories added by Git URL, checked out under the data directory
+        let _ = sqlx::query(&self.schema("ALTER TABLE repositories ADD COLUMN remote_url TEXT"))
+            .execute(&self.pool)
+            .await;
+        let _ = sqlx::query(&self.schema("ALTER TABLE repositories ADD COLUMN branch TEXT"))
+            .execute(&self.pool)
+            .await;
+        // Groups for filtering and per-group schedules (JSON list)
+        let _ = sqlx::query(
+            &self.schema("ALTER TABLE repositories ADD COLUMN tags TEXT NOT NULL DEFAULT '[]'"),
+        )
+        .execute(&self.pool)
+        .await;
+        // Archived repositories keep their results but are no longer scanned
+        let _ = sqlx::query(&self.schema("ALTER TABLE repositories ADD COLUMN archived_at TEXT"))
+            .execute(&self.pool)
+            .await;
+        // Build and test command overrides (JSON object)
+        let _ = sqlx::query(
+            &self.schema("ALTER TABLE repositories ADD COLUMN commands TEXT NOT NULL DEFAULT '{}'"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        sqlx::query(&self.schema(
             r#"
             CREATE TABLE IF NOT EXISTS analysis_results (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -69,17 +260,40 @@ impl Database {
                 FOREIGN KEY (repository_id) REFERENCES repositories(id)
             )
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await
         .context("Failed to create analysis_results table")?;
 
         // Add content_hash column if it doesn't exist (migration for existing databases)
-        let _ = sqlx::query("ALTER TABLE analysis_results ADD COLUMN content_hash TEXT")
+        let _ =
+            sqlx::query(&self.schema("ALTER TABLE analysis_results ADD COLUMN content_hash TEXT"))
+                .execute(&self.pool)
+                .await;
+
+        // Model attribution (migration for existing databases)
+        for column in ["endpoint TEXT", "model TEXT", "duration_ms INTEGER"] {
+            let _ = sqlx::query(&self.schema(&format!(
+                "ALTER TABLE analysis_results ADD COLUMN {}",
+                column
+            )))
             .execute(&self.pool)
             .await;
+        }
 
-        sqlx::query(
+        // Issue filed from the finding (migration for existing databases)
+        let _ = sqlx::query(&self.schema("ALTER TABLE analysis_results ADD COLUMN issue_url TEXT"))
+            .execute(&self.pool)
+            .await;
+        // Results of files deleted from their repository, hidden until purged
+        let _ = sqlx::query(
+            &self
+                .schema("ALTER TABLE analysis_results ADD COLUMN stale INTEGER NOT NULL DEFAULT 0"),
+        )
+        .execute(&self.pool)
+        .await;
+
+        sqlx::query(&self.schema(
             r#"
             CREATE TABLE IF NOT EXISTS daemon_state (
                 id INTEGER PRIMARY KEY CHECK (id = 1),
@@ -88,7 +302,7 @@ impl Database {
                 last_active TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
             )
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await
         .context("Failed to create daemon_state table")?;
@@ -96,15 +310,25 @@ impl Database {
         // Initialize daemon state if not exists
         sqlx::query(
             r#"
-            INSERT OR IGNORE INTO daemon_state (id, status) VALUES (1, 'idle')
+            INSERT INTO daemon_state (id, status) VALUES (1, 'idle')
+            ON CONFLICT (id) DO NOTHING
             "#,
         )
         .execute(&self.pool)
         .await
         .context("Failed to initialize daemon state")?;
 
+        // Last fatal cycle error and schedule pause (migration for existing databases)
+        for column in ["last_error TEXT", "last_error_at TEXT", "paused_until TEXT"] {
+            let _ = sqlx::query(
+                &self.schema(&format!("ALTER TABLE daemon_state ADD COLUMN {}", column)),
+            )
+            .execute(&self.pool)
+            .await;
+        }
+
         // Create mutation_results table
-        sqlx::query(
+        sqlx::query(&self.schema(
             r#"
             CREATE TABLE IF NOT EXISTS mutation_results (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -112,6 +336,7 @@ impl Database {
                 file_path TEXT NOT NULL,
                 description TEXT NOT NULL,
                 reasoning TEXT NOT NULL,
+                -- Superseded by mutation_replacements, always '[]' for new results
                 replacements_json TEXT NOT NULL,
                 test_outcome TEXT NOT NULL,
                 killing_test TEXT,
@@ -122,28 +347,133 @@ impl Database {
                 FOREIGN KEY (repository_id) REFERENCES repositories(id)
             )
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await
         .context("Failed to create mutation_results table")?;
 
+        // Toolchain used to run the mutation (migration for existing databases)
+        let _ = sqlx::query(&self.schema("ALTER TABLE mutation_results ADD COLUMN toolchain TEXT"))
+            .execute(&self.pool)
+            .await;
+
+        // Model attribution (migration for existing databases)
+        for column in ["endpoint TEXT", "model TEXT", "duration_ms INTEGER"] {
+            let _ = sqlx::query(&self.schema(&format!(
+                "ALTER TABLE mutation_results ADD COLUMN {}",
+                column
+            )))
+            .execute(&self.pool)
+            .await;
+        }
+
+        // Line coverage of the mutated code (migration for existing databases)
+        let _ =
+            sqlx::query(&self.schema("ALTER TABLE mutation_results ADD COLUMN covered INTEGER"))
+                .execute(&self.pool)
+                .await;
+
+        // Monorepo project the mutation was tested in (migration for existing databases)
+        let _ = sqlx::query(&self.schema("ALTER TABLE mutation_results ADD COLUMN project TEXT"))
+            .execute(&self.pool)
+            .await;
+
+        // Issue filed from the survived mutant (migration for existing databases)
+        let _ = sqlx::query(&self.schema("ALTER TABLE mutation_results ADD COLUMN issue_url TEXT"))
+            .execute(&self.pool)
+            .await;
+
+        // Triage state (migration for existing databases)
+        let _ =
+            sqlx::query(&self.schema(
+                "ALTER TABLE mutation_results ADD COLUMN status TEXT NOT NULL DEFAULT 'new'",
+            ))
+            .execute(&self.pool)
+            .await;
+
+        // Create mutation_replacements table (the line replacements of a mutation
+        // result, removed together with the result when it is pruned). PostgreSQL
+        // databases were created with it.
+        let has_replacements_table = self.backend == Backend::Postgres
+            || sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM sqlite_master \
+                 WHERE type = 'table' AND name = 'mutation_replacements'",
+            )
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to check for mutation_replacements table")?
+                > 0;
+
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS mutation_replacements (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mutation_result_id INTEGER NOT NULL,
+                repository_id INTEGER NOT NULL,
+                line_number INTEGER NOT NULL,
+                find TEXT NOT NULL,
+                replace TEXT NOT NULL,
+                original_line TEXT NOT NULL DEFAULT '',
+                FOREIGN KEY (mutation_result_id) REFERENCES mutation_results(id) ON DELETE CASCADE,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create mutation_replacements table")?;
+
+        sqlx::query(&self.schema(
+            "CREATE INDEX IF NOT EXISTS idx_mutation_replacements_result \
+             ON mutation_replacements(mutation_result_id)",
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create mutation_replacements index")?;
+
+        // Move replacements out of the JSON column of existing results (migration
+        // for existing databases)
+        if !has_replacements_table {
+            sqlx::query(
+                r#"
+                INSERT INTO mutation_replacements
+                    (mutation_result_id, repository_id, line_number, find, replace, original_line)
+                SELECT m.id, m.repository_id,
+                       json_extract(r.value, '$.line_number'),
+                       json_extract(r.value, '$.find'),
+                       json_extract(r.value, '$.replace'),
+                       COALESCE(json_extract(r.value, '$.original_line'), '')
+                FROM mutation_results m, json_each(m.replacements_json) r
+                WHERE json_valid(m.replacements_json)
+                  AND json_type(m.replacements_json) = 'array'
+                  AND json_type(r.value, '$.line_number') = 'integer'
+                  AND json_type(r.value, '$.find') = 'text'
+                  AND json_type(r.value, '$.replace') = 'text'
+                ORDER BY m.id, r.key
+                "#,
+            )
+            .execute(&self.pool)
+            .await
+            .context("Failed to migrate mutation replacements")?;
+        }
+
         // Create indexes for mutation_results
-        let _ = sqlx::query(
+        let _ = sqlx::query(&self.schema(
             "CREATE INDEX IF NOT EXISTS idx_mutation_results_repo_file \
              ON mutation_results(repository_id, file_path)",
-        )
+        ))
         .execute(&self.pool)
         .await;
 
-        let _ = sqlx::query(
+        let _ = sqlx::query(&self.schema(
             "CREATE INDEX IF NOT EXISTS idx_mutation_results_outcome \
              ON mutation_results(test_outcome)",
-        )
+        ))
         .execute(&self.pool)
         .await;
 
         // Create diagrams table for DOT diagram storage
-        sqlx::query(
+        sqlx::query(&self.schema(
             r#"
             CREATE TABLE IF NOT EXISTS diagrams (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -158,790 +488,6241 @@ impl Database {
                 FOREIGN KEY (repository_id) REFERENCES repositories(id)
             )
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await
         .context("Failed to create diagrams table")?;
 
-        // Create indexes for diagrams
+        // Source format, 'dot' or 'mermaid' (migration for existing databases)
         let _ = sqlx::query(
-            "CREATE INDEX IF NOT EXISTS idx_diagrams_repo_type \
-             ON diagrams(repository_id, diagram_type)",
+            &self.schema("ALTER TABLE diagrams ADD COLUMN format TEXT NOT NULL DEFAULT 'dot'"),
         )
         .execute(&self.pool)
         .await;
 
-        Ok(())
-    }
-
-    /// Get all repositories
-    pub async fn get_repositories(&self) -> Result<Vec<Repository>> {
-        let repos = sqlx::query_as::<_, Repository>("SELECT * FROM repositories ORDER BY name")
-            .fetch_all(&self.pool)
-            .await
-            .context("Failed to fetch repositories")?;
-
-        Ok(repos)
-    }
-
-    /// Get a repository by ID
-    pub async fn get_repository(&self, id: i64) -> Result<Option<Repository>> {
-        let repo = sqlx::query_as::<_, Repository>("SELECT * FROM repositories WHERE id = ?")
-            .bind(id)
-            .fetch_optional(&self.pool)
-            .await
-            .context("Failed to fetch repository")?;
-
-        Ok(repo)
-    }
+        // Create indexes for diagrams
+        let _ = sqlx::query(&self.schema(
+            "CREATE INDEX IF NOT EXISTS idx_diagrams_repo_type \
+             ON diagrams(repository_id, diagram_type)",
+        ))
+        .execute(&self.pool)
+        .await;
 
-    /// Add a new repository.
-    ///
-    /// The repository must contain a `noctum.toml` configuration file.
-    /// An empty file is acceptable, but the file must exist.
-    pub async fn add_repository(&self, path: &str, name: &str) -> Result<i64> {
-        // Validate noctum.toml exists
-        let repo_path = std::path::Path::new(path);
-        if !crate::repo_config::RepoConfig::exists(repo_path) {
-            anyhow::bail!(
-                "Repository must contain a noctum.toml configuration file. \
-                 Create one at: {}/noctum.toml",
-                path
-            );
+        // Create jobs table for the persistent task queue
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repository_id INTEGER NOT NULL,
+                job_type TEXT NOT NULL,
+                file_path TEXT,
+                status TEXT NOT NULL DEFAULT 'pending',
+                error TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                started_at TEXT,
+                finished_at TEXT,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create jobs table")?;
+
+        let _ =
+            sqlx::query(&self.schema("CREATE INDEX IF NOT EXISTS idx_jobs_status ON jobs(status)"))
+                .execute(&self.pool)
+                .await;
+
+        // Queue priority and the endpoint that ran the job (migration for existing databases)
+        for column in ["priority INTEGER NOT NULL DEFAULT 0", "endpoint TEXT"] {
+            let _ = sqlx::query(&self.schema(&format!("ALTER TABLE jobs ADD COLUMN {}", column)))
+                .execute(&self.pool)
+                .await;
         }
 
-        let result =
-            sqlx::query("INSERT INTO repositories (path, name) VALUES (?, ?) RETURNING id")
-                .bind(path)
-                .bind(name)
-                .fetch_one(&self.pool)
-                .await
-                .context("Failed to add repository")?;
-
-        Ok(sqlx::Row::get(&result, "id"))
-    }
-
-    /// Delete a repository and all its associated data
-    pub async fn delete_repository(&self, id: i64) -> Result<bool> {
-        // Delete associated diagrams first
-        sqlx::query("DELETE FROM diagrams WHERE repository_id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .context("Failed to delete diagrams")?;
+        // Create notifications table (log of sent notifications, used for the daily cap)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS notifications (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repository_id INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create notifications table")?;
 
-        // Delete associated mutation results
-        sqlx::query("DELETE FROM mutation_results WHERE repository_id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .context("Failed to delete mutation results")?;
+        // Create file_snapshots table (last analyzed content per file, for delta analysis)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS file_snapshots (
+                repository_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                content TEXT NOT NULL,
+                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (repository_id, file_path),
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create file_snapshots table")?;
 
-        // Delete associated analysis results
-        sqlx::query("DELETE FROM analysis_results WHERE repository_id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .context("Failed to delete analysis results")?;
+        // Create file_changelog table (per-file change history from delta analysis)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS file_changelog (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repository_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                entry TEXT NOT NULL,
+                content_hash TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create file_changelog table")?;
 
-        // Delete the repository itself
-        let result = sqlx::query("DELETE FROM repositories WHERE id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .context("Failed to delete repository")?;
+        let _ = sqlx::query(&self.schema(
+            "CREATE INDEX IF NOT EXISTS idx_file_changelog_repo_file \
+             ON file_changelog(repository_id, file_path)",
+        ))
+        .execute(&self.pool)
+        .await;
 
-        Ok(result.rows_affected() > 0)
-    }
+        // Create file_status table (files skipped or decoded lossily, for the file inventory)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS file_status (
+                repository_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                status TEXT NOT NULL,
+                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (repository_id, file_path),
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create file_status table")?;
 
-    /// Get recent analysis results (latest per file)
-    pub async fn get_recent_results(&self, limit: i32) -> Result<Vec<AnalysisResult>> {
-        // Get the latest result for each file/analysis_type combination
-        let results = sqlx::query_as::<_, AnalysisResult>(
+        // Create usage table (LLM token counts per day, endpoint, repository and analysis type)
+        sqlx::query(&self.schema(
             r#"
-            SELECT ar.* FROM analysis_results ar
-            INNER JOIN (
-                SELECT file_path, analysis_type, MAX(created_at) as max_created
-                FROM analysis_results
-                GROUP BY file_path, analysis_type
-            ) latest ON ar.file_path = latest.file_path
-                AND ar.analysis_type = latest.analysis_type
-                AND ar.created_at = latest.max_created
-            ORDER BY ar.created_at DESC
-            LIMIT ?
+            CREATE TABLE IF NOT EXISTS usage (
+                day TEXT NOT NULL,
+                endpoint TEXT NOT NULL,
+                repository_id INTEGER NOT NULL,
+                analysis_type TEXT NOT NULL,
+                requests INTEGER NOT NULL DEFAULT 0,
+                prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                completion_tokens INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (day, endpoint, repository_id, analysis_type),
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
             "#,
-        )
-        .bind(limit)
-        .fetch_all(&self.pool)
+        ))
+        .execute(&self.pool)
         .await
-        .context("Failed to fetch analysis results")?;
+        .context("Failed to create usage table")?;
 
-        Ok(results)
-    }
+        // Generations retried after transient errors
+        let _ = sqlx::query(
+            &self.schema("ALTER TABLE usage ADD COLUMN retries INTEGER NOT NULL DEFAULT 0"),
+        )
+        .execute(&self.pool)
+        .await;
 
-    /// Get daemon status
-    pub async fn get_daemon_status(&self) -> Result<DaemonState> {
-        let state = sqlx::query_as::<_, DaemonState>("SELECT * FROM daemon_state WHERE id = 1")
-            .fetch_one(&self.pool)
-            .await
-            .context("Failed to fetch daemon state")?;
+        // Responses rejected by validation
+        let _ = sqlx::query(
+            &self.schema("ALTER TABLE usage ADD COLUMN rejected INTEGER NOT NULL DEFAULT 0"),
+        )
+        .execute(&self.pool)
+        .await;
 
-        Ok(state)
-    }
+        // Create issues table (open issues imported from the repository's issue tracker)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS issues (
+                repository_id INTEGER NOT NULL,
+                number INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                url TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '',
+                fetched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (repository_id, number),
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create issues table")?;
 
-    /// Update daemon status
-    pub async fn update_daemon_status(
-        &self,
-        status: &str,
-        current_task: Option<&str>,
-    ) -> Result<()> {
-        sqlx::query(
-            "UPDATE daemon_state SET status = ?, current_task = ?, last_active = CURRENT_TIMESTAMP WHERE id = 1",
-        )
-        .bind(status)
-        .bind(current_task)
+        // Create findings table (structured findings of an analysis result, removed
+        // together with the result when it is pruned)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS findings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                analysis_result_id INTEGER NOT NULL,
+                repository_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                title TEXT NOT NULL,
+                severity TEXT NOT NULL,
+                category TEXT NOT NULL,
+                line_start INTEGER,
+                line_end INTEGER,
+                recommendation TEXT NOT NULL,
+                FOREIGN KEY (analysis_result_id) REFERENCES analysis_results(id) ON DELETE CASCADE,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
         .execute(&self.pool)
         .await
-        .context("Failed to update daemon state")?;
+        .context("Failed to create findings table")?;
 
-        Ok(())
-    }
+        sqlx::query(&self.schema(
+            "CREATE INDEX IF NOT EXISTS idx_findings_result ON findings(analysis_result_id)",
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create findings index")?;
+
+        // Deduplication across scans: a finding reported again moves to the new
+        // result, keeping when it was first seen and counting occurrences, and is
+        // resolved once a new result of the file no longer reports it
+        for column in [
+            "fingerprint TEXT",
+            "first_seen TEXT",
+            "last_seen TEXT",
+            "occurrences INTEGER NOT NULL DEFAULT 1",
+            "resolved_at TEXT",
+            "first_result_id INTEGER",
+        ] {
+            let _ =
+                sqlx::query(&self.schema(&format!("ALTER TABLE findings ADD COLUMN {}", column)))
+                    .execute(&self.pool)
+                    .await;
+        }
 
-    /// Save an analysis result
-    pub async fn save_analysis_result(
-        &self,
-        repository_id: i64,
-        file_path: &str,
-        analysis_type: &str,
-        result: &str,
-        severity: Option<&str>,
-        content_hash: Option<&str>,
-    ) -> Result<i64> {
-        let row = sqlx::query(
-            "INSERT INTO analysis_results (repository_id, file_path, analysis_type, result, severity, content_hash) \
-             VALUES (?, ?, ?, ?, ?, ?) RETURNING id",
+        sqlx::query(
+            r#"
+            UPDATE findings SET
+                first_result_id = analysis_result_id,
+                first_seen = (SELECT created_at FROM analysis_results WHERE id = analysis_result_id),
+                last_seen = (SELECT created_at FROM analysis_results WHERE id = analysis_result_id)
+            WHERE first_result_id IS NULL
+            "#,
         )
-        .bind(repository_id)
-        .bind(file_path)
-        .bind(analysis_type)
-        .bind(result)
-        .bind(severity)
-        .bind(content_hash)
-        .fetch_one(&self.pool)
+        .execute(&self.pool)
         .await
-        .context("Failed to save analysis result")?;
-
-        Ok(sqlx::Row::get(&row, "id"))
-    }
+        .context("Failed to backfill findings")?;
 
-    /// Get the latest content hash for a file
-    pub async fn get_latest_file_hash(
-        &self,
-        repository_id: i64,
-        file_path: &str,
-        analysis_type: &str,
-    ) -> Result<Option<String>> {
-        let result = sqlx::query_scalar::<_, Option<String>>(
-            "SELECT content_hash FROM analysis_results \
-             WHERE repository_id = ? AND file_path = ? AND analysis_type = ? \
-             ORDER BY id DESC LIMIT 1",
-        )
-        .bind(repository_id)
-        .bind(file_path)
-        .bind(analysis_type)
-        .fetch_optional(&self.pool)
+        sqlx::query(&self.schema(
+            "CREATE INDEX IF NOT EXISTS idx_findings_fingerprint \
+             ON findings(repository_id, fingerprint)",
+        ))
+        .execute(&self.pool)
         .await
-        .context("Failed to fetch file hash")?;
+        .context("Failed to create findings fingerprint index")?;
 
-        Ok(result.flatten())
-    }
+        // Create mutation_deferrals table (mutation testing left over for the
+        // next schedule window)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS mutation_deferrals (
+                repository_id INTEGER PRIMARY KEY,
+                pending_files INTEGER NOT NULL,
+                estimated_seconds INTEGER NOT NULL,
+                remaining_window_seconds INTEGER NOT NULL,
+                deferred_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create mutation_deferrals table")?;
 
-    /// Get analysis results for a specific repository and analysis type (latest per file)
-    pub async fn get_repository_results(
-        &self,
-        repository_id: i64,
-        analysis_type: &str,
-    ) -> Result<Vec<AnalysisResult>> {
-        // Get only the latest result for each file
-        let results = sqlx::query_as::<_, AnalysisResult>(
+        // Create mutation_runs table (the mutations generated for a file and
+        // which of them were executed, so an interrupted run resumes)
+        sqlx::query(&self.schema(
             r#"
-            SELECT ar.* FROM analysis_results ar
-            INNER JOIN (
-                SELECT file_path, MAX(created_at) as max_created
-                FROM analysis_results
-                WHERE repository_id = ? AND analysis_type = ?
-                GROUP BY file_path
-            ) latest ON ar.file_path = latest.file_path
-                AND ar.created_at = latest.max_created
-            WHERE ar.repository_id = ? AND ar.analysis_type = ?
-            ORDER BY ar.file_path
+            CREATE TABLE IF NOT EXISTS mutation_runs (
+                repository_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                mutations TEXT NOT NULL,
+                executed TEXT NOT NULL DEFAULT '[]',
+                endpoint TEXT,
+                model TEXT,
+                duration_ms INTEGER,
+                started_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (repository_id, file_path),
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
             "#,
-        )
-        .bind(repository_id)
-        .bind(analysis_type)
-        .bind(repository_id)
-        .bind(analysis_type)
-        .fetch_all(&self.pool)
+        ))
+        .execute(&self.pool)
         .await
-        .context("Failed to fetch repository results")?;
+        .context("Failed to create mutation_runs table")?;
 
-        Ok(results)
-    }
+        // Create mutation_bases table (the commit each file's mutations were
+        // last tested at, so the next run only mutates what changed since)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS mutation_bases (
+                repository_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                commit_hash TEXT NOT NULL,
+                tested_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (repository_id, file_path),
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create mutation_bases table")?;
 
-    /// Get all analysis results for a repository (latest per file/type)
-    pub async fn get_all_repository_results(
-        &self,
-        repository_id: i64,
-    ) -> Result<Vec<AnalysisResult>> {
-        let results = sqlx::query_as::<_, AnalysisResult>(
+        // Create audit_log table (kept when a repository is deleted, so the
+        // record outlives it)
+        sqlx::query(&self.schema(
             r#"
-            SELECT ar.* FROM analysis_results ar
-            INNER JOIN (
-                SELECT file_path, analysis_type, MAX(created_at) as max_created
-                FROM analysis_results
-                WHERE repository_id = ?
-                GROUP BY file_path, analysis_type
-            ) latest ON ar.file_path = latest.file_path
-                AND ar.analysis_type = latest.analysis_type
-                AND ar.created_at = latest.max_created
-            WHERE ar.repository_id = ?
-            ORDER BY ar.analysis_type DESC, ar.file_path
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repository_id INTEGER,
+                action TEXT NOT NULL,
+                detail TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
             "#,
-        )
-        .bind(repository_id)
-        .bind(repository_id)
-        .fetch_all(&self.pool)
+        ))
+        .execute(&self.pool)
         .await
-        .context("Failed to fetch all repository results")?;
+        .context("Failed to create audit_log table")?;
 
-        Ok(results)
-    }
+        // Create embeddings table (one vector per file, of its latest code
+        // understanding result)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS embeddings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repository_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                analysis_result_id INTEGER NOT NULL,
+                model TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE (repository_id, file_path),
+                FOREIGN KEY (analysis_result_id) REFERENCES analysis_results(id) ON DELETE CASCADE,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create embeddings table")?;
 
-    /// Save a mutation test result
-    #[allow(clippy::too_many_arguments)]
-    pub async fn save_mutation_result(
-        &self,
-        repository_id: i64,
-        file_path: &str,
-        description: &str,
-        reasoning: &str,
-        replacements_json: &str,
-        test_outcome: &str,
-        killing_test: Option<&str>,
-        test_output: Option<&str>,
-        execution_time_ms: Option<i32>,
-        content_hash: Option<&str>,
-    ) -> Result<i64> {
-        let row = sqlx::query(
+        // Create reanalysis_requests table (files to analyze again on the next
+        // scan even though their content is unchanged)
+        sqlx::query(&self.schema(
             r#"
-            INSERT INTO mutation_results (
-                repository_id, file_path, description, reasoning, replacements_json,
-                test_outcome, killing_test, test_output, execution_time_ms, content_hash
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            RETURNING id
+            CREATE TABLE IF NOT EXISTS reanalysis_requests (
+                repository_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                requested_at TEXT NOT NULL,
+                PRIMARY KEY (repository_id, file_path),
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
             "#,
-        )
-        .bind(repository_id)
-        .bind(file_path)
-        .bind(description)
-        .bind(reasoning)
-        .bind(replacements_json)
-        .bind(test_outcome)
-        .bind(killing_test)
-        .bind(test_output)
-        .bind(execution_time_ms)
-        .bind(content_hash)
-        .fetch_one(&self.pool)
+        ))
+        .execute(&self.pool)
         .await
-        .context("Failed to save mutation result")?;
+        .context("Failed to create reanalysis_requests table")?;
 
-        Ok(sqlx::Row::get(&row, "id"))
-    }
+        // Create diagram_requests table (diagrams to generate again on the next
+        // scan even though their sources are unchanged)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS diagram_requests (
+                repository_id INTEGER NOT NULL,
+                diagram_type TEXT NOT NULL,
+                requested_at TEXT NOT NULL,
+                PRIMARY KEY (repository_id, diagram_type),
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create diagram_requests table")?;
 
-    /// Get mutation results for a repository
-    pub async fn get_mutation_results(&self, repository_id: i64) -> Result<Vec<MutationResult>> {
-        let results = sqlx::query_as::<_, MutationResult>(
+        // Create test_suggestions table (test cases that would kill a surviving
+        // mutation, removed together with the mutation result)
+        sqlx::query(&self.schema(
             r#"
-            SELECT * FROM mutation_results
-            WHERE repository_id = ?
-            ORDER BY created_at DESC, file_path
+            CREATE TABLE IF NOT EXISTS test_suggestions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mutation_result_id INTEGER NOT NULL,
+                repository_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                scenario TEXT NOT NULL,
+                assertion TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (mutation_result_id) REFERENCES mutation_results(id) ON DELETE CASCADE,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
             "#,
-        )
-        .bind(repository_id)
-        .fetch_all(&self.pool)
+        ))
+        .execute(&self.pool)
         .await
-        .context("Failed to fetch mutation results")?;
+        .context("Failed to create test_suggestions table")?;
 
-        Ok(results)
-    }
+        sqlx::query(&self.schema(
+            "CREATE INDEX IF NOT EXISTS idx_test_suggestions_result \
+             ON test_suggestions(mutation_result_id)",
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create test_suggestions index")?;
 
-    /// Get mutation summary statistics for a repository
-    pub async fn get_mutation_summary(&self, repository_id: i64) -> Result<MutationSummary> {
-        let results = self.get_mutation_results(repository_id).await?;
+        // Create duplicate_clusters and duplicate_locations tables (code blocks
+        // copied across files, replaced on every scan)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS duplicate_clusters (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repository_id INTEGER NOT NULL,
+                fingerprint TEXT NOT NULL,
+                line_count INTEGER NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create duplicate_clusters table")?;
 
-        let mut summary = MutationSummary::default();
-        for result in results {
-            summary.total += 1;
-            match result.test_outcome.as_str() {
-                "killed" => summary.killed += 1,
-                "survived" => summary.survived += 1,
-                "timeout" => summary.timeout += 1,
-                "compile_error" => summary.compile_error += 1,
-                _ => {}
-            }
-        }
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS duplicate_locations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                cluster_id INTEGER NOT NULL,
+                repository_id INTEGER NOT NULL,
+                file_path TEXT NOT NULL,
+                line_start INTEGER NOT NULL,
+                line_end INTEGER NOT NULL,
+                file_lines INTEGER NOT NULL,
+                FOREIGN KEY (cluster_id) REFERENCES duplicate_clusters(id) ON DELETE CASCADE,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create duplicate_locations table")?;
 
-        Ok(summary)
-    }
+        sqlx::query(&self.schema(
+            "CREATE INDEX IF NOT EXISTS idx_duplicate_locations_cluster \
+             ON duplicate_locations(cluster_id)",
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create duplicate_locations index")?;
 
-    /// Check if a file has mutation results for a given content hash
-    pub async fn has_mutation_results_for_hash(
-        &self,
-        repository_id: i64,
-        file_path: &str,
-        content_hash: &str,
-    ) -> Result<bool> {
-        let count = sqlx::query_scalar::<_, i32>(
+        // Create scan_runs table (one row per processing cycle of the daemon).
+        // The marks are the highest result ids when the cycle started, so the
+        // results it saved can be counted when it finishes.
+        sqlx::query(&self.schema(
             r#"
-            SELECT COUNT(*) FROM mutation_results
-            WHERE repository_id = ? AND file_path = ? AND content_hash = ?
+            CREATE TABLE IF NOT EXISTS scan_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                trigger TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'running',
+                started_at TEXT NOT NULL,
+                finished_at TEXT,
+                repositories_processed INTEGER NOT NULL DEFAULT 0,
+                files_analyzed INTEGER NOT NULL DEFAULT 0,
+                mutations_run INTEGER NOT NULL DEFAULT 0,
+                errors INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                analysis_mark INTEGER NOT NULL DEFAULT 0,
+                mutation_mark INTEGER NOT NULL DEFAULT 0
+            )
             "#,
-        )
-        .bind(repository_id)
-        .bind(file_path)
-        .bind(content_hash)
-        .fetch_one(&self.pool)
+        ))
+        .execute(&self.pool)
         .await
-        .context("Failed to check mutation results for hash")?;
+        .context("Failed to create scan_runs table")?;
 
-        Ok(count > 0)
-    }
+        // Create digests table (digest emails, so each covers the scans since the previous one)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS digests (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                sent_at TEXT NOT NULL,
+                last_run_id INTEGER NOT NULL
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create digests table")?;
 
-    /// Save a new diagram (inserts new row, keeping history)
-    #[allow(clippy::too_many_arguments)]
-    pub async fn save_diagram(
-        &self,
-        repository_id: i64,
-        diagram_type: &str,
-        title: &str,
-        description: &str,
-        dot_content: &str,
-        svg_content: &str,
-        content_hash: Option<&str>,
-    ) -> Result<i64> {
-        let row = sqlx::query(
+        // Create scores table (repository health scores, recorded when they
+        // change after a processing cycle)
+        sqlx::query(&self.schema(
             r#"
-            INSERT INTO diagrams (repository_id, diagram_type, title, description, dot_content, svg_content, content_hash)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
-            RETURNING id
+            CREATE TABLE IF NOT EXISTS scores (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repository_id INTEGER NOT NULL,
+                scan_run_id INTEGER,
+                score REAL NOT NULL,
+                kill_rate REAL,
+                finding_score REAL,
+                doc_coverage REAL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
             "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create scores table")?;
+
+        sqlx::query(
+            &self.schema(
+                "CREATE INDEX IF NOT EXISTS idx_scores_repository ON scores(repository_id)",
+            ),
         )
-        .bind(repository_id)
-        .bind(diagram_type)
-        .bind(title)
-        .bind(description)
-        .bind(dot_content)
-        .bind(svg_content)
-        .bind(content_hash)
-        .fetch_one(&self.pool)
+        .execute(&self.pool)
         .await
-        .context("Failed to save diagram")?;
+        .context("Failed to create scores index")?;
 
-        Ok(sqlx::Row::get(&row, "id"))
-    }
+        // Create chat_messages table (questions about a repository and the
+        // answers grounded in its stored analyses)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS chat_messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repository_id INTEGER NOT NULL,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                sources TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create chat_messages table")?;
 
-    /// Get the latest diagram of each type for a repository
-    pub async fn get_latest_diagrams(&self, repository_id: i64) -> Result<Vec<Diagram>> {
-        let diagrams = sqlx::query_as::<_, Diagram>(
+        sqlx::query(&self.schema(
+            "CREATE INDEX IF NOT EXISTS idx_chat_messages_repository \
+             ON chat_messages(repository_id)",
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create chat_messages index")?;
+
+        // Create architecture_models table (the latest structured architecture
+        // model of each repository, served as JSON)
+        sqlx::query(&self.schema(
             r#"
-            SELECT d.* FROM diagrams d
-            INNER JOIN (
-                SELECT diagram_type, MAX(id) as max_id
-                FROM diagrams
-                WHERE repository_id = ?
-                GROUP BY diagram_type
-            ) latest ON d.diagram_type = latest.diagram_type
-                AND d.id = latest.max_id
-            WHERE d.repository_id = ?
-            ORDER BY d.diagram_type
+            CREATE TABLE IF NOT EXISTS architecture_models (
+                repository_id INTEGER PRIMARY KEY,
+                model TEXT NOT NULL,
+                endpoint TEXT,
+                llm_model TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
             "#,
-        )
-        .bind(repository_id)
-        .bind(repository_id)
-        .fetch_all(&self.pool)
+        ))
+        .execute(&self.pool)
         .await
-        .context("Failed to fetch latest diagrams")?;
+        .context("Failed to create architecture_models table")?;
 
-        Ok(diagrams)
+        // Create onboarding table (quick scans of repositories added from the
+        // dashboard, which full scans skip until confirmed)
+        sqlx::query(&self.schema(
+            r#"
+            CREATE TABLE IF NOT EXISTS onboarding (
+                repository_id INTEGER PRIMARY KEY,
+                status TEXT NOT NULL,
+                quick_scan TEXT NOT NULL DEFAULT '{}',
+                error TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (repository_id) REFERENCES repositories(id)
+            )
+            "#,
+        ))
+        .execute(&self.pool)
+        .await
+        .context("Failed to create onboarding table")?;
+
+        Ok(())
     }
 
-    /// Get the latest content hash for diagrams of a repository
-    /// Used to determine if diagrams need regeneration
-    pub async fn get_latest_diagram_hash(
+    /// Get all repositories
+    pub async fn get_repositories(&self) -> Result<Vec<Repository>> {
+        let repos = sqlx::query_as::<_, Repository>("SELECT * FROM repositories ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch repositories")?;
+
+        Ok(repos)
+    }
+
+    /// Get a repository by ID
+    pub async fn get_repository(&self, id: i64) -> Result<Option<Repository>> {
+        let repo = sqlx::query_as::<_, Repository>("SELECT * FROM repositories WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch repository")?;
+
+        Ok(repo)
+    }
+
+    /// Add a new repository.
+    ///
+    /// The repository must contain a `noctum.toml` configuration file.
+    /// An empty file is acceptable, but the file must exist.
+    pub async fn add_repository(&self, path: &str, name: &str) -> Result<i64> {
+        self.insert_repository(path, name, None, None).await
+    }
+
+    /// Add a repository cloned from `remote_url` into the checkout at `path`
+    pub async fn add_remote_repository(
         &self,
-        repository_id: i64,
-        diagram_type: &str,
-    ) -> Result<Option<String>> {
-        let result = sqlx::query_scalar::<_, Option<String>>(
-            r#"
-            SELECT content_hash FROM diagrams
-            WHERE repository_id = ? AND diagram_type = ?
-            ORDER BY id DESC
-            LIMIT 1
-            "#,
+        path: &str,
+        name: &str,
+        remote_url: &str,
+        branch: Option<&str>,
+    ) -> Result<i64> {
+        self.insert_repository(path, name, Some(remote_url), branch)
+            .await
+    }
+
+    async fn insert_repository(
+        &self,
+        path: &str,
+        name: &str,
+        remote_url: Option<&str>,
+        branch: Option<&str>,
+    ) -> Result<i64> {
+        // Validate noctum.toml exists
+        let repo_path = std::path::Path::new(path);
+        if !crate::repo_config::RepoConfig::exists(repo_path) {
+            anyhow::bail!(
+                "Repository must contain a noctum.toml configuration file. \
+                 Create one at: {}/noctum.toml",
+                path
+            );
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO repositories (path, name, remote_url, branch) VALUES ($1, $2, $3, $4) RETURNING id",
         )
-        .bind(repository_id)
-        .bind(diagram_type)
-        .fetch_optional(&self.pool)
+        .bind(path)
+        .bind(name)
+        .bind(remote_url)
+        .bind(branch)
+        .fetch_one(&self.pool)
         .await
-        .context("Failed to fetch diagram hash")?;
+        .context("Failed to add repository")?;
 
-        Ok(result.flatten())
+        Ok(sqlx::Row::get(&result, "id"))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::{NamedTempFile, TempDir};
+    /// Select the analysis passes that run for a repository. Returns false if
+    /// the repository doesn't exist.
+    pub async fn set_repository_analysis_types(&self, id: i64, passes: &[&str]) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE repositories SET analysis_types = $1, updated_at = $2 WHERE id = $3",
+        )
+        .bind(serde_json::to_string(passes)?)
+        .bind(current_timestamp())
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to update analysis types")?;
 
-    async fn create_test_db() -> (Database, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-        let db = Database::new(&db_path).await.unwrap();
-        db.run_migrations().await.unwrap();
-        (db, temp_dir)
+        Ok(result.rows_affected() > 0)
     }
 
-    /// Create a temporary repo directory with a noctum.toml file.
-    /// Returns the TempDir (must be kept alive for the path to remain valid).
-    fn create_test_repo() -> TempDir {
-        let temp_dir = TempDir::new().unwrap();
-        std::fs::write(temp_dir.path().join("noctum.toml"), "").unwrap();
-        temp_dir
+    /// Enable or disable analysis of a repository. Returns false if the
+    /// repository doesn't exist.
+    pub async fn set_repository_enabled(&self, id: i64, enabled: bool) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE repositories SET enabled = $1, updated_at = $2 WHERE id = $3")
+                .bind(i64::from(enabled))
+                .bind(current_timestamp())
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .context("Failed to update repository")?;
+
+        Ok(result.rows_affected() > 0)
     }
 
-    /// Helper to add a test repository to the database.
-    /// Creates a temp directory with noctum.toml and adds it to the db.
-    async fn add_test_repo(db: &Database, name: &str) -> (i64, TempDir) {
-        let repo_dir = create_test_repo();
-        let repo_id = db
-            .add_repository(&repo_dir.path().to_string_lossy(), name)
-            .await
-            .unwrap();
-        (repo_id, repo_dir)
+    /// Mark a repository as sensitive (or not). Returns false if the repository
+    /// doesn't exist.
+    pub async fn set_repository_sensitive(&self, id: i64, sensitive: bool) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE repositories SET sensitive = $1, updated_at = $2 WHERE id = $3")
+                .bind(i64::from(sensitive))
+                .bind(current_timestamp())
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .context("Failed to update sensitive flag")?;
+
+        Ok(result.rows_affected() > 0)
     }
 
-    #[tokio::test]
-    async fn test_database_creation() {
-        let temp_file = NamedTempFile::new().unwrap();
-        let db = Database::new(temp_file.path()).await;
-        assert!(db.is_ok(), "Database creation should succeed");
+    /// Replace the tags of a repository with already normalized `tags`. Returns
+    /// false if the repository doesn't exist.
+    pub async fn set_repository_tags(&self, id: i64, tags: &[String]) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE repositories SET tags = $1, updated_at = $2 WHERE id = $3")
+                .bind(serde_json::to_string(tags)?)
+                .bind(current_timestamp())
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .context("Failed to update tags")?;
+
+        Ok(result.rows_affected() > 0)
     }
 
-    #[tokio::test]
-    async fn test_run_migrations() {
-        let (db, _temp_dir) = create_test_db().await;
-        assert!(db.run_migrations().await.is_ok());
+    /// Replace the command overrides of a repository. Returns false if the
+    /// repository doesn't exist.
+    pub async fn set_repository_commands(
+        &self,
+        id: i64,
+        commands: &CommandOverrides,
+    ) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE repositories SET commands = $1, updated_at = $2 WHERE id = $3")
+                .bind(serde_json::to_string(commands)?)
+                .bind(current_timestamp())
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .context("Failed to update commands")?;
+
+        Ok(result.rows_affected() > 0)
     }
 
-    #[tokio::test]
-    async fn test_add_repository() {
-        let (db, _temp_dir) = create_test_db().await;
-        let repo_dir = create_test_repo();
-        let repo_path = repo_dir.path().to_string_lossy().to_string();
+    /// Archive a repository, or restore an archived one. Archiving an archived
+    /// repository keeps its original time. Returns false if the repository
+    /// doesn't exist.
+    pub async fn set_repository_archived(&self, id: i64, archived: bool) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE repositories
+            SET archived_at = CASE WHEN $1 THEN COALESCE(archived_at, $2) END,
+                updated_at = $2
+            WHERE id = $3
+            "#,
+        )
+        .bind(archived)
+        .bind(current_timestamp())
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to update archived state")?;
 
-        let id = db.add_repository(&repo_path, "Test Repo").await.unwrap();
-        assert!(id > 0, "Repository ID should be positive");
+        Ok(result.rows_affected() > 0)
+    }
 
-        let repo = db.get_repository(id).await.unwrap().unwrap();
-        assert_eq!(repo.path, repo_path);
-        assert_eq!(repo.name, "Test Repo");
-        assert!(repo.enabled);
+    /// Delete a repository and all its associated data
+    pub async fn delete_repository(&self, id: i64) -> Result<bool> {
+        // Delete queued jobs first
+        sqlx::query("DELETE FROM jobs WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete jobs")?;
+
+        // Delete delta analysis snapshots and change history
+        sqlx::query("DELETE FROM file_snapshots WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete file snapshots")?;
+        sqlx::query("DELETE FROM file_changelog WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete file changelog")?;
+
+        sqlx::query("DELETE FROM file_status WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete file statuses")?;
+        sqlx::query("DELETE FROM reanalysis_requests WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete reanalysis requests")?;
+
+        sqlx::query("DELETE FROM diagram_requests WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete diagram requests")?;
+
+        sqlx::query("DELETE FROM usage WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete usage")?;
+
+        sqlx::query("DELETE FROM issues WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete issues")?;
+
+        sqlx::query("DELETE FROM findings WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete findings")?;
+
+        sqlx::query("DELETE FROM duplicate_locations WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete duplicate locations")?;
+        sqlx::query("DELETE FROM duplicate_clusters WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete duplicate clusters")?;
+
+        sqlx::query("DELETE FROM embeddings WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete embeddings")?;
+
+        sqlx::query("DELETE FROM scores WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete scores")?;
+
+        sqlx::query("DELETE FROM chat_messages WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete chat messages")?;
+
+        sqlx::query("DELETE FROM architecture_models WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete architecture models")?;
+        sqlx::query("DELETE FROM onboarding WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete onboarding")?;
+
+        // Delete the notification log
+        sqlx::query("DELETE FROM notifications WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete notifications")?;
+
+        // Delete associated diagrams
+        sqlx::query("DELETE FROM diagrams WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete diagrams")?;
+
+        sqlx::query("DELETE FROM mutation_deferrals WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete mutation deferrals")?;
+        sqlx::query("DELETE FROM mutation_runs WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete mutation runs")?;
+        sqlx::query("DELETE FROM mutation_bases WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete mutation bases")?;
+
+        sqlx::query("DELETE FROM mutation_replacements WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete mutation replacements")?;
+        sqlx::query("DELETE FROM test_suggestions WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete test suggestions")?;
+
+        // Delete associated mutation results
+        sqlx::query("DELETE FROM mutation_results WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete mutation results")?;
+
+        // Delete associated analysis results
+        sqlx::query("DELETE FROM analysis_results WHERE repository_id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete analysis results")?;
+
+        // Delete the repository itself
+        let result = sqlx::query("DELETE FROM repositories WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete repository")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get recent analysis results (latest per file), optionally only those
+    /// produced by `model`
+    pub async fn get_recent_results(
+        &self,
+        limit: i32,
+        model: Option<&str>,
+    ) -> Result<Vec<AnalysisResult>> {
+        // Get the latest result for each file/analysis_type combination
+        let results = sqlx::query_as::<_, AnalysisResult>(
+            r#"
+            SELECT ar.* FROM analysis_results ar
+            INNER JOIN (
+                SELECT file_path, analysis_type, MAX(created_at) as max_created
+                FROM analysis_results
+                GROUP BY file_path, analysis_type
+            ) latest ON ar.file_path = latest.file_path
+                AND ar.analysis_type = latest.analysis_type
+                AND ar.created_at = latest.max_created
+            WHERE ($1 IS NULL OR ar.model = $1) AND ar.stale = 0
+            ORDER BY ar.created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(model)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch analysis results")?;
+
+        Ok(results)
+    }
+
+    /// Get daemon status
+    pub async fn get_daemon_status(&self) -> Result<DaemonState> {
+        let state = sqlx::query_as::<_, DaemonState>("SELECT * FROM daemon_state WHERE id = 1")
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to fetch daemon state")?;
+
+        Ok(state)
+    }
+
+    /// Update daemon status
+    pub async fn update_daemon_status(
+        &self,
+        status: &str,
+        current_task: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE daemon_state SET status = $1, current_task = $2, last_active = $3 WHERE id = 1",
+        )
+        .bind(status)
+        .bind(current_task)
+        .bind(current_timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to update daemon state")?;
+
+        Ok(())
+    }
+
+    /// Put the daemon in the error state, recording the error that ended its cycle
+    pub async fn record_daemon_error(&self, error: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE daemon_state SET status = 'error', current_task = NULL, last_error = $1, \
+             last_error_at = $2, last_active = $2 WHERE id = 1",
+        )
+        .bind(error)
+        .bind(current_timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to record daemon error")?;
+
+        Ok(())
+    }
+
+    /// Pause the schedule until the given time (RFC 3339), or resume it with None
+    pub async fn set_paused_until(&self, until: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE daemon_state SET paused_until = $1 WHERE id = 1")
+            .bind(until)
+            .execute(&self.pool)
+            .await
+            .context("Failed to update schedule pause")?;
+
+        Ok(())
+    }
+
+    /// Save an analysis result
+    #[allow(clippy::too_many_arguments)]
+    pub async fn save_analysis_result(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        analysis_type: &str,
+        result: &str,
+        severity: Option<&str>,
+        content_hash: Option<&str>,
+        attribution: &Attribution,
+    ) -> Result<i64> {
+        let row = sqlx::query(
+            "INSERT INTO analysis_results (repository_id, file_path, analysis_type, result, severity, content_hash, \
+             endpoint, model, duration_ms) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(analysis_type)
+        .bind(result)
+        .bind(severity)
+        .bind(content_hash)
+        .bind(&attribution.endpoint)
+        .bind(&attribution.model)
+        .bind(attribution.duration_ms)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to save analysis result")?;
+
+        Ok(sqlx::Row::get(&row, "id"))
+    }
+
+    /// Save analysis results of a repository in one transaction, returning
+    /// their ids in order
+    pub async fn save_analysis_results(
+        &self,
+        repository_id: i64,
+        results: &[NewAnalysisResult],
+    ) -> Result<Vec<i64>> {
+        if results.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (_write, mut tx) = self.begin_write().await?;
+
+        let mut ids = Vec::with_capacity(results.len());
+        for result in results {
+            let id = sqlx::query_scalar::<_, i64>(
+                "INSERT INTO analysis_results (repository_id, file_path, analysis_type, result, severity, content_hash, \
+                 endpoint, model, duration_ms) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+            )
+            .bind(repository_id)
+            .bind(&result.file_path)
+            .bind(&result.analysis_type)
+            .bind(&result.result)
+            .bind(&result.severity)
+            .bind(&result.content_hash)
+            .bind(&result.attribution.endpoint)
+            .bind(&result.attribution.model)
+            .bind(result.attribution.duration_ms)
+            .fetch_one(&mut *tx)
+            .await
+            .context("Failed to save analysis result")?;
+            ids.push(id);
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to save analysis results")?;
+        Ok(ids)
+    }
+
+    /// Get the latest content hash for a file
+    pub async fn get_latest_file_hash(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        analysis_type: &str,
+    ) -> Result<Option<String>> {
+        let result = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT content_hash FROM analysis_results \
+             WHERE repository_id = $1 AND file_path = $2 AND analysis_type = $3 \
+             ORDER BY id DESC LIMIT 1",
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(analysis_type)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch file hash")?;
+
+        Ok(result.flatten())
+    }
+
+    /// Get the latest analysis result of one type for a file
+    pub async fn get_latest_file_result(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        analysis_type: &str,
+    ) -> Result<Option<AnalysisResult>> {
+        let result = sqlx::query_as::<_, AnalysisResult>(
+            "SELECT * FROM analysis_results \
+             WHERE repository_id = $1 AND file_path = $2 AND analysis_type = $3 \
+             ORDER BY id DESC LIMIT 1",
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(analysis_type)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch file result")?;
+
+        Ok(result)
+    }
+
+    /// The most recent analysis results of a file, oldest first, optionally
+    /// limited to one repository and analysis type
+    pub async fn get_file_result_history(
+        &self,
+        repository_id: Option<i64>,
+        file_path: &str,
+        analysis_type: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<AnalysisResult>> {
+        let mut results = sqlx::query_as::<_, AnalysisResult>(
+            "SELECT * FROM analysis_results \
+             WHERE file_path = $1 AND ($2 IS NULL OR repository_id = $3) \
+               AND ($4 IS NULL OR analysis_type = $5) \
+             ORDER BY id DESC LIMIT $6",
+        )
+        .bind(file_path)
+        .bind(repository_id)
+        .bind(repository_id)
+        .bind(analysis_type)
+        .bind(analysis_type)
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch file result history")?;
+
+        results.reverse();
+        Ok(results)
+    }
+
+    /// Store the analyzed content of a file, replacing the previous snapshot
+    pub async fn save_file_snapshot(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        content_hash: &str,
+        content: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO file_snapshots (repository_id, file_path, content_hash, content)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (repository_id, file_path) DO UPDATE SET
+                content_hash = excluded.content_hash,
+                content = excluded.content,
+                updated_at = $5
+            "#,
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(content_hash)
+        .bind(content)
+        .bind(current_timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save file snapshot")?;
+
+        Ok(())
+    }
+
+    /// Get the last analyzed content of a file as `(content_hash, content)`
+    pub async fn get_file_snapshot(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+    ) -> Result<Option<(String, String)>> {
+        let snapshot = sqlx::query_as::<_, (String, String)>(
+            "SELECT content_hash, content FROM file_snapshots \
+             WHERE repository_id = $1 AND file_path = $2",
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch file snapshot")?;
+
+        Ok(snapshot)
+    }
+
+    /// Record a file's inventory status, or clear it when `status` is None
+    pub async fn set_file_status(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        status: Option<&str>,
+    ) -> Result<()> {
+        match status {
+            Some(status) => sqlx::query(
+                r#"
+                INSERT INTO file_status (repository_id, file_path, status)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (repository_id, file_path) DO UPDATE SET
+                    status = excluded.status,
+                    updated_at = $4
+                "#,
+            )
+            .bind(repository_id)
+            .bind(file_path)
+            .bind(status)
+            .bind(current_timestamp()),
+            None => {
+                sqlx::query("DELETE FROM file_status WHERE repository_id = $1 AND file_path = $2")
+                    .bind(repository_id)
+                    .bind(file_path)
+            }
+        }
+        .execute(&self.pool)
+        .await
+        .context("Failed to save file status")?;
+
+        Ok(())
+    }
+
+    /// Request that a file is analyzed again on the next scan, regardless of
+    /// its content hash
+    pub async fn request_reanalysis(&self, repository_id: i64, file_path: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO reanalysis_requests (repository_id, file_path, requested_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (repository_id, file_path) DO UPDATE SET
+                requested_at = excluded.requested_at
+            "#,
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(now_millis())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save reanalysis request")?;
+
+        Ok(())
+    }
+
+    /// Whether a reanalysis of the file was requested
+    pub async fn is_reanalysis_requested(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+    ) -> Result<bool> {
+        let requested = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM reanalysis_requests WHERE repository_id = $1 AND file_path = $2",
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to look up reanalysis request")?;
+
+        Ok(requested > 0)
+    }
+
+    /// Files of a repository whose reanalysis was requested
+    pub async fn get_reanalysis_requests(&self, repository_id: i64) -> Result<Vec<String>> {
+        let files = sqlx::query_scalar::<_, String>(
+            "SELECT file_path FROM reanalysis_requests WHERE repository_id = $1 ORDER BY file_path",
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch reanalysis requests")?;
+
+        Ok(files)
+    }
+
+    /// Remove the reanalysis requests of a repository made before `before`
+    /// (from [`now_millis`]), once a scan has analyzed the files
+    pub async fn clear_reanalysis_requests(&self, repository_id: i64, before: &str) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM reanalysis_requests WHERE repository_id = $1 AND requested_at < $2",
+        )
+        .bind(repository_id)
+        .bind(before)
+        .execute(&self.pool)
+        .await
+        .context("Failed to clear reanalysis requests")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Get the inventory statuses of a repository's files
+    pub async fn get_file_statuses(&self, repository_id: i64) -> Result<Vec<FileStatus>> {
+        let statuses = sqlx::query_as::<_, FileStatus>(
+            "SELECT * FROM file_status WHERE repository_id = $1 ORDER BY file_path",
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch file statuses")?;
+
+        Ok(statuses)
+    }
+
+    /// Replace the imported open issues of a repository
+    pub async fn replace_issues(&self, repository_id: i64, issues: &[TrackerIssue]) -> Result<()> {
+        let (_write, mut tx) = self.begin_write().await?;
+
+        sqlx::query("DELETE FROM issues WHERE repository_id = $1")
+            .bind(repository_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete issues")?;
+
+        for issue in issues {
+            sqlx::query(
+                r#"
+                INSERT INTO issues (repository_id, number, title, url, labels)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (repository_id, number) DO UPDATE SET
+                    title = excluded.title,
+                    url = excluded.url,
+                    labels = excluded.labels
+                "#,
+            )
+            .bind(repository_id)
+            .bind(issue.number)
+            .bind(&issue.title)
+            .bind(&issue.url)
+            .bind(issue.labels.join(","))
+            .execute(&mut *tx)
+            .await
+            .context("Failed to save issue")?;
+        }
+
+        tx.commit().await.context("Failed to save issues")?;
+        Ok(())
+    }
+
+    /// Save the structured findings of an analysis result of `content`.
+    ///
+    /// A finding with the fingerprint of one an earlier result of the same file
+    /// and analysis type reported moves to this result instead of being added
+    /// again: it keeps its first sighting, counts another occurrence and is
+    /// reopened if it was resolved. Findings of earlier results this one no
+    /// longer reports are marked resolved.
+    pub async fn save_findings(
+        &self,
+        analysis_result_id: i64,
+        repository_id: i64,
+        file_path: &str,
+        content: &str,
+        findings: &[StructuredFinding],
+    ) -> Result<()> {
+        let now = now_millis();
+        let (_write, mut tx) = self.begin_write().await?;
+
+        let analysis_type: String =
+            sqlx::query_scalar("SELECT analysis_type FROM analysis_results WHERE id = $1")
+                .bind(analysis_result_id)
+                .fetch_one(&mut *tx)
+                .await
+                .context("Failed to fetch the analysis result of findings")?;
+
+        for finding in findings {
+            let fingerprint = findings::fingerprint(file_path, finding, content);
+            let existing: Option<(i64, i64)> = sqlx::query_as(
+                r#"
+                SELECT f.id, f.analysis_result_id FROM findings f
+                INNER JOIN analysis_results ar ON ar.id = f.analysis_result_id
+                WHERE f.repository_id = $1 AND f.file_path = $2 AND f.fingerprint = $3
+                  AND ar.analysis_type = $4
+                ORDER BY f.id DESC LIMIT 1
+                "#,
+            )
+            .bind(repository_id)
+            .bind(file_path)
+            .bind(&fingerprint)
+            .bind(&analysis_type)
+            .fetch_optional(&mut *tx)
+            .await
+            .context("Failed to look up finding")?;
+
+            match existing {
+                // Reported twice in the same result
+                Some((_, result_id)) if result_id == analysis_result_id => {}
+                Some((id, _)) => {
+                    sqlx::query(
+                        r#"
+                        UPDATE findings SET analysis_result_id = $1, title = $2, severity = $3,
+                            category = $4, line_start = $5, line_end = $6, recommendation = $7,
+                            last_seen = $8, occurrences = occurrences + 1, resolved_at = NULL
+                        WHERE id = $9
+                        "#,
+                    )
+                    .bind(analysis_result_id)
+                    .bind(&finding.title)
+                    .bind(&finding.severity)
+                    .bind(&finding.category)
+                    .bind(finding.line_start.map(i64::from))
+                    .bind(finding.line_end.map(i64::from))
+                    .bind(&finding.recommendation)
+                    .bind(&now)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to update finding")?;
+                }
+                None => {
+                    sqlx::query(
+                        r#"
+                        INSERT INTO findings (analysis_result_id, repository_id, file_path, title,
+                            severity, category, line_start, line_end, recommendation,
+                            fingerprint, first_seen, last_seen, first_result_id)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                        "#,
+                    )
+                    .bind(analysis_result_id)
+                    .bind(repository_id)
+                    .bind(file_path)
+                    .bind(&finding.title)
+                    .bind(&finding.severity)
+                    .bind(&finding.category)
+                    .bind(finding.line_start.map(i64::from))
+                    .bind(finding.line_end.map(i64::from))
+                    .bind(&finding.recommendation)
+                    .bind(&fingerprint)
+                    .bind(&now)
+                    .bind(&now)
+                    .bind(analysis_result_id)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to save finding")?;
+                }
+            }
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE findings SET resolved_at = $1
+            WHERE repository_id = $2 AND file_path = $3 AND resolved_at IS NULL
+              AND analysis_result_id != $4
+              AND analysis_result_id IN (
+                  SELECT id FROM analysis_results
+                  WHERE repository_id = $5 AND file_path = $6 AND analysis_type = $7
+              )
+            "#,
+        )
+        .bind(&now)
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(analysis_result_id)
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(&analysis_type)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to resolve findings")?;
+
+        tx.commit().await.context("Failed to save findings")?;
+        Ok(())
+    }
+
+    /// Get the findings of a repository that stopped being reported, most
+    /// recently resolved first
+    pub async fn get_resolved_findings(
+        &self,
+        repository_id: i64,
+        limit: i64,
+    ) -> Result<Vec<AnalysisFinding>> {
+        let findings = sqlx::query_as::<_, AnalysisFinding>(
+            r#"
+            SELECT * FROM findings
+            WHERE repository_id = $1 AND resolved_at IS NOT NULL
+            ORDER BY resolved_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(repository_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch resolved findings")?;
+
+        Ok(findings)
+    }
+
+    /// Replace the duplicate clusters of a repository with those of a new scan
+    pub async fn replace_duplicate_clusters(
+        &self,
+        repository_id: i64,
+        clusters: &[NewDuplicateCluster],
+    ) -> Result<()> {
+        let (_write, mut tx) = self.begin_write().await?;
+
+        sqlx::query("DELETE FROM duplicate_locations WHERE repository_id = $1")
+            .bind(repository_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete duplicate locations")?;
+        sqlx::query("DELETE FROM duplicate_clusters WHERE repository_id = $1")
+            .bind(repository_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete duplicate clusters")?;
+
+        for cluster in clusters {
+            let cluster_id: i64 = sqlx::query_scalar(
+                "INSERT INTO duplicate_clusters (repository_id, fingerprint, line_count) \
+                 VALUES ($1, $2, $3) RETURNING id",
+            )
+            .bind(repository_id)
+            .bind(&cluster.fingerprint)
+            .bind(cluster.line_count)
+            .fetch_one(&mut *tx)
+            .await
+            .context("Failed to save duplicate cluster")?;
+
+            for location in &cluster.locations {
+                sqlx::query(
+                    r#"
+                    INSERT INTO duplicate_locations (cluster_id, repository_id, file_path,
+                        line_start, line_end, file_lines)
+                    VALUES ($1, $2, $3, $4, $5, $6)
+                    "#,
+                )
+                .bind(cluster_id)
+                .bind(repository_id)
+                .bind(&location.file_path)
+                .bind(location.line_start)
+                .bind(location.line_end)
+                .bind(location.file_lines)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to save duplicate location")?;
+            }
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to save duplicate clusters")?;
+        Ok(())
+    }
+
+    /// Get the duplicate clusters of a repository, largest first
+    pub async fn get_duplicate_clusters(
+        &self,
+        repository_id: i64,
+    ) -> Result<Vec<DuplicateCluster>> {
+        let mut clusters = sqlx::query_as::<_, DuplicateCluster>(
+            "SELECT * FROM duplicate_clusters WHERE repository_id = $1 \
+             ORDER BY line_count DESC, id",
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch duplicate clusters")?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT cluster_id, file_path, line_start, line_end, file_lines
+            FROM duplicate_locations
+            WHERE repository_id = $1
+            ORDER BY cluster_id, file_path, line_start
+            "#,
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch duplicate locations")?;
+
+        let mut by_cluster: HashMap<i64, Vec<DuplicateLocation>> = HashMap::new();
+        for row in &rows {
+            let location = <DuplicateLocation as sqlx::FromRow<_>>::from_row(row)
+                .context("Failed to decode duplicate location")?;
+            by_cluster
+                .entry(sqlx::Row::get(row, "cluster_id"))
+                .or_default()
+                .push(location);
+        }
+        for cluster in &mut clusters {
+            cluster.locations = by_cluster.remove(&cluster.id).unwrap_or_default();
+        }
+        Ok(clusters)
+    }
+
+    /// Get the findings of a repository's latest analysis results, most severe first
+    pub async fn get_findings(&self, repository_id: i64) -> Result<Vec<AnalysisFinding>> {
+        let findings = sqlx::query_as::<_, AnalysisFinding>(
+            r#"
+            SELECT f.* FROM findings f
+            INNER JOIN analysis_results ar ON ar.id = f.analysis_result_id
+            INNER JOIN (
+                SELECT file_path, analysis_type, MAX(created_at) as max_created
+                FROM analysis_results
+                WHERE repository_id = $1
+                GROUP BY file_path, analysis_type
+            ) latest ON ar.file_path = latest.file_path
+                AND ar.analysis_type = latest.analysis_type
+                AND ar.created_at = latest.max_created
+            WHERE f.repository_id = $1 AND f.resolved_at IS NULL AND ar.stale = 0
+            ORDER BY CASE f.severity WHEN 'error' THEN 0 WHEN 'warning' THEN 1 ELSE 2 END,
+                f.file_path, f.line_start, f.id
+            "#,
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch findings")?;
+
+        Ok(findings)
+    }
+
+    /// Get the open error and warning findings of the latest analysis results
+    /// of all repositories that aren't archived, at most `limit`. They are
+    /// ordered most severe first, or newest first with `recent_first`, and
+    /// `severity` keeps only findings of that severity.
+    pub async fn get_open_findings(
+        &self,
+        severity: Option<&str>,
+        recent_first: bool,
+        limit: i64,
+    ) -> Result<Vec<AnalysisFinding>> {
+        let rank = "CASE f.severity WHEN 'error' THEN 0 ELSE 1 END";
+        let seen = "COALESCE(f.first_seen, ar.created_at) DESC";
+        let order = if recent_first {
+            format!("{}, {}, f.id DESC", seen, rank)
+        } else {
+            format!("{}, {}, f.id DESC", rank, seen)
+        };
+        let findings = sqlx::query_as::<_, AnalysisFinding>(&format!(
+            r#"
+            SELECT f.* FROM findings f
+            INNER JOIN analysis_results ar ON ar.id = f.analysis_result_id
+            INNER JOIN repositories r ON r.id = f.repository_id
+            INNER JOIN (
+                SELECT repository_id, file_path, analysis_type, MAX(created_at) as max_created
+                FROM analysis_results
+                GROUP BY repository_id, file_path, analysis_type
+            ) latest ON ar.repository_id = latest.repository_id
+                AND ar.file_path = latest.file_path
+                AND ar.analysis_type = latest.analysis_type
+                AND ar.created_at = latest.max_created
+            WHERE f.resolved_at IS NULL AND r.archived_at IS NULL AND ar.stale = 0
+              AND f.severity IN ('error', 'warning')
+              AND ($1 IS NULL OR f.severity = $1)
+            ORDER BY {}
+            LIMIT $2
+            "#,
+            order
+        ))
+        .bind(severity)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch open findings")?;
+
+        Ok(findings)
+    }
+
+    /// Get the imported open issues of a repository, by number
+    pub async fn get_issues(&self, repository_id: i64) -> Result<Vec<Issue>> {
+        let issues = sqlx::query_as::<_, Issue>(
+            "SELECT * FROM issues WHERE repository_id = $1 ORDER BY number",
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch issues")?;
+
+        Ok(issues)
+    }
+
+    /// Add token usage to today's totals for an endpoint, repository and analysis type
+    pub async fn record_usage(
+        &self,
+        repository_id: i64,
+        endpoint: &str,
+        analysis_type: &str,
+        usage: &TokenUsage,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO usage (day, endpoint, repository_id, analysis_type, requests, prompt_tokens, completion_tokens, retries, rejected)
+            VALUES ($9, $1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (day, endpoint, repository_id, analysis_type) DO UPDATE SET
+                requests = usage.requests + excluded.requests,
+                prompt_tokens = usage.prompt_tokens + excluded.prompt_tokens,
+                completion_tokens = usage.completion_tokens + excluded.completion_tokens,
+                retries = usage.retries + excluded.retries,
+                rejected = usage.rejected + excluded.rejected
+            "#,
+        )
+        .bind(endpoint)
+        .bind(repository_id)
+        .bind(analysis_type)
+        .bind(usage.requests)
+        .bind(usage.prompt_tokens)
+        .bind(usage.completion_tokens)
+        .bind(usage.retries)
+        .bind(usage.rejected)
+        .bind(date_days_ago(0))
+        .execute(&self.pool)
+        .await
+        .context("Failed to record usage")?;
+
+        Ok(())
+    }
+
+    /// Token usage of the last `days` days (including today), per endpoint,
+    /// repository and analysis type, heaviest first
+    pub async fn get_usage_totals(&self, days: i64) -> Result<Vec<UsageTotal>> {
+        let totals = sqlx::query_as::<_, UsageTotal>(
+            r#"
+            SELECT u.endpoint, u.repository_id, r.name AS repository_name, u.analysis_type,
+                   CAST(SUM(u.requests) AS BIGINT) AS requests,
+                   CAST(SUM(u.prompt_tokens) AS BIGINT) AS prompt_tokens,
+                   CAST(SUM(u.completion_tokens) AS BIGINT) AS completion_tokens,
+                   CAST(SUM(u.retries) AS BIGINT) AS retries,
+                   CAST(SUM(u.rejected) AS BIGINT) AS rejected
+            FROM usage u
+            LEFT JOIN repositories r ON r.id = u.repository_id
+            WHERE u.day > $1
+            GROUP BY u.endpoint, u.repository_id, r.name, u.analysis_type
+            ORDER BY SUM(u.prompt_tokens + u.completion_tokens) DESC
+            "#,
+        )
+        .bind(date_days_ago(days))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch usage totals")?;
+
+        Ok(totals)
+    }
+
+    /// Token usage per day over the last `days` days (including today), newest first
+    pub async fn get_daily_usage(&self, days: i64) -> Result<Vec<DailyUsage>> {
+        let daily = sqlx::query_as::<_, DailyUsage>(
+            r#"
+            SELECT day,
+                   CAST(SUM(requests) AS BIGINT) AS requests,
+                   CAST(SUM(prompt_tokens) AS BIGINT) AS prompt_tokens,
+                   CAST(SUM(completion_tokens) AS BIGINT) AS completion_tokens,
+                   CAST(SUM(retries) AS BIGINT) AS retries,
+                   CAST(SUM(rejected) AS BIGINT) AS rejected
+            FROM usage
+            WHERE day > $1
+            GROUP BY day
+            ORDER BY day DESC
+            "#,
+        )
+        .bind(date_days_ago(days))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch daily usage")?;
+
+        Ok(daily)
+    }
+
+    /// Append an entry to a file's change log
+    pub async fn save_changelog_entry(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        entry: &str,
+        content_hash: Option<&str>,
+    ) -> Result<i64> {
+        let row = sqlx::query(
+            "INSERT INTO file_changelog (repository_id, file_path, entry, content_hash) \
+             VALUES ($1, $2, $3, $4) RETURNING id",
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(entry)
+        .bind(content_hash)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to save changelog entry")?;
+
+        Ok(sqlx::Row::get(&row, "id"))
+    }
+
+    /// Get change log entries for a repository, newest first, optionally for one file
+    pub async fn get_changelog(
+        &self,
+        repository_id: i64,
+        file_path: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<ChangelogEntry>> {
+        let entries = sqlx::query_as::<_, ChangelogEntry>(
+            r#"
+            SELECT * FROM file_changelog
+            WHERE repository_id = $1 AND ($2 IS NULL OR file_path = $2)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch changelog")?;
+
+        Ok(entries)
+    }
+
+    /// Get the findings of one analysis result, most severe first
+    pub async fn get_result_findings(
+        &self,
+        analysis_result_id: i64,
+    ) -> Result<Vec<AnalysisFinding>> {
+        let findings = sqlx::query_as::<_, AnalysisFinding>(
+            r#"
+            SELECT * FROM findings WHERE analysis_result_id = $1
+            ORDER BY CASE severity WHEN 'error' THEN 0 WHEN 'warning' THEN 1 ELSE 2 END,
+                line_start, id
+            "#,
+        )
+        .bind(analysis_result_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch findings")?;
+
+        Ok(findings)
+    }
+
+    /// Store the embedding of a file's analysis result, replacing the file's
+    /// previous one
+    pub async fn save_embedding(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        analysis_result_id: i64,
+        model: &str,
+        vector: &[u8],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO embeddings
+                (repository_id, file_path, analysis_result_id, model, vector, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (repository_id, file_path) DO UPDATE SET
+                analysis_result_id = excluded.analysis_result_id,
+                model = excluded.model,
+                vector = excluded.vector,
+                created_at = excluded.created_at
+            "#,
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(analysis_result_id)
+        .bind(model)
+        .bind(vector)
+        .bind(current_timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save embedding")?;
+
+        Ok(())
+    }
+
+    /// The analysis result each of a repository's files was embedded from
+    /// with `model`
+    pub async fn get_embedded_results(
+        &self,
+        repository_id: i64,
+        model: &str,
+    ) -> Result<HashMap<String, i64>> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT file_path, analysis_result_id FROM embeddings WHERE repository_id = $1 AND model = $2",
+        )
+        .bind(repository_id)
+        .bind(model)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch embeddings")?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// All embeddings made with `model`, across repositories
+    pub async fn get_embeddings(&self, model: &str) -> Result<Vec<Embedding>> {
+        let embeddings = sqlx::query_as::<_, Embedding>(
+            r#"
+            SELECT e.repository_id, r.name AS repository_name, r.path AS repository_path,
+                e.file_path,
+                e.analysis_result_id, e.vector
+            FROM embeddings e
+            INNER JOIN repositories r ON r.id = e.repository_id
+            WHERE e.model = $1
+            "#,
+        )
+        .bind(model)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch embeddings")?;
+
+        Ok(embeddings)
+    }
+
+    /// Get analysis results for a specific repository and analysis type (latest per file)
+    pub async fn get_repository_results(
+        &self,
+        repository_id: i64,
+        analysis_type: &str,
+    ) -> Result<Vec<AnalysisResult>> {
+        // Get only the latest result for each file
+        let results = sqlx::query_as::<_, AnalysisResult>(
+            r#"
+            SELECT ar.* FROM analysis_results ar
+            INNER JOIN (
+                SELECT file_path, MAX(created_at) as max_created
+                FROM analysis_results
+                WHERE repository_id = $1 AND analysis_type = $2
+                GROUP BY file_path
+            ) latest ON ar.file_path = latest.file_path
+                AND ar.created_at = latest.max_created
+            WHERE ar.repository_id = $3 AND ar.analysis_type = $4 AND ar.stale = 0
+            ORDER BY ar.file_path
+            "#,
+        )
+        .bind(repository_id)
+        .bind(analysis_type)
+        .bind(repository_id)
+        .bind(analysis_type)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch repository results")?;
+
+        Ok(results)
+    }
+
+    /// Get all analysis results for a repository (latest per file/type)
+    pub async fn get_all_repository_results(
+        &self,
+        repository_id: i64,
+    ) -> Result<Vec<AnalysisResult>> {
+        let results = sqlx::query_as::<_, AnalysisResult>(
+            r#"
+            SELECT ar.* FROM analysis_results ar
+            INNER JOIN (
+                SELECT file_path, analysis_type, MAX(created_at) as max_created
+                FROM analysis_results
+                WHERE repository_id = $1
+                GROUP BY file_path, analysis_type
+            ) latest ON ar.file_path = latest.file_path
+                AND ar.analysis_type = latest.analysis_type
+                AND ar.created_at = latest.max_created
+            WHERE ar.repository_id = $2 AND ar.stale = 0
+            ORDER BY ar.analysis_type DESC, ar.file_path
+            "#,
+        )
+        .bind(repository_id)
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch all repository results")?;
+
+        Ok(results)
+    }
+
+    /// Save a mutation test result.
+    ///
+    /// A mutation that was acknowledged or marked won't fix when it was last
+    /// tested (same file and description) keeps that triage state.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn save_mutation_result(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        description: &str,
+        reasoning: &str,
+        replacements: &[MutationReplacement],
+        test_outcome: &str,
+        killing_test: Option<&str>,
+        test_output: Option<&str>,
+        execution_time_ms: Option<i32>,
+        content_hash: Option<&str>,
+        toolchain: Option<&str>,
+        covered: Option<bool>,
+        project: Option<&str>,
+        attribution: &Attribution,
+    ) -> Result<i64> {
+        let (_write, mut tx) = self.begin_write().await?;
+
+        let row = sqlx::query(
+            r#"
+            INSERT INTO mutation_results (
+                repository_id, file_path, description, reasoning, replacements_json,
+                test_outcome, killing_test, test_output, execution_time_ms, content_hash,
+                toolchain, covered, project, endpoint, model, duration_ms, status
+            ) VALUES ($1, $2, $3, $4, '[]', $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, COALESCE((
+                SELECT CASE WHEN status IN ('acknowledged', 'wont_fix') THEN status END
+                FROM mutation_results
+                WHERE repository_id = $1 AND file_path = $2 AND description = $3
+                ORDER BY id DESC
+                LIMIT 1
+            ), 'new'))
+            RETURNING id
+            "#,
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(description)
+        .bind(reasoning)
+        .bind(test_outcome)
+        .bind(killing_test)
+        .bind(test_output)
+        .bind(execution_time_ms)
+        .bind(content_hash)
+        .bind(toolchain)
+        .bind(covered.map(i64::from))
+        .bind(project)
+        .bind(&attribution.endpoint)
+        .bind(&attribution.model)
+        .bind(attribution.duration_ms)
+        .fetch_one(&mut *tx)
+        .await
+        .context("Failed to save mutation result")?;
+        let id: i64 = sqlx::Row::get(&row, "id");
+
+        for replacement in replacements {
+            sqlx::query(
+                r#"
+                INSERT INTO mutation_replacements (mutation_result_id, repository_id,
+                    line_number, find, replace, original_line)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(id)
+            .bind(repository_id)
+            .bind(replacement.line_number)
+            .bind(&replacement.find)
+            .bind(&replacement.replace)
+            .bind(&replacement.original_line)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to save mutation replacement")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to save mutation result")?;
+        Ok(id)
+    }
+
+    /// Fill in the replacements of mutation results loaded from the database
+    async fn load_replacements(&self, results: &mut [MutationResult]) -> Result<()> {
+        if results.is_empty() {
+            return Ok(());
+        }
+        // The IDs are integers, so they are written into the query
+        let ids = results
+            .iter()
+            .map(|r| r.id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT mutation_result_id, line_number, find, replace, original_line
+            FROM mutation_replacements
+            WHERE mutation_result_id IN ({})
+            ORDER BY mutation_result_id, line_number, id
+            "#,
+            ids
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch mutation replacements")?;
+
+        let mut by_result: std::collections::HashMap<i64, Vec<MutationReplacement>> =
+            std::collections::HashMap::new();
+        for row in &rows {
+            let replacement = <MutationReplacement as sqlx::FromRow<_>>::from_row(row)
+                .context("Failed to decode mutation replacement")?;
+            by_result
+                .entry(sqlx::Row::get(row, "mutation_result_id"))
+                .or_default()
+                .push(replacement);
+        }
+        for result in results {
+            result.replacements = by_result.remove(&result.id).unwrap_or_default();
+        }
+        Ok(())
+    }
+
+    /// Surviving mutations of a repository that haven't been triaged as won't
+    /// fix or fixed and have no test suggestions yet, newest first
+    pub async fn get_survivors_without_suggestions(
+        &self,
+        repository_id: i64,
+        limit: u32,
+    ) -> Result<Vec<MutationResult>> {
+        let mut results = sqlx::query_as::<_, MutationResult>(
+            r#"
+            SELECT * FROM mutation_results m
+            WHERE repository_id = $1 AND test_outcome = 'survived'
+              AND status IN ('new', 'acknowledged')
+              AND NOT EXISTS (
+                  SELECT 1 FROM test_suggestions s WHERE s.mutation_result_id = m.id
+              )
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(repository_id)
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch surviving mutations")?;
+
+        self.load_replacements(&mut results).await?;
+        Ok(results)
+    }
+
+    /// Save the test cases suggested to kill a surviving mutation
+    pub async fn save_test_suggestions(
+        &self,
+        mutation_result_id: i64,
+        repository_id: i64,
+        suggestions: &[TestSuggestion],
+    ) -> Result<()> {
+        let (_write, mut tx) = self.begin_write().await?;
+
+        for suggestion in suggestions {
+            sqlx::query(
+                r#"
+                INSERT INTO test_suggestions (mutation_result_id, repository_id, name,
+                    scenario, assertion)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(mutation_result_id)
+            .bind(repository_id)
+            .bind(&suggestion.name)
+            .bind(&suggestion.scenario)
+            .bind(&suggestion.assertion)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to save test suggestion")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to save test suggestions")?;
+        Ok(())
+    }
+
+    /// Get the test suggestions of a repository's mutation results, by result ID
+    pub async fn get_test_suggestions(
+        &self,
+        repository_id: i64,
+    ) -> Result<HashMap<i64, Vec<MutationTestSuggestion>>> {
+        let suggestions = sqlx::query_as::<_, MutationTestSuggestion>(
+            r#"
+            SELECT mutation_result_id, name, scenario, assertion FROM test_suggestions
+            WHERE repository_id = $1
+            ORDER BY mutation_result_id, id
+            "#,
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch test suggestions")?;
+
+        let mut by_result: HashMap<i64, Vec<MutationTestSuggestion>> = HashMap::new();
+        for suggestion in suggestions {
+            by_result
+                .entry(suggestion.mutation_result_id)
+                .or_default()
+                .push(suggestion);
+        }
+        Ok(by_result)
+    }
+
+    /// Get an analysis result by ID
+    pub async fn get_analysis_result(&self, id: i64) -> Result<Option<AnalysisResult>> {
+        let result =
+            sqlx::query_as::<_, AnalysisResult>("SELECT * FROM analysis_results WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .context("Failed to fetch analysis result")?;
+
+        Ok(result)
+    }
+
+    /// Get a mutation result by ID
+    pub async fn get_mutation_result(&self, id: i64) -> Result<Option<MutationResult>> {
+        let result =
+            sqlx::query_as::<_, MutationResult>("SELECT * FROM mutation_results WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .context("Failed to fetch mutation result")?;
+
+        let mut results: Vec<MutationResult> = result.into_iter().collect();
+        self.load_replacements(&mut results).await?;
+        Ok(results.pop())
+    }
+
+    /// Record the issue filed from an analysis result
+    pub async fn set_analysis_issue_url(&self, id: i64, issue_url: &str) -> Result<()> {
+        sqlx::query("UPDATE analysis_results SET issue_url = $1 WHERE id = $2")
+            .bind(issue_url)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to save issue URL")?;
+
+        Ok(())
+    }
+
+    /// Set the triage state of a mutation result. Returns false if there is no
+    /// result with this ID.
+    pub async fn set_mutation_status(&self, id: i64, status: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE mutation_results SET status = $1 WHERE id = $2")
+            .bind(status)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to set mutation status")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record the issue filed from a mutation result
+    pub async fn set_mutation_issue_url(&self, id: i64, issue_url: &str) -> Result<()> {
+        sqlx::query("UPDATE mutation_results SET issue_url = $1 WHERE id = $2")
+            .bind(issue_url)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to save issue URL")?;
+
+        Ok(())
+    }
+
+    /// Get mutation results for a repository
+    pub async fn get_mutation_results(&self, repository_id: i64) -> Result<Vec<MutationResult>> {
+        let mut results = sqlx::query_as::<_, MutationResult>(
+            r#"
+            SELECT * FROM mutation_results
+            WHERE repository_id = $1
+            ORDER BY created_at DESC, file_path
+            "#,
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch mutation results")?;
+
+        self.load_replacements(&mut results).await?;
+        Ok(results)
+    }
+
+    /// Get one page of mutation results for a repository, newest first, together
+    /// with the total number of matching results.
+    ///
+    /// `filter.file` matches a substring of the file path.
+    pub async fn get_mutation_results_page(
+        &self,
+        repository_id: i64,
+        filter: &MutationFilter,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<MutationResult>, i64)> {
+        let position = match self.backend {
+            Backend::Sqlite => "instr(file_path, $3)",
+            Backend::Postgres => "strpos(file_path, $3)",
+        };
+        let filters = format!(
+            r#"
+            WHERE repository_id = $1
+              AND ($2 IS NULL OR test_outcome = $2)
+              AND ($3 IS NULL OR {} > 0)
+              AND ($4 IS NULL OR model = $4)
+              AND ($5 IS NULL OR status = $5)
+              AND ($6 IS NULL OR EXISTS (
+                  SELECT 1 FROM mutation_replacements r
+                  WHERE r.mutation_result_id = m.id AND r.line_number BETWEEN $6 AND $7
+              ))
+            "#,
+            position
+        );
+        let (line_start, line_end) = filter.lines.unzip();
+
+        let mut results = sqlx::query_as::<_, MutationResult>(&format!(
+            "SELECT * FROM mutation_results m {} \
+             ORDER BY created_at DESC, id DESC LIMIT $8 OFFSET $9",
+            filters
+        ))
+        .bind(repository_id)
+        .bind(&filter.outcome)
+        .bind(&filter.file)
+        .bind(&filter.model)
+        .bind(&filter.status)
+        .bind(line_start)
+        .bind(line_end)
+        .bind(i64::from(limit))
+        .bind(i64::from(offset))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch mutation results page")?;
+
+        let total = sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) FROM mutation_results m {}",
+            filters
+        ))
+        .bind(repository_id)
+        .bind(&filter.outcome)
+        .bind(&filter.file)
+        .bind(&filter.model)
+        .bind(&filter.status)
+        .bind(line_start)
+        .bind(line_end)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count mutation results")?;
+
+        self.load_replacements(&mut results).await?;
+        Ok((results, total))
+    }
+
+    /// Get mutation summary statistics for a repository
+    pub async fn get_mutation_summary(&self, repository_id: i64) -> Result<MutationSummary> {
+        let results = self.get_mutation_results(repository_id).await?;
+
+        let mut summary = MutationSummary::default();
+        for result in results {
+            summary.total += 1;
+            if let Some(covered) = result.covered {
+                let count = summary.covered_survived.get_or_insert(0);
+                if covered && result.test_outcome == "survived" {
+                    *count += 1;
+                }
+            }
+            match result.test_outcome.as_str() {
+                "killed" => summary.killed += 1,
+                "survived" => {
+                    summary.survived += 1;
+                    if result.status == "new" {
+                        summary.untriaged += 1;
+                    }
+                }
+                "timeout" => summary.timeout += 1,
+                "compile_error" => summary.compile_error += 1,
+                "rejected" => summary.rejected += 1,
+                _ => {}
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Check if a file has mutation results for a given content hash
+    pub async fn has_mutation_results_for_hash(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        content_hash: &str,
+    ) -> Result<bool> {
+        let count = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT COUNT(*) FROM mutation_results
+            WHERE repository_id = $1 AND file_path = $2 AND content_hash = $3
+            "#,
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(content_hash)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to check mutation results for hash")?;
+
+        Ok(count > 0)
+    }
+
+    /// Average execution time (build and tests) of the repository's recent
+    /// mutants, None if none has been executed yet
+    pub async fn get_average_mutation_time_ms(&self, repository_id: i64) -> Result<Option<i64>> {
+        let average = sqlx::query_scalar::<_, Option<f64>>(
+            r#"
+            SELECT CAST(AVG(execution_time_ms) AS DOUBLE PRECISION) FROM (
+                SELECT execution_time_ms FROM mutation_results
+                WHERE repository_id = $1 AND test_outcome != 'rejected'
+                  AND execution_time_ms > 0
+                ORDER BY id DESC
+                LIMIT 100
+            ) recent
+            "#,
+        )
+        .bind(repository_id)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to compute average mutation time")?;
+
+        Ok(average.map(|ms| ms.round() as i64))
+    }
+
+    /// Record that mutation testing of a repository was deferred to the next
+    /// schedule window, replacing any earlier deferral
+    pub async fn save_mutation_deferral(
+        &self,
+        repository_id: i64,
+        pending_files: i64,
+        estimated_seconds: i64,
+        remaining_window_seconds: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO mutation_deferrals (repository_id, pending_files, estimated_seconds,
+                remaining_window_seconds, deferred_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (repository_id) DO UPDATE SET
+                pending_files = excluded.pending_files,
+                estimated_seconds = excluded.estimated_seconds,
+                remaining_window_seconds = excluded.remaining_window_seconds,
+                deferred_at = excluded.deferred_at
+            "#,
+        )
+        .bind(repository_id)
+        .bind(pending_files)
+        .bind(estimated_seconds)
+        .bind(remaining_window_seconds)
+        .bind(current_timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save mutation deferral")?;
+
+        Ok(())
+    }
+
+    /// Clear a repository's deferral once its mutation testing completed
+    pub async fn clear_mutation_deferral(&self, repository_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM mutation_deferrals WHERE repository_id = $1")
+            .bind(repository_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to clear mutation deferral")?;
+
+        Ok(())
+    }
+
+    /// Get the pending deferral of a repository's mutation testing, if any
+    pub async fn get_mutation_deferral(
+        &self,
+        repository_id: i64,
+    ) -> Result<Option<MutationDeferral>> {
+        let deferral = sqlx::query_as::<_, MutationDeferral>(
+            "SELECT * FROM mutation_deferrals WHERE repository_id = $1",
+        )
+        .bind(repository_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch mutation deferral")?;
+
+        Ok(deferral)
+    }
+
+    /// Record the mutations generated for a file before they are executed,
+    /// replacing the run state of an earlier version of the file
+    pub async fn start_mutation_run(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        content_hash: &str,
+        mutations: &[GeneratedMutation],
+        attribution: &Attribution,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO mutation_runs (repository_id, file_path, content_hash, mutations,
+                endpoint, model, duration_ms, started_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (repository_id, file_path) DO UPDATE SET
+                content_hash = excluded.content_hash,
+                mutations = excluded.mutations,
+                executed = '[]',
+                endpoint = excluded.endpoint,
+                model = excluded.model,
+                duration_ms = excluded.duration_ms,
+                started_at = excluded.started_at
+            "#,
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .bind(content_hash)
+        .bind(serde_json::to_string(mutations)?)
+        .bind(&attribution.endpoint)
+        .bind(&attribution.model)
+        .bind(attribution.duration_ms)
+        .bind(current_timestamp())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save mutation run")?;
+
+        Ok(())
+    }
+
+    /// Record that the mutation at `index` of a file's run was executed
+    pub async fn mark_mutation_executed(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        index: usize,
+    ) -> Result<()> {
+        let append = match self.backend {
+            Backend::Sqlite => "json_insert(executed, '$[#]', $1)",
+            Backend::Postgres => "CAST(CAST(executed AS jsonb) || jsonb_build_array($1) AS TEXT)",
+        };
+        sqlx::query(&format!(
+            "UPDATE mutation_runs SET executed = {} WHERE repository_id = $2 AND file_path = $3",
+            append
+        ))
+        .bind(index as i64)
+        .bind(repository_id)
+        .bind(file_path)
+        .execute(&self.pool)
+        .await
+        .context("Failed to update mutation run")?;
+
+        Ok(())
+    }
+
+    /// Get the unfinished mutation run of a file, if any
+    pub async fn get_mutation_run(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+    ) -> Result<Option<MutationRun>> {
+        let run = sqlx::query_as::<_, MutationRun>(
+            "SELECT * FROM mutation_runs WHERE repository_id = $1 AND file_path = $2",
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch mutation run")?;
+
+        Ok(run)
+    }
+
+    /// Forget a file's mutation run once all its mutations were executed
+    pub async fn finish_mutation_run(&self, repository_id: i64, file_path: &str) -> Result<()> {
+        sqlx::query("DELETE FROM mutation_runs WHERE repository_id = $1 AND file_path = $2")
+            .bind(repository_id)
+            .bind(file_path)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete mutation run")?;
+
+        Ok(())
+    }
+
+    /// Record the commit a file's mutations were tested at, or forget it when
+    /// the tested content wasn't committed
+    pub async fn set_mutation_base(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+        commit_hash: Option<&str>,
+    ) -> Result<()> {
+        match commit_hash {
+            Some(commit_hash) => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO mutation_bases (repository_id, file_path, commit_hash, tested_at)
+                    VALUES ($1, $2, $3, $4)
+                    ON CONFLICT (repository_id, file_path) DO UPDATE SET
+                        commit_hash = excluded.commit_hash,
+                        tested_at = excluded.tested_at
+                    "#,
+                )
+                .bind(repository_id)
+                .bind(file_path)
+                .bind(commit_hash)
+                .bind(current_timestamp())
+                .execute(&self.pool)
+                .await
+                .context("Failed to save mutation base")?;
+            }
+            None => {
+                sqlx::query(
+                    "DELETE FROM mutation_bases WHERE repository_id = $1 AND file_path = $2",
+                )
+                .bind(repository_id)
+                .bind(file_path)
+                .execute(&self.pool)
+                .await
+                .context("Failed to delete mutation base")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The commit a file's mutations were last tested at, if recorded
+    pub async fn get_mutation_base(
+        &self,
+        repository_id: i64,
+        file_path: &str,
+    ) -> Result<Option<String>> {
+        let commit_hash = sqlx::query_scalar::<_, String>(
+            "SELECT commit_hash FROM mutation_bases WHERE repository_id = $1 AND file_path = $2",
+        )
+        .bind(repository_id)
+        .bind(file_path)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch mutation base")?;
+
+        Ok(commit_hash)
+    }
+
+    /// Append an entry to the audit log
+    pub async fn record_audit(
+        &self,
+        repository_id: Option<i64>,
+        action: &str,
+        detail: &str,
+    ) -> Result<()> {
+        sqlx::query("INSERT INTO audit_log (repository_id, action, detail) VALUES ($1, $2, $3)")
+            .bind(repository_id)
+            .bind(action)
+            .bind(detail)
+            .execute(&self.pool)
+            .await
+            .context("Failed to record audit entry")?;
+
+        Ok(())
+    }
+
+    /// Get the most recent audit log entries, newest first
+    pub async fn get_audit_log(&self, limit: i64) -> Result<Vec<AuditEntry>> {
+        let entries = sqlx::query_as::<_, AuditEntry>(
+            r#"
+            SELECT a.*, r.name AS repository_name
+            FROM audit_log a
+            LEFT JOIN repositories r ON r.id = a.repository_id
+            ORDER BY a.id DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch audit log")?;
+
+        Ok(entries)
+    }
+
+    /// Record the start of a processing cycle, returning its id
+    pub async fn start_scan_run(&self, trigger: &str) -> Result<i64> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO scan_runs (trigger, started_at, analysis_mark, mutation_mark)
+            VALUES (
+                $1, $2,
+                (SELECT COALESCE(MAX(id), 0) FROM analysis_results),
+                (SELECT COALESCE(MAX(id), 0) FROM mutation_results)
+            )
+            RETURNING id
+            "#,
+        )
+        .bind(trigger)
+        .bind(now_millis())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to record scan run")?;
+
+        Ok(id)
+    }
+
+    /// Record the end of a processing cycle. The files analyzed and mutations
+    /// run are counted from the results saved since the cycle started.
+    pub async fn finish_scan_run(
+        &self,
+        id: i64,
+        status: &str,
+        repositories_processed: i64,
+        errors: i64,
+        last_error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE scan_runs SET
+                status = $1,
+                finished_at = $2,
+                repositories_processed = $3,
+                errors = $4,
+                last_error = $5,
+                files_analyzed = (
+                    SELECT COUNT(DISTINCT repository_id || ':' || file_path)
+                    FROM analysis_results
+                    WHERE id > scan_runs.analysis_mark
+                      AND analysis_type != 'architecture_summary'
+                ),
+                mutations_run = (
+                    SELECT COUNT(*) FROM mutation_results
+                    WHERE id > scan_runs.mutation_mark AND test_outcome != 'rejected'
+                )
+            WHERE id = $6
+            "#,
+        )
+        .bind(status)
+        .bind(now_millis())
+        .bind(repositories_processed)
+        .bind(errors)
+        .bind(last_error)
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to finish scan run")?;
+
+        Ok(())
+    }
+
+    /// Mark cycles still running as interrupted, after the daemon exited
+    /// without finishing them. Returns how many were marked.
+    pub async fn interrupt_scan_runs(&self) -> Result<u64> {
+        let result =
+            sqlx::query("UPDATE scan_runs SET status = 'interrupted' WHERE status = 'running'")
+                .execute(&self.pool)
+                .await
+                .context("Failed to mark interrupted scan runs")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Get the most recent processing cycles, newest first
+    pub async fn get_scan_runs(&self, limit: i64) -> Result<Vec<ScanRun>> {
+        let runs = sqlx::query_as::<_, ScanRun>(
+            r#"
+            SELECT id, trigger, status, started_at, finished_at, repositories_processed,
+                   files_analyzed, mutations_run, errors, last_error
+            FROM scan_runs
+            ORDER BY id DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch scan runs")?;
+
+        Ok(runs)
+    }
+
+    /// Get a processing cycle by ID
+    pub async fn get_scan_run(&self, id: i64) -> Result<Option<ScanRun>> {
+        let run = sqlx::query_as::<_, ScanRun>(
+            r#"
+            SELECT id, trigger, status, started_at, finished_at, repositories_processed,
+                   files_analyzed, mutations_run, errors, last_error
+            FROM scan_runs
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch scan run")?;
+
+        Ok(run)
+    }
+
+    /// Get the most recent digest
+    pub async fn last_digest(&self) -> Result<Option<DigestRecord>> {
+        let digest = sqlx::query_as::<_, DigestRecord>(
+            "SELECT id, sent_at, last_run_id FROM digests ORDER BY id DESC LIMIT 1",
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch the last digest")?;
+
+        Ok(digest)
+    }
+
+    /// Record a digest covering the scan runs up to `last_run_id`
+    pub async fn record_digest(&self, last_run_id: i64) -> Result<()> {
+        sqlx::query("INSERT INTO digests (sent_at, last_run_id) VALUES ($1, $2)")
+            .bind(now_millis())
+            .bind(last_run_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to record digest")?;
+
+        Ok(())
+    }
+
+    /// Get the finished processing cycles after `after_run_id` that started
+    /// at or after `since` (RFC 3339), oldest first
+    pub async fn get_finished_scan_runs(
+        &self,
+        after_run_id: i64,
+        since: &str,
+    ) -> Result<Vec<ScanRun>> {
+        let runs = sqlx::query_as::<_, ScanRun>(
+            r#"
+            SELECT id, trigger, status, started_at, finished_at, repositories_processed,
+                   files_analyzed, mutations_run, errors, last_error
+            FROM scan_runs
+            WHERE id > $1 AND started_at >= $2 AND status != 'running'
+            ORDER BY id
+            "#,
+        )
+        .bind(after_run_id)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch finished scan runs")?;
+
+        Ok(runs)
+    }
+
+    /// Summarize per repository what the scan runs from `first_run_id` on
+    /// produced: files analyzed, findings no earlier result reported, and the
+    /// mutation score before and after. Repositories without new results are
+    /// left out.
+    pub async fn get_repository_digests(&self, first_run_id: i64) -> Result<Vec<RepositoryDigest>> {
+        let digests = sqlx::query_as::<_, RepositoryDigest>(
+            r#"
+            WITH marks AS (
+                SELECT analysis_mark, mutation_mark FROM scan_runs WHERE id = $1
+            ),
+            new_findings AS (
+                SELECT f.repository_id, f.severity FROM findings f, marks
+                WHERE f.first_result_id > marks.analysis_mark
+                  AND NOT EXISTS (
+                      SELECT 1 FROM findings earlier
+                      WHERE earlier.repository_id = f.repository_id
+                        AND earlier.first_result_id <= marks.analysis_mark
+                        AND earlier.file_path = f.file_path
+                        AND earlier.title = f.title
+                  )
+            ),
+            mutations AS (
+                SELECT m.repository_id, m.test_outcome, m.id > marks.mutation_mark AS new
+                FROM mutation_results m, marks
+            )
+            SELECT r.id AS repository_id, r.name,
+                (SELECT COUNT(DISTINCT a.file_path) FROM analysis_results a, marks
+                 WHERE a.repository_id = r.id AND a.id > marks.analysis_mark
+                   AND a.analysis_type != 'architecture_summary') AS files_analyzed,
+                (SELECT COUNT(*) FROM new_findings n
+                 WHERE n.repository_id = r.id AND n.severity = 'error') AS new_errors,
+                (SELECT COUNT(*) FROM new_findings n
+                 WHERE n.repository_id = r.id AND n.severity = 'warning') AS new_warnings,
+                (SELECT COUNT(*) FROM new_findings n
+                 WHERE n.repository_id = r.id AND n.severity = 'info') AS new_infos,
+                (SELECT COUNT(*) FROM mutations m
+                 WHERE m.repository_id = r.id AND m.new AND m.test_outcome != 'rejected') AS mutations_run,
+                (SELECT COUNT(*) FROM mutations m
+                 WHERE m.repository_id = r.id AND NOT m.new AND m.test_outcome = 'killed') AS killed_before,
+                (SELECT COUNT(*) FROM mutations m
+                 WHERE m.repository_id = r.id AND NOT m.new AND m.test_outcome = 'survived') AS survived_before,
+                (SELECT COUNT(*) FROM mutations m
+                 WHERE m.repository_id = r.id AND m.test_outcome = 'killed') AS killed,
+                (SELECT COUNT(*) FROM mutations m
+                 WHERE m.repository_id = r.id AND m.test_outcome = 'survived') AS survived
+            FROM repositories r
+            ORDER BY r.name
+            "#,
+        )
+        .bind(first_run_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to summarize repositories for the digest")?;
+
+        Ok(digests
+            .into_iter()
+            .filter(|digest| digest.files_analyzed > 0 || digest.mutations_run > 0)
+            .collect())
+    }
+
+    /// Collect what the health score of a repository is computed from
+    pub async fn get_score_inputs(&self, repository_id: i64) -> Result<ScoreInputs> {
+        let inputs = sqlx::query_as::<_, ScoreInputs>(
+            r#"
+            WITH latest AS (
+                SELECT MAX(id) AS id FROM analysis_results
+                WHERE repository_id = $1 AND analysis_type = 'code_understanding' AND stale = 0
+                GROUP BY file_path
+            ),
+            latest_findings AS (
+                SELECT f.file_path, f.severity, f.category FROM findings f
+                INNER JOIN latest ON latest.id = f.analysis_result_id
+            )
+            SELECT
+                (SELECT COUNT(*) FROM latest) AS files,
+                (SELECT COUNT(DISTINCT file_path) FROM latest_findings
+                 WHERE category = 'documentation') AS undocumented_files,
+                (SELECT COUNT(*) FROM latest_findings WHERE severity = 'error') AS errors,
+                (SELECT COUNT(*) FROM latest_findings WHERE severity = 'warning') AS warnings,
+                (SELECT COUNT(*) FROM latest_findings WHERE severity = 'info') AS infos,
+                (SELECT COUNT(*) FROM mutation_results
+                 WHERE repository_id = $1 AND test_outcome = 'killed') AS killed,
+                (SELECT COUNT(*) FROM mutation_results
+                 WHERE repository_id = $1 AND test_outcome = 'survived') AS survived
+            "#,
+        )
+        .bind(repository_id)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to collect score inputs")?;
+
+        Ok(inputs)
+    }
+
+    /// Record the health score computed from `inputs`, unless it can't be
+    /// computed or nothing changed since the last one. Returns whether a score
+    /// was recorded.
+    pub async fn record_score(
+        &self,
+        repository_id: i64,
+        scan_run_id: Option<i64>,
+        inputs: &ScoreInputs,
+    ) -> Result<bool> {
+        let Some(score) = inputs.health() else {
+            return Ok(false);
+        };
+        let (kill_rate, finding_score, doc_coverage) = (
+            inputs.kill_rate(),
+            inputs.finding_score(),
+            inputs.doc_coverage(),
+        );
+
+        let latest = sqlx::query_as::<_, HealthScore>(
+            "SELECT * FROM scores WHERE repository_id = $1 ORDER BY id DESC LIMIT 1",
+        )
+        .bind(repository_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch the latest score")?;
+        if latest.is_some_and(|latest| {
+            latest.score == score
+                && latest.kill_rate == kill_rate
+                && latest.finding_score == finding_score
+                && latest.doc_coverage == doc_coverage
+        }) {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO scores (repository_id, scan_run_id, score, kill_rate, finding_score,
+                doc_coverage, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(repository_id)
+        .bind(scan_run_id)
+        .bind(score)
+        .bind(kill_rate)
+        .bind(finding_score)
+        .bind(doc_coverage)
+        .bind(now_millis())
+        .execute(&self.pool)
+        .await
+        .context("Failed to record score")?;
+
+        Ok(true)
+    }
+
+    /// Get the last `per_repository` health scores of every repository, oldest
+    /// first, keyed by repository id
+    pub async fn get_recent_scores(
+        &self,
+        per_repository: i64,
+    ) -> Result<HashMap<i64, Vec<HealthScore>>> {
+        let scores = sqlx::query_as::<_, HealthScore>(
+            r#"
+            SELECT id, repository_id, scan_run_id, score, kill_rate, finding_score,
+                doc_coverage, created_at
+            FROM (
+                SELECT *, ROW_NUMBER() OVER (PARTITION BY repository_id ORDER BY id DESC) AS n
+                FROM scores
+            ) recent
+            WHERE n <= $1
+            ORDER BY id
+            "#,
+        )
+        .bind(per_repository)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch scores")?;
+
+        let mut by_repository: HashMap<i64, Vec<HealthScore>> = HashMap::new();
+        for score in scores {
+            by_repository
+                .entry(score.repository_id)
+                .or_default()
+                .push(score);
+        }
+        Ok(by_repository)
+    }
+
+    /// Add a message to a repository's chat
+    pub async fn save_chat_message(
+        &self,
+        repository_id: i64,
+        role: &str,
+        content: &str,
+        sources: &[String],
+    ) -> Result<ChatMessage> {
+        let message = sqlx::query_as::<_, ChatMessage>(
+            r#"
+            INSERT INTO chat_messages (repository_id, role, content, sources)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(repository_id)
+        .bind(role)
+        .bind(content)
+        .bind(serde_json::to_string(sources)?)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to save chat message")?;
+
+        Ok(message)
+    }
+
+    /// Get the last `limit` messages of a repository's chat, oldest first
+    pub async fn get_chat_messages(
+        &self,
+        repository_id: i64,
+        limit: i64,
+    ) -> Result<Vec<ChatMessage>> {
+        let mut messages = sqlx::query_as::<_, ChatMessage>(
+            "SELECT * FROM chat_messages WHERE repository_id = $1 ORDER BY id DESC LIMIT $2",
+        )
+        .bind(repository_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch chat messages")?;
+
+        messages.reverse();
+        Ok(messages)
+    }
+
+    /// Delete a repository's chat, returning the number of messages deleted
+    pub async fn clear_chat_messages(&self, repository_id: i64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM chat_messages WHERE repository_id = $1")
+            .bind(repository_id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to clear chat messages")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Replace the structured architecture model of a repository
+    pub async fn save_architecture_model(
+        &self,
+        repository_id: i64,
+        model: &ArchitectureModel,
+        attribution: &Attribution,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO architecture_models (repository_id, model, endpoint, llm_model, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT(repository_id) DO UPDATE SET
+                model = excluded.model,
+                endpoint = excluded.endpoint,
+                llm_model = excluded.llm_model,
+                created_at = excluded.created_at
+            "#,
+        )
+        .bind(repository_id)
+        .bind(serde_json::to_string(model)?)
+        .bind(&attribution.endpoint)
+        .bind(&attribution.model)
+        .bind(now_millis())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save architecture model")?;
+
+        Ok(())
+    }
+
+    /// Get the structured architecture model of a repository, if one was generated
+    pub async fn get_architecture_model(
+        &self,
+        repository_id: i64,
+    ) -> Result<Option<StoredArchitectureModel>> {
+        let model = sqlx::query_as::<_, StoredArchitectureModel>(
+            "SELECT * FROM architecture_models WHERE repository_id = $1",
+        )
+        .bind(repository_id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch architecture model")?;
+
+        Ok(model)
+    }
+
+    /// Start the quick scan of a newly added repository, holding it back from
+    /// full scans until it is confirmed
+    pub async fn start_onboarding(&self, repository_id: i64) -> Result<()> {
+        let now = now_millis();
+        sqlx::query(
+            r#"
+            INSERT INTO onboarding (repository_id, status, quick_scan, created_at, updated_at)
+            VALUES ($1, $2, '{}', $3, $4)
+            ON CONFLICT(repository_id) DO UPDATE SET
+                status = excluded.status,
+                quick_scan = excluded.quick_scan,
+                error = NULL,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(repository_id)
+        .bind(onboarding::STATUS_SCANNING)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .context("Failed to start onboarding")?;
+
+        Ok(())
+    }
+
+    /// Store what the quick scan of a repository found. A repository confirmed
+    /// while its quick scan ran stays confirmed.
+    pub async fn finish_onboarding(&self, repository_id: i64, scan: &QuickScan) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE onboarding SET quick_scan = $1, updated_at = $2,
+                status = CASE status WHEN $3 THEN $4 ELSE status END
+            WHERE repository_id = $5
+            "#,
+        )
+        .bind(serde_json::to_string(scan)?)
+        .bind(now_millis())
+        .bind(onboarding::STATUS_SCANNING)
+        .bind(onboarding::STATUS_READY)
+        .bind(repository_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to save quick scan")?;
+
+        Ok(())
+    }
+
+    /// Record that the quick scan of a repository failed
+    pub async fn fail_onboarding(&self, repository_id: i64, error: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE onboarding SET error = $1, updated_at = $2,
+                status = CASE status WHEN $3 THEN $4 ELSE status END
+            WHERE repository_id = $5
+            "#,
+        )
+        .bind(error)
+        .bind(now_millis())
+        .bind(onboarding::STATUS_SCANNING)
+        .bind(onboarding::STATUS_FAILED)
+        .bind(repository_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to save quick scan failure")?;
+
+        Ok(())
+    }
+
+    /// Confirm a repository for full scans. Returns false if it wasn't waiting
+    /// for confirmation.
+    pub async fn confirm_onboarding(&self, repository_id: i64) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE onboarding SET status = $1, updated_at = $2 WHERE repository_id = $3 AND status != $4",
+        )
+        .bind(onboarding::STATUS_CONFIRMED)
+        .bind(now_millis())
+        .bind(repository_id)
+        .bind(onboarding::STATUS_CONFIRMED)
+        .execute(&self.pool)
+        .await
+        .context("Failed to confirm onboarding")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the onboarding state of a repository (None unless added from the dashboard)
+    pub async fn get_onboarding(&self, repository_id: i64) -> Result<Option<Onboarding>> {
+        let onboarding =
+            sqlx::query_as::<_, Onboarding>("SELECT * FROM onboarding WHERE repository_id = $1")
+                .bind(repository_id)
+                .fetch_optional(&self.pool)
+                .await
+                .context("Failed to fetch onboarding")?;
+
+        Ok(onboarding)
+    }
+
+    /// IDs of the repositories full scans skip until they are confirmed
+    pub async fn get_unconfirmed_repositories(&self) -> Result<Vec<i64>> {
+        let ids = sqlx::query_scalar("SELECT repository_id FROM onboarding WHERE status != $1")
+            .bind(onboarding::STATUS_CONFIRMED)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to fetch unconfirmed repositories")?;
+
+        Ok(ids)
+    }
+
+    /// ID of the newest analysis result (0 without any), marking where a scan starts
+    pub async fn latest_analysis_result_id(&self) -> Result<i64> {
+        let id: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM analysis_results")
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to fetch the latest analysis result")?;
+
+        Ok(id)
+    }
+
+    /// Get the error-severity findings of a repository's analysis results newer
+    /// than `since_result_id` that no earlier result reported for the same file
+    pub async fn get_new_critical_findings(
+        &self,
+        repository_id: i64,
+        since_result_id: i64,
+    ) -> Result<Vec<AnalysisFinding>> {
+        let findings = sqlx::query_as::<_, AnalysisFinding>(
+            r#"
+            SELECT f.* FROM findings f
+            WHERE f.repository_id = $1
+              AND f.severity = 'error'
+              AND f.first_result_id > $2
+              AND NOT EXISTS (
+                  SELECT 1 FROM findings earlier
+                  WHERE earlier.repository_id = $1
+                    AND earlier.first_result_id <= $2
+                    AND earlier.file_path = f.file_path
+                    AND earlier.title = f.title
+              )
+            ORDER BY f.file_path, f.line_start, f.id
+            "#,
+        )
+        .bind(repository_id)
+        .bind(since_result_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch new critical findings")?;
+
+        Ok(findings)
+    }
+
+    /// Save a new diagram (inserts new row, keeping history)
+    #[allow(clippy::too_many_arguments)]
+    pub async fn save_diagram(
+        &self,
+        repository_id: i64,
+        diagram_type: &str,
+        title: &str,
+        description: &str,
+        format: &str,
+        dot_content: &str,
+        svg_content: &str,
+        content_hash: Option<&str>,
+    ) -> Result<i64> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO diagrams (repository_id, diagram_type, title, description, format, dot_content, svg_content, content_hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id
+            "#,
+        )
+        .bind(repository_id)
+        .bind(diagram_type)
+        .bind(title)
+        .bind(description)
+        .bind(format)
+        .bind(dot_content)
+        .bind(svg_content)
+        .bind(content_hash)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to save diagram")?;
+
+        Ok(sqlx::Row::get(&row, "id"))
+    }
+
+    /// Get the latest diagram of each type for a repository
+    pub async fn get_latest_diagrams(&self, repository_id: i64) -> Result<Vec<Diagram>> {
+        let diagrams = sqlx::query_as::<_, Diagram>(
+            r#"
+            SELECT d.* FROM diagrams d
+            INNER JOIN (
+                SELECT diagram_type, MAX(id) as max_id
+                FROM diagrams
+                WHERE repository_id = $1
+                GROUP BY diagram_type
+            ) latest ON d.diagram_type = latest.diagram_type
+                AND d.id = latest.max_id
+            WHERE d.repository_id = $2
+            ORDER BY d.diagram_type
+            "#,
+        )
+        .bind(repository_id)
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch latest diagrams")?;
+
+        Ok(diagrams)
+    }
+
+    /// Get the latest diagram of one type for a repository
+    pub async fn get_latest_diagram(
+        &self,
+        repository_id: i64,
+        diagram_type: &str,
+    ) -> Result<Option<Diagram>> {
+        let diagram = sqlx::query_as::<_, Diagram>(
+            r#"
+            SELECT * FROM diagrams
+            WHERE repository_id = $1 AND diagram_type = $2
+            ORDER BY id DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(repository_id)
+        .bind(diagram_type)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch diagram")?;
+
+        Ok(diagram)
+    }
+
+    /// Get a diagram by ID
+    pub async fn get_diagram(&self, id: i64) -> Result<Option<Diagram>> {
+        let diagram = sqlx::query_as::<_, Diagram>("SELECT * FROM diagrams WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch diagram")?;
+
+        Ok(diagram)
+    }
+
+    /// Get the saved versions of one diagram type for a repository, oldest first
+    pub async fn get_diagram_versions(
+        &self,
+        repository_id: i64,
+        diagram_type: &str,
+    ) -> Result<Vec<DiagramVersion>> {
+        let versions = sqlx::query_as::<_, DiagramVersion>(
+            r#"
+            SELECT id, format, content_hash, created_at FROM diagrams
+            WHERE repository_id = $1 AND diagram_type = $2
+            ORDER BY id
+            "#,
+        )
+        .bind(repository_id)
+        .bind(diagram_type)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch diagram versions")?;
+
+        Ok(versions)
+    }
+
+    /// Request that the next scan generates a diagram again, even if its
+    /// sources are unchanged
+    pub async fn request_diagram_regeneration(
+        &self,
+        repository_id: i64,
+        diagram_type: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO diagram_requests (repository_id, diagram_type, requested_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (repository_id, diagram_type) DO UPDATE SET
+                requested_at = excluded.requested_at
+            "#,
+        )
+        .bind(repository_id)
+        .bind(diagram_type)
+        .bind(now_millis())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save diagram request")?;
+
+        Ok(())
+    }
+
+    /// Diagram types of a repository whose regeneration was requested
+    pub async fn get_diagram_requests(&self, repository_id: i64) -> Result<Vec<String>> {
+        let types = sqlx::query_scalar::<_, String>(
+            "SELECT diagram_type FROM diagram_requests WHERE repository_id = $1 ORDER BY diagram_type",
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch diagram requests")?;
+
+        Ok(types)
+    }
+
+    /// Remove the request for a diagram if it was made before `before` (from
+    /// [`now_millis`]), once a scan has generated the diagram
+    pub async fn clear_diagram_request(
+        &self,
+        repository_id: i64,
+        diagram_type: &str,
+        before: &str,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM diagram_requests \
+             WHERE repository_id = $1 AND diagram_type = $2 AND requested_at < $3",
+        )
+        .bind(repository_id)
+        .bind(diagram_type)
+        .bind(before)
+        .execute(&self.pool)
+        .await
+        .context("Failed to clear diagram request")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the latest content hash for diagrams of a repository
+    /// Used to determine if diagrams need regeneration
+    pub async fn get_latest_diagram_hash(
+        &self,
+        repository_id: i64,
+        diagram_type: &str,
+    ) -> Result<Option<String>> {
+        let result = sqlx::query_scalar::<_, Option<String>>(
+            r#"
+            SELECT content_hash FROM diagrams
+            WHERE repository_id = $1 AND diagram_type = $2
+            ORDER BY id DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(repository_id)
+        .bind(diagram_type)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch diagram hash")?;
+
+        Ok(result.flatten())
+    }
+
+    /// Enqueue a job, reusing an existing pending job for the same work item
+    pub async fn enqueue_job(
+        &self,
+        repository_id: i64,
+        job_type: &str,
+        file_path: Option<&str>,
+    ) -> Result<i64> {
+        let existing = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT id FROM jobs
+            WHERE repository_id = $1 AND job_type = $2 AND file_path IS NOT DISTINCT FROM $3
+              AND status = 'pending'
+            ORDER BY id
+            LIMIT 1
+            "#,
+        )
+        .bind(repository_id)
+        .bind(job_type)
+        .bind(file_path)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to look up pending job")?;
+
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+
+        let row = sqlx::query(
+            "INSERT INTO jobs (repository_id, job_type, file_path) VALUES ($1, $2, $3) RETURNING id",
+        )
+        .bind(repository_id)
+        .bind(job_type)
+        .bind(file_path)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to enqueue job")?;
+
+        Ok(sqlx::Row::get(&row, "id"))
+    }
+
+    /// Update the status of a job, recording start/finish timestamps
+    pub async fn update_job_status(
+        &self,
+        id: i64,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE jobs SET
+                status = $1,
+                error = $2,
+                started_at = CASE WHEN $1 = 'running' THEN $3 ELSE started_at END,
+                finished_at = CASE WHEN $1 IN ('done', 'failed') THEN $3 ELSE NULL END
+            WHERE id = $4
+            "#,
+        )
+        .bind(status)
+        .bind(error)
+        .bind(current_timestamp())
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to update job status")?;
+
+        Ok(())
+    }
+
+    /// Mark a pending job as running on `endpoint`. Returns false if the job is
+    /// no longer pending (e.g. it was removed from the queue).
+    pub async fn claim_job(&self, id: i64, endpoint: Option<&str>) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE jobs SET status = 'running', endpoint = $1, error = NULL,
+                started_at = $2, finished_at = NULL
+            WHERE id = $3 AND status = 'pending'
+            "#,
+        )
+        .bind(endpoint)
+        .bind(current_timestamp())
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to claim job")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get running and pending jobs: running first, then pending by priority
+    /// (highest first) and age
+    pub async fn get_queued_jobs(&self) -> Result<Vec<Job>> {
+        let jobs = sqlx::query_as::<_, Job>(
+            r#"
+            SELECT j.*, r.name AS repository_name FROM jobs j
+            LEFT JOIN repositories r ON r.id = j.repository_id
+            WHERE j.status IN ('running', 'pending')
+            ORDER BY CASE j.status WHEN 'running' THEN 0 ELSE 1 END, j.priority DESC, j.id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch queued jobs")?;
+
+        Ok(jobs)
+    }
+
+    /// Delete a pending job. Returns false if there is no pending job with this ID.
+    pub async fn delete_pending_job(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM jobs WHERE id = $1 AND status = 'pending'")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete pending job")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Set the priority of a pending job. Returns false if there is no pending
+    /// job with this ID.
+    pub async fn set_job_priority(&self, id: i64, priority: i64) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE jobs SET priority = $1 WHERE id = $2 AND status = 'pending'")
+                .bind(priority)
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .context("Failed to set job priority")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Highest priority of the pending jobs of each file in a repository
+    pub async fn get_pending_file_priorities(
+        &self,
+        repository_id: i64,
+    ) -> Result<Vec<(String, i64)>> {
+        let priorities = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT file_path, MAX(priority) FROM jobs
+            WHERE repository_id = $1 AND status = 'pending' AND file_path IS NOT NULL
+            GROUP BY file_path
+            "#,
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch pending file priorities")?;
+
+        Ok(priorities)
+    }
+
+    /// Get a job by ID
+    pub async fn get_job(&self, id: i64) -> Result<Option<Job>> {
+        let job = sqlx::query_as::<_, Job>(
+            r#"
+            SELECT j.*, r.name AS repository_name FROM jobs j
+            LEFT JOIN repositories r ON r.id = j.repository_id
+            WHERE j.id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch job")?;
+
+        Ok(job)
+    }
+
+    /// Get jobs for display: running first, then pending (highest priority, then
+    /// oldest first), then recently finished
+    pub async fn get_jobs(&self, limit: i32) -> Result<Vec<Job>> {
+        let jobs = sqlx::query_as::<_, Job>(
+            r#"
+            SELECT j.*, r.name AS repository_name FROM jobs j
+            LEFT JOIN repositories r ON r.id = j.repository_id
+            ORDER BY
+                CASE j.status WHEN 'running' THEN 0 WHEN 'pending' THEN 1 ELSE 2 END,
+                CASE WHEN j.status = 'pending' THEN -j.priority ELSE 0 END,
+                CASE WHEN j.status = 'pending' THEN j.id ELSE -j.id END
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch jobs")?;
+
+        Ok(jobs)
+    }
+
+    /// Get per-file jobs of one type whose most recent run failed
+    pub async fn get_failed_file_jobs(
+        &self,
+        repository_id: i64,
+        job_type: &str,
+    ) -> Result<Vec<Job>> {
+        let jobs = sqlx::query_as::<_, Job>(
+            r#"
+            SELECT j.*, NULL AS repository_name FROM jobs j
+            INNER JOIN (
+                SELECT file_path, MAX(id) AS max_id FROM jobs
+                WHERE repository_id = $1 AND job_type = $2 AND file_path IS NOT NULL
+                GROUP BY file_path
+            ) latest ON j.id = latest.max_id
+            WHERE j.status = 'failed'
+            ORDER BY j.file_path
+            "#,
+        )
+        .bind(repository_id)
+        .bind(job_type)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch failed jobs")?;
+
+        Ok(jobs)
+    }
+
+    /// Query analysis results across repositories, newest first
+    pub async fn query_findings(&self, filter: &QueryFilter) -> Result<Vec<FindingRow>> {
+        let rows = sqlx::query_as::<_, FindingRow>(
+            r#"
+            SELECT r.name AS repository, ar.file_path, ar.analysis_type, ar.severity,
+                   ar.result, ar.created_at, ar.endpoint, ar.model, ar.duration_ms
+            FROM analysis_results ar
+            INNER JOIN repositories r ON r.id = ar.repository_id
+            WHERE ($1 IS NULL OR r.name = $1)
+              AND ($2 IS NULL OR ar.severity = $2)
+              AND ($3 IS NULL OR ar.analysis_type = $3)
+              AND ($4 IS NULL OR ar.created_at >= $4)
+              AND ($6 IS NULL OR ar.model = $6)
+              AND ar.stale = 0
+            ORDER BY ar.created_at DESC, ar.id DESC
+            LIMIT $5
+            "#,
+        )
+        .bind(&filter.repository)
+        .bind(&filter.severity)
+        .bind(&filter.analysis_type)
+        .bind(filter.since_days.map(|days| days_ago(days.into())))
+        .bind(i64::from(filter.limit))
+        .bind(&filter.model)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to query findings")?;
+
+        Ok(rows)
+    }
+
+    /// Query mutation results across repositories, newest first
+    pub async fn query_mutations(&self, filter: &QueryFilter) -> Result<Vec<MutationRow>> {
+        let rows = sqlx::query_as::<_, MutationRow>(
+            r#"
+            SELECT r.name AS repository, m.file_path, m.description, m.test_outcome,
+                   m.killing_test, m.created_at, m.endpoint, m.model, m.duration_ms
+            FROM mutation_results m
+            INNER JOIN repositories r ON r.id = m.repository_id
+            WHERE ($1 IS NULL OR r.name = $1)
+              AND ($2 IS NULL OR m.test_outcome = $2)
+              AND ($3 IS NULL OR m.created_at >= $3)
+              AND ($5 IS NULL OR m.model = $5)
+            ORDER BY m.created_at DESC, m.id DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(&filter.repository)
+        .bind(&filter.outcome)
+        .bind(filter.since_days.map(|days| days_ago(days.into())))
+        .bind(i64::from(filter.limit))
+        .bind(&filter.model)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to query mutations")?;
+
+        Ok(rows)
+    }
+
+    /// Delete analysis and mutation results produced by `model`, optionally
+    /// limited to one repository.
+    ///
+    /// Files whose latest result is removed no longer match their content hash,
+    /// so the next scan regenerates them (unless an older result for the same
+    /// content remains, which then becomes the latest).
+    pub async fn delete_results_by_model(
+        &self,
+        model: &str,
+        repository_id: Option<i64>,
+    ) -> Result<PurgeCounts> {
+        let analysis = sqlx::query(
+            "DELETE FROM analysis_results WHERE model = $1 AND ($2 IS NULL OR repository_id = $2)",
+        )
+        .bind(model)
+        .bind(repository_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete analysis results")?;
+
+        let mutations = sqlx::query(
+            "DELETE FROM mutation_results WHERE model = $1 AND ($2 IS NULL OR repository_id = $2)",
+        )
+        .bind(model)
+        .bind(repository_id)
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete mutation results")?;
+
+        Ok(PurgeCounts {
+            analysis_results: analysis.rows_affected(),
+            mutation_results: mutations.rows_affected(),
+        })
+    }
+
+    /// The files a repository has analysis results for, each with whether its
+    /// results are marked stale
+    pub async fn get_result_files(&self, repository_id: i64) -> Result<Vec<(String, bool)>> {
+        let files: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT file_path, MAX(stale) AS stale FROM analysis_results
+            WHERE repository_id = $1
+            GROUP BY file_path
+            ORDER BY file_path
+            "#,
+        )
+        .bind(repository_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch result files")?;
+
+        Ok(files
+            .into_iter()
+            .map(|(file_path, stale)| (file_path, stale != 0))
+            .collect())
+    }
+
+    /// Mark the results of files of a repository stale (the files were deleted)
+    /// or current again (they came back). Returns the number of results changed.
+    pub async fn set_results_stale(
+        &self,
+        repository_id: i64,
+        file_paths: &[String],
+        stale: bool,
+    ) -> Result<u64> {
+        let (_write, mut tx) = self.begin_write().await?;
+        let mut changed = 0;
+        for file_path in file_paths {
+            changed += sqlx::query(
+                "UPDATE analysis_results SET stale = $1 WHERE repository_id = $2 AND file_path = $3",
+            )
+            .bind(i64::from(stale))
+            .bind(repository_id)
+            .bind(file_path)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to mark results stale")?
+            .rows_affected();
+        }
+        tx.commit().await.context("Failed to mark results stale")?;
+        Ok(changed)
+    }
+
+    /// Number of files of a repository whose results are stale
+    pub async fn count_stale_files(&self, repository_id: i64) -> Result<i64> {
+        let count = sqlx::query_scalar(
+            "SELECT COUNT(DISTINCT file_path) FROM analysis_results WHERE repository_id = $1 AND stale = 1",
+        )
+        .bind(repository_id)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count stale files")?;
+
+        Ok(count)
+    }
+
+    /// Delete the stale results of a repository, with their findings, and
+    /// return the number of results deleted
+    pub async fn purge_stale_results(&self, repository_id: i64) -> Result<u64> {
+        let result =
+            sqlx::query("DELETE FROM analysis_results WHERE repository_id = $1 AND stale = 1")
+                .bind(repository_id)
+                .execute(&self.pool)
+                .await
+                .context("Failed to purge stale results")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete superseded results, keeping the `keep_last` newest analysis results
+    /// per file and analysis type, the mutation results of the `keep_last` newest
+    /// content versions per file, and the `keep_last` newest diagrams per type.
+    /// `keep_last` must be at least 1 so the latest results survive.
+    pub async fn prune_history(&self, keep_last: u32) -> Result<PruneCounts> {
+        anyhow::ensure!(keep_last >= 1, "keep_last must be at least 1");
+
+        let analysis = sqlx::query(
+            r#"
+            DELETE FROM analysis_results WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, ROW_NUMBER() OVER (
+                        PARTITION BY repository_id, file_path, analysis_type
+                        ORDER BY created_at DESC, id DESC
+                    ) AS position
+                    FROM analysis_results
+                ) ranked
+                WHERE position > $1
+            )
+            "#,
+        )
+        .bind(i64::from(keep_last))
+        .execute(&self.pool)
+        .await
+        .context("Failed to prune analysis results")?;
+
+        // A mutation run produces many rows for the same content hash, so runs
+        // rather than rows are counted
+        let mutations = sqlx::query(
+            r#"
+            DELETE FROM mutation_results WHERE id IN (
+                SELECT m.id
+                FROM mutation_results m
+                JOIN (
+                    SELECT repository_id, file_path, content_hash, ROW_NUMBER() OVER (
+                        PARTITION BY repository_id, file_path
+                        ORDER BY MAX(id) DESC
+                    ) AS position
+                    FROM mutation_results
+                    GROUP BY repository_id, file_path, content_hash
+                ) runs ON m.repository_id = runs.repository_id
+                    AND m.file_path = runs.file_path
+                    AND m.content_hash IS NOT DISTINCT FROM runs.content_hash
+                WHERE runs.position > $1
+            )
+            "#,
+        )
+        .bind(i64::from(keep_last))
+        .execute(&self.pool)
+        .await
+        .context("Failed to prune mutation results")?;
+
+        let diagrams = sqlx::query(
+            r#"
+            DELETE FROM diagrams WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, ROW_NUMBER() OVER (
+                        PARTITION BY repository_id, diagram_type
+                        ORDER BY id DESC
+                    ) AS position
+                    FROM diagrams
+                ) ranked
+                WHERE position > $1
+            )
+            "#,
+        )
+        .bind(i64::from(keep_last))
+        .execute(&self.pool)
+        .await
+        .context("Failed to prune diagrams")?;
+
+        Ok(PruneCounts {
+            analysis_results: analysis.rows_affected(),
+            mutation_results: mutations.rows_affected(),
+            diagrams: diagrams.rows_affected(),
+        })
+    }
+
+    /// Reclaim the space of deleted rows. SQLite rebuilds the database file,
+    /// returning the space to the filesystem; PostgreSQL keeps it for new rows.
+    pub async fn vacuum(&self) -> Result<()> {
+        sqlx::query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .context("Failed to vacuum database")?;
+        Ok(())
+    }
+
+    /// Row counts and sizes of all tables, by name
+    pub async fn table_stats(&self) -> Result<Vec<TableStats>> {
+        let (names, sizes) = match self.backend {
+            Backend::Sqlite => (
+                "SELECT name FROM sqlite_master \
+                 WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+                // dbstat is an optional SQLite extension
+                "SELECT name, SUM(pgsize) FROM dbstat GROUP BY name",
+            ),
+            Backend::Postgres => (
+                "SELECT CAST(table_name AS TEXT) AS name FROM information_schema.tables \
+                 WHERE table_schema = current_schema() AND table_type = 'BASE TABLE' \
+                 ORDER BY name",
+                "SELECT CAST(table_name AS TEXT), \
+                     pg_table_size(CAST(quote_ident(table_name) AS regclass)) \
+                 FROM information_schema.tables \
+                 WHERE table_schema = current_schema() AND table_type = 'BASE TABLE'",
+            ),
+        };
+        let names: Vec<String> = sqlx::query_scalar(names)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list tables")?;
+        let sizes: Vec<(String, i64)> = sqlx::query_as(sizes)
+            .fetch_all(&self.pool)
+            .await
+            .unwrap_or_default();
+
+        let mut stats = Vec::with_capacity(names.len());
+        for name in names {
+            let rows: i64 = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM \"{}\"",
+                name.replace('"', "\"\"")
+            ))
+            .fetch_one(&self.pool)
+            .await
+            .with_context(|| format!("Failed to count rows of {}", name))?;
+            let bytes = sizes.iter().find(|(n, _)| *n == name).map(|(_, b)| *b);
+            stats.push(TableStats { name, rows, bytes });
+        }
+        Ok(stats)
+    }
+
+    /// Record a sent notification
+    pub async fn record_notification(&self, repository_id: i64, title: &str) -> Result<()> {
+        sqlx::query("INSERT INTO notifications (repository_id, title) VALUES ($1, $2)")
+            .bind(repository_id)
+            .bind(title)
+            .execute(&self.pool)
+            .await
+            .context("Failed to record notification")?;
+
+        Ok(())
+    }
+
+    /// Count notifications sent in the last 24 hours
+    pub async fn count_recent_notifications(&self) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM notifications WHERE created_at >= $1",
+        )
+        .bind(days_ago(1))
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count notifications")?;
+
+        Ok(count)
+    }
+
+    /// Count jobs by status
+    pub async fn get_job_counts(&self) -> Result<JobCounts> {
+        let rows =
+            sqlx::query_as::<_, (String, i64)>("SELECT status, COUNT(*) FROM jobs GROUP BY status")
+                .fetch_all(&self.pool)
+                .await
+                .context("Failed to count jobs")?;
+
+        let mut counts = JobCounts::default();
+        for (status, count) in rows {
+            match status.as_str() {
+                "pending" => counts.pending = count,
+                "running" => counts.running = count,
+                "done" => counts.done = count,
+                "failed" => counts.failed = count,
+                _ => {}
+            }
+        }
+
+        Ok(counts)
+    }
+
+    /// Reset jobs left running by an interrupted daemon back to pending
+    pub async fn reset_running_jobs(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE jobs SET status = 'pending', started_at = NULL WHERE status = 'running'",
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to reset running jobs")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Get IDs of repositories that have unfinished (pending) jobs, those with
+    /// the highest-priority job first
+    pub async fn get_repositories_with_pending_jobs(&self) -> Result<Vec<i64>> {
+        let ids = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT repository_id FROM jobs WHERE status = 'pending'
+            GROUP BY repository_id
+            ORDER BY MAX(priority) DESC, repository_id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch repositories with pending jobs")?;
+
+        Ok(ids)
+    }
+
+    /// Delete pending jobs for a repository (work that is no longer needed)
+    pub async fn delete_pending_jobs(&self, repository_id: i64) -> Result<u64> {
+        let result =
+            sqlx::query("DELETE FROM jobs WHERE repository_id = $1 AND status = 'pending'")
+                .bind(repository_id)
+                .execute(&self.pool)
+                .await
+                .context("Failed to delete pending jobs")?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete finished jobs older than the given number of days
+    pub async fn delete_finished_jobs(&self, older_than_days: u32) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM jobs
+            WHERE status IN ('done', 'failed')
+              AND finished_at < $1
+            "#,
+        )
+        .bind(days_ago(older_than_days.into()))
+        .execute(&self.pool)
+        .await
+        .context("Failed to delete finished jobs")?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::{NamedTempFile, TempDir};
+
+    async fn create_test_db() -> (Database, TempDir) {
+        // NOCTUM_TEST_POSTGRES_URL runs the tests on a PostgreSQL server instead,
+        // each in a new database
+        let Ok(url) = std::env::var("NOCTUM_TEST_POSTGRES_URL") else {
+            return create_sqlite_test_db().await;
+        };
+        let db = create_postgres_test_db(&url).await;
+        db.run_migrations().await.unwrap();
+        (db, TempDir::new().unwrap())
+    }
+
+    /// Create a SQLite test database, for tests of what only SQLite does
+    async fn create_sqlite_test_db() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+        (db, temp_dir)
+    }
+
+    async fn create_postgres_test_db(url: &str) -> Database {
+        static DATABASES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let name = format!(
+            "noctum_test_{}_{}",
+            std::process::id(),
+            DATABASES.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        );
+        let server = Database::open(&DatabaseUrl::Postgres(url.to_string()))
+            .await
+            .unwrap();
+        for statement in [
+            format!("DROP DATABASE IF EXISTS {}", name),
+            format!("CREATE DATABASE {}", name),
+        ] {
+            sqlx::query(&statement).execute(&server.pool).await.unwrap();
+        }
+        server.pool.close().await;
+
+        let (server_url, _) = url.rsplit_once('/').unwrap();
+        Database::open(&DatabaseUrl::Postgres(format!("{}/{}", server_url, name)))
+            .await
+            .unwrap()
+    }
+
+    /// Create a temporary repo directory with a noctum.toml file.
+    /// Returns the TempDir (must be kept alive for the path to remain valid).
+    fn create_test_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("noctum.toml"), "").unwrap();
+        temp_dir
+    }
+
+    /// Helper to add a test repository to the database.
+    /// Creates a temp directory with noctum.toml and adds it to the db.
+    async fn add_test_repo(db: &Database, name: &str) -> (i64, TempDir) {
+        let repo_dir = create_test_repo();
+        let repo_id = db
+            .add_repository(&repo_dir.path().to_string_lossy(), name)
+            .await
+            .unwrap();
+        (repo_id, repo_dir)
+    }
+
+    #[tokio::test]
+    async fn test_database_creation() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = Database::new(temp_file.path()).await;
+        assert!(db.is_ok(), "Database creation should succeed");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_access_settings() {
+        let (db, _temp_dir) = create_sqlite_test_db().await;
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+        let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(busy_timeout, BUSY_TIMEOUT.as_millis() as i64);
+    }
+
+    #[tokio::test]
+    async fn test_save_analysis_results_batch() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test Repo").await;
+        assert!(db
+            .save_analysis_results(repo_id, &[])
+            .await
+            .unwrap()
+            .is_empty());
+
+        let results: Vec<_> = ["/a.rs", "/b.rs"]
+            .iter()
+            .map(|file| NewAnalysisResult {
+                file_path: file.to_string(),
+                analysis_type: "plugin:lint".to_string(),
+                result: format!("{} is fine", file),
+                severity: Some("low".to_string()),
+                content_hash: Some("hash".to_string()),
+                attribution: Attribution {
+                    duration_ms: Some(5),
+                    ..Attribution::default()
+                },
+            })
+            .collect();
+
+        // Concurrent batches wait for each other instead of failing
+        let (first, second) = tokio::join!(
+            db.save_analysis_results(repo_id, &results),
+            db.save_analysis_results(repo_id, &results)
+        );
+        let ids = first.unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(second.unwrap().len(), 2);
+
+        let saved = db.get_analysis_result(ids[1]).await.unwrap().unwrap();
+        assert_eq!(saved.file_path, "/b.rs");
+        assert_eq!(saved.result, "/b.rs is fine");
+        assert_eq!(saved.severity.as_deref(), Some("low"));
+        assert_eq!(saved.duration_ms, Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_replace_duplicate_clusters() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test Repo").await;
+        let location = |file: &str, line_start| DuplicateLocation {
+            file_path: file.to_string(),
+            line_start,
+            line_end: line_start + 7,
+            file_lines: 40,
+        };
+        let cluster = |fingerprint: &str, line_count| NewDuplicateCluster {
+            fingerprint: fingerprint.to_string(),
+            line_count,
+            locations: vec![location("/b.rs", 3), location("/a.rs", 10)],
+        };
+
+        db.replace_duplicate_clusters(repo_id, &[cluster("old", 6)])
+            .await
+            .unwrap();
+        db.replace_duplicate_clusters(repo_id, &[cluster("small", 6), cluster("large", 8)])
+            .await
+            .unwrap();
+
+        let clusters = db.get_duplicate_clusters(repo_id).await.unwrap();
+        let fingerprints: Vec<_> = clusters.iter().map(|c| c.fingerprint.as_str()).collect();
+        assert_eq!(fingerprints, ["large", "small"]);
+        assert_eq!(
+            clusters[0].locations,
+            [location("/a.rs", 10), location("/b.rs", 3)]
+        );
+
+        assert!(db.delete_repository(repo_id).await.unwrap());
+        assert!(db.get_duplicate_clusters(repo_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_runs() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test Repo").await;
+        let save = |file: &'static str, analysis_type: &'static str| {
+            let db = db.clone();
+            async move {
+                db.save_analysis_result(
+                    repo_id,
+                    file,
+                    analysis_type,
+                    "{}",
+                    None,
+                    None,
+                    &Attribution::default(),
+                )
+                .await
+                .unwrap();
+            }
+        };
+        let save_mutation = |outcome: &'static str| {
+            let db = db.clone();
+            async move {
+                sqlx::query(
+                    "INSERT INTO mutation_results (repository_id, file_path, description, \
+                     reasoning, replacements_json, test_outcome) VALUES ($1, 'a.rs', 'd', 'r', '[]', $2)",
+                )
+                .bind(repo_id)
+                .bind(outcome)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+            }
+        };
+
+        // Results saved before the cycle don't count
+        save("/a.rs", "code_understanding").await;
+        save_mutation("killed").await;
+
+        let first = db.start_scan_run("scheduled").await.unwrap();
+        save("/a.rs", "code_understanding").await;
+        save("/a.rs", "security").await;
+        save("/b.rs", "code_understanding").await;
+        save("/repo", "architecture_summary").await;
+        save_mutation("survived").await;
+        save_mutation("rejected").await;
+        db.finish_scan_run(first, "completed", 1, 0, None)
+            .await
+            .unwrap();
+
+        let second = db.start_scan_run("manual").await.unwrap();
+        assert_eq!(db.interrupt_scan_runs().await.unwrap(), 1);
+
+        let runs = db.get_scan_runs(10).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].id, second);
+        assert_eq!(runs[0].status, "interrupted");
+        assert_eq!(runs[0].trigger, "manual");
+        assert!(runs[0].finished_at.is_none());
+
+        let run = &runs[1];
+        assert_eq!(run.status, "completed");
+        assert_eq!(run.repositories_processed, 1);
+        assert_eq!(run.files_analyzed, 2);
+        assert_eq!(run.mutations_run, 1);
+        assert_eq!(run.errors, 0);
+        assert!(run.finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_repository_digests() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test Repo").await;
+        add_test_repo(&db, "Idle Repo").await;
+        let save_mutation = |outcome: &'static str| {
+            let db = db.clone();
+            async move {
+                sqlx::query(
+                    "INSERT INTO mutation_results (repository_id, file_path, description, \
+                     reasoning, replacements_json, test_outcome) VALUES ($1, 'a.rs', 'd', 'r', '[]', $2)",
+                )
+                .bind(repo_id)
+                .bind(outcome)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+            }
+        };
+        let save_finding = |title: &'static str, severity: &'static str| {
+            let db = db.clone();
+            async move {
+                let id = db
+                    .save_analysis_result(
+                        repo_id,
+                        "src/a.rs",
+                        "security",
+                        "{}",
+                        None,
+                        None,
+                        &Attribution::default(),
+                    )
+                    .await
+                    .unwrap();
+                let finding = StructuredFinding {
+                    title: title.to_string(),
+                    severity: severity.to_string(),
+                    category: "security".to_string(),
+                    line_start: None,
+                    line_end: None,
+                    recommendation: String::new(),
+                };
+                db.save_findings(id, repo_id, "src/a.rs", "", &[finding])
+                    .await
+                    .unwrap();
+            }
+        };
+
+        save_mutation("killed").await;
+        save_mutation("survived").await;
+        save_finding("Injection", "error").await;
+
+        assert!(db.last_digest().await.unwrap().is_none());
+        let run = db.start_scan_run("scheduled").await.unwrap();
+        save_mutation("killed").await;
+        save_mutation("killed").await;
+        save_mutation("rejected").await;
+        save_finding("Injection", "error").await;
+        save_finding("Weak hash", "warning").await;
+        // Still running
+        assert!(db.get_finished_scan_runs(0, "").await.unwrap().is_empty());
+        db.finish_scan_run(run, "completed", 2, 0, None)
+            .await
+            .unwrap();
+        let runs = db.get_finished_scan_runs(0, "").await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert!(db
+            .get_finished_scan_runs(0, "2999-01-01T00:00:00.000Z")
+            .await
+            .unwrap()
+            .is_empty());
+
+        let digests = db.get_repository_digests(run).await.unwrap();
+        assert_eq!(
+            digests,
+            [RepositoryDigest {
+                repository_id: repo_id,
+                name: "Test Repo".to_string(),
+                files_analyzed: 1,
+                new_errors: 0,
+                new_warnings: 1,
+                new_infos: 0,
+                mutations_run: 2,
+                killed_before: 1,
+                survived_before: 1,
+                killed: 3,
+                survived: 1,
+            }]
+        );
+        assert_eq!(digests[0].score_before(), Some(0.5));
+        assert_eq!(digests[0].score(), Some(0.75));
+
+        db.record_digest(run).await.unwrap();
+        assert_eq!(db.last_digest().await.unwrap().unwrap().last_run_id, run);
+        assert!(db.get_finished_scan_runs(run, "").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations() {
+        let (db, _temp_dir) = create_test_db().await;
+        assert!(db.run_migrations().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_add_repository() {
+        let (db, _temp_dir) = create_test_db().await;
+        let repo_dir = create_test_repo();
+        let repo_path = repo_dir.path().to_string_lossy().to_string();
+
+        let id = db.add_repository(&repo_path, "Test Repo").await.unwrap();
+        assert!(id > 0, "Repository ID should be positive");
+
+        let repo = db.get_repository(id).await.unwrap().unwrap();
+        assert_eq!(repo.path, repo_path);
+        assert_eq!(repo.name, "Test Repo");
+        assert!(repo.enabled);
+    }
+
+    #[tokio::test]
+    async fn test_get_repositories() {
+        let (db, _temp_dir) = create_test_db().await;
+        let repo_dir1 = create_test_repo();
+        let repo_dir2 = create_test_repo();
+
+        db.add_repository(&repo_dir1.path().to_string_lossy(), "Repo 1")
+            .await
+            .unwrap();
+        db.add_repository(&repo_dir2.path().to_string_lossy(), "Repo 2")
+            .await
+            .unwrap();
+
+        let repos = db.get_repositories().await.unwrap();
+        assert_eq!(repos.len(), 2);
+        assert_eq!(repos[0].name, "Repo 1");
+        assert_eq!(repos[1].name, "Repo 2");
+    }
+
+    #[tokio::test]
+    async fn test_repository_analysis_types() {
+        let (db, _temp_dir) = create_test_db().await;
+        let repo_dir = create_test_repo();
+        let id = db
+            .add_repository(&repo_dir.path().to_string_lossy(), "Test Repo")
+            .await
+            .unwrap();
+
+        // New repositories run every pass
+        let repo = db.get_repository(id).await.unwrap().unwrap();
+        assert_eq!(repo.analysis_types, None);
+        assert_eq!(repo.analysis_passes(), ANALYSIS_PASSES);
+
+        assert!(db
+            .set_repository_analysis_types(id, &["code_understanding", "security"])
+            .await
+            .unwrap());
+        let repo = db.get_repository(id).await.unwrap().unwrap();
+        assert!(repo.runs("code_understanding"));
+        assert!(repo.runs("security"));
+        assert!(!repo.runs("mutation_testing"));
+        assert!(!repo.runs("diagrams"));
+
+        // Deselecting everything is remembered rather than reset to all
+        db.set_repository_analysis_types(id, &[]).await.unwrap();
+        let repo = db.get_repository(id).await.unwrap().unwrap();
+        assert!(repo.analysis_passes().is_empty());
+
+        assert!(!db.set_repository_analysis_types(999, &[]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_repository_tags() {
+        let (db, _temp_dir) = create_test_db().await;
+        let repo_dir = create_test_repo();
+        let id = db
+            .add_repository(&repo_dir.path().to_string_lossy(), "Test Repo")
+            .await
+            .unwrap();
+        assert!(db
+            .get_repository(id)
+            .await
+            .unwrap()
+            .unwrap()
+            .tags
+            .is_empty());
+
+        let tags = normalize_tags(&[" Work", "backend", "work", ""]).unwrap();
+        assert_eq!(tags, ["backend", "work"]);
+        assert!(db.set_repository_tags(id, &tags).await.unwrap());
+        let repo = db.get_repository(id).await.unwrap().unwrap();
+        assert!(repo.has_tag("work"));
+        assert!(!repo.has_tag("personal"));
+
+        assert!(normalize_tags(&["two words"]).is_err());
+        assert!(!db.set_repository_tags(999, &tags).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_set_repository_enabled() {
+        let (db, _temp_dir) = create_test_db().await;
+        let repo_dir = create_test_repo();
+        let id = db
+            .add_repository(&repo_dir.path().to_string_lossy(), "Test Repo")
+            .await
+            .unwrap();
+
+        assert!(db.set_repository_enabled(id, false).await.unwrap());
+        assert!(!db.get_repository(id).await.unwrap().unwrap().enabled);
+        assert!(db.set_repository_enabled(id, true).await.unwrap());
+        assert!(db.get_repository(id).await.unwrap().unwrap().enabled);
+        assert!(!db.set_repository_enabled(999, true).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_repository_not_found() {
+        let (db, _temp_dir) = create_test_db().await;
+
+        let repo = db.get_repository(999).await.unwrap();
+        assert!(repo.is_none(), "Non-existent repository should return None");
+    }
+
+    #[tokio::test]
+    async fn test_save_analysis_result() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let id = db
+            .save_analysis_result(
+                repo_id,
+                "src/main.rs",
+                "code_understanding",
+                "Test analysis result",
+                Some("info"),
+                Some("hash123"),
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+
+        assert!(id > 0);
+
+        let results = db
+            .get_repository_results(repo_id, "code_understanding")
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_path, "src/main.rs");
+        assert_eq!(results[0].analysis_type, "code_understanding");
+        assert_eq!(results[0].result, "Test analysis result");
+        assert_eq!(results[0].severity, Some("info".to_string()));
+        assert_eq!(results[0].content_hash, Some("hash123".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_get_recent_results() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        db.save_analysis_result(
+            repo_id,
+            "file1.rs",
+            "type1",
+            "result1",
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+        db.save_analysis_result(
+            repo_id,
+            "file2.rs",
+            "type2",
+            "result2",
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+
+        let results = db.get_recent_results(10, None).await.unwrap();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_repository_results() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        db.save_analysis_result(
+            repo_id,
+            "file1.rs",
+            "type1",
+            "result1",
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+        db.save_analysis_result(
+            repo_id,
+            "file2.rs",
+            "type1",
+            "result2",
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+        db.save_analysis_result(
+            repo_id,
+            "file1.rs",
+            "type2",
+            "result3",
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+
+        let results = db.get_repository_results(repo_id, "type1").await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.analysis_type == "type1"));
+    }
+
+    #[tokio::test]
+    async fn test_get_all_repository_results() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        db.save_analysis_result(
+            repo_id,
+            "file1.rs",
+            "type1",
+            "result1",
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+        db.save_analysis_result(
+            repo_id,
+            "file2.rs",
+            "type2",
+            "result2",
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+
+        let results = db.get_all_repository_results(repo_id).await.unwrap();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_file_hash() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        db.save_analysis_result(
+            repo_id,
+            "test.rs",
+            "type1",
+            "result",
+            None,
+            Some("hash1"),
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+        db.save_analysis_result(
+            repo_id,
+            "test.rs",
+            "type1",
+            "result2",
+            None,
+            Some("hash2"),
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+
+        let hash = db
+            .get_latest_file_hash(repo_id, "test.rs", "type1")
+            .await
+            .unwrap();
+        assert_eq!(hash, Some("hash2".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_file_hash_no_results() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let hash = db
+            .get_latest_file_hash(repo_id, "nonexistent.rs", "type1")
+            .await
+            .unwrap();
+        assert!(hash.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_daemon_state() {
+        let (db, _temp_dir) = create_test_db().await;
+
+        db.update_daemon_status("processing", Some("analyzing files"))
+            .await
+            .unwrap();
+
+        let state = db.get_daemon_status().await.unwrap();
+        assert_eq!(state.status, "processing");
+        assert_eq!(state.current_task, Some("analyzing files".to_string()));
+
+        db.update_daemon_status("idle", None).await.unwrap();
+        let state = db.get_daemon_status().await.unwrap();
+        assert_eq!(state.status, "idle");
+        assert!(state.current_task.is_none());
+        assert!(state.last_error.is_none());
+
+        db.record_daemon_error("database is locked").await.unwrap();
+        let state = db.get_daemon_status().await.unwrap();
+        assert_eq!(state.status, "error");
+        assert_eq!(state.last_error.as_deref(), Some("database is locked"));
+        assert!(state.last_error_at.is_some());
+
+        // The last error is kept once the daemon recovers
+        db.update_daemon_status("processing", None).await.unwrap();
+        let state = db.get_daemon_status().await.unwrap();
+        assert_eq!(state.status, "processing");
+        assert_eq!(state.last_error.as_deref(), Some("database is locked"));
+    }
+
+    #[tokio::test]
+    async fn test_save_mutation_result() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let replacements = vec![
+            MutationReplacement {
+                line_number: 10,
+                find: "x > 0".to_string(),
+                replace: "x >= 0".to_string(),
+                original_line: "    if x > 0 {".to_string(),
+            },
+            MutationReplacement {
+                line_number: 1,
+                find: "use a;".to_string(),
+                replace: "use b;".to_string(),
+                original_line: String::new(),
+            },
+        ];
+
+        let id = db
+            .save_mutation_result(
+                repo_id,
+                "src/main.rs",
+                "Changed > to >=",
+                "Test reasoning",
+                &replacements,
+                "killed",
+                Some("test_foo"),
+                Some("Test output"),
+                Some(100),
+                Some("hash123"),
+                Some("rust nightly"),
+                None,
+                Some("core"),
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+
+        assert!(id > 0);
+
+        let results = db.get_mutation_results(repo_id).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_path, "src/main.rs");
+        assert_eq!(results[0].description, "Changed > to >=");
+        assert_eq!(results[0].test_outcome, "killed");
+        assert_eq!(results[0].killing_test, Some("test_foo".to_string()));
+        assert_eq!(results[0].execution_time_ms, Some(100));
+        assert_eq!(results[0].toolchain.as_deref(), Some("rust nightly"));
+        assert_eq!(results[0].project.as_deref(), Some("core"));
+        // Replacements come back ordered by line
+        assert_eq!(
+            results[0].replacements,
+            vec![replacements[1].clone(), replacements[0].clone()]
+        );
+        let result = db.get_mutation_result(id).await.unwrap().unwrap();
+        assert_eq!(result.replacements.len(), 2);
+        assert_eq!(result.replacements[1].mutated(), "    if x >= 0 {");
+        assert_eq!(result.replacements[0].original(), "use a;");
+    }
+
+    #[tokio::test]
+    async fn test_test_suggestions() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let mut ids = Vec::new();
+        for outcome in ["survived", "survived", "killed", "survived"] {
+            let id = db
+                .save_mutation_result(
+                    repo_id,
+                    "src/lib.rs",
+                    "Changed > to >=",
+                    "Boundary",
+                    &[],
+                    outcome,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    &Attribution::default(),
+                )
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+        db.set_mutation_status(ids[3], "wont_fix").await.unwrap();
+
+        let survivors = db
+            .get_survivors_without_suggestions(repo_id, 10)
+            .await
+            .unwrap();
+        let mut survivor_ids: Vec<_> = survivors.iter().map(|m| m.id).collect();
+        survivor_ids.sort();
+        assert_eq!(survivor_ids, [ids[0], ids[1]]);
+
+        let suggestion = TestSuggestion {
+            name: "rejects_zero".to_string(),
+            scenario: "Call with 0".to_string(),
+            assertion: "assert!(check(0).is_err())".to_string(),
+        };
+        db.save_test_suggestions(ids[0], repo_id, std::slice::from_ref(&suggestion))
+            .await
+            .unwrap();
+
+        let survivors = db
+            .get_survivors_without_suggestions(repo_id, 10)
+            .await
+            .unwrap();
+        assert_eq!(survivors.len(), 1);
+        assert_eq!(survivors[0].id, ids[1]);
+
+        let suggestions = db.get_test_suggestions(repo_id).await.unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[&ids[0]][0].name, "rejects_zero");
+        assert_eq!(suggestions[&ids[0]][0].assertion, suggestion.assertion);
+    }
+
+    #[tokio::test]
+    async fn test_mutation_status_persists_across_scans() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let save = |description: &'static str| {
+            let db = db.clone();
+            async move {
+                db.save_mutation_result(
+                    repo_id,
+                    "src/lib.rs",
+                    description,
+                    "reason",
+                    &[],
+                    "survived",
+                    None,
+                    None,
+                    None,
+                    Some("hash"),
+                    None,
+                    None,
+                    None,
+                    &Attribution::default(),
+                )
+                .await
+                .unwrap()
+            }
+        };
+        let acknowledged = save("Changed > to >=").await;
+        let fixed = save("Removed call").await;
+        let summary = db.get_mutation_summary(repo_id).await.unwrap();
+        assert_eq!(summary.untriaged, 2);
+
+        assert!(db
+            .set_mutation_status(acknowledged, "acknowledged")
+            .await
+            .unwrap());
+        assert!(db.set_mutation_status(fixed, "fixed").await.unwrap());
+        assert!(!db.set_mutation_status(9999, "fixed").await.unwrap());
+        assert_eq!(db.get_mutation_summary(repo_id).await.unwrap().untriaged, 0);
+
+        // The next scan's results keep the triage state, except for supposedly
+        // fixed mutations that survive again
+        let acknowledged = save("Changed > to >=").await;
+        let fixed = save("Removed call").await;
+        let status = |id| {
+            let db = db.clone();
+            async move { db.get_mutation_result(id).await.unwrap().unwrap().status }
+        };
+        assert_eq!(status(acknowledged).await, "acknowledged");
+        assert_eq!(status(fixed).await, "new");
+
+        let (page, total) = db
+            .get_mutation_results_page(
+                repo_id,
+                &MutationFilter {
+                    status: Some("acknowledged".to_string()),
+                    ..MutationFilter::default()
+                },
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+        assert!(page.iter().all(|r| r.description == "Changed > to >="));
+    }
+
+    #[tokio::test]
+    async fn test_migrate_replacements_json() {
+        let (db, _temp_dir) = create_sqlite_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        // A database from before replacements had their own table
+        sqlx::query("DROP TABLE mutation_replacements")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        for json in [
+            r#"[{"line_number": 7, "find": "+", "replace": "-", "original_line": "a + b"},
+                {"line_number": 2, "find": "x", "replace": "y"}]"#,
+            "{}",
+            "not json",
+        ] {
+            sqlx::query(
+                "INSERT INTO mutation_results (repository_id, file_path, description, \
+                 reasoning, replacements_json, test_outcome) VALUES ($1, 'a.rs', 'd', 'r', $2, 'killed')",
+            )
+            .bind(repo_id)
+            .bind(json)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+        db.run_migrations().await.unwrap();
+
+        let mut results = db.get_mutation_results(repo_id).await.unwrap();
+        results.sort_by_key(|r| r.id);
+        assert_eq!(
+            results[0].replacements,
+            vec![
+                MutationReplacement {
+                    line_number: 2,
+                    find: "x".to_string(),
+                    replace: "y".to_string(),
+                    original_line: String::new(),
+                },
+                MutationReplacement {
+                    line_number: 7,
+                    find: "+".to_string(),
+                    replace: "-".to_string(),
+                    original_line: "a + b".to_string(),
+                },
+            ]
+        );
+        assert!(results[1].replacements.is_empty());
+        assert!(results[2].replacements.is_empty());
+
+        // Migrating again doesn't duplicate them
+        db.run_migrations().await.unwrap();
+        let result = db
+            .get_mutation_result(results[0].id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.replacements.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_mutation_summary() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let replacements: Vec<MutationReplacement> = Vec::new();
+
+        db.save_mutation_result(
+            repo_id,
+            "f1.rs",
+            "desc1",
+            "reason",
+            &replacements,
+            "killed",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+        db.save_mutation_result(
+            repo_id,
+            "f2.rs",
+            "desc2",
+            "reason",
+            &replacements,
+            "killed",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+        db.save_mutation_result(
+            repo_id,
+            "f3.rs",
+            "desc3",
+            "reason",
+            &replacements,
+            "survived",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+        db.save_mutation_result(
+            repo_id,
+            "f4.rs",
+            "desc4",
+            "reason",
+            &replacements,
+            "timeout",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+        db.save_mutation_result(
+            repo_id,
+            "f5.rs",
+            "desc5",
+            "reason",
+            &replacements,
+            "compile_error",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+
+        let summary = db.get_mutation_summary(repo_id).await.unwrap();
+        assert_eq!(summary.total, 5);
+        assert_eq!(summary.killed, 2);
+        assert_eq!(summary.survived, 1);
+        assert_eq!(summary.timeout, 1);
+        assert_eq!(summary.compile_error, 1);
+    }
+
+    #[tokio::test]
+    async fn test_mutation_summary_counts_covered_survivors() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let summary = db.get_mutation_summary(repo_id).await.unwrap();
+        assert_eq!(summary.covered_survived, None);
+
+        for (outcome, covered) in [
+            ("survived", Some(true)),
+            ("survived", None),
+            ("killed", Some(true)),
+        ] {
+            db.save_mutation_result(
+                repo_id,
+                "lib.rs",
+                "desc",
+                "reason",
+                &[],
+                outcome,
+                None,
+                None,
+                None,
+                None,
+                None,
+                covered,
+                None,
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let summary = db.get_mutation_summary(repo_id).await.unwrap();
+        assert_eq!(summary.survived, 2);
+        assert_eq!(summary.covered_survived, Some(1));
+        let results = db.get_mutation_results(repo_id).await.unwrap();
+        assert!(results.iter().any(|r| r.covered == Some(true)));
+    }
+
+    #[tokio::test]
+    async fn test_get_mutation_results_page() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        for (file, outcome, line) in [
+            ("/repo/src/a.rs", "survived", 5),
+            ("/repo/src/a.rs", "killed", 20),
+            ("/repo/src/b.rs", "survived", 12),
+            ("/repo/src/c.rs", "survived", 30),
+        ] {
+            let replacements = [MutationReplacement {
+                line_number: line,
+                find: "<".to_string(),
+                replace: "<=".to_string(),
+                original_line: String::new(),
+            }];
+            db.save_mutation_result(
+                repo_id,
+                file,
+                "desc",
+                "reason",
+                &replacements,
+                outcome,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let survived = MutationFilter {
+            outcome: Some("survived".to_string()),
+            ..MutationFilter::default()
+        };
+        let (page, total) = db
+            .get_mutation_results_page(repo_id, &survived, 2, 0)
+            .await
+            .unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].file_path, "/repo/src/c.rs");
+
+        let (page, _) = db
+            .get_mutation_results_page(repo_id, &survived, 2, 2)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].file_path, "/repo/src/a.rs");
+
+        let (page, total) = db
+            .get_mutation_results_page(
+                repo_id,
+                &MutationFilter {
+                    file: Some("src/a.rs".to_string()),
+                    ..MutationFilter::default()
+                },
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+        assert!(page.iter().all(|r| r.file_path == "/repo/src/a.rs"));
+
+        let (page, total) = db
+            .get_mutation_results_page(
+                repo_id,
+                &MutationFilter {
+                    lines: Some((10, 20)),
+                    ..MutationFilter::default()
+                },
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+        let lines: Vec<i64> = page.iter().map(|r| r.replacements[0].line_number).collect();
+        assert_eq!(lines, vec![12, 20]);
+    }
+
+    #[tokio::test]
+    async fn test_notification_log() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        assert_eq!(db.count_recent_notifications().await.unwrap(), 0);
+        db.record_notification(repo_id, "3 survived mutants in Test")
+            .await
+            .unwrap();
+        db.record_notification(repo_id, "1 survived mutant in Test")
+            .await
+            .unwrap();
+        assert_eq!(db.count_recent_notifications().await.unwrap(), 2);
+
+        db.delete_repository(repo_id).await.unwrap();
+        assert_eq!(db.count_recent_notifications().await.unwrap(), 0);
     }
 
     #[tokio::test]
-    async fn test_get_repositories() {
+    async fn test_file_snapshots_and_changelog() {
         let (db, _temp_dir) = create_test_db().await;
-        let repo_dir1 = create_test_repo();
-        let repo_dir2 = create_test_repo();
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
 
-        db.add_repository(&repo_dir1.path().to_string_lossy(), "Repo 1")
+        assert!(db
+            .get_file_snapshot(repo_id, "a.rs")
+            .await
+            .unwrap()
+            .is_none());
+        db.save_file_snapshot(repo_id, "a.rs", "h1", "fn a() {}")
             .await
             .unwrap();
-        db.add_repository(&repo_dir2.path().to_string_lossy(), "Repo 2")
+        db.save_file_snapshot(repo_id, "a.rs", "h2", "fn a() { b() }")
             .await
             .unwrap();
+        assert_eq!(
+            db.get_file_snapshot(repo_id, "a.rs").await.unwrap(),
+            Some(("h2".to_string(), "fn a() { b() }".to_string()))
+        );
 
-        let repos = db.get_repositories().await.unwrap();
-        assert_eq!(repos.len(), 2);
-        assert_eq!(repos[0].name, "Repo 1");
-        assert_eq!(repos[1].name, "Repo 2");
+        db.save_changelog_entry(repo_id, "a.rs", "- Calls b", Some("h2"))
+            .await
+            .unwrap();
+        db.save_changelog_entry(repo_id, "b.rs", "- New file", None)
+            .await
+            .unwrap();
+        let entries = db.get_changelog(repo_id, Some("a.rs"), 10).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].entry, "- Calls b");
+        assert_eq!(db.get_changelog(repo_id, None, 10).await.unwrap().len(), 2);
+
+        db.delete_repository(repo_id).await.unwrap();
+        assert!(db
+            .get_file_snapshot(repo_id, "a.rs")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .get_changelog(repo_id, None, 10)
+            .await
+            .unwrap()
+            .is_empty());
     }
 
     #[tokio::test]
-    async fn test_get_repository_not_found() {
+    async fn test_file_statuses() {
         let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
 
-        let repo = db.get_repository(999).await.unwrap();
-        assert!(repo.is_none(), "Non-existent repository should return None");
+        db.set_file_status(repo_id, "b.js", Some("skipped_minified"))
+            .await
+            .unwrap();
+        db.set_file_status(repo_id, "a.rs", Some("skipped_binary"))
+            .await
+            .unwrap();
+        db.set_file_status(repo_id, "a.rs", Some("lossy_utf8"))
+            .await
+            .unwrap();
+        let statuses = db.get_file_statuses(repo_id).await.unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].file_path, "a.rs");
+        assert_eq!(statuses[0].status, "lossy_utf8");
+
+        db.set_file_status(repo_id, "b.js", None).await.unwrap();
+        assert_eq!(db.get_file_statuses(repo_id).await.unwrap().len(), 1);
     }
 
     #[tokio::test]
-    async fn test_save_analysis_result() {
+    async fn test_set_issue_urls() {
         let (db, _temp_dir) = create_test_db().await;
         let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
 
-        let id = db
+        let analysis_id = db
             .save_analysis_result(
                 repo_id,
-                "src/main.rs",
+                "/repo/a.rs",
                 "code_understanding",
-                "Test analysis result",
-                Some("info"),
-                Some("hash123"),
+                "Bug",
+                None,
+                None,
+                &Attribution::default(),
             )
             .await
             .unwrap();
+        let mutation_id = db
+            .save_mutation_result(
+                repo_id,
+                "/repo/a.rs",
+                "desc",
+                "reason",
+                &[],
+                "survived",
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_analysis_result(analysis_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .issue_url,
+            None
+        );
 
-        assert!(id > 0);
-
-        let results = db
-            .get_repository_results(repo_id, "code_understanding")
+        db.set_analysis_issue_url(analysis_id, "https://example.com/issues/1")
             .await
             .unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].file_path, "src/main.rs");
-        assert_eq!(results[0].analysis_type, "code_understanding");
-        assert_eq!(results[0].result, "Test analysis result");
-        assert_eq!(results[0].severity, Some("info".to_string()));
-        assert_eq!(results[0].content_hash, Some("hash123".to_string()));
+        db.set_mutation_issue_url(mutation_id, "https://example.com/issues/2")
+            .await
+            .unwrap();
+
+        let analysis = db.get_analysis_result(analysis_id).await.unwrap().unwrap();
+        assert_eq!(
+            analysis.issue_url.as_deref(),
+            Some("https://example.com/issues/1")
+        );
+        let mutation = db.get_mutation_result(mutation_id).await.unwrap().unwrap();
+        assert_eq!(
+            mutation.issue_url.as_deref(),
+            Some("https://example.com/issues/2")
+        );
+        assert!(db
+            .get_mutation_result(mutation_id + 1)
+            .await
+            .unwrap()
+            .is_none());
     }
 
     #[tokio::test]
-    async fn test_get_recent_results() {
+    async fn test_replace_issues() {
         let (db, _temp_dir) = create_test_db().await;
         let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
 
-        db.save_analysis_result(repo_id, "file1.rs", "type1", "result1", None, None)
-            .await
-            .unwrap();
-        db.save_analysis_result(repo_id, "file2.rs", "type2", "result2", None, None)
+        let issue = |number: i64, title: &str, labels: &[&str]| TrackerIssue {
+            number,
+            title: title.to_string(),
+            url: format!("https://github.com/acme/app/issues/{}", number),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+        };
+        db.replace_issues(
+            repo_id,
+            &[
+                issue(7, "Slow startup", &[]),
+                issue(3, "Crash", &["bug", "p1"]),
+            ],
+        )
+        .await
+        .unwrap();
+        let issues = db.get_issues(repo_id).await.unwrap();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].number, 3);
+        assert_eq!(issues[0].labels, "bug,p1");
+
+        // Closed issues disappear on the next import
+        db.replace_issues(repo_id, &[issue(7, "Slow startup", &[])])
             .await
             .unwrap();
+        assert_eq!(db.get_issues(repo_id).await.unwrap().len(), 1);
 
-        let results = db.get_recent_results(10).await.unwrap();
-        assert_eq!(results.len(), 2);
+        db.delete_repository(repo_id).await.unwrap();
+        assert!(db.get_issues(repo_id).await.unwrap().is_empty());
     }
 
     #[tokio::test]
-    async fn test_get_repository_results() {
+    async fn test_findings_of_latest_results() {
         let (db, _temp_dir) = create_test_db().await;
         let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
 
-        db.save_analysis_result(repo_id, "file1.rs", "type1", "result1", None, None)
-            .await
-            .unwrap();
-        db.save_analysis_result(repo_id, "file2.rs", "type1", "result2", None, None)
+        let finding = |title: &str, severity: &str| StructuredFinding {
+            title: title.to_string(),
+            severity: severity.to_string(),
+            category: "bug".to_string(),
+            line_start: Some(3),
+            line_end: Some(4),
+            recommendation: "Fix it".to_string(),
+        };
+        let save = |findings: Vec<StructuredFinding>| {
+            let db = &db;
+            async move {
+                let id = db
+                    .save_analysis_result(
+                        repo_id,
+                        "src/lib.rs",
+                        "code_understanding",
+                        "Analysis",
+                        Some("warning"),
+                        None,
+                        &Attribution::default(),
+                    )
+                    .await
+                    .unwrap();
+                db.save_findings(id, repo_id, "src/lib.rs", "", &findings)
+                    .await
+                    .unwrap();
+            }
+        };
+
+        save(vec![finding("Old", "error")]).await;
+        sqlx::query("UPDATE analysis_results SET created_at = $1")
+            .bind(timestamp(chrono::Utc::now() - chrono::Duration::hours(1)))
+            .execute(&db.pool)
             .await
             .unwrap();
-        db.save_analysis_result(repo_id, "file1.rs", "type2", "result3", None, None)
+        save(vec![
+            finding("Minor", "info"),
+            finding("Overflow", "warning"),
+        ])
+        .await;
+
+        let findings = db.get_findings(repo_id).await.unwrap();
+        let titles: Vec<_> = findings.iter().map(|f| f.title.as_str()).collect();
+        assert_eq!(titles, ["Overflow", "Minor"]);
+        assert_eq!(findings[0].line_start, Some(3));
+
+        // Pruning the superseded result removes its findings
+        db.prune_history(1).await.unwrap();
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM findings")
+            .fetch_one(&db.pool)
             .await
             .unwrap();
+        assert_eq!(remaining, 2);
 
-        let results = db.get_repository_results(repo_id, "type1").await.unwrap();
-        assert_eq!(results.len(), 2);
-        assert!(results.iter().all(|r| r.analysis_type == "type1"));
+        db.delete_repository(repo_id).await.unwrap();
+        assert!(db.get_findings(repo_id).await.unwrap().is_empty());
     }
 
     #[tokio::test]
-    async fn test_get_all_repository_results() {
+    async fn test_new_critical_findings() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let finding = |title: &str, severity: &str| StructuredFinding {
+            title: title.to_string(),
+            severity: severity.to_string(),
+            category: "security".to_string(),
+            line_start: Some(7),
+            line_end: None,
+            recommendation: "Fix it".to_string(),
+        };
+        let save = |file: &'static str, findings: Vec<StructuredFinding>| {
+            let db = &db;
+            async move {
+                let id = db
+                    .save_analysis_result(
+                        repo_id,
+                        file,
+                        "security",
+                        "Analysis",
+                        Some("error"),
+                        None,
+                        &Attribution::default(),
+                    )
+                    .await
+                    .unwrap();
+                db.save_findings(id, repo_id, file, "", &findings)
+                    .await
+                    .unwrap();
+            }
+        };
+
+        assert_eq!(db.latest_analysis_result_id().await.unwrap(), 0);
+        save("src/a.rs", vec![finding("Injection", "error")]).await;
+        let since = db.latest_analysis_result_id().await.unwrap();
+        assert!(since > 0);
+
+        // Reported again, newly found, not an error, and found in another file
+        save(
+            "src/a.rs",
+            vec![
+                finding("Injection", "error"),
+                finding("Path traversal", "error"),
+                finding("Weak hash", "warning"),
+            ],
+        )
+        .await;
+        save("src/b.rs", vec![finding("Injection", "error")]).await;
+
+        let findings = db.get_new_critical_findings(repo_id, since).await.unwrap();
+        let found: Vec<_> = findings
+            .iter()
+            .map(|f| (f.file_path.as_str(), f.title.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [("src/a.rs", "Path traversal"), ("src/b.rs", "Injection")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_findings_across_repositories() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (app_id, _app_dir) = add_test_repo(&db, "App").await;
+        let (lib_id, _lib_dir) = add_test_repo(&db, "Lib").await;
+        let (old_id, _old_dir) = add_test_repo(&db, "Old").await;
+
+        let finding = |title: &str, severity: &str| StructuredFinding {
+            title: title.to_string(),
+            severity: severity.to_string(),
+            category: "bug".to_string(),
+            line_start: None,
+            line_end: None,
+            recommendation: "Fix it".to_string(),
+        };
+        let save = |repo_id: i64, findings: Vec<StructuredFinding>| {
+            let db = &db;
+            async move {
+                let id = db
+                    .save_analysis_result(
+                        repo_id,
+                        "src/lib.rs",
+                        "code_understanding",
+                        "Analysis",
+                        None,
+                        None,
+                        &Attribution::default(),
+                    )
+                    .await
+                    .unwrap();
+                db.save_findings(id, repo_id, "src/lib.rs", "", &findings)
+                    .await
+                    .unwrap();
+            }
+        };
+
+        save(
+            app_id,
+            vec![finding("Leak", "warning"), finding("Style", "info")],
+        )
+        .await;
+        save(lib_id, vec![finding("Overflow", "error")]).await;
+        save(old_id, vec![finding("Archived", "error")]).await;
+        db.set_repository_archived(old_id, true).await.unwrap();
+        sqlx::query(
+            "UPDATE findings SET first_seen = '2025-01-01T00:00:00.000Z' WHERE title = 'Overflow'",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        let titles = |findings: Vec<AnalysisFinding>| -> Vec<String> {
+            findings.into_iter().map(|f| f.title).collect()
+        };
+        assert_eq!(
+            titles(db.get_open_findings(None, false, 100).await.unwrap()),
+            ["Overflow", "Leak"]
+        );
+        assert_eq!(
+            titles(db.get_open_findings(None, true, 100).await.unwrap()),
+            ["Leak", "Overflow"]
+        );
+        assert_eq!(
+            titles(
+                db.get_open_findings(Some("error"), false, 100)
+                    .await
+                    .unwrap()
+            ),
+            ["Overflow"]
+        );
+        assert_eq!(db.get_open_findings(None, false, 1).await.unwrap().len(), 1);
+
+        // Resolved findings drop out
+        save(lib_id, vec![]).await;
+        assert_eq!(
+            titles(db.get_open_findings(None, false, 100).await.unwrap()),
+            ["Leak"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stale_results() {
         let (db, _temp_dir) = create_test_db().await;
         let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
 
-        db.save_analysis_result(repo_id, "file1.rs", "type1", "result1", None, None)
+        for file in ["/repo/kept.rs", "/repo/deleted.rs"] {
+            let id = db
+                .save_analysis_result(
+                    repo_id,
+                    file,
+                    "code_understanding",
+                    "Analysis",
+                    Some("error"),
+                    None,
+                    &Attribution::default(),
+                )
+                .await
+                .unwrap();
+            let finding = StructuredFinding {
+                title: format!("Bug in {}", file),
+                severity: "error".to_string(),
+                category: "bug".to_string(),
+                line_start: None,
+                line_end: None,
+                recommendation: "Fix it".to_string(),
+            };
+            db.save_findings(id, repo_id, file, "", &[finding])
+                .await
+                .unwrap();
+        }
+
+        let deleted = vec!["/repo/deleted.rs".to_string()];
+        assert_eq!(
+            db.set_results_stale(repo_id, &deleted, true).await.unwrap(),
+            1
+        );
+        assert_eq!(
+            db.get_result_files(repo_id).await.unwrap(),
+            [
+                ("/repo/deleted.rs".to_string(), true),
+                ("/repo/kept.rs".to_string(), false)
+            ]
+        );
+        assert_eq!(db.count_stale_files(repo_id).await.unwrap(), 1);
+
+        // Stale results and their findings are hidden
+        let files = |results: Vec<AnalysisResult>| -> Vec<String> {
+            results.into_iter().map(|r| r.file_path).collect()
+        };
+        assert_eq!(
+            files(db.get_all_repository_results(repo_id).await.unwrap()),
+            ["/repo/kept.rs"]
+        );
+        assert_eq!(
+            files(db.get_recent_results(10, None).await.unwrap()),
+            ["/repo/kept.rs"]
+        );
+        assert_eq!(db.get_findings(repo_id).await.unwrap().len(), 1);
+        assert_eq!(
+            db.get_open_findings(None, false, 10).await.unwrap().len(),
+            1
+        );
+
+        // Purging deletes only the stale results
+        assert_eq!(db.purge_stale_results(repo_id).await.unwrap(), 1);
+        assert_eq!(db.count_stale_files(repo_id).await.unwrap(), 0);
+        assert_eq!(db.get_result_files(repo_id).await.unwrap().len(), 1);
+        let findings: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM findings")
+            .fetch_one(&db.pool)
             .await
             .unwrap();
-        db.save_analysis_result(repo_id, "file2.rs", "type2", "result2", None, None)
+        assert_eq!(findings, 1);
+    }
+
+    #[tokio::test]
+    async fn test_findings_deduplicated_across_scans() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let finding = |title: &str, line: u32| StructuredFinding {
+            title: title.to_string(),
+            severity: "warning".to_string(),
+            category: "bug".to_string(),
+            line_start: Some(line),
+            line_end: None,
+            recommendation: "Fix it".to_string(),
+        };
+        let save = |content: &'static str, findings: Vec<StructuredFinding>| {
+            let db = &db;
+            async move {
+                let id = db
+                    .save_analysis_result(
+                        repo_id,
+                        "src/lib.rs",
+                        "code_understanding",
+                        "Analysis",
+                        Some("warning"),
+                        None,
+                        &Attribution::default(),
+                    )
+                    .await
+                    .unwrap();
+                db.save_findings(id, repo_id, "src/lib.rs", content, &findings)
+                    .await
+                    .unwrap();
+                id
+            }
+        };
+
+        let first = save(
+            "let a = v[0];\nlet b = w[1];\n",
+            vec![finding("Unchecked index", 1), finding("Unchecked index", 2)],
+        )
+        .await;
+        // The first line moved down and the second one was fixed
+        let second = save(
+            "// indexing\nlet a = v[0];\nlet b = w.get(1);\n",
+            vec![finding("Unchecked index", 2)],
+        )
+        .await;
+
+        let findings = db.get_findings(repo_id).await.unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].analysis_result_id, second);
+        assert_eq!(findings[0].line_start, Some(2));
+        assert_eq!(findings[0].occurrences, 2);
+        assert!(findings[0].first_seen.is_some());
+        assert!(findings[0].resolved_at.is_none());
+
+        let resolved = db.get_resolved_findings(repo_id, 10).await.unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].analysis_result_id, first);
+        assert!(resolved[0].resolved_at.is_some());
+
+        // The fixed line comes back: its finding is reopened rather than added
+        save(
+            "let a = v[0];\nlet b = w[1];\n",
+            vec![finding("Unchecked index", 2)],
+        )
+        .await;
+        let findings = db.get_findings(repo_id).await.unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].first_seen, resolved[0].first_seen);
+        assert_eq!(findings[0].occurrences, 2);
+        let resolved = db.get_resolved_findings(repo_id, 10).await.unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].occurrences, 2);
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM findings")
+            .fetch_one(&db.pool)
             .await
             .unwrap();
-
-        let results = db.get_all_repository_results(repo_id).await.unwrap();
-        assert_eq!(results.len(), 2);
+        assert_eq!(total, 2);
     }
 
     #[tokio::test]
-    async fn test_get_latest_file_hash() {
+    async fn test_chat_messages() {
         let (db, _temp_dir) = create_test_db().await;
         let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+        let (other_id, _other_dir) = add_test_repo(&db, "Other").await;
 
-        db.save_analysis_result(repo_id, "test.rs", "type1", "result", None, Some("hash1"))
+        db.save_chat_message(repo_id, "user", "Where is auth handled?", &[])
             .await
             .unwrap();
-        db.save_analysis_result(repo_id, "test.rs", "type1", "result2", None, Some("hash2"))
+        let answer = db
+            .save_chat_message(
+                repo_id,
+                "assistant",
+                "In src/auth.rs",
+                &["src/auth.rs".to_string()],
+            )
             .await
             .unwrap();
-
-        let hash = db
-            .get_latest_file_hash(repo_id, "test.rs", "type1")
+        assert_eq!(answer.sources, ["src/auth.rs"]);
+        db.save_chat_message(other_id, "user", "Hello", &[])
             .await
             .unwrap();
-        assert_eq!(hash, Some("hash2".to_string()));
+
+        let messages = db.get_chat_messages(repo_id, 10).await.unwrap();
+        assert_eq!(
+            messages.iter().map(|m| m.role.as_str()).collect::<Vec<_>>(),
+            ["user", "assistant"]
+        );
+        assert!(messages[0].sources.is_empty());
+        assert_eq!(messages[1].sources, ["src/auth.rs"]);
+        // The newest messages, still oldest first
+        let last = db.get_chat_messages(repo_id, 1).await.unwrap();
+        assert_eq!(last.len(), 1);
+        assert_eq!(last[0].content, "In src/auth.rs");
+
+        assert_eq!(db.clear_chat_messages(repo_id).await.unwrap(), 2);
+        assert!(db.get_chat_messages(repo_id, 10).await.unwrap().is_empty());
+        assert_eq!(db.get_chat_messages(other_id, 10).await.unwrap().len(), 1);
     }
 
     #[tokio::test]
-    async fn test_get_latest_file_hash_no_results() {
+    async fn test_architecture_model() {
+        use crate::analyzer::architecture::Component;
+
         let (db, _temp_dir) = create_test_db().await;
         let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
-
-        let hash = db
-            .get_latest_file_hash(repo_id, "nonexistent.rs", "type1")
+        assert!(db.get_architecture_model(repo_id).await.unwrap().is_none());
+
+        let model = |summary: &str| ArchitectureModel {
+            summary: summary.to_string(),
+            components: vec![Component {
+                name: "web".to_string(),
+                responsibility: "Serves the dashboard".to_string(),
+                paths: vec!["src/web".to_string()],
+                depends_on: Vec::new(),
+            }],
+        };
+        let attribution = Attribution {
+            endpoint: Some("local".to_string()),
+            model: Some("llama".to_string()),
+            duration_ms: None,
+        };
+        db.save_architecture_model(repo_id, &model("First"), &attribution)
             .await
             .unwrap();
-        assert!(hash.is_none());
+        db.save_architecture_model(repo_id, &model("Second"), &attribution)
+            .await
+            .unwrap();
+
+        // Only the latest model is kept
+        let stored = db.get_architecture_model(repo_id).await.unwrap().unwrap();
+        assert_eq!(stored.model, model("Second"));
+        assert_eq!(stored.endpoint.as_deref(), Some("local"));
+        assert_eq!(stored.llm_model.as_deref(), Some("llama"));
+
+        db.delete_repository(repo_id).await.unwrap();
+        assert!(db.get_architecture_model(repo_id).await.unwrap().is_none());
     }
 
     #[tokio::test]
-    async fn test_daemon_state() {
+    async fn test_onboarding() {
         let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+        assert!(db.get_onboarding(repo_id).await.unwrap().is_none());
+        assert!(!db.confirm_onboarding(repo_id).await.unwrap());
+
+        db.start_onboarding(repo_id).await.unwrap();
+        let onboarding = db.get_onboarding(repo_id).await.unwrap().unwrap();
+        assert_eq!(onboarding.status, "scanning");
+        assert_eq!(onboarding.quick_scan, QuickScan::default());
+        assert_eq!(db.get_unconfirmed_repositories().await.unwrap(), [repo_id]);
+
+        let scan = QuickScan {
+            file_count: 3,
+            readme_summary: Some("A tool".to_string()),
+            ..QuickScan::default()
+        };
+        db.finish_onboarding(repo_id, &scan).await.unwrap();
+        let onboarding = db.get_onboarding(repo_id).await.unwrap().unwrap();
+        assert_eq!(onboarding.status, "ready");
+        assert_eq!(onboarding.quick_scan, scan);
+        assert!(onboarding.awaits_confirmation());
+
+        assert!(db.confirm_onboarding(repo_id).await.unwrap());
+        assert!(!db.confirm_onboarding(repo_id).await.unwrap());
+        assert!(db.get_unconfirmed_repositories().await.unwrap().is_empty());
+
+        // A quick scan finishing after confirmation doesn't hold the repository back
+        db.fail_onboarding(repo_id, "gone").await.unwrap();
+        let onboarding = db.get_onboarding(repo_id).await.unwrap().unwrap();
+        assert_eq!(onboarding.status, "confirmed");
+        assert_eq!(onboarding.error.as_deref(), Some("gone"));
 
-        db.update_daemon_status("processing", Some("analyzing files"))
-            .await
-            .unwrap();
+        db.delete_repository(repo_id).await.unwrap();
+        assert!(db.get_onboarding(repo_id).await.unwrap().is_none());
+    }
 
-        let state = db.get_daemon_status().await.unwrap();
-        assert_eq!(state.status, "processing");
-        assert_eq!(state.current_task, Some("analyzing files".to_string()));
+    #[tokio::test]
+    async fn test_scores() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+        let (other_id, _other_dir) = add_test_repo(&db, "Other").await;
+
+        let finding = |severity: &str, category: &str| StructuredFinding {
+            title: format!("{} {}", severity, category),
+            severity: severity.to_string(),
+            category: category.to_string(),
+            line_start: None,
+            line_end: None,
+            recommendation: String::new(),
+        };
+        let save =
+            |file: &'static str, analysis_type: &'static str, findings: Vec<StructuredFinding>| {
+                let db = &db;
+                async move {
+                    let id = db
+                        .save_analysis_result(
+                            repo_id,
+                            file,
+                            analysis_type,
+                            "Analysis",
+                            None,
+                            None,
+                            &Attribution::default(),
+                        )
+                        .await
+                        .unwrap();
+                    db.save_findings(id, repo_id, file, "", &findings)
+                        .await
+                        .unwrap();
+                }
+            };
+
+        // Nothing to score yet
+        let inputs = db.get_score_inputs(repo_id).await.unwrap();
+        assert_eq!(inputs, ScoreInputs::default());
+        assert!(!db.record_score(repo_id, None, &inputs).await.unwrap());
+
+        // Superseded results and other analysis types don't count
+        save(
+            "src/a.rs",
+            "code_understanding",
+            vec![finding("error", "bug")],
+        )
+        .await;
+        save(
+            "src/a.rs",
+            "code_understanding",
+            vec![
+                finding("warning", "documentation"),
+                finding("info", "style"),
+            ],
+        )
+        .await;
+        save("src/b.rs", "code_understanding", vec![]).await;
+        save("src/b.rs", "security", vec![finding("error", "security")]).await;
+        sqlx::query(
+            "INSERT INTO mutation_results (repository_id, file_path, description, reasoning, \
+             replacements_json, test_outcome) VALUES ($1, 'a.rs', 'd', 'r', '[]', 'killed'), \
+             ($1, 'a.rs', 'd', 'r', '[]', 'survived'), ($1, 'a.rs', 'd', 'r', '[]', 'timeout')",
+        )
+        .bind(repo_id)
+        .execute(&db.pool)
+        .await
+        .unwrap();
 
-        db.update_daemon_status("idle", None).await.unwrap();
-        let state = db.get_daemon_status().await.unwrap();
-        assert_eq!(state.status, "idle");
-        assert!(state.current_task.is_none());
+        let inputs = db.get_score_inputs(repo_id).await.unwrap();
+        assert_eq!(
+            inputs,
+            ScoreInputs {
+                files: 2,
+                undocumented_files: 1,
+                errors: 0,
+                warnings: 1,
+                infos: 1,
+                killed: 1,
+                survived: 1,
+            }
+        );
+        assert_eq!(inputs.kill_rate(), Some(0.5));
+        assert_eq!(inputs.finding_score(), Some(1.0 / (1.0 + 2.5 / 2.0)));
+        assert_eq!(inputs.doc_coverage(), Some(0.5));
+        // (0.4 * 0.5 + 0.4 * 0.444 + 0.2 * 0.5) / 1.0
+        assert_eq!(inputs.health(), Some(47.8));
+
+        let run = db.start_scan_run("scheduled").await.unwrap();
+        assert!(db.record_score(repo_id, Some(run), &inputs).await.unwrap());
+        // Unchanged
+        assert!(!db.record_score(repo_id, Some(run), &inputs).await.unwrap());
+
+        // Without mutants the other components carry the score
+        let untested = ScoreInputs {
+            files: 4,
+            ..ScoreInputs::default()
+        };
+        assert_eq!(untested.health(), Some(100.0));
+        assert!(db.record_score(repo_id, None, &untested).await.unwrap());
+        assert!(db.record_score(other_id, None, &untested).await.unwrap());
+
+        let scores = db.get_recent_scores(30).await.unwrap();
+        let history: Vec<_> = scores[&repo_id].iter().map(|s| s.score).collect();
+        assert_eq!(history, [47.8, 100.0]);
+        assert_eq!(scores[&repo_id][0].scan_run_id, Some(run));
+        assert_eq!(scores[&other_id].len(), 1);
+
+        let latest = db.get_recent_scores(1).await.unwrap();
+        assert_eq!(latest[&repo_id].len(), 1);
+        assert_eq!(latest[&repo_id][0].score, 100.0);
+
+        db.delete_repository(repo_id).await.unwrap();
+        assert!(!db
+            .get_recent_scores(30)
+            .await
+            .unwrap()
+            .contains_key(&repo_id));
     }
 
     #[tokio::test]
-    async fn test_save_mutation_result() {
+    async fn test_usage_aggregation() {
         let (db, _temp_dir) = create_test_db().await;
         let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
 
-        let replacements_json = serde_json::json!({
-            "line_number": 10,
-            "find": "x > 0",
-            "replace": "x >= 0"
-        })
-        .to_string();
-
-        let id = db
-            .save_mutation_result(
-                repo_id,
-                "src/main.rs",
-                "Changed > to >=",
-                "Test reasoning",
-                &replacements_json,
-                "killed",
-                Some("test_foo"),
-                Some("Test output"),
-                Some(100),
-                Some("hash123"),
-            )
+        let usage = TokenUsage {
+            requests: 1,
+            prompt_tokens: 100,
+            completion_tokens: 20,
+            retries: 1,
+            rejected: 2,
+        };
+        db.record_usage(repo_id, "local", "code_understanding", &usage)
+            .await
+            .unwrap();
+        db.record_usage(repo_id, "local", "code_understanding", &usage)
+            .await
+            .unwrap();
+        db.record_usage(repo_id, "gpu", "mutation_testing", &usage)
             .await
             .unwrap();
 
-        assert!(id > 0);
+        let totals = db.get_usage_totals(7).await.unwrap();
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].endpoint, "local");
+        assert_eq!(totals[0].repository_name.as_deref(), Some("Test"));
+        assert_eq!(totals[0].requests, 2);
+        assert_eq!(totals[0].prompt_tokens, 200);
+        assert_eq!(totals[0].completion_tokens, 40);
 
-        let results = db.get_mutation_results(repo_id).await.unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].file_path, "src/main.rs");
-        assert_eq!(results[0].description, "Changed > to >=");
-        assert_eq!(results[0].test_outcome, "killed");
-        assert_eq!(results[0].killing_test, Some("test_foo".to_string()));
-        assert_eq!(results[0].execution_time_ms, Some(100));
+        let daily = db.get_daily_usage(1).await.unwrap();
+        assert_eq!(daily.len(), 1);
+        assert_eq!(daily[0].requests, 3);
+        assert_eq!(daily[0].retries, 3);
+        assert_eq!(daily[0].rejected, 6);
+
+        db.delete_repository(repo_id).await.unwrap();
+        assert!(db.get_usage_totals(7).await.unwrap().is_empty());
     }
 
     #[tokio::test]
-    async fn test_get_mutation_summary() {
+    async fn test_prune_history_keeps_newest() {
         let (db, _temp_dir) = create_test_db().await;
         let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
 
-        let replacements_json = "{}".to_string();
-
-        db.save_mutation_result(
+        for hash in ["h1", "h2", "h3"] {
+            db.save_analysis_result(
+                repo_id,
+                "a.rs",
+                "type1",
+                hash,
+                None,
+                Some(hash),
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+            for _ in 0..2 {
+                db.save_mutation_result(
+                    repo_id,
+                    "a.rs",
+                    "desc",
+                    "why",
+                    &[],
+                    "killed",
+                    None,
+                    None,
+                    None,
+                    Some(hash),
+                    None,
+                    None,
+                    None,
+                    &Attribution::default(),
+                )
+                .await
+                .unwrap();
+            }
+        }
+        db.save_analysis_result(
             repo_id,
-            "f1.rs",
-            "desc1",
-            "reason",
-            &replacements_json,
-            "killed",
-            None,
-            None,
-            None,
+            "b.rs",
+            "type1",
+            "only",
             None,
+            Some("h1"),
+            &Attribution::default(),
         )
         .await
         .unwrap();
-        db.save_mutation_result(
+
+        let pruned = db.prune_history(2).await.unwrap();
+        assert_eq!(pruned.analysis_results, 1);
+        assert_eq!(pruned.mutation_results, 2);
+        assert_eq!(pruned.diagrams, 0);
+
+        // The latest result per file is unaffected
+        assert_eq!(
+            db.get_latest_file_hash(repo_id, "a.rs", "type1")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("h3")
+        );
+        assert!(db
+            .has_mutation_results_for_hash(repo_id, "a.rs", "h2")
+            .await
+            .unwrap());
+        assert!(!db
+            .has_mutation_results_for_hash(repo_id, "a.rs", "h1")
+            .await
+            .unwrap());
+
+        assert!(db.prune_history(0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_table_stats_and_vacuum() {
+        let (db, _temp_dir) = create_test_db().await;
+        add_test_repo(&db, "Test").await;
+
+        db.vacuum().await.unwrap();
+        let stats = db.table_stats().await.unwrap();
+        let repositories = stats.iter().find(|t| t.name == "repositories").unwrap();
+        assert_eq!(repositories.rows, 1);
+        assert!(stats
+            .iter()
+            .any(|t| t.name == "mutation_results" && t.rows == 0));
+    }
+
+    #[tokio::test]
+    async fn test_delete_results_by_model() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let attribution = |model: &str| Attribution {
+            endpoint: Some("local".to_string()),
+            model: Some(model.to_string()),
+            duration_ms: Some(10),
+        };
+        db.save_analysis_result(
             repo_id,
-            "f2.rs",
-            "desc2",
-            "reason",
-            &replacements_json,
-            "killed",
-            None,
-            None,
-            None,
+            "a.rs",
+            "type1",
+            "good",
             None,
+            Some("h1"),
+            &attribution("good-model"),
         )
         .await
         .unwrap();
-        db.save_mutation_result(
+        db.save_analysis_result(
             repo_id,
-            "f3.rs",
-            "desc3",
-            "reason",
-            &replacements_json,
-            "survived",
-            None,
-            None,
-            None,
+            "a.rs",
+            "type1",
+            "bad",
             None,
+            Some("h2"),
+            &attribution("bad-model"),
         )
         .await
         .unwrap();
         db.save_mutation_result(
             repo_id,
-            "f4.rs",
-            "desc4",
-            "reason",
-            &replacements_json,
-            "timeout",
-            None,
-            None,
+            "a.rs",
+            "desc",
+            "why",
+            &[],
+            "survived",
             None,
             None,
-        )
-        .await
-        .unwrap();
-        db.save_mutation_result(
-            repo_id,
-            "f5.rs",
-            "desc5",
-            "reason",
-            &replacements_json,
-            "compile_error",
             None,
+            Some("h2"),
             None,
             None,
             None,
+            &attribution("bad-model"),
         )
         .await
         .unwrap();
 
-        let summary = db.get_mutation_summary(repo_id).await.unwrap();
-        assert_eq!(summary.total, 5);
-        assert_eq!(summary.killed, 2);
-        assert_eq!(summary.survived, 1);
-        assert_eq!(summary.timeout, 1);
-        assert_eq!(summary.compile_error, 1);
+        let latest = db.get_recent_results(10, Some("bad-model")).await.unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].endpoint.as_deref(), Some("local"));
+
+        let purged = db.delete_results_by_model("bad-model", None).await.unwrap();
+        assert_eq!(purged.analysis_results, 1);
+        assert_eq!(purged.mutation_results, 1);
+
+        // The stale hash means the file is regenerated on the next scan
+        assert_eq!(
+            db.get_latest_file_hash(repo_id, "a.rs", "type1")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("h1")
+        );
+        assert!(db.get_mutation_results(repo_id).await.unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -949,18 +6730,22 @@ mod tests {
         let (db, _temp_dir) = create_test_db().await;
         let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
 
-        let replacements_json = "{}".to_string();
+        let replacements: Vec<MutationReplacement> = Vec::new();
         db.save_mutation_result(
             repo_id,
             "test.rs",
             "desc",
             "reason",
-            &replacements_json,
+            &replacements,
             "killed",
             None,
             None,
             None,
             Some("hash123"),
+            None,
+            None,
+            None,
+            &Attribution::default(),
         )
         .await
         .unwrap();
@@ -979,6 +6764,166 @@ mod tests {
             .unwrap());
     }
 
+    #[tokio::test]
+    async fn test_mutation_run_state() {
+        use crate::mutation::Replacement;
+
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+        let mutation = |description: &str| GeneratedMutation {
+            file_path: "/tmp/copy/src/lib.rs".to_string(),
+            replacements: vec![Replacement {
+                line_number: 2,
+                find: ">".to_string(),
+                replace: ">=".to_string(),
+            }],
+            reasoning: "Boundary".to_string(),
+            description: description.to_string(),
+        };
+        let attribution = Attribution {
+            endpoint: Some("local".to_string()),
+            model: Some("llama".to_string()),
+            duration_ms: Some(1500),
+        };
+
+        assert!(db
+            .get_mutation_run(repo_id, "src/lib.rs")
+            .await
+            .unwrap()
+            .is_none());
+        db.start_mutation_run(
+            repo_id,
+            "src/lib.rs",
+            "hash",
+            &[mutation("a"), mutation("b"), mutation("c")],
+            &attribution,
+        )
+        .await
+        .unwrap();
+        db.mark_mutation_executed(repo_id, "src/lib.rs", 2)
+            .await
+            .unwrap();
+        db.mark_mutation_executed(repo_id, "src/lib.rs", 0)
+            .await
+            .unwrap();
+
+        let run = db
+            .get_mutation_run(repo_id, "src/lib.rs")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(run.content_hash, "hash");
+        assert_eq!(run.mutations.len(), 3);
+        assert_eq!(run.mutations[1].description, "b");
+        assert_eq!(run.executed, [2, 0]);
+        assert_eq!(run.attribution(), attribution);
+
+        // A new run of the file starts over
+        db.start_mutation_run(
+            repo_id,
+            "src/lib.rs",
+            "new-hash",
+            &[mutation("d")],
+            &attribution,
+        )
+        .await
+        .unwrap();
+        let run = db
+            .get_mutation_run(repo_id, "src/lib.rs")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(run.content_hash, "new-hash");
+        assert!(run.executed.is_empty());
+
+        db.finish_mutation_run(repo_id, "src/lib.rs").await.unwrap();
+        assert!(db
+            .get_mutation_run(repo_id, "src/lib.rs")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mutation_base() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        assert_eq!(
+            db.get_mutation_base(repo_id, "src/lib.rs").await.unwrap(),
+            None
+        );
+        db.set_mutation_base(repo_id, "src/lib.rs", Some("abc123"))
+            .await
+            .unwrap();
+        db.set_mutation_base(repo_id, "src/lib.rs", Some("def456"))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_mutation_base(repo_id, "src/lib.rs").await.unwrap(),
+            Some("def456".to_string())
+        );
+
+        db.set_mutation_base(repo_id, "src/lib.rs", None)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_mutation_base(repo_id, "src/lib.rs").await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mutation_deferral_and_average_time() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        assert_eq!(
+            db.get_average_mutation_time_ms(repo_id).await.unwrap(),
+            None
+        );
+        // Rejected mutants were never executed and don't count
+        for (outcome, ms) in [("killed", 1000), ("survived", 3000), ("rejected", 0)] {
+            db.save_mutation_result(
+                repo_id,
+                "test.rs",
+                "desc",
+                "reason",
+                &[],
+                outcome,
+                None,
+                None,
+                Some(ms),
+                Some("hash"),
+                None,
+                None,
+                None,
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+        }
+        assert_eq!(
+            db.get_average_mutation_time_ms(repo_id).await.unwrap(),
+            Some(2000)
+        );
+
+        assert!(db.get_mutation_deferral(repo_id).await.unwrap().is_none());
+        db.save_mutation_deferral(repo_id, 5, 3600, 600)
+            .await
+            .unwrap();
+        db.save_mutation_deferral(repo_id, 3, 1800, 300)
+            .await
+            .unwrap();
+        let deferral = db.get_mutation_deferral(repo_id).await.unwrap().unwrap();
+        assert_eq!(deferral.pending_files, 3);
+        assert_eq!(deferral.estimated_seconds, 1800);
+        assert_eq!(deferral.remaining_window_seconds, 300);
+
+        db.clear_mutation_deferral(repo_id).await.unwrap();
+        assert!(db.get_mutation_deferral(repo_id).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_duplicate_repository_path() {
         let (db, _temp_dir) = create_test_db().await;
@@ -991,19 +6936,137 @@ mod tests {
         assert!(result.is_err(), "Duplicate path should fail");
     }
 
+    #[tokio::test]
+    async fn test_archive_repository() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test Repo").await;
+        db.save_analysis_result(
+            repo_id,
+            "file.rs",
+            "type1",
+            "result",
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+
+        let repo = db.get_repository(repo_id).await.unwrap().unwrap();
+        assert!(!repo.archived());
+        assert_eq!(repo.purge_token(), None);
+
+        assert!(db.set_repository_archived(repo_id, true).await.unwrap());
+        let archived = db.get_repository(repo_id).await.unwrap().unwrap();
+        assert!(archived.archived());
+        let token = archived.purge_token().unwrap();
+        assert_eq!(token.len(), 16);
+
+        // Archiving again keeps the time, and so the token
+        assert!(db.set_repository_archived(repo_id, true).await.unwrap());
+        let again = db.get_repository(repo_id).await.unwrap().unwrap();
+        assert_eq!(again.archived_at, archived.archived_at);
+        assert_eq!(again.purge_token(), Some(token));
+
+        // Results are kept
+        assert_eq!(
+            db.get_repository_results(repo_id, "type1")
+                .await
+                .unwrap()
+                .len(),
+            1,
+            "Archiving should keep results"
+        );
+
+        assert!(db.set_repository_archived(repo_id, false).await.unwrap());
+        let restored = db.get_repository(repo_id).await.unwrap().unwrap();
+        assert!(!restored.archived());
+        assert!(!db.set_repository_archived(9999, true).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_repository_commands() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test Repo").await;
+
+        let repo = db.get_repository(repo_id).await.unwrap().unwrap();
+        assert!(repo.commands.is_empty());
+
+        let commands = CommandOverrides {
+            test_command: Some("cargo nextest run".to_string()),
+            timeout_seconds: Some(600),
+            working_dir: Some("backend".to_string()),
+            ..CommandOverrides::default()
+        };
+        assert!(db
+            .set_repository_commands(repo_id, &commands)
+            .await
+            .unwrap());
+        let repo = db.get_repository(repo_id).await.unwrap().unwrap();
+        assert_eq!(repo.commands, commands);
+
+        assert!(!db.set_repository_commands(9999, &commands).await.unwrap());
+
+        let normalized = CommandOverrides {
+            compile_command: Some("  ".to_string()),
+            test_command: Some(" npm run test:unit ".to_string()),
+            ..CommandOverrides::default()
+        }
+        .normalize()
+        .unwrap();
+        assert_eq!(normalized.compile_command, None);
+        assert_eq!(
+            normalized.test_command.as_deref(),
+            Some("npm run test:unit")
+        );
+        for working_dir in ["../elsewhere", "/tmp"] {
+            let commands = CommandOverrides {
+                working_dir: Some(working_dir.to_string()),
+                ..CommandOverrides::default()
+            };
+            assert!(commands.normalize().is_err(), "{}", working_dir);
+        }
+        let commands = CommandOverrides {
+            timeout_seconds: Some(0),
+            ..CommandOverrides::default()
+        };
+        assert!(commands.normalize().is_err());
+    }
+
     #[tokio::test]
     async fn test_delete_repository() {
         let (db, _temp_dir) = create_test_db().await;
         let (repo_id, _repo_dir) = add_test_repo(&db, "Test Repo").await;
 
         // Add some analysis results
-        db.save_analysis_result(repo_id, "file.rs", "type1", "result", None, None)
-            .await
-            .unwrap();
+        db.save_analysis_result(
+            repo_id,
+            "file.rs",
+            "type1",
+            "result",
+            None,
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
 
         // Add some mutation results
         db.save_mutation_result(
-            repo_id, "file.rs", "desc", "reason", "{}", "killed", None, None, None, None,
+            repo_id,
+            "file.rs",
+            "desc",
+            "reason",
+            &[],
+            "killed",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            &Attribution::default(),
         )
         .await
         .unwrap();
@@ -1014,6 +7077,7 @@ mod tests {
             "system_architecture",
             "Title",
             "Desc",
+            "dot",
             "digraph { a -> b }",
             "<svg></svg>",
             None,
@@ -1068,6 +7132,7 @@ mod tests {
                 "system_architecture",
                 "System Architecture",
                 "High-level view of components",
+                "dot",
                 "digraph { web -> db }",
                 "<svg>web-db</svg>",
                 Some("hash123"),
@@ -1081,6 +7146,7 @@ mod tests {
         assert_eq!(diagrams.len(), 1);
         assert_eq!(diagrams[0].diagram_type, "system_architecture");
         assert_eq!(diagrams[0].title, "System Architecture");
+        assert_eq!(diagrams[0].format, "dot");
         assert_eq!(diagrams[0].dot_content, "digraph { web -> db }");
         assert_eq!(diagrams[0].svg_content, "<svg>web-db</svg>");
         assert_eq!(diagrams[0].content_hash, Some("hash123".to_string()));
@@ -1097,6 +7163,7 @@ mod tests {
             "system_architecture",
             "Architecture",
             "Desc",
+            "dot",
             "digraph { a -> b }",
             "<svg>a-b</svg>",
             None,
@@ -1109,6 +7176,7 @@ mod tests {
             "data_flow",
             "Data Flow",
             "Desc",
+            "dot",
             "digraph { x -> y }",
             "<svg>x-y</svg>",
             None,
@@ -1121,6 +7189,7 @@ mod tests {
             "database_schema",
             "DB Schema",
             "Desc",
+            "dot",
             "digraph { users -> posts }",
             "<svg>users-posts</svg>",
             None,
@@ -1151,6 +7220,7 @@ mod tests {
                 "system_architecture",
                 "Old Version",
                 "Desc",
+                "dot",
                 "digraph { old -> content }",
                 "<svg>old</svg>",
                 Some("hash1"),
@@ -1164,6 +7234,7 @@ mod tests {
                 "system_architecture",
                 "New Version",
                 "Desc",
+                "dot",
                 "digraph { new -> content }",
                 "<svg>new</svg>",
                 Some("hash2"),
@@ -1184,6 +7255,46 @@ mod tests {
         assert_eq!(diagrams[0].content_hash, Some("hash2".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_diagram_versions() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let mut ids = Vec::new();
+        for (diagram_type, hash) in [
+            ("system_architecture", "hash1"),
+            ("data_flow", "hash2"),
+            ("system_architecture", "hash3"),
+        ] {
+            let id = db
+                .save_diagram(
+                    repo_id,
+                    diagram_type,
+                    "Title",
+                    "Desc",
+                    "dot",
+                    "digraph { a -> b }",
+                    "<svg>a-b</svg>",
+                    Some(hash),
+                )
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+
+        let versions = db
+            .get_diagram_versions(repo_id, "system_architecture")
+            .await
+            .unwrap();
+        let version_ids: Vec<i64> = versions.iter().map(|v| v.id).collect();
+        assert_eq!(version_ids, [ids[0], ids[2]]);
+        assert_eq!(versions[1].content_hash.as_deref(), Some("hash3"));
+
+        let diagram = db.get_diagram(ids[1]).await.unwrap().unwrap();
+        assert_eq!(diagram.diagram_type, "data_flow");
+        assert!(db.get_diagram(ids[2] + 1).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_get_latest_diagram_hash() {
         let (db, _temp_dir) = create_test_db().await;
@@ -1202,6 +7313,7 @@ mod tests {
             "system_architecture",
             "Title",
             "Desc",
+            "dot",
             "digraph { a -> b }",
             "<svg></svg>",
             Some("hash123"),
@@ -1223,6 +7335,75 @@ mod tests {
         assert!(hash.is_none());
     }
 
+    #[tokio::test]
+    async fn test_diagram_requests() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        assert!(db
+            .get_latest_diagram(repo_id, "data_flow")
+            .await
+            .unwrap()
+            .is_none());
+        for svg in ["<svg>1</svg>", "<svg>2</svg>"] {
+            db.save_diagram(
+                repo_id,
+                "data_flow",
+                "Title",
+                "Desc",
+                "dot",
+                "digraph {}",
+                svg,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let latest = db
+            .get_latest_diagram(repo_id, "data_flow")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.svg_content, "<svg>2</svg>");
+
+        db.request_diagram_regeneration(repo_id, "data_flow")
+            .await
+            .unwrap();
+        db.request_diagram_regeneration(repo_id, "data_flow")
+            .await
+            .unwrap();
+        db.request_diagram_regeneration(repo_id, "database_schema")
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_diagram_requests(repo_id).await.unwrap(),
+            ["data_flow", "database_schema"]
+        );
+
+        // Requests made after the generation started stay for the next scan
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let started_at = now_millis();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        db.request_diagram_regeneration(repo_id, "database_schema")
+            .await
+            .unwrap();
+        assert!(db
+            .clear_diagram_request(repo_id, "data_flow", &started_at)
+            .await
+            .unwrap());
+        assert!(!db
+            .clear_diagram_request(repo_id, "database_schema", &started_at)
+            .await
+            .unwrap());
+        assert_eq!(
+            db.get_diagram_requests(repo_id).await.unwrap(),
+            ["database_schema"]
+        );
+
+        db.delete_repository(repo_id).await.unwrap();
+        assert!(db.get_diagram_requests(repo_id).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_delete_repository_deletes_diagrams() {
         let (db, _temp_dir) = create_test_db().await;
@@ -1234,6 +7415,7 @@ mod tests {
             "system_architecture",
             "Title",
             "Desc",
+            "dot",
             "digraph { a -> b }",
             "<svg></svg>",
             None,
@@ -1261,4 +7443,82 @@ mod tests {
         let diagrams = db.get_latest_diagrams(repo_id).await.unwrap();
         assert!(diagrams.is_empty());
     }
+
+    // =========================================================================
+    // Job queue tests
+    // =========================================================================
+
+    #[tokio::test]
+    async fn test_delete_repository_deletes_jobs() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        db.enqueue_job(repo_id, "code_understanding", Some("file.rs"))
+            .await
+            .unwrap();
+        assert_eq!(db.get_jobs(10).await.unwrap().len(), 1);
+
+        db.delete_repository(repo_id).await.unwrap();
+        assert!(db.get_jobs(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_jobs_orders_running_first() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let done = db.enqueue_job(repo_id, "a", None).await.unwrap();
+        db.update_job_status(done, "done", None).await.unwrap();
+        let pending = db.enqueue_job(repo_id, "b", None).await.unwrap();
+        let running = db.enqueue_job(repo_id, "c", None).await.unwrap();
+        db.update_job_status(running, "running", None)
+            .await
+            .unwrap();
+
+        let ids: Vec<i64> = db
+            .get_jobs(10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|j| j.id)
+            .collect();
+        assert_eq!(ids, vec![running, pending, done]);
+    }
+
+    #[tokio::test]
+    async fn test_get_failed_file_jobs_uses_latest_run() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        for (file, statuses) in [("a.rs", ["done", "failed"]), ("b.rs", ["failed", "done"])] {
+            for status in statuses {
+                let id = db.enqueue_job(repo_id, "type1", Some(file)).await.unwrap();
+                db.update_job_status(id, status, Some("boom"))
+                    .await
+                    .unwrap();
+            }
+        }
+        let failed = db
+            .enqueue_job(repo_id, "type2", Some("c.rs"))
+            .await
+            .unwrap();
+        db.update_job_status(failed, "failed", None).await.unwrap();
+
+        let jobs = db.get_failed_file_jobs(repo_id, "type1").await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].file_path.as_deref(), Some("a.rs"));
+        assert_eq!(jobs[0].error.as_deref(), Some("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_finished_jobs_keeps_recent() {
+        let (db, _temp_dir) = create_test_db().await;
+        let (repo_id, _repo_dir) = add_test_repo(&db, "Test").await;
+
+        let id = db.enqueue_job(repo_id, "a", None).await.unwrap();
+        db.update_job_status(id, "done", None).await.unwrap();
+
+        assert_eq!(db.delete_finished_jobs(7).await.unwrap(), 0);
+        assert_eq!(db.get_job_counts().await.unwrap().done, 1);
+    }
 }
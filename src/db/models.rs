@@ -1,5 +1,9 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sha2::{Digest, Sha256};
+use sqlx::any::{Any, AnyTypeInfo, AnyValueRef};
+use sqlx::error::BoxDynError;
+use sqlx::{Decode, FromRow, Type};
 
 /// A repository configured for analysis
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -7,9 +11,182 @@ pub struct Repository {
     pub id: i64,
     pub path: String,
     pub name: String,
+    #[sqlx(try_from = "Flag")]
     pub enabled: bool,
     pub created_at: String,
     pub updated_at: String,
+    /// JSON list of the [`ANALYSIS_PASSES`] that run (None runs all of them)
+    pub analysis_types: Option<String>,
+    /// Only local endpoints may analyze this repository
+    #[sqlx(try_from = "Flag")]
+    pub sensitive: bool,
+    /// Git URL the repository was added by; `path` is then its managed checkout
+    pub remote_url: Option<String>,
+    /// Branch checked out from `remote_url` (None for the default branch)
+    pub branch: Option<String>,
+    /// Groups the repository belongs to, e.g. `work` or `personal`
+    #[sqlx(try_from = "JsonText<Vec<String>>")]
+    pub tags: Vec<String>,
+    /// When the repository was archived: it is no longer scanned or listed by
+    /// default, but its results are kept
+    pub archived_at: Option<String>,
+    /// Commands mutation testing runs instead of those in `noctum.toml`
+    #[sqlx(try_from = "JsonText<CommandOverrides>")]
+    pub commands: CommandOverrides,
+}
+
+/// Build and test commands set in a repository's settings, for repositories
+/// whose tests need something other than the commands of their `noctum.toml`
+/// mutation rules (e.g. `cargo nextest run` or `npm run test:unit`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandOverrides {
+    /// Replaces the `build_command` of every rule
+    pub compile_command: Option<String>,
+    /// Replaces the `test_command` of every rule
+    pub test_command: Option<String>,
+    /// Replaces the `timeout_seconds` of every rule
+    pub timeout_seconds: Option<u64>,
+    /// Directory the commands run in, relative to the repository root,
+    /// instead of the root of the project a file belongs to
+    pub working_dir: Option<String>,
+}
+
+impl CommandOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The overrides that are set, e.g. `test: cargo nextest run, timeout: 600s`
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(command) = &self.compile_command {
+            parts.push(format!("compile: {}", command));
+        }
+        if let Some(command) = &self.test_command {
+            parts.push(format!("test: {}", command));
+        }
+        if let Some(timeout) = self.timeout_seconds {
+            parts.push(format!("timeout: {}s", timeout));
+        }
+        if let Some(dir) = &self.working_dir {
+            parts.push(format!("working directory: {}", dir));
+        }
+        parts.join(", ")
+    }
+
+    /// Trim the overrides, treating blank ones as unset. The working directory
+    /// must stay inside the repository.
+    pub fn normalize(self) -> Result<Self, String> {
+        let trimmed = |value: Option<String>| {
+            value
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let working_dir = trimmed(self.working_dir);
+        if let Some(dir) = &working_dir {
+            let path = std::path::Path::new(dir);
+            if !path.components().all(|c| {
+                matches!(
+                    c,
+                    std::path::Component::Normal(_) | std::path::Component::CurDir
+                )
+            }) {
+                return Err(format!(
+                    "Invalid working directory '{}': use a path relative to the repository root",
+                    dir
+                ));
+            }
+        }
+        if self.timeout_seconds == Some(0) {
+            return Err("The timeout must be at least one second".to_string());
+        }
+        Ok(Self {
+            compile_command: trimmed(self.compile_command),
+            test_command: trimmed(self.test_command),
+            timeout_seconds: self.timeout_seconds,
+            working_dir,
+        })
+    }
+}
+
+/// Analysis passes that can be switched off per repository
+pub const ANALYSIS_PASSES: &[&str] = &[
+    "code_understanding",
+    "architecture",
+    "diagrams",
+    "mutation_testing",
+    "test_gaps",
+    "duplication",
+    "dead_code",
+    "security",
+];
+
+impl Repository {
+    /// The analysis passes selected for this repository. A missing or unreadable
+    /// selection runs every pass.
+    pub fn analysis_passes(&self) -> Vec<&'static str> {
+        let selected: Option<Vec<String>> = self
+            .analysis_types
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok());
+        match selected {
+            Some(selected) => ANALYSIS_PASSES
+                .iter()
+                .copied()
+                .filter(|pass| selected.iter().any(|s| s == pass))
+                .collect(),
+            None => ANALYSIS_PASSES.to_vec(),
+        }
+    }
+
+    /// Whether the analysis pass `pass` runs for this repository
+    pub fn runs(&self, pass: &str) -> bool {
+        self.analysis_passes().contains(&pass)
+    }
+
+    /// Whether the repository is tagged `tag`
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Whether the repository is archived
+    pub fn archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+
+    /// Token that must be passed to purge the repository. Only archived
+    /// repositories have one, and it changes each time they are archived.
+    pub fn purge_token(&self) -> Option<String> {
+        let archived_at = self.archived_at.as_deref()?;
+        let digest = Sha256::digest(format!("{}\0{}\0{}", self.id, self.path, archived_at));
+        Some(hex::encode(&digest[..8]))
+    }
+}
+
+/// Normalize user-entered repository tags: trimmed, lowercased, sorted and
+/// deduplicated. Tags may contain letters, digits, `-` and `_`.
+pub fn normalize_tags<S: AsRef<str>>(tags: &[S]) -> Result<Vec<String>, String> {
+    let mut normalized = Vec::new();
+    for tag in tags {
+        let tag = tag.as_ref().trim().to_lowercase();
+        if tag.is_empty() {
+            continue;
+        }
+        if !tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "Invalid tag '{}': use only letters, digits, '-' and '_'",
+                tag
+            ));
+        }
+        normalized.push(tag);
+    }
+    normalized.sort();
+    normalized.dedup();
+    Ok(normalized)
 }
 
 /// An analysis result from the daemon
@@ -23,6 +200,65 @@ pub struct AnalysisResult {
     pub severity: Option<String>,
     pub content_hash: Option<String>,
     pub created_at: String,
+    /// Name of the endpoint that produced the result (None for plugins)
+    pub endpoint: Option<String>,
+    /// Model that produced the result (None for plugins)
+    pub model: Option<String>,
+    /// How long the analysis took in milliseconds
+    pub duration_ms: Option<i64>,
+    /// Tracker issue filed from this finding
+    pub issue_url: Option<String>,
+}
+
+/// A structured finding of an analysis result
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AnalysisFinding {
+    pub id: i64,
+    pub analysis_result_id: i64,
+    pub repository_id: i64,
+    pub file_path: String,
+    pub title: String,
+    /// 'error', 'warning' or 'info'
+    pub severity: String,
+    pub category: String,
+    pub line_start: Option<i64>,
+    pub line_end: Option<i64>,
+    pub recommendation: String,
+    /// Identifies the finding across scans (None for findings saved before
+    /// deduplication)
+    pub fingerprint: Option<String>,
+    /// When a scan first and last reported the finding
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+    /// Number of results that reported the finding
+    pub occurrences: i64,
+    /// When a new result of the file stopped reporting the finding
+    pub resolved_at: Option<String>,
+}
+
+/// Which language model produced a result, and how long generation took.
+///
+/// Unset fields are stored as NULL, e.g. for plugin results or deterministic
+/// mutations that involve no model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Attribution {
+    /// Endpoint display name
+    pub endpoint: Option<String>,
+    pub model: Option<String>,
+    pub duration_ms: Option<i64>,
+}
+
+/// An analysis result to save with [`Database::save_analysis_results`]
+///
+/// [`Database::save_analysis_results`]: super::Database::save_analysis_results
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewAnalysisResult {
+    pub file_path: String,
+    pub analysis_type: String,
+    pub result: String,
+    pub severity: Option<String>,
+    pub content_hash: Option<String>,
+    pub attribution: Attribution,
 }
 
 /// Current daemon state
@@ -32,6 +268,11 @@ pub struct DaemonState {
     pub status: String,
     pub current_task: Option<String>,
     pub last_active: String,
+    /// Error that ended the most recent failed cycle
+    pub last_error: Option<String>,
+    pub last_error_at: Option<String>,
+    /// Scheduled processing is paused until this time (RFC 3339)
+    pub paused_until: Option<String>,
 }
 
 /// A mutation testing result
@@ -44,14 +285,96 @@ pub struct MutationResult {
     pub description: String,
     /// Why this mutation was chosen
     pub reasoning: String,
-    /// JSON map of line numbers to replacement content
-    pub replacements_json: String,
+    /// Line replacements that make up the mutation, by line number
+    #[sqlx(skip)]
+    pub replacements: Vec<MutationReplacement>,
     pub test_outcome: String,
     pub killing_test: Option<String>,
     pub test_output: Option<String>,
     pub execution_time_ms: Option<i32>,
     pub content_hash: Option<String>,
     pub created_at: String,
+    /// Pinned toolchain the mutation was tested with (None for host defaults)
+    pub toolchain: Option<String>,
+    /// Name of the endpoint that generated the mutation (None for deterministic mutations)
+    pub endpoint: Option<String>,
+    /// Model that generated the mutation (None for deterministic mutations)
+    pub model: Option<String>,
+    /// How long generating the file's mutations took in milliseconds
+    pub duration_ms: Option<i64>,
+    /// Whether tests execute the mutated lines (None without coverage data)
+    #[sqlx(try_from = "Flag")]
+    pub covered: Option<bool>,
+    /// Name of the project (e.g. workspace member) whose tests ran the
+    /// mutation (None for results recorded before projects were)
+    pub project: Option<String>,
+    /// Tracker issue filed from this survived mutant
+    pub issue_url: Option<String>,
+    /// Triage state, one of [`MUTATION_STATUSES`]
+    pub status: String,
+}
+
+/// Triage states of a mutation result: `new` until someone looks at it, then
+/// `acknowledged`, `wont_fix` or `fixed`
+pub const MUTATION_STATUSES: &[&str] = &["new", "acknowledged", "wont_fix", "fixed"];
+
+/// Filters for a page of mutation results. Unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MutationFilter {
+    /// Test outcome, e.g. 'survived'
+    pub outcome: Option<String>,
+    /// Substring of the file path
+    pub file: Option<String>,
+    /// Model that generated the mutation
+    pub model: Option<String>,
+    /// Triage state
+    pub status: Option<String>,
+    /// Inclusive range of line numbers one of the replacements must touch
+    pub lines: Option<(i64, i64)>,
+}
+
+/// One line replacement of a mutation result
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct MutationReplacement {
+    /// 1-indexed line number in the mutated file
+    pub line_number: i64,
+    pub find: String,
+    pub replace: String,
+    /// The line before the mutation (empty for results saved without it)
+    #[serde(default)]
+    pub original_line: String,
+}
+
+impl MutationReplacement {
+    /// The line before the mutation, or just the replaced text if unknown
+    pub fn original(&self) -> &str {
+        if self.original_line.is_empty() {
+            &self.find
+        } else {
+            &self.original_line
+        }
+    }
+
+    /// The line with the mutation applied, or just the replacement text if unknown
+    pub fn mutated(&self) -> String {
+        if self.original_line.is_empty() {
+            self.replace.clone()
+        } else {
+            self.original_line.replacen(&self.find, &self.replace, 1)
+        }
+    }
+}
+
+/// A test case suggested to kill a surviving mutation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct MutationTestSuggestion {
+    pub mutation_result_id: i64,
+    /// Test function name
+    pub name: String,
+    /// What the test sets up and exercises
+    pub scenario: String,
+    /// Sketch of the assertion that fails on the mutated code
+    pub assertion: String,
 }
 
 /// Summary statistics for mutation testing
@@ -62,8 +385,286 @@ pub struct MutationSummary {
     pub survived: usize,
     pub timeout: usize,
     pub compile_error: usize,
+    /// Mutations rejected by the quality filter without being executed
+    pub rejected: usize,
+    /// Survived mutations on lines tests execute (None if no result has coverage data)
+    pub covered_survived: Option<usize>,
+    /// Survived mutations nobody has triaged yet
+    pub untriaged: usize,
+}
+
+/// Mutation testing deferred to the next schedule window because the rest of
+/// the run would not have fit in the current one
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MutationDeferral {
+    pub repository_id: i64,
+    /// Files left to mutation test
+    pub pending_files: i64,
+    /// Estimated time to test them
+    pub estimated_seconds: i64,
+    /// Time that was left in the window when the run was deferred
+    pub remaining_window_seconds: i64,
+    pub deferred_at: String,
+}
+
+/// The mutations generated for a file whose mutation testing hasn't finished
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MutationRun {
+    pub repository_id: i64,
+    pub file_path: String,
+    /// Hash of the content the mutations were generated for
+    pub content_hash: String,
+    #[sqlx(try_from = "JsonText<Vec<crate::mutation::GeneratedMutation>>")]
+    pub mutations: Vec<crate::mutation::GeneratedMutation>,
+    /// Indices of the mutations already executed
+    #[sqlx(try_from = "JsonText<Vec<usize>>")]
+    pub executed: Vec<usize>,
+    pub endpoint: Option<String>,
+    pub model: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub started_at: String,
+}
+
+impl MutationRun {
+    /// How the mutations were generated, for their results
+    pub fn attribution(&self) -> Attribution {
+        Attribution {
+            endpoint: self.endpoint.clone(),
+            model: self.model.clone(),
+            duration_ms: self.duration_ms,
+        }
+    }
+}
+
+/// An entry of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub repository_id: Option<i64>,
+    /// Name of the repository, if it still exists
+    pub repository_name: Option<String>,
+    /// What happened, e.g. `sensitive_routing`
+    pub action: String,
+    pub detail: String,
+    pub created_at: String,
+}
+
+/// A processing cycle of the daemon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct ScanRun {
+    pub id: i64,
+    /// What started the cycle (`scheduled` or `manual`)
+    pub trigger: String,
+    /// `running`, `completed`, `stopped` (by a pause or shutdown), `failed`,
+    /// or `interrupted` (the daemon exited mid-cycle)
+    pub status: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub repositories_processed: i64,
+    pub files_analyzed: i64,
+    pub mutations_run: i64,
+    /// Repositories whose analysis failed, plus a failure of the cycle itself
+    pub errors: i64,
+    pub last_error: Option<String>,
+}
+
+/// What the scans since a digest's starting point produced for a repository,
+/// see [`Database::get_repository_digests`](super::Database::get_repository_digests)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct RepositoryDigest {
+    pub repository_id: i64,
+    pub name: String,
+    pub files_analyzed: i64,
+    /// Findings no earlier result reported, by severity
+    pub new_errors: i64,
+    pub new_warnings: i64,
+    pub new_infos: i64,
+    pub mutations_run: i64,
+    /// Killed and survived mutants before the period
+    pub killed_before: i64,
+    pub survived_before: i64,
+    /// Killed and survived mutants including the period
+    pub killed: i64,
+    pub survived: i64,
+}
+
+impl RepositoryDigest {
+    /// Mutation score before the period (None without tested mutants)
+    pub fn score_before(&self) -> Option<f64> {
+        score(self.killed_before, self.survived_before)
+    }
+
+    /// Mutation score including the period (None without tested mutants)
+    pub fn score(&self) -> Option<f64> {
+        score(self.killed, self.survived)
+    }
 }
 
+fn score(killed: i64, survived: i64) -> Option<f64> {
+    let testable = killed + survived;
+    (testable > 0).then(|| killed as f64 / testable as f64)
+}
+
+/// What a repository's health score is computed from: its latest code
+/// understanding results and all its mutation results, see
+/// [`Database::get_score_inputs`](super::Database::get_score_inputs)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct ScoreInputs {
+    /// Files with a code understanding result
+    pub files: i64,
+    /// Files whose latest result has a documentation finding
+    pub undocumented_files: i64,
+    /// Findings of the latest results, by severity
+    pub errors: i64,
+    pub warnings: i64,
+    pub infos: i64,
+    pub killed: i64,
+    pub survived: i64,
+}
+
+impl ScoreInputs {
+    /// Weights of the mutation kill rate, finding and documentation components
+    const WEIGHTS: [f64; 3] = [0.4, 0.4, 0.2];
+
+    /// Share of tested mutants the tests killed (None without tested mutants)
+    pub fn kill_rate(&self) -> Option<f64> {
+        score(self.killed, self.survived)
+    }
+
+    /// 1 without findings, falling towards 0 as the severity-weighted findings
+    /// per file grow: one warning per file gives 1/3, one error per file 1/6
+    /// (None without analyzed files)
+    pub fn finding_score(&self) -> Option<f64> {
+        (self.files > 0).then(|| {
+            let weighted =
+                5.0 * self.errors as f64 + 2.0 * self.warnings as f64 + 0.5 * self.infos as f64;
+            1.0 / (1.0 + weighted / self.files as f64)
+        })
+    }
+
+    /// Share of analyzed files without documentation findings (None without
+    /// analyzed files)
+    pub fn doc_coverage(&self) -> Option<f64> {
+        (self.files > 0).then(|| {
+            let undocumented = self.undocumented_files.min(self.files);
+            (self.files - undocumented) as f64 / self.files as f64
+        })
+    }
+
+    /// The composite health score from 0 to 100: the weighted average of the
+    /// components that can be computed (None if none can)
+    pub fn health(&self) -> Option<f64> {
+        let components = [self.kill_rate(), self.finding_score(), self.doc_coverage()];
+        let (sum, weights) = components
+            .iter()
+            .zip(Self::WEIGHTS)
+            .filter_map(|(component, weight)| component.map(|c| (c * weight, weight)))
+            .fold((0.0, 0.0), |(sum, weights), (c, w)| (sum + c, weights + w));
+        (weights > 0.0).then(|| (sum / weights * 1000.0).round() / 10.0)
+    }
+}
+
+/// A repository's health score recorded after a processing cycle
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct HealthScore {
+    pub id: i64,
+    pub repository_id: i64,
+    /// The processing cycle that recorded it
+    pub scan_run_id: Option<i64>,
+    /// 0 to 100, see [`ScoreInputs::health`]
+    pub score: f64,
+    /// The components, from 0 to 1, that went into the score
+    pub kill_rate: Option<f64>,
+    pub finding_score: Option<f64>,
+    pub doc_coverage: Option<f64>,
+    pub created_at: String,
+}
+
+/// A message of a repository's chat
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ChatMessage {
+    pub id: i64,
+    pub repository_id: i64,
+    /// 'user' or 'assistant'
+    pub role: String,
+    pub content: String,
+    /// Files whose stored analyses the answer was grounded in (assistant messages)
+    #[sqlx(try_from = "JsonText<Vec<String>>")]
+    pub sources: Vec<String>,
+    pub created_at: String,
+}
+
+/// The structured architecture model of a repository
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StoredArchitectureModel {
+    pub repository_id: i64,
+    #[sqlx(try_from = "JsonText<crate::analyzer::ArchitectureModel>")]
+    pub model: crate::analyzer::ArchitectureModel,
+    /// Endpoint and model that generated it
+    pub endpoint: Option<String>,
+    pub llm_model: Option<String>,
+    pub created_at: String,
+}
+
+/// Quick scan and confirmation state of a repository added from the dashboard
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Onboarding {
+    pub repository_id: i64,
+    /// 'scanning', 'ready', 'failed' or 'confirmed'
+    pub status: String,
+    /// What the quick scan found (empty while it runs)
+    #[sqlx(try_from = "JsonText<crate::onboarding::QuickScan>")]
+    pub quick_scan: crate::onboarding::QuickScan,
+    /// Why the quick scan failed
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Onboarding {
+    /// Whether full scans still skip the repository
+    pub fn awaits_confirmation(&self) -> bool {
+        self.status != crate::onboarding::STATUS_CONFIRMED
+    }
+}
+
+/// A digest email, or a skipped one when nothing ran
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DigestRecord {
+    pub id: i64,
+    pub sent_at: String,
+    /// Newest scan run the digest covered
+    pub last_run_id: i64,
+}
+
+/// The embedding of a file's code understanding result
+#[derive(Debug, Clone, FromRow)]
+pub struct Embedding {
+    pub repository_id: i64,
+    pub repository_name: String,
+    pub repository_path: String,
+    pub file_path: String,
+    pub analysis_result_id: i64,
+    /// Little-endian `f32` components
+    pub vector: Vec<u8>,
+}
+
+/// Audit action: a sensitive repository's content was routed to local endpoints
+/// only (or withheld entirely)
+pub const AUDIT_SENSITIVE_ROUTING: &str = "sensitive_routing";
+
+/// Audit action: a repository was marked or unmarked as sensitive
+pub const AUDIT_SENSITIVE_CHANGED: &str = "sensitive_changed";
+
+/// Audit action: a repository was archived or restored
+pub const AUDIT_ARCHIVE_CHANGED: &str = "archive_changed";
+
+/// Audit action: a repository and all of its results were deleted
+pub const AUDIT_PURGED: &str = "purged";
+
+/// Audit action: a repository's command overrides were changed
+pub const AUDIT_COMMANDS_CHANGED: &str = "commands_changed";
+
 /// A generated DOT diagram for a repository
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Diagram {
@@ -75,15 +676,234 @@ pub struct Diagram {
     pub title: String,
     /// Description of what the diagram shows
     pub description: String,
-    /// The GraphViz DOT diagram source code
+    /// Source format: 'dot', or 'mermaid' for diagrams rendered in the browser
+    pub format: String,
+    /// The diagram source code, GraphViz DOT or Mermaid depending on `format`
     pub dot_content: String,
-    /// Pre-rendered SVG content
+    /// Pre-rendered SVG content (empty for Mermaid)
     pub svg_content: String,
     /// Combined hash of source files used to generate this diagram
     pub content_hash: Option<String>,
     pub created_at: String,
 }
 
+/// A saved version of a diagram, without its content
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DiagramVersion {
+    pub id: i64,
+    pub format: String,
+    pub content_hash: Option<String>,
+    pub created_at: String,
+}
+
+/// A block of code found (after normalization) in several files
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DuplicateCluster {
+    pub id: i64,
+    pub repository_id: i64,
+    /// Hash of the normalized code the locations share
+    pub fingerprint: String,
+    /// Number of code lines (ignoring blank and punctuation-only lines) in the block
+    pub line_count: i64,
+    /// Where the block occurs, by file path
+    #[sqlx(skip)]
+    pub locations: Vec<DuplicateLocation>,
+    pub created_at: String,
+}
+
+/// One occurrence of a duplicated block
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, FromRow)]
+pub struct DuplicateLocation {
+    pub file_path: String,
+    /// First line of the block (1-based)
+    pub line_start: i64,
+    /// Last line of the block (1-based, inclusive)
+    pub line_end: i64,
+    /// Code lines in the whole file, for similarity scores
+    pub file_lines: i64,
+}
+
+/// A duplicate cluster to save with [`Database::replace_duplicate_clusters`]
+///
+/// [`Database::replace_duplicate_clusters`]: super::Database::replace_duplicate_clusters
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewDuplicateCluster {
+    pub fingerprint: String,
+    pub line_count: i64,
+    pub locations: Vec<DuplicateLocation>,
+}
+
+/// A unit of queued daemon work (per-file analysis or a repository-level phase)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Job {
+    pub id: i64,
+    pub repository_id: i64,
+    /// Kind of work, e.g. 'code_understanding' or 'mutation_testing'
+    pub job_type: String,
+    /// File the job applies to (None for repository-level jobs)
+    pub file_path: Option<String>,
+    /// One of 'pending', 'running', 'done', 'failed'
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    /// Pending jobs with a higher priority run first
+    pub priority: i64,
+    /// Endpoint the job was assigned to when it started
+    pub endpoint: Option<String>,
+    /// Name of the owning repository (joined for display)
+    pub repository_name: Option<String>,
+}
+
+/// Number of jobs in each status
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobCounts {
+    pub pending: i64,
+    pub running: i64,
+    pub done: i64,
+    pub failed: i64,
+}
+
+/// Filters for the scripting query interface. Unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryFilter {
+    /// Repository name
+    pub repository: Option<String>,
+    /// Analysis result severity ('error', 'warning', 'info')
+    pub severity: Option<String>,
+    /// Analysis type, e.g. 'code_understanding'
+    pub analysis_type: Option<String>,
+    /// Mutation test outcome, e.g. 'survived'
+    pub outcome: Option<String>,
+    /// Model that produced the result
+    pub model: Option<String>,
+    /// Only rows created within this many days
+    pub since_days: Option<u32>,
+    /// Maximum number of rows
+    pub limit: u32,
+}
+
+/// An analysis result row returned by the `findings` query
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FindingRow {
+    pub repository: String,
+    pub file_path: String,
+    pub analysis_type: String,
+    pub severity: Option<String>,
+    pub result: String,
+    pub created_at: String,
+    pub endpoint: Option<String>,
+    pub model: Option<String>,
+    pub duration_ms: Option<i64>,
+}
+
+/// A mutation result row returned by the `mutations` query
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MutationRow {
+    pub repository: String,
+    pub file_path: String,
+    pub description: String,
+    pub test_outcome: String,
+    pub killing_test: Option<String>,
+    pub created_at: String,
+    pub endpoint: Option<String>,
+    pub model: Option<String>,
+    pub duration_ms: Option<i64>,
+}
+
+/// A change-log entry written by delta analysis
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ChangelogEntry {
+    pub id: i64,
+    pub repository_id: i64,
+    pub file_path: String,
+    /// What changed, as described by the LLM
+    pub entry: String,
+    /// Hash of the file content the entry describes
+    pub content_hash: Option<String>,
+    pub created_at: String,
+}
+
+/// Inventory status of a file that was skipped or decoded lossily
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FileStatus {
+    pub repository_id: i64,
+    pub file_path: String,
+    /// 'skipped_binary', 'skipped_minified' or 'lossy_utf8'
+    pub status: String,
+    pub updated_at: String,
+}
+
+/// An open issue imported from a repository's issue tracker
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Issue {
+    pub repository_id: i64,
+    /// Issue number on GitHub, project-level IID on GitLab
+    pub number: i64,
+    pub title: String,
+    pub url: String,
+    /// Comma-separated label names
+    pub labels: String,
+    pub fetched_at: String,
+}
+
+/// LLM token usage of one endpoint, repository and analysis type over a period
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UsageTotal {
+    pub endpoint: String,
+    pub repository_id: i64,
+    /// None if the repository has since been deleted
+    pub repository_name: Option<String>,
+    pub analysis_type: String,
+    pub requests: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    /// Generations retried after transient errors
+    pub retries: i64,
+    /// Responses rejected by validation
+    pub rejected: i64,
+}
+
+/// LLM token usage of all endpoints on one day
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DailyUsage {
+    /// YYYY-MM-DD (UTC)
+    pub day: String,
+    pub requests: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    /// Generations retried after transient errors
+    pub retries: i64,
+    /// Responses rejected by validation
+    pub rejected: i64,
+}
+
+/// Number of rows removed by [`Database::delete_results_by_model`](super::Database::delete_results_by_model)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PurgeCounts {
+    pub analysis_results: u64,
+    pub mutation_results: u64,
+}
+
+/// Number of rows removed by [`Database::prune_history`](super::Database::prune_history)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PruneCounts {
+    pub analysis_results: u64,
+    pub mutation_results: u64,
+    pub diagrams: u64,
+}
+
+/// Row count and on-disk size of a table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableStats {
+    pub name: String,
+    pub rows: i64,
+    /// Bytes used by the table, excluding indexes (None if the SQLite build
+    /// lacks the `dbstat` table)
+    pub bytes: Option<i64>,
+}
+
 impl MutationSummary {
     /// Calculate the mutation score (killed / (killed + survived))
     pub fn mutation_score(&self) -> f64 {
@@ -96,6 +916,78 @@ impl MutationSummary {
     }
 }
 
+/// A JSON document in a TEXT column, which the database drivers behind
+/// [`Any`] can't decode by themselves
+pub struct JsonText<T>(T);
+
+impl<T> Type<Any> for JsonText<T> {
+    fn type_info() -> AnyTypeInfo {
+        <String as Type<Any>>::type_info()
+    }
+
+    fn compatible(ty: &AnyTypeInfo) -> bool {
+        <String as Type<Any>>::compatible(ty)
+    }
+}
+
+impl<'r, T: DeserializeOwned> Decode<'r, Any> for JsonText<T> {
+    fn decode(value: AnyValueRef<'r>) -> Result<Self, BoxDynError> {
+        let text = <&str as Decode<Any>>::decode(value)?;
+        Ok(Self(serde_json::from_str(text)?))
+    }
+}
+
+macro_rules! json_columns {
+    ($($ty:ty),* $(,)?) => {
+        $(impl From<JsonText<$ty>> for $ty {
+            fn from(json: JsonText<$ty>) -> Self {
+                json.0
+            }
+        })*
+    };
+}
+
+json_columns!(
+    Vec<String>,
+    Vec<usize>,
+    CommandOverrides,
+    Vec<crate::mutation::GeneratedMutation>,
+    crate::analyzer::ArchitectureModel,
+    crate::onboarding::QuickScan,
+);
+
+/// A flag in an INTEGER column (0 or 1), which both SQLite and PostgreSQL
+/// return as an integer rather than a boolean
+pub struct Flag(Option<i64>);
+
+impl Type<Any> for Flag {
+    fn type_info() -> AnyTypeInfo {
+        <i64 as Type<Any>>::type_info()
+    }
+
+    fn compatible(ty: &AnyTypeInfo) -> bool {
+        <i64 as Type<Any>>::compatible(ty)
+    }
+}
+
+impl<'r> Decode<'r, Any> for Flag {
+    fn decode(value: AnyValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(Self(<Option<i64> as Decode<Any>>::decode(value)?))
+    }
+}
+
+impl From<Flag> for bool {
+    fn from(flag: Flag) -> Self {
+        flag.0.is_some_and(|value| value != 0)
+    }
+}
+
+impl From<Flag> for Option<bool> {
+    fn from(flag: Flag) -> Self {
+        flag.0.map(|value| value != 0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,6 +1000,9 @@ mod tests {
             survived: 0,
             timeout: 0,
             compile_error: 0,
+            rejected: 0,
+            covered_survived: None,
+            untriaged: 0,
         };
         assert!((summary.mutation_score() - 1.0).abs() < f64::EPSILON);
     }
@@ -120,6 +1015,9 @@ mod tests {
             survived: 10,
             timeout: 0,
             compile_error: 0,
+            rejected: 0,
+            covered_survived: None,
+            untriaged: 0,
         };
         assert!((summary.mutation_score() - 0.0).abs() < f64::EPSILON);
     }
@@ -132,6 +1030,9 @@ mod tests {
             survived: 5,
             timeout: 0,
             compile_error: 0,
+            rejected: 0,
+            covered_survived: None,
+            untriaged: 0,
         };
         assert!((summary.mutation_score() - 0.5).abs() < f64::EPSILON);
     }
@@ -145,6 +1046,9 @@ mod tests {
             survived: 0,
             timeout: 5,
             compile_error: 5,
+            rejected: 0,
+            covered_survived: None,
+            untriaged: 0,
         };
         assert!((summary.mutation_score() - 0.0).abs() < f64::EPSILON);
     }
@@ -164,6 +1068,9 @@ mod tests {
             survived: 4,
             timeout: 5,
             compile_error: 5,
+            rejected: 0,
+            covered_survived: None,
+            untriaged: 0,
         };
         // 6 / (6 + 4) = 0.6
         assert!((summary.mutation_score() - 0.6).abs() < f64::EPSILON);
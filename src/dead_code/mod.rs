@@ -0,0 +1,649 @@
+//! Dead code and unused dependency detection.
+//!
+//! Deterministic checks come first: dependencies declared in a `Cargo.toml`
+//! or `package.json` that no source file of the project refers to, and
+//! TypeScript/JavaScript exports that no other file of the project mentions.
+//! These are cheap but noisy (a crate used only through a macro, an export
+//! consumed by another package), so each file's candidates are then shown to
+//! the model, which confirms or dismisses them and suggests how to remove the
+//! confirmed ones. Without an available endpoint the candidates are kept as
+//! they are. The result is stored per file as structured findings in the
+//! `dead_code` category.
+
+use crate::analyzer::{respond_in, StructuredFinding};
+use crate::daemon::truncate_at_char_boundary;
+use crate::language::Language;
+use crate::project::Project;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Category of dead code findings
+pub const CATEGORY: &str = "dead_code";
+
+/// Maximum number of candidates reported per file
+pub const MAX_CANDIDATES_PER_FILE: usize = 20;
+
+/// Maximum characters of a file shown to the model
+const MAX_CONTENT_CHARS: usize = 12_000;
+
+/// Dependency tables of a `Cargo.toml`, at the top level or under `[target.*]`
+const CARGO_DEPENDENCY_TABLES: &[&str] =
+    &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Kind of unused code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateKind {
+    /// A dependency declared in a manifest
+    Dependency,
+    /// An exported declaration of a module
+    Export,
+}
+
+/// Something that looks unused
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// File declaring it (a manifest or a source file)
+    pub file: PathBuf,
+    /// Line of the declaration (1-based), if found
+    pub line: Option<u32>,
+    pub kind: CandidateKind,
+    /// Dependency or exported name
+    pub name: String,
+}
+
+impl Candidate {
+    pub fn title(&self) -> String {
+        match self.kind {
+            CandidateKind::Dependency => format!("Unused dependency `{}`", self.name),
+            CandidateKind::Export => format!("Unused export `{}`", self.name),
+        }
+    }
+
+    /// Why it was flagged, for the prompt and the model-less report
+    fn evidence(&self) -> &'static str {
+        match self.kind {
+            CandidateKind::Dependency => {
+                "declared in the manifest, but no source file of the project refers to it"
+            }
+            CandidateKind::Export => {
+                "exported, but no other source file of the project refers to it"
+            }
+        }
+    }
+
+    /// Unused dependencies cost build time and supply chain exposure; unused
+    /// exports only clutter the API
+    fn severity(&self) -> &'static str {
+        match self.kind {
+            CandidateKind::Dependency => "warning",
+            CandidateKind::Export => "info",
+        }
+    }
+
+    fn default_suggestion(&self) -> String {
+        match self.kind {
+            CandidateKind::Dependency => {
+                format!("Remove `{}` from the manifest", self.name)
+            }
+            CandidateKind::Export => format!(
+                "Remove `{}`, or drop the `export` if it is still used within the file",
+                self.name
+            ),
+        }
+    }
+}
+
+/// Find unused dependencies and exports in `projects`, ordered by file. Only
+/// Rust and TypeScript projects are checked.
+pub fn find_candidates(projects: &[Project]) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    for project in projects {
+        let Ok(files) = project.language.find_source_files(&project.root) else {
+            continue;
+        };
+        let sources: Vec<(PathBuf, String)> = files
+            .into_iter()
+            .filter_map(|path| Some((path.clone(), std::fs::read_to_string(&path).ok()?)))
+            .collect();
+        match project.language {
+            Language::Rust => candidates.extend(unused_crates(&project.root, &sources)),
+            Language::TypeScript => {
+                candidates.extend(unused_packages(&project.root, &sources));
+                candidates.extend(unused_exports(&sources));
+            }
+            _ => {}
+        }
+    }
+
+    // A workspace root's sources include its members', so files can be seen twice
+    let mut seen = HashSet::new();
+    candidates.retain(|c| seen.insert((c.file.clone(), c.name.clone())));
+    candidates.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    candidates
+}
+
+/// Crates of the project's `Cargo.toml` no Rust file refers to by name
+fn unused_crates(root: &Path, sources: &[(PathBuf, String)]) -> Vec<Candidate> {
+    let manifest_path = root.join("Cargo.toml");
+    let Ok(manifest) = std::fs::read_to_string(&manifest_path) else {
+        return Vec::new();
+    };
+    let used: HashSet<&str> = sources
+        .iter()
+        .flat_map(|(_, content)| identifiers(content))
+        .collect();
+
+    cargo_dependencies(&manifest)
+        .into_iter()
+        .filter(|name| !used.contains(name.replace('-', "_").as_str()))
+        .map(|name| Candidate {
+            file: manifest_path.clone(),
+            line: declaration_line(&manifest, &name),
+            kind: CandidateKind::Dependency,
+            name,
+        })
+        .collect()
+}
+
+/// Names of the dependencies declared in a `Cargo.toml`, sorted
+fn cargo_dependencies(manifest: &str) -> Vec<String> {
+    let Ok(doc) = manifest.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let targets = doc
+        .get("target")
+        .and_then(|t| t.as_table())
+        .into_iter()
+        .flat_map(|targets| targets.values().filter_map(|t| t.as_table()));
+
+    let names: BTreeSet<String> = std::iter::once(&doc)
+        .chain(targets)
+        .flat_map(|table| {
+            CARGO_DEPENDENCY_TABLES
+                .iter()
+                .filter_map(|key| table.get(*key)?.as_table())
+        })
+        // A renamed dependency (`alias = { package = "real-name" }`) is
+        // referred to by its key
+        .flat_map(|deps| deps.keys().cloned())
+        .collect();
+    names.into_iter().collect()
+}
+
+/// Packages of the project's `package.json` no source file imports. Type
+/// packages (`@types/*`) are used by the compiler and are skipped.
+fn unused_packages(root: &Path, sources: &[(PathBuf, String)]) -> Vec<Candidate> {
+    let manifest_path = root.join("package.json");
+    let Ok(manifest) = std::fs::read_to_string(&manifest_path) else {
+        return Vec::new();
+    };
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(&manifest) else {
+        return Vec::new();
+    };
+    let Some(dependencies) = package.get("dependencies").and_then(|d| d.as_object()) else {
+        return Vec::new();
+    };
+    let imported: HashSet<&str> = sources
+        .iter()
+        .flat_map(|(_, content)| string_literals(content))
+        .filter_map(package_name)
+        .collect();
+
+    dependencies
+        .keys()
+        .filter(|name| !name.starts_with("@types/") && !imported.contains(name.as_str()))
+        .map(|name| Candidate {
+            file: manifest_path.clone(),
+            line: declaration_line(&manifest, name),
+            kind: CandidateKind::Dependency,
+            name: name.clone(),
+        })
+        .collect()
+}
+
+/// Exports no other source file mentions. Index files, entry points, type
+/// declarations and tests are skipped, since their exports are consumed from
+/// outside the project or by the test runner.
+fn unused_exports(sources: &[(PathBuf, String)]) -> Vec<Candidate> {
+    // Number of files each identifier occurs in
+    let mut file_counts: HashMap<&str, usize> = HashMap::new();
+    for (_, content) in sources {
+        let unique: HashSet<&str> = identifiers(content).collect();
+        for identifier in unique {
+            *file_counts.entry(identifier).or_default() += 1;
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (path, content) in sources {
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let stem = file_name.split('.').next().unwrap_or("");
+        if matches!(stem, "index" | "main")
+            || file_name.ends_with(".d.ts")
+            || file_name.contains(".test.")
+            || file_name.contains(".spec.")
+            || file_name.contains(".config.")
+        {
+            continue;
+        }
+        for (name, line) in exported_names(content) {
+            // The declaring file always counts once
+            if file_counts.get(name.as_str()).copied().unwrap_or(0) <= 1 {
+                candidates.push(Candidate {
+                    file: path.clone(),
+                    line: Some(line),
+                    kind: CandidateKind::Export,
+                    name,
+                });
+            }
+        }
+    }
+    candidates
+}
+
+/// Names exported by the declarations of a module and the lines they are on.
+/// Default exports and re-exports (`export ... from`) are skipped.
+fn exported_names(content: &str) -> Vec<(String, u32)> {
+    const DECLARATIONS: &[&str] = &[
+        "function*",
+        "function",
+        "const",
+        "let",
+        "var",
+        "class",
+        "interface",
+        "type",
+        "enum",
+    ];
+    let mut names = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index as u32 + 1;
+        let Some(rest) = line.trim_start().strip_prefix("export ") else {
+            continue;
+        };
+        let mut rest = rest.trim_start();
+        for modifier in ["declare ", "async ", "abstract "] {
+            rest = rest.strip_prefix(modifier).unwrap_or(rest).trim_start();
+        }
+
+        if let Some(list) = rest.strip_prefix('{') {
+            // `export { a, b as c }`, without a `from` clause
+            let Some((list, tail)) = list.split_once('}') else {
+                continue;
+            };
+            if tail.contains("from") {
+                continue;
+            }
+            for item in list.split(',') {
+                let exported = item.rsplit(" as ").next().unwrap_or(item).trim();
+                if is_identifier(exported) && exported != "default" {
+                    names.push((exported.to_string(), line_number));
+                }
+            }
+            continue;
+        }
+
+        for keyword in DECLARATIONS {
+            if let Some(after) = rest.strip_prefix(keyword) {
+                if !after.starts_with(|c: char| c.is_whitespace()) {
+                    continue;
+                }
+                let name: String = after
+                    .trim_start()
+                    .chars()
+                    .take_while(|&c| is_identifier_char(c))
+                    .collect();
+                if is_identifier(&name) {
+                    names.push((name, line_number));
+                }
+                break;
+            }
+        }
+    }
+    names
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with(|c: char| c.is_ascii_digit())
+        && s.chars().all(is_identifier_char)
+}
+
+/// The identifiers (and numbers) of source code
+fn identifiers(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .split(|c: char| !is_identifier_char(c))
+        .filter(|word| !word.is_empty())
+}
+
+/// The contents of the single-, double- and backtick-quoted strings on each
+/// line of source code
+fn string_literals(content: &str) -> impl Iterator<Item = &str> {
+    content.lines().flat_map(|line| {
+        let mut literals = Vec::new();
+        let mut open: Option<(char, usize)> = None;
+        for (index, c) in line.char_indices() {
+            match open {
+                Some((quote, start)) if c == quote => {
+                    literals.push(&line[start..index]);
+                    open = None;
+                }
+                None if matches!(c, '\'' | '"' | '`') => open = Some((c, index + 1)),
+                _ => {}
+            }
+        }
+        literals
+    })
+}
+
+/// Package a module specifier refers to (`lodash/fp` → `lodash`,
+/// `@scope/pkg/sub` → `@scope/pkg`), or None for relative and absolute paths
+fn package_name(specifier: &str) -> Option<&str> {
+    if specifier.is_empty() || specifier.starts_with('.') || specifier.starts_with('/') {
+        return None;
+    }
+    let specifier = specifier.strip_prefix("node:").unwrap_or(specifier);
+    let end = if specifier.starts_with('@') {
+        specifier.match_indices('/').nth(1).map(|(i, _)| i)
+    } else {
+        specifier.find('/')
+    };
+    Some(&specifier[..end.unwrap_or(specifier.len())])
+}
+
+/// Line a manifest declares the dependency `name` on: a `name = ...` or
+/// `"name": ...` entry, or a `[dependencies.name]` table
+fn declaration_line(manifest: &str, name: &str) -> Option<u32> {
+    manifest
+        .lines()
+        .position(|line| {
+            let line = line.trim();
+            let key = line
+                .strip_prefix('"')
+                .and_then(|l| l.strip_prefix(name))
+                .and_then(|l| l.strip_prefix('"'))
+                .or_else(|| line.strip_prefix(name));
+            match key {
+                Some(rest) => rest.trim_start().starts_with(['=', ':', '.']),
+                None => {
+                    line.starts_with('[')
+                        && line.trim_end_matches(']').ends_with(&format!(".{}", name))
+                }
+            }
+        })
+        .map(|index| index as u32 + 1)
+}
+
+/// The model's verdict on one candidate
+#[derive(Debug, Clone, Deserialize)]
+pub struct Verdict {
+    /// Number of the candidate in the prompt (1-based)
+    pub number: usize,
+    pub unused: bool,
+    #[serde(default)]
+    pub suggestion: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewResponse {
+    pub candidates: Vec<Verdict>,
+}
+
+/// JSON schema for the review response
+pub fn review_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "candidates": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "number": {
+                            "type": "integer",
+                            "description": "Number of the candidate"
+                        },
+                        "unused": {
+                            "type": "boolean",
+                            "description": "Whether the candidate really is unused and can be removed"
+                        },
+                        "suggestion": {
+                            "type": "string",
+                            "description": "How to remove it, e.g. the lines to delete and anything that has to change with them"
+                        }
+                    },
+                    "required": ["number", "unused", "suggestion"]
+                }
+            }
+        },
+        "required": ["candidates"]
+    })
+}
+
+/// Prompt asking the model to confirm the `candidates` declared in a file
+pub fn review_prompt(
+    file_path: &str,
+    content: &str,
+    candidates: &[Candidate],
+    output_language: &str,
+) -> String {
+    let numbered: String = truncate_at_char_boundary(content, MAX_CONTENT_CHARS)
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:4} | {}\n", i + 1, line))
+        .collect();
+    let listed: String = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let line = c.line.map(|l| format!(" (line {})", l)).unwrap_or_default();
+            format!("{}. {}{}: {}\n", i + 1, c.title(), line, c.evidence())
+        })
+        .collect();
+
+    format!(
+        "A text search flagged the items below as possibly unused. Text search misses some \
+         uses: dependencies used only through macros, derives, build scripts, plugins or \
+         configuration files, exports that are part of a library's public API, and names \
+         built dynamically. For each item, decide from the file whether it really is unused \
+         and can be removed, and if so, say how to remove it.\n\n\
+         File: {}\n\n```\n{}```\n\n# Possibly unused\n{}\n{}",
+        file_path,
+        numbered,
+        listed,
+        respond_in(output_language, true)
+    )
+}
+
+/// Findings for the `candidates` of a file. With the model's `verdicts`, the
+/// candidates it dismissed are dropped and its suggestions are used.
+pub fn findings(candidates: &[Candidate], verdicts: Option<&[Verdict]>) -> Vec<StructuredFinding> {
+    candidates
+        .iter()
+        .take(MAX_CANDIDATES_PER_FILE)
+        .enumerate()
+        .filter_map(|(i, candidate)| {
+            let verdict = verdicts.map(|verdicts| verdicts.iter().find(|v| v.number == i + 1));
+            let suggestion = match verdict {
+                Some(Some(verdict)) if !verdict.unused => return None,
+                Some(Some(verdict)) if !verdict.suggestion.trim().is_empty() => {
+                    verdict.suggestion.trim().to_string()
+                }
+                _ => candidate.default_suggestion(),
+            };
+            Some(StructuredFinding {
+                title: candidate.title(),
+                severity: candidate.severity().to_string(),
+                category: CATEGORY.to_string(),
+                line_start: candidate.line,
+                line_end: candidate.line,
+                recommendation: suggestion,
+            })
+        })
+        .collect()
+}
+
+/// Markdown report of a file's dead code findings
+pub fn render(findings: &[StructuredFinding], reviewed: bool) -> String {
+    if findings.is_empty() {
+        return "No dead code found.".to_string();
+    }
+    let mut out = String::new();
+    if !reviewed {
+        out.push_str("_Not reviewed by a model; candidates come from a text search._\n\n");
+    }
+    for finding in findings {
+        let line = finding
+            .line_start
+            .map(|l| format!(" (line {})", l))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "- **{}**{}: {}\n",
+            finding.title, line, finding.recommendation
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::discover_projects;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, path: &str, content: &str) {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn names(candidates: &[Candidate]) -> Vec<&str> {
+        candidates.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_unused_crates() {
+        let repo = TempDir::new().unwrap();
+        write(
+            repo.path(),
+            "Cargo.toml",
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n\
+             [dependencies]\nserde = \"1\"\nserde-json = \"1\"\nanyhow = \"1\"\n\n\
+             [dependencies.regex]\nversion = \"1\"\n\n\
+             [target.'cfg(unix)'.dependencies]\nlibc = \"0.2\"\n",
+        );
+        write(
+            repo.path(),
+            "src/main.rs",
+            "use serde::Serialize;\nfn main() -> anyhow::Result<()> { serde_json::to_string(&1)?; Ok(()) }\n",
+        );
+
+        let candidates = find_candidates(&discover_projects(repo.path()).unwrap());
+        assert_eq!(names(&candidates), ["regex", "libc"]);
+        assert_eq!(candidates[0].kind, CandidateKind::Dependency);
+        assert_eq!(candidates[0].file, repo.path().join("Cargo.toml"));
+        assert_eq!(candidates[0].line, Some(10));
+        assert_eq!(candidates[1].line, Some(14));
+    }
+
+    #[test]
+    fn test_unused_packages_and_exports() {
+        let repo = TempDir::new().unwrap();
+        write(
+            repo.path(),
+            "package.json",
+            r#"{
+  "name": "web",
+  "dependencies": {
+    "lodash": "^4",
+    "@scope/ui": "^1",
+    "left-pad": "^1",
+    "@types/node": "^20"
+  }
+}"#,
+        );
+        write(
+            repo.path(),
+            "src/index.ts",
+            "import { map } from 'lodash/fp';\nimport { Button } from \"@scope/ui/button\";\nimport { used } from './util';\nexport const entry = used;\n",
+        );
+        write(
+            repo.path(),
+            "src/util.ts",
+            "export function used() {}\nexport async function unused() {}\nexport { helper as renamed };\nexport interface Shape {}\nexport * from './other';\n",
+        );
+
+        let candidates = find_candidates(&discover_projects(repo.path()).unwrap());
+        assert_eq!(
+            names(&candidates),
+            ["left-pad", "unused", "renamed", "Shape"]
+        );
+        assert_eq!(candidates[0].line, Some(6));
+        assert_eq!(candidates[1].kind, CandidateKind::Export);
+        assert_eq!(candidates[1].line, Some(2));
+    }
+
+    #[test]
+    fn test_package_name() {
+        assert_eq!(package_name("lodash/fp"), Some("lodash"));
+        assert_eq!(package_name("@scope/pkg/sub"), Some("@scope/pkg"));
+        assert_eq!(package_name("@scope/pkg"), Some("@scope/pkg"));
+        assert_eq!(package_name("node:fs"), Some("fs"));
+        assert_eq!(package_name("./util"), None);
+    }
+
+    #[test]
+    fn test_findings_from_verdicts() {
+        let candidate = |name: &str, kind| Candidate {
+            file: PathBuf::from("/repo/Cargo.toml"),
+            line: Some(3),
+            kind,
+            name: name.to_string(),
+        };
+        let candidates = [
+            candidate("regex", CandidateKind::Dependency),
+            candidate("serde", CandidateKind::Dependency),
+            candidate("helper", CandidateKind::Export),
+        ];
+
+        let unreviewed = findings(&candidates, None);
+        assert_eq!(unreviewed.len(), 3);
+        assert_eq!(unreviewed[0].severity, "warning");
+        assert_eq!(unreviewed[0].category, CATEGORY);
+        assert_eq!(
+            unreviewed[0].recommendation,
+            "Remove `regex` from the manifest"
+        );
+        assert_eq!(unreviewed[2].severity, "info");
+
+        let verdicts = [
+            Verdict {
+                number: 1,
+                unused: true,
+                suggestion: " Delete line 3 ".to_string(),
+            },
+            Verdict {
+                number: 2,
+                unused: false,
+                suggestion: "Used by derives".to_string(),
+            },
+        ];
+        let reviewed = findings(&candidates, Some(&verdicts));
+        let titles: Vec<_> = reviewed.iter().map(|f| f.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            ["Unused dependency `regex`", "Unused export `helper`"]
+        );
+        assert_eq!(reviewed[0].recommendation, "Delete line 3");
+        assert_eq!(reviewed[0].line_end, Some(3));
+
+        assert_eq!(render(&[], true), "No dead code found.");
+        assert!(render(&unreviewed, false).starts_with("_Not reviewed"));
+    }
+}
@@ -0,0 +1,231 @@
+//! Dependency installation for mutation testing sandboxes.
+//!
+//! The temp copy of a JavaScript/TypeScript repository usually lacks
+//! `node_modules` (it is ignored by git and typically listed in `copy_ignore`).
+//! Before baseline verification, dependencies are installed with the project's
+//! package manager in frozen-lockfile mode. Installed trees are cached under the
+//! data directory keyed by the lockfile hash, so unchanged lockfiles restore from
+//! the cache instead of reinstalling on every run.
+
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Name of the installed dependency directory
+const NODE_MODULES: &str = "node_modules";
+
+/// A JavaScript package manager, identified by its lockfile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Npm,
+    Pnpm,
+    Yarn,
+}
+
+impl PackageManager {
+    /// Detect the package manager from the lockfile in `project_root`.
+    pub fn detect(project_root: &Path) -> Option<Self> {
+        [Self::Pnpm, Self::Yarn, Self::Npm]
+            .into_iter()
+            .find(|pm| project_root.join(pm.lockfile()).is_file())
+    }
+
+    /// Lockfile written by this package manager
+    pub fn lockfile(&self) -> &'static str {
+        match self {
+            Self::Npm => "package-lock.json",
+            Self::Pnpm => "pnpm-lock.yaml",
+            Self::Yarn => "yarn.lock",
+        }
+    }
+
+    /// Command that installs exactly the locked dependencies
+    pub fn install_command(&self) -> &'static str {
+        match self {
+            Self::Npm => "npm ci",
+            Self::Pnpm => "pnpm install --frozen-lockfile",
+            Self::Yarn => "yarn install --frozen-lockfile",
+        }
+    }
+}
+
+/// Cache of installed `node_modules` trees keyed by lockfile hash
+#[derive(Debug, Clone)]
+pub struct DependencyCache {
+    dir: PathBuf,
+}
+
+impl DependencyCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Cache key for a project: the package manager and lockfile content hash.
+    pub fn key(project_root: &Path, manager: PackageManager) -> anyhow::Result<String> {
+        let lockfile = project_root.join(manager.lockfile());
+        let content = std::fs::read(&lockfile)
+            .with_context(|| format!("Failed to read {}", lockfile.display()))?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(manager.lockfile().as_bytes());
+        hasher.update(&content);
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Restore a cached `node_modules` into `project_root`.
+    ///
+    /// Returns false when nothing is cached for `key`.
+    pub fn restore(&self, key: &str, project_root: &Path) -> anyhow::Result<bool> {
+        let cached = self.dir.join(key).join(NODE_MODULES);
+        if !cached.is_dir() {
+            return Ok(false);
+        }
+        copy_tree(&cached, &project_root.join(NODE_MODULES))
+            .context("Failed to restore cached dependencies")?;
+        Ok(true)
+    }
+
+    /// Store the freshly installed `node_modules` of `project_root` under `key`.
+    ///
+    /// The tree is copied to a staging directory first and renamed into place, so
+    /// an interrupted copy never leaves a partial cache entry behind.
+    pub fn store(&self, key: &str, project_root: &Path) -> anyhow::Result<()> {
+        let installed = project_root.join(NODE_MODULES);
+        if !installed.is_dir() {
+            return Ok(());
+        }
+
+        let entry = self.dir.join(key);
+        if entry.exists() {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        let staging = tempfile::tempdir_in(&self.dir).context("Failed to create staging dir")?;
+        copy_tree(&installed, &staging.path().join(NODE_MODULES))
+            .context("Failed to cache dependencies")?;
+        std::fs::rename(staging.keep(), &entry)
+            .with_context(|| format!("Failed to move cache entry to {}", entry.display()))?;
+        Ok(())
+    }
+}
+
+/// Copy a directory tree, preserving symlinks (package managers rely on
+/// relative links in `node_modules/.bin` and pnpm's store layout).
+fn copy_tree(src: &Path, dest: &Path) -> anyhow::Result<()> {
+    for entry in walkdir::WalkDir::new(src) {
+        let entry = entry.context("Failed to read directory entry")?;
+        let relative = entry.path().strip_prefix(src)?;
+        let target = dest.join(relative);
+        let file_type = entry.file_type();
+
+        if file_type.is_dir() {
+            std::fs::create_dir_all(&target)
+                .with_context(|| format!("Failed to create {}", target.display()))?;
+        } else if file_type.is_symlink() {
+            let link = std::fs::read_link(entry.path())?;
+            copy_symlink(&link, entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)
+                .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(link: &Path, _source: &Path, target: &Path) -> anyhow::Result<()> {
+    std::os::unix::fs::symlink(link, target)
+        .with_context(|| format!("Failed to create symlink {}", target.display()))
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(_link: &Path, source: &Path, target: &Path) -> anyhow::Result<()> {
+    // Without portable symlinks, copy what the link points to
+    if source.is_dir() {
+        copy_tree(&std::fs::canonicalize(source)?, target)
+    } else {
+        std::fs::copy(source, target)
+            .map(|_| ())
+            .with_context(|| format!("Failed to copy {}", source.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_package_manager() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(PackageManager::detect(dir.path()), None);
+
+        std::fs::write(dir.path().join("package-lock.json"), "{}").unwrap();
+        assert_eq!(
+            PackageManager::detect(dir.path()),
+            Some(PackageManager::Npm)
+        );
+
+        std::fs::write(dir.path().join("pnpm-lock.yaml"), "").unwrap();
+        assert_eq!(
+            PackageManager::detect(dir.path()),
+            Some(PackageManager::Pnpm)
+        );
+        assert_eq!(
+            PackageManager::Pnpm.install_command(),
+            "pnpm install --frozen-lockfile"
+        );
+    }
+
+    #[test]
+    fn test_key_changes_with_lockfile() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("package-lock.json"), "{\"v\": 1}").unwrap();
+        let first = DependencyCache::key(dir.path(), PackageManager::Npm).unwrap();
+        assert_eq!(
+            first,
+            DependencyCache::key(dir.path(), PackageManager::Npm).unwrap()
+        );
+
+        std::fs::write(dir.path().join("package-lock.json"), "{\"v\": 2}").unwrap();
+        assert_ne!(
+            first,
+            DependencyCache::key(dir.path(), PackageManager::Npm).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_store_and_restore() {
+        let cache_dir = TempDir::new().unwrap();
+        let cache = DependencyCache::new(cache_dir.path().join("node_modules"));
+
+        let project = TempDir::new().unwrap();
+        assert!(!cache.restore("abc", project.path()).unwrap());
+
+        let pkg = project.path().join("node_modules").join("left-pad");
+        std::fs::create_dir_all(&pkg).unwrap();
+        std::fs::write(pkg.join("index.js"), "module.exports = 1;").unwrap();
+        #[cfg(unix)]
+        {
+            let bin = project.path().join("node_modules").join(".bin");
+            std::fs::create_dir_all(&bin).unwrap();
+            std::os::unix::fs::symlink("../left-pad/index.js", bin.join("left-pad")).unwrap();
+        }
+        cache.store("abc", project.path()).unwrap();
+
+        let fresh = TempDir::new().unwrap();
+        assert!(cache.restore("abc", fresh.path()).unwrap());
+        let restored = fresh.path().join("node_modules");
+        assert_eq!(
+            std::fs::read_to_string(restored.join("left-pad").join("index.js")).unwrap(),
+            "module.exports = 1;"
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            std::fs::read_link(restored.join(".bin").join("left-pad")).unwrap(),
+            PathBuf::from("../left-pad/index.js")
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Module dependency graphs built from `use crate::` imports.
+//!
+//! Unlike the other diagram types this one needs no LLM: every Rust file is
+//! mapped to its module path (`src/daemon/health.rs` is `daemon::health`), its
+//! `use crate::...` statements are expanded, and each imported path is
+//! resolved to the longest known module it starts with. The resulting edges
+//! are written out as DOT or Mermaid in a stable order, so unchanged code
+//! gives an identical diagram.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Label of a crate's root module (`main.rs` or `lib.rs`)
+const ROOT_MODULE: &str = "crate";
+
+/// Node ids and labels, and edges between node ids, in a stable order
+type Graph = (BTreeMap<String, String>, BTreeSet<(String, String)>);
+
+/// Build a DOT module dependency graph from Rust source files.
+///
+/// Files are grouped into crates by the directory containing their `src`
+/// directory. Returns None if no module imports another.
+pub fn module_dependency_dot<'a>(
+    files: impl IntoIterator<Item = (&'a Path, &'a str)>,
+) -> Option<String> {
+    let (nodes, edges) = module_dependencies(files)?;
+    let mut dot =
+        String::from("digraph ModuleDependencies {\n    rankdir=LR;\n    node [shape=box];\n\n");
+    for (id, label) in &nodes {
+        dot.push_str(&format!("    {} [label=\"{}\"];\n", id, label));
+    }
+    dot.push('\n');
+    for (from, to) in &edges {
+        dot.push_str(&format!("    {} -> {};\n", from, to));
+    }
+    dot.push_str("}\n");
+    Some(dot)
+}
+
+/// Build the module dependency graph as a Mermaid flowchart, see
+/// [`module_dependency_dot`]
+pub fn module_dependency_mermaid<'a>(
+    files: impl IntoIterator<Item = (&'a Path, &'a str)>,
+) -> Option<String> {
+    let (nodes, edges) = module_dependencies(files)?;
+    let mut mermaid = String::from("flowchart LR\n");
+    for (id, label) in &nodes {
+        mermaid.push_str(&format!("    {}[\"{}\"]\n", id, label));
+    }
+    for (from, to) in &edges {
+        mermaid.push_str(&format!("    {} --> {}\n", from, to));
+    }
+    Some(mermaid)
+}
+
+/// The modules importing another and their imports (None without any)
+fn module_dependencies<'a>(files: impl IntoIterator<Item = (&'a Path, &'a str)>) -> Option<Graph> {
+    // crate -> module -> modules it imports
+    let mut crates: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for (path, content) in files {
+        if let Some((crate_name, module)) = module_of(path) {
+            crates
+                .entry(crate_name)
+                .or_default()
+                .insert(module, crate_use_paths(content));
+        }
+    }
+
+    let multiple_crates = crates.len() > 1;
+    let mut nodes: BTreeMap<String, String> = BTreeMap::new();
+    let mut edges: BTreeSet<(String, String)> = BTreeSet::new();
+    for (crate_name, modules) in &crates {
+        let node_id = |module: &str| node_id(crate_name, module);
+        let label = |module: &str| {
+            if multiple_crates {
+                format!("{}::{}", crate_name, module)
+            } else {
+                module.to_string()
+            }
+        };
+
+        for (module, imports) in modules {
+            for import in imports {
+                let Some(target) = resolve(import, modules) else {
+                    continue;
+                };
+                if target == *module {
+                    continue;
+                }
+                nodes.insert(node_id(module), label(module));
+                nodes.insert(node_id(&target), label(&target));
+                edges.insert((node_id(module), node_id(&target)));
+            }
+        }
+    }
+
+    (!edges.is_empty()).then_some((nodes, edges))
+}
+
+/// Crate and module path of a Rust file, from the path below its `src`
+/// directory (None for files outside one)
+fn module_of(path: &Path) -> Option<(String, String)> {
+    if path.extension().is_none_or(|ext| ext != "rs") {
+        return None;
+    }
+    let components: Vec<&str> = path.iter().filter_map(|c| c.to_str()).collect();
+    let src = components.iter().rposition(|c| *c == "src")?;
+    let crate_name = match src {
+        0 => ROOT_MODULE.to_string(),
+        _ => components[src - 1].to_string(),
+    };
+
+    let mut segments: Vec<&str> = components[src + 1..].to_vec();
+    let file = segments.pop()?.trim_end_matches(".rs");
+    let at_root = segments.is_empty();
+    if !(file == "mod" || (at_root && (file == "main" || file == "lib"))) {
+        segments.push(file);
+    }
+
+    let module = if segments.is_empty() {
+        ROOT_MODULE.to_string()
+    } else {
+        segments.join("::")
+    };
+    Some((crate_name, module))
+}
+
+/// The module an imported path belongs to: the longest known module it
+/// starts with, or the crate root for items defined there
+fn resolve(import: &str, modules: &BTreeMap<String, Vec<String>>) -> Option<String> {
+    let segments: Vec<&str> = import.split("::").skip(1).collect();
+    (1..=segments.len())
+        .rev()
+        .map(|len| segments[..len].join("::"))
+        .find(|module| modules.contains_key(module))
+        .or_else(|| {
+            modules
+                .contains_key(ROOT_MODULE)
+                .then(|| ROOT_MODULE.to_string())
+        })
+}
+
+/// Every path imported by a `use crate::...` statement, with groups expanded
+/// (`use crate::{a, b::c};` gives `crate::a` and `crate::b::c`)
+pub fn crate_use_paths(content: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let statement = strip_visibility(line.trim());
+        if !statement.starts_with("use crate::") {
+            continue;
+        }
+
+        // Statements may span lines until the semicolon
+        let mut tree = statement.trim_start_matches("use ").to_string();
+        while !tree.contains(';') {
+            match lines.next() {
+                Some(next) => tree.push_str(next.trim()),
+                None => break,
+            }
+        }
+        let tree = tree.split(';').next().unwrap_or_default();
+        paths.extend(expand_use_tree(tree));
+    }
+    paths
+}
+
+fn strip_visibility(statement: &str) -> &str {
+    if let Some(rest) = statement.strip_prefix("pub(") {
+        return rest
+            .split_once(')')
+            .map_or(statement, |(_, rest)| rest.trim_start());
+    }
+    statement.strip_prefix("pub ").unwrap_or(statement)
+}
+
+/// Expand a use tree into plain paths, dropping aliases, `self` and globs
+fn expand_use_tree(tree: &str) -> Vec<String> {
+    let tree = tree.trim();
+    let Some(open) = tree.find('{') else {
+        let path = tree.split(" as ").next().unwrap_or(tree).trim();
+        let path = path.trim_end_matches("::*").trim_end_matches("::self");
+        return vec![path.to_string()];
+    };
+
+    let prefix = tree[..open].trim_end_matches("::");
+    let inner = &tree[open + 1..tree.rfind('}').unwrap_or(tree.len())];
+
+    let mut paths = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                paths.extend(expand_group_item(prefix, &inner[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    paths.extend(expand_group_item(prefix, &inner[start..]));
+    paths
+}
+
+fn expand_group_item(prefix: &str, item: &str) -> Vec<String> {
+    let item = item.trim();
+    match item {
+        "" => Vec::new(),
+        "self" | "*" => vec![prefix.to_string()],
+        _ => expand_use_tree(&format!("{}::{}", prefix, item)),
+    }
+}
+
+/// A DOT (and Mermaid) identifier for a module
+fn node_id(crate_name: &str, module: &str) -> String {
+    format!("{}__{}", crate_name, module)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::{render_dot_to_svg, validate_mermaid_syntax, DiagramType};
+
+    #[test]
+    fn test_module_of() {
+        let module = |path: &str| module_of(Path::new(path)).map(|(_, m)| m);
+        assert_eq!(module("/repo/src/main.rs").as_deref(), Some("crate"));
+        assert_eq!(module("/repo/src/daemon/mod.rs").as_deref(), Some("daemon"));
+        assert_eq!(
+            module("/repo/src/daemon/health.rs").as_deref(),
+            Some("daemon::health")
+        );
+        assert_eq!(
+            module("/repo/src/bin/main.rs").as_deref(),
+            Some("bin::main")
+        );
+        assert_eq!(module("/repo/build.rs"), None);
+        assert_eq!(
+            module_of(Path::new("/repo/core/src/lib.rs")).map(|(c, _)| c),
+            Some("core".to_string())
+        );
+    }
+
+    #[test]
+    fn test_crate_use_paths_expands_groups() {
+        let content = r#"
+use std::sync::Arc;
+use crate::config::Config;
+pub(crate) use crate::db::{self, models::{Repository, Embedding as E}};
+use crate::analyzer::{
+    OllamaClient,
+    TokenUsage,
+};
+pub use crate::web::*;
+"#;
+        assert_eq!(
+            crate_use_paths(content),
+            vec![
+                "crate::config::Config",
+                "crate::db",
+                "crate::db::models::Repository",
+                "crate::db::models::Embedding",
+                "crate::analyzer::OllamaClient",
+                "crate::analyzer::TokenUsage",
+                "crate::web",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_module_dependency_dot() {
+        let files = [
+            ("/repo/src/main.rs", "mod daemon;\nuse crate::daemon::Daemon;"),
+            (
+                "/repo/src/daemon/mod.rs",
+                "use crate::daemon::health::HealthMonitor;\nuse crate::db::Database;\nuse crate::Cli;",
+            ),
+            ("/repo/src/daemon/health.rs", "use crate::daemon::Status;"),
+            ("/repo/src/db/mod.rs", "use std::path::Path;"),
+        ];
+        let dot = module_dependency_dot(files.iter().map(|(p, c)| (Path::new(*p), *c))).unwrap();
+
+        assert!(dot.contains("repo__crate -> repo__daemon;"));
+        assert!(dot.contains("repo__daemon -> repo__daemon__health;"));
+        assert!(dot.contains("repo__daemon -> repo__db;"));
+        assert!(dot.contains("repo__daemon -> repo__crate;"));
+        assert!(dot.contains("repo__daemon__health -> repo__daemon;"));
+        assert!(dot.contains("[label=\"daemon::health\"]"));
+        assert!(render_dot_to_svg(&dot).is_ok());
+
+        // Deterministic output
+        assert_eq!(
+            module_dependency_dot(files.iter().rev().map(|(p, c)| (Path::new(*p), *c))),
+            Some(dot)
+        );
+    }
+
+    #[test]
+    fn test_module_dependency_dot_without_imports() {
+        let files = [("/repo/src/lib.rs", "pub fn answer() -> u32 { 42 }")];
+        assert_eq!(
+            module_dependency_dot(files.iter().map(|(p, c)| (Path::new(*p), *c))),
+            None
+        );
+        assert_eq!(
+            module_dependency_mermaid(files.iter().map(|(p, c)| (Path::new(*p), *c))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_module_dependency_mermaid() {
+        let files = [
+            ("/repo/src/main.rs", "mod db;\nuse crate::db::Database;"),
+            ("/repo/src/db/mod.rs", "use std::path::Path;"),
+        ];
+        let mermaid =
+            module_dependency_mermaid(files.iter().map(|(p, c)| (Path::new(*p), *c))).unwrap();
+        assert_eq!(
+            mermaid,
+            "flowchart LR\n    repo__crate[\"crate\"]\n    repo__db[\"db\"]\n    repo__crate --> repo__db\n"
+        );
+        assert!(validate_mermaid_syntax(&mermaid, DiagramType::ModuleDependencyGraph).is_ok());
+    }
+}
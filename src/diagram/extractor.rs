@@ -15,7 +15,8 @@ use crate::language::Language;
 pub struct DiagramExtractor;
 
 impl DiagramExtractor {
-    /// Get the extraction prompt for a specific diagram type.
+    /// Get the extraction prompt for a specific diagram type, or None for
+    /// types that aren't extracted (see [`DiagramType::is_extracted`]).
     ///
     /// Delegates to language-specific prompt generation.
     pub fn prompt_for_type(
@@ -23,14 +24,24 @@ impl DiagramExtractor {
         file_path: &str,
         code: &str,
         language: Language,
-    ) -> String {
-        match diagram_type {
+        output_language: &str,
+    ) -> Option<String> {
+        let prompt = match diagram_type {
             DiagramType::SystemArchitecture => {
-                language.diagram_architecture_prompt(file_path, code)
+                language.diagram_architecture_prompt(file_path, code, output_language)
+            }
+            DiagramType::DataFlow => {
+                language.diagram_data_flow_prompt(file_path, code, output_language)
+            }
+            DiagramType::DatabaseSchema => {
+                language.diagram_database_schema_prompt(file_path, code, output_language)
+            }
+            DiagramType::SequenceDiagram => {
+                language.diagram_sequence_prompt(file_path, code, output_language)
             }
-            DiagramType::DataFlow => language.diagram_data_flow_prompt(file_path, code),
-            DiagramType::DatabaseSchema => language.diagram_database_schema_prompt(file_path, code),
-        }
+            DiagramType::ModuleDependencyGraph => return None,
+        };
+        Some(prompt)
     }
 
     /// Prompt for architecture-focused file analysis (used for Architecture tab).
@@ -40,8 +51,9 @@ impl DiagramExtractor {
         file_path: &str,
         code: &str,
         language: Language,
+        output_language: &str,
     ) -> String {
-        language.architecture_file_analysis_prompt(file_path, code)
+        language.architecture_file_analysis_prompt(file_path, code, output_language)
     }
 
     /// Prompt for analyzing documentation and context files (READMEs, Cargo.toml, etc.).
@@ -51,8 +63,9 @@ impl DiagramExtractor {
         file_path: &str,
         content: &str,
         language: Language,
+        output_language: &str,
     ) -> String {
-        language.documentation_prompt(file_path, content)
+        language.documentation_prompt(file_path, content, output_language)
     }
 }
 
@@ -67,24 +80,45 @@ mod tests {
             "test.rs",
             "code",
             Language::Rust,
+            "English",
         );
-        assert!(arch_prompt.contains("ARCHITECTURAL"));
+        assert!(arch_prompt.unwrap().contains("ARCHITECTURAL"));
 
         let flow_prompt = DiagramExtractor::prompt_for_type(
             DiagramType::DataFlow,
             "test.rs",
             "code",
             Language::Rust,
+            "English",
         );
-        assert!(flow_prompt.contains("DATA FLOW"));
+        assert!(flow_prompt.unwrap().contains("DATA FLOW"));
 
         let db_prompt = DiagramExtractor::prompt_for_type(
             DiagramType::DatabaseSchema,
             "test.rs",
             "code",
             Language::Rust,
+            "English",
         );
-        assert!(db_prompt.contains("DATABASE"));
+        assert!(db_prompt.unwrap().contains("DATABASE"));
+
+        let sequence_prompt = DiagramExtractor::prompt_for_type(
+            DiagramType::SequenceDiagram,
+            "test.rs",
+            "code",
+            Language::Rust,
+            "English",
+        );
+        assert!(sequence_prompt.unwrap().contains("INTERACTION SEQUENCES"));
+
+        assert!(DiagramExtractor::prompt_for_type(
+            DiagramType::ModuleDependencyGraph,
+            "test.rs",
+            "code",
+            Language::Rust,
+            "English",
+        )
+        .is_none());
     }
 
     #[test]
@@ -94,16 +128,18 @@ mod tests {
             "test.ts",
             "code",
             Language::TypeScript,
+            "English",
         );
-        assert!(arch_prompt.contains("ARCHITECTURAL"));
+        assert!(arch_prompt.unwrap().contains("ARCHITECTURAL"));
 
         let flow_prompt = DiagramExtractor::prompt_for_type(
             DiagramType::DataFlow,
             "test.ts",
             "code",
             Language::TypeScript,
+            "English",
         );
-        assert!(flow_prompt.contains("DATA FLOW"));
+        assert!(flow_prompt.unwrap().contains("DATA FLOW"));
     }
 
     #[test]
@@ -112,6 +148,7 @@ mod tests {
             "src/web/mod.rs",
             "pub mod handlers;",
             Language::Rust,
+            "English",
         );
         assert!(prompt.contains("ARCHITECTURAL"));
         assert!(prompt.contains("Layer"));
@@ -124,7 +161,9 @@ mod tests {
             "README.md",
             "# My Project",
             Language::Rust,
+            "German",
         );
         assert!(prompt.contains("README.md"));
+        assert!(prompt.contains("Respond only in German"));
     }
 }
@@ -9,13 +9,15 @@ use super::DiagramType;
 pub struct DiagramGenerator;
 
 impl DiagramGenerator {
-    /// Get the generation prompt for a specific diagram type
+    /// Get the generation prompt for a specific diagram type, or None for
+    /// types built without an LLM
     pub fn prompt_for_type(
         diagram_type: DiagramType,
         repo_name: &str,
         extractions: &str,
-    ) -> String {
-        match diagram_type {
+        output_language: &str,
+    ) -> Option<String> {
+        let prompt = match diagram_type {
             DiagramType::SystemArchitecture => {
                 Self::architecture_diagram_prompt(repo_name, extractions)
             }
@@ -23,7 +25,13 @@ impl DiagramGenerator {
             DiagramType::DatabaseSchema => {
                 Self::database_schema_diagram_prompt(repo_name, extractions)
             }
-        }
+            DiagramType::SequenceDiagram => Self::sequence_diagram_prompt(repo_name, extractions),
+            DiagramType::ModuleDependencyGraph => return None,
+        };
+        Some(format!(
+            "{}\n\nWrite the labels in {}; keep node names as they are.",
+            prompt, output_language
+        ))
     }
 
     /// Generate a system architecture DOT diagram
@@ -199,6 +207,65 @@ digraph Schema {{
 }}
 ```
 
+Output ONLY valid DOT code. No markdown code fences. No explanations."#,
+            repo_name, extractions
+        )
+    }
+
+    /// Generate a sequence DOT diagram
+    pub fn sequence_diagram_prompt(repo_name: &str, extractions: &str) -> String {
+        format!(
+            r#"Generate a GraphViz DOT diagram showing the key interaction sequences in '{}'.
+
+Based on these file analyses:
+{}
+
+Pick the 1-3 most important flows (e.g. handling a request, a scheduled job, startup) and create a
+DOT digraph showing:
+- Each participating component (caller, callee, database, external service) as a node
+- One edge per call, labeled with a step number and the action, in the order the calls happen
+- A cluster per flow when there is more than one
+
+DOT syntax reference:
+- Graph: `digraph Sequence {{ rankdir=LR; ... }}`
+- Nodes: `node_name [label="Label"];`
+- Numbered edges: `source -> target [label="1. action"];`
+- Clusters: `subgraph cluster_flow {{ label="Flow name"; ... }}`
+
+Example structure:
+```
+digraph Sequence {{
+    rankdir=LR;
+    node [shape=box];
+
+    subgraph cluster_add_repository {{
+        label="Add repository";
+        browser [label="Browser"];
+        handler [label="add_repository handler"];
+        database [label="Database"];
+        daemon [label="Daemon"];
+    }}
+
+    browser -> handler [label="1. POST /repositories"];
+    handler -> database [label="2. insert repository"];
+    handler -> daemon [label="3. trigger scan"];
+    handler -> browser [label="4. 201 Created"];
+}}
+```
+
+Rules:
+1. Use snake_case for node names
+2. Number the steps of each flow starting at 1, in call order
+3. Show calls between components, not every helper function
+4. Prefix cluster names with "cluster_" for proper rendering
+
+If no significant flows are found in the extractions, output:
+```
+digraph Sequence {{
+    no_flows [label="No significant flows detected"];
+}}
+```
+
 Output ONLY valid DOT code. No markdown code fences. No explanations."#,
             repo_name, extractions
         )
@@ -266,6 +333,13 @@ mod tests {
         assert!(prompt.contains("FK"));
     }
 
+    #[test]
+    fn test_sequence_diagram_prompt_numbers_steps() {
+        let prompt = DiagramGenerator::sequence_diagram_prompt("test", "extractions");
+        assert!(prompt.contains("step number"));
+        assert!(prompt.contains("1. POST /repositories"));
+    }
+
     #[test]
     fn test_fix_dot_prompt_contains_error() {
         let prompt = DiagramGenerator::fix_dot_prompt("digraph { broken", "Unbalanced braces");
@@ -280,15 +354,40 @@ mod tests {
             DiagramType::SystemArchitecture,
             "repo",
             "extractions",
+            "English",
+        );
+        assert!(arch_prompt.unwrap().contains("system architecture"));
+
+        let flow_prompt = DiagramGenerator::prompt_for_type(
+            DiagramType::DataFlow,
+            "repo",
+            "extractions",
+            "English",
+        );
+        assert!(flow_prompt.unwrap().contains("data flow"));
+
+        let db_prompt = DiagramGenerator::prompt_for_type(
+            DiagramType::DatabaseSchema,
+            "repo",
+            "extractions",
+            "English",
         );
-        assert!(arch_prompt.contains("system architecture"));
+        assert!(db_prompt.unwrap().contains("database schema"));
 
-        let flow_prompt =
-            DiagramGenerator::prompt_for_type(DiagramType::DataFlow, "repo", "extractions");
-        assert!(flow_prompt.contains("data flow"));
+        let sequence_prompt = DiagramGenerator::prompt_for_type(
+            DiagramType::SequenceDiagram,
+            "repo",
+            "extractions",
+            "English",
+        );
+        assert!(sequence_prompt.unwrap().contains("interaction sequences"));
 
-        let db_prompt =
-            DiagramGenerator::prompt_for_type(DiagramType::DatabaseSchema, "repo", "extractions");
-        assert!(db_prompt.contains("database schema"));
+        assert!(DiagramGenerator::prompt_for_type(
+            DiagramType::ModuleDependencyGraph,
+            "repo",
+            "extractions",
+            "English"
+        )
+        .is_none());
     }
 }
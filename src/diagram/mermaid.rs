@@ -0,0 +1,594 @@
+//! Mermaid diagram generation prompts and validation.
+//!
+//! With `diagram.format = "mermaid"` the aggregation phase asks for Mermaid
+//! instead of DOT. Mermaid is rendered in the browser, so there is no parser
+//! on this side to validate with; [`validate_mermaid_syntax`] checks the
+//! structure LLMs most often get wrong (the header, unbalanced brackets and
+//! quotes, unclosed blocks) rather than the full grammar.
+
+use super::DiagramType;
+
+/// Provides prompts for generating Mermaid diagrams from aggregated extractions
+pub struct MermaidGenerator;
+
+impl MermaidGenerator {
+    /// Get the generation prompt for a specific diagram type, or None for
+    /// types built without an LLM
+    pub fn prompt_for_type(
+        diagram_type: DiagramType,
+        repo_name: &str,
+        extractions: &str,
+        output_language: &str,
+    ) -> Option<String> {
+        let prompt = match diagram_type {
+            DiagramType::SystemArchitecture => {
+                Self::architecture_diagram_prompt(repo_name, extractions)
+            }
+            DiagramType::DataFlow => Self::data_flow_diagram_prompt(repo_name, extractions),
+            DiagramType::DatabaseSchema => {
+                Self::database_schema_diagram_prompt(repo_name, extractions)
+            }
+            DiagramType::SequenceDiagram => Self::sequence_diagram_prompt(repo_name, extractions),
+            DiagramType::ModuleDependencyGraph => return None,
+        };
+        Some(format!(
+            "{}\n\nWrite the labels in {}; keep node names as they are.",
+            prompt, output_language
+        ))
+    }
+
+    /// Generate a system architecture Mermaid flowchart
+    pub fn architecture_diagram_prompt(repo_name: &str, extractions: &str) -> String {
+        format!(
+            r#"Generate a Mermaid flowchart showing the system architecture of '{}'.
+
+Based on these file analyses:
+{}
+
+Create a flowchart that shows:
+- Main modules/components as labeled nodes
+- Dependencies between modules as labeled arrows
+- Related modules grouped into subgraphs
+- External dependencies as separate nodes
+
+Example structure:
+```
+flowchart TB
+    subgraph web["Web Layer"]
+        handlers["HTTP Handlers"]
+        templates["Templates"]
+    end
+
+    subgraph db["Database Layer"]
+        models["Models"]
+        queries["Queries"]
+    end
+
+    handlers -->|executes| queries
+    templates -->|renders| handlers
+```
+
+Rules:
+1. Start with `flowchart TB`
+2. Use snake_case node ids (no spaces, no special chars except underscore)
+3. Put every label in double quotes: `node_id["Label"]`
+4. Close every `subgraph` with `end`, and never use `end` as a node id
+5. Keep the diagram focused - show major components, not every file
+
+Output ONLY valid Mermaid code. No markdown code fences. No explanations."#,
+            repo_name, extractions
+        )
+    }
+
+    /// Generate a data flow Mermaid flowchart
+    pub fn data_flow_diagram_prompt(repo_name: &str, extractions: &str) -> String {
+        format!(
+            r#"Generate a Mermaid flowchart showing data flow in '{}'.
+
+Based on these file analyses:
+{}
+
+Create a left-to-right flowchart showing:
+- Data sources on the left (users, external APIs, files, etc.)
+- Processing stages in the middle
+- Data sinks on the right (databases, responses, files, etc.)
+- Arrows showing data movement, labeled with the data
+
+Example structure:
+```
+flowchart LR
+    subgraph sources["Data Sources"]
+        user["User Request"]
+        config["Config Files"]
+    end
+
+    subgraph processing["Processing"]
+        validation["Validation"]
+        transform["Transform"]
+    end
+
+    subgraph sinks["Data Sinks"]
+        database[("Database")]
+        response["HTTP Response"]
+    end
+
+    user -->|JSON payload| validation
+    validation -->|validated data| transform
+    transform -->|model objects| database
+    transform -->|JSON response| response
+```
+
+Rules:
+1. Start with `flowchart LR`
+2. Use snake_case node ids and put every label in double quotes
+3. Label arrows with what data flows through them: `a -->|data| b`
+4. Close every `subgraph` with `end`, and never use `end` as a node id
+5. Show the main data paths, not every detail
+
+Output ONLY valid Mermaid code. No markdown code fences. No explanations."#,
+            repo_name, extractions
+        )
+    }
+
+    /// Generate a database schema Mermaid ER diagram
+    pub fn database_schema_diagram_prompt(repo_name: &str, extractions: &str) -> String {
+        format!(
+            r#"Generate a Mermaid entity relationship diagram showing the database schema for '{}'.
+
+Based on these file analyses:
+{}
+
+Create an erDiagram showing:
+- Each database table as an entity
+- Key columns with their types inside each entity
+- Foreign key relationships between entities
+
+Example structure:
+```
+erDiagram
+    users {{
+        INTEGER id PK
+        TEXT name
+        TEXT email
+        TIMESTAMP created_at
+    }}
+    posts {{
+        INTEGER id PK
+        INTEGER user_id FK
+        TEXT title
+    }}
+    users ||--o{{ posts : "writes"
+```
+
+Rules:
+1. Start with `erDiagram`
+2. Use the actual table names from the codebase
+3. Write each column as `TYPE name`, followed by PK or FK for keys
+4. Show only the most important columns (5-7 max per table)
+5. Quote relationship labels: `a ||--o{{ b : "label"`
+
+If no database tables are found in the extractions, output:
+```
+erDiagram
+    NO_SCHEMA_DETECTED
+```
+
+Output ONLY valid Mermaid code. No markdown code fences. No explanations."#,
+            repo_name, extractions
+        )
+    }
+
+    /// Generate a Mermaid sequence diagram
+    pub fn sequence_diagram_prompt(repo_name: &str, extractions: &str) -> String {
+        format!(
+            r#"Generate a Mermaid sequence diagram showing the key interaction sequences in '{}'.
+
+Based on these file analyses:
+{}
+
+Pick the 1-3 most important flows (e.g. handling a request, a scheduled job, startup) and create a
+sequenceDiagram showing:
+- Each participating component (caller, callee, database, external service) as a participant
+- One message per call, in the order the calls happen
+- A `rect` block per flow when there is more than one, with a note naming the flow
+
+Example structure:
+```
+sequenceDiagram
+    participant browser as Browser
+    participant handler as add_repository handler
+    participant database as Database
+    participant daemon as Daemon
+
+    browser->>handler: POST /repositories
+    handler->>database: insert repository
+    handler->>daemon: trigger scan
+    handler-->>browser: 201 Created
+```
+
+Rules:
+1. Start with `sequenceDiagram`
+2. Declare participants with snake_case ids: `participant id as Label`
+3. Use `->>` for calls and `-->>` for responses
+4. Close every `loop`, `alt`, `opt`, `par` and `rect` block with `end`
+5. Show calls between components, not every helper function
+
+If no significant flows are found in the extractions, output:
+```
+sequenceDiagram
+    participant system as System
+    Note over system: No significant flows detected
+```
+
+Output ONLY valid Mermaid code. No markdown code fences. No explanations."#,
+            repo_name, extractions
+        )
+    }
+
+    /// Prompt to fix invalid Mermaid syntax
+    pub fn fix_mermaid_prompt(mermaid_code: &str, error_message: &str) -> String {
+        format!(
+            r#"The following Mermaid diagram has a syntax error:
+
+{}
+
+Error: {}
+
+Fix the Mermaid syntax error and output the corrected diagram.
+
+Common fixes:
+- Keep the diagram type on the first line (`flowchart TB`, `erDiagram`, `sequenceDiagram`)
+- Ensure all brackets [ ] ( ) {{ }} and double quotes are balanced
+- Put labels containing spaces or punctuation in double quotes
+- Close every `subgraph`, `loop`, `alt`, `opt`, `par` and `rect` block with `end`
+- Flowchart arrows are --> (not ->)
+- Use snake_case node ids, and never `end` as a node id
+
+Output ONLY the corrected Mermaid code. No markdown code fences. No explanations."#,
+            mermaid_code, error_message
+        )
+    }
+}
+
+/// Clean up Mermaid code from LLM output.
+/// Removes markdown code fences.
+pub fn clean_mermaid_output(raw_output: &str) -> String {
+    let mut result = raw_output.trim();
+    for prefix in ["```mermaid", "```"] {
+        if let Some(stripped) = result.strip_prefix(prefix) {
+            result = stripped;
+            break;
+        }
+    }
+    result
+        .strip_suffix("```")
+        .unwrap_or(result)
+        .trim()
+        .to_string()
+}
+
+/// Check Mermaid code for `diagram_type`.
+/// Returns Ok(()) if it looks valid, or Err with a descriptive error message.
+pub fn validate_mermaid_syntax(
+    mermaid_code: &str,
+    diagram_type: DiagramType,
+) -> Result<(), String> {
+    let mut lines = mermaid_code
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with("%%"));
+
+    let Some((_, header)) = lines.next() else {
+        return Err("Mermaid diagram is empty".to_string());
+    };
+    let kind = expected_kind(diagram_type);
+    if !kind.matches(header) {
+        return Err(format!(
+            "Mermaid syntax error: the diagram must start with {}, found '{}'",
+            kind.header(),
+            header
+        ));
+    }
+
+    let mut blocks: Vec<(usize, &str)> = Vec::new();
+    let mut open_entities = 0;
+    for (line_number, line) in lines {
+        let keyword = line.split_whitespace().next().unwrap_or("");
+        if keyword == "end" {
+            if blocks.pop().is_none() {
+                return Err(format!(
+                    "Mermaid syntax error: 'end' without an open block on line {}",
+                    line_number
+                ));
+            }
+            continue;
+        }
+        if kind.opens_block(keyword) {
+            blocks.push((line_number, keyword));
+        }
+        if kind == Kind::Er {
+            // Entities' braces span lines, and relationships use them in
+            // their cardinality (`||--o{`), so they are matched by line
+            if line == "}" {
+                if open_entities == 0 {
+                    return Err(format!(
+                        "Mermaid syntax error: unexpected '}}' on line {}",
+                        line_number
+                    ));
+                }
+                open_entities -= 1;
+                continue;
+            }
+            if line.ends_with('{') {
+                open_entities += 1;
+            }
+        }
+        check_line(line, line_number, kind)?;
+    }
+
+    if let Some((line_number, keyword)) = blocks.pop() {
+        return Err(format!(
+            "Mermaid syntax error: '{}' on line {} is never closed with 'end'",
+            keyword, line_number
+        ));
+    }
+    if open_entities > 0 {
+        return Err("Mermaid syntax error: an entity's '{' is never closed".to_string());
+    }
+    Ok(())
+}
+
+/// The kinds of Mermaid diagram used
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Flowchart,
+    Er,
+    Sequence,
+}
+
+/// The kind of diagram `diagram_type` is drawn as
+fn expected_kind(diagram_type: DiagramType) -> Kind {
+    match diagram_type {
+        DiagramType::SystemArchitecture
+        | DiagramType::DataFlow
+        | DiagramType::ModuleDependencyGraph => Kind::Flowchart,
+        DiagramType::DatabaseSchema => Kind::Er,
+        DiagramType::SequenceDiagram => Kind::Sequence,
+    }
+}
+
+impl Kind {
+    fn header(self) -> &'static str {
+        match self {
+            Kind::Flowchart => "'flowchart' and a direction",
+            Kind::Er => "'erDiagram'",
+            Kind::Sequence => "'sequenceDiagram'",
+        }
+    }
+
+    fn matches(self, header: &str) -> bool {
+        let mut words = header.split_whitespace();
+        let keyword = words.next().unwrap_or("");
+        match self {
+            Kind::Flowchart => {
+                matches!(keyword, "flowchart" | "graph")
+                    && words
+                        .next()
+                        .is_none_or(|d| matches!(d, "TB" | "TD" | "BT" | "RL" | "LR"))
+            }
+            Kind::Er => keyword == "erDiagram",
+            Kind::Sequence => keyword == "sequenceDiagram",
+        }
+    }
+
+    /// Whether a line starting with `keyword` opens a block closed by `end`
+    fn opens_block(self, keyword: &str) -> bool {
+        match self {
+            Kind::Flowchart => keyword == "subgraph",
+            Kind::Er => false,
+            Kind::Sequence => matches!(
+                keyword,
+                "loop" | "alt" | "opt" | "par" | "critical" | "break" | "rect"
+            ),
+        }
+    }
+}
+
+/// Check that a line's quotes and brackets are balanced (but for ER braces),
+/// and that flowcharts don't use DOT's `->`
+fn check_line(line: &str, line_number: usize, kind: Kind) -> Result<(), String> {
+    let error = |message: &str| {
+        Err(format!(
+            "Mermaid syntax error: {} on line {}: {}",
+            message, line_number, line
+        ))
+    };
+
+    // Message and relationship text after ':' is free-form
+    let line = match kind {
+        Kind::Sequence | Kind::Er => line.split_once(':').map_or(line, |(code, _)| code),
+        Kind::Flowchart => line,
+    };
+
+    let mut stack = Vec::new();
+    let mut in_quote = false;
+    let mut in_edge_label = false;
+    let mut previous = [' ', ' '];
+    for c in line.chars() {
+        if in_quote {
+            in_quote = c != '"';
+        } else if c == '"' {
+            in_quote = true;
+        } else if kind == Kind::Flowchart && c == '|' {
+            in_edge_label = !in_edge_label;
+        } else if in_edge_label {
+            // Arrow labels (`-->|text|`) are free-form
+        } else if kind == Kind::Er && matches!(c, '{' | '}') {
+            // Matched by line in validate_mermaid_syntax
+        } else if let Some(open) = match c {
+            ')' => Some('('),
+            ']' => Some('['),
+            '}' => Some('{'),
+            _ => None,
+        } {
+            if stack.pop() != Some(open) {
+                return error(&format!("unexpected '{}'", c));
+            }
+        } else if matches!(c, '(' | '[' | '{') {
+            stack.push(c);
+        } else if kind == Kind::Flowchart
+            && c == '>'
+            && previous[1] == '-'
+            && !matches!(previous[0], '-' | '.' | '=')
+        {
+            return error("'->' is not a flowchart arrow, use '-->'");
+        }
+        previous = [previous[1], c];
+    }
+
+    if in_quote {
+        return error("unterminated quote");
+    }
+    if in_edge_label {
+        return error("unterminated arrow label");
+    }
+    match stack.last() {
+        Some(open) => error(&format!("unclosed '{}'", open)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_for_type_dispatches_correctly() {
+        let arch = MermaidGenerator::prompt_for_type(
+            DiagramType::SystemArchitecture,
+            "repo",
+            "x",
+            "English",
+        )
+        .unwrap();
+        assert!(arch.contains("flowchart TB"));
+        let flow = MermaidGenerator::prompt_for_type(DiagramType::DataFlow, "repo", "x", "English")
+            .unwrap();
+        assert!(flow.contains("flowchart LR"));
+        let schema =
+            MermaidGenerator::prompt_for_type(DiagramType::DatabaseSchema, "repo", "x", "English")
+                .unwrap();
+        assert!(schema.contains("erDiagram"));
+        let sequence =
+            MermaidGenerator::prompt_for_type(DiagramType::SequenceDiagram, "repo", "x", "English")
+                .unwrap();
+        assert!(sequence.contains("sequenceDiagram"));
+        assert!(MermaidGenerator::prompt_for_type(
+            DiagramType::ModuleDependencyGraph,
+            "repo",
+            "x",
+            "English"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_prompt_examples_are_valid() {
+        // The examples the prompts give must pass validation themselves
+        for diagram_type in DiagramType::all() {
+            let Some(prompt) =
+                MermaidGenerator::prompt_for_type(*diagram_type, "repo", "x", "English")
+            else {
+                continue;
+            };
+            for example in prompt.split("```\n").skip(1).step_by(2) {
+                assert_eq!(
+                    validate_mermaid_syntax(example, *diagram_type),
+                    Ok(()),
+                    "{}",
+                    example
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fix_mermaid_prompt_contains_error() {
+        let prompt = MermaidGenerator::fix_mermaid_prompt("flowchart TB\n a[", "unclosed '['");
+        assert!(prompt.contains("flowchart TB\n a["));
+        assert!(prompt.contains("unclosed '['"));
+    }
+
+    #[test]
+    fn test_clean_mermaid_output() {
+        assert_eq!(
+            clean_mermaid_output("```mermaid\nflowchart LR\n  a --> b\n```"),
+            "flowchart LR\n  a --> b"
+        );
+        assert_eq!(clean_mermaid_output("```\nerDiagram\n```"), "erDiagram");
+        assert_eq!(
+            clean_mermaid_output("  sequenceDiagram  "),
+            "sequenceDiagram"
+        );
+    }
+
+    #[test]
+    fn test_validate_mermaid_syntax_valid() {
+        let flowchart = "%% generated\nflowchart LR\n    a[\"A (main)\"] -->|calls (twice)| b((B))\n    b -.-> c{C}\n    c <--> d[(D)]\n";
+        assert_eq!(
+            validate_mermaid_syntax(flowchart, DiagramType::DataFlow),
+            Ok(())
+        );
+
+        let er = "erDiagram\n    users {\n        INTEGER id PK\n    }\n    users ||--o{ posts : \"writes (many)\"\n";
+        assert_eq!(
+            validate_mermaid_syntax(er, DiagramType::DatabaseSchema),
+            Ok(())
+        );
+
+        let sequence = "sequenceDiagram\n    a->>b: GET /x (json\n    loop every minute\n        b-->>a: ok\n    end\n";
+        assert_eq!(
+            validate_mermaid_syntax(sequence, DiagramType::SequenceDiagram),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_validate_mermaid_syntax_invalid() {
+        let check =
+            |code: &str, diagram_type| validate_mermaid_syntax(code, diagram_type).unwrap_err();
+
+        assert!(check("", DiagramType::DataFlow).contains("empty"));
+        assert!(check("%% only a comment", DiagramType::DataFlow).contains("empty"));
+        assert!(check("digraph { a -> b }", DiagramType::DataFlow).contains("must start with"));
+        assert!(
+            check("flowchart LR\n a --> b", DiagramType::SequenceDiagram)
+                .contains("sequenceDiagram")
+        );
+        assert!(check("flowchart sideways", DiagramType::DataFlow).contains("must start with"));
+        assert!(check("flowchart LR\n a[A --> b", DiagramType::DataFlow).contains("unclosed '['"));
+        assert!(check("flowchart LR\n a[\"A] --> b", DiagramType::DataFlow).contains("quote"));
+        assert!(check("flowchart LR\n a -> b", DiagramType::DataFlow).contains("'->'"));
+        assert!(
+            check("flowchart LR\n subgraph s\n a --> b", DiagramType::DataFlow)
+                .contains("never closed")
+        );
+        assert!(check("flowchart LR\n a --> b\n end", DiagramType::DataFlow).contains("'end'"));
+        assert!(check(
+            "sequenceDiagram\n alt ok\n a->>b: hi",
+            DiagramType::SequenceDiagram
+        )
+        .contains("'alt' on line 2"));
+        assert!(check(
+            "erDiagram\n users {\n id INTEGER\n }\n }",
+            DiagramType::DatabaseSchema
+        )
+        .contains("unexpected '}'"));
+        assert!(check(
+            "erDiagram\n users {\n id INTEGER",
+            DiagramType::DatabaseSchema
+        )
+        .contains("never closed"));
+    }
+}
@@ -4,16 +4,28 @@
 //! 1. **Extraction Phase**: Per-file analysis to extract diagram-relevant information
 //! 2. **Generation Phase**: Aggregation of extractions into final DOT diagrams
 //!
+//! With `diagram.format = "mermaid"` the generation phase produces Mermaid
+//! instead, which the dashboard renders in the browser.
+//!
 //! Supported diagram types:
 //! - System Architecture: High-level component relationships
 //! - Data Flow: How data moves through the system
 //! - Database Schema: Database tables and relationships
+//! - Module Dependencies: Which modules import which (built from `use crate::`
+//!   imports without an LLM, so it has no extraction phase)
+//! - Sequence Diagram: Interactions between components in key flows
 
+mod dependency;
 mod extractor;
 mod generator;
+mod mermaid;
+mod render;
 
+pub use dependency::{crate_use_paths, module_dependency_dot, module_dependency_mermaid};
 pub use extractor::DiagramExtractor;
 pub use generator::DiagramGenerator;
+pub use mermaid::{clean_mermaid_output, validate_mermaid_syntax, MermaidGenerator};
+pub use render::DotRenderer;
 
 use layout::backends::svg::SVGWriter;
 use layout::gv::{DotParser, GraphBuilder};
@@ -29,6 +41,10 @@ pub enum DiagramType {
     DataFlow,
     /// Database tables and their relationships
     DatabaseSchema,
+    /// Imports between the modules of a Rust crate
+    ModuleDependencyGraph,
+    /// Interactions between components in key flows
+    SequenceDiagram,
 }
 
 impl DiagramType {
@@ -38,15 +54,25 @@ impl DiagramType {
             DiagramType::SystemArchitecture,
             DiagramType::DataFlow,
             DiagramType::DatabaseSchema,
+            DiagramType::ModuleDependencyGraph,
+            DiagramType::SequenceDiagram,
         ]
     }
 
+    /// Whether the diagram is generated from per-file LLM extractions (the
+    /// module dependency graph is built directly from the source)
+    pub fn is_extracted(&self) -> bool {
+        !matches!(self, DiagramType::ModuleDependencyGraph)
+    }
+
     /// Returns the string identifier for this diagram type (used in database)
     pub fn as_str(&self) -> &'static str {
         match self {
             DiagramType::SystemArchitecture => "system_architecture",
             DiagramType::DataFlow => "data_flow",
             DiagramType::DatabaseSchema => "database_schema",
+            DiagramType::ModuleDependencyGraph => "module_dependency_graph",
+            DiagramType::SequenceDiagram => "sequence_diagram",
         }
     }
 
@@ -56,6 +82,8 @@ impl DiagramType {
             DiagramType::SystemArchitecture => "System Architecture",
             DiagramType::DataFlow => "Data Flow",
             DiagramType::DatabaseSchema => "Database Schema",
+            DiagramType::ModuleDependencyGraph => "Module Dependencies",
+            DiagramType::SequenceDiagram => "Sequence Diagram",
         }
     }
 
@@ -67,6 +95,8 @@ impl DiagramType {
             }
             DiagramType::DataFlow => "How data moves through the system",
             DiagramType::DatabaseSchema => "Database tables and their relationships",
+            DiagramType::ModuleDependencyGraph => "Which modules import which",
+            DiagramType::SequenceDiagram => "Interactions between components in key flows",
         }
     }
 }
@@ -80,7 +110,18 @@ impl std::fmt::Display for DiagramType {
 /// Validate DOT syntax using the layout-rs parser.
 /// Returns Ok(()) if valid, or Err with a descriptive error message.
 pub fn validate_dot_syntax(dot_code: &str) -> Result<(), String> {
-    // Check for empty content first
+    check_not_empty(dot_code)?;
+
+    // Use the layout-rs parser for real validation
+    let mut parser = DotParser::new(dot_code);
+    match parser.process() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("DOT syntax error: {}", err)),
+    }
+}
+
+/// Reject DOT without anything but blank lines and comments
+fn check_not_empty(dot_code: &str) -> Result<(), String> {
     let content = dot_code
         .lines()
         .filter(|l| {
@@ -91,13 +132,7 @@ pub fn validate_dot_syntax(dot_code: &str) -> Result<(), String> {
     if content == 0 {
         return Err("DOT diagram is empty".to_string());
     }
-
-    // Use the layout-rs parser for real validation
-    let mut parser = DotParser::new(dot_code);
-    match parser.process() {
-        Ok(_) => Ok(()),
-        Err(err) => Err(format!("DOT syntax error: {}", err)),
-    }
+    Ok(())
 }
 
 /// Render DOT code to SVG using the layout-rs library.
@@ -148,10 +183,18 @@ mod tests {
     #[test]
     fn test_diagram_type_all() {
         let types = DiagramType::all();
-        assert_eq!(types.len(), 3);
+        assert_eq!(types.len(), 5);
         assert!(types.contains(&DiagramType::SystemArchitecture));
         assert!(types.contains(&DiagramType::DataFlow));
         assert!(types.contains(&DiagramType::DatabaseSchema));
+        assert!(types.contains(&DiagramType::ModuleDependencyGraph));
+        assert!(types.contains(&DiagramType::SequenceDiagram));
+    }
+
+    #[test]
+    fn test_diagram_type_is_extracted() {
+        assert!(DiagramType::SequenceDiagram.is_extracted());
+        assert!(!DiagramType::ModuleDependencyGraph.is_extracted());
     }
 
     #[test]
@@ -162,6 +205,11 @@ mod tests {
         );
         assert_eq!(DiagramType::DataFlow.as_str(), "data_flow");
         assert_eq!(DiagramType::DatabaseSchema.as_str(), "database_schema");
+        assert_eq!(
+            DiagramType::ModuleDependencyGraph.as_str(),
+            "module_dependency_graph"
+        );
+        assert_eq!(DiagramType::SequenceDiagram.as_str(), "sequence_diagram");
     }
 
     #[test]
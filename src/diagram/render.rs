@@ -0,0 +1,212 @@
+//! Rendering DOT to SVG.
+//!
+//! The built-in layout-rs renderer needs nothing installed, but rejects or
+//! mangles some valid DOT (HTML labels, some record shapes and cluster
+//! layouts). With `diagram.renderer = "graphviz"` the system's `dot -Tsvg` is
+//! used instead, and layout-rs remains the fallback when `dot` can't be run.
+
+use super::{check_not_empty, render_dot_to_svg, validate_dot_syntax};
+use crate::config::{DiagramConfig, DiagramRenderer};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// How long `dot` may take to lay out one diagram
+const DOT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Renders DOT to SVG with the configured renderer
+#[derive(Debug, Clone, Default)]
+pub struct DotRenderer {
+    /// The Graphviz `dot` binary, None to render with layout-rs
+    graphviz: Option<PathBuf>,
+}
+
+impl DotRenderer {
+    /// The renderer `config` asks for. If Graphviz is asked for but `dot`
+    /// can't be run, layout-rs is used with a warning.
+    pub async fn new(config: &DiagramConfig) -> Self {
+        if config.renderer == DiagramRenderer::Layout {
+            return Self::default();
+        }
+
+        let dot = config
+            .dot_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("dot"));
+        match dot_version(&dot).await {
+            Ok(version) => {
+                tracing::debug!("Rendering diagrams with {}", version);
+                Self {
+                    graphviz: Some(dot),
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Graphviz is not available ({}), rendering diagrams with layout-rs",
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Render `dot_code` to SVG. An error is the renderer's complaint about
+    /// the DOT, for the LLM to fix.
+    pub async fn render(&self, dot_code: &str) -> Result<String, String> {
+        let Some(dot) = &self.graphviz else {
+            validate_dot_syntax(dot_code)?;
+            return render_dot_to_svg(dot_code);
+        };
+
+        check_not_empty(dot_code)?;
+        match run_dot(dot, dot_code).await {
+            Ok(svg) => Ok(svg),
+            // Rarely, layout-rs manages what dot crashed or timed out on
+            Err(e) => render_dot_to_svg(dot_code).map_err(|_| e),
+        }
+    }
+}
+
+/// The version line `dot -V` prints, checking that it runs
+async fn dot_version(dot: &Path) -> Result<String, String> {
+    let output = tokio::time::timeout(
+        DOT_TIMEOUT,
+        tokio::process::Command::new(dot)
+            .arg("-V")
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| format!("{} -V timed out", dot.display()))?
+    .map_err(|e| format!("failed to run {}: {}", dot.display(), e))?;
+
+    if !output.status.success() {
+        return Err(format!("{} -V failed", dot.display()));
+    }
+    // dot prints its version to stderr
+    Ok(String::from_utf8_lossy(&output.stderr).trim().to_string())
+}
+
+/// Render with `dot -Tsvg`, feeding the DOT on stdin
+async fn run_dot(dot: &Path, dot_code: &str) -> Result<String, String> {
+    let mut child = tokio::process::Command::new(dot)
+        .arg("-Tsvg")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", dot.display(), e))?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = dot_code.as_bytes().to_vec();
+    // Write while reading, so a large graph can't fill both pipes
+    let write = async move {
+        let written = stdin.write_all(&input).await;
+        drop(stdin);
+        written
+    };
+    let (written, output) = tokio::time::timeout(DOT_TIMEOUT, async {
+        tokio::join!(write, child.wait_with_output())
+    })
+    .await
+    .map_err(|_| "Graphviz timed out".to_string())?;
+    let output = output.map_err(|e| format!("Failed to run {}: {}", dot.display(), e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("DOT syntax error: {}", stderr.trim()));
+    }
+    written.map_err(|e| format!("Failed to write to {}: {}", dot.display(), e))?;
+    String::from_utf8(output.stdout).map_err(|_| "Graphviz wrote invalid UTF-8".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A stand-in for `dot` that answers `-V`, renders a fixed SVG, and fails
+    /// on input containing "broken"
+    #[cfg(unix)]
+    fn fake_dot(dir: &TempDir) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.path().join("dot");
+        std::fs::write(
+            &path,
+            r#"#!/bin/sh
+if [ "$1" = "-V" ]; then echo "dot - graphviz version 0.0 (fake)" >&2; exit 0; fi
+input=$(cat)
+case "$input" in
+  *broken*) echo "Error: syntax error in line 1 near 'broken'" >&2; exit 1 ;;
+esac
+echo '<svg data-renderer="graphviz"></svg>'
+"#,
+        )
+        .unwrap();
+        let mut perms = std::fs::metadata(&path).unwrap().permissions();
+        perms.set_mode(0o755);
+        std::fs::set_permissions(&path, perms).unwrap();
+        path
+    }
+
+    fn config(renderer: DiagramRenderer, dot_path: Option<PathBuf>) -> DiagramConfig {
+        DiagramConfig {
+            renderer,
+            dot_path,
+            ..DiagramConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layout_renderer() {
+        let renderer = DotRenderer::new(&config(DiagramRenderer::Layout, None)).await;
+        let svg = renderer.render("digraph { a -> b }").await.unwrap();
+        assert!(svg.contains("<svg"));
+        assert!(renderer.render("").await.is_err());
+        assert!(renderer.render("digraph { a -> ").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_graphviz_falls_back_when_missing() {
+        let dir = TempDir::new().unwrap();
+        let missing = dir.path().join("no-such-dot");
+        let renderer = DotRenderer::new(&config(DiagramRenderer::Graphviz, Some(missing))).await;
+        assert!(renderer.graphviz.is_none());
+        assert!(renderer
+            .render("digraph { a -> b }")
+            .await
+            .unwrap()
+            .contains("<svg"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_graphviz_renderer() {
+        let dir = TempDir::new().unwrap();
+        let dot = fake_dot(&dir);
+        let renderer = DotRenderer::new(&config(DiagramRenderer::Graphviz, Some(dot))).await;
+        assert!(renderer.graphviz.is_some());
+
+        // HTML labels, which layout-rs can't parse
+        let html = r#"digraph { a [label=<<b>A</b>>]; a -> b }"#;
+        assert!(validate_dot_syntax(html).is_err());
+        assert_eq!(
+            renderer.render(html).await.unwrap().trim(),
+            r#"<svg data-renderer="graphviz"></svg>"#
+        );
+
+        assert_eq!(
+            renderer.render("  \n").await.unwrap_err(),
+            "DOT diagram is empty"
+        );
+        // Neither renderer manages, so dot's error is reported
+        assert_eq!(
+            renderer.render("digraph { broken -> }").await.unwrap_err(),
+            "DOT syntax error: Error: syntax error in line 1 near 'broken'"
+        );
+    }
+}
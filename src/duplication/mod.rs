@@ -0,0 +1,307 @@
+//! Duplicate code detection.
+//!
+//! Each source file is reduced to its code lines: whitespace is collapsed and
+//! blank or punctuation-only lines (closing braces and the like) are dropped,
+//! so reindented copies still match. Every window of [`WINDOW_LINES`]
+//! consecutive code lines is hashed, windows shared by different files are
+//! extended into the longest common blocks, and identical blocks are grouped
+//! into clusters. No model is involved, so the pass is cheap enough to run on
+//! every scan.
+
+use crate::db::{DuplicateCluster, DuplicateLocation, NewDuplicateCluster};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Code lines a duplicated block must span
+pub const WINDOW_LINES: usize = 6;
+
+/// Windows found in more places than this are boilerplate (license headers,
+/// import lists) rather than copy-pasted logic, and are ignored
+const MAX_OCCURRENCES: usize = 20;
+
+/// A normalized code line and its 1-based line number in the file
+struct CodeLine {
+    number: usize,
+    text: String,
+}
+
+/// The code lines of a file, with whitespace collapsed
+fn code_lines(content: &str) -> Vec<CodeLine> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.chars().any(char::is_alphanumeric))
+        .map(|(index, line)| CodeLine {
+            number: index + 1,
+            text: line.split_whitespace().collect::<Vec<_>>().join(" "),
+        })
+        .collect()
+}
+
+fn window_hash(lines: &[CodeLine]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for line in lines {
+        line.text.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Find blocks of at least [`WINDOW_LINES`] code lines that occur in more than
+/// one of `files` (path and content), largest first
+pub fn detect(files: &[(String, &str)]) -> Vec<NewDuplicateCluster> {
+    let lines: Vec<Vec<CodeLine>> = files
+        .iter()
+        .map(|(_, content)| code_lines(content))
+        .collect();
+
+    // Where each window occurs, as (file index, index of its first code line)
+    let mut windows: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+    for (file, file_lines) in lines.iter().enumerate() {
+        for (start, window) in file_lines.windows(WINDOW_LINES).enumerate() {
+            windows
+                .entry(window_hash(window))
+                .or_default()
+                .push((file, start));
+        }
+    }
+
+    // Shared windows as (file a, start in a, file b, start in b) with a < b
+    let mut matches: HashSet<(usize, usize, usize, usize)> = HashSet::new();
+    for occurrences in windows.values() {
+        if occurrences.len() < 2 || occurrences.len() > MAX_OCCURRENCES {
+            continue;
+        }
+        for (i, &(a, start_a)) in occurrences.iter().enumerate() {
+            for &(b, start_b) in &occurrences[i + 1..] {
+                if a < b {
+                    matches.insert((a, start_a, b, start_b));
+                } else if b < a {
+                    matches.insert((b, start_b, a, start_a));
+                }
+            }
+        }
+    }
+
+    // Extend each match that doesn't continue an earlier one into a block,
+    // grouping blocks by their normalized content
+    let mut clusters: BTreeMap<String, (usize, BTreeSet<DuplicateLocation>)> = BTreeMap::new();
+    let location = |file: usize, start: usize, len: usize| DuplicateLocation {
+        file_path: files[file].0.clone(),
+        line_start: lines[file][start].number as i64,
+        line_end: lines[file][start + len - 1].number as i64,
+        file_lines: lines[file].len() as i64,
+    };
+    for &(a, start_a, b, start_b) in &matches {
+        if start_a > 0 && start_b > 0 && matches.contains(&(a, start_a - 1, b, start_b - 1)) {
+            continue;
+        }
+        let mut windows = 1;
+        while matches.contains(&(a, start_a + windows, b, start_b + windows)) {
+            windows += 1;
+        }
+        let len = windows + WINDOW_LINES - 1;
+
+        let mut hasher = Sha256::new();
+        for line in &lines[a][start_a..start_a + len] {
+            hasher.update(line.text.as_bytes());
+            hasher.update(b"\n");
+        }
+        let (_, locations) = clusters
+            .entry(format!("{:x}", hasher.finalize()))
+            .or_insert_with(|| (len, BTreeSet::new()));
+        locations.insert(location(a, start_a, len));
+        locations.insert(location(b, start_b, len));
+    }
+
+    let mut clusters: Vec<NewDuplicateCluster> = clusters
+        .into_iter()
+        .map(|(fingerprint, (len, locations))| NewDuplicateCluster {
+            fingerprint,
+            line_count: len as i64,
+            locations: locations.into_iter().collect(),
+        })
+        .collect();
+    clusters.sort_by(|x, y| {
+        y.line_count
+            .cmp(&x.line_count)
+            .then_with(|| x.locations.cmp(&y.locations))
+    });
+    clusters
+}
+
+/// Two files sharing duplicated code
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilePair {
+    pub file_a: String,
+    pub file_b: String,
+    /// Code lines the files share
+    pub duplicated_lines: i64,
+    /// Share of the two files' code lines that is duplicated between them (0 to 1)
+    pub similarity: f64,
+    /// Number of distinct blocks the files share
+    pub blocks: usize,
+}
+
+/// The pairs of files sharing code in `clusters`, most similar first
+pub fn file_pairs(clusters: &[DuplicateCluster]) -> Vec<FilePair> {
+    let mut pairs: BTreeMap<(&str, &str), (i64, i64, i64, usize)> = BTreeMap::new();
+    for cluster in clusters {
+        for (i, a) in cluster.locations.iter().enumerate() {
+            for b in &cluster.locations[i + 1..] {
+                let (a, b) = match a.file_path.cmp(&b.file_path) {
+                    std::cmp::Ordering::Less => (a, b),
+                    std::cmp::Ordering::Greater => (b, a),
+                    std::cmp::Ordering::Equal => continue,
+                };
+                let pair = pairs
+                    .entry((a.file_path.as_str(), b.file_path.as_str()))
+                    .or_insert((0, a.file_lines, b.file_lines, 0));
+                pair.0 += cluster.line_count;
+                pair.3 += 1;
+            }
+        }
+    }
+
+    let mut pairs: Vec<FilePair> = pairs
+        .into_iter()
+        .map(
+            |((file_a, file_b), (duplicated, lines_a, lines_b, blocks))| {
+                let total = (lines_a + lines_b).max(1) as f64;
+                FilePair {
+                    file_a: file_a.to_string(),
+                    file_b: file_b.to_string(),
+                    duplicated_lines: duplicated,
+                    similarity: (2.0 * duplicated as f64 / total).min(1.0),
+                    blocks,
+                }
+            },
+        )
+        .collect();
+    pairs.sort_by(|x, y| {
+        y.similarity
+            .total_cmp(&x.similarity)
+            .then_with(|| y.duplicated_lines.cmp(&x.duplicated_lines))
+    });
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARSER: &str = "fn parse(input: &str) -> Result<Config> {
+    let mut config = Config::default();
+    for line in input.lines() {
+        let (key, value) = line.split_once('=').context(\"missing =\")?;
+        config.set(key.trim(), value.trim())?;
+    }
+    validate(&config)?;
+    Ok(config)
+}
+";
+
+    fn file(path: &str, content: &str) -> (String, String) {
+        (path.to_string(), content.to_string())
+    }
+
+    fn run(files: &[(String, String)]) -> Vec<NewDuplicateCluster> {
+        let files: Vec<(String, &str)> = files
+            .iter()
+            .map(|(path, content)| (path.clone(), content.as_str()))
+            .collect();
+        detect(&files)
+    }
+
+    #[test]
+    fn test_detects_reindented_copies() {
+        let copy = format!(
+            "use std::fmt;\n\nmod inner {{\n{}}}\n",
+            PARSER
+                .lines()
+                .map(|l| format!("        {}\n", l))
+                .collect::<String>()
+        );
+        let clusters = run(&[
+            file("/repo/a.rs", PARSER),
+            file("/repo/b.rs", &copy),
+            file("/repo/c.rs", "fn unrelated() {}\n"),
+        ]);
+
+        assert_eq!(clusters.len(), 1);
+        // The closing braces don't count as code lines
+        assert_eq!(clusters[0].line_count, 7);
+        assert_eq!(
+            clusters[0].locations,
+            [
+                DuplicateLocation {
+                    file_path: "/repo/a.rs".to_string(),
+                    line_start: 1,
+                    line_end: 8,
+                    file_lines: 7,
+                },
+                DuplicateLocation {
+                    file_path: "/repo/b.rs".to_string(),
+                    line_start: 4,
+                    line_end: 11,
+                    file_lines: 9,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_three_copies_form_one_cluster() {
+        let clusters = run(&[
+            file("/a.rs", PARSER),
+            file("/b.rs", PARSER),
+            file("/c.rs", PARSER),
+        ]);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].locations.len(), 3);
+    }
+
+    #[test]
+    fn test_ignores_short_and_same_file_repeats() {
+        let short: String = PARSER.lines().take(4).collect::<Vec<_>>().join("\n");
+        assert!(run(&[file("/a.rs", &short), file("/b.rs", &short)]).is_empty());
+        assert!(run(&[file("/a.rs", &format!("{}{}", PARSER, PARSER))]).is_empty());
+    }
+
+    fn cluster(line_count: i64, locations: &[(&str, i64)]) -> DuplicateCluster {
+        DuplicateCluster {
+            id: 1,
+            repository_id: 1,
+            fingerprint: String::new(),
+            line_count,
+            locations: locations
+                .iter()
+                .map(|(path, file_lines)| DuplicateLocation {
+                    file_path: path.to_string(),
+                    line_start: 1,
+                    line_end: 10,
+                    file_lines: *file_lines,
+                })
+                .collect(),
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_file_pairs() {
+        let pairs = file_pairs(&[
+            cluster(10, &[("/b.rs", 20), ("/a.rs", 20)]),
+            cluster(6, &[("/a.rs", 20), ("/b.rs", 20), ("/c.rs", 100)]),
+        ]);
+
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs[0].file_a, "/a.rs");
+        assert_eq!(pairs[0].file_b, "/b.rs");
+        assert_eq!(pairs[0].duplicated_lines, 16);
+        assert_eq!(pairs[0].blocks, 2);
+        assert!((pairs[0].similarity - 0.8).abs() < 1e-9);
+        assert!((pairs[1].similarity - 0.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,405 @@
+//! Offsite export of reports and database backups.
+//!
+//! Files are uploaded with a plain HTTP `PUT` to every target configured in
+//! `[export]`: an S3-compatible bucket (requests are signed with AWS Signature
+//! Version 4, which MinIO, Garage, B2 and friends all accept) and/or a WebDAV
+//! collection. Uploads overwrite the previous copy of the same key, so a
+//! report always reflects the latest scan while backups keep their timestamped
+//! file names.
+
+use crate::config::{ExportConfig, S3Target, WebDavTarget};
+use anyhow::{bail, Context, Result};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+/// Key of a repository's report, e.g. `reports/my-app.html`
+pub fn report_key(repository_name: &str, extension: &str) -> String {
+    format!("reports/{}.{}", slug(repository_name), extension)
+}
+
+/// Key of a database backup file
+pub fn backup_key(file_name: &str) -> String {
+    format!("backups/{}", file_name)
+}
+
+/// A repository name reduced to characters that are safe in object keys and URLs
+fn slug(name: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let slug = slug.trim_matches(|c| c == '-' || c == '.');
+    if slug.is_empty() {
+        "repository".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Uploads files to the configured export targets
+pub struct Exporter {
+    config: ExportConfig,
+    client: reqwest::Client,
+}
+
+impl Exporter {
+    pub fn new(config: ExportConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Upload a file to every configured target.
+    ///
+    /// Targets fail independently; an error is returned only if every
+    /// configured target failed.
+    pub async fn upload(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        let mut attempted = 0;
+        let mut errors = Vec::new();
+
+        if let Some(target) = &self.config.s3 {
+            attempted += 1;
+            if let Err(e) = self.put_s3(target, key, body.clone(), content_type).await {
+                tracing::warn!("S3 export of {} failed: {:#}", key, e);
+                errors.push(e);
+            }
+        }
+        if let Some(target) = &self.config.webdav {
+            attempted += 1;
+            if let Err(e) = self.put_webdav(target, key, body, content_type).await {
+                tracing::warn!("WebDAV export of {} failed: {:#}", key, e);
+                errors.push(e);
+            }
+        }
+
+        if attempted > 0 && errors.len() == attempted {
+            return Err(errors.remove(0));
+        }
+        Ok(())
+    }
+
+    async fn put_s3(
+        &self,
+        target: &S3Target,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<()> {
+        let key = match target.prefix.as_deref().map(|p| p.trim_matches('/')) {
+            Some(prefix) if !prefix.is_empty() => format!("{}/{}", prefix, key),
+            _ => key.to_string(),
+        };
+        let url = s3_object_url(target, &key)?;
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let authorization = sign_s3_put(target, &url, &amz_date, &payload_hash)?;
+
+        let response = self
+            .client
+            .put(url)
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-content-sha256", &payload_hash)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body)
+            .send()
+            .await
+            .context("Failed to reach S3 endpoint")?;
+        check_status(response).await
+    }
+
+    async fn put_webdav(
+        &self,
+        target: &WebDavTarget,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<()> {
+        let base = target.url.trim_end_matches('/');
+
+        // Create the parent collections; an existing collection answers 405
+        let mut collection = base.to_string();
+        let segments: Vec<&str> = key.split('/').collect();
+        for segment in &segments[..segments.len() - 1] {
+            collection = format!("{}/{}", collection, encode_uri(segment, false));
+            let mkcol = Method::from_bytes(b"MKCOL").expect("MKCOL is a valid method");
+            let response = self
+                .webdav_request(target, mkcol, &collection)
+                .send()
+                .await
+                .context("Failed to reach WebDAV server")?;
+            if !response.status().is_success()
+                && response.status() != StatusCode::METHOD_NOT_ALLOWED
+            {
+                check_status(response).await?;
+            }
+        }
+
+        let url = format!("{}/{}", base, encode_uri(key, true));
+        let response = self
+            .webdav_request(target, Method::PUT, &url)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body)
+            .send()
+            .await
+            .context("Failed to reach WebDAV server")?;
+        check_status(response).await
+    }
+
+    fn webdav_request(
+        &self,
+        target: &WebDavTarget,
+        method: Method,
+        url: &str,
+    ) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        match &target.username {
+            Some(username) => request.basic_auth(username, target.password.as_deref()),
+            None => request,
+        }
+    }
+}
+
+/// Turn an error status into an error carrying the start of the response body
+async fn check_status(response: reqwest::Response) -> Result<()> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    bail!(
+        "Upload failed with {}: {}",
+        status,
+        body.chars().take(200).collect::<String>()
+    )
+}
+
+/// URL of an object, addressed path-style or virtual-hosted-style
+fn s3_object_url(target: &S3Target, key: &str) -> Result<Url> {
+    let mut url = Url::parse(&target.endpoint)
+        .with_context(|| format!("Invalid S3 endpoint {:?}", target.endpoint))?;
+    let key = encode_uri(key, true);
+    if target.path_style {
+        let base = url.path().trim_end_matches('/').to_string();
+        url.set_path(&format!("{}/{}/{}", base, target.bucket, key));
+    } else {
+        let host = url.host_str().context("S3 endpoint has no host")?;
+        let host = format!("{}.{}", target.bucket, host);
+        url.set_host(Some(&host))
+            .context("Invalid bucket name for virtual-hosted-style addressing")?;
+        url.set_path(&format!("/{}", key));
+    }
+    Ok(url)
+}
+
+/// `Authorization` header of an unsigned-query `PUT` (AWS Signature Version 4)
+fn sign_s3_put(target: &S3Target, url: &Url, amz_date: &str, payload_hash: &str) -> Result<String> {
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let date = &amz_date[..8];
+    let scope = format!("{}/{}/s3/aws4_request", date, target.region);
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+    let canonical_request = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        url.path(),
+        host,
+        payload_hash,
+        amz_date,
+        signed_headers,
+        payload_hash
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = signing_key(&target.secret_access_key, date, &target.region, "s3")?;
+    let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes())?);
+
+    Ok(format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        target.access_key_id, scope, signed_headers, signature
+    ))
+}
+
+/// SigV4 signing key for a date (`YYYYMMDD`), region and service
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Result<Vec<u8>> {
+    let key = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date.as_bytes())?;
+    let key = hmac_sha256(&key, region.as_bytes())?;
+    let key = hmac_sha256(&key, service.as_bytes())?;
+    hmac_sha256(&key, b"aws4_request")
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).context("Invalid HMAC key")?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+/// Percent-encode everything but unreserved characters (and `/` if `keep_slash`)
+fn encode_uri(value: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{basic_auth, body_string, header, header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn s3_target(endpoint: &str) -> S3Target {
+        S3Target {
+            endpoint: endpoint.to_string(),
+            bucket: "backups".to_string(),
+            region: "us-east-1".to_string(),
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            prefix: Some("noctum/".to_string()),
+            path_style: true,
+        }
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_eq!(report_key("my app/v2", "html"), "reports/my-app-v2.html");
+        assert_eq!(report_key("..", "md"), "reports/repository.md");
+        assert_eq!(
+            backup_key("noctum-20260101T000000Z.db"),
+            "backups/noctum-20260101T000000Z.db"
+        );
+    }
+
+    #[test]
+    fn test_signing_key() {
+        // Example from the AWS Signature Version 4 documentation
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_s3_object_url() {
+        let mut target = s3_target("https://s3.example.com");
+        assert_eq!(
+            s3_object_url(&target, "reports/a b.html").unwrap().as_str(),
+            "https://s3.example.com/backups/reports/a%20b.html"
+        );
+
+        target.path_style = false;
+        assert_eq!(
+            s3_object_url(&target, "reports/a.html").unwrap().as_str(),
+            "https://backups.s3.example.com/reports/a.html"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_to_s3_and_webdav() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/backups/noctum/reports/app.html"))
+            .and(header_exists("x-amz-date"))
+            .and(header(
+                "x-amz-content-sha256",
+                hex::encode(Sha256::digest(b"<html></html>")).as_str(),
+            ))
+            .and(body_string("<html></html>"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("MKCOL"))
+            .and(path("/dav/reports"))
+            .and(basic_auth("me", "pw"))
+            .respond_with(ResponseTemplate::new(405))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/dav/reports/app.html"))
+            .and(basic_auth("me", "pw"))
+            .and(header("content-type", "text/html"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let exporter = Exporter::new(ExportConfig {
+            s3: Some(s3_target(&server.uri())),
+            webdav: Some(WebDavTarget {
+                url: format!("{}/dav/", server.uri()),
+                username: Some("me".to_string()),
+                password: Some("pw".to_string()),
+            }),
+            ..ExportConfig::default()
+        });
+        exporter
+            .upload(
+                &report_key("app", "html"),
+                b"<html></html>".to_vec(),
+                "text/html",
+            )
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let authorization = requests
+            .iter()
+            .find(|r| r.url.path().starts_with("/backups/"))
+            .and_then(|r| r.headers.get("authorization"))
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+        assert!(authorization.contains("/us-east-1/s3/aws4_request"));
+    }
+
+    #[tokio::test]
+    async fn test_upload_fails_when_every_target_fails() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(403).set_body_string("AccessDenied"))
+            .mount(&server)
+            .await;
+
+        let exporter = Exporter::new(ExportConfig {
+            s3: Some(s3_target(&server.uri())),
+            ..ExportConfig::default()
+        });
+        let err = exporter
+            .upload("backups/a.db", vec![1, 2, 3], "application/octet-stream")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("AccessDenied"));
+    }
+}
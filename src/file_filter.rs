@@ -0,0 +1,341 @@
+//! Detecting vendored and generated source files.
+//!
+//! Third-party code checked into a repository and the output of code
+//! generators (protobuf, ORMs, parsers) aren't what anyone wants reviewed, and
+//! they are often the largest files in a tree. They are recognized by path
+//! (`vendor/`, `*.pb.rs`, ...), by a generator's header comment (`@generated`,
+//! `Code generated ... DO NOT EDIT`), or by content so repetitive it can only
+//! be data. Minified bundles, with their few enormous lines, are caught when
+//! the file is decoded. The checks are shared by every language and can be
+//! turned off or overridden per repository in `noctum.toml`, which also
+//! lists glob patterns of files to include and exclude.
+
+use std::path::{Component, Path};
+
+/// Directories holding third-party code
+const VENDORED_DIRS: &[&str] = &[
+    "vendor",
+    "vendored",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "bower_components",
+    "Pods",
+];
+
+/// File name suffixes of bundled third-party code
+const VENDORED_SUFFIXES: &[&str] = &[".min.js", ".min.css", ".bundle.js"];
+
+/// Directories holding generator output
+const GENERATED_DIRS: &[&str] = &["generated", "__generated__"];
+
+/// File name suffixes of generator output
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".pb.rs",
+    ".pb.go",
+    ".pb.cc",
+    ".pb.h",
+    ".pb.ts",
+    "_pb.js",
+    "_pb.d.ts",
+    "_pb2.py",
+    "_pb2_grpc.py",
+    ".g.dart",
+    ".freezed.dart",
+    ".designer.cs",
+];
+
+/// File name infixes of generator output, e.g. `schema.generated.ts`
+const GENERATED_INFIXES: &[&str] = &[".generated.", "_generated.", ".gen."];
+
+/// Leading lines searched for a generator's header comment
+const HEADER_LINES: usize = 30;
+
+/// Smallest file checked for data-like content
+const MIN_ENTROPY_CHECK_BYTES: usize = 4096;
+
+/// Shannon entropy (bits per byte) below which content is treated as data.
+/// Code is usually around 4.5; long runs of repeated literals fall under 2.5.
+const MIN_ENTROPY_BITS: f64 = 2.5;
+
+/// Why a file looks like it shouldn't be analyzed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detected {
+    Vendored,
+    Generated,
+}
+
+/// Which heuristics apply to a repository
+#[derive(Debug, Clone)]
+pub struct Detection {
+    /// Skip files that look vendored
+    pub vendored: bool,
+    /// Skip files that look generated
+    pub generated: bool,
+    /// Files analyzed regardless of what the heuristics say
+    pub keep: Globs,
+}
+
+impl Default for Detection {
+    fn default() -> Self {
+        Self {
+            vendored: true,
+            generated: true,
+            keep: Globs::default(),
+        }
+    }
+}
+
+impl Detection {
+    /// Whether the file at `relative_path` with `content` is vendored or
+    /// generated, or None if it should be analyzed
+    pub fn detect(&self, relative_path: &Path, content: &str) -> Option<Detected> {
+        if self.keep.is_match(relative_path) {
+            return None;
+        }
+        if self.vendored && is_vendored_path(relative_path) {
+            return Some(Detected::Vendored);
+        }
+        if self.generated
+            && (is_generated_path(relative_path)
+                || has_generated_header(content)
+                || is_low_entropy(content))
+        {
+            return Some(Detected::Generated);
+        }
+        None
+    }
+}
+
+/// Glob patterns matched against paths relative to the repository root. A
+/// pattern without a `/` also matches file names in any directory, so
+/// `*_generated.rs` covers `src/api_generated.rs`.
+#[derive(Debug, Clone, Default)]
+pub struct Globs(Vec<String>);
+
+impl Globs {
+    /// Fails if any pattern has an unclosed `[` or unbalanced braces.
+    pub fn new(patterns: &[String]) -> anyhow::Result<Self> {
+        for pattern in patterns {
+            check_glob(pattern)?;
+        }
+        Ok(Self(patterns.to_vec()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn is_match(&self, relative_path: &Path) -> bool {
+        let path = relative_path.to_string_lossy();
+        let name = file_name(relative_path);
+        self.0.iter().any(|pattern| {
+            glob_match::glob_match(pattern, &path)
+                || (!pattern.contains('/') && glob_match::glob_match(pattern, name))
+        })
+    }
+}
+
+/// `glob_match` treats malformed patterns as matching nothing, so they are
+/// rejected up front instead of silently filtering every file
+fn check_glob(pattern: &str) -> anyhow::Result<()> {
+    let mut chars = pattern.chars();
+    let mut braces = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => anyhow::ensure!(
+                chars.by_ref().any(|c| c == ']'),
+                "Invalid glob pattern: {}: unclosed '['",
+                pattern
+            ),
+            '{' => braces += 1,
+            '}' => {
+                braces = braces.checked_sub(1).ok_or_else(|| {
+                    anyhow::anyhow!("Invalid glob pattern: {}: unmatched '}}'", pattern)
+                })?
+            }
+            _ => {}
+        }
+    }
+    anyhow::ensure!(
+        braces == 0,
+        "Invalid glob pattern: {}: unclosed '{{'",
+        pattern
+    );
+    Ok(())
+}
+
+fn dir_names(relative_path: &Path) -> impl Iterator<Item = &str> {
+    let parent = relative_path.parent().unwrap_or(Path::new(""));
+    parent.components().filter_map(|c| match c {
+        Component::Normal(name) => name.to_str(),
+        _ => None,
+    })
+}
+
+fn file_name(relative_path: &Path) -> &str {
+    relative_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+}
+
+/// Whether the path is inside a vendor directory or names a bundled library
+pub fn is_vendored_path(relative_path: &Path) -> bool {
+    let name = file_name(relative_path);
+    dir_names(relative_path).any(|dir| VENDORED_DIRS.contains(&dir))
+        || VENDORED_SUFFIXES.iter().any(|s| name.ends_with(s))
+}
+
+/// Whether the path follows a code generator's naming convention
+pub fn is_generated_path(relative_path: &Path) -> bool {
+    let name = file_name(relative_path);
+    dir_names(relative_path).any(|dir| GENERATED_DIRS.contains(&dir))
+        || GENERATED_SUFFIXES.iter().any(|s| name.ends_with(s))
+        || GENERATED_INFIXES.iter().any(|s| name.contains(s))
+}
+
+/// Whether a comment near the top of the file says it was generated
+pub fn has_generated_header(content: &str) -> bool {
+    const COMMENT_PREFIXES: &[&str] = &["//", "#", "/*", "*", "--", "<!--", ";"];
+
+    content
+        .lines()
+        .take(HEADER_LINES)
+        .map(str::trim_start)
+        .filter(|line| COMMENT_PREFIXES.iter().any(|p| line.starts_with(p)))
+        .map(str::to_lowercase)
+        .any(|line| {
+            line.contains("@generated")
+                || line.contains("code generated")
+                || line.contains("auto-generated")
+                || line.contains("autogenerated")
+                || line.contains("automatically generated")
+                || (line.contains("generated") && line.contains("do not edit"))
+        })
+}
+
+/// Whether the content is too repetitive to be hand-written code, e.g. an
+/// embedded lookup table or test vectors
+pub fn is_low_entropy(content: &str) -> bool {
+    if content.len() < MIN_ENTROPY_CHECK_BYTES {
+        return false;
+    }
+    let mut counts = [0usize; 256];
+    for &b in content.as_bytes() {
+        counts[b as usize] += 1;
+    }
+    let len = content.len() as f64;
+    let entropy: f64 = counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum();
+    entropy < MIN_ENTROPY_BITS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_globs() {
+        let globs =
+            Globs::new(&["**/fixtures/**".to_string(), "*_generated.rs".to_string()]).unwrap();
+        assert!(globs.is_match(Path::new("tests/fixtures/input.rs")));
+        assert!(globs.is_match(Path::new("fixtures/input.rs")));
+        assert!(globs.is_match(Path::new("api_generated.rs")));
+        assert!(globs.is_match(Path::new("src/proto/api_generated.rs")));
+        assert!(!globs.is_match(Path::new("src/main.rs")));
+
+        // Patterns with a directory are anchored at the repository root
+        let globs = Globs::new(&["src/*.rs".to_string()]).unwrap();
+        assert!(globs.is_match(Path::new("src/lib.rs")));
+        assert!(!globs.is_match(Path::new("lib.rs")));
+        assert!(!globs.is_match(Path::new("crates/a/src/lib.rs")));
+
+        assert!(!Globs::default().is_match(Path::new("src/lib.rs")));
+        assert!(Globs::new(&["src/{a,b}.rs".to_string(), "[ab].rs".to_string()]).is_ok());
+        assert!(Globs::new(&["src/[".to_string()]).is_err());
+        assert!(Globs::new(&["src/{a,b.rs".to_string()]).is_err());
+        assert!(Globs::new(&["src/a}.rs".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_paths() {
+        assert!(is_vendored_path(Path::new("vendor/github.com/x/y.go")));
+        assert!(is_vendored_path(Path::new("web/third_party/lib.ts")));
+        assert!(is_vendored_path(Path::new("static/jquery.min.js")));
+        assert!(!is_vendored_path(Path::new("src/vendor.rs")));
+
+        assert!(is_generated_path(Path::new("src/proto/api.pb.rs")));
+        assert!(is_generated_path(Path::new("api/service_pb2.py")));
+        assert!(is_generated_path(Path::new("src/__generated__/types.ts")));
+        assert!(is_generated_path(Path::new("src/schema.generated.ts")));
+        assert!(!is_generated_path(Path::new("src/generator.rs")));
+    }
+
+    #[test]
+    fn test_generated_header() {
+        assert!(has_generated_header(
+            "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage api\n"
+        ));
+        assert!(has_generated_header(
+            "#![allow(dead_code)]\n// @generated\n"
+        ));
+        assert!(has_generated_header(
+            "/*\n * This file is automatically generated.\n */\n"
+        ));
+        // Only comments count, not code mentioning the marker
+        assert!(!has_generated_header(
+            "fn is_generated(s: &str) -> bool { s.contains(\"@generated\") }\n"
+        ));
+        assert!(!has_generated_header("// DO NOT EDIT the order below\n"));
+
+        let late = format!("{}// @generated\n", "fn f() {}\n".repeat(HEADER_LINES));
+        assert!(!has_generated_header(&late));
+    }
+
+    #[test]
+    fn test_low_entropy() {
+        let table = format!("static TABLE: [u8; 4096] = [{}];\n", "0, ".repeat(2000));
+        assert!(is_low_entropy(&table));
+
+        let code = "fn add(left: u64, right: u64) -> u64 {\n    left.wrapping_add(right)\n}\n\n"
+            .repeat(60);
+        assert!(!is_low_entropy(&code));
+        assert!(!is_low_entropy("aaaa"));
+    }
+
+    #[test]
+    fn test_detection_settings() {
+        let detection = Detection::default();
+        assert_eq!(
+            detection.detect(Path::new("vendor/lib.rs"), "fn f() {}"),
+            Some(Detected::Vendored)
+        );
+        assert_eq!(
+            detection.detect(Path::new("src/lib.rs"), "// @generated\nfn f() {}"),
+            Some(Detected::Generated)
+        );
+        assert_eq!(detection.detect(Path::new("src/lib.rs"), "fn f() {}"), None);
+
+        let detection = Detection {
+            generated: false,
+            keep: Globs::new(&["vendor/ours/**".to_string()]).unwrap(),
+            ..Detection::default()
+        };
+        assert_eq!(detection.detect(Path::new("vendor/ours/lib.rs"), ""), None);
+        assert_eq!(
+            detection.detect(Path::new("vendor/theirs/lib.rs"), ""),
+            Some(Detected::Vendored)
+        );
+        assert_eq!(detection.detect(Path::new("src/api.pb.rs"), ""), None);
+    }
+}
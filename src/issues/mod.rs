@@ -0,0 +1,879 @@
+//! Issue tracker import.
+//!
+//! For repositories with `[issues] enabled = true` in their `noctum.toml`, the
+//! open issues of the matching GitHub or GitLab project are fetched on every
+//! scan. Their titles and labels are added to the code analysis and
+//! architecture summary prompts as project context, and analysis results that
+//! mention an issue's title are linked to it on the dashboard.
+//!
+//! Findings and survived mutants can also be filed as new issues, rendered
+//! from the title and body templates in the repository's `[issues]` section.
+//!
+//! The project is taken from the repository config, or detected from the
+//! `origin` remote. Tokens live in the global config, never in the repository.
+
+use crate::config::IssueTrackerConfig;
+use crate::db::{AnalysisResult, Issue, MutationResult};
+use crate::repo_config::IssuesSection;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Maximum number of open issues fetched per repository (one API page)
+const MAX_ISSUES: usize = 100;
+
+/// Maximum number of issues listed in a prompt
+const MAX_PROMPT_ISSUES: usize = 30;
+
+/// Issue titles longer than this (in bytes) are truncated in prompts
+const MAX_PROMPT_TITLE_LEN: usize = 120;
+
+/// Minimum number of significant words for a title to be matched word by word
+const MIN_MATCH_WORDS: usize = 3;
+
+/// Maximum length (in bytes) of a summary taken from an analysis result
+const MAX_SUMMARY_LEN: usize = 80;
+
+/// Title of issues created from findings, unless `[issues] title_template` is set
+const DEFAULT_TITLE_TEMPLATE: &str = "{summary} in {file}";
+
+/// Body of issues created from findings, unless `[issues] body_template` is set
+const DEFAULT_BODY_TEMPLATE: &str =
+    "{details}\n\n---\n_Filed by Noctum from the {source} of `{file}` in {repository}._";
+
+/// Issue tracker hosting a project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueProvider {
+    GitHub,
+    GitLab,
+}
+
+/// A project on an issue tracker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueSource {
+    pub provider: IssueProvider,
+    /// `owner/repo` on GitHub, the full project path on GitLab
+    pub project: String,
+}
+
+impl IssueSource {
+    /// The project configured in `[issues]`, with a missing provider or project
+    /// detected from the repository's `origin` remote
+    pub async fn resolve(
+        settings: &IssuesSection,
+        tracker: &IssueTrackerConfig,
+        repo_path: &Path,
+    ) -> Option<Self> {
+        if let (Some(provider), Some(project)) = (settings.provider, &settings.project) {
+            return Some(Self {
+                provider,
+                project: project.clone(),
+            });
+        }
+
+        let gitlab_host = reqwest::Url::parse(&tracker.gitlab_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let detected = Self::from_remote(&origin_url(repo_path).await?, &gitlab_host)?;
+        Some(Self {
+            provider: settings.provider.unwrap_or(detected.provider),
+            project: settings.project.clone().unwrap_or(detected.project),
+        })
+    }
+
+    /// Detect the project from a git remote URL (HTTPS or SSH).
+    /// `gitlab_host` is the host name of the configured GitLab instance.
+    pub fn from_remote(url: &str, gitlab_host: &str) -> Option<Self> {
+        let url = url.trim().trim_end_matches('/');
+        let url = url.strip_suffix(".git").unwrap_or(url);
+
+        let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+            // https://host/path or ssh://git@host:22/path
+            let (authority, path) = rest.split_once('/')?;
+            let host = authority.rsplit('@').next()?;
+            (host.split(':').next()?, path)
+        } else {
+            // git@host:path
+            let (authority, path) = url.split_once(':')?;
+            (authority.rsplit('@').next()?, path)
+        };
+        let path = path.trim_matches('/');
+        if path.split('/').count() < 2 || path.split('/').any(str::is_empty) {
+            return None;
+        }
+
+        let provider = if host.eq_ignore_ascii_case("github.com") {
+            if path.split('/').count() != 2 {
+                return None;
+            }
+            IssueProvider::GitHub
+        } else if host.eq_ignore_ascii_case(gitlab_host) {
+            IssueProvider::GitLab
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            provider,
+            project: path.to_string(),
+        })
+    }
+}
+
+/// An open issue as fetched from a tracker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackerIssue {
+    pub number: i64,
+    pub title: String,
+    pub url: String,
+    pub labels: Vec<String>,
+}
+
+/// URL of the `origin` remote of a git repository, if any
+pub async fn origin_url(repo_path: &Path) -> Option<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .current_dir(repo_path)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|u| !u.is_empty())
+}
+
+/// Fetches open issues from GitHub and GitLab
+pub struct IssueClient {
+    config: IssueTrackerConfig,
+    client: reqwest::Client,
+}
+
+impl IssueClient {
+    pub fn new(config: IssueTrackerConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Fetch the most recently updated open issues of a project
+    pub async fn fetch_open_issues(&self, source: &IssueSource) -> Result<Vec<TrackerIssue>> {
+        match source.provider {
+            IssueProvider::GitHub => self.fetch_github(&source.project).await,
+            IssueProvider::GitLab => self.fetch_gitlab(&source.project).await,
+        }
+    }
+
+    /// File a new issue
+    pub async fn create_issue(
+        &self,
+        source: &IssueSource,
+        draft: &IssueDraft,
+    ) -> Result<TrackerIssue> {
+        match source.provider {
+            IssueProvider::GitHub => self.create_github(&source.project, draft).await,
+            IssueProvider::GitLab => self.create_gitlab(&source.project, draft).await,
+        }
+    }
+
+    async fn create_github(&self, project: &str, draft: &IssueDraft) -> Result<TrackerIssue> {
+        #[derive(Deserialize)]
+        struct Created {
+            number: i64,
+            html_url: String,
+        }
+
+        let token = self
+            .config
+            .github_token
+            .as_deref()
+            .context("Creating GitHub issues requires [issues] github_token in the config")?;
+        let url = format!(
+            "{}/repos/{}/issues",
+            self.config.github_api_url.trim_end_matches('/'),
+            project
+        );
+        let created: Created = self
+            .client
+            .post(&url)
+            .bearer_auth(token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header(reqwest::header::USER_AGENT, "noctum")
+            .json(&serde_json::json!({
+                "title": draft.title,
+                "body": draft.body,
+                "labels": draft.labels,
+            }))
+            .send()
+            .await
+            .with_context(|| format!("Failed to create issue in {} on GitHub", project))?
+            .error_for_status()
+            .context("GitHub returned an error status")?
+            .json()
+            .await
+            .context("Failed to parse created GitHub issue")?;
+
+        Ok(TrackerIssue {
+            number: created.number,
+            title: draft.title.clone(),
+            url: created.html_url,
+            labels: draft.labels.clone(),
+        })
+    }
+
+    async fn create_gitlab(&self, project: &str, draft: &IssueDraft) -> Result<TrackerIssue> {
+        #[derive(Deserialize)]
+        struct Created {
+            iid: i64,
+            web_url: String,
+        }
+
+        let token = self
+            .config
+            .gitlab_token
+            .as_deref()
+            .context("Creating GitLab issues requires [issues] gitlab_token in the config")?;
+        let url = format!(
+            "{}/api/v4/projects/{}/issues",
+            self.config.gitlab_url.trim_end_matches('/'),
+            project.replace('/', "%2F")
+        );
+        let created: Created = self
+            .client
+            .post(&url)
+            .header("PRIVATE-TOKEN", token)
+            .json(&serde_json::json!({
+                "title": draft.title,
+                "description": draft.body,
+                "labels": draft.labels.join(","),
+            }))
+            .send()
+            .await
+            .with_context(|| format!("Failed to create issue in {} on GitLab", project))?
+            .error_for_status()
+            .context("GitLab returned an error status")?
+            .json()
+            .await
+            .context("Failed to parse created GitLab issue")?;
+
+        Ok(TrackerIssue {
+            number: created.iid,
+            title: draft.title.clone(),
+            url: created.web_url,
+            labels: draft.labels.clone(),
+        })
+    }
+
+    async fn fetch_github(&self, project: &str) -> Result<Vec<TrackerIssue>> {
+        #[derive(Deserialize)]
+        struct Label {
+            name: String,
+        }
+        #[derive(Deserialize)]
+        struct GitHubIssue {
+            number: i64,
+            title: String,
+            html_url: String,
+            #[serde(default)]
+            labels: Vec<Label>,
+            /// Present when the "issue" is a pull request
+            pull_request: Option<serde_json::Value>,
+        }
+
+        let url = format!(
+            "{}/repos/{}/issues",
+            self.config.github_api_url.trim_end_matches('/'),
+            project
+        );
+        let mut request = self
+            .client
+            .get(&url)
+            .query(&[
+                ("state", "open"),
+                ("sort", "updated"),
+                ("per_page", &MAX_ISSUES.to_string()),
+            ])
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header(reqwest::header::USER_AGENT, "noctum");
+        if let Some(token) = &self.config.github_token {
+            request = request.bearer_auth(token);
+        }
+
+        let issues: Vec<GitHubIssue> = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch issues of {} from GitHub", project))?
+            .error_for_status()
+            .context("GitHub returned an error status")?
+            .json()
+            .await
+            .context("Failed to parse GitHub issues")?;
+
+        Ok(issues
+            .into_iter()
+            .filter(|issue| issue.pull_request.is_none())
+            .map(|issue| TrackerIssue {
+                number: issue.number,
+                title: issue.title,
+                url: issue.html_url,
+                labels: issue.labels.into_iter().map(|l| l.name).collect(),
+            })
+            .collect())
+    }
+
+    async fn fetch_gitlab(&self, project: &str) -> Result<Vec<TrackerIssue>> {
+        #[derive(Deserialize)]
+        struct GitLabIssue {
+            iid: i64,
+            title: String,
+            web_url: String,
+            #[serde(default)]
+            labels: Vec<String>,
+        }
+
+        let url = format!(
+            "{}/api/v4/projects/{}/issues",
+            self.config.gitlab_url.trim_end_matches('/'),
+            project.replace('/', "%2F")
+        );
+        let mut request = self.client.get(&url).query(&[
+            ("state", "opened"),
+            ("order_by", "updated_at"),
+            ("per_page", &MAX_ISSUES.to_string()),
+        ]);
+        if let Some(token) = &self.config.gitlab_token {
+            request = request.header("PRIVATE-TOKEN", token);
+        }
+
+        let issues: Vec<GitLabIssue> = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch issues of {} from GitLab", project))?
+            .error_for_status()
+            .context("GitLab returned an error status")?
+            .json()
+            .await
+            .context("Failed to parse GitLab issues")?;
+
+        Ok(issues
+            .into_iter()
+            .map(|issue| TrackerIssue {
+                number: issue.iid,
+                title: issue.title,
+                url: issue.web_url,
+                labels: issue.labels,
+            })
+            .collect())
+    }
+}
+
+/// An issue to be filed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueDraft {
+    pub title: String,
+    pub body: String,
+    pub labels: Vec<String>,
+}
+
+impl IssueDraft {
+    /// Draft an issue for an analysis finding. `file_path` is relative to the repository root.
+    pub fn from_analysis(
+        settings: &IssuesSection,
+        repository: &str,
+        file_path: &str,
+        result: &AnalysisResult,
+    ) -> Self {
+        let summary = result
+            .result
+            .lines()
+            .map(|line| {
+                line.trim_matches(|c: char| c == '#' || c == '*' || c == '-' || c.is_whitespace())
+            })
+            .find(|line| !line.is_empty())
+            .unwrap_or(&result.analysis_type);
+        let summary = match crate::daemon::truncate_at_char_boundary(summary, MAX_SUMMARY_LEN) {
+            truncated if truncated.len() < summary.len() => format!("{}...", truncated),
+            _ => summary.to_string(),
+        };
+
+        Self::render(
+            settings,
+            &[
+                ("repository", repository),
+                ("file", file_path),
+                ("summary", &summary),
+                ("details", &result.result),
+                ("source", &format!("{} analysis", result.analysis_type)),
+                ("severity", result.severity.as_deref().unwrap_or("unknown")),
+            ],
+        )
+    }
+
+    /// Draft an issue for a survived mutant. `file_path` is relative to the repository root.
+    pub fn from_mutation(
+        settings: &IssuesSection,
+        repository: &str,
+        file_path: &str,
+        result: &MutationResult,
+    ) -> Self {
+        let mut details = format!(
+            "No test failed when this mutation was applied to `{}`:\n\n**{}**\n",
+            file_path, result.description
+        );
+        if !result.replacements.is_empty() {
+            details.push_str("\n```diff\n");
+            for change in &result.replacements {
+                details.push_str(&format!(
+                    "- {}\n+ {}\n",
+                    change.original(),
+                    change.mutated()
+                ));
+            }
+            details.push_str("```\n");
+        }
+        if !result.reasoning.is_empty() {
+            details.push_str(&format!("\n{}\n", result.reasoning));
+        }
+
+        Self::render(
+            settings,
+            &[
+                ("repository", repository),
+                ("file", file_path),
+                (
+                    "summary",
+                    &format!("Survived mutation: {}", result.description),
+                ),
+                ("details", details.trim_end()),
+                ("source", "mutation testing"),
+                ("severity", "survived"),
+            ],
+        )
+    }
+
+    fn render(settings: &IssuesSection, values: &[(&str, &str)]) -> Self {
+        let fill = |template: &str| {
+            values
+                .iter()
+                .fold(template.to_string(), |text, (name, value)| {
+                    text.replace(&format!("{{{}}}", name), value)
+                })
+        };
+        let title_template = settings
+            .title_template
+            .as_deref()
+            .unwrap_or(DEFAULT_TITLE_TEMPLATE);
+        let body_template = settings
+            .body_template
+            .as_deref()
+            .unwrap_or(DEFAULT_BODY_TEMPLATE);
+
+        Self {
+            // Titles are single-line on both trackers
+            title: fill(title_template)
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            body: fill(body_template),
+            labels: settings.labels.clone(),
+        }
+    }
+}
+
+/// Prompt section listing known open issues (empty if there are none)
+pub fn prompt_context(issues: &[Issue]) -> String {
+    if issues.is_empty() {
+        return String::new();
+    }
+
+    let mut context = String::from(
+        "# Known Open Issues\n\
+         The project's issue tracker lists these open issues. If a finding relates to one, \
+         mention it by number (e.g. #12):\n",
+    );
+    for issue in issues.iter().take(MAX_PROMPT_ISSUES) {
+        let title = crate::daemon::truncate_at_char_boundary(&issue.title, MAX_PROMPT_TITLE_LEN);
+        context.push_str(&format!("- #{} {}", issue.number, title));
+        if !issue.labels.is_empty() {
+            context.push_str(&format!(" [{}]", issue.labels));
+        }
+        context.push('\n');
+    }
+    if issues.len() > MAX_PROMPT_ISSUES {
+        context.push_str(&format!(
+            "- ...and {} more\n",
+            issues.len() - MAX_PROMPT_ISSUES
+        ));
+    }
+    context.push('\n');
+    context
+}
+
+/// Issues an analysis result refers to: by number (`#12`), by its full title,
+/// or by all significant words of a longer title on a single line
+pub fn matching_issues<'a>(text: &str, issues: &'a [Issue]) -> Vec<&'a Issue> {
+    let normalized_text = normalize(text);
+    let lines: Vec<String> = text.lines().map(normalize).collect();
+
+    issues
+        .iter()
+        .filter(|issue| {
+            let reference = format!("#{}", issue.number);
+            let mentioned = text.match_indices(&reference).any(|(i, _)| {
+                !text[i + reference.len()..].starts_with(|c: char| c.is_ascii_digit())
+            });
+            if mentioned {
+                return true;
+            }
+
+            let title = normalize(&issue.title);
+            if title.is_empty() {
+                return false;
+            }
+            if format!(" {} ", normalized_text).contains(&format!(" {} ", title)) {
+                return true;
+            }
+
+            let words: Vec<&str> = title.split(' ').filter(|w| w.len() >= 4).collect();
+            words.len() >= MIN_MATCH_WORDS
+                && lines.iter().any(|line| {
+                    let line_words: Vec<&str> = line.split(' ').collect();
+                    words.iter().all(|w| line_words.contains(w))
+                })
+        })
+        .collect()
+}
+
+/// Lowercase alphanumeric words separated by single spaces
+fn normalize(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MutationReplacement;
+
+    fn issue(number: i64, title: &str, labels: &str) -> Issue {
+        Issue {
+            repository_id: 1,
+            number,
+            title: title.to_string(),
+            url: format!("https://github.com/acme/app/issues/{}", number),
+            labels: labels.to_string(),
+            fetched_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_source_from_remote() {
+        let github = |project: &str| {
+            Some(IssueSource {
+                provider: IssueProvider::GitHub,
+                project: project.to_string(),
+            })
+        };
+        assert_eq!(
+            IssueSource::from_remote("https://github.com/acme/app.git", "gitlab.com"),
+            github("acme/app")
+        );
+        assert_eq!(
+            IssueSource::from_remote("git@github.com:acme/app.git\n", "gitlab.com"),
+            github("acme/app")
+        );
+        assert_eq!(
+            IssueSource::from_remote("ssh://git@github.com:22/acme/app", "gitlab.com"),
+            github("acme/app")
+        );
+        assert_eq!(
+            IssueSource::from_remote(
+                "git@gitlab.example.com:group/sub/app.git",
+                "gitlab.example.com"
+            ),
+            Some(IssueSource {
+                provider: IssueProvider::GitLab,
+                project: "group/sub/app".to_string(),
+            })
+        );
+
+        assert_eq!(
+            IssueSource::from_remote("https://bitbucket.org/acme/app", "gitlab.com"),
+            None
+        );
+        assert_eq!(
+            IssueSource::from_remote("https://github.com/acme", "gitlab.com"),
+            None
+        );
+        assert_eq!(IssueSource::from_remote("/srv/git/app", "gitlab.com"), None);
+    }
+
+    #[test]
+    fn test_prompt_context() {
+        assert_eq!(prompt_context(&[]), "");
+
+        let context = prompt_context(&[
+            issue(12, "Parser panics on empty input", "bug,parser"),
+            issue(14, "Add dark mode", ""),
+        ]);
+        assert!(context.starts_with("# Known Open Issues\n"));
+        assert!(context.contains("- #12 Parser panics on empty input [bug,parser]\n"));
+        assert!(context.contains("- #14 Add dark mode\n"));
+    }
+
+    #[test]
+    fn test_matching_issues() {
+        let issues = vec![
+            issue(12, "Parser panics on empty input", "bug"),
+            issue(3, "Cache invalidation", ""),
+            issue(1, "Slow startup", ""),
+        ];
+        let numbers = |text: &str| -> Vec<i64> {
+            matching_issues(text, &issues)
+                .iter()
+                .map(|i| i.number)
+                .collect()
+        };
+
+        assert_eq!(
+            numbers("## Bugs\n- The parser panics when given empty input."),
+            vec![12]
+        );
+        assert_eq!(numbers("Likely the cause of #3."), vec![3]);
+        assert_eq!(numbers("See #12345 and #30"), Vec::<i64>::new());
+        assert_eq!(
+            numbers("**Cache invalidation**: entries never expire"),
+            vec![3]
+        );
+        // Short titles need an exact match, not scattered words
+        assert_eq!(numbers("Startup is slow"), Vec::<i64>::new());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_github_issues_skips_pull_requests() {
+        use wiremock::matchers::{header, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/acme/app/issues"))
+            .and(query_param("state", "open"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {
+                    "number": 12,
+                    "title": "Parser panics",
+                    "html_url": "https://github.com/acme/app/issues/12",
+                    "labels": [{ "name": "bug" }]
+                },
+                {
+                    "number": 13,
+                    "title": "Fix parser",
+                    "html_url": "https://github.com/acme/app/pull/13",
+                    "labels": [],
+                    "pull_request": {}
+                }
+            ])))
+            .mount(&server)
+            .await;
+
+        let client = IssueClient::new(IssueTrackerConfig {
+            github_token: Some("secret".to_string()),
+            github_api_url: server.uri(),
+            ..Default::default()
+        });
+        let issues = client
+            .fetch_open_issues(&IssueSource {
+                provider: IssueProvider::GitHub,
+                project: "acme/app".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            issues,
+            vec![TrackerIssue {
+                number: 12,
+                title: "Parser panics".to_string(),
+                url: "https://github.com/acme/app/issues/12".to_string(),
+                labels: vec!["bug".to_string()],
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_gitlab_issues() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v4/projects/group%2Fapp/issues"))
+            .and(header("private-token", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {
+                    "iid": 7,
+                    "title": "Slow startup",
+                    "web_url": "https://gitlab.com/group/app/-/issues/7",
+                    "labels": ["performance"]
+                }
+            ])))
+            .mount(&server)
+            .await;
+
+        let client = IssueClient::new(IssueTrackerConfig {
+            gitlab_token: Some("secret".to_string()),
+            gitlab_url: server.uri(),
+            ..Default::default()
+        });
+        let issues = client
+            .fetch_open_issues(&IssueSource {
+                provider: IssueProvider::GitLab,
+                project: "group/app".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].number, 7);
+        assert_eq!(issues[0].labels, vec!["performance".to_string()]);
+    }
+
+    fn analysis_result(result: &str) -> AnalysisResult {
+        AnalysisResult {
+            id: 1,
+            repository_id: 1,
+            file_path: "/repo/src/parser.rs".to_string(),
+            analysis_type: "code_understanding".to_string(),
+            result: result.to_string(),
+            severity: Some("high".to_string()),
+            content_hash: None,
+            created_at: String::new(),
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            issue_url: None,
+        }
+    }
+
+    #[test]
+    fn test_draft_from_analysis() {
+        let result = analysis_result(
+            "## Unchecked index\n\nThe parser indexes `tokens[0]` without a length check.",
+        );
+        let draft =
+            IssueDraft::from_analysis(&IssuesSection::default(), "app", "src/parser.rs", &result);
+        assert_eq!(draft.title, "Unchecked index in src/parser.rs");
+        assert!(draft.body.starts_with(&result.result));
+        assert!(draft.body.ends_with(
+            "_Filed by Noctum from the code_understanding analysis of `src/parser.rs` in app._"
+        ));
+        assert!(draft.labels.is_empty());
+
+        let settings = IssuesSection {
+            labels: vec!["noctum".to_string()],
+            title_template: Some("[{severity}] {file}".to_string()),
+            body_template: Some("{repository}: {summary}".to_string()),
+            ..Default::default()
+        };
+        let draft = IssueDraft::from_analysis(&settings, "app", "src/parser.rs", &result);
+        assert_eq!(draft.title, "[high] src/parser.rs");
+        assert_eq!(draft.body, "app: Unchecked index");
+        assert_eq!(draft.labels, vec!["noctum".to_string()]);
+    }
+
+    #[test]
+    fn test_draft_from_mutation() {
+        let result = MutationResult {
+            id: 1,
+            repository_id: 1,
+            file_path: "/repo/src/lib.rs".to_string(),
+            description: "Changed > to >=".to_string(),
+            reasoning: "Boundary of the retry limit".to_string(),
+            replacements: vec![MutationReplacement {
+                line_number: 3,
+                find: ">".to_string(),
+                replace: ">=".to_string(),
+                original_line: "    if attempts > limit {".to_string(),
+            }],
+            test_outcome: "survived".to_string(),
+            killing_test: None,
+            test_output: None,
+            execution_time_ms: None,
+            content_hash: None,
+            created_at: String::new(),
+            toolchain: None,
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            covered: Some(true),
+            project: None,
+            issue_url: None,
+            status: "new".to_string(),
+        };
+
+        let draft =
+            IssueDraft::from_mutation(&IssuesSection::default(), "app", "src/lib.rs", &result);
+        assert_eq!(
+            draft.title,
+            "Survived mutation: Changed > to >= in src/lib.rs"
+        );
+        assert!(draft
+            .body
+            .contains("```diff\n-     if attempts > limit {\n+     if attempts >= limit {\n```"));
+        assert!(draft.body.contains("Boundary of the retry limit"));
+    }
+
+    #[tokio::test]
+    async fn test_create_github_issue() {
+        use wiremock::matchers::{body_partial_json, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/repos/acme/app/issues"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(serde_json::json!({
+                "title": "Crash",
+                "labels": ["noctum"]
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "number": 42,
+                "html_url": "https://github.com/acme/app/issues/42"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let source = IssueSource {
+            provider: IssueProvider::GitHub,
+            project: "acme/app".to_string(),
+        };
+        let draft = IssueDraft {
+            title: "Crash".to_string(),
+            body: "Details".to_string(),
+            labels: vec!["noctum".to_string()],
+        };
+
+        let client = IssueClient::new(IssueTrackerConfig {
+            github_token: Some("secret".to_string()),
+            github_api_url: server.uri(),
+            ..Default::default()
+        });
+        let created = client.create_issue(&source, &draft).await.unwrap();
+        assert_eq!(created.number, 42);
+        assert_eq!(created.url, "https://github.com/acme/app/issues/42");
+
+        // Creating issues needs a token
+        let anonymous = IssueClient::new(IssueTrackerConfig {
+            github_api_url: server.uri(),
+            ..Default::default()
+        });
+        assert!(anonymous.create_issue(&source, &draft).await.is_err());
+    }
+}
@@ -0,0 +1,586 @@
+//! Languages configured with external commands.
+//!
+//! A `[[languages]]` entry of the config file describes a stack Noctum has no
+//! built-in support for: the extensions of its sources, the files marking a
+//! project root, and the shell commands that compile and test it. Prompts come
+//! from the entry's templates, or generic ones naming the language.
+
+use super::{TestOutcome, TestRunResult};
+use crate::analyzer::respond_in;
+use crate::config::LanguageConfig;
+use crate::project::walk_source_tree;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::process::Command;
+
+/// Directories skipped for every command language, besides hidden ones.
+const DEFAULT_SKIP_DIRS: &[&str] = &["node_modules", "vendor"];
+
+/// Handler of a language configured in `[[languages]]`.
+///
+/// Handlers live for the whole process, so [`super::Language`] stays `Copy`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct CommandLanguage {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    pub markers: &'static [&'static str],
+    pub skip_dirs: &'static [&'static str],
+    pub compile_command: Option<&'static str>,
+    pub test_command: Option<&'static str>,
+    analysis_prompt: Option<&'static str>,
+    mutation_prompt: Option<&'static str>,
+    architecture_prompt: Option<&'static str>,
+}
+
+impl CommandLanguage {
+    /// Create the handler of a configured language, kept until the process exits.
+    pub fn leak(config: &LanguageConfig) -> &'static CommandLanguage {
+        let extensions = config
+            .extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.'))
+            .filter(|ext| !ext.is_empty())
+            .map(leak_str)
+            .collect();
+        let skip_dirs = DEFAULT_SKIP_DIRS
+            .iter()
+            .copied()
+            .chain(config.skip_dirs.iter().map(|dir| leak_str(dir)))
+            .collect();
+
+        Box::leak(Box::new(CommandLanguage {
+            name: leak_str(&config.name),
+            extensions: leak_slice(extensions),
+            markers: leak_slice(config.markers.iter().map(|m| leak_str(m)).collect()),
+            skip_dirs: leak_slice(skip_dirs),
+            compile_command: config.compile_command.as_deref().map(leak_str),
+            test_command: config.test_command.as_deref().map(leak_str),
+            analysis_prompt: config.analysis_prompt.as_deref().map(leak_str),
+            mutation_prompt: config.mutation_prompt.as_deref().map(leak_str),
+            architecture_prompt: config.architecture_prompt.as_deref().map(leak_str),
+        }))
+    }
+
+    /// Whether a directory is the root of a project in this language.
+    pub fn is_project_root(&self, dir: &Path) -> bool {
+        self.markers.iter().any(|marker| dir.join(marker).exists())
+    }
+
+    /// Find all files with one of the language's extensions in a directory.
+    pub fn find_source_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+
+        if !dir.is_dir() {
+            return Ok(files);
+        }
+
+        for entry in walk_source_tree(dir, self.skip_dirs) {
+            let entry = entry?;
+            let path = entry.path();
+
+            let is_source = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|ext| self.extensions.contains(&ext));
+            if path.is_file() && is_source {
+                files.push(path.to_path_buf());
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Run the configured compile command. Languages without one always pass.
+    ///
+    /// Returns `Ok(())` if compilation succeeds, `Err(error_output)` if it fails.
+    pub async fn compile_check(
+        &self,
+        repo_path: &Path,
+        timeout_seconds: u64,
+    ) -> Result<(), String> {
+        let Some(command) = self.compile_command else {
+            return Ok(());
+        };
+
+        let check_future = shell(command).current_dir(repo_path).output();
+
+        match tokio::time::timeout(
+            std::time::Duration::from_secs(timeout_seconds),
+            check_future,
+        )
+        .await
+        {
+            Ok(Ok(output)) => {
+                if output.status.success() {
+                    Ok(())
+                } else {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    Err(format!("{}\n{}", stdout, stderr))
+                }
+            }
+            Ok(Err(e)) => Err(format!("Failed to run '{}': {}", command, e)),
+            Err(_) => Err(format!("'{}' timed out", command)),
+        }
+    }
+
+    /// Run the configured test command.
+    ///
+    /// A failing compile command counts as a compile error, since test
+    /// commands of most stacks don't tell compile errors apart by exit code.
+    pub async fn run_tests(&self, project_root: &Path, timeout_seconds: u64) -> TestRunResult {
+        let start = Instant::now();
+
+        let Some(command) = self.test_command else {
+            return TestRunResult {
+                outcome: TestOutcome::CompileError,
+                failing_test: None,
+                output: Some(format!("No test command configured for {}", self.name)),
+                duration_ms: 0,
+            };
+        };
+
+        if let Err(output) = self.compile_check(project_root, timeout_seconds).await {
+            return TestRunResult {
+                outcome: TestOutcome::CompileError,
+                failing_test: None,
+                output: Some(truncate_output(&output, 10_000)),
+                duration_ms: start.elapsed().as_millis() as u64,
+            };
+        }
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(timeout_seconds),
+            shell(command).current_dir(project_root).output(),
+        )
+        .await;
+
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        match result {
+            Ok(Ok(output)) => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let combined = format!("{}\n{}", stdout, stderr);
+
+                TestRunResult {
+                    outcome: if output.status.success() {
+                        TestOutcome::Passed
+                    } else {
+                        TestOutcome::Failed
+                    },
+                    failing_test: None,
+                    output: Some(truncate_output(&combined, 10_000)),
+                    duration_ms,
+                }
+            }
+            Ok(Err(e)) => TestRunResult {
+                outcome: TestOutcome::CompileError,
+                failing_test: None,
+                output: Some(format!("Failed to execute '{}': {}", command, e)),
+                duration_ms,
+            },
+            Err(_) => TestRunResult {
+                outcome: TestOutcome::Timeout,
+                failing_test: None,
+                output: Some(format!("Test timed out after {} seconds", timeout_seconds)),
+                duration_ms,
+            },
+        }
+    }
+
+    /// Generate a prompt for code analysis.
+    pub fn analysis_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
+        if let Some(template) = self.analysis_prompt {
+            return self.render(template, file_path, content);
+        }
+        let name = self.name;
+        format!(
+            "Analyze the following {name} code and provide a brief summary of what it does:\n\n\
+             File: {file_path}\n\n\
+             ```\n{content}\n```\n\n\
+             Provide a concise analysis including:\n\
+             1. Purpose of the code\n\
+             2. Key functions, types and modules\n\
+             3. Any potential issues or improvements\n\
+             4. Up to two specific code modification recommendations\n\n\
+             {respond}",
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for mutation testing.
+    pub fn mutation_prompt(&self, file_path: &str, content: &str) -> String {
+        let numbered_code = add_line_numbers(content);
+        if let Some(template) = self.mutation_prompt {
+            return self.render(template, file_path, &numbered_code);
+        }
+        let name = self.name;
+        format!(
+            r#"You are a mutation testing expert. Analyze this {name} code and generate up to 3 small, targeted mutations.
+
+VALID mutation types:
+- Comparison operators: > to >=, < to <=, == to !=, etc.
+- Boolean literals: true to false, false to true
+- Arithmetic operators: + to -, * to /, etc.
+- Boundary values: n to n+1, n to n-1
+- Numeric constants: 0 to 1, 1 to 0
+
+RULES:
+- The "find" text must be copied EXACTLY from the code (same spacing, same characters)
+- The "replace" text should differ by only ONE small change
+- Skip comments, imports, type definitions, and test code
+
+File: {file_path}
+
+```
+{numbered_code}
+```
+
+For each mutation provide:
+- line_number: The line where this expression appears
+- find: The EXACT text to find (copy it precisely from the code above)
+- replace: The modified text
+- reasoning: Why this tests important logic
+- description: What changed (e.g., "Changed > to >=")"#
+        )
+    }
+
+    /// Find context files (project marker files, READMEs, markdown docs) in a directory.
+    pub fn find_context_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+
+        if !dir.is_dir() {
+            return Ok(files);
+        }
+
+        for entry in walk_source_tree(dir, self.skip_dirs) {
+            let entry = entry?;
+            let path = entry.path();
+
+            if !path.is_file() {
+                continue;
+            }
+
+            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let is_context_file = self.markers.contains(&file_name)
+                || file_name.to_lowercase().starts_with("readme")
+                || path.extension().and_then(|e| e.to_str()) == Some("md");
+            if is_context_file {
+                files.push(path.to_path_buf());
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Generate a documentation analysis prompt for a marker file or markdown doc.
+    pub fn documentation_prompt(
+        &self,
+        file_path: &str,
+        content: &str,
+        output_language: &str,
+    ) -> String {
+        format!(
+            r#"Analyze this file of a {} project and extract project-level information:
+
+File: {}
+
+```
+{}
+```
+
+Provide a concise summary including:
+1. **Purpose**: What is this project/component about?
+2. **Key Features**: What are the main capabilities described?
+3. **Dependencies**: What major libraries or tools does it use?
+4. **Architecture Notes**: Any architectural patterns or design decisions mentioned?
+5. **Project Type**: Is this a library, application, tool, etc.?
+
+{respond}"#,
+            self.name,
+            file_path,
+            content,
+            respond = respond_in(output_language, false),
+        )
+    }
+
+    /// Generate a prompt for architecture-focused file analysis.
+    pub fn architecture_file_analysis_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        if let Some(template) = self.architecture_prompt {
+            return self.render(template, file_path, code);
+        }
+        let name = self.name;
+        format!(
+            r#"Analyze this {name} file from an ARCHITECTURAL perspective.
+
+File: {file_path}
+
+```
+{code}
+```
+
+Extract ONLY the following (skip if not present):
+1. **Layer**: Where does this fit? (API, business logic, data access, utilities, configuration)
+2. **Key Abstractions**: Main types, modules or functions defined
+3. **Dependencies**: What does this module depend on? (other modules of the project)
+4. **Public API**: What does this file provide to others?
+5. **Patterns**: Design patterns used
+
+Be concise - this will be aggregated with other files for an overall architecture summary.
+
+{respond}"#,
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for architecture diagram extraction.
+    pub fn diagram_architecture_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        let name = self.name;
+        format!(
+            r#"Analyze this {name} file for ARCHITECTURAL diagram information.
+
+File: {file_path}
+
+```
+{code}
+```
+
+Extract ONLY the following for diagram generation:
+1. **Component Name**: The logical name of this module/component
+2. **Component Type**: service, handler, model, utility, config, etc.
+3. **Dependencies**: List of project modules this file uses (not external libraries)
+4. **Public Interface**: Main public functions and what they provide
+5. **Data Flow**: What data comes in and what goes out
+
+Format as structured text that can be aggregated later.
+
+{respond}"#,
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for data flow diagram extraction.
+    pub fn diagram_data_flow_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        let name = self.name;
+        format!(
+            r#"Analyze this {name} file for DATA FLOW diagram information.
+
+File: {file_path}
+
+```
+{code}
+```
+
+Extract ONLY the following for data flow diagram generation:
+1. **Data Sources**: Where does data come from? (requests, files, databases, messages)
+2. **Data Transformations**: How is data processed or transformed?
+3. **Data Sinks**: Where does data go? (responses, files, databases, messages)
+4. **State**: What state is held or modified?
+5. **Side Effects**: Network calls, file I/O, processes
+
+Skip if this file has no significant data flow.
+
+{respond}"#,
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for database schema diagram extraction.
+    pub fn diagram_database_schema_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        let name = self.name;
+        format!(
+            r#"Analyze this {name} file for DATABASE/SCHEMA diagram information.
+
+File: {file_path}
+
+```
+{code}
+```
+
+Extract ONLY the following:
+1. **Entities/Tables**: Types or schemas that map to database tables
+2. **Fields/Columns**: Field names and types
+3. **Relationships**: Foreign keys, references between entities
+4. **Database Operations**: Queries, migrations, or ORM usage
+
+Skip if this file has no database-related content.
+
+{respond}"#,
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for sequence diagram extraction.
+    pub fn diagram_sequence_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        let name = self.name;
+        format!(
+            r#"Analyze this {name} file for SEQUENCE diagram information.
+
+File: {file_path}
+
+```
+{code}
+```
+
+Extract ONLY the following:
+1. **Entry Points**: Public functions, handlers or jobs that start a flow
+2. **Call Sequence**: The ordered calls each entry point makes, one per line as `caller -> callee: action`
+3. **Responses**: What is returned or emitted at the end of each flow
+
+If this file has no significant flow, say "No significant interaction flow".
+
+{respond}"#,
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Fill in a prompt template. The content goes in last, so placeholders
+    /// appearing in the code are left alone.
+    fn render(&self, template: &str, file_path: &str, content: &str) -> String {
+        template
+            .replace("{language}", self.name)
+            .replace("{file_path}", file_path)
+            .replace("{content}", content)
+    }
+}
+
+/// A shell running `command`, killed when the command's future is dropped.
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command).kill_on_drop(true);
+    cmd
+}
+
+fn leak_str(s: &str) -> &'static str {
+    Box::leak(s.to_string().into_boxed_str())
+}
+
+fn leak_slice(items: Vec<&'static str>) -> &'static [&'static str] {
+    Box::leak(items.into_boxed_slice())
+}
+
+/// Add line numbers to code for mutation prompts.
+fn add_line_numbers(code: &str) -> String {
+    code.lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:5} | {}", i + 1, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Truncate output to a maximum length.
+fn truncate_output(output: &str, max_len: usize) -> String {
+    if output.len() <= max_len {
+        output.to_string()
+    } else {
+        let end = (0..=max_len)
+            .rev()
+            .find(|&i| output.is_char_boundary(i))
+            .unwrap_or(0);
+        format!("{}...\n(output truncated)", &output[..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn elixir() -> &'static CommandLanguage {
+        CommandLanguage::leak(&LanguageConfig {
+            name: "Elixir".to_string(),
+            extensions: vec!["ex".to_string(), ".exs".to_string()],
+            markers: vec!["mix.exs".to_string()],
+            skip_dirs: vec!["_build".to_string(), "deps".to_string()],
+            compile_command: Some("test -f lib/app.ex".to_string()),
+            test_command: Some("grep -q passing lib/app.ex".to_string()),
+            analysis_prompt: Some("Review {language} file {file_path}:\n{content}".to_string()),
+            ..LanguageConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_find_source_files() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("lib")).unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("_build/dev")).unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("deps/plug")).unwrap();
+        std::fs::write(temp_dir.path().join("mix.exs"), "").unwrap();
+        std::fs::write(temp_dir.path().join("lib/app.ex"), "").unwrap();
+        std::fs::write(temp_dir.path().join("lib/notes.txt"), "").unwrap();
+        std::fs::write(temp_dir.path().join("_build/dev/gen.ex"), "").unwrap();
+        std::fs::write(temp_dir.path().join("deps/plug/plug.ex"), "").unwrap();
+
+        let language = elixir();
+        assert!(language.is_project_root(temp_dir.path()));
+        let mut files = language.find_source_files(temp_dir.path()).unwrap();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                temp_dir.path().join("lib/app.ex"),
+                temp_dir.path().join("mix.exs")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_tests_with_configured_commands() {
+        let temp_dir = TempDir::new().unwrap();
+        let language = elixir();
+
+        // The compile command fails without lib/app.ex
+        let result = language.run_tests(temp_dir.path(), 10).await;
+        assert_eq!(result.outcome, TestOutcome::CompileError);
+
+        std::fs::create_dir_all(temp_dir.path().join("lib")).unwrap();
+        std::fs::write(temp_dir.path().join("lib/app.ex"), "failing").unwrap();
+        let result = language.run_tests(temp_dir.path(), 10).await;
+        assert_eq!(result.outcome, TestOutcome::Failed);
+
+        std::fs::write(temp_dir.path().join("lib/app.ex"), "passing").unwrap();
+        let result = language.run_tests(temp_dir.path(), 10).await;
+        assert_eq!(result.outcome, TestOutcome::Passed);
+    }
+
+    #[test]
+    fn test_prompt_templates() {
+        let language = elixir();
+        assert_eq!(
+            language.analysis_prompt("lib/app.ex", "def f, do: \"{file_path}\"", "English"),
+            "Review Elixir file lib/app.ex:\ndef f, do: \"{file_path}\""
+        );
+
+        // Without a template, the generic prompt names the language
+        let prompt = language.mutation_prompt("lib/app.ex", "x > 0");
+        assert!(prompt.contains("this Elixir code"));
+        assert!(prompt.contains("    1 | x > 0"));
+    }
+}
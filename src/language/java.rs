@@ -0,0 +1,816 @@
+//! Java and Kotlin language support.
+//!
+//! Both languages build with Maven or Gradle and share the `src/main/<lang>`
+//! source layout, so one handler covers them. Prompts name the language of the
+//! file being analyzed (`.java` or `.kt`).
+
+use super::{TestOutcome, TestRunResult};
+use crate::analyzer::respond_in;
+use crate::project::walk_source_tree;
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+use std::time::Instant;
+use tokio::process::Command;
+
+/// Java/Kotlin language handler.
+pub struct JavaLanguage;
+
+/// Directories that hold build output or tool state rather than sources.
+const SKIP_DIRS: &[&str] = &["target", "build", "out", ".gradle", ".idea", "node_modules"];
+
+/// Gradle build files, Groovy and Kotlin DSL.
+pub const GRADLE_BUILD_FILES: &[&str] = &[
+    "build.gradle",
+    "build.gradle.kts",
+    "settings.gradle",
+    "settings.gradle.kts",
+];
+
+/// Build tool of a JVM project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildTool {
+    Maven,
+    Gradle,
+}
+
+impl BuildTool {
+    /// Detect the build tool from the files in a project root. Maven wins
+    /// when both are present, since Gradle builds rarely keep a `pom.xml`.
+    pub fn detect(project_root: &Path) -> Option<Self> {
+        if project_root.join("pom.xml").exists() {
+            Some(BuildTool::Maven)
+        } else if GRADLE_BUILD_FILES
+            .iter()
+            .any(|file| project_root.join(file).exists())
+        {
+            Some(BuildTool::Gradle)
+        } else {
+            None
+        }
+    }
+
+    /// Program and arguments that compile main and test sources.
+    fn compile_command(&self, project_root: &Path) -> (String, Vec<&'static str>) {
+        match self {
+            BuildTool::Maven => ("mvn".to_string(), vec!["-q", "test-compile"]),
+            BuildTool::Gradle => (gradle_program(project_root), vec!["-q", "testClasses"]),
+        }
+    }
+
+    /// Program and arguments that run the test suite.
+    fn test_command(&self, project_root: &Path) -> (String, Vec<&'static str>) {
+        match self {
+            BuildTool::Maven => ("mvn".to_string(), vec!["-q", "test"]),
+            BuildTool::Gradle => (gradle_program(project_root), vec!["test"]),
+        }
+    }
+}
+
+/// Context file types that provide project-level information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextFileType {
+    /// pom.xml - Maven project descriptor
+    PomXml,
+    /// build.gradle(.kts) or settings.gradle(.kts) - Gradle build script
+    GradleBuild,
+    /// README or other markdown documentation
+    Markdown,
+}
+
+impl JavaLanguage {
+    /// Find all Java and Kotlin sources under `src/main/java` or
+    /// `src/main/kotlin` in a directory.
+    ///
+    /// Modules of a multi-module build are projects of their own, so their
+    /// files are left out.
+    pub fn find_source_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+
+        for path in project_files(dir)? {
+            let is_source = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|ext| ext == "java" || ext == "kt");
+            if is_source && is_main_source(path.strip_prefix(dir)?) {
+                files.push(path);
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Compile main and test sources with Maven (`mvn -q test-compile`) or
+    /// Gradle (`./gradlew -q testClasses`).
+    ///
+    /// Returns `Ok(())` if compilation succeeds, `Err(error_output)` if it fails.
+    pub async fn compile_check(
+        &self,
+        repo_path: &Path,
+        timeout_seconds: u64,
+    ) -> Result<(), String> {
+        let Some(build_tool) = BuildTool::detect(repo_path) else {
+            return Err("No pom.xml or Gradle build file found".to_string());
+        };
+        let (program, args) = build_tool.compile_command(repo_path);
+
+        let check_future = Command::new(&program)
+            .args(&args)
+            .current_dir(repo_path)
+            .kill_on_drop(true)
+            .output();
+
+        match tokio::time::timeout(
+            std::time::Duration::from_secs(timeout_seconds),
+            check_future,
+        )
+        .await
+        {
+            Ok(Ok(output)) => {
+                if output.status.success() {
+                    Ok(())
+                } else {
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    Err(format!("{}\n{}", stdout, stderr))
+                }
+            }
+            Ok(Err(e)) => Err(format!("Failed to run {}: {}", program, e)),
+            Err(_) => Err(format!("{} compile timed out", program)),
+        }
+    }
+
+    /// Run tests with `mvn -q test` or `./gradlew test`.
+    ///
+    /// The build is killed when it runs past the timeout, so a mutant that
+    /// makes a test loop forever doesn't keep the JVM running.
+    pub async fn run_tests(&self, project_root: &Path, timeout_seconds: u64) -> TestRunResult {
+        let start = Instant::now();
+
+        let Some(build_tool) = BuildTool::detect(project_root) else {
+            return TestRunResult {
+                outcome: TestOutcome::CompileError,
+                failing_test: None,
+                output: Some("No pom.xml or Gradle build file found".to_string()),
+                duration_ms: 0,
+            };
+        };
+        let (program, args) = build_tool.test_command(project_root);
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(timeout_seconds),
+            Command::new(&program)
+                .args(&args)
+                .current_dir(project_root)
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await;
+
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        match result {
+            Ok(Ok(output)) => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let combined = format!("{}\n{}", stdout, stderr);
+                let truncated = truncate_output(&combined, 10_000);
+
+                let outcome = if output.status.success() {
+                    TestOutcome::Passed
+                } else if is_compile_error(&combined) {
+                    TestOutcome::CompileError
+                } else {
+                    TestOutcome::Failed
+                };
+                let failing_test = if outcome == TestOutcome::Failed {
+                    extract_failing_test(&combined)
+                } else {
+                    None
+                };
+
+                TestRunResult {
+                    outcome,
+                    failing_test,
+                    output: Some(truncated),
+                    duration_ms,
+                }
+            }
+            Ok(Err(e)) => TestRunResult {
+                outcome: TestOutcome::CompileError,
+                failing_test: None,
+                output: Some(format!("Failed to execute {}: {}", program, e)),
+                duration_ms,
+            },
+            Err(_) => TestRunResult {
+                outcome: TestOutcome::Timeout,
+                failing_test: None,
+                output: Some(format!("Test timed out after {} seconds", timeout_seconds)),
+                duration_ms,
+            },
+        }
+    }
+
+    /// Generate a prompt for code analysis.
+    pub fn analysis_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
+        let (name, fence) = dialect(file_path);
+        format!(
+            "Analyze the following {name} code and provide a brief summary of what it does:\n\n\
+             File: {file_path}\n\n\
+             ```{fence}\n{content}\n```\n\n\
+             Provide a concise analysis including:\n\
+             1. Purpose of the code\n\
+             2. Key classes, interfaces and methods\n\
+             3. Any potential issues or improvements (null handling, resource leaks, \
+             exception handling, thread safety)\n\
+             4. Up to two specific code modification recommendations\n\n\
+             {respond}",
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for mutation testing.
+    pub fn mutation_prompt(&self, file_path: &str, content: &str) -> String {
+        let (name, _) = dialect(file_path);
+        let numbered_code = add_line_numbers(content);
+        format!(
+            r#"You are a mutation testing expert. Analyze this {name} code and generate up to 3 small, targeted mutations.
+
+VALID mutation types:
+- Comparison operators: > to >=, < to <=, == to !=, etc.
+- Boolean literals: true to false, false to true
+- Arithmetic operators: + to -, * to /, etc.
+- Boundary values: n to n+1, n to n-1
+- Return values: null to a default value, Optional.of(x) to Optional.empty()
+- Collection checks: isEmpty() to !isEmpty()
+- Numeric constants: 0 to 1, 1 to 0
+
+RULES:
+- The "find" text must be copied EXACTLY from the code (same spacing, same characters)
+- The "replace" text should differ by only ONE small change
+- Skip comments, imports, annotations, type declarations, and test code
+
+File: {file_path}
+
+```
+{numbered_code}
+```
+
+For each mutation provide:
+- line_number: The line where this expression appears
+- find: The EXACT text to find (copy it precisely from the code above)
+- replace: The modified text
+- reasoning: Why this tests important logic
+- description: What changed (e.g., "Changed > to >=")
+
+Example for line `   42 |         if (count > 0) {{`:
+  line_number: 42
+  find: "count > 0"
+  replace: "count >= 0"
+  description: "Changed > to >=""#
+        )
+    }
+
+    /// Find context files (Maven/Gradle build files, READMEs, markdown docs) in a directory.
+    pub fn find_context_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        Ok(project_files(dir)?
+            .into_iter()
+            .filter(|path| self.context_file_type(path).is_some())
+            .collect())
+    }
+
+    /// Determine the type of a context file.
+    pub fn context_file_type(&self, file_path: &Path) -> Option<ContextFileType> {
+        let file_name = file_path.file_name().and_then(|n| n.to_str())?;
+
+        if file_name == "pom.xml" {
+            Some(ContextFileType::PomXml)
+        } else if GRADLE_BUILD_FILES.contains(&file_name) {
+            Some(ContextFileType::GradleBuild)
+        } else if file_name.to_lowercase().starts_with("readme")
+            || file_path.extension().and_then(|e| e.to_str()) == Some("md")
+        {
+            Some(ContextFileType::Markdown)
+        } else {
+            None
+        }
+    }
+
+    /// Generate a documentation analysis prompt based on context file type.
+    pub fn documentation_prompt(
+        &self,
+        file_path: &str,
+        content: &str,
+        output_language: &str,
+    ) -> String {
+        match self.context_file_type(Path::new(file_path)) {
+            Some(ContextFileType::PomXml) | Some(ContextFileType::GradleBuild) => {
+                self.build_file_prompt(file_path, content, output_language)
+            }
+            _ => self.markdown_doc_prompt(file_path, content, output_language),
+        }
+    }
+
+    /// Generate a prompt for analyzing a Maven or Gradle build file.
+    fn build_file_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
+        let fence = if file_path.ends_with(".xml") {
+            "xml"
+        } else if file_path.ends_with(".kts") {
+            "kotlin"
+        } else {
+            "groovy"
+        };
+        format!(
+            r#"Analyze this build file and extract project-level information:
+
+File: {file_path}
+
+```{fence}
+{content}
+```
+
+Provide a concise summary including:
+1. **Project Name and Description**: What is this project or module?
+2. **Purpose**: What does this project do based on its description and dependencies?
+3. **Modules**: What sub-modules or sub-projects does the build include?
+4. **Key Dependencies**: What major libraries/frameworks does it use? (Spring, Hibernate, Ktor, etc.)
+5. **Build Setup**: Java/Kotlin version, plugins and test frameworks (JUnit, TestNG, Kotest, etc.)
+6. **Project Type**: Is this a library, application, multi-module aggregator, etc.?
+
+{respond}"#,
+            respond = respond_in(output_language, false),
+        )
+    }
+
+    /// Generate a prompt for analyzing markdown documentation.
+    fn markdown_doc_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
+        format!(
+            r#"Analyze this documentation file and extract project-level information:
+
+File: {}
+
+```markdown
+{}
+```
+
+Provide a concise summary including:
+1. **Purpose**: What is this project/component about?
+2. **Key Features**: What are the main capabilities described?
+3. **Usage**: How is this meant to be used?
+4. **Architecture Notes**: Any architectural patterns or design decisions mentioned?
+5. **Dependencies/Requirements**: What does this project depend on?
+
+{respond}"#,
+            file_path,
+            content,
+            respond = respond_in(output_language, false),
+        )
+    }
+
+    /// Generate a prompt for architecture-focused file analysis.
+    pub fn architecture_file_analysis_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        let (name, fence) = dialect(file_path);
+        format!(
+            r#"Analyze this {name} file from an ARCHITECTURAL perspective.
+
+File: {file_path}
+
+```{fence}
+{code}
+```
+
+Extract ONLY the following (skip if not present):
+1. **Layer**: Where does this fit? (controller/API, service, repository/data access, domain model, infrastructure)
+2. **Key Abstractions**: Main classes, interfaces, records or data classes defined
+3. **Dependencies**: What does this class depend on? (imports from other project packages, injected collaborators)
+4. **Public API**: What does this file provide to others?
+5. **Patterns**: Design patterns used (Dependency Injection, Factory, Builder, Strategy, Observer, etc.)
+6. **Framework Role**: For Spring and similar frameworks: @Controller, @Service, @Repository, @Entity, @Configuration, etc.
+
+Be concise - this will be aggregated with other files for an overall architecture summary.
+
+{respond}"#,
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for architecture diagram extraction.
+    pub fn diagram_architecture_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        let (name, fence) = dialect(file_path);
+        format!(
+            r#"Analyze this {name} file for ARCHITECTURAL diagram information.
+
+File: {file_path}
+
+```{fence}
+{code}
+```
+
+Extract ONLY the following for diagram generation:
+1. **Component Name**: The logical name of this class or package
+2. **Component Type**: controller, service, repository, entity, configuration, client, utility, etc.
+3. **Dependencies**: List of project classes or packages this file uses (not external libraries)
+4. **Public Interface**: Main public methods and what they provide
+5. **Data Flow**: What data comes in (parameters, requests) and what goes out (return values, responses)
+
+Format as structured text that can be aggregated later.
+
+{respond}"#,
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for data flow diagram extraction.
+    pub fn diagram_data_flow_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        let (name, fence) = dialect(file_path);
+        format!(
+            r#"Analyze this {name} file for DATA FLOW diagram information.
+
+File: {file_path}
+
+```{fence}
+{code}
+```
+
+Extract ONLY the following for data flow diagram generation:
+1. **Data Sources**: HTTP requests, databases, message queues, files, external APIs
+2. **Data Transformations**: How is data processed, mapped or validated?
+3. **Data Sinks**: Where does data go? (responses, database writes, published events)
+4. **State**: What state is held here? (fields, caches, sessions)
+5. **Side Effects**: Network calls, scheduled jobs, event listeners
+
+Skip if this file has no significant data flow.
+
+{respond}"#,
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for database schema diagram extraction.
+    pub fn diagram_database_schema_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        let (name, fence) = dialect(file_path);
+        format!(
+            r#"Analyze this {name} file for DATABASE/SCHEMA diagram information.
+
+File: {file_path}
+
+```{fence}
+{code}
+```
+
+Extract ONLY the following:
+1. **Entities/Tables**: JPA/Hibernate @Entity classes, Exposed tables, or records mapped to tables
+2. **Fields/Columns**: Field names, types, and column annotations (@Id, @Column, @GeneratedValue)
+3. **Relationships**: @OneToMany, @ManyToOne, @ManyToMany, foreign keys
+4. **Database Operations**: Repositories, JDBC/jOOQ queries, or raw SQL
+
+Skip if this file has no database-related content.
+
+{respond}"#,
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for sequence diagram extraction.
+    pub fn diagram_sequence_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        let (name, fence) = dialect(file_path);
+        format!(
+            r#"Analyze this {name} file for SEQUENCE diagram information.
+
+File: {file_path}
+
+```{fence}
+{code}
+```
+
+Extract ONLY the following:
+1. **Entry Points**: Controller endpoints, message listeners, scheduled jobs or public service methods that start a flow
+2. **Call Sequence**: The ordered calls each entry point makes, one per line as `caller -> callee: action`
+   (services, repositories, clients, other components)
+3. **Responses**: What is returned or published at the end of each flow
+
+If this file has no significant flow, say "No significant interaction flow".
+
+{respond}"#,
+            respond = respond_in(output_language, true),
+        )
+    }
+}
+
+/// Files of the project rooted at `dir`, leaving out nested Maven or Gradle
+/// projects (the modules of a multi-module build).
+fn project_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut nested_projects: Vec<PathBuf> = Vec::new();
+
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+
+    // Directories are visited before their contents
+    for entry in walk_source_tree(dir, SKIP_DIRS) {
+        let entry = entry?;
+        let path = entry.path();
+
+        if nested_projects
+            .iter()
+            .any(|nested| path.starts_with(nested))
+        {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            if path != dir && BuildTool::detect(path).is_some() {
+                nested_projects.push(path.to_path_buf());
+            }
+        } else if entry.file_type().is_file() {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    Ok(files)
+}
+
+/// Language name and code fence for a source file.
+fn dialect(file_path: &str) -> (&'static str, &'static str) {
+    if file_path.ends_with(".kt") || file_path.ends_with(".kts") {
+        ("Kotlin", "kotlin")
+    } else {
+        ("Java", "java")
+    }
+}
+
+/// Whether a path relative to the project root is inside a main source set
+/// (`src/main/java` or `src/main/kotlin`), at any depth.
+fn is_main_source(relative: &Path) -> bool {
+    let components: Vec<_> = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect();
+    components
+        .windows(3)
+        .any(|w| w[0] == "src" && w[1] == "main" && (w[2] == "java" || w[2] == "kotlin"))
+}
+
+/// The Gradle wrapper of the build containing `project_root`, or `gradle` if
+/// there is none. Sub-projects of a multi-project build share the wrapper in
+/// the build root, next to `settings.gradle`.
+fn gradle_program(project_root: &Path) -> String {
+    for dir in project_root.ancestors() {
+        let wrapper = dir.join("gradlew");
+        if wrapper.is_file() {
+            return wrapper.to_string_lossy().to_string();
+        }
+        let is_build_root = ["settings.gradle", "settings.gradle.kts"]
+            .iter()
+            .any(|file| dir.join(file).exists());
+        if is_build_root {
+            break;
+        }
+    }
+    "gradle".to_string()
+}
+
+/// Whether build output shows a compilation failure rather than failing tests.
+fn is_compile_error(output: &str) -> bool {
+    output.contains("COMPILATION ERROR")
+        || output.contains("Compilation failed")
+        || output.contains("Compilation error")
+        || output.contains("error: cannot find symbol")
+        || output.contains(":compileJava FAILED")
+        || output.contains(":compileKotlin FAILED")
+        || output.contains(":compileTestJava FAILED")
+        || output.contains(":compileTestKotlin FAILED")
+        // kotlinc diagnostics: "e: file:///src/main/kotlin/App.kt:3:5 Unresolved reference"
+        || output.lines().any(|line| line.starts_with("e: "))
+}
+
+/// Add line numbers to code for mutation prompts.
+fn add_line_numbers(code: &str) -> String {
+    code.lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:5} | {}", i + 1, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Truncate output to a maximum length.
+fn truncate_output(output: &str, max_len: usize) -> String {
+    if output.len() <= max_len {
+        output.to_string()
+    } else {
+        let end = (0..=max_len)
+            .rev()
+            .find(|&i| output.is_char_boundary(i))
+            .unwrap_or(0);
+        format!("{}...\n(output truncated)", &output[..end])
+    }
+}
+
+/// Extract a failing test name from Maven Surefire or Gradle output.
+fn extract_failing_test(output: &str) -> Option<String> {
+    let mut in_failures = false;
+    for line in output.lines() {
+        let trimmed = line.trim();
+
+        // Gradle: "CalculatorTest > addsNumbers() FAILED"
+        if let Some(test) = trimmed.strip_suffix(" FAILED") {
+            if test.contains(" > ") {
+                return Some(test.to_string());
+            }
+        }
+
+        // Surefire summary:
+        // [ERROR] Failures:
+        // [ERROR]   CalculatorTest.addsNumbers:12 expected: <3> but was: <4>
+        let message = trimmed.strip_prefix("[ERROR]").map(str::trim);
+        match message {
+            Some("Failures:") | Some("Errors:") => in_failures = true,
+            Some(entry) if in_failures && !entry.is_empty() => {
+                let test = entry.split_whitespace().next().unwrap_or(entry);
+                let test = test.split(':').next().unwrap_or(test);
+                return Some(test.trim_end_matches('»').to_string());
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_source_files_in_main_source_sets() {
+        let temp = TempDir::new().unwrap();
+        let java = temp.path().join("src/main/java/com/example");
+        let kotlin = temp.path().join("core/src/main/kotlin/com/example");
+        let tests = temp.path().join("src/test/java/com/example");
+        let output = temp.path().join("target/generated-sources/src/main/java");
+        for dir in [&java, &kotlin, &tests, &output] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(java.join("App.java"), "class App {}").unwrap();
+        std::fs::write(kotlin.join("Util.kt"), "object Util").unwrap();
+        std::fs::write(tests.join("AppTest.java"), "class AppTest {}").unwrap();
+        std::fs::write(output.join("Gen.java"), "class Gen {}").unwrap();
+        std::fs::write(temp.path().join("build.gradle.kts"), "").unwrap();
+
+        let files = JavaLanguage.find_source_files(temp.path()).unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().any(|f| f.ends_with("App.java")));
+        assert!(files.iter().any(|f| f.ends_with("Util.kt")));
+    }
+
+    #[test]
+    fn test_find_source_files_leaves_out_modules() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("pom.xml"), "<project/>").unwrap();
+        let root_src = temp.path().join("src/main/java");
+        let module_src = temp.path().join("core/src/main/java");
+        std::fs::create_dir_all(&root_src).unwrap();
+        std::fs::create_dir_all(&module_src).unwrap();
+        std::fs::write(root_src.join("App.java"), "class App {}").unwrap();
+        std::fs::write(module_src.join("Core.java"), "class Core {}").unwrap();
+        std::fs::write(temp.path().join("core/pom.xml"), "<project/>").unwrap();
+
+        let files = JavaLanguage.find_source_files(temp.path()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("App.java"));
+
+        let context = JavaLanguage.find_context_files(temp.path()).unwrap();
+        assert_eq!(context, vec![temp.path().join("pom.xml")]);
+
+        let module = JavaLanguage
+            .find_source_files(&temp.path().join("core"))
+            .unwrap();
+        assert_eq!(module.len(), 1);
+        assert!(module[0].ends_with("Core.java"));
+    }
+
+    #[test]
+    fn test_build_tool_detection() {
+        let temp = TempDir::new().unwrap();
+        assert_eq!(BuildTool::detect(temp.path()), None);
+
+        std::fs::write(temp.path().join("settings.gradle.kts"), "").unwrap();
+        assert_eq!(BuildTool::detect(temp.path()), Some(BuildTool::Gradle));
+
+        std::fs::write(temp.path().join("pom.xml"), "<project/>").unwrap();
+        assert_eq!(BuildTool::detect(temp.path()), Some(BuildTool::Maven));
+    }
+
+    #[test]
+    fn test_gradle_program_uses_build_root_wrapper() {
+        let temp = TempDir::new().unwrap();
+        let module = temp.path().join("app");
+        std::fs::create_dir_all(&module).unwrap();
+        std::fs::write(module.join("build.gradle"), "").unwrap();
+        assert_eq!(gradle_program(&module), "gradle");
+
+        std::fs::write(temp.path().join("settings.gradle"), "include 'app'").unwrap();
+        std::fs::write(temp.path().join("gradlew"), "").unwrap();
+        assert_eq!(
+            gradle_program(&module),
+            temp.path().join("gradlew").to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_context_file_type() {
+        let lang = JavaLanguage;
+
+        assert_eq!(
+            lang.context_file_type(Path::new("pom.xml")),
+            Some(ContextFileType::PomXml)
+        );
+        assert_eq!(
+            lang.context_file_type(Path::new("app/build.gradle.kts")),
+            Some(ContextFileType::GradleBuild)
+        );
+        assert_eq!(
+            lang.context_file_type(Path::new("README.md")),
+            Some(ContextFileType::Markdown)
+        );
+        assert_eq!(lang.context_file_type(Path::new("App.java")), None);
+    }
+
+    #[test]
+    fn test_prompts_name_the_file_language() {
+        let lang = JavaLanguage;
+
+        let java = lang.analysis_prompt("src/main/java/App.java", "class App {}", "English");
+        assert!(java.contains("Java code"));
+        assert!(java.contains("```java"));
+
+        let kotlin =
+            lang.diagram_data_flow_prompt("src/main/kotlin/App.kt", "object App", "English");
+        assert!(kotlin.contains("Kotlin file"));
+        assert!(kotlin.contains("```kotlin"));
+    }
+
+    #[test]
+    fn test_extract_failing_test_surefire() {
+        let output = "[INFO] Running com.example.CalculatorTest\n\
+                      [ERROR] Tests run: 2, Failures: 1, Errors: 0, Skipped: 0\n\
+                      [ERROR] Failures: \n\
+                      [ERROR]   CalculatorTest.addsNumbers:12 expected: <3> but was: <4>\n";
+        assert_eq!(
+            extract_failing_test(output),
+            Some("CalculatorTest.addsNumbers".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_failing_test_gradle() {
+        let output = "> Task :test\n\nCalculatorTest > addsNumbers() FAILED\n    \
+                      org.opentest4j.AssertionFailedError at CalculatorTest.kt:12\n\n\
+                      > Task :test FAILED\n";
+        assert_eq!(
+            extract_failing_test(output),
+            Some("CalculatorTest > addsNumbers()".to_string())
+        );
+    }
+
+    #[test]
+    fn test_is_compile_error() {
+        assert!(is_compile_error(
+            "[ERROR] COMPILATION ERROR :\n[ERROR] App.java:[3,5] cannot find symbol"
+        ));
+        assert!(is_compile_error("> Task :compileKotlin FAILED"));
+        assert!(is_compile_error(
+            "e: file:///app/src/main/kotlin/App.kt:3:5 Unresolved reference: foo"
+        ));
+        assert!(!is_compile_error("CalculatorTest > addsNumbers() FAILED"));
+    }
+}
@@ -5,12 +5,19 @@
 
 #![allow(dead_code)]
 
+mod command;
+mod java;
 mod rust;
 mod typescript;
 
+use crate::config::LanguageConfig;
+use crate::repo_config::FileFilter;
 use anyhow::Result;
 use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
 
+pub use command::CommandLanguage;
+pub use java::{BuildTool, JavaLanguage, GRADLE_BUILD_FILES};
 pub use rust::RustLanguage;
 pub use typescript::TypeScriptLanguage;
 
@@ -19,9 +26,59 @@ pub use typescript::TypeScriptLanguage;
 pub enum Language {
     Rust,
     TypeScript,
+    /// Java and Kotlin, built with Maven or Gradle
+    Java,
+    /// A language configured in `[[languages]]`, built and tested with shell commands
+    Command(&'static CommandLanguage),
+}
+
+/// Languages supported without configuration.
+const BUILTIN_LANGUAGES: &[Language] = &[Language::Rust, Language::TypeScript, Language::Java];
+
+/// Built-in languages followed by the registered command languages.
+static LANGUAGES: RwLock<&'static [Language]> = RwLock::new(BUILTIN_LANGUAGES);
+
+/// Make the languages of the config's `[[languages]]` entries available,
+/// replacing any registered before. Entries without extensions or named like
+/// a built-in language are skipped.
+pub fn register_command_languages(configs: &[LanguageConfig]) {
+    let mut languages = BUILTIN_LANGUAGES.to_vec();
+    for config in configs {
+        let taken = languages
+            .iter()
+            .any(|language| language.name().eq_ignore_ascii_case(&config.name));
+        if config.name.is_empty() || taken || config.extensions.is_empty() {
+            tracing::warn!(
+                "Skipping language '{}': it needs a unique name and at least one extension",
+                config.name
+            );
+            continue;
+        }
+        tracing::info!(
+            "Registered language {} ({})",
+            config.name,
+            config.extensions.join(", ")
+        );
+        languages.push(Language::Command(CommandLanguage::leak(config)));
+    }
+
+    *LANGUAGES.write().unwrap_or_else(PoisonError::into_inner) = Box::leak(languages.into());
 }
 
 impl Language {
+    /// All supported languages, including registered command languages.
+    pub fn all() -> &'static [Language] {
+        *LANGUAGES.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The registered command languages.
+    pub fn commands() -> impl Iterator<Item = &'static CommandLanguage> {
+        Self::all().iter().filter_map(|language| match language {
+            Language::Command(command) => Some(*command),
+            _ => None,
+        })
+    }
+
     /// Detect the primary language of a repository by examining its contents.
     pub fn detect(repo_path: &Path) -> Option<Self> {
         // Check for language-specific marker files
@@ -31,6 +88,12 @@ impl Language {
         if repo_path.join("package.json").exists() {
             return Some(Language::TypeScript);
         }
+        if BuildTool::detect(repo_path).is_some() {
+            return Some(Language::Java);
+        }
+        if let Some(command) = Self::commands().find(|c| c.is_project_root(repo_path)) {
+            return Some(Language::Command(command));
+        }
 
         None
     }
@@ -40,6 +103,8 @@ impl Language {
         match self {
             Language::Rust => "Rust",
             Language::TypeScript => "TypeScript",
+            Language::Java => "Java",
+            Language::Command(command) => command.name,
         }
     }
 
@@ -48,6 +113,8 @@ impl Language {
         match self {
             Language::Rust => &["rs"],
             Language::TypeScript => &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+            Language::Java => &["java", "kt"],
+            Language::Command(command) => command.extensions,
         }
     }
 
@@ -56,6 +123,8 @@ impl Language {
         match self {
             Language::Rust => &["target", "node_modules", ".git"],
             Language::TypeScript => &["node_modules", ".git", "dist", "build", ".next", "coverage"],
+            Language::Java => &["target", "build", "out", ".gradle", ".git"],
+            Language::Command(command) => command.skip_dirs,
         }
     }
 
@@ -64,9 +133,25 @@ impl Language {
         match self {
             Language::Rust => RustLanguage.find_source_files(dir),
             Language::TypeScript => TypeScriptLanguage.find_source_files(dir),
+            Language::Java => JavaLanguage.find_source_files(dir),
+            Language::Command(command) => command.find_source_files(dir),
         }
     }
 
+    /// Find the source files in a directory that a repository's
+    /// include/exclude globs select, matched by their path relative to
+    /// `repo_root`.
+    pub fn find_included_source_files(
+        &self,
+        dir: &Path,
+        repo_root: &Path,
+        filter: &FileFilter,
+    ) -> Result<Vec<PathBuf>> {
+        let mut files = self.find_source_files(dir)?;
+        files.retain(|file| filter.matches(file.strip_prefix(repo_root).unwrap_or(file)));
+        Ok(files)
+    }
+
     /// Run a compile check to verify compilation without running tests.
     ///
     /// Returns `Ok(())` if compilation succeeds, `Err(error_output)` if it fails.
@@ -82,6 +167,8 @@ impl Language {
                     .compile_check(repo_path, timeout_seconds)
                     .await
             }
+            Language::Java => JavaLanguage.compile_check(repo_path, timeout_seconds).await,
+            Language::Command(command) => command.compile_check(repo_path, timeout_seconds).await,
         }
     }
 
@@ -94,14 +181,22 @@ impl Language {
                     .run_tests(repo_path, timeout_seconds)
                     .await
             }
+            Language::Java => JavaLanguage.run_tests(repo_path, timeout_seconds).await,
+            Language::Command(command) => command.run_tests(repo_path, timeout_seconds).await,
         }
     }
 
     /// Generate a prompt for code analysis.
-    pub fn analysis_prompt(&self, file_path: &str, content: &str) -> String {
+    pub fn analysis_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
         match self {
-            Language::Rust => RustLanguage.analysis_prompt(file_path, content),
-            Language::TypeScript => TypeScriptLanguage.analysis_prompt(file_path, content),
+            Language::Rust => RustLanguage.analysis_prompt(file_path, content, output_language),
+            Language::TypeScript => {
+                TypeScriptLanguage.analysis_prompt(file_path, content, output_language)
+            }
+            Language::Java => JavaLanguage.analysis_prompt(file_path, content, output_language),
+            Language::Command(command) => {
+                command.analysis_prompt(file_path, content, output_language)
+            }
         }
     }
 
@@ -110,6 +205,8 @@ impl Language {
         match self {
             Language::Rust => RustLanguage.mutation_prompt(file_path, content),
             Language::TypeScript => TypeScriptLanguage.mutation_prompt(file_path, content),
+            Language::Java => JavaLanguage.mutation_prompt(file_path, content),
+            Language::Command(command) => command.mutation_prompt(file_path, content),
         }
     }
 
@@ -118,6 +215,8 @@ impl Language {
         match self {
             Language::Rust => 50,
             Language::TypeScript => 50,
+            Language::Java => 50,
+            Language::Command(_) => 50,
         }
     }
 
@@ -126,6 +225,8 @@ impl Language {
         match self {
             Language::Rust => 100_000,
             Language::TypeScript => 100_000,
+            Language::Java => 100_000,
+            Language::Command(_) => 100_000,
         }
     }
 
@@ -134,6 +235,8 @@ impl Language {
         match self {
             Language::Rust => 100,
             Language::TypeScript => 100,
+            Language::Java => 100,
+            Language::Command(_) => 100,
         }
     }
 
@@ -142,6 +245,8 @@ impl Language {
         match self {
             Language::Rust => 50_000,
             Language::TypeScript => 50_000,
+            Language::Java => 50_000,
+            Language::Command(_) => 50_000,
         }
     }
 
@@ -150,51 +255,149 @@ impl Language {
         match self {
             Language::Rust => RustLanguage.find_context_files(dir),
             Language::TypeScript => TypeScriptLanguage.find_context_files(dir),
+            Language::Java => JavaLanguage.find_context_files(dir),
+            Language::Command(command) => command.find_context_files(dir),
         }
     }
 
     /// Generate a prompt for documentation/context file analysis.
-    pub fn documentation_prompt(&self, file_path: &str, content: &str) -> String {
+    pub fn documentation_prompt(
+        &self,
+        file_path: &str,
+        content: &str,
+        output_language: &str,
+    ) -> String {
         match self {
-            Language::Rust => RustLanguage.documentation_prompt(file_path, content),
-            Language::TypeScript => TypeScriptLanguage.documentation_prompt(file_path, content),
+            Language::Rust => {
+                RustLanguage.documentation_prompt(file_path, content, output_language)
+            }
+            Language::TypeScript => {
+                TypeScriptLanguage.documentation_prompt(file_path, content, output_language)
+            }
+            Language::Java => {
+                JavaLanguage.documentation_prompt(file_path, content, output_language)
+            }
+            Language::Command(command) => {
+                command.documentation_prompt(file_path, content, output_language)
+            }
         }
     }
 
     /// Generate a prompt for architecture-focused file analysis.
-    pub fn architecture_file_analysis_prompt(&self, file_path: &str, content: &str) -> String {
+    pub fn architecture_file_analysis_prompt(
+        &self,
+        file_path: &str,
+        content: &str,
+        output_language: &str,
+    ) -> String {
         match self {
-            Language::Rust => RustLanguage.architecture_file_analysis_prompt(file_path, content),
-            Language::TypeScript => {
-                TypeScriptLanguage.architecture_file_analysis_prompt(file_path, content)
+            Language::Rust => {
+                RustLanguage.architecture_file_analysis_prompt(file_path, content, output_language)
+            }
+            Language::TypeScript => TypeScriptLanguage.architecture_file_analysis_prompt(
+                file_path,
+                content,
+                output_language,
+            ),
+            Language::Java => {
+                JavaLanguage.architecture_file_analysis_prompt(file_path, content, output_language)
+            }
+            Language::Command(command) => {
+                command.architecture_file_analysis_prompt(file_path, content, output_language)
             }
         }
     }
 
     /// Generate a prompt for diagram architecture extraction.
-    pub fn diagram_architecture_prompt(&self, file_path: &str, content: &str) -> String {
+    pub fn diagram_architecture_prompt(
+        &self,
+        file_path: &str,
+        content: &str,
+        output_language: &str,
+    ) -> String {
         match self {
-            Language::Rust => RustLanguage.diagram_architecture_prompt(file_path, content),
+            Language::Rust => {
+                RustLanguage.diagram_architecture_prompt(file_path, content, output_language)
+            }
             Language::TypeScript => {
-                TypeScriptLanguage.diagram_architecture_prompt(file_path, content)
+                TypeScriptLanguage.diagram_architecture_prompt(file_path, content, output_language)
+            }
+            Language::Java => {
+                JavaLanguage.diagram_architecture_prompt(file_path, content, output_language)
+            }
+            Language::Command(command) => {
+                command.diagram_architecture_prompt(file_path, content, output_language)
             }
         }
     }
 
     /// Generate a prompt for diagram data flow extraction.
-    pub fn diagram_data_flow_prompt(&self, file_path: &str, content: &str) -> String {
+    pub fn diagram_data_flow_prompt(
+        &self,
+        file_path: &str,
+        content: &str,
+        output_language: &str,
+    ) -> String {
         match self {
-            Language::Rust => RustLanguage.diagram_data_flow_prompt(file_path, content),
-            Language::TypeScript => TypeScriptLanguage.diagram_data_flow_prompt(file_path, content),
+            Language::Rust => {
+                RustLanguage.diagram_data_flow_prompt(file_path, content, output_language)
+            }
+            Language::TypeScript => {
+                TypeScriptLanguage.diagram_data_flow_prompt(file_path, content, output_language)
+            }
+            Language::Java => {
+                JavaLanguage.diagram_data_flow_prompt(file_path, content, output_language)
+            }
+            Language::Command(command) => {
+                command.diagram_data_flow_prompt(file_path, content, output_language)
+            }
         }
     }
 
     /// Generate a prompt for diagram database schema extraction.
-    pub fn diagram_database_schema_prompt(&self, file_path: &str, content: &str) -> String {
+    pub fn diagram_database_schema_prompt(
+        &self,
+        file_path: &str,
+        content: &str,
+        output_language: &str,
+    ) -> String {
+        match self {
+            Language::Rust => {
+                RustLanguage.diagram_database_schema_prompt(file_path, content, output_language)
+            }
+            Language::TypeScript => TypeScriptLanguage.diagram_database_schema_prompt(
+                file_path,
+                content,
+                output_language,
+            ),
+            Language::Java => {
+                JavaLanguage.diagram_database_schema_prompt(file_path, content, output_language)
+            }
+            Language::Command(command) => {
+                command.diagram_database_schema_prompt(file_path, content, output_language)
+            }
+        }
+    }
+
+    /// Generate a prompt for sequence diagram extraction.
+    pub fn diagram_sequence_prompt(
+        &self,
+        file_path: &str,
+        content: &str,
+        output_language: &str,
+    ) -> String {
         match self {
-            Language::Rust => RustLanguage.diagram_database_schema_prompt(file_path, content),
+            Language::Rust => {
+                RustLanguage.diagram_sequence_prompt(file_path, content, output_language)
+            }
             Language::TypeScript => {
-                TypeScriptLanguage.diagram_database_schema_prompt(file_path, content)
+                TypeScriptLanguage.diagram_sequence_prompt(file_path, content, output_language)
+            }
+            Language::Java => {
+                JavaLanguage.diagram_sequence_prompt(file_path, content, output_language)
+            }
+            Language::Command(command) => {
+                command.diagram_sequence_prompt(file_path, content, output_language)
             }
         }
     }
@@ -265,6 +468,17 @@ mod tests {
         assert_eq!(lang, Some(Language::Rust));
     }
 
+    #[test]
+    fn test_language_detect_java() {
+        let maven = TempDir::new().unwrap();
+        std::fs::write(maven.path().join("pom.xml"), "<project/>").unwrap();
+        assert_eq!(Language::detect(maven.path()), Some(Language::Java));
+
+        let gradle = TempDir::new().unwrap();
+        std::fs::write(gradle.path().join("build.gradle.kts"), "").unwrap();
+        assert_eq!(Language::detect(gradle.path()), Some(Language::Java));
+    }
+
     #[test]
     fn test_language_detect_unknown() {
         let temp_dir = TempDir::new().unwrap();
@@ -306,7 +520,7 @@ mod tests {
 
     #[test]
     fn test_language_file_size_limits() {
-        for lang in [Language::Rust, Language::TypeScript] {
+        for lang in Language::all() {
             assert!(lang.min_file_size() < lang.max_file_size());
             assert!(lang.min_mutation_file_size() < lang.max_mutation_file_size());
         }
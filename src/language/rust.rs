@@ -1,6 +1,8 @@
 //! Rust language support.
 
 use super::{TestOutcome, TestRunResult};
+use crate::analyzer::respond_in;
+use crate::project::walk_source_tree;
 use anyhow::Result;
 use std::path::{Path, PathBuf};
 use std::time::Instant;
@@ -26,21 +28,9 @@ impl RustLanguage {
             return Ok(files);
         }
 
-        let root_dir = dir.to_path_buf();
         let skip_dirs: &[&str] = &["target", "node_modules", ".git"];
 
-        for entry in walkdir::WalkDir::new(dir)
-            .follow_links(false)
-            .into_iter()
-            .filter_entry(|e| {
-                // Don't filter the root directory itself (may be a temp dir starting with .)
-                if e.path() == root_dir {
-                    return true;
-                }
-                let name = e.file_name().to_string_lossy();
-                !name.starts_with('.') && !skip_dirs.contains(&name.as_ref())
-            })
-        {
+        for entry in walk_source_tree(dir, skip_dirs) {
             let entry = entry?;
             let path = entry.path();
 
@@ -148,7 +138,7 @@ impl RustLanguage {
         }
     }
 
-    pub fn analysis_prompt(&self, file_path: &str, content: &str) -> String {
+    pub fn analysis_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
         format!(
             "Analyze the following Rust code and provide a brief summary of what it does:\n\n\
              File: {}\n\n\
@@ -158,8 +148,10 @@ impl RustLanguage {
              2. Key functions/structs\n\
              3. Any potential issues or improvements\n\
              4. Up to two specific code modification recommendations\n\n\
-             IMPORTANT: Respond only in English (or code)",
-            file_path, content
+             {respond}",
+            file_path,
+            content,
+            respond = respond_in(output_language, true),
         )
     }
 
@@ -210,21 +202,9 @@ Example for line `   42 |     if count > 0 {{`:
             return Ok(files);
         }
 
-        let root_dir = dir.to_path_buf();
         let skip_dirs: &[&str] = &["target", "node_modules", ".git"];
 
-        for entry in walkdir::WalkDir::new(dir)
-            .follow_links(false)
-            .into_iter()
-            .filter_entry(|e| {
-                // Don't filter the root directory itself (may be a temp dir starting with .)
-                if e.path() == root_dir {
-                    return true;
-                }
-                let name = e.file_name().to_string_lossy();
-                !name.starts_with('.') && !skip_dirs.contains(&name.as_ref())
-            })
-        {
+        for entry in walk_source_tree(dir, skip_dirs) {
             let entry = entry?;
             let path = entry.path();
 
@@ -266,17 +246,26 @@ Example for line `   42 |     if count > 0 {{`:
     }
 
     /// Generate a prompt for documentation/context file analysis.
-    pub fn documentation_prompt(&self, file_path: &str, content: &str) -> String {
+    pub fn documentation_prompt(
+        &self,
+        file_path: &str,
+        content: &str,
+        output_language: &str,
+    ) -> String {
         let path = Path::new(file_path);
         match self.context_file_type(path) {
-            Some(ContextFileType::CargoToml) => self.cargo_toml_prompt(file_path, content),
-            Some(ContextFileType::Markdown) => self.markdown_doc_prompt(file_path, content),
-            None => self.markdown_doc_prompt(file_path, content), // fallback
+            Some(ContextFileType::CargoToml) => {
+                self.cargo_toml_prompt(file_path, content, output_language)
+            }
+            Some(ContextFileType::Markdown) => {
+                self.markdown_doc_prompt(file_path, content, output_language)
+            }
+            None => self.markdown_doc_prompt(file_path, content, output_language), // fallback
         }
     }
 
     /// Prompt for analyzing Cargo.toml files.
-    fn cargo_toml_prompt(&self, file_path: &str, content: &str) -> String {
+    fn cargo_toml_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
         format!(
             r#"Analyze this Cargo.toml file for PROJECT STRUCTURE information.
 
@@ -305,13 +294,15 @@ Extract the following architectural context:
 
 Keep the analysis concise and focused on what these dependencies tell us about the project's architecture.
 
-IMPORTANT: Respond only in English."#,
-            file_path, content
+{respond}"#,
+            file_path,
+            content,
+            respond = respond_in(output_language, false),
         )
     }
 
     /// Prompt for analyzing markdown documentation files.
-    fn markdown_doc_prompt(&self, file_path: &str, content: &str) -> String {
+    fn markdown_doc_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
         format!(
             r#"Analyze this documentation file for PROJECT CONTEXT.
 
@@ -337,13 +328,20 @@ Focus on information that helps understand the system architecture.
 Skip installation instructions, contribution guidelines, or license information.
 If the document has no architectural relevance, say "No architectural context".
 
-IMPORTANT: Respond only in English."#,
-            file_path, content
+{respond}"#,
+            file_path,
+            content,
+            respond = respond_in(output_language, false),
         )
     }
 
     /// Prompt for architecture-focused file analysis.
-    pub fn architecture_file_analysis_prompt(&self, file_path: &str, code: &str) -> String {
+    pub fn architecture_file_analysis_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
         format!(
             r#"Analyze this Rust file from an ARCHITECTURAL perspective.
 
@@ -372,13 +370,20 @@ Provide a brief architectural analysis including:
 Keep the analysis concise and focused on architectural significance.
 Do not describe implementation details or suggest improvements.
 
-IMPORTANT: Respond only in English."#,
-            file_path, code
+{respond}"#,
+            file_path,
+            code,
+            respond = respond_in(output_language, false),
         )
     }
 
     /// Prompt for extracting architecture-relevant information from a file (for diagrams).
-    pub fn diagram_architecture_prompt(&self, file_path: &str, code: &str) -> String {
+    pub fn diagram_architecture_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
         format!(
             r#"Analyze this Rust file for ARCHITECTURAL information only.
 
@@ -403,13 +408,20 @@ Extract ONLY the following (be very concise, use bullet points):
 Keep responses brief and factual. Focus on structure, not implementation details.
 If this file has no significant architectural role (e.g., just re-exports), say "Minimal architectural significance".
 
-IMPORTANT: Respond only in English."#,
-            file_path, code
+{respond}"#,
+            file_path,
+            code,
+            respond = respond_in(output_language, false),
         )
     }
 
     /// Prompt for extracting data flow information from a file (for diagrams).
-    pub fn diagram_data_flow_prompt(&self, file_path: &str, code: &str) -> String {
+    pub fn diagram_data_flow_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
         format!(
             r#"Analyze this Rust file for DATA FLOW patterns.
 
@@ -445,13 +457,20 @@ Extract ONLY the following (be very concise):
 
 If this file has no significant data flow (e.g., type definitions only, utilities), say "No significant data flow".
 
-IMPORTANT: Respond only in English."#,
-            file_path, code
+{respond}"#,
+            file_path,
+            code,
+            respond = respond_in(output_language, false),
         )
     }
 
     /// Prompt for extracting database schema information from a file (for diagrams).
-    pub fn diagram_database_schema_prompt(&self, file_path: &str, code: &str) -> String {
+    pub fn diagram_database_schema_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
         format!(
             r#"Analyze this Rust file for DATABASE-RELATED structures.
 
@@ -483,8 +502,47 @@ Extract ONLY the following (be very concise):
 
 If this file has no database relevance, say "No database content".
 
-IMPORTANT: Respond only in English."#,
-            file_path, code
+{respond}"#,
+            file_path,
+            code,
+            respond = respond_in(output_language, false),
+        )
+    }
+
+    /// Prompt for extracting call sequences of key flows from a file (for diagrams).
+    pub fn diagram_sequence_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        format!(
+            r#"Analyze this Rust file for INTERACTION SEQUENCES in key flows.
+
+File: {}
+
+```rust
+{}
+```
+
+Extract ONLY the following (be very concise):
+
+1. **Entry Points**: Functions that start a significant flow
+   - HTTP handlers, CLI commands, daemon loops, public API functions
+
+2. **Call Sequence**: For each entry point, the ordered calls it makes to other components
+   - Format each step as `caller -> callee: action`
+   - Include calls into other modules, the database, external services and spawned tasks
+   - Skip trivial helpers, formatting and logging
+
+3. **Responses**: What is returned or sent back at the end of each flow
+
+If this file has no significant flow (e.g., type definitions only, utilities), say "No significant interaction flow".
+
+{respond}"#,
+            file_path,
+            code,
+            respond = respond_in(output_language, false),
         )
     }
 }
@@ -607,9 +665,10 @@ thread 'my_failing_test' panicked
     #[test]
     fn test_analysis_prompt_contains_file() {
         let handler = RustLanguage;
-        let prompt = handler.analysis_prompt("src/main.rs", "fn main() {}");
+        let prompt = handler.analysis_prompt("src/main.rs", "fn main() {}", "Spanish");
         assert!(prompt.contains("src/main.rs"));
         assert!(prompt.contains("fn main()"));
+        assert!(prompt.ends_with("Respond only in Spanish (or code)"));
     }
 
     #[test]
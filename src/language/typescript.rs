@@ -1,6 +1,8 @@
 //! TypeScript/JavaScript language support.
 
 use super::{TestOutcome, TestRunResult};
+use crate::analyzer::respond_in;
+use crate::project::walk_source_tree;
 use anyhow::Result;
 use std::path::{Path, PathBuf};
 use std::time::Instant;
@@ -31,7 +33,6 @@ impl TypeScriptLanguage {
             return Ok(files);
         }
 
-        let root_dir = dir.to_path_buf();
         let skip_dirs: &[&str] = &[
             "node_modules",
             ".git",
@@ -44,18 +45,7 @@ impl TypeScriptLanguage {
         ];
         let extensions: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs"];
 
-        for entry in walkdir::WalkDir::new(dir)
-            .follow_links(false)
-            .into_iter()
-            .filter_entry(|e| {
-                // Don't filter the root directory itself (may be a temp dir starting with .)
-                if e.path() == root_dir {
-                    return true;
-                }
-                let name = e.file_name().to_string_lossy();
-                !name.starts_with('.') && !skip_dirs.contains(&name.as_ref())
-            })
-        {
+        for entry in walk_source_tree(dir, skip_dirs) {
             let entry = entry?;
             let path = entry.path();
 
@@ -251,7 +241,7 @@ impl TypeScriptLanguage {
     }
 
     /// Generate a prompt for code analysis.
-    pub fn analysis_prompt(&self, file_path: &str, content: &str) -> String {
+    pub fn analysis_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
         format!(
             "Analyze the following TypeScript/JavaScript code and provide a brief summary of what it does:\n\n\
              File: {}\n\n\
@@ -261,8 +251,9 @@ impl TypeScriptLanguage {
              2. Key functions, classes, or React components\n\
              3. Any potential issues or improvements\n\
              4. Up to two specific code modification recommendations\n\n\
-             IMPORTANT: Respond only in English (or code)",
-            file_path, content
+             {respond}",
+            file_path, content,
+            respond = respond_in(output_language, true),
         )
     }
 
@@ -317,21 +308,9 @@ Example for line `   42 |     if (count > 0) {{`:
             return Ok(files);
         }
 
-        let root_dir = dir.to_path_buf();
         let skip_dirs: &[&str] = &["node_modules", ".git", "dist", "build", ".next", "coverage"];
 
-        for entry in walkdir::WalkDir::new(dir)
-            .follow_links(false)
-            .into_iter()
-            .filter_entry(|e| {
-                // Don't filter the root directory itself (may be a temp dir starting with .)
-                if e.path() == root_dir {
-                    return true;
-                }
-                let name = e.file_name().to_string_lossy();
-                !name.starts_with('.') && !skip_dirs.contains(&name.as_ref())
-            })
-        {
+        for entry in walk_source_tree(dir, skip_dirs) {
             let entry = entry?;
             let path = entry.path();
 
@@ -375,21 +354,26 @@ Example for line `   42 |     if (count > 0) {{`:
     }
 
     /// Generate a documentation analysis prompt based on context file type.
-    pub fn documentation_prompt(&self, file_path: &str, content: &str) -> String {
+    pub fn documentation_prompt(
+        &self,
+        file_path: &str,
+        content: &str,
+        output_language: &str,
+    ) -> String {
         let file_name = std::path::Path::new(file_path)
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("");
 
         if file_name == "package.json" {
-            self.package_json_prompt(file_path, content)
+            self.package_json_prompt(file_path, content, output_language)
         } else {
-            self.markdown_doc_prompt(file_path, content)
+            self.markdown_doc_prompt(file_path, content, output_language)
         }
     }
 
     /// Generate a prompt for analyzing package.json.
-    fn package_json_prompt(&self, file_path: &str, content: &str) -> String {
+    fn package_json_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
         format!(
             r#"Analyze this package.json file and extract project-level information:
 
@@ -407,13 +391,15 @@ Provide a concise summary including:
 5. **Dev Stack**: What development tools are configured? (TypeScript, ESLint, Prettier, etc.)
 6. **Project Type**: Is this a library, application, monorepo package, etc.?
 
-IMPORTANT: Respond only in English"#,
-            file_path, content
+{respond}"#,
+            file_path,
+            content,
+            respond = respond_in(output_language, false),
         )
     }
 
     /// Generate a prompt for analyzing markdown documentation.
-    fn markdown_doc_prompt(&self, file_path: &str, content: &str) -> String {
+    fn markdown_doc_prompt(&self, file_path: &str, content: &str, output_language: &str) -> String {
         format!(
             r#"Analyze this documentation file and extract project-level information:
 
@@ -430,13 +416,20 @@ Provide a concise summary including:
 4. **Architecture Notes**: Any architectural patterns or design decisions mentioned?
 5. **Dependencies/Requirements**: What does this project depend on?
 
-IMPORTANT: Respond only in English"#,
-            file_path, content
+{respond}"#,
+            file_path,
+            content,
+            respond = respond_in(output_language, false),
         )
     }
 
     /// Generate a prompt for architecture-focused file analysis.
-    pub fn architecture_file_analysis_prompt(&self, file_path: &str, code: &str) -> String {
+    pub fn architecture_file_analysis_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
         format!(
             r#"Analyze this TypeScript/JavaScript file from an ARCHITECTURAL perspective.
 
@@ -456,13 +449,20 @@ Extract ONLY the following (skip if not present):
 
 Be concise - this will be aggregated with other files for an overall architecture summary.
 
-IMPORTANT: Respond only in English (or code)"#,
-            file_path, code
+{respond}"#,
+            file_path,
+            code,
+            respond = respond_in(output_language, true),
         )
     }
 
     /// Generate a prompt for architecture diagram extraction.
-    pub fn diagram_architecture_prompt(&self, file_path: &str, code: &str) -> String {
+    pub fn diagram_architecture_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
         format!(
             r#"Analyze this TypeScript/JavaScript file for ARCHITECTURAL diagram information.
 
@@ -481,13 +481,20 @@ Extract ONLY the following for diagram generation:
 
 Format as structured text that can be aggregated later.
 
-IMPORTANT: Respond only in English (or code)"#,
-            file_path, code
+{respond}"#,
+            file_path,
+            code,
+            respond = respond_in(output_language, true),
         )
     }
 
     /// Generate a prompt for data flow diagram extraction.
-    pub fn diagram_data_flow_prompt(&self, file_path: &str, code: &str) -> String {
+    pub fn diagram_data_flow_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
         format!(
             r#"Analyze this TypeScript/JavaScript file for DATA FLOW diagram information.
 
@@ -506,13 +513,20 @@ Extract ONLY the following for data flow diagram generation:
 
 Skip if this file has no significant data flow.
 
-IMPORTANT: Respond only in English (or code)"#,
-            file_path, code
+{respond}"#,
+            file_path,
+            code,
+            respond = respond_in(output_language, true),
         )
     }
 
     /// Generate a prompt for database schema diagram extraction.
-    pub fn diagram_database_schema_prompt(&self, file_path: &str, code: &str) -> String {
+    pub fn diagram_database_schema_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
         format!(
             r#"Analyze this TypeScript/JavaScript file for DATABASE/SCHEMA diagram information.
 
@@ -530,8 +544,41 @@ Extract ONLY the following:
 
 Skip if this file has no database-related content.
 
-IMPORTANT: Respond only in English (or code)"#,
-            file_path, code
+{respond}"#,
+            file_path,
+            code,
+            respond = respond_in(output_language, true),
+        )
+    }
+
+    /// Generate a prompt for sequence diagram extraction.
+    pub fn diagram_sequence_prompt(
+        &self,
+        file_path: &str,
+        code: &str,
+        output_language: &str,
+    ) -> String {
+        format!(
+            r#"Analyze this TypeScript/JavaScript file for SEQUENCE diagram information.
+
+File: {}
+
+```typescript
+{}
+```
+
+Extract ONLY the following for sequence diagram generation:
+1. **Entry Points**: Route handlers, event handlers, exported functions or components that start a flow
+2. **Call Sequence**: The ordered calls each entry point makes, one per line as `caller -> callee: action`
+   (other modules, services, APIs, databases, stores)
+3. **Responses**: What is returned, rendered or emitted at the end of each flow
+
+If this file has no significant flow, say "No significant interaction flow".
+
+{respond}"#,
+            file_path,
+            code,
+            respond = respond_in(output_language, true),
         )
     }
 }
@@ -683,7 +730,7 @@ mod tests {
     #[test]
     fn test_analysis_prompt_contains_file_path() {
         let lang = TypeScriptLanguage;
-        let prompt = lang.analysis_prompt("src/index.ts", "const x = 1;");
+        let prompt = lang.analysis_prompt("src/index.ts", "const x = 1;", "English");
 
         assert!(prompt.contains("src/index.ts"));
         assert!(prompt.contains("const x = 1;"));
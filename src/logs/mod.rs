@@ -0,0 +1,436 @@
+//! Live log capture for the dashboard.
+//!
+//! A tracing layer keeps the most recent log records in a ring buffer and
+//! broadcasts new ones, so `/api/logs/stream` can send a backlog followed by
+//! live records. While the daemon runs, records are also appended to
+//! `<data_dir>/logs/noctum.log` as JSON lines, and the ring buffer is seeded
+//! from the end of that file on startup, so the backlog survives restarts.
+//!
+//! Which records are kept at all is decided by `general.log_level`, parsed
+//! here into a filter with per-module levels.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::layer::{Context as LayerContext, Layer};
+
+/// Records kept in memory
+const CAPACITY: usize = 2000;
+
+/// The filter for `directives`, a level optionally followed by per-module
+/// levels, e.g. `info,noctum::daemon=debug,sqlx=warn`. Stricter than
+/// `RUST_LOG`, where a misspelled level silently becomes a module name.
+pub fn parse_filter(directives: &str) -> Result<EnvFilter> {
+    let directives: Vec<&str> = directives
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .collect();
+    for directive in &directives {
+        let level = match directive.rsplit_once('=') {
+            Some((module, level)) if !module.trim().is_empty() => level,
+            Some(_) => anyhow::bail!("Invalid log directive '{}': missing module", directive),
+            None => directive,
+        };
+        level.trim().parse::<LevelFilter>().map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid log directive '{}': expected a level (trace, debug, info, warn, error, off) or module=level",
+                directive
+            )
+        })?;
+    }
+    let directives = directives.join(",");
+    EnvFilter::try_new(&directives)
+        .with_context(|| format!("Invalid log directives '{}'", directives))
+}
+
+/// When the log file is rotated
+#[derive(Debug, Clone, Copy)]
+pub struct Rotation {
+    /// Size at which `noctum.log` is moved to `noctum.log.1`
+    pub max_bytes: u64,
+    /// Rotated files kept; older ones are deleted
+    pub keep: u32,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: 10 * 1024 * 1024,
+            keep: 5,
+        }
+    }
+}
+
+/// A captured log record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// RFC 3339 timestamp
+    pub timestamp: String,
+    /// `ERROR`, `WARN`, `INFO`, `DEBUG` or `TRACE`
+    pub level: String,
+    /// Module the record was logged from, e.g. `noctum::daemon`
+    pub target: String,
+    /// The message followed by any other fields as `key=value`
+    pub message: String,
+}
+
+/// Which records to show. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// Least severe level shown
+    pub level: Option<Level>,
+    /// Target prefix, e.g. `noctum::mutation`
+    pub target: Option<String>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        // More verbose levels compare greater in tracing
+        let level_ok = match (self.level, record.level.parse::<Level>()) {
+            (Some(max), Ok(level)) => level <= max,
+            (Some(_), Err(_)) => false,
+            (None, _) => true,
+        };
+        level_ok
+            && self
+                .target
+                .as_deref()
+                .is_none_or(|target| record.target.starts_with(target))
+    }
+}
+
+/// The log file records are appended to
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    rotation: Rotation,
+}
+
+impl LogFile {
+    fn open(path: &Path, rotation: Rotation) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open log file {}", path.display()))?;
+        let size = file.metadata().map_or(0, |m| m.len());
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
+            rotation,
+        })
+    }
+
+    /// `noctum.log.<n>`
+    fn rotated_path(&self, n: u32) -> PathBuf {
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    /// Shift `noctum.log.<n>` to `.<n+1>`, dropping the oldest, and start a
+    /// fresh file
+    fn rotate(&mut self) -> Result<()> {
+        let keep = self.rotation.keep;
+        if keep == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated_path(keep));
+            for n in (1..keep).rev() {
+                let _ = std::fs::rename(self.rotated_path(n), self.rotated_path(n + 1));
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        *self = Self::open(&self.path, self.rotation)?;
+        Ok(())
+    }
+
+    fn append(&mut self, record: &LogRecord) {
+        if self.size > self.rotation.max_bytes && self.rotate().is_err() {
+            return;
+        }
+        if let Ok(mut line) = serde_json::to_string(record) {
+            line.push('\n');
+            if self.file.write_all(line.as_bytes()).is_ok() {
+                self.size += line.len() as u64;
+            }
+        }
+    }
+}
+
+struct Inner {
+    records: Mutex<VecDeque<LogRecord>>,
+    sender: broadcast::Sender<LogRecord>,
+    file: Mutex<Option<LogFile>>,
+}
+
+/// Recent log records, shared between the tracing layer and the web server.
+/// Cheap to clone.
+#[derive(Clone)]
+pub struct LogBuffer {
+    inner: Arc<Inner>,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogBuffer {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self {
+            inner: Arc::new(Inner {
+                records: Mutex::new(VecDeque::with_capacity(CAPACITY)),
+                sender,
+                file: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// The tracing layer capturing records into this buffer
+    pub fn layer(&self) -> LogLayer {
+        LogLayer {
+            buffer: self.clone(),
+        }
+    }
+
+    /// Seed the buffer from the end of the log file at `path` and append all
+    /// further records to it, rotating it as it grows
+    pub fn attach_file(&self, path: &Path, rotation: Rotation) -> Result<()> {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            let earlier: Vec<LogRecord> = content
+                .lines()
+                .rev()
+                .take(CAPACITY)
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect();
+            let mut records = self.inner.records.lock().unwrap_or_else(|e| e.into_inner());
+            for record in earlier {
+                if records.len() >= CAPACITY {
+                    break;
+                }
+                records.push_front(record);
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create log directory {}", parent.display()))?;
+        }
+        let file = LogFile::open(path, rotation)?;
+        *self.inner.file.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+        Ok(())
+    }
+
+    /// The last `limit` records matching `filter`, plus a receiver of all
+    /// records captured after them
+    pub fn backlog_and_subscribe(
+        &self,
+        filter: &LogFilter,
+        limit: usize,
+    ) -> (Vec<LogRecord>, broadcast::Receiver<LogRecord>) {
+        // Subscribing under the lock means no record is missed or sent twice
+        let records = self.inner.records.lock().unwrap_or_else(|e| e.into_inner());
+        let mut backlog: Vec<LogRecord> = records
+            .iter()
+            .rev()
+            .filter(|record| filter.matches(record))
+            .take(limit)
+            .cloned()
+            .collect();
+        backlog.reverse();
+        (backlog, self.inner.sender.subscribe())
+    }
+
+    fn push(&self, record: LogRecord) {
+        if let Some(file) = self
+            .inner
+            .file
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_mut()
+        {
+            file.append(&record);
+        }
+
+        let mut records = self.inner.records.lock().unwrap_or_else(|e| e.into_inner());
+        if records.len() >= CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+        // No subscribers is fine
+        let _ = self.inner.sender.send(record);
+    }
+}
+
+/// Tracing layer feeding a [`LogBuffer`]
+pub struct LogLayer {
+    buffer: LogBuffer,
+}
+
+impl<S: Subscriber> Layer<S> for LogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        self.buffer.push(LogRecord {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: visitor.message + &visitor.fields,
+        });
+    }
+}
+
+/// Collects an event's message and its other fields
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn record(level: &str, target: &str, message: &str) -> LogRecord {
+        LogRecord {
+            timestamp: "2025-01-01T00:00:00.000Z".to_string(),
+            level: level.to_string(),
+            target: target.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_filter_by_level_and_target() {
+        let filter = LogFilter {
+            level: Some(Level::WARN),
+            target: Some("noctum::daemon".to_string()),
+        };
+        assert!(filter.matches(&record("ERROR", "noctum::daemon", "")));
+        assert!(filter.matches(&record("WARN", "noctum::daemon::queue", "")));
+        assert!(!filter.matches(&record("INFO", "noctum::daemon", "")));
+        assert!(!filter.matches(&record("WARN", "noctum::web", "")));
+        assert!(LogFilter::default().matches(&record("TRACE", "sqlx", "")));
+    }
+
+    #[test]
+    fn test_layer_captures_and_broadcasts() {
+        let buffer = LogBuffer::new();
+        let subscriber = tracing_subscriber::registry().with(buffer.layer());
+        let (_, mut receiver) = buffer.backlog_and_subscribe(&LogFilter::default(), 10);
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(files = 3, "Scanning {}", "app");
+            tracing::warn!("Endpoint down");
+        });
+
+        let live = receiver.try_recv().unwrap();
+        assert_eq!(live.message, "Scanning app files=3");
+        assert_eq!(live.level, "INFO");
+
+        let warnings = LogFilter {
+            level: Some(Level::WARN),
+            target: None,
+        };
+        let (backlog, _) = buffer.backlog_and_subscribe(&warnings, 10);
+        assert_eq!(backlog.len(), 1);
+        assert_eq!(backlog[0].message, "Endpoint down");
+    }
+
+    #[test]
+    fn test_backlog_survives_restart_through_the_log_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("noctum.log");
+
+        let buffer = LogBuffer::new();
+        buffer.attach_file(&path, Rotation::default()).unwrap();
+        buffer.push(record("INFO", "noctum", "first run"));
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+
+        let restarted = LogBuffer::new();
+        restarted.attach_file(&path, Rotation::default()).unwrap();
+        restarted.push(record("INFO", "noctum", "second run"));
+
+        let (backlog, _) = restarted.backlog_and_subscribe(&LogFilter::default(), 10);
+        let messages: Vec<_> = backlog.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, ["first run", "second run"]);
+
+        let (backlog, _) = restarted.backlog_and_subscribe(&LogFilter::default(), 1);
+        assert_eq!(backlog[0].message, "second run");
+    }
+
+    #[test]
+    fn test_log_file_rotation() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("logs").join("noctum.log");
+
+        let buffer = LogBuffer::new();
+        let rotation = Rotation {
+            max_bytes: 1,
+            keep: 2,
+        };
+        buffer.attach_file(&path, rotation).unwrap();
+        for message in ["one", "two", "three", "four"] {
+            buffer.push(record("INFO", "noctum", message));
+        }
+
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+        assert!(read(path.clone()).contains("four"));
+        assert!(read(dir.path().join("logs/noctum.log.1")).contains("three"));
+        assert!(read(dir.path().join("logs/noctum.log.2")).contains("two"));
+        assert!(!dir.path().join("logs/noctum.log.3").exists());
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        assert!(parse_filter("info").is_ok());
+        assert!(parse_filter("warn,noctum::daemon=debug, sqlx=off").is_ok());
+        assert!(parse_filter("noctum=trace").is_ok());
+
+        let error = parse_filter("infoo").unwrap_err().to_string();
+        assert!(error.contains("'infoo'"), "{}", error);
+        assert!(parse_filter("info,noctum::daemon=loud").is_err());
+        assert!(parse_filter("=debug").is_err());
+    }
+}
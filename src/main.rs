@@ -1,25 +1,48 @@
 mod analyzer;
+mod chat;
 mod config;
+mod control;
 mod daemon;
 mod db;
+mod dead_code;
+mod deps;
 mod diagram;
+mod duplication;
+mod export;
+mod file_filter;
+mod issues;
 mod language;
+mod logs;
 mod mutation;
+mod notify;
+mod onboarding;
+mod plugin;
 mod project;
+mod query;
+mod remote;
 mod repo_config;
+mod report;
+mod review;
+mod search;
+mod snapshot;
+mod theme;
+mod toolchain;
 mod web;
 
+use anyhow::Context;
 use clap::{Parser, Subcommand};
 use std::sync::Arc;
 use tokio::signal;
 use tokio::sync::RwLock;
-use tracing::Level;
-use tracing_subscriber::FmtSubscriber;
+use tracing_subscriber::filter::EnvFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::reload;
+use tracing_subscriber::util::SubscriberInitExt;
 
 use crate::config::Config;
 use crate::daemon::{Daemon, DaemonHandle};
 use crate::db::Database;
-use crate::web::start_server;
+use crate::web::{start_server, ExpensiveLimit};
 
 #[derive(Parser)]
 #[command(name = "noctum")]
@@ -32,12 +55,262 @@ struct Cli {
     /// Path to configuration file
     #[arg(short, long)]
     config: Option<std::path::PathBuf>,
+
+    /// Named profile from the configuration file to use
+    #[arg(short, long, global = true)]
+    profile: Option<String>,
 }
 
 #[derive(Subcommand, Debug, PartialEq)]
 enum Commands {
     /// Start the daemon and web server
     Start,
+    /// Review the uncommitted changes (staged and unstaged) of a repository
+    Review {
+        /// Path to the repository (defaults to the current directory)
+        #[arg(default_value = ".")]
+        path: std::path::PathBuf,
+    },
+    /// Run a named query against the result database and print JSON
+    Query {
+        /// Query name (omit to list available queries)
+        name: Option<String>,
+        /// Query parameters as key=value pairs (e.g. severity=error since_days=7)
+        params: Vec<String>,
+    },
+    /// Export a repository's latest results as a Markdown or HTML report
+    Report {
+        /// Repository ID (as shown by `noctum query repositories`)
+        repository_id: i64,
+        /// Report format: md or html
+        #[arg(short, long, default_value = "md")]
+        format: report::ReportFormat,
+        /// Write the report to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Export a repository's results for other tools
+    Export {
+        #[command(subcommand)]
+        command: ExportCommand,
+    },
+    /// Restore the database from a backup (stop the daemon first)
+    Restore {
+        /// Backup file to restore (defaults to the newest backup)
+        backup: Option<std::path::PathBuf>,
+    },
+    /// Manage the repositories analyzed by the daemon
+    Repo {
+        #[command(subcommand)]
+        command: RepoCommand,
+    },
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Dashboard and report templates
+    Templates {
+        #[command(subcommand)]
+        command: TemplatesCommand,
+    },
+    /// Show, edit and validate the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Control the running daemon through its control socket
+    Daemon {
+        #[command(subcommand)]
+        command: DaemonCommand,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+enum DaemonCommand {
+    /// Print the daemon's state as JSON
+    Status,
+    /// Start a scan now, regardless of the schedule
+    Scan,
+    /// Stop processing until resumed
+    Pause,
+    /// Resume processing after a pause
+    Resume,
+    /// Shut the daemon down
+    Stop,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+enum ExportCommand {
+    /// Export the latest findings as SARIF 2.1.0, e.g. for GitHub code scanning
+    Sarif {
+        /// Repository ID (as shown by `noctum repo list`)
+        repository_id: i64,
+        /// Write the log to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+enum TemplatesCommand {
+    /// Copy the built-in templates into a directory, as a starting point for
+    /// `web.templates_dir` (existing files are left untouched)
+    Export {
+        /// Target directory
+        dir: std::path::PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+enum ConfigCommand {
+    /// Print the effective configuration, with the profile applied
+    Show,
+    /// Set a key, e.g. `web.port 9000` or `endpoints[0].model llama3`
+    /// (with --profile, in that profile's section)
+    Set {
+        /// Dotted path of the key
+        key: String,
+        /// New value, read as TOML (`9000`, `true`, `["a"]`) or else a string
+        value: String,
+    },
+    /// Add an Ollama endpoint (with --profile, to that profile's endpoints)
+    AddEndpoint {
+        /// Unique endpoint name
+        #[arg(long)]
+        name: String,
+        /// Ollama URL, e.g. http://localhost:11434
+        #[arg(long)]
+        url: String,
+        /// Model to analyze with
+        #[arg(long)]
+        model: String,
+        /// Context window of the model in tokens
+        #[arg(long)]
+        context_window: Option<u32>,
+        /// The endpoint runs on trusted hardware and may see sensitive code
+        #[arg(long)]
+        local: bool,
+    },
+    /// Check the config file, naming the offending key of each problem
+    Validate,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+enum RepoCommand {
+    /// Add a repository (it must contain a noctum.toml)
+    Add {
+        /// Path to the repository, or a Git URL to clone
+        path: std::path::PathBuf,
+        /// Display name (defaults to the directory or repository name)
+        #[arg(long)]
+        name: Option<String>,
+        /// Branch to check out of a Git URL (defaults to the remote's default branch)
+        #[arg(long)]
+        branch: Option<String>,
+    },
+    /// Find the repositories in a directory and add the new ones in bulk
+    ScanDir {
+        /// Directory to search, e.g. ~/code
+        dir: std::path::PathBuf,
+        /// How many directory levels to search
+        #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(1..=8))]
+        depth: u32,
+        /// Add the new repositories without asking
+        #[arg(long, short)]
+        yes: bool,
+        /// Create an empty noctum.toml in repositories without one, so they
+        /// can be added
+        #[arg(long)]
+        init: bool,
+    },
+    /// List the configured repositories
+    List {
+        /// Only repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// List the archived repositories instead
+        #[arg(long)]
+        archived: bool,
+    },
+    /// Replace the tags of a repository (none to clear them)
+    Tag {
+        /// Repository ID
+        id: i64,
+        /// Tags, e.g. `work backend`
+        tags: Vec<String>,
+    },
+    /// Show or set the commands mutation testing runs instead of those in
+    /// noctum.toml
+    Commands {
+        /// Repository ID
+        id: i64,
+        /// Build command replacing each rule's build_command
+        #[arg(long)]
+        compile: Option<String>,
+        /// Test command replacing each rule's test_command, e.g. `cargo nextest run`
+        #[arg(long)]
+        test: Option<String>,
+        /// Timeout in seconds replacing each rule's timeout_seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+        /// Directory to run the commands in, relative to the repository root
+        #[arg(long)]
+        working_dir: Option<String>,
+        /// Remove all overrides (applied before the other options)
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Remove a repository and all of its results
+    #[command(alias = "remove")]
+    Rm {
+        /// Repository ID (as shown by `noctum repo list`)
+        id: i64,
+    },
+    /// Resume analysis of a repository
+    Enable {
+        /// Repository ID
+        id: i64,
+    },
+    /// Stop analyzing a repository, keeping its results
+    Disable {
+        /// Repository ID
+        id: i64,
+    },
+    /// Stop analyzing a repository and hide it from listings, keeping its results
+    Archive {
+        /// Repository ID
+        id: i64,
+    },
+    /// Restore an archived repository
+    Restore {
+        /// Repository ID
+        id: i64,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+enum DbCommand {
+    /// Compact the database file, returning space freed by deleted rows
+    Vacuum,
+    /// Delete superseded analysis results, mutation runs and diagrams
+    Prune {
+        /// Number of versions to keep per file (and per diagram type)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        keep_last: u32,
+    },
+    /// Print row counts and sizes of all tables
+    Stats,
+    /// Snapshot the database, safely while the daemon is running
+    Backup {
+        /// File to write, or a directory to write a timestamped backup into
+        path: std::path::PathBuf,
+    },
+    /// Restore the database from a backup (stop the daemon first)
+    Restore {
+        /// Backup file to restore (defaults to the newest backup)
+        backup: Option<std::path::PathBuf>,
+    },
 }
 
 /// Shared application state
@@ -45,21 +318,54 @@ pub struct AppState {
     pub db: Database,
     pub config: Arc<RwLock<Config>>,
     pub daemon: DaemonHandle,
+    /// Recent log records for the dashboard
+    pub logs: logs::LogBuffer,
+    /// Rate limit and queue shared by the expensive API endpoints
+    pub expensive: ExpensiveLimit,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .with_target(false)
+    // Initialize logging, keeping recent records for the dashboard. The
+    // configured level replaces INFO once the config is loaded.
+    let log_buffer = logs::LogBuffer::new();
+    let (log_filter, log_filter_handle) = reload::Layer::new(EnvFilter::new("info"));
+    tracing_subscriber::registry()
+        .with(log_filter)
+        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(log_buffer.layer())
         .init();
 
     let cli = Cli::parse();
 
     // Load configuration
     let config_path = cli.config.clone().or_else(Config::default_config_path);
-    let config = Config::load(cli.config.as_deref())?;
+
+    // Config commands must work on a config file that doesn't load
+    let command = match cli.command.unwrap_or(Commands::Start) {
+        Commands::Config { command } => {
+            let path = config_path.context("No config path available; pass --config")?;
+            return config_command(&path, cli.profile.as_deref(), command);
+        }
+        command => command,
+    };
+
+    let config = Config::load(cli.config.as_deref(), cli.profile.as_deref())?;
+
+    // RUST_LOG, when set, takes precedence over general.log_level
+    let filter = match std::env::var("RUST_LOG") {
+        Ok(directives) if !directives.is_empty() => {
+            logs::parse_filter(&directives).context("Invalid RUST_LOG environment variable")?
+        }
+        _ => logs::parse_filter(&config.general.log_level)
+            .context("Invalid general.log_level in the config file")?,
+    };
+    log_filter_handle
+        .reload(filter)
+        .context("Failed to apply the log level")?;
+    config
+        .check_database_url()
+        .context("Invalid general.database_url in the config file")?;
 
     tracing::info!(
         "Config path: {}",
@@ -68,32 +374,76 @@ async fn main() -> anyhow::Result<()> {
             .map(|p| p.display().to_string())
             .unwrap_or_else(|| "(none, using defaults)".to_string())
     );
+    if let Some(ref profile) = config.profile {
+        tracing::info!("Profile: {}", profile);
+    }
     tracing::info!("Data directory: {}", config.data_dir().display());
+    language::register_command_languages(&config.languages);
 
-    match cli.command.unwrap_or(Commands::Start) {
+    match command {
         Commands::Start => {
             tracing::info!("Starting Noctum daemon...");
 
             // Initialize database
-            let db = Database::new(&config.database_path()).await?;
+            let db = Database::open(&config.database()).await?;
             db.run_migrations().await?;
             tracing::info!("Database initialized");
 
+            if config.general.log_file {
+                let log_path = config.log_dir().join("noctum.log");
+                // The log used to live directly in the data directory
+                let legacy_path = config.data_dir().join("noctum.log");
+                if legacy_path.exists() && !log_path.exists() {
+                    let _ = std::fs::create_dir_all(config.log_dir());
+                    let _ = std::fs::rename(&legacy_path, &log_path);
+                }
+                let rotation = logs::Rotation {
+                    max_bytes: config.general.log_max_size_mb.max(1) * 1024 * 1024,
+                    keep: config.general.log_max_files,
+                };
+                if let Err(e) = log_buffer.attach_file(&log_path, rotation) {
+                    tracing::warn!("Logging to memory only: {:#}", e);
+                }
+            }
+
             // Initialize daemon with shared config
             let config = Arc::new(RwLock::new(config));
             let mut daemon = Daemon::new(config.clone(), db.clone());
             let daemon_handle = daemon.handle();
 
             // Create shared state
+            let expensive = {
+                let web = &config.read().await.web;
+                ExpensiveLimit::new(web.expensive_requests_per_minute, web.expensive_queue_size)
+            };
             let state = Arc::new(AppState {
                 db,
                 config: config.clone(),
                 daemon: daemon_handle.clone(),
+                logs: log_buffer,
+                expensive,
             });
 
             // Start the daemon in a background task
             let mut daemon_task = tokio::spawn(async move { daemon.run().await });
 
+            // Accept commands on the control socket
+            let control_socket = {
+                let config = config.read().await;
+                config
+                    .general
+                    .control_socket
+                    .then(|| config.control_socket_path())
+            };
+            if let Some(path) = control_socket.clone() {
+                let handle = daemon_handle.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = control::serve(&path, handle).await {
+                        tracing::warn!("Control socket unavailable: {:#}", e);
+                    }
+                });
+            }
+
             // Start the web server
             let web_host = config.read().await.web.host.clone();
             let web_port = config.read().await.web.port;
@@ -106,17 +456,19 @@ async fn main() -> anyhow::Result<()> {
             );
             tracing::info!("Press Ctrl+C to stop");
 
-            // Wait for shutdown signal or task failure
-            tokio::select! {
+            // Wait for shutdown signal, a stop command or task failure
+            let daemon_finished = tokio::select! {
                 _ = shutdown_signal() => {
                     tracing::info!("Shutdown signal received");
+                    false
                 }
                 result = &mut daemon_task => {
                     match result {
-                        Ok(Ok(())) => tracing::info!("Daemon stopped unexpectedly"),
+                        Ok(Ok(())) => tracing::debug!("Daemon task completed"),
                         Ok(Err(e)) => tracing::error!("Daemon error: {}", e),
                         Err(e) => tracing::error!("Daemon task panicked: {}", e),
                     }
+                    true
                 }
                 result = &mut server_handle => {
                     match result {
@@ -124,31 +476,509 @@ async fn main() -> anyhow::Result<()> {
                         Ok(Err(e)) => tracing::error!("Server error: {}", e),
                         Err(e) => tracing::error!("Server task panicked: {}", e),
                     }
+                    false
+                }
+            };
+
+            if !daemon_finished {
+                // Signal daemon to stop
+                tracing::info!("Stopping daemon...");
+                daemon_handle.stop();
+
+                // Give the daemon a moment to finish current work, then exit
+                // The web server will be terminated when we exit
+                tokio::select! {
+                    _ = tokio::time::sleep(std::time::Duration::from_secs(2)) => {
+                        tracing::debug!("Shutdown timeout reached");
+                    }
+                    _ = &mut daemon_task => {
+                        tracing::debug!("Daemon task completed");
+                    }
                 }
             }
 
-            // Signal daemon to stop
-            tracing::info!("Stopping daemon...");
-            daemon_handle.stop();
+            if let Some(path) = control_socket {
+                let _ = std::fs::remove_file(path);
+            }
+            tracing::info!("Noctum stopped");
+        }
+        Commands::Review { path } => match review::review_repository(&config, &path).await? {
+            Some(review) => println!("{}", review),
+            None => println!("No uncommitted changes to review"),
+        },
+        Commands::Query { name, params } => {
+            let Some(name) = name else {
+                for info in query::QUERIES {
+                    println!("{:<16} {}", info.name, info.description);
+                    if !info.params.is_empty() {
+                        println!("{:<16} params: {}", "", info.params.join(", "));
+                    }
+                }
+                return Ok(());
+            };
 
-            // Give the daemon a moment to finish current work, then exit
-            // The web server will be terminated when we exit
-            tokio::select! {
-                _ = tokio::time::sleep(std::time::Duration::from_secs(2)) => {
-                    tracing::debug!("Shutdown timeout reached");
+            let query = query::Query::parse(&name, &query::parse_params(&params)?)?;
+            let db = Database::open(&config.database()).await?;
+            db.run_migrations().await?;
+            let rows = query.run(&db).await?;
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+        }
+        Commands::Report {
+            repository_id,
+            format,
+            output,
+        } => {
+            let db = Database::open(&config.database()).await?;
+            db.run_migrations().await?;
+            let templates = theme::Templates::new(config.web.templates_dir.as_deref());
+            let rendered = report::Report::load(&db, repository_id)
+                .await?
+                .render(format, &templates)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, rendered)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    tracing::info!("Report written to {}", path.display());
                 }
-                _ = &mut daemon_task => {
-                    tracing::debug!("Daemon task completed");
+                None => print!("{}", rendered),
+            }
+        }
+        Commands::Export {
+            command:
+                ExportCommand::Sarif {
+                    repository_id,
+                    output,
+                },
+        } => {
+            let db = Database::open(&config.database()).await?;
+            db.run_migrations().await?;
+            let log = report::sarif::load(&db, repository_id).await?;
+            let rendered = serde_json::to_string_pretty(&log)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, rendered)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    tracing::info!("SARIF log written to {}", path.display());
                 }
+                None => println!("{}", rendered),
             }
+        }
+        Commands::Restore { backup }
+        | Commands::Db {
+            command: DbCommand::Restore { backup },
+        } => restore_database(&config, backup).await?,
+        Commands::Db {
+            command: DbCommand::Backup { path },
+        } => {
+            let destination = if path.is_dir() {
+                db::backup::new_backup_path(&path)
+            } else {
+                path
+            };
+            db::backup::backup_to(&db::backup::database_file(&config)?, &destination).await?;
+            println!(
+                "Database backed up to {} ({} bytes)",
+                destination.display(),
+                file_size(&destination)
+            );
+        }
+        Commands::Repo { command } => {
+            let db = Database::open(&config.database()).await?;
+            db.run_migrations().await?;
+            match command {
+                RepoCommand::Add { path, name, branch } => {
+                    let source = path.to_string_lossy().to_string();
+                    let (id, name, location) = if remote::is_git_url(&source) {
+                        let name = name.unwrap_or_else(|| remote::repository_name(&source));
+                        println!("Cloning {}...", remote::redact(&source));
+                        let (id, checkout) = remote::add_repository(
+                            &db,
+                            &config.data_dir(),
+                            &source,
+                            branch.as_deref(),
+                            &name,
+                        )
+                        .await?;
+                        (id, name, format!("checked out at {}", checkout.display()))
+                    } else {
+                        if branch.is_some() {
+                            anyhow::bail!("--branch only applies to repositories added by Git URL");
+                        }
+                        let path = project::resolve_repository_path(&path, &config.data_dir())
+                            .with_context(|| format!("Cannot add {}", path.display()))?;
+                        let name = name.unwrap_or_else(|| {
+                            path.file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_else(|| path.to_string_lossy().to_string())
+                        });
+                        let id = db.add_repository(&path.to_string_lossy(), &name).await?;
+                        (id, name, path.display().to_string())
+                    };
+                    println!("Added repository {} ({}) with ID {}", name, location, id);
+                }
+                RepoCommand::ScanDir {
+                    dir,
+                    depth,
+                    yes,
+                    init,
+                } => {
+                    let found = project::find_repositories(&dir, depth as usize)?;
+                    let registered: std::collections::HashSet<String> = db
+                        .get_repositories()
+                        .await?
+                        .into_iter()
+                        .map(|repo| repo.path)
+                        .collect();
 
-            tracing::info!("Noctum stopped");
+                    println!("{:<24} {:<12} {:<20} PATH", "NAME", "STATUS", "LANGUAGES");
+                    let mut new = Vec::new();
+                    let mut unconfigured = 0;
+                    for repo in &found {
+                        let status = if registered.contains(&*repo.path.to_string_lossy()) {
+                            "added"
+                        } else if repo.configured || init {
+                            new.push(repo);
+                            "new"
+                        } else {
+                            unconfigured += 1;
+                            "no config"
+                        };
+                        println!(
+                            "{:<24} {:<12} {:<20} {}",
+                            repo.name,
+                            status,
+                            repo.languages.join(","),
+                            repo.path.display()
+                        );
+                    }
+                    if unconfigured > 0 {
+                        println!(
+                            "{} repositories have no noctum.toml; pass --init to create one",
+                            unconfigured
+                        );
+                    }
+                    if new.is_empty() {
+                        println!("No new repositories found in {}", dir.display());
+                        return Ok(());
+                    }
+                    if !yes && !confirm(&format!("Add {} repositories?", new.len()))? {
+                        println!("No repositories added");
+                        return Ok(());
+                    }
+
+                    let mut added = 0;
+                    for repo in new {
+                        let result = async {
+                            let path =
+                                project::resolve_repository_path(&repo.path, &config.data_dir())?;
+                            if !repo.configured {
+                                std::fs::write(path.join("noctum.toml"), "")
+                                    .context("Failed to create noctum.toml")?;
+                            }
+                            db.add_repository(&path.to_string_lossy(), &repo.name).await
+                        }
+                        .await;
+                        match result {
+                            Ok(id) => {
+                                println!("Added repository {} with ID {}", repo.name, id);
+                                added += 1;
+                            }
+                            Err(e) => println!("Skipped {}: {:#}", repo.path.display(), e),
+                        }
+                    }
+                    println!("Added {} repositories", added);
+                }
+                RepoCommand::List { tag, archived } => {
+                    let tag = tag.map(|tag| tag.trim().to_lowercase());
+                    println!(
+                        "{:>4}  {:<24} {:<8} {:<16} SOURCE",
+                        "ID", "NAME", "ENABLED", "TAGS"
+                    );
+                    for repo in db.get_repositories().await? {
+                        if repo.archived() != archived
+                            || tag.as_deref().is_some_and(|tag| !repo.has_tag(tag))
+                        {
+                            continue;
+                        }
+                        println!(
+                            "{:>4}  {:<24} {:<8} {:<16} {}",
+                            repo.id,
+                            repo.name,
+                            if repo.enabled { "yes" } else { "no" },
+                            repo.tags.join(","),
+                            remote::describe_source(&repo)
+                        );
+                    }
+                }
+                RepoCommand::Tag { id, tags } => {
+                    let tags = crate::db::normalize_tags(&tags).map_err(anyhow::Error::msg)?;
+                    if !db.set_repository_tags(id, &tags).await? {
+                        anyhow::bail!("No repository with ID {}", id);
+                    }
+                    if tags.is_empty() {
+                        println!("Removed the tags of repository {}", id);
+                    } else {
+                        println!("Tagged repository {}: {}", id, tags.join(", "));
+                    }
+                }
+                RepoCommand::Commands {
+                    id,
+                    compile,
+                    test,
+                    timeout,
+                    working_dir,
+                    clear,
+                } => {
+                    let repo = db
+                        .get_repository(id)
+                        .await?
+                        .with_context(|| format!("No repository with ID {}", id))?;
+                    let current = if clear {
+                        crate::db::CommandOverrides::default()
+                    } else {
+                        repo.commands.clone()
+                    };
+                    let commands = crate::db::CommandOverrides {
+                        compile_command: compile.or(current.compile_command),
+                        test_command: test.or(current.test_command),
+                        timeout_seconds: timeout.or(current.timeout_seconds),
+                        working_dir: working_dir.or(current.working_dir),
+                    }
+                    .normalize()
+                    .map_err(anyhow::Error::msg)?;
+
+                    if commands != repo.commands {
+                        db.set_repository_commands(id, &commands).await?;
+                        let detail = if commands.is_empty() {
+                            "Cleared command overrides".to_string()
+                        } else {
+                            format!("Set command overrides: {}", commands.summary())
+                        };
+                        db.record_audit(Some(id), crate::db::AUDIT_COMMANDS_CHANGED, &detail)
+                            .await?;
+                    }
+                    if commands.is_empty() {
+                        println!("Repository {} uses the commands of its noctum.toml", id);
+                    } else {
+                        println!("Repository {}: {}", id, commands.summary());
+                    }
+                }
+                RepoCommand::Rm { id } => {
+                    let repo = db.get_repository(id).await?;
+                    if !db.delete_repository(id).await? {
+                        anyhow::bail!("No repository with ID {}", id);
+                    }
+                    if let Some(repo) = repo {
+                        remote::remove_repository_checkout(&repo, &config.data_dir());
+                    }
+                    println!("Removed repository {}", id);
+                }
+                RepoCommand::Enable { id } | RepoCommand::Disable { id } => {
+                    let enabled = matches!(command, RepoCommand::Enable { .. });
+                    if !db.set_repository_enabled(id, enabled).await? {
+                        anyhow::bail!("No repository with ID {}", id);
+                    }
+                    println!(
+                        "{} repository {}",
+                        if enabled { "Enabled" } else { "Disabled" },
+                        id
+                    );
+                }
+                RepoCommand::Archive { id } | RepoCommand::Restore { id } => {
+                    let archived = matches!(command, RepoCommand::Archive { .. });
+                    if !db.set_repository_archived(id, archived).await? {
+                        anyhow::bail!("No repository with ID {}", id);
+                    }
+                    if archived {
+                        db.delete_pending_jobs(id).await?;
+                    }
+                    println!(
+                        "{} repository {}",
+                        if archived { "Archived" } else { "Restored" },
+                        id
+                    );
+                }
+            }
+        }
+        Commands::Daemon { command } => {
+            let command = match command {
+                DaemonCommand::Status => control::Command::Status,
+                DaemonCommand::Scan => control::Command::TriggerScan,
+                DaemonCommand::Pause => control::Command::Pause,
+                DaemonCommand::Resume => control::Command::Resume,
+                DaemonCommand::Stop => control::Command::Stop,
+            };
+            let reply = control::send(&config.control_socket_path(), command).await?;
+            match reply.get("message").and_then(|m| m.as_str()) {
+                Some(message) => println!("{}", message),
+                None => println!("{}", serde_json::to_string_pretty(&reply)?),
+            }
+        }
+        Commands::Db { command } => {
+            let db_path = config.database_path();
+            let db = Database::open(&config.database()).await?;
+            db.run_migrations().await?;
+            match command {
+                DbCommand::Vacuum => match db_path {
+                    Some(db_path) => {
+                        let before = file_size(&db_path);
+                        db.vacuum().await?;
+                        tracing::info!(
+                            "Database compacted from {} to {} bytes",
+                            before,
+                            file_size(&db_path)
+                        );
+                    }
+                    None => {
+                        db.vacuum().await?;
+                        tracing::info!("Database vacuumed");
+                    }
+                },
+                DbCommand::Prune { keep_last } => {
+                    let pruned = db.prune_history(keep_last).await?;
+                    tracing::info!(
+                        "Deleted {} analysis result(s), {} mutation result(s) and {} diagram(s); \
+                         run `noctum db vacuum` to reclaim the space",
+                        pruned.analysis_results,
+                        pruned.mutation_results,
+                        pruned.diagrams
+                    );
+                }
+                DbCommand::Stats => {
+                    println!("{:<24} {:>10} {:>12}", "TABLE", "ROWS", "BYTES");
+                    for table in db.table_stats().await? {
+                        let bytes = table.bytes.map_or("-".to_string(), |b| b.to_string());
+                        println!("{:<24} {:>10} {:>12}", table.name, table.rows, bytes);
+                    }
+                    if let Some(db_path) = db_path {
+                        println!("Database file: {} bytes", file_size(&db_path));
+                    }
+                }
+                DbCommand::Backup { .. } | DbCommand::Restore { .. } => {
+                    unreachable!("backup and restore run without opening the database")
+                }
+            }
         }
+        Commands::Templates {
+            command: TemplatesCommand::Export { dir },
+        } => {
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+            for name in theme::Templates::builtin_names() {
+                let path = dir.join(&name);
+                if path.exists() {
+                    tracing::info!("Skipping existing {}", path.display());
+                    continue;
+                }
+                let source = theme::Templates::builtin_source(&name).unwrap_or_default();
+                std::fs::write(&path, source)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                tracing::info!("Wrote {}", path.display());
+            }
+        }
+        Commands::Config { .. } => unreachable!("config commands run before the config is loaded"),
+    }
+
+    Ok(())
+}
+
+/// Run a `noctum config` subcommand against the config file at `path`
+fn config_command(
+    path: &std::path::Path,
+    profile: Option<&str>,
+    command: ConfigCommand,
+) -> anyhow::Result<()> {
+    use crate::config::edit;
+
+    match command {
+        ConfigCommand::Show => {
+            let config = Config::load(Some(path), profile)?;
+            print!("{}", toml::to_string_pretty(&config)?);
+        }
+        ConfigCommand::Set { key, value } => {
+            let mut document = edit::read_document(path)?;
+            edit::set(&mut document, profile, &key, &value)?;
+            edit::write_document(path, &document)?;
+            println!("Set {} in {}", key, path.display());
+        }
+        ConfigCommand::AddEndpoint {
+            name,
+            url,
+            model,
+            context_window,
+            local,
+        } => {
+            let mut endpoint = config::OllamaEndpoint::new(name, url, model);
+            endpoint.context_window = context_window;
+            if local {
+                endpoint.location = config::EndpointLocation::Local;
+            }
+            let mut document = edit::read_document(path)?;
+            edit::add_endpoint(&mut document, profile, &endpoint)?;
+            edit::write_document(path, &document)?;
+            println!("Added endpoint {} to {}", endpoint.name, path.display());
+        }
+        ConfigCommand::Validate => {
+            let problems = edit::validate(&edit::read_document(path)?);
+            for problem in &problems {
+                eprintln!("{}", problem);
+            }
+            if !problems.is_empty() {
+                anyhow::bail!("{} has {} problem(s)", path.display(), problems.len());
+            }
+            if let Some(profile) = profile {
+                Config::load(Some(path), Some(profile))?;
+            }
+            println!("{} is valid", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal; anything but "y" or "yes" is a no
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .context("Failed to read the answer")?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Restore the database from `backup`, or the newest automatic backup. The
+/// current database is backed up first, so the restore can be undone.
+async fn restore_database(
+    config: &Config,
+    backup: Option<std::path::PathBuf>,
+) -> anyhow::Result<()> {
+    if control::send(&config.control_socket_path(), control::Command::Status)
+        .await
+        .is_ok()
+    {
+        anyhow::bail!("The daemon is running; stop it first (`noctum daemon stop`)");
     }
 
+    let (backup_path, safety) = db::backup::restore(
+        &db::backup::database_file(config)?,
+        &config.backup_dir(),
+        backup,
+    )
+    .await?;
+    if let Some(safety) = safety {
+        tracing::info!("Current database saved to {}", safety.display());
+    }
+    tracing::info!("Database restored from {}", backup_path.display());
     Ok(())
 }
 
+/// Size of a file in bytes (0 if it can't be read)
+fn file_size(path: &std::path::Path) -> u64 {
+    std::fs::metadata(path).map_or(0, |m| m.len())
+}
+
 /// Wait for shutdown signal (Ctrl+C or SIGTERM)
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -184,6 +1014,7 @@ mod tests {
         let cli = Cli::try_parse_from(["noctum"]).unwrap();
         assert!(cli.command.is_none());
         assert!(cli.config.is_none());
+        assert!(cli.profile.is_none());
     }
 
     #[test]
@@ -192,6 +1023,18 @@ mod tests {
         assert_eq!(cli.command, Some(Commands::Start));
     }
 
+    #[test]
+    fn test_cli_parse_daemon() {
+        let cli = Cli::try_parse_from(["noctum", "daemon", "pause"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Daemon {
+                command: DaemonCommand::Pause
+            })
+        );
+        assert!(Cli::try_parse_from(["noctum", "daemon", "restart"]).is_err());
+    }
+
     #[test]
     fn test_cli_parse_config_flag() {
         let cli = Cli::try_parse_from(["noctum", "--config", "/path/to/config.toml"]).unwrap();
@@ -221,6 +1064,312 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cli_parse_profile_flag() {
+        let cli = Cli::try_parse_from(["noctum", "start", "--profile", "work"]).unwrap();
+        assert_eq!(cli.command, Some(Commands::Start));
+        assert_eq!(cli.profile.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn test_cli_parse_profile_short_flag_before_command() {
+        let cli = Cli::try_parse_from(["noctum", "-p", "work", "start"]).unwrap();
+        assert_eq!(cli.profile.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn test_cli_parse_review_default_path() {
+        let cli = Cli::try_parse_from(["noctum", "review"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Review {
+                path: std::path::PathBuf::from(".")
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_review_with_path() {
+        let cli = Cli::try_parse_from(["noctum", "review", "/path/to/repo"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Review {
+                path: std::path::PathBuf::from("/path/to/repo")
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_query() {
+        let cli = Cli::try_parse_from([
+            "noctum",
+            "query",
+            "findings",
+            "severity=error",
+            "since_days=7",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Query {
+                name: Some("findings".to_string()),
+                params: vec!["severity=error".to_string(), "since_days=7".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_report() {
+        let cli = Cli::try_parse_from(["noctum", "report", "3", "--format", "html"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Report {
+                repository_id: 3,
+                format: report::ReportFormat::Html,
+                output: None,
+            })
+        );
+        assert!(Cli::try_parse_from(["noctum", "report", "3", "--format", "pdf"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_export_sarif() {
+        let cli =
+            Cli::try_parse_from(["noctum", "export", "sarif", "3", "-o", "results.sarif"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Export {
+                command: ExportCommand::Sarif {
+                    repository_id: 3,
+                    output: Some(std::path::PathBuf::from("results.sarif")),
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_restore_latest() {
+        let cli = Cli::try_parse_from(["noctum", "restore"]).unwrap();
+        assert_eq!(cli.command, Some(Commands::Restore { backup: None }));
+    }
+
+    #[test]
+    fn test_cli_parse_restore_file() {
+        let cli = Cli::try_parse_from(["noctum", "restore", "/backups/noctum-1.db"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Restore {
+                backup: Some(std::path::PathBuf::from("/backups/noctum-1.db"))
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_db() {
+        let cli = Cli::try_parse_from(["noctum", "db", "prune", "--keep-last", "3"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Db {
+                command: DbCommand::Prune { keep_last: 3 }
+            })
+        );
+        assert!(Cli::try_parse_from(["noctum", "db", "prune", "--keep-last", "0"]).is_err());
+        assert!(Cli::try_parse_from(["noctum", "db", "prune"]).is_err());
+
+        let cli = Cli::try_parse_from(["noctum", "db", "stats"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Db {
+                command: DbCommand::Stats
+            })
+        );
+
+        let cli = Cli::try_parse_from(["noctum", "db", "backup", "/tmp/noctum.db"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Db {
+                command: DbCommand::Backup {
+                    path: std::path::PathBuf::from("/tmp/noctum.db")
+                }
+            })
+        );
+        assert!(Cli::try_parse_from(["noctum", "db", "backup"]).is_err());
+
+        let cli = Cli::try_parse_from(["noctum", "db", "restore"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Db {
+                command: DbCommand::Restore { backup: None }
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_repo() {
+        let cli =
+            Cli::try_parse_from(["noctum", "repo", "add", "../app", "--name", "App"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Repo {
+                command: RepoCommand::Add {
+                    path: std::path::PathBuf::from("../app"),
+                    name: Some("App".to_string()),
+                    branch: None,
+                }
+            })
+        );
+
+        let cli =
+            Cli::try_parse_from(["noctum", "repo", "scan-dir", "/code", "--depth", "3", "-y"])
+                .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Repo {
+                command: RepoCommand::ScanDir {
+                    dir: std::path::PathBuf::from("/code"),
+                    depth: 3,
+                    yes: true,
+                    init: false,
+                }
+            })
+        );
+        assert!(
+            Cli::try_parse_from(["noctum", "repo", "scan-dir", "/code", "--depth", "0"]).is_err()
+        );
+
+        let cli = Cli::try_parse_from(["noctum", "repo", "remove", "3"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Repo {
+                command: RepoCommand::Rm { id: 3 }
+            })
+        );
+
+        let cli = Cli::try_parse_from(["noctum", "repo", "disable", "2"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Repo {
+                command: RepoCommand::Disable { id: 2 }
+            })
+        );
+        let cli = Cli::try_parse_from(["noctum", "repo", "archive", "2"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Repo {
+                command: RepoCommand::Archive { id: 2 }
+            })
+        );
+        let cli = Cli::try_parse_from(["noctum", "repo", "list", "--archived"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Repo {
+                command: RepoCommand::List {
+                    tag: None,
+                    archived: true
+                }
+            })
+        );
+        let cli = Cli::try_parse_from(["noctum", "repo", "tag", "2", "work", "backend"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Repo {
+                command: RepoCommand::Tag {
+                    id: 2,
+                    tags: vec!["work".to_string(), "backend".to_string()]
+                }
+            })
+        );
+        let cli = Cli::try_parse_from([
+            "noctum",
+            "repo",
+            "commands",
+            "3",
+            "--test",
+            "cargo nextest run",
+            "--timeout",
+            "600",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Repo {
+                command: RepoCommand::Commands {
+                    id: 3,
+                    compile: None,
+                    test: Some("cargo nextest run".to_string()),
+                    timeout: Some(600),
+                    working_dir: None,
+                    clear: false,
+                }
+            })
+        );
+        assert!(Cli::try_parse_from(["noctum", "repo", "enable"]).is_err());
+        assert!(Cli::try_parse_from(["noctum", "repo", "rm", "app"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_templates_export() {
+        let cli = Cli::try_parse_from(["noctum", "templates", "export", "theme"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Templates {
+                command: TemplatesCommand::Export {
+                    dir: std::path::PathBuf::from("theme")
+                }
+            })
+        );
+        assert!(Cli::try_parse_from(["noctum", "templates", "export"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_config() {
+        let cli = Cli::try_parse_from(["noctum", "config", "set", "web.port", "9000"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Config {
+                command: ConfigCommand::Set {
+                    key: "web.port".to_string(),
+                    value: "9000".to_string(),
+                }
+            })
+        );
+
+        let cli = Cli::try_parse_from([
+            "noctum",
+            "config",
+            "add-endpoint",
+            "--name",
+            "local",
+            "--url",
+            "http://localhost:11434",
+            "--model",
+            "llama3",
+            "--local",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Config {
+                command: ConfigCommand::AddEndpoint {
+                    name: "local".to_string(),
+                    url: "http://localhost:11434".to_string(),
+                    model: "llama3".to_string(),
+                    context_window: None,
+                    local: true,
+                }
+            })
+        );
+        assert!(Cli::try_parse_from(["noctum", "config", "add-endpoint", "--name", "x"]).is_err());
+
+        let cli = Cli::try_parse_from(["noctum", "config", "validate"]).unwrap();
+        assert_eq!(
+            cli.command,
+            Some(Commands::Config {
+                command: ConfigCommand::Validate
+            })
+        );
+    }
+
     #[test]
     fn test_cli_validate() {
         let cmd = Cli::command();
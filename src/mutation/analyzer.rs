@@ -1,6 +1,7 @@
 //! LLM-based mutation analysis - discovers mutation points and generates mutations.
 
 use crate::analyzer::OllamaClient;
+use crate::mutation::changes::ChangedLines;
 use crate::mutation::{GeneratedMutation, Replacement};
 use anyhow::{Context, Result};
 use serde::Deserialize;
@@ -178,8 +179,15 @@ fn add_line_numbers(code: &str) -> String {
 }
 
 /// Generate the analysis prompt
-fn analysis_prompt(file_path: &str, code: &str) -> String {
+fn analysis_prompt(file_path: &str, code: &str, changed: Option<&ChangedLines>) -> String {
     let numbered_code = add_line_numbers(code);
+    let changed = match changed {
+        Some(changed) => format!(
+            "- Only mutate lines {}: these functions changed since the rest of the file was tested.\n",
+            changed
+        ),
+        None => String::new(),
+    };
     format!(
         r#"You are a mutation testing expert. Analyze this Rust code and generate up to 3 small, targeted mutations. Focus on business logic and important functionality.
 
@@ -200,7 +208,7 @@ RULES:
 - If the file appears to be a test file (based on file path or content), do not mutate it.
 - Do not introduce changes that would fail to compile
 - Do not change type signatures of functions or methods.
-
+{changed}
 File: {file_path}
 
 ```
@@ -232,6 +240,7 @@ fn fix_mutation_prompt(
     failed_mutation: &GeneratedMutation,
     compile_error: &str,
     attempt: u8,
+    max_attempts: u8,
 ) -> String {
     let numbered_code = add_line_numbers(code);
     let replacements_json = serde_json::to_string_pretty(&failed_mutation.replacements)
@@ -261,7 +270,7 @@ Fix the mutation so it compiles. Common fixes:
 - Ensure the "find" text matches EXACTLY what's in the code
 - If the error mentions an unknown type/function, add the appropriate use statement
 
-This is attempt {attempt}/3. Return a corrected mutation with:
+This is attempt {attempt}/{max_attempts}. Return a corrected mutation with:
 - replacements: Array of {{line_number, find, replace}} - include import additions if needed
 - reasoning: Why this mutation is valuable and how you fixed it
 - description: Brief description of the mutation"#,
@@ -338,14 +347,17 @@ const LINE_TOLERANCE: usize = 3;
 
 /// Analyze a file and generate mutations in a single LLM call.
 ///
-/// Returns a list of ready-to-test mutations with their replacements.
+/// Returns a list of ready-to-test mutations with their replacements. With
+/// `changed`, the LLM is asked to stay within the changed functions and
+/// mutations outside them are dropped.
 pub async fn analyze_and_generate_mutations(
     client: &OllamaClient,
     file_path: &str,
     code: &str,
     max_mutations: usize,
+    changed: Option<&ChangedLines>,
 ) -> Result<Vec<GeneratedMutation>> {
-    let prompt = analysis_prompt(file_path, code);
+    let prompt = analysis_prompt(file_path, code, changed);
     let schema = analysis_schema();
 
     let parsed: AnalysisResponse = client
@@ -406,8 +418,16 @@ pub async fn analyze_and_generate_mutations(
                 let actual_line = match found_line {
                     Some(ln) => ln,
                     None => {
-                        // Try searching the whole file as fallback
-                        let global_match = lines.iter().position(|l| l.contains(&raw_repl.find));
+                        // Try searching the whole file as fallback, preferring the
+                        // changed functions
+                        let global_match = lines
+                            .iter()
+                            .enumerate()
+                            .position(|(idx, l)| {
+                                l.contains(&raw_repl.find)
+                                    && changed.is_none_or(|c| c.contains(idx + 1))
+                            })
+                            .or_else(|| lines.iter().position(|l| l.contains(&raw_repl.find)));
                         match global_match {
                             Some(idx) => {
                                 tracing::debug!(
@@ -445,6 +465,16 @@ pub async fn analyze_and_generate_mutations(
                 description: raw.description,
             })
         })
+        .filter(|mutation| {
+            let inside = changed.is_none_or(|c| c.mutation_changed(code, mutation));
+            if !inside {
+                tracing::debug!(
+                    "Dropping mutation outside the changed functions: {}",
+                    mutation.description
+                );
+            }
+            inside
+        })
         .take(max_mutations)
         .collect();
 
@@ -464,8 +494,16 @@ pub async fn fix_mutation_with_error(
     failed_mutation: &GeneratedMutation,
     compile_error: &str,
     attempt: u8,
+    max_attempts: u8,
 ) -> Result<GeneratedMutation> {
-    let prompt = fix_mutation_prompt(file_path, code, failed_mutation, compile_error, attempt);
+    let prompt = fix_mutation_prompt(
+        file_path,
+        code,
+        failed_mutation,
+        compile_error,
+        attempt,
+        max_attempts,
+    );
     let schema = fix_mutation_schema();
 
     let parsed: FixMutationResponse = client
@@ -568,8 +606,18 @@ mod tests {
 
     #[test]
     fn test_analysis_prompt_contains_file_path() {
-        let prompt = analysis_prompt("src/lib.rs", "fn foo() {}");
+        let prompt = analysis_prompt("src/lib.rs", "fn foo() {}", None);
         assert!(prompt.contains("src/lib.rs"));
         assert!(prompt.contains("   1 | fn foo() {}"));
+        assert!(!prompt.contains("Only mutate lines"));
+    }
+
+    #[test]
+    fn test_analysis_prompt_limits_to_changed_lines() {
+        let old = "fn foo() -> u8 {\n    1\n}\n";
+        let new = "fn foo() -> u8 {\n    2\n}\n";
+        let changed = ChangedLines::between(old, new).unwrap();
+        let prompt = analysis_prompt("src/lib.rs", new, Some(&changed));
+        assert!(prompt.contains("- Only mutate lines 1-3: these functions changed"));
     }
 }
@@ -0,0 +1,447 @@
+//! Mutation testing of changed functions.
+//!
+//! Content hashing skips files that didn't change, but a one-line change would
+//! still have the whole file mutated again. After a file's mutations are
+//! tested, the commit it was tested at is recorded (when the file matches that
+//! commit); the next run diffs the file against the commit and only mutates the
+//! functions overlapping the changed lines, so nightly runs grow with the
+//! churn instead of the size of the touched files.
+
+use crate::mutation::executor::target_line;
+use crate::mutation::GeneratedMutation;
+use anyhow::{Context, Result};
+use similar::{DiffTag, TextDiff};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// Keywords opening a function definition in the supported languages
+const FUNCTION_KEYWORDS: &[&str] = &["fn", "function", "def", "func", "fun"];
+
+/// Keywords opening a definition that contains functions rather than being one
+const CONTAINER_KEYWORDS: &[&str] = &[
+    "class",
+    "interface",
+    "impl",
+    "trait",
+    "mod",
+    "enum",
+    "struct",
+    "object",
+    "namespace",
+    "module",
+];
+
+/// Statements that take parenthesized arguments but don't define a method
+const CONTROL_KEYWORDS: &[&str] = &[
+    "if",
+    "else",
+    "for",
+    "while",
+    "switch",
+    "match",
+    "catch",
+    "try",
+    "do",
+    "return",
+    "loop",
+    "synchronized",
+    "throw",
+    "new",
+];
+
+/// Lines (1-indexed) of a file that changed since its last mutation run,
+/// widened to the functions containing them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedLines {
+    ranges: Vec<RangeInclusive<usize>>,
+}
+
+impl ChangedLines {
+    /// The functions of `new` that differ from `old`, None if nothing changed
+    pub fn between(old: &str, new: &str) -> Option<Self> {
+        let lines: Vec<&str> = new.lines().collect();
+        let mut ranges: Vec<RangeInclusive<usize>> = changed_ranges(old, new)
+            .into_iter()
+            .map(|range| {
+                let first = function_at(&lines, *range.start());
+                let last = function_at(&lines, *range.end());
+                let start = first.map_or(*range.start(), |f| *f.start().min(range.start()));
+                let end = last.map_or(*range.end(), |f| *f.end().max(range.end()));
+                start..=end
+            })
+            .collect();
+        if ranges.is_empty() {
+            return None;
+        }
+
+        ranges.sort_by_key(|range| *range.start());
+        let mut merged: Vec<RangeInclusive<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if *range.start() <= last.end() + 1 => {
+                    *last = *last.start()..=*last.end().max(range.end());
+                }
+                _ => merged.push(range),
+            }
+        }
+        Some(Self { ranges: merged })
+    }
+
+    /// Whether `line` (1-indexed) is in one of the changed functions
+    pub fn contains(&self, line: usize) -> bool {
+        self.ranges.iter().any(|range| range.contains(&line))
+    }
+
+    /// Number of lines in the changed functions
+    pub fn line_count(&self) -> usize {
+        self.ranges
+            .iter()
+            .map(|range| range.end() - range.start() + 1)
+            .sum()
+    }
+
+    /// Whether a mutation changes one of the lines of `content`. Replacements
+    /// that can't be located count at the line they were reported on.
+    pub fn mutation_changed(&self, content: &str, mutation: &GeneratedMutation) -> bool {
+        let lines: Vec<&str> = content.lines().collect();
+        mutation.replacements.iter().any(|replacement| {
+            self.contains(target_line(&lines, replacement).unwrap_or(replacement.line_number))
+        })
+    }
+}
+
+/// The ranges as `3-10, 42`
+impl fmt::Display for ChangedLines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, range) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            if range.start() == range.end() {
+                write!(f, "{}", range.start())?;
+            } else {
+                write!(f, "{}-{}", range.start(), range.end())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lines of `new` inserted or modified since `old`. A deletion marks the lines
+/// on both sides of the gap, since it changes the code around them.
+fn changed_ranges(old: &str, new: &str) -> Vec<RangeInclusive<usize>> {
+    let line_count = new.lines().count();
+    let diff = TextDiff::from_lines(old, new);
+    diff.ops()
+        .iter()
+        .filter(|op| op.tag() != DiffTag::Equal)
+        .filter_map(|op| {
+            let range = op.new_range();
+            let (start, end) = if range.is_empty() {
+                (range.start.max(1), (range.start + 1).min(line_count))
+            } else {
+                (range.start + 1, range.end)
+            };
+            (start <= end).then_some(start..=end)
+        })
+        .collect()
+}
+
+/// The outermost function containing `line` (1-indexed), from its signature
+/// to the end of its body, judged by indentation since any of the supported
+/// languages may be mutated
+fn function_at(lines: &[&str], line: usize) -> Option<RangeInclusive<usize>> {
+    let mut function = None;
+    // Enclosing definitions are indented less than what they contain
+    let mut bound = usize::MAX;
+    for i in (0..line.min(lines.len())).rev() {
+        if lines[i].trim().is_empty() || indentation(lines[i]) >= bound {
+            continue;
+        }
+        bound = indentation(lines[i]);
+        let header = signature_start(lines, i);
+        if is_container(lines[header]) {
+            break;
+        }
+        if is_function(lines[header]) {
+            function = Some(header);
+        }
+        if bound == 0 {
+            break;
+        }
+    }
+    function.map(|header| header + 1..=body_end(lines, header) + 1)
+}
+
+/// The first line of a signature continued over several lines, e.g. from the
+/// `) -> Result<()> {` line back to `fn run(`
+fn signature_start(lines: &[&str], mut i: usize) -> usize {
+    let indent = indentation(lines[i]);
+    while is_closing(lines[i]) || lines[i].trim_start().starts_with("where") {
+        let previous = (0..i)
+            .rev()
+            .find(|&j| !lines[j].trim().is_empty() && indentation(lines[j]) <= indent);
+        match previous {
+            Some(j) => i = j,
+            None => break,
+        }
+    }
+    i
+}
+
+/// The last line (0-indexed) of the body of the definition starting at `header`
+fn body_end(lines: &[&str], header: usize) -> usize {
+    let indent = indentation(lines[header]);
+    let mut end = header;
+    for (i, line) in lines.iter().enumerate().skip(header + 1) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if indentation(line) > indent {
+            end = i;
+            continue;
+        }
+        // A brace on its own line or the rest of a multi-line signature
+        if trimmed.starts_with('{') || (is_closing(line) && opens_block(trimmed)) {
+            end = i;
+            continue;
+        }
+        if is_closing(line) || trimmed == "end" {
+            return i;
+        }
+        break;
+    }
+    end
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_closing(line: &str) -> bool {
+    matches!(line.trim_start().chars().next(), Some('}' | ')' | ']'))
+}
+
+fn opens_block(trimmed: &str) -> bool {
+    trimmed.ends_with('{') || trimmed.ends_with(':') || trimmed.ends_with("=>")
+}
+
+fn words(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+}
+
+fn is_container(line: &str) -> bool {
+    !is_function(line) && words(line).any(|word| CONTAINER_KEYWORDS.contains(&word))
+}
+
+/// Whether `line` starts a function: a function keyword, an arrow function,
+/// or a method signature such as `public int size() {`
+fn is_function(line: &str) -> bool {
+    let trimmed = line.trim();
+    if words(trimmed).any(|word| FUNCTION_KEYWORDS.contains(&word)) || trimmed.contains("=>") {
+        return true;
+    }
+    let Some(paren) = trimmed.find('(') else {
+        return false;
+    };
+    let before = &trimmed[..paren];
+    let first = words(trimmed).next().unwrap_or_default();
+    !before.contains('=')
+        && !before.contains('.')
+        && !CONTROL_KEYWORDS.contains(&first)
+        && (trimmed.ends_with('{') || trimmed.ends_with('(') || trimmed.ends_with(','))
+}
+
+/// The commit checked out in the repository at `repo_path`, None outside a
+/// git repository or before the first commit
+pub async fn head_commit(repo_path: &Path) -> Option<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+        .current_dir(repo_path)
+        .output()
+        .await
+        .ok()?;
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !commit.is_empty()).then_some(commit)
+}
+
+/// Whether `relative_path` is tracked and unchanged from HEAD, so that a diff
+/// against HEAD later shows exactly what changed since now
+pub async fn is_committed(repo_path: &Path, relative_path: &str) -> bool {
+    // Untracked and ignored files are listed too (`??` and `!!`)
+    let output = tokio::process::Command::new("git")
+        .args(["status", "--porcelain", "--ignored", "--", relative_path])
+        .current_dir(repo_path)
+        .output()
+        .await;
+    matches!(output, Ok(output) if output.status.success() && output.stdout.is_empty())
+}
+
+/// Content of `relative_path` at `commit`
+pub async fn file_at_commit(repo_path: &Path, commit: &str, relative_path: &str) -> Result<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["show", &format!("{}:./{}", commit, relative_path)])
+        .current_dir(repo_path)
+        .output()
+        .await
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git show failed in {}: {}",
+            repo_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::Replacement;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    const RUST: &str = "\
+use std::fmt;
+
+pub struct Counter {
+    count: u32,
+}
+
+impl Counter {
+    pub fn increment(&mut self) {
+        self.count += 1;
+    }
+
+    pub fn add(
+        &mut self,
+        n: u32,
+    ) -> u32 {
+        self.count += n;
+        self.count
+    }
+}
+
+fn helper() -> bool {
+    true
+}
+";
+
+    fn changed(old: &str, new: &str) -> String {
+        ChangedLines::between(old, new).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_change_widened_to_function() {
+        let new = RUST.replace("self.count += 1;", "self.count += 2;");
+        assert_eq!(changed(RUST, &new), "8-10");
+
+        // The body of a signature spread over several lines
+        let new = RUST.replace("self.count += n;", "self.count -= n;");
+        assert_eq!(changed(RUST, &new), "12-18");
+
+        let new = RUST.replace("    true\n", "    false\n");
+        assert_eq!(changed(RUST, &new), "21-23");
+
+        // Two functions, unrelated lines outside any function stay as they are
+        let new = RUST
+            .replace("    true\n", "    false\n")
+            .replace("use std::fmt;", "use std::fmt::Debug;")
+            .replace("self.count += 1;", "self.count += 2;");
+        assert_eq!(changed(RUST, &new), "1, 8-10, 21-23");
+
+        assert_eq!(ChangedLines::between(RUST, RUST), None);
+    }
+
+    #[test]
+    fn test_deletion_marks_surrounding_function() {
+        let new = RUST.replace("        self.count += n;\n", "");
+        assert_eq!(changed(RUST, &new), "12-17");
+    }
+
+    #[test]
+    fn test_functions_of_other_languages() {
+        let python = "import os\n\nclass Store:\n    def get(self, key):\n        if key:\n            return 1\n        return 0\n\n    def put(self, key):\n        pass\n";
+        let new = python.replace("return 1", "return 2");
+        assert_eq!(changed(python, &new), "4-7");
+
+        let java = "public class App {\n    public int size() {\n        if (items != null) {\n            return items.size();\n        }\n        return 0;\n    }\n}\n";
+        let new = java.replace("return 0;", "return -1;");
+        assert_eq!(changed(java, &new), "2-7");
+
+        let typescript =
+            "export const total = (items: number[]): number => {\n  return items.length;\n};\n";
+        let new = typescript.replace("length", "length + 1");
+        assert_eq!(changed(typescript, &new), "1-3");
+    }
+
+    #[test]
+    fn test_mutation_changed() {
+        let new = RUST.replace("self.count += 1;", "self.count += 2;");
+        let changed = ChangedLines::between(RUST, &new).unwrap();
+        assert!(changed.contains(9));
+        assert!(!changed.contains(22));
+        assert_eq!(changed.line_count(), 3);
+
+        let mutation = |line_number: usize, find: &str| GeneratedMutation {
+            file_path: "src/lib.rs".to_string(),
+            replacements: vec![Replacement {
+                line_number,
+                find: find.to_string(),
+                replace: String::new(),
+            }],
+            reasoning: String::new(),
+            description: String::new(),
+        };
+        assert!(changed.mutation_changed(&new, &mutation(9, "+= 2")));
+        // Located by its text rather than the reported line
+        assert!(changed.mutation_changed(&new, &mutation(30, "+= 2")));
+        assert!(!changed.mutation_changed(&new, &mutation(22, "true")));
+    }
+
+    #[tokio::test]
+    async fn test_git_helpers() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "--quiet"]);
+        git(dir.path(), &["config", "user.email", "test@example.com"]);
+        git(dir.path(), &["config", "user.name", "Test"]);
+        assert_eq!(head_commit(dir.path()).await, None);
+
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), RUST).unwrap();
+        assert!(!is_committed(dir.path(), "src/lib.rs").await);
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "--quiet", "-m", "init"]);
+
+        let head = head_commit(dir.path()).await.unwrap();
+        assert!(is_committed(dir.path(), "src/lib.rs").await);
+        assert_eq!(
+            file_at_commit(dir.path(), &head, "src/lib.rs")
+                .await
+                .unwrap(),
+            RUST
+        );
+
+        std::fs::write(dir.path().join("src/lib.rs"), "fn f() {}\n").unwrap();
+        assert!(!is_committed(dir.path(), "src/lib.rs").await);
+        assert!(file_at_commit(dir.path(), &head, "src/missing.rs")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Line coverage for mutation testing.
+//!
+//! A mutation on a line no test executes survives by definition, so testing it
+//! only costs a build and test cycle and adds noise to the survivor list. With
+//! `[mutation.coverage]` enabled, line coverage from an lcov file (written by
+//! `cargo llvm-cov` or any other lcov producer) is loaded before mutation
+//! testing; mutations whose lines were never executed are skipped, and the
+//! remaining survivors are marked as covered, so "covered but survived" counts
+//! the gaps the tests actually walk past.
+
+use crate::mutation::executor::target_line;
+use crate::mutation::GeneratedMutation;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Per-line execution counts of the files in an lcov report
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    /// Hit count per instrumented line (1-indexed), by path relative to the repository root
+    files: HashMap<PathBuf, HashMap<usize, u64>>,
+}
+
+impl CoverageReport {
+    /// Read an lcov file. Absolute source paths are made relative to the first
+    /// of `roots` containing them; sources outside all roots are ignored.
+    pub fn load(lcov_path: &Path, roots: &[&Path]) -> Result<Self> {
+        let lcov = std::fs::read_to_string(lcov_path)
+            .with_context(|| format!("Failed to read coverage file {:?}", lcov_path))?;
+        Ok(Self::parse(&lcov, roots))
+    }
+
+    /// Parse lcov tracefile content (`SF:` and `DA:` records; everything else is ignored)
+    pub fn parse(lcov: &str, roots: &[&Path]) -> Self {
+        let mut files: HashMap<PathBuf, HashMap<usize, u64>> = HashMap::new();
+        let mut current: Option<PathBuf> = None;
+
+        for line in lcov.lines().map(str::trim) {
+            if let Some(source) = line.strip_prefix("SF:") {
+                let source = Path::new(source);
+                current = if source.is_absolute() {
+                    roots
+                        .iter()
+                        .find_map(|root| source.strip_prefix(root).ok())
+                        .map(Path::to_path_buf)
+                } else {
+                    Some(source.to_path_buf())
+                };
+            } else if let Some(record) = line.strip_prefix("DA:") {
+                let (Some(file), Some((line_number, hits))) = (&current, parse_da(record)) else {
+                    continue;
+                };
+                *files
+                    .entry(file.clone())
+                    .or_default()
+                    .entry(line_number)
+                    .or_default() += hits;
+            } else if line == "end_of_record" {
+                current = None;
+            }
+        }
+
+        Self { files }
+    }
+
+    /// Number of files with coverage data
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Coverage of a file, by path relative to the repository root
+    pub fn file(&self, relative_path: &Path) -> Option<FileCoverage<'_>> {
+        self.files
+            .get(relative_path)
+            .map(|lines| FileCoverage { lines })
+    }
+}
+
+/// `line,hits[,checksum]` of a `DA:` record
+fn parse_da(record: &str) -> Option<(usize, u64)> {
+    let mut fields = record.split(',');
+    let line_number = fields.next()?.trim().parse().ok()?;
+    // Some producers write hit counts as floats or negative numbers on overflow
+    let hits = fields.next()?.trim().parse::<f64>().ok()?.max(0.0) as u64;
+    Some((line_number, hits))
+}
+
+/// Line coverage of one file
+#[derive(Debug, Clone, Copy)]
+pub struct FileCoverage<'a> {
+    lines: &'a HashMap<usize, u64>,
+}
+
+impl FileCoverage<'_> {
+    /// Whether any test executes the lines a mutation changes in `content`:
+    /// `Some(true)` if one of them was executed, `Some(false)` if they were
+    /// instrumented but never executed, and `None` if coverage doesn't say
+    /// (e.g. declarations without executable code, or unlocatable replacements).
+    pub fn mutation_covered(&self, content: &str, mutation: &GeneratedMutation) -> Option<bool> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut instrumented = false;
+        for replacement in &mutation.replacements {
+            let Ok(line_number) = target_line(&lines, replacement) else {
+                return None;
+            };
+            match self.lines.get(&line_number) {
+                Some(&hits) if hits > 0 => return Some(true),
+                Some(_) => instrumented = true,
+                None => {}
+            }
+        }
+        instrumented.then_some(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::Replacement;
+
+    const LCOV: &str = "\
+TN:
+SF:/tmp/noctum-copy/src/lib.rs
+FN:1,add
+DA:1,3
+DA:2,3
+DA:5,0
+DA:6,0
+end_of_record
+SF:src/main.rs
+DA:1,1
+end_of_record
+SF:/usr/lib/rustlib/src/core.rs
+DA:1,7
+end_of_record
+";
+
+    const CONTENT: &str =
+        "fn add(a: u8, b: u8) -> u8 {\n    a + b\n}\n\nfn unused(a: u8) -> bool {\n    a > 1\n}\n";
+
+    fn mutation(replacements: &[(usize, &str, &str)]) -> GeneratedMutation {
+        GeneratedMutation {
+            file_path: "src/lib.rs".to_string(),
+            replacements: replacements
+                .iter()
+                .map(|&(line_number, find, replace)| Replacement {
+                    line_number,
+                    find: find.to_string(),
+                    replace: replace.to_string(),
+                })
+                .collect(),
+            reasoning: String::new(),
+            description: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_lcov_relativizes_paths() {
+        let report = CoverageReport::parse(
+            LCOV,
+            &[Path::new("/srv/app"), Path::new("/tmp/noctum-copy")],
+        );
+        assert_eq!(report.len(), 2);
+        assert!(report.file(Path::new("src/lib.rs")).is_some());
+        assert!(report.file(Path::new("src/main.rs")).is_some());
+        assert!(report.file(Path::new("src/other.rs")).is_none());
+    }
+
+    #[test]
+    fn test_mutation_covered() {
+        let report = CoverageReport::parse(LCOV, &[Path::new("/tmp/noctum-copy")]);
+        let file = report.file(Path::new("src/lib.rs")).unwrap();
+
+        assert_eq!(
+            file.mutation_covered(CONTENT, &mutation(&[(2, "a + b", "a - b")])),
+            Some(true)
+        );
+        assert_eq!(
+            file.mutation_covered(CONTENT, &mutation(&[(6, "a > 1", "a >= 1")])),
+            Some(false)
+        );
+        // The line number is approximate; the replacement is located like the executor does
+        assert_eq!(
+            file.mutation_covered(CONTENT, &mutation(&[(4, "a > 1", "a < 1")])),
+            Some(false)
+        );
+        // A closing brace isn't instrumented
+        assert_eq!(
+            file.mutation_covered(CONTENT, &mutation(&[(3, "}", "};")])),
+            None
+        );
+        assert_eq!(
+            file.mutation_covered(CONTENT, &mutation(&[(2, "a * b", "a / b")])),
+            None
+        );
+    }
+}
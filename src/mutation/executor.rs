@@ -1,34 +1,132 @@
 //! Mutation test executor.
 //!
 //! Handles applying mutations, running tests, and reverting changes.
-//! Includes retry logic for compile errors - re-prompts the LLM up to 3 times.
+//! Includes retry logic for compile errors - re-prompts the LLM with the error
+//! as configured in [`CompileFixConfig`](crate::mutation::CompileFixConfig), and
+//! optionally falls back to a deterministic operator mutation on the same lines.
+//! Without an LLM client (deterministic mutations), compile errors are not retried
+//! and test outcomes are determined from the exit code.
+//! [`execute_mutation_tests`] runs several mutations at once, one per workspace
+//! of a [`WorkspacePool`].
 
 use crate::analyzer::OllamaClient;
 use crate::mutation::analyzer::{analyze_test_output, fix_mutation_with_error};
+use crate::mutation::operators;
+use crate::mutation::workspace::WorkspacePool;
 use crate::mutation::{
     GeneratedMutation, MutationConfig, MutationTestResult, Replacement, TestOutcome,
 };
+use crate::toolchain::{wait_with_output, CommandRunner, CommandWait};
 use anyhow::{Context, Result};
+use futures::stream::{self, Stream, StreamExt};
 use std::path::Path;
 use std::process::Stdio;
 use std::time::Instant;
 use tokio::time::Duration;
-
-/// Maximum number of times to retry a mutation that fails to compile.
-const MAX_COMPILE_RETRIES: u8 = 3;
+use tokio_util::sync::CancellationToken;
 
 /// Execute mutation testing for a single mutation.
 ///
 /// This function:
 /// 1. Applies the mutation to the source file
 /// 2. Runs the configured build command to check compilation
-/// 3. If compilation fails, re-prompts the LLM to fix the mutation (up to 3 times)
+/// 3. If compilation fails, re-prompts the LLM to fix the mutation (when a client
+///    is given and `config.compile_fix` allows retries)
 /// 4. Runs the configured test command if compilation succeeds
 /// 5. Reverts the file (always, even on error)
-/// 6. Returns the test result
+/// 6. If the mutation still fails to compile and `fallback_to_operator` is set,
+///    repeats steps 1-5 once with an operator mutation on the same lines
+/// 7. Returns the test result
+///
+/// If `cancel` is cancelled, running commands are killed, the file is reverted,
+/// and an error is returned.
 #[allow(clippy::too_many_arguments)]
 pub async fn execute_mutation_test(
-    client: &OllamaClient,
+    client: Option<&OllamaClient>,
+    runner: &CommandRunner,
+    repo_path: &Path,
+    mutation: GeneratedMutation,
+    original_code: &str,
+    config: &MutationConfig,
+    build_command: &str,
+    test_command: &str,
+    timeout_seconds: u64,
+    cancel: &CancellationToken,
+) -> Result<MutationTestResult> {
+    let start_time = Instant::now();
+    let fallback = match client {
+        Some(_) if config.compile_fix.fallback_to_operator => {
+            operator_fallback(&mutation, original_code)
+        }
+        _ => None,
+    };
+
+    let result = execute_with_fixes(
+        client,
+        runner,
+        repo_path,
+        mutation,
+        original_code,
+        config,
+        build_command,
+        test_command,
+        timeout_seconds,
+        cancel,
+    )
+    .await?;
+    let Some(fallback) = fallback.filter(|_| result.outcome == TestOutcome::CompileError) else {
+        return Ok(result);
+    };
+
+    tracing::info!(
+        "Falling back to operator mutation for {}: {}",
+        result.mutation.description,
+        fallback.description
+    );
+    let mut result = execute_with_fixes(
+        None,
+        runner,
+        repo_path,
+        fallback,
+        original_code,
+        config,
+        build_command,
+        test_command,
+        timeout_seconds,
+        cancel,
+    )
+    .await?;
+    result.execution_time_ms = start_time.elapsed().as_millis() as u64;
+    Ok(result)
+}
+
+/// A deterministic operator mutation on the lines of a mutation that failed to
+/// compile, if any of them has a mutation site
+fn operator_fallback(
+    mutation: &GeneratedMutation,
+    original_code: &str,
+) -> Option<GeneratedMutation> {
+    let lines: Vec<usize> = mutation
+        .replacements
+        .iter()
+        .map(|r| r.line_number)
+        .collect();
+    let mut fallback =
+        operators::generate_mutations(&mutation.file_path, original_code, usize::MAX, None)
+            .into_iter()
+            .find(|candidate| lines.contains(&candidate.replacements[0].line_number))?;
+    fallback.reasoning = format!(
+        "Deterministic fallback for \"{}\", which failed to compile",
+        mutation.description
+    );
+    Some(fallback)
+}
+
+/// Apply, build and test a mutation, re-prompting the LLM to fix compile errors
+#[allow(clippy::too_many_arguments)]
+async fn execute_with_fixes(
+    client: Option<&OllamaClient>,
+    runner: &CommandRunner,
     repo_path: &Path,
     mutation: GeneratedMutation,
     original_code: &str,
@@ -36,8 +134,10 @@ pub async fn execute_mutation_test(
     build_command: &str,
     test_command: &str,
     timeout_seconds: u64,
+    cancel: &CancellationToken,
 ) -> Result<MutationTestResult> {
     let start_time = Instant::now();
+    let max_attempts = config.compile_fix.max_attempts();
 
     // Clone the file path before moving mutation
     let file_path_str = mutation.file_path.clone();
@@ -52,7 +152,7 @@ pub async fn execute_mutation_test(
     let mut last_compile_error: Option<String> = None;
 
     // Retry loop for compile errors
-    for attempt in 1..=MAX_COMPILE_RETRIES {
+    for attempt in 1..=max_attempts {
         // Apply mutation
         let mutated_content =
             match apply_replacements(&original_content, &current_mutation.replacements) {
@@ -71,26 +171,39 @@ pub async fn execute_mutation_test(
             };
 
         // Write mutated file
-        tokio::fs::write(file_path, &mutated_content)
+        replace_file(file_path, &mutated_content)
             .await
             .context("Failed to write mutated file")?;
 
         // Fast compile check first using configured build command
-        match run_build_command(repo_path, build_command, timeout_seconds).await {
+        let build_result =
+            run_build_command(runner, repo_path, build_command, timeout_seconds, cancel).await;
+        if cancel.is_cancelled() {
+            revert_file(file_path, &original_content).await;
+            anyhow::bail!("Mutation test cancelled");
+        }
+
+        match build_result {
             Ok(()) => {
                 // Compilation succeeded! Run the test suite using configured test command
                 let test_result = run_tests_with_command(
                     client,
+                    runner,
                     repo_path,
                     test_command,
                     timeout_seconds,
                     config,
+                    cancel,
                 )
                 .await;
 
                 // Revert file before returning
                 revert_file(file_path, &original_content).await;
 
+                if cancel.is_cancelled() {
+                    anyhow::bail!("Mutation test cancelled");
+                }
+
                 let execution_time_ms = start_time.elapsed().as_millis() as u64;
 
                 let (outcome, killing_test, test_output) = match test_result {
@@ -134,11 +247,16 @@ pub async fn execute_mutation_test(
 
                 last_compile_error = Some(compile_error.clone());
 
-                if attempt < MAX_COMPILE_RETRIES {
+                let Some(client) = client else {
+                    // Deterministic mutations have nobody to fix them
+                    break;
+                };
+
+                if attempt < max_attempts {
                     tracing::debug!(
                         "Mutation compile error (attempt {}/{}), re-prompting LLM: {}",
                         attempt,
-                        MAX_COMPILE_RETRIES,
+                        max_attempts,
                         current_mutation.description
                     );
 
@@ -154,6 +272,7 @@ pub async fn execute_mutation_test(
                         &current_mutation,
                         &compile_error,
                         attempt,
+                        max_attempts,
                     )
                     .await
                     {
@@ -174,7 +293,7 @@ pub async fn execute_mutation_test(
                 } else {
                     tracing::warn!(
                         "Mutation failed to compile after {} attempts: {}",
-                        MAX_COMPILE_RETRIES,
+                        max_attempts,
                         current_mutation.description
                     );
                 }
@@ -201,19 +320,84 @@ pub async fn execute_mutation_test(
     })
 }
 
+/// Execute mutation tests for mutations of one file concurrently.
+///
+/// Each mutation runs in a workspace checked out from `workspaces`, with its
+/// file path and `project_root` (both inside the primary copy) rebased onto that
+/// workspace, so at most `workspaces.size()` mutations run at once. Results are
+/// yielded in completion order, each with the key its mutation was passed with.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_mutation_tests<'a>(
+    client: Option<&'a OllamaClient>,
+    runner: &'a CommandRunner,
+    workspaces: &'a WorkspacePool,
+    project_root: &'a Path,
+    mutations: Vec<(usize, GeneratedMutation)>,
+    original_code: &'a str,
+    config: &'a MutationConfig,
+    build_command: &'a str,
+    test_command: &'a str,
+    timeout_seconds: u64,
+    cancel: &'a CancellationToken,
+) -> impl Stream<Item = (usize, Result<MutationTestResult>)> + 'a {
+    stream::iter(mutations)
+        .map(move |(key, mut mutation)| async move {
+            if cancel.is_cancelled() {
+                return (key, Err(anyhow::anyhow!("Mutation test cancelled")));
+            }
+            let workspace = workspaces.acquire().await;
+            mutation.file_path = workspace
+                .rebase(Path::new(&mutation.file_path))
+                .to_string_lossy()
+                .to_string();
+            let result = execute_mutation_test(
+                client,
+                runner,
+                &workspace.rebase(project_root),
+                mutation,
+                original_code,
+                config,
+                build_command,
+                test_command,
+                timeout_seconds,
+                cancel,
+            )
+            .await;
+            (key, result)
+        })
+        .buffer_unordered(workspaces.size())
+}
+
 /// Revert a file to its original content, with retry on failure.
 async fn revert_file(file_path: &Path, original_content: &str) {
-    if let Err(e) = tokio::fs::write(file_path, original_content).await {
+    if let Err(e) = replace_file(file_path, original_content).await {
         tracing::warn!(
             "CRITICAL: Failed to revert file {}: {}",
             file_path.display(),
             e
         );
         // Try once more
-        let _ = tokio::fs::write(file_path, original_content).await;
+        let _ = replace_file(file_path, original_content).await;
     }
 }
 
+/// Replace a file with new content by writing a sibling file and renaming it
+/// over the original. A snapshot's files may be hard links to the repository
+/// (the `hardlink` snapshot strategy), and writing them in place would change
+/// the repository too.
+async fn replace_file(file_path: &Path, content: &str) -> std::io::Result<()> {
+    let name = file_path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    let staged = file_path.with_file_name(format!(".{}.noctum-mutation", name));
+    tokio::fs::write(&staged, content).await?;
+    if let Ok(metadata) = tokio::fs::metadata(file_path).await {
+        let _ = tokio::fs::set_permissions(&staged, metadata.permissions()).await;
+    }
+    tokio::fs::rename(&staged, file_path).await
+}
+
 /// Line tolerance when applying replacements (search nearby lines).
 const LINE_TOLERANCE: usize = 3;
 
@@ -222,14 +406,14 @@ const LINE_TOLERANCE: usize = 3;
 /// Replacements are applied in descending line order to prevent line number
 /// shifts from affecting subsequent replacements (important when a replacement
 /// adds or removes lines, like adding an import).
-fn apply_replacements(content: &str, replacements: &[Replacement]) -> Result<String> {
+pub(crate) fn apply_replacements(content: &str, replacements: &[Replacement]) -> Result<String> {
     if replacements.is_empty() {
         anyhow::bail!("No replacements to apply");
     }
 
     // Sort replacements by line number descending
     let mut sorted_replacements: Vec<&Replacement> = replacements.iter().collect();
-    sorted_replacements.sort_by(|a, b| b.line_number.cmp(&a.line_number));
+    sorted_replacements.sort_by_key(|r| std::cmp::Reverse(r.line_number));
 
     let mut current_content = content.to_string();
 
@@ -256,36 +440,7 @@ fn apply_single_replacement(content: &str, replacement: &Replacement) -> Result<
         );
     }
 
-    // Search for the "find" text within the line window
-    let start_line = replacement
-        .line_number
-        .saturating_sub(LINE_TOLERANCE)
-        .max(1);
-    let end_line = (replacement.line_number + LINE_TOLERANCE).min(line_count);
-
-    let mut target_line = None;
-    for line_num in start_line..=end_line {
-        if lines[line_num - 1].contains(&replacement.find) {
-            target_line = Some(line_num);
-            break;
-        }
-    }
-
-    // Fallback: search entire file
-    let target_line = match target_line {
-        Some(ln) => ln,
-        None => lines
-            .iter()
-            .position(|l| l.contains(&replacement.find))
-            .map(|idx| idx + 1)
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "Could not find '{}' in file (searched around line {})",
-                    replacement.find,
-                    replacement.line_number
-                )
-            })?,
-    };
+    let target_line = target_line(&lines, replacement)?;
 
     // Build the new content with the replacement on the target line
     let mut new_lines: Vec<String> = Vec::with_capacity(lines.len());
@@ -311,6 +466,37 @@ fn apply_single_replacement(content: &str, replacement: &Replacement) -> Result<
     Ok(new_lines.join(line_ending))
 }
 
+/// The line (1-indexed) a replacement applies to: the first line within
+/// `LINE_TOLERANCE` of `replacement.line_number` containing the `find` text,
+/// or else the first such line in the whole file
+pub(crate) fn target_line(lines: &[&str], replacement: &Replacement) -> Result<usize> {
+    let start_line = replacement
+        .line_number
+        .saturating_sub(LINE_TOLERANCE)
+        .max(1);
+    let end_line = (replacement.line_number + LINE_TOLERANCE).min(lines.len());
+
+    // Search for the "find" text within the line window
+    if let Some(line_num) =
+        (start_line..=end_line).find(|&line_num| lines[line_num - 1].contains(&replacement.find))
+    {
+        return Ok(line_num);
+    }
+
+    // Fallback: search entire file
+    lines
+        .iter()
+        .position(|l| l.contains(&replacement.find))
+        .map(|idx| idx + 1)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not find '{}' in file (searched around line {})",
+                replacement.find,
+                replacement.line_number
+            )
+        })
+}
+
 enum TestResult {
     Passed,
     Failed { test_name: String, output: String },
@@ -323,13 +509,14 @@ enum TestResult {
 /// Returns `Ok(())` if the command succeeds (exit code 0),
 /// or `Err(output)` with the command output if it fails.
 async fn run_build_command(
+    runner: &CommandRunner,
     repo_path: &Path,
     build_command: &str,
     timeout_seconds: u64,
+    cancel: &CancellationToken,
 ) -> std::result::Result<(), String> {
-    let child = tokio::process::Command::new("sh")
-        .arg("-c")
-        .arg(build_command)
+    let child = runner
+        .command(build_command)
         .current_dir(repo_path)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -343,10 +530,9 @@ async fn run_build_command(
     };
 
     let timeout = Duration::from_secs(timeout_seconds);
-    let result = tokio::time::timeout(timeout, child.wait_with_output()).await;
 
-    match result {
-        Ok(Ok(output)) => {
+    match wait_with_output(child, timeout, cancel).await {
+        CommandWait::Finished(Ok(output)) => {
             if output.status.success() {
                 Ok(())
             } else {
@@ -355,25 +541,27 @@ async fn run_build_command(
                 Err(format!("{}{}", stdout, stderr))
             }
         }
-        Ok(Err(e)) => Err(format!("Build command execution error: {}", e)),
-        Err(_) => Err(format!(
+        CommandWait::Finished(Err(e)) => Err(format!("Build command execution error: {}", e)),
+        CommandWait::TimedOut => Err(format!(
             "Build command timed out after {} seconds",
             timeout_seconds
         )),
+        CommandWait::Cancelled => Err("Build command cancelled".to_string()),
     }
 }
 
 /// Run test command and analyze output with LLM.
 async fn run_tests_with_command(
-    client: &OllamaClient,
+    client: Option<&OllamaClient>,
+    runner: &CommandRunner,
     repo_path: &Path,
     test_command: &str,
     timeout_seconds: u64,
     config: &MutationConfig,
+    cancel: &CancellationToken,
 ) -> TestResult {
-    let child = tokio::process::Command::new("sh")
-        .arg("-c")
-        .arg(test_command)
+    let child = runner
+        .command(test_command)
         .current_dir(repo_path)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -389,22 +577,22 @@ async fn run_tests_with_command(
     };
 
     let timeout = Duration::from_secs(timeout_seconds);
-    let result = tokio::time::timeout(timeout, child.wait_with_output()).await;
 
-    let (exit_code, output) = match result {
-        Ok(Ok(cmd_output)) => {
+    let (exit_code, output) = match wait_with_output(child, timeout, cancel).await {
+        CommandWait::Finished(Ok(cmd_output)) => {
             let stdout = String::from_utf8_lossy(&cmd_output.stdout);
             let stderr = String::from_utf8_lossy(&cmd_output.stderr);
             let combined = format!("{}{}", stdout, stderr);
             let code = cmd_output.status.code();
             (code, combined)
         }
-        Ok(Err(e)) => {
+        CommandWait::Finished(Err(e)) => {
             return TestResult::CompileError {
                 output: format!("Test command execution error: {}", e),
             };
         }
-        Err(_) => {
+        // The caller checks for cancellation; the outcome is discarded
+        CommandWait::TimedOut | CommandWait::Cancelled => {
             return TestResult::Timeout;
         }
     };
@@ -420,6 +608,10 @@ async fn run_tests_with_command(
     // between test failures vs. compile errors
     let truncated_output = truncate_output(&output, config.max_test_output_bytes);
 
+    let Some(client) = client else {
+        return result_from_exit_code(exit_code, truncated_output);
+    };
+
     match analyze_test_output(client, &truncated_output, exit_code).await {
         Ok(analysis) => match analysis.outcome.as_str() {
             "passed" => TestResult::Passed,
@@ -439,14 +631,7 @@ async fn run_tests_with_command(
                     "Unexpected LLM outcome: {}, falling back to exit code",
                     analysis.outcome
                 );
-                match exit_code {
-                    Some(0) => TestResult::Passed,
-                    Some(_) => TestResult::Failed {
-                        test_name: "unknown".to_string(),
-                        output: truncated_output,
-                    },
-                    None => TestResult::Timeout,
-                }
+                result_from_exit_code(exit_code, truncated_output)
             }
         },
         Err(e) => {
@@ -455,18 +640,23 @@ async fn run_tests_with_command(
                 "Failed to analyze test output with LLM: {}, falling back to exit code",
                 e
             );
-            match exit_code {
-                Some(0) => TestResult::Passed,
-                Some(_) => TestResult::Failed {
-                    test_name: "unknown".to_string(),
-                    output: truncated_output,
-                },
-                None => TestResult::Timeout,
-            }
+            result_from_exit_code(exit_code, truncated_output)
         }
     }
 }
 
+/// Classify a test run from its exit code alone.
+fn result_from_exit_code(exit_code: Option<i32>, output: String) -> TestResult {
+    match exit_code {
+        Some(0) => TestResult::Passed,
+        Some(_) => TestResult::Failed {
+            test_name: "unknown".to_string(),
+            output,
+        },
+        None => TestResult::Timeout,
+    }
+}
+
 fn truncate_output(output: &str, max_bytes: usize) -> String {
     if output.len() <= max_bytes {
         output.to_string()
@@ -495,6 +685,38 @@ pub fn truncate_output_tail(output: &str, max_bytes: usize) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_operator_fallback_uses_the_same_lines() {
+        let code =
+            "fn f(a: i32, b: i32) -> bool {\n    a > b\n}\n\nfn g() -> i32 {\n    1 + 2\n}\n";
+        let failed = GeneratedMutation {
+            file_path: "src/lib.rs".to_string(),
+            replacements: vec![Replacement {
+                line_number: 6,
+                find: "1 + 2".to_string(),
+                replace: "1 + \"2\"".to_string(),
+            }],
+            description: "Add a string".to_string(),
+            reasoning: String::new(),
+        };
+
+        let fallback = operator_fallback(&failed, code).unwrap();
+        assert_eq!(fallback.replacements[0].line_number, 6);
+        assert_eq!(fallback.replacements[0].replace, "    1 - 2");
+        assert!(fallback.reasoning.contains("Add a string"));
+
+        // No mutation site on the line
+        let failed = GeneratedMutation {
+            replacements: vec![Replacement {
+                line_number: 5,
+                find: "fn g()".to_string(),
+                replace: "fn g(x)".to_string(),
+            }],
+            ..failed
+        };
+        assert!(operator_fallback(&failed, code).is_none());
+    }
+
     // =========================================================================
     // truncate_output tests
     // =========================================================================
@@ -758,4 +980,19 @@ mod tests {
         let result = apply_replacements(content, &replacements);
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_replace_file_breaks_hard_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original.rs");
+        let snapshot = dir.path().join("snapshot.rs");
+        std::fs::write(&original, "fn f() {}").unwrap();
+        std::fs::hard_link(&original, &snapshot).unwrap();
+
+        replace_file(&snapshot, "fn g() {}").await.unwrap();
+
+        assert_eq!(std::fs::read_to_string(&snapshot).unwrap(), "fn g() {}");
+        assert_eq!(std::fs::read_to_string(&original).unwrap(), "fn f() {}");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
 }
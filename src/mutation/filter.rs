@@ -0,0 +1,160 @@
+//! Quality filter for generated mutations.
+//!
+//! LLMs occasionally suggest mutations that cannot change behavior: whitespace
+//! tweaks, edits inside comments or string literals, or rewrites that only
+//! differ in formatting. Building and testing them costs a full compile and
+//! test cycle, so they are rejected before execution and recorded with the
+//! reason instead.
+
+use crate::mutation::executor::apply_replacements;
+use crate::mutation::operators::code_mask;
+use crate::mutation::GeneratedMutation;
+
+/// Why a mutation should not be executed, or None if it is worth testing.
+///
+/// Mutations whose replacements can't be applied are left to the executor,
+/// which reports them as compile errors.
+pub fn rejection_reason(original: &str, mutation: &GeneratedMutation) -> Option<String> {
+    let mutated = apply_replacements(original, &mutation.replacements).ok()?;
+    // Applying replacements drops the final newline, which is not a change
+    let (original, mutated) = (original.trim_end(), mutated.trim_end());
+
+    if mutated == original {
+        return Some("Mutation does not change the file".to_string());
+    }
+    if mutation
+        .replacements
+        .iter()
+        .all(|r| strip_whitespace(&r.find) == strip_whitespace(&r.replace))
+    {
+        return Some("Whitespace-only change".to_string());
+    }
+    if strip_whitespace(original) == strip_whitespace(mutated) {
+        return Some("Identical to the original after formatting".to_string());
+    }
+    if !changes_code(original, mutated) {
+        return Some("Change is inside a comment or string literal".to_string());
+    }
+
+    None
+}
+
+/// Whether the edit from `original` to `mutated` touches anything but
+/// whitespace, comments and string literals
+fn changes_code(original: &str, mutated: &str) -> bool {
+    let prefix = original
+        .char_indices()
+        .zip(mutated.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(original.len().min(mutated.len()), |((i, _), _)| i);
+    let suffix = original[prefix..]
+        .chars()
+        .rev()
+        .zip(mutated[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum::<usize>();
+
+    let has_code = |content: &str| {
+        let mask = code_mask(content);
+        let end = content.len() - suffix;
+        content[prefix..end]
+            .char_indices()
+            .any(|(i, c)| mask[prefix + i] && !c.is_whitespace())
+    };
+    has_code(original) || has_code(mutated)
+}
+
+fn strip_whitespace(s: &str) -> String {
+    s.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::Replacement;
+
+    fn mutation(line_number: usize, find: &str, replace: &str) -> GeneratedMutation {
+        GeneratedMutation {
+            file_path: "src/lib.rs".to_string(),
+            replacements: vec![Replacement {
+                line_number,
+                find: find.to_string(),
+                replace: replace.to_string(),
+            }],
+            reasoning: String::new(),
+            description: String::new(),
+        }
+    }
+
+    const CODE: &str =
+        "fn f(a: i32) -> bool {\n    // a>1 means big\n    let s = \"a>1\";\n    a > 1\n}\n";
+
+    #[test]
+    fn test_accepts_behavioral_change() {
+        assert_eq!(
+            rejection_reason(CODE, &mutation(4, "a > 1", "a >= 1")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rejects_noop_and_whitespace_changes() {
+        assert_eq!(
+            rejection_reason(CODE, &mutation(4, "a > 1", "a > 1")).unwrap(),
+            "Mutation does not change the file"
+        );
+        assert_eq!(
+            rejection_reason(CODE, &mutation(4, "a > 1", "a  >  1")).unwrap(),
+            "Whitespace-only change"
+        );
+    }
+
+    #[test]
+    fn test_rejects_formatting_only_rewrite() {
+        // Moving the operator to the next line changes each line, but not the code
+        let code = "fn g() -> u8 {\n    1 +\n    7\n}\n";
+        let rewrite = GeneratedMutation {
+            replacements: vec![
+                Replacement {
+                    line_number: 2,
+                    find: "1 +".to_string(),
+                    replace: "1".to_string(),
+                },
+                Replacement {
+                    line_number: 3,
+                    find: "7".to_string(),
+                    replace: "+ 7".to_string(),
+                },
+            ],
+            ..mutation(2, "", "")
+        };
+        assert_eq!(
+            rejection_reason(code, &rewrite).unwrap(),
+            "Identical to the original after formatting"
+        );
+    }
+
+    #[test]
+    fn test_rejects_changes_in_comments_and_strings() {
+        let reason = "Change is inside a comment or string literal";
+        assert_eq!(
+            rejection_reason(CODE, &mutation(2, "a>1", "a>=1")).unwrap(),
+            reason
+        );
+        assert_eq!(
+            rejection_reason(CODE, &mutation(3, "a>1", "a<1")).unwrap(),
+            reason
+        );
+        // Appending a comment to a line of code
+        assert_eq!(
+            rejection_reason(CODE, &mutation(4, "a > 1", "a > 1 // check")).unwrap(),
+            reason
+        );
+    }
+
+    #[test]
+    fn test_unapplicable_mutation_is_left_to_executor() {
+        assert_eq!(rejection_reason(CODE, &mutation(4, "b < 2", "b > 2")), None);
+    }
+}
@@ -2,10 +2,20 @@
 //!
 //! This module provides functionality for:
 //! - Analyzing Rust code to find and generate mutations in a single LLM call
-//! - Executing tests against mutations and recording results
+//! - Generating deterministic operator mutations without an LLM
+//! - Rejecting mutations that cannot change behavior before they are executed
+//! - Skipping mutations on lines no test executes, using lcov line coverage
+//! - Restricting mutations to the functions changed since the last run
+//! - Executing tests against mutations and recording results, optionally in
+//!   parallel across isolated copies of the repository
 
 pub mod analyzer;
+pub mod changes;
+pub mod coverage;
 pub mod executor;
+pub mod filter;
+pub mod operators;
+pub mod workspace;
 
 // Re-export main function for convenience
 pub use analyzer::analyze_and_generate_mutations;
@@ -52,6 +62,8 @@ pub enum TestOutcome {
     Timeout,
     /// Mutation caused compilation failure
     CompileError,
+    /// Mutation was not executed because it cannot change behavior
+    Rejected,
 }
 
 impl std::fmt::Display for TestOutcome {
@@ -61,6 +73,7 @@ impl std::fmt::Display for TestOutcome {
             Self::Survived => write!(f, "survived"),
             Self::Timeout => write!(f, "timeout"),
             Self::CompileError => write!(f, "compile_error"),
+            Self::Rejected => write!(f, "rejected"),
         }
     }
 }
@@ -75,6 +88,63 @@ pub struct MutationTestResult {
     pub execution_time_ms: u64,
 }
 
+/// How mutations are generated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationStrategy {
+    /// Ask the LLM for high-value mutations (requires an endpoint)
+    #[default]
+    Llm,
+    /// Apply the built-in operator table only; no endpoint needed
+    Deterministic,
+    /// Use the LLM when an endpoint is available, falling back to operators
+    Hybrid,
+}
+
+/// How mutations that fail to compile are handled
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CompileFixConfig {
+    /// Re-prompt the LLM with the compiler error to fix the mutation. Default: true.
+    #[serde(default = "default_compile_retry")]
+    pub retry: bool,
+    /// Maximum LLM fixes per mutation. Default: 2.
+    #[serde(default = "default_compile_max_retries")]
+    pub max_retries: u8,
+    /// When the fixes fail, test a deterministic operator mutation on the same
+    /// lines instead of recording a compile error. Default: false.
+    #[serde(default)]
+    pub fallback_to_operator: bool,
+}
+
+fn default_compile_retry() -> bool {
+    true
+}
+
+fn default_compile_max_retries() -> u8 {
+    2
+}
+
+impl Default for CompileFixConfig {
+    fn default() -> Self {
+        Self {
+            retry: default_compile_retry(),
+            max_retries: default_compile_max_retries(),
+            fallback_to_operator: false,
+        }
+    }
+}
+
+impl CompileFixConfig {
+    /// Build attempts per mutation, including the first one
+    pub fn max_attempts(&self) -> u8 {
+        if self.retry {
+            self.max_retries.saturating_add(1)
+        } else {
+            1
+        }
+    }
+}
+
 /// Configuration for mutation testing
 #[derive(Debug, Clone)]
 pub struct MutationConfig {
@@ -85,6 +155,10 @@ pub struct MutationConfig {
     pub test_timeout_seconds: u64,
     /// Maximum test output to store (bytes)
     pub max_test_output_bytes: usize,
+    /// How mutations are generated
+    pub strategy: MutationStrategy,
+    /// How mutations that fail to compile are handled
+    pub compile_fix: CompileFixConfig,
 }
 
 impl Default for MutationConfig {
@@ -93,6 +167,8 @@ impl Default for MutationConfig {
             max_mutations_per_file: 10,
             test_timeout_seconds: 300, // 5 minutes
             max_test_output_bytes: 10000,
+            strategy: MutationStrategy::Llm,
+            compile_fix: CompileFixConfig::default(),
         }
     }
 }
@@ -107,6 +183,7 @@ mod tests {
         assert_eq!(TestOutcome::Survived.to_string(), "survived");
         assert_eq!(TestOutcome::Timeout.to_string(), "timeout");
         assert_eq!(TestOutcome::CompileError.to_string(), "compile_error");
+        assert_eq!(TestOutcome::Rejected.to_string(), "rejected");
     }
 
     #[test]
@@ -115,5 +192,22 @@ mod tests {
         assert_eq!(config.max_mutations_per_file, 10);
         assert_eq!(config.test_timeout_seconds, 300);
         assert_eq!(config.max_test_output_bytes, 10000);
+        assert_eq!(config.strategy, MutationStrategy::Llm);
+        assert_eq!(config.compile_fix.max_attempts(), 3);
+    }
+
+    #[test]
+    fn test_compile_fix_attempts() {
+        let no_retry = CompileFixConfig {
+            retry: false,
+            ..CompileFixConfig::default()
+        };
+        assert_eq!(no_retry.max_attempts(), 1);
+
+        let many = CompileFixConfig {
+            max_retries: u8::MAX,
+            ..CompileFixConfig::default()
+        };
+        assert_eq!(many.max_attempts(), u8::MAX);
     }
 }
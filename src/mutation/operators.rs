@@ -0,0 +1,447 @@
+//! Deterministic mutation operators.
+//!
+//! Generates classic mutation-testing mutants (boundary, equality, logical,
+//! boolean and arithmetic operator swaps) with a simple lexical scan, so
+//! mutation testing still works when no LLM endpoint is available.
+//!
+//! The scan is deliberately conservative: comments and string literals are
+//! skipped, and symbolic operators are only mutated when they are surrounded by
+//! spaces and follow an operand, which filters out generics (`Vec<u8>`),
+//! arrows (`->`, `=>`), references (`&&x`) and closures (`|| x`).
+
+use crate::mutation::changes::ChangedLines;
+use crate::mutation::{GeneratedMutation, Replacement};
+
+/// A mutation operator: replace `find` with `replace`
+struct Operator {
+    find: &'static str,
+    replace: &'static str,
+    kind: &'static str,
+}
+
+/// Operator table. Longer operators come first so `>=` is matched before `>`.
+const OPERATORS: &[Operator] = &[
+    Operator {
+        find: ">=",
+        replace: ">",
+        kind: "relational boundary",
+    },
+    Operator {
+        find: "<=",
+        replace: "<",
+        kind: "relational boundary",
+    },
+    Operator {
+        find: "==",
+        replace: "!=",
+        kind: "equality",
+    },
+    Operator {
+        find: "!=",
+        replace: "==",
+        kind: "equality",
+    },
+    Operator {
+        find: "&&",
+        replace: "||",
+        kind: "logical",
+    },
+    Operator {
+        find: "||",
+        replace: "&&",
+        kind: "logical",
+    },
+    Operator {
+        find: ">",
+        replace: ">=",
+        kind: "relational boundary",
+    },
+    Operator {
+        find: "<",
+        replace: "<=",
+        kind: "relational boundary",
+    },
+    Operator {
+        find: "+",
+        replace: "-",
+        kind: "arithmetic",
+    },
+    Operator {
+        find: "-",
+        replace: "+",
+        kind: "arithmetic",
+    },
+    Operator {
+        find: "*",
+        replace: "/",
+        kind: "arithmetic",
+    },
+    Operator {
+        find: "/",
+        replace: "*",
+        kind: "arithmetic",
+    },
+];
+
+/// Boolean literal flips
+const BOOLEANS: &[Operator] = &[
+    Operator {
+        find: "true",
+        replace: "false",
+        kind: "boolean",
+    },
+    Operator {
+        find: "false",
+        replace: "true",
+        kind: "boolean",
+    },
+];
+
+/// Keywords that can precede a closure's `||` with spaces around it
+const CLOSURE_KEYWORDS: &[&str] = &["move", "return", "in", "else"];
+
+/// A single mutation site found in the source
+#[derive(Debug, Clone, PartialEq)]
+struct Site {
+    /// 1-indexed line number
+    line_number: usize,
+    /// Byte offset of the operator within the line
+    column: usize,
+    find: &'static str,
+    replace: &'static str,
+    kind: &'static str,
+}
+
+/// Lexer state carried across lines
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Code,
+    BlockComment,
+    String(char),
+}
+
+impl State {
+    /// If byte offset `i` of `line` is inside (or starts) a comment or string
+    /// literal, consume it and return the offset to continue from. Returns None
+    /// when `i` is code.
+    fn skip_non_code(&mut self, line: &str, i: usize) -> Option<usize> {
+        let bytes = line.as_bytes();
+        let c = bytes[i] as char;
+        match *self {
+            State::BlockComment => {
+                if bytes[i..].starts_with(b"*/") {
+                    *self = State::Code;
+                    Some(i + 2)
+                } else {
+                    Some(i + 1)
+                }
+            }
+            State::String(quote) => {
+                if c == '\\' {
+                    Some(i + 2)
+                } else {
+                    if c == quote {
+                        *self = State::Code;
+                    }
+                    Some(i + 1)
+                }
+            }
+            State::Code => {
+                if bytes[i..].starts_with(b"//") {
+                    Some(line.len())
+                } else if bytes[i..].starts_with(b"/*") {
+                    *self = State::BlockComment;
+                    Some(i + 2)
+                } else if c == '"' || c == '`' {
+                    *self = State::String(c);
+                    Some(i + 1)
+                } else if c == '\'' {
+                    // Character/string literal if it closes on this line, otherwise a lifetime
+                    Some(closing_quote(&line[i + 1..]).map_or(i + 1, |end| i + end + 2))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Strings other than template literals do not span lines in practice;
+    /// resetting avoids an unbalanced quote hiding the rest of the file
+    fn end_line(&mut self) {
+        if matches!(self, State::String(q) if *q != '`') {
+            *self = State::Code;
+        }
+    }
+}
+
+/// Generate up to `max_mutations` deterministic mutations for a file.
+///
+/// When a file has more mutation sites than `max_mutations`, sites are picked
+/// evenly across the file so results are spread out and stable between runs.
+/// With `changed`, only sites in the changed functions are mutated.
+pub fn generate_mutations(
+    file_path: &str,
+    content: &str,
+    max_mutations: usize,
+    changed: Option<&ChangedLines>,
+) -> Vec<GeneratedMutation> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut sites = find_sites(&lines);
+    if let Some(changed) = changed {
+        sites.retain(|site| changed.contains(site.line_number));
+    }
+
+    select_evenly(&sites, max_mutations)
+        .into_iter()
+        .map(|site| {
+            let line = lines[site.line_number - 1];
+            let mutated = format!(
+                "{}{}{}",
+                &line[..site.column],
+                site.replace,
+                &line[site.column + site.find.len()..]
+            );
+            GeneratedMutation {
+                file_path: file_path.to_string(),
+                replacements: vec![Replacement {
+                    line_number: site.line_number,
+                    find: line.to_string(),
+                    replace: mutated,
+                }],
+                reasoning: format!("Deterministic {} operator mutation", site.kind),
+                description: format!(
+                    "Changed `{}` to `{}` on line {}",
+                    site.find, site.replace, site.line_number
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Find every mutation site outside comments, strings and test modules
+fn find_sites(lines: &[&str]) -> Vec<Site> {
+    let mut sites = Vec::new();
+    let mut state = State::Code;
+
+    for (idx, line) in lines.iter().enumerate() {
+        // Mutating test code is pointless; Rust test modules conventionally sit at the end
+        if state == State::Code && line.trim_start().starts_with("#[cfg(test)]") {
+            break;
+        }
+        // Attributes and preprocessor-like lines have no runtime behavior to mutate
+        if state == State::Code && line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let bytes = line.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if let Some(next) = state.skip_non_code(line, i) {
+                i = next;
+                continue;
+            }
+
+            if let Some(op) = match_operator(line, i) {
+                sites.push(Site {
+                    line_number: idx + 1,
+                    column: i,
+                    find: op.find,
+                    replace: op.replace,
+                    kind: op.kind,
+                });
+                i += op.find.len();
+                continue;
+            }
+
+            i += 1;
+            // Keep `i` on a char boundary for non-ASCII source
+            while i < bytes.len() && !line.is_char_boundary(i) {
+                i += 1;
+            }
+        }
+        state.end_line();
+    }
+
+    sites
+}
+
+/// Whether each byte of `content` is code, as opposed to part of a comment or
+/// string literal (same lexical rules as the operator scan)
+pub(crate) fn code_mask(content: &str) -> Vec<bool> {
+    let mut mask = vec![false; content.len()];
+    let mut state = State::Code;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let mut i = 0;
+        while i < line.len() {
+            match state.skip_non_code(line, i) {
+                Some(next) => i = next,
+                None => {
+                    mask[offset + i] = true;
+                    i += 1;
+                }
+            }
+        }
+        state.end_line();
+        offset += line.len();
+    }
+
+    mask
+}
+
+/// Byte offset of the closing `'` of a quoted literal, honoring escapes
+fn closing_quote(rest: &str) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\'' => return Some(i),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Match a mutable operator starting at byte offset `i` of `line`
+fn match_operator(line: &str, i: usize) -> Option<&'static Operator> {
+    let rest = &line[i..];
+
+    if let Some(op) = BOOLEANS.iter().find(|op| rest.starts_with(op.find)) {
+        let end = i + op.find.len();
+        let before_ok = line[..i].chars().next_back().is_none_or(|c| !is_ident(c));
+        let after_ok = line[end..].chars().next().is_none_or(|c| !is_ident(c));
+        return (before_ok && after_ok).then_some(op);
+    }
+
+    let op = OPERATORS.iter().find(|op| rest.starts_with(op.find))?;
+    let end = i + op.find.len();
+
+    // Binary operators only: surrounded by spaces and following an operand
+    if !line[..i].ends_with(' ') || !line[end..].starts_with(' ') {
+        return None;
+    }
+    let before = line[..i].trim_end();
+    let prev_word: String = before
+        .chars()
+        .rev()
+        .take_while(|c| is_ident(*c))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    let operand_end = before
+        .chars()
+        .next_back()
+        .is_some_and(|c| is_ident(c) || matches!(c, ')' | ']' | '"' | '\'' | '?' | '`'));
+    if !operand_end || CLOSURE_KEYWORDS.contains(&prev_word.as_str()) {
+        return None;
+    }
+
+    // `T: Clone + Send` is a trait bound, not an addition
+    if op.find == "+" {
+        let next = line[end..].trim_start().chars().next();
+        if next.is_some_and(|c| c.is_ascii_uppercase() || c == '\'') {
+            return None;
+        }
+    }
+
+    Some(op)
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Pick at most `max` sites spread evenly across the list
+fn select_evenly(sites: &[Site], max: usize) -> Vec<Site> {
+    if sites.len() <= max {
+        return sites.to_vec();
+    }
+    (0..max)
+        .map(|k| sites[k * sites.len() / max].clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mutated_lines(content: &str) -> Vec<String> {
+        generate_mutations("src/lib.rs", content, 100, None)
+            .into_iter()
+            .map(|m| m.replacements[0].replace.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_operator_swaps() {
+        let content = "fn f(a: i32, b: i32) -> bool {\n    a >= b && a * 2 == b\n}\n";
+        assert_eq!(
+            mutated_lines(content),
+            vec![
+                "    a > b && a * 2 == b",
+                "    a >= b || a * 2 == b",
+                "    a >= b && a / 2 == b",
+                "    a >= b && a * 2 != b",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_boolean_flips_respect_word_boundaries() {
+        let content = "let enabled = true;\nlet is_true_value = untrue;\n";
+        assert_eq!(mutated_lines(content), vec!["let enabled = false;"]);
+    }
+
+    #[test]
+    fn test_skips_comments_and_strings() {
+        let content = r#"// a > b
+let s = "a > b && true";
+/* x == y
+   still a comment */
+let c = '>';
+"#;
+        assert!(mutated_lines(content).is_empty());
+    }
+
+    #[test]
+    fn test_skips_non_binary_operators() {
+        let content = "fn f<'a>(v: Vec<u8>, x: &'a i32) -> impl Fn() + Send {\n    let g = move || *x > 0;\n    let h = |x| x => y;\n    let n = -1;\n    &&v\n}\n";
+        assert_eq!(mutated_lines(content), vec!["    let g = move || *x >= 0;"]);
+    }
+
+    #[test]
+    fn test_stops_at_test_module() {
+        let content = "fn f() -> bool { 1 < 2 }\n\n#[cfg(test)]\nmod tests {\n    fn g() -> bool { 1 < 2 }\n}\n";
+        let mutations = generate_mutations("src/lib.rs", content, 100, None);
+        assert_eq!(mutations.len(), 1);
+        assert_eq!(mutations[0].replacements[0].line_number, 1);
+        assert_eq!(mutations[0].description, "Changed `<` to `<=` on line 1");
+    }
+
+    #[test]
+    fn test_limit_spreads_mutations() {
+        let content = (0..20)
+            .map(|i| format!("let x{} = a + {};", i, i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mutations = generate_mutations("src/lib.rs", &content, 4, None);
+        let lines: Vec<usize> = mutations
+            .iter()
+            .map(|m| m.replacements[0].line_number)
+            .collect();
+        assert_eq!(lines, vec![1, 6, 11, 16]);
+    }
+
+    #[test]
+    fn test_limit_to_changed_functions() {
+        let old = "fn f(a: i32) -> bool {\n    a > 1\n}\n\nfn g(a: i32) -> bool {\n    a < 1\n}\n";
+        let new = old.replace("a < 1", "a < 2");
+        let changed = ChangedLines::between(old, &new).unwrap();
+        let mutations = generate_mutations("src/lib.rs", &new, 100, Some(&changed));
+        assert_eq!(mutations.len(), 1);
+        assert_eq!(mutations[0].replacements[0].line_number, 6);
+    }
+}
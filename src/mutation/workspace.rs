@@ -0,0 +1,173 @@
+//! Isolated workspaces for parallel mutation testing.
+//!
+//! Mutations are applied in place, so two mutations can only be tested at the
+//! same time in separate copies of the repository. The pool hands out the
+//! primary temp copy plus `size - 1` extra copies made from it after the
+//! baseline run, so each copy starts with installed dependencies and warm
+//! build artifacts. Every copy has its own build directory (e.g. `target/`),
+//! which also keeps concurrent builds from blocking on each other's locks.
+
+use crate::project::TEMP_DIR_PREFIX;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// A fixed set of repository copies, each used by one mutation at a time
+pub struct WorkspacePool {
+    primary: PathBuf,
+    /// Roots of workspaces not currently checked out
+    free: Mutex<Vec<PathBuf>>,
+    available: Semaphore,
+    size: usize,
+    /// Extra copies, deleted when the pool is dropped
+    _copies: Vec<tempfile::TempDir>,
+}
+
+impl WorkspacePool {
+    /// Create a pool of `size` workspaces (at least one), copying `primary` as needed
+    pub async fn create(primary: &Path, size: usize) -> Result<Self> {
+        let size = size.max(1);
+        let mut copies = Vec::with_capacity(size - 1);
+        for _ in 1..size {
+            let source = primary.to_path_buf();
+            let copy = tokio::task::spawn_blocking(move || -> Result<tempfile::TempDir> {
+                let dir = tempfile::TempDir::with_prefix(TEMP_DIR_PREFIX)?;
+                copy_workspace(&source, dir.path())?;
+                Ok(dir)
+            })
+            .await??;
+            copies.push(copy);
+        }
+
+        let mut free: Vec<PathBuf> = copies.iter().map(|c| c.path().to_path_buf()).collect();
+        free.push(primary.to_path_buf());
+
+        Ok(Self {
+            primary: primary.to_path_buf(),
+            free: Mutex::new(free),
+            available: Semaphore::new(size),
+            size,
+            _copies: copies,
+        })
+    }
+
+    /// Number of workspaces (and so the maximum number of concurrent mutations)
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Check out a workspace, waiting until one is free
+    pub async fn acquire(&self) -> Workspace<'_> {
+        let permit = self
+            .available
+            .acquire()
+            .await
+            .expect("workspace semaphore is never closed");
+        let root = self
+            .free
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop()
+            .expect("a permit guarantees a free workspace");
+        Workspace {
+            pool: self,
+            root,
+            _permit: permit,
+        }
+    }
+}
+
+/// A checked-out workspace, returned to the pool on drop
+pub struct Workspace<'a> {
+    pool: &'a WorkspacePool,
+    root: PathBuf,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl Workspace<'_> {
+    /// Map a path inside the primary copy to the same path in this workspace
+    pub fn rebase(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.pool.primary) {
+            Ok(relative) => self.root.join(relative),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+}
+
+impl Drop for Workspace<'_> {
+    fn drop(&mut self) {
+        self.pool
+            .free
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(std::mem::take(&mut self.root));
+    }
+}
+
+/// Copy a workspace, recreating symlinks (e.g. `node_modules/.bin`) rather than
+/// following them. Absolute links into the source are pointed at the copy.
+fn copy_workspace(src: &Path, dest: &Path) -> Result<()> {
+    for entry in walkdir::WalkDir::new(src).min_depth(1) {
+        let entry = entry.context("Failed to read directory entry")?;
+        let relative = entry.path().strip_prefix(src)?;
+        let dest_path = dest.join(relative);
+
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dest_path)
+                .with_context(|| format!("Failed to create directory {:?}", dest_path))?;
+        } else if entry.file_type().is_file() {
+            crate::snapshot::clone_or_copy(entry.path(), &dest_path)
+                .with_context(|| format!("Failed to copy {:?} to {:?}", entry.path(), dest_path))?;
+        } else if entry.file_type().is_symlink() {
+            let target = std::fs::read_link(entry.path())?;
+            let target = match target.strip_prefix(src) {
+                Ok(inside) => dest.join(inside),
+                Err(_) => target,
+            };
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(&target, &dest_path)
+                .with_context(|| format!("Failed to create symlink {:?}", dest_path))?;
+            #[cfg(not(unix))]
+            tracing::debug!("Not copying symlink {:?} -> {:?}", dest_path, target);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pool_hands_out_distinct_copies() {
+        let primary = tempfile::tempdir().unwrap();
+        std::fs::create_dir(primary.path().join("src")).unwrap();
+        std::fs::write(primary.path().join("src/lib.rs"), "fn f() {}").unwrap();
+
+        let pool = WorkspacePool::create(primary.path(), 2).await.unwrap();
+        assert_eq!(pool.size(), 2);
+
+        let file = primary.path().join("src/lib.rs");
+        let first = pool.acquire().await;
+        let second = pool.acquire().await;
+        let (a, b) = (first.rebase(&file), second.rebase(&file));
+        assert_ne!(a, b);
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "fn f() {}");
+        assert_eq!(std::fs::read_to_string(&b).unwrap(), "fn f() {}");
+
+        // Returned workspaces are handed out again
+        drop(first);
+        assert_eq!(pool.acquire().await.rebase(&file), a);
+    }
+
+    #[tokio::test]
+    async fn test_single_workspace_uses_primary() {
+        let primary = tempfile::tempdir().unwrap();
+        let pool = WorkspacePool::create(primary.path(), 0).await.unwrap();
+        assert_eq!(pool.size(), 1);
+
+        let path = primary.path().join("Cargo.toml");
+        assert_eq!(pool.acquire().await.rebase(&path), path);
+    }
+}
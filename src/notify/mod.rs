@@ -0,0 +1,699 @@
+//! Notifications about scans, critical findings and mutation testing outcomes.
+//!
+//! Three events are reported: a finished processing cycle (with the counts of
+//! its run history entry), error-severity findings that weren't reported
+//! before, and new survived mutants after a mutation testing run. Findings
+//! and mutants are consolidated into a single notification per repository and
+//! sent to every channel subscribed to the event (webhooks in generic, Slack
+//! or Discord format, desktop, email). A daily cap keeps a noisy night from
+//! flooding anyone's inbox; notifications link back to the dashboard for
+//! details.
+//!
+//! Email also carries a [`DigestEmail`] each morning, summarizing what the scans
+//! since the previous one found per repository.
+
+mod smtp;
+
+use crate::config::{EmailConfig, NotificationConfig, NotificationEvent, WebhookFormat};
+use crate::db::{RepositoryDigest, ScanRun};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::json;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+/// Maximum length of a Discord message
+const DISCORD_MAX_CHARS: usize = 2000;
+
+/// A survived mutant to report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SurvivedMutant {
+    /// File path relative to the repository root
+    pub file_path: String,
+    pub description: String,
+}
+
+/// An error-severity finding to report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CriticalFinding {
+    /// File path relative to the repository root
+    pub file_path: String,
+    pub title: String,
+    pub line_start: Option<i64>,
+}
+
+/// A notification about one event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    /// Repository the notification is about (none for scan summaries)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    pub title: String,
+    /// Plain-text body
+    pub text: String,
+    /// Link to the details on the dashboard
+    pub url: String,
+    /// Listed mutants (at most `max_mutants`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mutants: Vec<SurvivedMutant>,
+    /// Listed findings (at most `max_mutants`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<CriticalFinding>,
+    /// The finished processing cycle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run: Option<ScanRun>,
+    /// Total number of new mutants or findings, including unlisted ones
+    pub total: usize,
+}
+
+impl Notification {
+    /// Build a notification listing up to `max_mutants` survived mutants.
+    pub fn survived_mutants(
+        repository: &str,
+        repository_id: i64,
+        mut mutants: Vec<SurvivedMutant>,
+        max_mutants: usize,
+        dashboard_url: &str,
+    ) -> Self {
+        let total = mutants.len();
+        mutants.truncate(max_mutants);
+
+        let title = format!(
+            "{} new survived mutant{} in {}",
+            total,
+            if total == 1 { "" } else { "s" },
+            repository
+        );
+        let url = format!("{}/repositories/{}/mutations", dashboard_url, repository_id);
+        let lines = mutants
+            .iter()
+            .map(|mutant| format!("{}: {}", mutant.file_path, mutant.description))
+            .collect();
+
+        Self {
+            event: NotificationEvent::SurvivedMutants,
+            repository: Some(repository.to_string()),
+            title,
+            text: list_text(lines, total, &url),
+            url,
+            mutants,
+            findings: Vec::new(),
+            run: None,
+            total,
+        }
+    }
+
+    /// Build a notification listing up to `max_findings` new error-severity findings.
+    pub fn critical_findings(
+        repository: &str,
+        repository_id: i64,
+        mut findings: Vec<CriticalFinding>,
+        max_findings: usize,
+        dashboard_url: &str,
+    ) -> Self {
+        let total = findings.len();
+        findings.truncate(max_findings);
+
+        let title = format!(
+            "{} new critical finding{} in {}",
+            total,
+            if total == 1 { "" } else { "s" },
+            repository
+        );
+        let url = format!("{}/repositories/{}/files", dashboard_url, repository_id);
+        let lines = findings
+            .iter()
+            .map(|finding| match finding.line_start {
+                Some(line) => format!("{}:{}: {}", finding.file_path, line, finding.title),
+                None => format!("{}: {}", finding.file_path, finding.title),
+            })
+            .collect();
+
+        Self {
+            event: NotificationEvent::CriticalFindings,
+            repository: Some(repository.to_string()),
+            title,
+            text: list_text(lines, total, &url),
+            url,
+            mutants: Vec::new(),
+            findings,
+            run: None,
+            total,
+        }
+    }
+
+    /// Build a summary of a finished processing cycle
+    pub fn scan_completed(run: ScanRun, dashboard_url: &str) -> Self {
+        let title = format!("Noctum scan {}", run.status);
+        let url = format!("{}/history", dashboard_url);
+        let mut text = format!(
+            "{} repositories processed, {} files analyzed, {} mutations run, {} errors\n",
+            run.repositories_processed, run.files_analyzed, run.mutations_run, run.errors
+        );
+        if let Some(error) = &run.last_error {
+            text.push_str(&format!("Last error: {}\n", error));
+        }
+        text.push_str(&format!("\nDetails: {}\n", url));
+
+        Self {
+            event: NotificationEvent::ScanCompleted,
+            repository: None,
+            title,
+            text,
+            url,
+            mutants: Vec::new(),
+            findings: Vec::new(),
+            run: Some(run),
+            total: 0,
+        }
+    }
+
+    /// JSON body of the notification for a webhook in `format`
+    pub fn webhook_payload(&self, format: WebhookFormat) -> serde_json::Value {
+        match format {
+            WebhookFormat::Generic => serde_json::to_value(self).unwrap_or_default(),
+            WebhookFormat::Slack => json!({ "text": format!("*{}*\n{}", self.title, self.text) }),
+            WebhookFormat::Discord => {
+                let content = format!("**{}**\n{}", self.title, self.text);
+                let content = match content.char_indices().nth(DISCORD_MAX_CHARS - 1) {
+                    Some((end, _)) => format!("{}…", &content[..end]),
+                    None => content,
+                };
+                json!({ "username": "Noctum", "content": content })
+            }
+        }
+    }
+}
+
+/// Body listing `lines` (of `total` items) followed by a link to the details
+fn list_text(lines: Vec<String>, total: usize, url: &str) -> String {
+    let mut text = String::new();
+    for line in &lines {
+        text.push_str(&format!("- {}\n", line));
+    }
+    if total > lines.len() {
+        text.push_str(&format!("...and {} more\n", total - lines.len()));
+    }
+    text.push_str(&format!("\nDetails: {}\n", url));
+    text
+}
+
+/// Sends notifications to the configured channels
+pub struct Notifier {
+    config: NotificationConfig,
+    client: reqwest::Client,
+}
+
+impl Notifier {
+    pub fn new(config: NotificationConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send a notification to every configured channel.
+    ///
+    /// Channels fail independently; an error is returned only if every
+    /// configured channel failed.
+    pub async fn send(&self, notification: &Notification) -> Result<()> {
+        let mut attempted = 0;
+        let mut errors = Vec::new();
+
+        for webhook in &self.config.webhooks {
+            if !webhook.events.contains(&notification.event) {
+                continue;
+            }
+            attempted += 1;
+            if let Err(e) = self
+                .send_webhook(&webhook.url, webhook.format, notification)
+                .await
+            {
+                tracing::warn!("Webhook notification failed: {}", e);
+                errors.push(e);
+            }
+        }
+
+        if !self.config.events.contains(&notification.event) {
+            return finish(attempted, errors);
+        }
+        if let Some(url) = &self.config.webhook_url {
+            attempted += 1;
+            if let Err(e) = self
+                .send_webhook(url, WebhookFormat::Generic, notification)
+                .await
+            {
+                tracing::warn!("Webhook notification failed: {}", e);
+                errors.push(e);
+            }
+        }
+        if self.config.desktop {
+            attempted += 1;
+            if let Err(e) = send_desktop(notification).await {
+                tracing::warn!("Desktop notification failed: {}", e);
+                errors.push(e);
+            }
+        }
+        if let Some(email) = self.config.email.as_ref().filter(|email| email.notify) {
+            attempted += 1;
+            if let Err(e) = send_email(email, &notification.title, &notification.text).await {
+                tracing::warn!("Email notification failed: {}", e);
+                errors.push(e);
+            }
+        }
+
+        finish(attempted, errors)
+    }
+
+    async fn send_webhook(
+        &self,
+        url: &str,
+        format: WebhookFormat,
+        notification: &Notification,
+    ) -> Result<()> {
+        self.client
+            .post(url)
+            .json(&notification.webhook_payload(format))
+            .send()
+            .await
+            .context("Failed to send webhook")?
+            .error_for_status()
+            .context("Webhook returned an error status")?;
+        Ok(())
+    }
+}
+
+/// Fail only if every attempted channel failed
+fn finish(attempted: usize, mut errors: Vec<anyhow::Error>) -> Result<()> {
+    if attempted > 0 && errors.len() == attempted {
+        return Err(errors.remove(0));
+    }
+    Ok(())
+}
+
+/// Show a desktop notification with the platform's notification tool
+async fn send_desktop(notification: &Notification) -> Result<()> {
+    let body = &notification.text;
+
+    let mut cmd = if cfg!(target_os = "macos") {
+        // Pass text as arguments rather than interpolating it into the script
+        let mut cmd = tokio::process::Command::new("osascript");
+        cmd.args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display notification (item 2 of argv) with title (item 1 of argv)",
+            "-e",
+            "end run",
+            &notification.title,
+            body,
+        ]);
+        cmd
+    } else {
+        let mut cmd = tokio::process::Command::new("notify-send");
+        cmd.args(["--app-name=Noctum", &notification.title, body]);
+        cmd
+    };
+
+    let status = cmd
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .context("Failed to run desktop notification command")?;
+    anyhow::ensure!(status.success(), "Desktop notification command failed");
+    Ok(())
+}
+
+/// Send an email to the recipients of `config`, through its SMTP server or
+/// the local `sendmail`
+async fn send_email(config: &EmailConfig, subject: &str, text: &str) -> Result<()> {
+    let message = email_message(&config.to.join(", "), config.from.as_deref(), subject, text)?;
+
+    match &config.smtp_host {
+        Some(host) => {
+            let from = config
+                .from
+                .as_deref()
+                .or(config.to.first().map(String::as_str))
+                .context("No email recipients configured")?;
+            smtp::send(config, host, from, &message).await
+        }
+        None => sendmail(&message).await,
+    }
+}
+
+/// Send an email message through the local `sendmail`
+async fn sendmail(message: &str) -> Result<()> {
+    let mut child = tokio::process::Command::new("sendmail")
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to run sendmail")?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(message.as_bytes())
+            .await
+            .context("Failed to write email to sendmail")?;
+    }
+
+    let status = child.wait().await.context("Failed to wait for sendmail")?;
+    anyhow::ensure!(status.success(), "sendmail exited with {}", status);
+    Ok(())
+}
+
+/// Build the RFC 822 message for an email to `to` (comma-separated addresses)
+fn email_message(to: &str, from: Option<&str>, subject: &str, text: &str) -> Result<String> {
+    // Header values must not contain line breaks, or they could inject headers
+    let is_header_safe = |value: &str| !value.contains(['\r', '\n']);
+    anyhow::ensure!(
+        is_header_safe(to) && from.is_none_or(is_header_safe) && is_header_safe(subject),
+        "Email addresses and subject must be a single line"
+    );
+
+    let mut message = format!("To: {}\nSubject: [Noctum] {}\n", to, subject);
+    if let Some(from) = from {
+        message.push_str(&format!("From: {}\n", from));
+    }
+    message.push_str(&format!(
+        "Date: {}\nContent-Type: text/plain; charset=utf-8\n\n{}",
+        chrono::Local::now().to_rfc2822(),
+        text
+    ));
+    Ok(message)
+}
+
+/// A morning email summarizing the scans since the previous digest
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestEmail {
+    pub subject: String,
+    pub text: String,
+}
+
+impl DigestEmail {
+    /// Summarize the finished `runs` and what they produced per repository
+    /// (repositories without new results are left out)
+    pub fn new(runs: &[ScanRun], repositories: &[RepositoryDigest], dashboard_url: &str) -> Self {
+        let sum = |count: fn(&ScanRun) -> i64| runs.iter().map(count).sum::<i64>();
+        let new_errors: i64 = repositories.iter().map(|r| r.new_errors).sum();
+        let subject = format!(
+            "Digest: {} scanned, {}",
+            plural(repositories.len() as i64, "repository", "repositories"),
+            plural(new_errors, "new error", "new errors")
+        );
+
+        let mut text = format!(
+            "{} since the last digest: {} analyzed, {} run, {}.\n",
+            plural(runs.len() as i64, "scan", "scans"),
+            plural(sum(|r| r.files_analyzed), "file", "files"),
+            plural(sum(|r| r.mutations_run), "mutation", "mutations"),
+            plural(sum(|r| r.errors), "error", "errors"),
+        );
+        if repositories.is_empty() {
+            text.push_str("\nNo repository had new results.\n");
+        }
+        for repo in repositories {
+            text.push_str(&format!(
+                "\n{}\n  Files analyzed: {}\n  New findings: {}, {}, {}\n  Mutation score: {}\n  Details: {}/repositories/{}\n",
+                repo.name,
+                repo.files_analyzed,
+                plural(repo.new_errors, "error", "errors"),
+                plural(repo.new_warnings, "warning", "warnings"),
+                plural(repo.new_infos, "info", "infos"),
+                score_change(repo.score_before(), repo.score()),
+                dashboard_url,
+                repo.repository_id
+            ));
+        }
+        text.push_str(&format!("\nRun history: {}/history\n", dashboard_url));
+
+        Self { subject, text }
+    }
+
+    /// Email the digest to the recipients of `config`
+    pub async fn send(&self, config: &EmailConfig) -> Result<()> {
+        send_email(config, &self.subject, &self.text).await
+    }
+}
+
+/// `count` followed by the singular or plural noun
+fn plural(count: i64, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
+/// A mutation score and how it changed, e.g. `71.0% -> 74.5% (+3.5)`
+fn score_change(before: Option<f64>, after: Option<f64>) -> String {
+    match (before, after) {
+        (_, None) => "no mutants tested".to_string(),
+        (None, Some(after)) => format!("{:.1}%", after * 100.0),
+        (Some(before), Some(after)) if (after - before).abs() < 0.0005 => {
+            format!("{:.1}% (unchanged)", after * 100.0)
+        }
+        (Some(before), Some(after)) => format!(
+            "{:.1}% -> {:.1}% ({:+.1})",
+            before * 100.0,
+            after * 100.0,
+            (after - before) * 100.0
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WebhookConfig;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn mutants(count: usize) -> Vec<SurvivedMutant> {
+        (0..count)
+            .map(|i| SurvivedMutant {
+                file_path: format!("src/file{}.rs", i),
+                description: format!("Changed `>` to `>=` on line {}", i),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_notification_caps_listed_mutants() {
+        let notification =
+            Notification::survived_mutants("myapp", 3, mutants(5), 2, "http://localhost:8420");
+
+        assert_eq!(notification.title, "5 new survived mutants in myapp");
+        assert_eq!(notification.mutants.len(), 2);
+        assert_eq!(notification.total, 5);
+        assert_eq!(
+            notification.url,
+            "http://localhost:8420/repositories/3/mutations"
+        );
+        assert!(notification.text.contains("src/file1.rs"));
+        assert!(!notification.text.contains("src/file2.rs"));
+        assert!(notification.text.contains("...and 3 more"));
+    }
+
+    #[test]
+    fn test_email_message_rejects_header_injection() {
+        let notification = Notification::survived_mutants("app", 1, mutants(1), 10, "http://x");
+        let message = email_message(
+            "me@example.com",
+            None,
+            &notification.title,
+            &notification.text,
+        )
+        .unwrap();
+        assert!(message
+            .starts_with("To: me@example.com\nSubject: [Noctum] 1 new survived mutant in app\n"));
+
+        assert!(email_message(
+            "me@example.com\nBcc: evil@example.com",
+            None,
+            &notification.title,
+            &notification.text
+        )
+        .is_err());
+        assert!(email_message(
+            "me@example.com",
+            Some("noctum@example.com\nBcc: evil@example.com"),
+            &notification.title,
+            &notification.text
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_webhook() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = Notifier::new(NotificationConfig {
+            webhook_url: Some(format!("{}/hook", server.uri())),
+            ..NotificationConfig::default()
+        });
+        let notification = Notification::survived_mutants("app", 1, mutants(2), 10, "http://x");
+        notifier.send(&notification).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["repository"], "app");
+    }
+
+    fn run() -> ScanRun {
+        ScanRun {
+            id: 7,
+            trigger: "scheduled".to_string(),
+            status: "completed".to_string(),
+            started_at: "2025-01-01T02:00:00.000Z".to_string(),
+            finished_at: Some("2025-01-01T03:00:00.000Z".to_string()),
+            repositories_processed: 2,
+            files_analyzed: 14,
+            mutations_run: 30,
+            errors: 0,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_critical_findings_notification() {
+        let findings = vec![
+            CriticalFinding {
+                file_path: "src/db.rs".to_string(),
+                title: "SQL built from user input".to_string(),
+                line_start: Some(12),
+            },
+            CriticalFinding {
+                file_path: "src/auth.rs".to_string(),
+                title: "Token compared in variable time".to_string(),
+                line_start: None,
+            },
+        ];
+        let notification =
+            Notification::critical_findings("myapp", 3, findings, 10, "http://localhost:8420");
+
+        assert_eq!(notification.title, "2 new critical findings in myapp");
+        assert_eq!(
+            notification.url,
+            "http://localhost:8420/repositories/3/files"
+        );
+        assert!(notification
+            .text
+            .contains("- src/db.rs:12: SQL built from user input\n"));
+        assert!(notification
+            .text
+            .contains("- src/auth.rs: Token compared in variable time\n"));
+    }
+
+    #[test]
+    fn test_webhook_payload_formats() {
+        let notification = Notification::scan_completed(run(), "http://x");
+        assert_eq!(notification.title, "Noctum scan completed");
+        assert_eq!(notification.url, "http://x/history");
+
+        let generic = notification.webhook_payload(WebhookFormat::Generic);
+        assert_eq!(generic["event"], "scan_completed");
+        assert_eq!(generic["run"]["files_analyzed"], 14);
+        assert!(generic.get("repository").is_none());
+        assert!(generic.get("mutants").is_none());
+
+        let slack = notification.webhook_payload(WebhookFormat::Slack);
+        assert!(slack["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("*Noctum scan completed*\n2 repositories processed, 14 files analyzed"));
+
+        let long = Notification::survived_mutants("app", 1, mutants(100), 100, "http://x");
+        let discord = long.webhook_payload(WebhookFormat::Discord);
+        let content = discord["content"].as_str().unwrap();
+        assert!(content.starts_with("**100 new survived mutants in app**\n"));
+        assert_eq!(content.chars().count(), DISCORD_MAX_CHARS);
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_receive_subscribed_events() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let notifier = Notifier::new(NotificationConfig {
+            webhooks: vec![
+                WebhookConfig {
+                    url: format!("{}/slack", server.uri()),
+                    format: WebhookFormat::Slack,
+                    events: vec![NotificationEvent::SurvivedMutants],
+                },
+                WebhookConfig {
+                    url: format!("{}/discord", server.uri()),
+                    format: WebhookFormat::Discord,
+                    events: vec![NotificationEvent::ScanCompleted],
+                },
+            ],
+            ..NotificationConfig::default()
+        });
+        notifier
+            .send(&Notification::scan_completed(run(), "http://x"))
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].url.path(), "/discord");
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert!(body["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("**Noctum scan completed**"));
+    }
+
+    #[test]
+    fn test_digest() {
+        let repositories = vec![
+            RepositoryDigest {
+                repository_id: 1,
+                name: "api".to_string(),
+                files_analyzed: 12,
+                new_errors: 1,
+                new_warnings: 3,
+                mutations_run: 40,
+                killed_before: 71,
+                survived_before: 29,
+                killed: 101,
+                survived: 39,
+                ..RepositoryDigest::default()
+            },
+            RepositoryDigest {
+                repository_id: 2,
+                name: "web".to_string(),
+                files_analyzed: 2,
+                ..RepositoryDigest::default()
+            },
+        ];
+        let digest = DigestEmail::new(&[run(), run()], &repositories, "http://x");
+
+        assert_eq!(
+            digest.subject,
+            "Digest: 2 repositories scanned, 1 new error"
+        );
+        assert!(digest.text.starts_with(
+            "2 scans since the last digest: 28 files analyzed, 60 mutations run, 0 errors.\n"
+        ));
+        assert!(digest.text.contains(
+            "\napi\n  Files analyzed: 12\n  New findings: 1 error, 3 warnings, 0 infos\n  \
+             Mutation score: 71.0% -> 72.1% (+1.1)\n  Details: http://x/repositories/1\n"
+        ));
+        assert!(digest
+            .text
+            .contains("  Mutation score: no mutants tested\n"));
+        assert!(digest.text.ends_with("\nRun history: http://x/history\n"));
+    }
+}
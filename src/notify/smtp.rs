@@ -0,0 +1,295 @@
+//! A minimal SMTP client for email notifications and digests.
+//!
+//! Just enough of RFC 5321 to hand one message to a mail server: implicit TLS
+//! or `STARTTLS` (or neither, for a relay on localhost), optional
+//! `AUTH PLAIN`, and one `RCPT TO` per recipient.
+
+use crate::config::{EmailConfig, SmtpTls};
+use anyhow::{Context, Result};
+use base64::Engine;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// How long to wait for the server to connect or reply
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A plain or TLS connection
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// Send `message` (headers and body, lines ending in `\n`) from `from` to
+/// every recipient of `config` through the SMTP server at `host`
+pub async fn send(config: &EmailConfig, host: &str, from: &str, message: &str) -> Result<()> {
+    let port = config.smtp_port();
+    let tcp = tokio::time::timeout(TIMEOUT, TcpStream::connect((host, port)))
+        .await
+        .with_context(|| format!("Timed out connecting to {}:{}", host, port))?
+        .with_context(|| format!("Failed to connect to {}:{}", host, port))?;
+    let stream: Box<dyn Stream> = match config.smtp_tls {
+        SmtpTls::Tls => Box::new(start_tls(host, tcp).await?),
+        SmtpTls::Starttls | SmtpTls::None => Box::new(tcp),
+    };
+
+    let mut connection = Connection {
+        stream: BufReader::new(stream),
+    };
+    connection.reply("greeting", &[220]).await?;
+    connection.command("EHLO localhost", &[250]).await?;
+
+    if config.smtp_tls == SmtpTls::Starttls {
+        connection.command("STARTTLS", &[220]).await?;
+        let stream = start_tls(host, connection.stream.into_inner()).await?;
+        connection = Connection {
+            stream: BufReader::new(Box::new(stream)),
+        };
+        connection.command("EHLO localhost", &[250]).await?;
+    }
+
+    if let Some(username) = &config.smtp_username {
+        let password = config.smtp_password.as_deref().unwrap_or_default();
+        let credentials = base64::engine::general_purpose::STANDARD
+            .encode(format!("\0{}\0{}", username, password));
+        connection
+            .send_line(&format!("AUTH PLAIN {}", credentials))
+            .await?;
+        // Don't echo the credentials in errors
+        connection.reply("AUTH", &[235]).await?;
+    }
+
+    connection
+        .command(&format!("MAIL FROM:<{}>", from), &[250])
+        .await?;
+    for recipient in &config.to {
+        connection
+            .command(&format!("RCPT TO:<{}>", recipient), &[250, 251])
+            .await?;
+    }
+    connection.command("DATA", &[354]).await?;
+    connection.send_raw(&data(message)).await?;
+    connection.reply("message", &[250]).await?;
+
+    // The message is accepted; a failed goodbye doesn't matter
+    let _ = connection.command("QUIT", &[221]).await;
+    Ok(())
+}
+
+/// Wrap `stream` in TLS, verifying the certificate of `host`
+async fn start_tls<S: Stream>(host: &str, stream: S) -> Result<impl Stream> {
+    let connector =
+        tokio_native_tls::native_tls::TlsConnector::new().context("Failed to set up TLS")?;
+    tokio::time::timeout(
+        TIMEOUT,
+        tokio_native_tls::TlsConnector::from(connector).connect(host, stream),
+    )
+    .await
+    .context("Timed out starting TLS")?
+    .with_context(|| format!("TLS handshake with {} failed", host))
+}
+
+/// The message in the `DATA` format: CRLF line endings, lines starting with a
+/// dot escaped with another one, and a lone dot at the end
+fn data(message: &str) -> String {
+    let mut data = String::with_capacity(message.len() + 64);
+    for line in message.lines() {
+        if line.starts_with('.') {
+            data.push('.');
+        }
+        data.push_str(line);
+        data.push_str("\r\n");
+    }
+    data.push_str(".\r\n");
+    data
+}
+
+struct Connection {
+    stream: BufReader<Box<dyn Stream>>,
+}
+
+impl Connection {
+    /// Send a command and check its reply code
+    async fn command(&mut self, command: &str, expected: &[u16]) -> Result<String> {
+        self.send_line(command).await?;
+        let verb = command.split([' ', ':']).next().unwrap_or(command);
+        self.reply(verb, expected).await
+    }
+
+    async fn send_line(&mut self, line: &str) -> Result<()> {
+        self.send_raw(&format!("{}\r\n", line)).await
+    }
+
+    async fn send_raw(&mut self, data: &str) -> Result<()> {
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(data.as_bytes())
+            .await
+            .context("Failed to write to the SMTP server")?;
+        stream
+            .flush()
+            .await
+            .context("Failed to write to the SMTP server")
+    }
+
+    /// Read a (possibly multiline) reply and check its code, returning its text
+    async fn reply(&mut self, step: &str, expected: &[u16]) -> Result<String> {
+        let mut text = String::new();
+        loop {
+            let mut line = String::new();
+            let read = tokio::time::timeout(TIMEOUT, self.stream.read_line(&mut line))
+                .await
+                .with_context(|| format!("Timed out waiting for the reply to {}", step))?
+                .context("Failed to read from the SMTP server")?;
+            anyhow::ensure!(read > 0, "SMTP server closed the connection");
+
+            let line = line.trim_end();
+            let code: u16 = line
+                .get(..3)
+                .and_then(|code| code.parse().ok())
+                .with_context(|| format!("Malformed SMTP reply: {}", line))?;
+            text.push_str(line.get(4..).unwrap_or_default());
+            text.push('\n');
+
+            // "250-..." continues the reply, "250 ..." ends it
+            if line.as_bytes().get(3) != Some(&b'-') {
+                anyhow::ensure!(
+                    expected.contains(&code),
+                    "SMTP server rejected {}: {} {}",
+                    step,
+                    code,
+                    text.trim_end()
+                );
+                return Ok(text);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_data_escapes_dots() {
+        assert_eq!(
+            data("Subject: x\n\n.hidden\nfine.\n"),
+            "Subject: x\r\n\r\n..hidden\r\nfine.\r\n.\r\n"
+        );
+    }
+
+    /// Accept one connection, answer each command from `replies` and return
+    /// the lines received
+    async fn fake_server(
+        replies: Vec<(&'static str, &'static str)>,
+    ) -> (u16, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = socket.into_split();
+            let mut reader = BufReader::new(reader);
+            writer.write_all(b"220 test ESMTP\r\n").await.unwrap();
+
+            let mut received = Vec::new();
+            let mut in_data = false;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end_matches("\r\n").to_string();
+                received.push(line.clone());
+                if in_data {
+                    if line == "." {
+                        in_data = false;
+                        writer.write_all(b"250 queued\r\n").await.unwrap();
+                    }
+                    continue;
+                }
+                let reply = replies
+                    .iter()
+                    .find(|(prefix, _)| line.starts_with(prefix))
+                    .map(|(_, reply)| *reply)
+                    .unwrap_or("500 unknown\r\n");
+                in_data = line == "DATA";
+                writer.write_all(reply.as_bytes()).await.unwrap();
+                if line == "QUIT" {
+                    break;
+                }
+            }
+            received
+        });
+        (port, handle)
+    }
+
+    fn config(port: u16) -> EmailConfig {
+        EmailConfig {
+            to: vec!["a@example.com".to_string(), "b@example.com".to_string()],
+            smtp_host: Some("127.0.0.1".to_string()),
+            smtp_port: Some(port),
+            smtp_tls: SmtpTls::None,
+            smtp_username: Some("noctum".to_string()),
+            smtp_password: Some("secret".to_string()),
+            ..EmailConfig::address(String::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send() {
+        let (port, server) = fake_server(vec![
+            ("EHLO", "250-test\r\n250 AUTH PLAIN\r\n"),
+            ("AUTH PLAIN", "235 ok\r\n"),
+            ("MAIL FROM", "250 ok\r\n"),
+            ("RCPT TO", "250 ok\r\n"),
+            ("DATA", "354 go ahead\r\n"),
+            ("QUIT", "221 bye\r\n"),
+        ])
+        .await;
+
+        send(
+            &config(port),
+            "127.0.0.1",
+            "noctum@example.com",
+            "Subject: Hi\n\n.dot\n",
+        )
+        .await
+        .unwrap();
+
+        let received = server.await.unwrap();
+        let credentials = base64::engine::general_purpose::STANDARD.encode("\0noctum\0secret");
+        assert_eq!(
+            received,
+            [
+                "EHLO localhost".to_string(),
+                format!("AUTH PLAIN {}", credentials),
+                "MAIL FROM:<noctum@example.com>".to_string(),
+                "RCPT TO:<a@example.com>".to_string(),
+                "RCPT TO:<b@example.com>".to_string(),
+                "DATA".to_string(),
+                "Subject: Hi".to_string(),
+                String::new(),
+                "..dot".to_string(),
+                ".".to_string(),
+                "QUIT".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_reports_rejections_without_credentials() {
+        let (port, _server) = fake_server(vec![
+            ("EHLO", "250 test\r\n"),
+            ("AUTH PLAIN", "535 authentication failed\r\n"),
+        ])
+        .await;
+
+        let error = send(&config(port), "127.0.0.1", "noctum@example.com", "x")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "SMTP server rejected AUTH: 535 authentication failed"
+        );
+    }
+}
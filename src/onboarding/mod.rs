@@ -0,0 +1,311 @@
+//! Repository onboarding.
+//!
+//! A repository added from the dashboard gets a quick scan right away: its
+//! projects and languages are discovered, its source files counted and its
+//! README summarized, so the repository page has something to show before the
+//! first full scan. Full scans skip the repository until the detected projects
+//! are confirmed on that page.
+
+use crate::config::Config;
+use crate::daemon::{
+    endpoints_for, find_available_endpoint, truncate_at_char_boundary, HealthMonitor,
+};
+use crate::db::{Database, Repository};
+use crate::language::Language;
+use crate::project::{self, ProjectType};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio_util::sync::CancellationToken;
+
+/// The quick scan is running
+pub const STATUS_SCANNING: &str = "scanning";
+/// The quick scan finished and waits for confirmation
+pub const STATUS_READY: &str = "ready";
+/// The quick scan failed; the repository can still be confirmed
+pub const STATUS_FAILED: &str = "failed";
+/// The repository was confirmed and is included in full scans
+pub const STATUS_CONFIRMED: &str = "confirmed";
+
+/// README files looked for at the repository root, in order
+const README_NAMES: &[&str] = &[
+    "README.md",
+    "README",
+    "README.txt",
+    "README.rst",
+    "readme.md",
+];
+
+/// Maximum characters of the README sent to the model
+const MAX_README_CHARS: usize = 8_000;
+
+/// Maximum characters of the README excerpt used without a model
+const MAX_EXCERPT_CHARS: usize = 500;
+
+/// What a quick scan found
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuickScan {
+    pub projects: Vec<DetectedProject>,
+    /// Languages by number of source files, most first
+    pub languages: Vec<LanguageCount>,
+    /// Source files of all languages
+    pub file_count: usize,
+    /// Summary of the README, or its first paragraph if no model was available
+    pub readme_summary: Option<String>,
+}
+
+/// A project found by the quick scan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedProject {
+    pub name: String,
+    /// Path relative to the repository root ("." for the root)
+    pub path: String,
+    pub language: String,
+    /// "standalone", "workspace root" or "workspace member"
+    pub kind: String,
+    /// Source files under the project root
+    pub files: usize,
+}
+
+/// Number of source files of a language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageCount {
+    pub language: String,
+    pub files: usize,
+}
+
+/// Discover the projects and languages of a repository and count its source
+/// files. The README summary is left to [`summarize_readme`].
+pub fn survey(repo_path: &Path) -> Result<QuickScan> {
+    let discovered = project::discover_projects(repo_path)?;
+
+    let mut languages: Vec<Language> = Vec::new();
+    for language in discovered.iter().map(|p| p.language) {
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+    if languages.is_empty() {
+        languages.extend(Language::detect(repo_path));
+    }
+
+    let projects = discovered
+        .iter()
+        .map(|p| DetectedProject {
+            name: p.name.clone(),
+            path: if p.relative_path.is_empty() {
+                ".".to_string()
+            } else {
+                p.relative_path.clone()
+            },
+            language: p.language.name().to_string(),
+            kind: match p.project_type {
+                ProjectType::Standalone => "standalone",
+                ProjectType::WorkspaceRoot => "workspace root",
+                ProjectType::WorkspaceMember => "workspace member",
+            }
+            .to_string(),
+            files: p
+                .language
+                .find_source_files(&p.root)
+                .map_or(0, |files| files.len()),
+        })
+        .collect();
+
+    // Counted over the whole repository, so files of nested projects count once
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for language in &languages {
+        let files = language.find_source_files(repo_path)?.len();
+        *counts.entry(language.name()).or_default() += files;
+    }
+    let mut languages: Vec<LanguageCount> = counts
+        .into_iter()
+        .map(|(language, files)| LanguageCount {
+            language: language.to_string(),
+            files,
+        })
+        .collect();
+    languages.sort_by_key(|l| std::cmp::Reverse(l.files));
+
+    Ok(QuickScan {
+        projects,
+        file_count: languages.iter().map(|l| l.files).sum(),
+        languages,
+        readme_summary: None,
+    })
+}
+
+/// Contents of the repository's README, if it has one
+pub fn read_readme(repo_path: &Path) -> Option<String> {
+    README_NAMES
+        .iter()
+        .filter_map(|name| std::fs::read_to_string(repo_path.join(name)).ok())
+        .find(|content| !content.trim().is_empty())
+}
+
+/// First paragraph of prose in a README, skipping headings, badges and HTML
+pub fn readme_excerpt(readme: &str) -> Option<String> {
+    let paragraph = readme
+        .split("\n\n")
+        .map(|block| {
+            block
+                .lines()
+                .map(str::trim)
+                .filter(|line| {
+                    !line.is_empty()
+                        && !line.starts_with('#')
+                        && !line.starts_with("[![")
+                        && !line.starts_with("![")
+                        && !line.starts_with('<')
+                        && !line.chars().all(|c| matches!(c, '=' | '-'))
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .find(|paragraph| !paragraph.is_empty())?;
+
+    if paragraph.chars().count() > MAX_EXCERPT_CHARS {
+        let cut = truncate_at_char_boundary(&paragraph, MAX_EXCERPT_CHARS);
+        Some(format!("{}...", cut.trim_end()))
+    } else {
+        Some(paragraph)
+    }
+}
+
+fn readme_prompt(name: &str, readme: &str, output_language: &str) -> String {
+    format!(
+        "Summarize in two or three sentences what the project \"{}\" is and does, \
+         based on its README. Reply with the summary only, in {}.\n\nREADME:\n{}",
+        name,
+        output_language,
+        truncate_at_char_boundary(readme, MAX_README_CHARS)
+    )
+}
+
+/// Summarize a README with the first available endpoint the repository may
+/// use, falling back to its first paragraph
+pub async fn summarize_readme(
+    db: &Database,
+    config: &Config,
+    health: &HealthMonitor,
+    repo: &Repository,
+    readme: &str,
+) -> Option<String> {
+    let enabled: Vec<_> = config
+        .endpoints
+        .iter()
+        .filter(|e| e.enabled)
+        .cloned()
+        .collect();
+    let endpoints = endpoints_for(repo, &enabled);
+
+    if let Some((client, endpoint_name)) =
+        find_available_endpoint(&endpoints, health, &CancellationToken::new()).await
+    {
+        let generated = client
+            .generate(&readme_prompt(
+                &repo.name,
+                readme,
+                &config.general.output_language,
+            ))
+            .await;
+        let usage = client.take_usage();
+        if !usage.is_empty() {
+            if let Err(e) = db
+                .record_usage(repo.id, &endpoint_name, "onboarding", &usage)
+                .await
+            {
+                tracing::warn!("Failed to record usage: {}", e);
+            }
+        }
+        match generated {
+            Ok(summary) if !summary.trim().is_empty() => return Some(summary.trim().to_string()),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to summarize the README of {}: {:#}", repo.name, e),
+        }
+    }
+
+    readme_excerpt(readme)
+}
+
+/// Run the quick scan of a newly added repository and store what it found
+pub async fn run(db: &Database, config: &Config, health: &HealthMonitor, repo: &Repository) {
+    let path = repo.path.clone();
+    let surveyed = tokio::task::spawn_blocking(move || survey(Path::new(&path)))
+        .await
+        .context("Quick scan panicked")
+        .and_then(|scan| scan);
+
+    let saved = match surveyed {
+        Ok(mut scan) => {
+            if let Some(readme) = read_readme(Path::new(&repo.path)) {
+                scan.readme_summary = summarize_readme(db, config, health, repo, &readme).await;
+            }
+            tracing::info!(
+                "Quick scan of {} found {} projects and {} source files",
+                repo.name,
+                scan.projects.len(),
+                scan.file_count
+            );
+            db.finish_onboarding(repo.id, &scan).await
+        }
+        Err(e) => {
+            tracing::warn!("Quick scan of {} failed: {:#}", repo.name, e);
+            db.fail_onboarding(repo.id, &format!("{:#}", e)).await
+        }
+    };
+    if let Err(e) = saved {
+        tracing::warn!("Failed to save the quick scan of {}: {}", repo.name, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_survey() {
+        let repo = TempDir::new().unwrap();
+        std::fs::write(
+            repo.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir(repo.path().join("src")).unwrap();
+        std::fs::write(repo.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(repo.path().join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+
+        let scan = survey(repo.path()).unwrap();
+        assert_eq!(scan.projects.len(), 1);
+        assert_eq!(scan.projects[0].name, "app");
+        assert_eq!(scan.projects[0].path, ".");
+        assert_eq!(scan.projects[0].kind, "standalone");
+        assert_eq!(
+            scan.languages,
+            [LanguageCount {
+                language: "Rust".to_string(),
+                files: 2
+            }]
+        );
+        assert_eq!(scan.file_count, 2);
+    }
+
+    #[test]
+    fn test_readme_excerpt() {
+        let readme =
+            "# Noctum\n\n[![CI](badge.svg)](ci)\n\nAnalyzes code\nwhile you sleep.\n\n## Usage\n";
+        assert_eq!(
+            readme_excerpt(readme).as_deref(),
+            Some("Analyzes code while you sleep.")
+        );
+        assert_eq!(readme_excerpt("# Title\n\n## Only headings\n"), None);
+
+        let long = "word ".repeat(200);
+        let excerpt = readme_excerpt(&long).unwrap();
+        assert!(excerpt.ends_with("..."));
+        assert!(excerpt.chars().count() <= MAX_EXCERPT_CHARS + 3);
+    }
+}
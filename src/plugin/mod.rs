@@ -0,0 +1,389 @@
+//! WASM analyzer plugins.
+//!
+//! Plugins are deterministic, sandboxed analyzers compiled to WebAssembly and listed in a
+//! repository's `noctum.toml`. They run per file alongside the LLM pipelines, so teams can
+//! ship proprietary checks without forking Noctum.
+//!
+//! A plugin is a core WASM module with no imports that exports:
+//! - `memory`: its linear memory
+//! - `alloc(len: i32) -> i32`: allocate `len` bytes and return a pointer
+//! - `analyze(path_ptr: i32, path_len: i32, content_ptr: i32, content_len: i32) -> i64`:
+//!   analyze a file and return `(ptr << 32) | len` of a UTF-8 JSON array of findings
+//!
+//! Each finding is an object like `{"line": 12, "severity": "warning", "message": "..."}`,
+//! where `line` and `severity` are optional.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use wasmtime::{Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Maximum fuel (roughly, WASM instructions) a plugin may use per file
+const FUEL_PER_FILE: u64 = 2_000_000_000;
+
+/// Maximum linear memory a plugin may grow to
+const MAX_MEMORY_BYTES: usize = 256 * 1024 * 1024;
+
+/// Maximum size of the findings JSON a plugin may return
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// A single finding reported by a plugin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    /// 1-based line number the finding refers to
+    #[serde(default)]
+    pub line: Option<u32>,
+    /// One of "error", "warning", "info" (defaults to "info")
+    #[serde(default)]
+    pub severity: Option<String>,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// A compiled analyzer plugin
+#[derive(Clone)]
+pub struct Plugin {
+    /// Name derived from the file name (used in the analysis type, e.g. `plugin_license`)
+    pub name: String,
+    /// Hash of the plugin binary, so results are recomputed when the plugin changes
+    pub hash: String,
+    module: Module,
+}
+
+impl Plugin {
+    /// Analysis type under which this plugin's results are stored
+    pub fn analysis_type(&self) -> String {
+        format!("plugin_{}", self.name)
+    }
+}
+
+/// Host that compiles and runs analyzer plugins
+#[derive(Clone)]
+pub struct PluginHost {
+    engine: Engine,
+    plugins: Vec<Plugin>,
+}
+
+/// Per-invocation store data
+struct PluginState {
+    limits: StoreLimits,
+}
+
+impl PluginHost {
+    /// Compile the plugins at the given paths (relative paths resolve against `base_dir`).
+    ///
+    /// Plugins that fail to load are logged and skipped so one bad plugin does not
+    /// block the others.
+    pub fn load(base_dir: &Path, paths: &[PathBuf]) -> Result<Self> {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).context("Failed to create WASM engine")?;
+
+        let mut plugins = Vec::new();
+        for path in paths {
+            let path = base_dir.join(path);
+            match Self::compile(&engine, &path) {
+                Ok(plugin) => {
+                    tracing::info!("Loaded analyzer plugin '{}'", plugin.name);
+                    plugins.push(plugin);
+                }
+                Err(e) => tracing::warn!("Failed to load plugin {}: {:#}", path.display(), e),
+            }
+        }
+
+        Ok(Self { engine, plugins })
+    }
+
+    fn compile(engine: &Engine, path: &Path) -> Result<Plugin> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("Failed to read plugin {:?}", path))?;
+        let module = Module::new(engine, &bytes).context("Failed to compile plugin")?;
+
+        if module.imports().len() > 0 {
+            anyhow::bail!("Plugins must not import host functions");
+        }
+
+        let name: String = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        if name.is_empty() {
+            anyhow::bail!("Plugin file name must not be empty");
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(&bytes);
+
+        Ok(Plugin {
+            name,
+            hash: format!("{:x}", hasher.finalize()),
+            module,
+        })
+    }
+
+    /// The successfully loaded plugins
+    pub fn plugins(&self) -> &[Plugin] {
+        &self.plugins
+    }
+
+    /// Run a plugin against a single file.
+    ///
+    /// Each call gets a fresh instance with bounded fuel and memory, so plugins cannot
+    /// keep state between files, loop forever, or exhaust host memory.
+    pub fn analyze(&self, plugin: &Plugin, file_path: &str, content: &str) -> Result<Vec<Finding>> {
+        let mut store = Store::new(
+            &self.engine,
+            PluginState {
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(MAX_MEMORY_BYTES)
+                    .build(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_PER_FILE)?;
+
+        let linker = Linker::new(&self.engine);
+        let instance = linker
+            .instantiate(&mut store, &plugin.module)
+            .context("Failed to instantiate plugin")?;
+
+        let path_ptr = write_input(&mut store, &instance, file_path.as_bytes())?;
+        let content_ptr = write_input(&mut store, &instance, content.as_bytes())?;
+
+        let analyze = instance
+            .get_typed_func::<(i32, i32, i32, i32), i64>(&mut store, "analyze")
+            .context("Plugin does not export analyze(i32, i32, i32, i32) -> i64")?;
+        let packed = analyze
+            .call(
+                &mut store,
+                (
+                    path_ptr,
+                    file_path.len() as i32,
+                    content_ptr,
+                    content.len() as i32,
+                ),
+            )
+            .context("Plugin analyze call failed")?;
+
+        let out_ptr = (packed as u64 >> 32) as usize;
+        let out_len = (packed as u64 & 0xFFFF_FFFF) as usize;
+        if out_len > MAX_OUTPUT_BYTES {
+            anyhow::bail!("Plugin output too large ({} bytes)", out_len);
+        }
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .context("Plugin does not export memory")?;
+        let mut output = vec![0u8; out_len];
+        memory
+            .read(&store, out_ptr, &mut output)
+            .context("Plugin returned an out-of-bounds result")?;
+
+        serde_json::from_slice(&output).context("Plugin returned invalid findings JSON")
+    }
+}
+
+/// Copy input bytes into plugin memory using its `alloc` export
+fn write_input(store: &mut Store<PluginState>, instance: &Instance, bytes: &[u8]) -> Result<i32> {
+    let len = i32::try_from(bytes.len()).context("Input too large for plugin")?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&mut *store, "alloc")
+        .context("Plugin does not export alloc(i32) -> i32")?;
+    let ptr = alloc
+        .call(&mut *store, len)
+        .context("Plugin alloc call failed")?;
+
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .context("Plugin does not export memory")?;
+    memory
+        .write(&mut *store, ptr as u32 as usize, bytes)
+        .context("Plugin alloc returned an out-of-bounds pointer")?;
+
+    Ok(ptr)
+}
+
+/// Most severe level among the findings ("error" > "warning" > "info")
+pub fn overall_severity(findings: &[Finding]) -> &'static str {
+    let rank = |f: &Finding| match f.severity.as_deref() {
+        Some("error") => 2,
+        Some("warning") => 1,
+        _ => 0,
+    };
+
+    match findings.iter().map(rank).max() {
+        Some(2) => "error",
+        Some(1) => "warning",
+        _ => "info",
+    }
+}
+
+/// Render findings as Markdown for display alongside other analysis results
+pub fn render_findings(findings: &[Finding]) -> String {
+    if findings.is_empty() {
+        return "No findings.".to_string();
+    }
+
+    findings
+        .iter()
+        .map(|f| {
+            let severity = f.severity.as_deref().unwrap_or("info");
+            match f.line {
+                Some(line) => format!("- **{}** (line {}): {}", severity, line, f.message),
+                None => format!("- **{}**: {}", severity, f.message),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A plugin that reports one finding per occurrence of the byte 'X' in the content
+    const X_FINDER: &str = r#"
+(module
+  (memory (export "memory") 2)
+  (global $next (mut i32) (i32.const 1024))
+  (data (i32.const 0) "[{\22line\22:1,\22severity\22:\22warning\22,\22message\22:\22found X\22}]")
+  (data (i32.const 100) "[]")
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (local.get $ptr))
+  (func (export "analyze") (param i32 i32 i32 i32) (result i64)
+    (local $i i32)
+    (block $done
+      (loop $scan
+        (br_if $done (i32.ge_u (local.get $i) (local.get 3)))
+        (if (i32.eq (i32.load8_u (i32.add (local.get 2) (local.get $i))) (i32.const 88))
+          (then (return (i64.const 53))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $scan)))
+    (i64.or (i64.shl (i64.const 100) (i64.const 32)) (i64.const 2)))
+)
+"#;
+
+    const INFINITE_LOOP: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 0))
+  (func (export "analyze") (param i32 i32 i32 i32) (result i64)
+    (loop $forever (br $forever))
+    (i64.const 0))
+)
+"#;
+
+    const WITH_IMPORT: &str = r#"
+(module
+  (import "env" "log" (func $log (param i32)))
+  (memory (export "memory") 1)
+)
+"#;
+
+    fn write_plugin(dir: &Path, name: &str, wat: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, wat).unwrap();
+        PathBuf::from(name)
+    }
+
+    #[test]
+    fn test_load_and_analyze() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_plugin(temp_dir.path(), "x-finder.wat", X_FINDER);
+
+        let host = PluginHost::load(temp_dir.path(), &[path]).unwrap();
+        assert_eq!(host.plugins().len(), 1);
+        let plugin = &host.plugins()[0];
+        assert_eq!(plugin.name, "x_finder");
+        assert_eq!(plugin.analysis_type(), "plugin_x_finder");
+
+        let findings = host.analyze(plugin, "src/lib.rs", "let X = 1;").unwrap();
+        assert_eq!(
+            findings,
+            vec![Finding {
+                line: Some(1),
+                severity: Some("warning".to_string()),
+                message: "found X".to_string(),
+            }]
+        );
+
+        let findings = host.analyze(plugin, "src/lib.rs", "clean").unwrap();
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_infinite_loop_runs_out_of_fuel() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = write_plugin(temp_dir.path(), "loop.wat", INFINITE_LOOP);
+
+        let host = PluginHost::load(temp_dir.path(), &[path]).unwrap();
+        let result = host.analyze(&host.plugins()[0], "a.rs", "code");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_invalid_plugins_are_skipped() {
+        let temp_dir = TempDir::new().unwrap();
+        let import = write_plugin(temp_dir.path(), "import.wat", WITH_IMPORT);
+        let garbage = write_plugin(temp_dir.path(), "garbage.wasm", "not wasm");
+        let good = write_plugin(temp_dir.path(), "good.wat", X_FINDER);
+
+        let host = PluginHost::load(
+            temp_dir.path(),
+            &[import, garbage, PathBuf::from("missing.wasm"), good],
+        )
+        .unwrap();
+        assert_eq!(host.plugins().len(), 1);
+        assert_eq!(host.plugins()[0].name, "good");
+    }
+
+    #[test]
+    fn test_overall_severity() {
+        let finding = |severity: Option<&str>| Finding {
+            line: None,
+            severity: severity.map(String::from),
+            message: "m".to_string(),
+        };
+
+        assert_eq!(overall_severity(&[]), "info");
+        assert_eq!(overall_severity(&[finding(None)]), "info");
+        assert_eq!(
+            overall_severity(&[finding(Some("warning")), finding(None)]),
+            "warning"
+        );
+        assert_eq!(
+            overall_severity(&[finding(Some("warning")), finding(Some("error"))]),
+            "error"
+        );
+    }
+
+    #[test]
+    fn test_render_findings() {
+        assert_eq!(render_findings(&[]), "No findings.");
+
+        let findings = vec![
+            Finding {
+                line: Some(3),
+                severity: Some("error".to_string()),
+                message: "bad".to_string(),
+            },
+            Finding {
+                line: None,
+                severity: None,
+                message: "note".to_string(),
+            },
+        ];
+        assert_eq!(
+            render_findings(&findings),
+            "- **error** (line 3): bad\n- **info**: note"
+        );
+    }
+}
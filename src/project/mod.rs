@@ -1,12 +1,29 @@
 //! Project discovery for multi-project/monorepo support.
 //!
 //! This module provides functionality to discover sub-projects within a repository,
-//! supporting workspaces (Cargo workspaces, npm workspaces) and mixed-language repos.
-
-use crate::language::Language;
-use anyhow::Result;
+//! supporting workspaces (Cargo workspaces, npm workspaces, multi-module Maven and
+//! Gradle builds) and mixed-language repos.
+
+use crate::language::{Language, GRADLE_BUILD_FILES};
+use crate::repo_config::RepoConfig;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+/// Prefix of the temp directories holding repository copies
+pub const TEMP_DIR_PREFIX: &str = "noctum-";
+
+/// Maximum directory depth walked during discovery. Real projects are far
+/// shallower; deeper trees are generated output or recursive copies.
+const MAX_WALK_DEPTH: usize = 40;
+
+/// Maximum number of directory entries walked during discovery
+const MAX_WALK_ENTRIES: usize = 200_000;
+
+/// Directories never searched for repositories: dependencies and build output
+const REPOSITORY_SEARCH_SKIP_DIRS: &[&str] = &["node_modules", "target", "build", "dist", "vendor"];
+
 /// A discovered project within a repository.
 #[derive(Debug, Clone)]
 pub struct Project {
@@ -23,6 +40,62 @@ pub struct Project {
     pub project_type: ProjectType,
 }
 
+/// Cargo subcommands that accept `-p` and are commonly used as compile checks
+const SCOPABLE_CARGO_SUBCOMMANDS: &[&str] = &["check", "build", "clippy"];
+
+/// Cargo subcommands that accept `-p` and run tests
+const SCOPABLE_CARGO_TEST_SUBCOMMANDS: &[&str] = &["test"];
+
+impl Project {
+    /// Narrow a plain `cargo check`/`build`/`clippy` command to this project's
+    /// package when it is a Cargo workspace member, so a mutation only
+    /// recompiles the member (and its dependencies) instead of the whole
+    /// workspace. Commands that already select packages, chain several commands,
+    /// or aren't cargo are returned unchanged.
+    pub fn scope_build_command(&self, command: &str) -> String {
+        self.scope_cargo_command(command, SCOPABLE_CARGO_SUBCOMMANDS)
+    }
+
+    /// Narrow a plain `cargo test` command to this project's package when it is
+    /// a Cargo workspace member, so only the member's tests run against its
+    /// mutants, the same way as [`Project::scope_build_command`]
+    pub fn scope_test_command(&self, command: &str) -> String {
+        self.scope_cargo_command(command, SCOPABLE_CARGO_TEST_SUBCOMMANDS)
+    }
+
+    fn scope_cargo_command(&self, command: &str, subcommands: &[&str]) -> String {
+        let is_valid_package_name = !self.name.is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if self.language != Language::Rust
+            || self.project_type != ProjectType::WorkspaceMember
+            || !is_valid_package_name
+        {
+            return command.to_string();
+        }
+
+        let tokens: Vec<&str> = command.split_whitespace().collect();
+        let is_plain_cargo = tokens.len() >= 2
+            && tokens[0] == "cargo"
+            && subcommands.contains(&tokens[1])
+            && !command.contains(['&', '|', ';', '`', '$', '\n']);
+        let selects_packages = tokens.iter().any(|t| {
+            matches!(*t, "-p" | "--package" | "--workspace" | "--all")
+                || t.starts_with("--package=")
+                || (t.starts_with("-p") && t.len() > 2)
+        });
+        if !is_plain_cargo || selects_packages {
+            return command.to_string();
+        }
+
+        let mut scoped = vec![tokens[0], tokens[1], "-p", &self.name];
+        scoped.extend(&tokens[2..]);
+        scoped.join(" ")
+    }
+}
+
 /// The type of project structure.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProjectType {
@@ -43,8 +116,9 @@ struct MarkerFile {
 
 /// Discover all projects within a repository.
 ///
-/// Walks the directory tree looking for language marker files (Cargo.toml, package.json),
-/// parses workspace configurations, and returns a list of discovered projects.
+/// Walks the directory tree looking for language marker files (Cargo.toml, package.json,
+/// those of registered command languages), parses workspace configurations, and returns
+/// a list of discovered projects.
 ///
 /// If no projects are found, returns an empty Vec. The caller should decide how to
 /// handle repos with no detected project structure.
@@ -239,6 +313,89 @@ pub fn discover_projects(repo_path: &Path) -> Result<Vec<Project>> {
         });
     }
 
+    // Process Java/Kotlin projects (pom.xml, Gradle build files). A directory
+    // with several Gradle files is one project.
+    let mut jvm_roots: Vec<PathBuf> = markers
+        .iter()
+        .filter(|m| m.language == Language::Java)
+        .filter_map(|m| m.path.parent().map(Path::to_path_buf))
+        .collect();
+    jvm_roots.sort();
+    jvm_roots.dedup();
+
+    // First pass: identify multi-module builds and their modules
+    let mut jvm_module_paths: std::collections::HashSet<PathBuf> = std::collections::HashSet::new();
+    for project_root in &jvm_roots {
+        let Some(modules) = parse_jvm_modules(project_root) else {
+            continue;
+        };
+
+        projects.push(Project {
+            root: project_root.clone(),
+            relative_path: relative_path(&repo_path, project_root),
+            language: Language::Java,
+            name: parse_jvm_project_name(project_root)
+                .unwrap_or_else(|| directory_name(project_root)),
+            project_type: ProjectType::WorkspaceRoot,
+        });
+
+        for module in modules {
+            let module_path = project_root.join(&module);
+            if !module_path.is_dir() || !jvm_module_paths.insert(module_path.clone()) {
+                continue;
+            }
+            projects.push(Project {
+                relative_path: relative_path(&repo_path, &module_path),
+                language: Language::Java,
+                name: parse_jvm_project_name(&module_path)
+                    .unwrap_or_else(|| directory_name(&module_path)),
+                project_type: ProjectType::WorkspaceMember,
+                root: module_path,
+            });
+        }
+    }
+
+    // Second pass: add standalone Java/Kotlin projects
+    for project_root in &jvm_roots {
+        if jvm_module_paths.contains(project_root) {
+            continue;
+        }
+        let relative = relative_path(&repo_path, project_root);
+        if projects.iter().any(|p| p.relative_path == relative) {
+            continue;
+        }
+
+        projects.push(Project {
+            root: project_root.clone(),
+            relative_path: relative,
+            language: Language::Java,
+            name: parse_jvm_project_name(project_root)
+                .unwrap_or_else(|| directory_name(project_root)),
+            project_type: ProjectType::Standalone,
+        });
+    }
+
+    // Process projects of command languages, which have no workspaces: every
+    // directory with a marker file is a project of its own
+    for marker in &markers {
+        let Language::Command(_) = marker.language else {
+            continue;
+        };
+        let project_root = marker.path.parent().unwrap_or(&repo_path);
+        let relative = relative_path(&repo_path, project_root);
+        if projects.iter().any(|p| p.relative_path == relative) {
+            continue;
+        }
+
+        projects.push(Project {
+            root: project_root.to_path_buf(),
+            relative_path: relative,
+            language: marker.language,
+            name: directory_name(project_root),
+            project_type: ProjectType::Standalone,
+        });
+    }
+
     // Deduplicate projects by relative_path
     projects.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
     projects.dedup_by(|a, b| a.relative_path == b.relative_path);
@@ -264,21 +421,12 @@ pub fn discover_projects(repo_path: &Path) -> Result<Vec<Project>> {
 fn find_marker_files(repo_path: &Path) -> Result<Vec<MarkerFile>> {
     let mut markers = Vec::new();
 
-    let root_dir = repo_path.to_path_buf();
-    let skip_dirs = ["target", "node_modules", ".git", "dist", "build"];
+    let mut skip_dirs = vec!["target", "node_modules", ".git", "dist", "build", "out"];
+    for command in Language::commands() {
+        skip_dirs.extend(command.skip_dirs);
+    }
 
-    for entry in walkdir::WalkDir::new(repo_path)
-        .follow_links(false)
-        .into_iter()
-        .filter_entry(|e| {
-            // Don't filter the root directory itself (may be a temp dir starting with .)
-            if e.path() == root_dir {
-                return true;
-            }
-            let name = e.file_name().to_string_lossy();
-            !name.starts_with('.') && !skip_dirs.contains(&name.as_ref())
-        })
-    {
+    for entry in walk_source_tree(repo_path, &skip_dirs) {
         let entry = entry?;
         let path = entry.path();
 
@@ -303,6 +451,22 @@ fn find_marker_files(repo_path: &Path) -> Result<Vec<MarkerFile>> {
                 language: Language::TypeScript,
             });
         }
+
+        // Check for Java/Kotlin markers (Maven or Gradle)
+        if file_name == "pom.xml" || GRADLE_BUILD_FILES.contains(&file_name) {
+            markers.push(MarkerFile {
+                path: path.to_path_buf(),
+                language: Language::Java,
+            });
+        }
+
+        // Check for markers of command languages (e.g. mix.exs, build.zig)
+        if let Some(command) = Language::commands().find(|c| c.markers.contains(&file_name)) {
+            markers.push(MarkerFile {
+                path: path.to_path_buf(),
+                language: Language::Command(command),
+            });
+        }
     }
 
     Ok(markers)
@@ -374,6 +538,98 @@ fn parse_npm_package_name(package_json_path: &Path) -> Option<String> {
     json.get("name")?.as_str().map(String::from)
 }
 
+/// Modules of a multi-module Maven or Gradle build rooted at `project_root`,
+/// as paths relative to it. Returns None if the build has no modules.
+fn parse_jvm_modules(project_root: &Path) -> Option<Vec<String>> {
+    let modules = if let Ok(pom) = std::fs::read_to_string(project_root.join("pom.xml")) {
+        xml_elements(&pom, "module")
+    } else {
+        let settings = ["settings.gradle", "settings.gradle.kts"]
+            .iter()
+            .find_map(|file| std::fs::read_to_string(project_root.join(file)).ok())?;
+        parse_gradle_includes(&settings)
+    };
+
+    if modules.is_empty() {
+        None
+    } else {
+        Some(modules)
+    }
+}
+
+/// Project paths from the `include` statements of a Gradle settings script,
+/// e.g. `include("app", ":libs:core")` gives `app` and `libs/core`.
+fn parse_gradle_includes(settings: &str) -> Vec<String> {
+    let mut modules = Vec::new();
+    for line in settings.lines() {
+        let line = line.trim();
+        let Some(args) = line.strip_prefix("include") else {
+            continue;
+        };
+        // `include(...)` or `include '...'`, but not `includeBuild`
+        if !args.starts_with(['(', ' ', '\'', '"']) {
+            continue;
+        }
+        for part in args.split(',') {
+            let name = part
+                .trim()
+                .trim_matches(|c: char| c == '(' || c == ')' || c.is_whitespace())
+                .trim_matches(|c| c == '\'' || c == '"');
+            let path = name.trim_start_matches(':').replace(':', "/");
+            if !path.is_empty() && !path.contains(['(', ')', ' ', '$']) {
+                modules.push(path);
+            }
+        }
+    }
+    modules
+}
+
+/// Name of a Java/Kotlin project: the Maven `artifactId` or the Gradle
+/// `rootProject.name`.
+fn parse_jvm_project_name(project_root: &Path) -> Option<String> {
+    if let Ok(pom) = std::fs::read_to_string(project_root.join("pom.xml")) {
+        // The parent's artifactId comes first in poms that inherit from one
+        let own = match (pom.find("<parent>"), pom.find("</parent>")) {
+            (Some(start), Some(end)) if start < end => {
+                format!("{}{}", &pom[..start], &pom[end..])
+            }
+            _ => pom,
+        };
+        return xml_elements(&own, "artifactId").into_iter().next();
+    }
+
+    let settings = ["settings.gradle", "settings.gradle.kts"]
+        .iter()
+        .find_map(|file| std::fs::read_to_string(project_root.join(file)).ok())?;
+    settings.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("rootProject.name")?;
+        let value = value.trim().strip_prefix('=')?.trim();
+        let name = value.trim_matches(|c| c == '\'' || c == '"');
+        (!name.is_empty()).then(|| name.to_string())
+    })
+}
+
+/// Text of every `<tag>...</tag>` element in an XML document. Enough for the
+/// flat elements of a pom, without pulling in an XML parser.
+fn xml_elements(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        let value = rest[..end].trim();
+        if !value.is_empty() {
+            values.push(value.to_string());
+        }
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
+
 /// Resolve a workspace member glob pattern to actual paths.
 fn resolve_workspace_glob(workspace_root: &Path, pattern: &str) -> Vec<PathBuf> {
     let full_pattern = workspace_root.join(pattern);
@@ -393,6 +649,186 @@ fn resolve_workspace_glob(workspace_root: &Path, pattern: &str) -> Vec<PathBuf>
     }
 }
 
+/// Walk a source tree for discovery.
+///
+/// Hidden directories, `skip_dirs` and Noctum's own temp copies are skipped, and
+/// symlinks are not followed so link cycles can't recurse. The walk is capped in
+/// depth and entry count, so pointing Noctum at a huge tree (e.g. a home
+/// directory) can't stall the daemon.
+pub fn walk_source_tree<'a>(
+    root: &'a Path,
+    skip_dirs: &'a [&'a str],
+) -> impl Iterator<Item = walkdir::Result<walkdir::DirEntry>> + 'a {
+    walkdir::WalkDir::new(root)
+        .follow_links(false)
+        .max_depth(MAX_WALK_DEPTH)
+        .into_iter()
+        .filter_entry(move |e| {
+            // Don't filter the root directory itself (may be a temp dir starting with .)
+            if e.path() == root {
+                return true;
+            }
+            let name = e.file_name().to_string_lossy();
+            let is_temp_copy = e.file_type().is_dir() && is_noctum_temp_dir(e.path());
+            !name.starts_with('.') && !skip_dirs.contains(&name.as_ref()) && !is_temp_copy
+        })
+        .enumerate()
+        .map_while(move |(count, entry)| {
+            if count == MAX_WALK_ENTRIES {
+                tracing::warn!(
+                    "Stopped walking {} after {} entries; exclude large directories with copy_ignore",
+                    root.display(),
+                    MAX_WALK_ENTRIES
+                );
+                return None;
+            }
+            Some(entry)
+        })
+}
+
+/// Whether `path` is one of Noctum's own temp copies (`$TMPDIR/noctum-*`).
+pub fn is_noctum_temp_dir(path: &Path) -> bool {
+    let prefixed = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with(TEMP_DIR_PREFIX));
+    if !prefixed {
+        return false;
+    }
+
+    let temp_dir = std::env::temp_dir();
+    let temp_dir = temp_dir.canonicalize().unwrap_or(temp_dir);
+    path.parent()
+        .and_then(|parent| parent.canonicalize().ok())
+        .is_some_and(|parent| parent == temp_dir)
+}
+
+/// Whether `path` is, or is inside, one of Noctum's own temp copies.
+pub fn is_inside_noctum_temp_dir(path: &Path) -> bool {
+    path.ancestors().any(is_noctum_temp_dir)
+}
+
+/// Resolve the path of a repository being added: it must be an existing
+/// directory outside Noctum's data directory and temp copies, since analyzing
+/// those would feed on itself. Returns the canonical path, so the same
+/// repository is always stored the same way.
+pub fn resolve_repository_path(path: &Path, data_dir: &Path) -> Result<PathBuf> {
+    if !path.exists() {
+        anyhow::bail!("Path does not exist");
+    }
+    if !path.is_dir() {
+        anyhow::bail!("Path must be a directory");
+    }
+    let canonical = path.canonicalize().map_err(|e| {
+        tracing::warn!("Failed to canonicalize path {}: {}", path.display(), e);
+        anyhow::anyhow!("Failed to resolve path")
+    })?;
+
+    let data_dir = data_dir
+        .canonicalize()
+        .unwrap_or_else(|_| data_dir.to_path_buf());
+    if is_inside_noctum_temp_dir(&canonical) || canonical.starts_with(&data_dir) {
+        anyhow::bail!("Path is inside Noctum's data directory or one of its temp copies");
+    }
+    Ok(canonical)
+}
+
+/// Whether `repo_path` is the source of the running Noctum binary: either it
+/// contains the executable (e.g. `target/release/noctum`) or it is the crate
+/// directory the binary was built from.
+pub fn is_running_binary_source(repo_path: &Path) -> bool {
+    let Ok(repo_path) = repo_path.canonicalize() else {
+        return false;
+    };
+
+    let contains_exe = std::env::current_exe()
+        .and_then(|exe| exe.canonicalize())
+        .is_ok_and(|exe| exe.starts_with(&repo_path));
+    let is_build_source = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .canonicalize()
+        .is_ok_and(|source| source == repo_path);
+
+    contains_exe || is_build_source
+}
+
+/// A repository found by [`find_repositories`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FoundRepository {
+    /// Canonical path of the repository
+    pub path: PathBuf,
+    /// Suggested display name: the directory name, or the path relative to the
+    /// searched directory when several repositories share a directory name
+    pub name: String,
+    /// Languages of the projects found in it
+    pub languages: Vec<String>,
+    /// Whether it contains the noctum.toml that adding it requires
+    pub configured: bool,
+}
+
+/// Find the repositories in `parent`, up to `max_depth` levels down: Git
+/// repositories, directories with a noctum.toml and project roots (e.g. with a
+/// Cargo.toml). The search doesn't descend into the repositories it finds,
+/// hidden directories or dependency and build directories.
+pub fn find_repositories(parent: &Path, max_depth: usize) -> Result<Vec<FoundRepository>> {
+    let parent = parent
+        .canonicalize()
+        .with_context(|| format!("Cannot read {}", parent.display()))?;
+    anyhow::ensure!(parent.is_dir(), "{} is not a directory", parent.display());
+
+    let mut found = Vec::new();
+    let mut walker = walkdir::WalkDir::new(&parent)
+        .follow_links(false)
+        .min_depth(1)
+        .max_depth(max_depth)
+        .sort_by_file_name()
+        .into_iter();
+    while let Some(entry) = walker.next() {
+        let Ok(entry) = entry else { continue };
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy();
+        if name.starts_with('.')
+            || REPOSITORY_SEARCH_SKIP_DIRS.contains(&name.as_ref())
+            || is_noctum_temp_dir(path)
+        {
+            walker.skip_current_dir();
+            continue;
+        }
+
+        let configured = RepoConfig::exists(path);
+        if !configured && !path.join(".git").exists() && Language::detect(path).is_none() {
+            continue;
+        }
+        walker.skip_current_dir();
+
+        let mut languages: Vec<String> = Vec::new();
+        for project in discover_projects(path).unwrap_or_default() {
+            let language = project.language.name().to_string();
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+        found.push(FoundRepository {
+            path: path.to_path_buf(),
+            name: directory_name(path),
+            languages,
+            configured,
+        });
+    }
+
+    let mut names: HashMap<String, usize> = HashMap::new();
+    for repo in &found {
+        *names.entry(repo.name.clone()).or_default() += 1;
+    }
+    for repo in &mut found {
+        if names[&repo.name] > 1 {
+            repo.name = relative_path(&parent, &repo.path);
+        }
+    }
+    Ok(found)
+}
+
 /// Get the relative path from repo root to a directory.
 fn relative_path(repo_root: &Path, path: &Path) -> String {
     path.strip_prefix(repo_root)
@@ -413,6 +849,156 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_noctum_temp_dirs() {
+        let copy = tempfile::Builder::new()
+            .prefix(TEMP_DIR_PREFIX)
+            .tempdir()
+            .unwrap();
+        assert!(is_noctum_temp_dir(copy.path()));
+        assert!(is_inside_noctum_temp_dir(&copy.path().join("src")));
+
+        // Directories that merely share the prefix elsewhere are walked
+        let repo = TempDir::new().unwrap();
+        let nested = repo.path().join("noctum-plugin");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert!(!is_noctum_temp_dir(&nested));
+        assert!(!is_inside_noctum_temp_dir(&nested));
+        assert!(walk_source_tree(repo.path(), &[])
+            .filter_map(|e| e.ok())
+            .any(|e| e.path() == nested));
+    }
+
+    #[test]
+    fn test_find_repositories() {
+        let root = TempDir::new().unwrap();
+        let dir = |path: &str| {
+            let path = root.path().join(path);
+            std::fs::create_dir_all(&path).unwrap();
+            path
+        };
+        let app = dir("app/.git").parent().unwrap().to_path_buf();
+        std::fs::write(
+            app.join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        std::fs::write(app.join("noctum.toml"), "").unwrap();
+        dir("app/vendored/.git");
+        std::fs::write(dir("web").join("package.json"), "{}").unwrap();
+        dir("work/api/.git");
+        dir("personal/api/.git");
+        dir("node_modules/left-pad/.git");
+        dir(".cache/tool/.git");
+        dir("notes/drafts");
+
+        let found = find_repositories(root.path(), 2).unwrap();
+        let names: Vec<_> = found.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["app", "personal/api", "web", "work/api"]);
+        assert_eq!(found[0].path, app.canonicalize().unwrap());
+        assert_eq!(found[0].languages, ["Rust"]);
+        assert!(found[0].configured);
+        assert!(!found[1].configured);
+
+        let names: Vec<_> = find_repositories(root.path(), 1)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.name)
+            .collect();
+        assert_eq!(names, ["app", "web"]);
+        assert!(find_repositories(&root.path().join("missing"), 2).is_err());
+    }
+
+    #[test]
+    fn test_resolve_repository_path() {
+        let root = TempDir::new().unwrap();
+        let repo = root.path().join("app");
+        let data_dir = root.path().join("data");
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::fs::write(repo.join("Cargo.toml"), "").unwrap();
+
+        let resolved = resolve_repository_path(&repo.join("src/.."), &data_dir).unwrap();
+        assert_eq!(resolved, repo.canonicalize().unwrap());
+
+        let err = |path: &Path| {
+            resolve_repository_path(path, &data_dir)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(err(&root.path().join("missing")), "Path does not exist");
+        assert_eq!(err(&repo.join("Cargo.toml")), "Path must be a directory");
+        assert!(err(&data_dir).contains("data directory"));
+    }
+
+    #[test]
+    fn test_scope_build_command() {
+        let member = Project {
+            root: PathBuf::from("/ws/crates/core"),
+            relative_path: "crates/core".to_string(),
+            language: Language::Rust,
+            name: "my-core".to_string(),
+            project_type: ProjectType::WorkspaceMember,
+        };
+        assert_eq!(
+            member.scope_build_command("cargo check"),
+            "cargo check -p my-core"
+        );
+        assert_eq!(
+            member.scope_build_command("cargo build --tests --offline"),
+            "cargo build -p my-core --tests --offline"
+        );
+        for unchanged in [
+            "cargo check --workspace",
+            "cargo check -p other",
+            "cargo check --package=other",
+            "cargo check && cargo test",
+            "cargo test",
+            "make check",
+        ] {
+            assert_eq!(member.scope_build_command(unchanged), unchanged);
+        }
+
+        let standalone = Project {
+            project_type: ProjectType::Standalone,
+            ..member
+        };
+        assert_eq!(standalone.scope_build_command("cargo check"), "cargo check");
+    }
+
+    #[test]
+    fn test_scope_test_command() {
+        let member = Project {
+            root: PathBuf::from("/ws/crates/core"),
+            relative_path: "crates/core".to_string(),
+            language: Language::Rust,
+            name: "my-core".to_string(),
+            project_type: ProjectType::WorkspaceMember,
+        };
+        assert_eq!(
+            member.scope_test_command("cargo test --offline"),
+            "cargo test -p my-core --offline"
+        );
+        for unchanged in ["cargo test --workspace", "cargo check", "npm test"] {
+            assert_eq!(member.scope_test_command(unchanged), unchanged);
+        }
+
+        let standalone = Project {
+            project_type: ProjectType::Standalone,
+            ..member
+        };
+        assert_eq!(standalone.scope_test_command("cargo test"), "cargo test");
+    }
+
+    #[test]
+    fn test_is_running_binary_source() {
+        assert!(is_running_binary_source(Path::new(env!(
+            "CARGO_MANIFEST_DIR"
+        ))));
+        let other = TempDir::new().unwrap();
+        assert!(!is_running_binary_source(other.path()));
+    }
+
     fn create_cargo_toml(dir: &Path, name: &str, is_workspace: bool, members: &[&str]) {
         let mut content = String::new();
 
@@ -504,6 +1090,128 @@ edition = "2021"
         assert_eq!(projects[0].relative_path, "backend");
     }
 
+    #[test]
+    fn test_discover_maven_multi_module_build() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join("pom.xml"),
+            r#"<project>
+  <groupId>com.example</groupId>
+  <artifactId>shop-parent</artifactId>
+  <packaging>pom</packaging>
+  <modules>
+    <module>api</module>
+    <module>core</module>
+  </modules>
+</project>"#,
+        )
+        .unwrap();
+        for module in ["api", "core"] {
+            let dir = temp.path().join(module);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(
+                dir.join("pom.xml"),
+                format!(
+                    "<project><parent><artifactId>shop-parent</artifactId></parent>\
+                     <artifactId>shop-{}</artifactId></project>",
+                    module
+                ),
+            )
+            .unwrap();
+        }
+
+        let projects = discover_projects(temp.path()).unwrap();
+
+        assert_eq!(projects.len(), 3);
+        assert!(projects.iter().all(|p| p.language == Language::Java));
+        assert_eq!(projects[0].name, "shop-parent");
+        assert_eq!(projects[0].project_type, ProjectType::WorkspaceRoot);
+        assert_eq!(projects[1].name, "shop-api");
+        assert_eq!(projects[1].relative_path, "api");
+        assert_eq!(projects[1].project_type, ProjectType::WorkspaceMember);
+        assert_eq!(projects[2].name, "shop-core");
+    }
+
+    #[test]
+    fn test_discover_gradle_multi_project_build() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join("settings.gradle.kts"),
+            "rootProject.name = \"shop\"\ninclude(\"app\", \":libs:core\")\nincludeBuild(\"plugins\")\n",
+        )
+        .unwrap();
+        std::fs::write(temp.path().join("build.gradle.kts"), "").unwrap();
+        std::fs::create_dir_all(temp.path().join("app")).unwrap();
+        std::fs::write(temp.path().join("app/build.gradle.kts"), "").unwrap();
+        std::fs::create_dir_all(temp.path().join("libs/core")).unwrap();
+        // A standalone Gradle project elsewhere in the repo
+        std::fs::create_dir_all(temp.path().join("tools/gen")).unwrap();
+        std::fs::write(temp.path().join("tools/gen/build.gradle"), "").unwrap();
+
+        let projects = discover_projects(temp.path()).unwrap();
+        let summary: Vec<_> = projects
+            .iter()
+            .map(|p| (p.relative_path.as_str(), p.name.as_str(), p.project_type))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("", "shop", ProjectType::WorkspaceRoot),
+                ("app", "app", ProjectType::WorkspaceMember),
+                ("libs/core", "core", ProjectType::WorkspaceMember),
+                ("tools/gen", "gen", ProjectType::Standalone),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discover_command_language_projects() {
+        // The only test registering command languages, since they're global
+        crate::language::register_command_languages(&[crate::config::LanguageConfig {
+            name: "Zig".to_string(),
+            extensions: vec!["zig".to_string()],
+            markers: vec!["build.zig".to_string()],
+            skip_dirs: vec!["zig-cache".to_string()],
+            ..Default::default()
+        }]);
+
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("build.zig"), "").unwrap();
+        std::fs::create_dir_all(temp.path().join("tools/fmt")).unwrap();
+        std::fs::write(temp.path().join("tools/fmt/build.zig"), "").unwrap();
+        // Markers in skipped directories are ignored
+        std::fs::create_dir_all(temp.path().join("zig-cache/pkg")).unwrap();
+        std::fs::write(temp.path().join("zig-cache/pkg/build.zig"), "").unwrap();
+
+        let language = Language::detect(temp.path()).unwrap();
+        assert_eq!(language.name(), "Zig");
+        assert_eq!(language.file_extensions(), ["zig"]);
+
+        let projects = discover_projects(temp.path()).unwrap();
+        let summary: Vec<_> = projects
+            .iter()
+            .map(|p| (p.relative_path.as_str(), p.language, p.project_type))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("", language, ProjectType::Standalone),
+                ("tools/fmt", language, ProjectType::Standalone),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_gradle_includes() {
+        assert_eq!(
+            parse_gradle_includes(
+                "include 'app', ':libs:core'\n// include 'old'\nincludeBuild 'x'"
+            ),
+            vec!["app".to_string(), "libs/core".to_string()]
+        );
+    }
+
     #[test]
     fn test_parse_cargo_workspace_members() {
         let temp = TempDir::new().unwrap();
@@ -0,0 +1,331 @@
+//! Named queries for scripting.
+//!
+//! `noctum query` and `/api/query` expose a fixed allow-list of read-only queries
+//! over the result database, so scripts can extract data (e.g. "files with error
+//! findings from the last week") without depending on the internal schema.
+//! Parameters are passed as `key=value` pairs and are always bound, never
+//! interpolated into SQL.
+
+use crate::db::{Database, QueryFilter};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Default maximum number of rows returned
+const DEFAULT_LIMIT: u32 = 100;
+
+/// Upper bound for the `limit` parameter
+const MAX_LIMIT: u32 = 10_000;
+
+/// Description of a named query
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Accepted parameters
+    pub params: &'static [&'static str],
+}
+
+/// The allow-list of named queries
+pub const QUERIES: &[QueryInfo] = &[
+    QueryInfo {
+        name: "repositories",
+        description: "Configured repositories",
+        params: &[],
+    },
+    QueryInfo {
+        name: "findings",
+        description: "Analysis results, newest first",
+        params: &[
+            "repository",
+            "severity",
+            "analysis_type",
+            "model",
+            "since_days",
+            "limit",
+        ],
+    },
+    QueryInfo {
+        name: "mutations",
+        description: "Mutation test results, newest first",
+        params: &["repository", "outcome", "model", "since_days", "limit"],
+    },
+    QueryInfo {
+        name: "mutation_scores",
+        description: "Mutation testing summary and score per repository",
+        params: &["repository"],
+    },
+];
+
+/// A validated named query, ready to run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub name: &'static str,
+    pub filter: QueryFilter,
+}
+
+impl Query {
+    /// Validate a query name and its parameters against the allow-list
+    pub fn parse(name: &str, params: &BTreeMap<String, String>) -> Result<Self> {
+        let info = QUERIES.iter().find(|q| q.name == name).with_context(|| {
+            format!(
+                "Unknown query '{}'. Available queries: {}",
+                name,
+                QUERIES
+                    .iter()
+                    .map(|q| q.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+
+        if let Some(key) = params.keys().find(|k| !info.params.contains(&k.as_str())) {
+            anyhow::bail!(
+                "Unknown parameter '{}' for query '{}'. Accepted parameters: {}",
+                key,
+                name,
+                if info.params.is_empty() {
+                    "none".to_string()
+                } else {
+                    info.params.join(", ")
+                }
+            );
+        }
+
+        let limit = match params.get("limit") {
+            Some(value) => parse_number("limit", value)?.min(MAX_LIMIT),
+            None => DEFAULT_LIMIT,
+        };
+        let since_days = params
+            .get("since_days")
+            .map(|value| parse_number("since_days", value))
+            .transpose()?;
+
+        Ok(Self {
+            name: info.name,
+            filter: QueryFilter {
+                repository: params.get("repository").cloned(),
+                severity: params.get("severity").cloned(),
+                analysis_type: params.get("analysis_type").cloned(),
+                outcome: params.get("outcome").cloned(),
+                model: params.get("model").cloned(),
+                since_days,
+                limit,
+            },
+        })
+    }
+
+    /// Run the query and return its rows as a JSON array
+    pub async fn run(&self, db: &Database) -> Result<serde_json::Value> {
+        let rows = match self.name {
+            "repositories" => serde_json::to_value(db.get_repositories().await?)?,
+            "findings" => serde_json::to_value(db.query_findings(&self.filter).await?)?,
+            "mutations" => serde_json::to_value(db.query_mutations(&self.filter).await?)?,
+            "mutation_scores" => {
+                let mut scores = Vec::new();
+                for repo in db.get_repositories().await? {
+                    if self
+                        .filter
+                        .repository
+                        .as_ref()
+                        .is_some_and(|name| *name != repo.name)
+                    {
+                        continue;
+                    }
+
+                    let summary = db.get_mutation_summary(repo.id).await?;
+                    scores.push(serde_json::json!({
+                        "repository": repo.name,
+                        "total": summary.total,
+                        "killed": summary.killed,
+                        "survived": summary.survived,
+                        "timeout": summary.timeout,
+                        "compile_error": summary.compile_error,
+                        "rejected": summary.rejected,
+                        "covered_survived": summary.covered_survived,
+                        "mutation_score": summary.mutation_score(),
+                    }));
+                }
+                serde_json::Value::Array(scores)
+            }
+            other => anyhow::bail!("Query '{}' is not implemented", other),
+        };
+
+        Ok(rows)
+    }
+}
+
+fn parse_number(key: &str, value: &str) -> Result<u32> {
+    value
+        .parse()
+        .with_context(|| format!("Parameter '{}' must be a non-negative integer", key))
+}
+
+/// Parse `key=value` command line arguments into query parameters
+pub fn parse_params(args: &[String]) -> Result<BTreeMap<String, String>> {
+    args.iter()
+        .map(|arg| {
+            let (key, value) = arg
+                .split_once('=')
+                .with_context(|| format!("Expected key=value, got '{}'", arg))?;
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Attribution;
+    use tempfile::TempDir;
+
+    fn params(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    async fn create_test_db() -> (Database, i64, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+
+        let repo_dir = temp_dir.path().join("myapp");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        std::fs::write(repo_dir.join("noctum.toml"), "").unwrap();
+        let repo_id = db
+            .add_repository(&repo_dir.to_string_lossy(), "myapp")
+            .await
+            .unwrap();
+
+        (db, repo_id, temp_dir)
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let args = vec!["severity=error".to_string(), "since_days = 7".to_string()];
+        assert_eq!(
+            parse_params(&args).unwrap(),
+            params(&[("severity", "error"), ("since_days", "7")])
+        );
+        assert!(parse_params(&["severity".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_query_defaults() {
+        let query = Query::parse("findings", &BTreeMap::new()).unwrap();
+        assert_eq!(query.name, "findings");
+        assert_eq!(query.filter.limit, DEFAULT_LIMIT);
+        assert_eq!(query.filter.since_days, None);
+    }
+
+    #[test]
+    fn test_parse_query_params() {
+        let query = Query::parse(
+            "findings",
+            &params(&[
+                ("severity", "error"),
+                ("since_days", "7"),
+                ("limit", "999999"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(query.filter.severity.as_deref(), Some("error"));
+        assert_eq!(query.filter.since_days, Some(7));
+        assert_eq!(query.filter.limit, MAX_LIMIT);
+    }
+
+    #[test]
+    fn test_parse_query_rejects_unknown() {
+        assert!(Query::parse("drop_tables", &BTreeMap::new()).is_err());
+        assert!(Query::parse("repositories", &params(&[("severity", "error")])).is_err());
+        assert!(Query::parse("mutations", &params(&[("since_days", "-1")])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_findings_query() {
+        let (db, repo_id, _temp) = create_test_db().await;
+        db.save_analysis_result(
+            repo_id,
+            "/a.rs",
+            "code_understanding",
+            "bug",
+            Some("error"),
+            None,
+            &Attribution {
+                endpoint: Some("local".to_string()),
+                model: Some("llama3".to_string()),
+                duration_ms: Some(1200),
+            },
+        )
+        .await
+        .unwrap();
+        db.save_analysis_result(
+            repo_id,
+            "/b.rs",
+            "code_understanding",
+            "ok",
+            Some("info"),
+            None,
+            &Attribution::default(),
+        )
+        .await
+        .unwrap();
+
+        let query = Query::parse(
+            "findings",
+            &params(&[("severity", "error"), ("since_days", "7")]),
+        )
+        .unwrap();
+        let rows = query.run(&db).await.unwrap();
+        let rows = rows.as_array().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["file_path"], "/a.rs");
+        assert_eq!(rows[0]["repository"], "myapp");
+        assert_eq!(rows[0]["endpoint"], "local");
+        assert_eq!(rows[0]["duration_ms"], 1200);
+
+        let query = Query::parse("findings", &params(&[("model", "llama3")])).unwrap();
+        assert_eq!(query.run(&db).await.unwrap().as_array().unwrap().len(), 1);
+
+        let query = Query::parse("findings", &params(&[("repository", "other")])).unwrap();
+        assert_eq!(query.run(&db).await.unwrap(), serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_run_mutation_queries() {
+        let (db, repo_id, _temp) = create_test_db().await;
+        for outcome in ["killed", "survived", "survived"] {
+            db.save_mutation_result(
+                repo_id,
+                "/a.rs",
+                "desc",
+                "why",
+                &[],
+                outcome,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let query = Query::parse("mutations", &params(&[("outcome", "survived")])).unwrap();
+        assert_eq!(query.run(&db).await.unwrap().as_array().unwrap().len(), 2);
+
+        let query = Query::parse("mutation_scores", &BTreeMap::new()).unwrap();
+        let rows = query.run(&db).await.unwrap();
+        assert_eq!(rows[0]["repository"], "myapp");
+        assert_eq!(rows[0]["survived"], 2);
+        assert_eq!(rows[0]["killed"], 1);
+    }
+}
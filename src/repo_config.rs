@@ -15,8 +15,12 @@
 //!
 //! If these checks fail, the config file is rejected and a warning is logged.
 
+use crate::db::CommandOverrides;
+use crate::file_filter::{Detected, Detection, Globs};
+use crate::issues::IssueProvider;
+use crate::mutation::{CompileFixConfig, MutationStrategy};
 use serde::Deserialize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Result of validating a config file's security properties.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -111,6 +115,11 @@ pub struct RepoConfig {
     #[serde(default)]
     pub enable_code_analysis: bool,
 
+    /// Update the previous code analysis of a changed file from its diff instead of
+    /// re-analyzing it from scratch, and record a per-file change log. Default: false.
+    #[serde(default)]
+    pub delta_analysis: bool,
+
     /// Enable architecture analysis (Architecture summary). Default: false.
     #[serde(default)]
     pub enable_architecture_analysis: bool,
@@ -124,6 +133,14 @@ pub struct RepoConfig {
     #[serde(default)]
     pub enable_mutation_testing: bool,
 
+    /// Enable duplicate code detection across files (Duplication tab). Default: false.
+    #[serde(default)]
+    pub enable_duplication_detection: bool,
+
+    /// Enable unused dependency and export detection. Default: false.
+    #[serde(default)]
+    pub enable_dead_code_detection: bool,
+
     /// Glob patterns for files/directories to exclude when copying to temp directory.
     /// Useful for excluding `node_modules`, build artifacts, or other large directories
     /// that would be regenerated by the build command anyway.
@@ -136,18 +153,213 @@ pub struct RepoConfig {
     #[serde(default)]
     pub setup_command: Option<String>,
 
+    /// Install JavaScript dependencies (`npm ci`, `pnpm install --frozen-lockfile` or
+    /// `yarn install --frozen-lockfile`, chosen by lockfile) in the temp copy before
+    /// baseline verification. Installs are cached by lockfile hash. Default: false.
+    #[serde(default)]
+    pub install_dependencies: bool,
+
+    /// Glob patterns (relative to the repository root) of source files to analyze.
+    /// When empty, all source files are included.
+    /// Example: `["src/**"]`
+    #[serde(default)]
+    pub include_globs: Vec<String>,
+
+    /// Glob patterns of source files to skip in both analysis and mutation testing.
+    /// Example: `["**/fixtures/**", "*_generated.rs"]`
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
+
+    /// Analyze third-party code too: files under `vendor/`, `third_party/` and the
+    /// like, and bundled `*.min.js`, which are skipped otherwise. Default: false.
+    #[serde(default)]
+    pub analyze_vendored: bool,
+
+    /// Analyze generator output too: files named like `*.pb.rs` or `*.generated.ts`,
+    /// files with a `@generated` or `DO NOT EDIT` header comment, and data-like
+    /// files with extremely repetitive content, which are skipped otherwise.
+    /// Default: false.
+    #[serde(default)]
+    pub analyze_generated: bool,
+
+    /// Glob patterns of files to analyze even though they look vendored or generated.
+    /// Example: `["vendor/our-fork/**"]`
+    #[serde(default)]
+    pub keep_globs: Vec<String>,
+
+    /// WASM analyzer plugins to run on every source file, relative to the repository root.
+    /// Example: `["tools/license-check.wasm"]`
+    #[serde(default)]
+    pub plugins: Vec<PathBuf>,
+
     /// Mutation testing configuration.
     #[serde(default)]
     pub mutation: MutationRepoConfig,
+
+    /// Repository-level daemon behavior.
+    #[serde(default)]
+    pub repository: RepositorySection,
+
+    /// Issue tracker import.
+    #[serde(default)]
+    pub issues: IssuesSection,
+
+    /// Project context for code analysis.
+    #[serde(default)]
+    pub context: ContextSection,
 }
 
-/// Mutation testing configuration section.
+/// `[repository]` section of `noctum.toml`.
 #[derive(Debug, Clone, Default, Deserialize)]
+pub struct RepositorySection {
+    /// Watch the repository for file changes and analyze changed files as soon as
+    /// they settle, outside the scheduled window. Only code analysis runs on
+    /// changes; aggregation and mutation testing wait for the scheduled scan.
+    /// Default: false.
+    #[serde(default)]
+    pub watch: bool,
+}
+
+/// `[issues]` section of `noctum.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IssuesSection {
+    /// Fetch the project's open issues on every scan and use them as context for
+    /// code and architecture analysis. Default: false.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// `github` or `gitlab`. Detected from the `origin` remote when not set.
+    #[serde(default)]
+    pub provider: Option<IssueProvider>,
+
+    /// `owner/repo` (GitHub) or the project path (GitLab). Detected from the
+    /// `origin` remote when not set.
+    #[serde(default)]
+    pub project: Option<String>,
+
+    /// Labels added to issues created from findings.
+    #[serde(default)]
+    pub labels: Vec<String>,
+
+    /// Title of issues created from findings. Placeholders: `{summary}`, `{file}`,
+    /// `{repository}`, `{source}`, `{severity}`. Default: `"{summary} in {file}"`.
+    #[serde(default)]
+    pub title_template: Option<String>,
+
+    /// Markdown body of issues created from findings; same placeholders as the
+    /// title plus `{details}` (the full result).
+    #[serde(default)]
+    pub body_template: Option<String>,
+}
+
+/// `[context]` section of `noctum.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContextSection {
+    /// Give code analysis the architecture summary, the parent module's
+    /// architecture analysis and the signatures of imported project files.
+    /// Default: false.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Approximate number of tokens the context may add to each prompt.
+    /// Default: 1500.
+    #[serde(default = "default_context_max_tokens")]
+    pub max_tokens: usize,
+}
+
+fn default_context_max_tokens() -> usize {
+    1500
+}
+
+impl Default for ContextSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_tokens: default_context_max_tokens(),
+        }
+    }
+}
+
+/// Mutation testing configuration section.
+#[derive(Debug, Clone, Deserialize)]
 pub struct MutationRepoConfig {
     /// Rules for matching files to build/test commands.
     /// Rules are evaluated in order; the first matching glob wins.
     #[serde(default)]
     pub rules: Vec<MutationRule>,
+    /// How mutations are generated: `llm` (default), `deterministic` or `hybrid`.
+    #[serde(default)]
+    pub strategy: MutationStrategy,
+    /// Number of mutations tested concurrently, each in its own copy of the
+    /// repository. Default: 1 (sequential).
+    #[serde(default = "default_parallelism")]
+    pub parallelism: usize,
+    /// Only mutate the functions changed since the commit a file was last
+    /// tested at, instead of the whole file. Default: true.
+    #[serde(default = "default_changed_only")]
+    pub changed_only: bool,
+    /// Line coverage used to skip mutations on lines no test executes.
+    #[serde(default)]
+    pub coverage: MutationCoverageConfig,
+    /// `[mutation.compile_fix]`: retrying mutations that fail to compile.
+    #[serde(default)]
+    pub compile_fix: CompileFixConfig,
+}
+
+/// `[mutation.coverage]` section of `noctum.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MutationCoverageConfig {
+    /// Load line coverage before mutation testing. Default: false.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Command that writes an lcov file, run in the temp copy after baseline
+    /// verification. Defaults to `cargo llvm-cov --lcov --output-path lcov.info`
+    /// unless `lcov_path` points at an existing file.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// lcov file relative to the repository root. Without `command`, an existing
+    /// file is read from the repository instead of running anything.
+    #[serde(default)]
+    pub lcov_path: Option<PathBuf>,
+    /// Timeout in seconds for the coverage command (defaults to 1800).
+    #[serde(default = "default_coverage_timeout")]
+    pub timeout_seconds: u64,
+}
+
+fn default_coverage_timeout() -> u64 {
+    1800
+}
+
+impl Default for MutationCoverageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: None,
+            lcov_path: None,
+            timeout_seconds: default_coverage_timeout(),
+        }
+    }
+}
+
+fn default_parallelism() -> usize {
+    1
+}
+
+fn default_changed_only() -> bool {
+    true
+}
+
+impl Default for MutationRepoConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            strategy: MutationStrategy::default(),
+            parallelism: default_parallelism(),
+            changed_only: default_changed_only(),
+            coverage: MutationCoverageConfig::default(),
+            compile_fix: CompileFixConfig::default(),
+        }
+    }
 }
 
 /// A single mutation testing rule that maps a glob pattern to commands.
@@ -188,6 +400,23 @@ impl MutationRule {
         }
         true
     }
+
+    /// This rule with the commands and timeout replaced by those `overrides`
+    /// sets
+    pub fn with_overrides(&self, overrides: &CommandOverrides) -> MutationRule {
+        MutationRule {
+            build_command: overrides
+                .compile_command
+                .clone()
+                .unwrap_or_else(|| self.build_command.clone()),
+            test_command: overrides
+                .test_command
+                .clone()
+                .unwrap_or_else(|| self.test_command.clone()),
+            timeout_seconds: overrides.timeout_seconds.unwrap_or(self.timeout_seconds),
+            ..self.clone()
+        }
+    }
 }
 
 impl RepoConfig {
@@ -225,6 +454,18 @@ impl RepoConfig {
         Self::load_internal(repo_path, false)
     }
 
+    /// Build the file filter from `include_globs`, `exclude_globs` and the
+    /// vendored/generated file detection settings.
+    pub fn file_filter(&self) -> anyhow::Result<FileFilter> {
+        let mut filter = FileFilter::new(&self.include_globs, &self.exclude_globs)?;
+        filter.detection = Detection {
+            vendored: !self.analyze_vendored,
+            generated: !self.analyze_generated,
+            keep: Globs::new(&self.keep_globs)?,
+        };
+        Ok(filter)
+    }
+
     fn load_internal(repo_path: &Path, check_security: bool) -> Option<Self> {
         let config_path = repo_path.join("noctum.toml");
         if !config_path.exists() {
@@ -283,6 +524,40 @@ impl RepoConfig {
     }
 }
 
+/// Compiled `include_globs`/`exclude_globs` of a repository, and which files
+/// are skipped as vendored or generated.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    include: Globs,
+    exclude: Globs,
+    detection: Detection,
+}
+
+impl FileFilter {
+    /// Compile include and exclude patterns. Fails if any pattern is invalid.
+    pub fn new(include: &[String], exclude: &[String]) -> anyhow::Result<Self> {
+        Ok(Self {
+            include: Globs::new(include)?,
+            exclude: Globs::new(exclude)?,
+            detection: Detection::default(),
+        })
+    }
+
+    /// Check whether a file (path relative to the repository root) should be analyzed.
+    pub fn matches(&self, relative_path: &Path) -> bool {
+        if self.exclude.is_match(relative_path) {
+            return false;
+        }
+        self.include.is_empty() || self.include.is_match(relative_path)
+    }
+
+    /// Whether a file that passed [`matches`](Self::matches) is vendored or
+    /// generated, judging by its path and content.
+    pub fn detect(&self, relative_path: &Path, content: &str) -> Option<Detected> {
+        self.detection.detect(relative_path, content)
+    }
+}
+
 impl MutationRepoConfig {
     /// Find the first rule matching the given file path.
     ///
@@ -392,6 +667,7 @@ timeout_seconds = 600
                 test_command: "cargo test".to_string(),
                 timeout_seconds: 300,
             }],
+            ..Default::default()
         };
 
         assert!(config.find_rule("src/main.rs").is_some());
@@ -410,6 +686,7 @@ timeout_seconds = 600
                 test_command: "npm test".to_string(),
                 timeout_seconds: 300,
             }],
+            ..Default::default()
         };
 
         assert!(config.find_rule("packages/frontend/src/App.tsx").is_some());
@@ -439,6 +716,7 @@ timeout_seconds = 600
                     timeout_seconds: 300,
                 },
             ],
+            ..Default::default()
         };
 
         // Should match first rule
@@ -460,6 +738,7 @@ timeout_seconds = 600
                 test_command: "cargo test".to_string(),
                 timeout_seconds: 300,
             }],
+            ..Default::default()
         };
 
         assert!(config.find_rule("src/main.py").is_none());
@@ -468,7 +747,10 @@ timeout_seconds = 600
 
     #[test]
     fn test_find_rule_empty_rules() {
-        let config = MutationRepoConfig { rules: vec![] };
+        let config = MutationRepoConfig {
+            rules: vec![],
+            ..Default::default()
+        };
         assert!(config.find_rule("any/file.rs").is_none());
     }
 
@@ -550,6 +832,102 @@ enable_mutation_testing = true
 
         let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
         assert!(config.copy_ignore.is_empty());
+        assert!(config.plugins.is_empty());
+    }
+
+    #[test]
+    fn test_file_filter_exclude() {
+        let filter = FileFilter::new(
+            &[],
+            &["**/fixtures/**".to_string(), "*_generated.rs".to_string()],
+        )
+        .unwrap();
+
+        assert!(filter.matches(Path::new("src/main.rs")));
+        assert!(!filter.matches(Path::new("tests/fixtures/input.rs")));
+        assert!(!filter.matches(Path::new("fixtures/input.rs")));
+        assert!(!filter.matches(Path::new("src/proto/api_generated.rs")));
+    }
+
+    #[test]
+    fn test_file_filter_include() {
+        let filter =
+            FileFilter::new(&["src/**".to_string()], &["**/*_test.rs".to_string()]).unwrap();
+
+        assert!(filter.matches(Path::new("src/lib.rs")));
+        assert!(!filter.matches(Path::new("benches/bench.rs")));
+        assert!(!filter.matches(Path::new("src/parser_test.rs")));
+    }
+
+    #[test]
+    fn test_file_filter_detection_overrides() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("noctum.toml"), "").unwrap();
+        let filter = RepoConfig::load_unchecked(temp_dir.path())
+            .unwrap()
+            .file_filter()
+            .unwrap();
+        assert_eq!(
+            filter.detect(Path::new("vendor/lib/a.rs"), ""),
+            Some(Detected::Vendored)
+        );
+
+        std::fs::write(
+            temp_dir.path().join("noctum.toml"),
+            "analyze_generated = true\nkeep_globs = [\"vendor/lib/**\"]\n",
+        )
+        .unwrap();
+        let filter = RepoConfig::load_unchecked(temp_dir.path())
+            .unwrap()
+            .file_filter()
+            .unwrap();
+        assert_eq!(filter.detect(Path::new("vendor/lib/a.rs"), ""), None);
+        assert_eq!(
+            filter.detect(Path::new("vendor/other/a.rs"), ""),
+            Some(Detected::Vendored)
+        );
+        assert_eq!(filter.detect(Path::new("src/api.pb.rs"), ""), None);
+    }
+
+    #[test]
+    fn test_file_filter_default_matches_everything() {
+        let filter = FileFilter::default();
+        assert!(filter.matches(Path::new("anything/at/all.ts")));
+    }
+
+    #[test]
+    fn test_file_filter_invalid_pattern() {
+        assert!(FileFilter::new(&[], &["src/[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_load_include_exclude_globs() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_content = r#"
+include_globs = ["src/**"]
+exclude_globs = ["**/fixtures/**"]
+"#;
+        std::fs::write(temp_dir.path().join("noctum.toml"), config_content).unwrap();
+
+        let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
+        let filter = config.file_filter().unwrap();
+        assert!(filter.matches(Path::new("src/lib.rs")));
+        assert!(!filter.matches(Path::new("src/fixtures/a.rs")));
+    }
+
+    #[test]
+    fn test_load_plugins() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_content = r#"
+plugins = ["tools/license-check.wasm"]
+"#;
+        std::fs::write(temp_dir.path().join("noctum.toml"), config_content).unwrap();
+
+        let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
+        assert_eq!(
+            config.plugins,
+            vec![PathBuf::from("tools/license-check.wasm")]
+        );
     }
 
     #[test]
@@ -568,6 +946,7 @@ test_command = "npm test"
 
         let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
         assert_eq!(config.setup_command, Some("npm ci".to_string()));
+        assert!(!config.install_dependencies);
         assert_eq!(config.mutation.rules.len(), 1);
         assert_eq!(config.mutation.rules[0].build_command, "npm run build");
     }
@@ -639,6 +1018,7 @@ test_command = "cargo test"
                 test_command: "npm test".to_string(),
                 timeout_seconds: 300,
             }],
+            ..Default::default()
         };
 
         // Should match regular .ts files
@@ -660,6 +1040,7 @@ test_command = "cargo test"
                 test_command: "npm test".to_string(),
                 timeout_seconds: 300,
             }],
+            ..Default::default()
         };
 
         assert!(config.find_rule("src/service.ts").is_some());
@@ -676,6 +1057,7 @@ test_command = "cargo test"
                 test_command: "npm test".to_string(),
                 timeout_seconds: 300,
             }],
+            ..Default::default()
         };
 
         assert!(config.find_rule("src/utils.ts").is_some());
@@ -693,6 +1075,7 @@ test_command = "cargo test"
                 test_command: "npm test".to_string(),
                 timeout_seconds: 300,
             }],
+            ..Default::default()
         };
 
         // Without glob_ignore, all .ts files should match
@@ -716,6 +1099,31 @@ test_command = "cargo test"
         assert!(!rule.matches("src/main.rs")); // wrong extension
     }
 
+    #[test]
+    fn test_mutation_rule_with_overrides() {
+        let rule = MutationRule {
+            glob: "**/*.rs".to_string(),
+            glob_ignore: None,
+            build_command: "cargo check".to_string(),
+            test_command: "cargo test".to_string(),
+            timeout_seconds: 300,
+        };
+
+        let overridden = rule.with_overrides(&CommandOverrides {
+            test_command: Some("cargo nextest run".to_string()),
+            timeout_seconds: Some(900),
+            ..CommandOverrides::default()
+        });
+        assert_eq!(overridden.glob, "**/*.rs");
+        assert_eq!(overridden.build_command, "cargo check");
+        assert_eq!(overridden.test_command, "cargo nextest run");
+        assert_eq!(overridden.timeout_seconds, 900);
+
+        let unchanged = rule.with_overrides(&CommandOverrides::default());
+        assert_eq!(unchanged.test_command, "cargo test");
+        assert_eq!(unchanged.timeout_seconds, 300);
+    }
+
     #[test]
     fn test_load_glob_ignore_from_config() {
         let temp_dir = TempDir::new().unwrap();
@@ -754,4 +1162,138 @@ test_command = "npm test"
         let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
         assert_eq!(config.mutation.rules[0].glob_ignore, None);
     }
+
+    #[test]
+    fn test_load_mutation_strategy() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_content = r#"
+[mutation]
+strategy = "hybrid"
+
+[[mutation.rules]]
+glob = "**/*.rs"
+build_command = "cargo build"
+test_command = "cargo test"
+"#;
+        std::fs::write(temp_dir.path().join("noctum.toml"), config_content).unwrap();
+
+        let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
+        assert_eq!(config.mutation.strategy, MutationStrategy::Hybrid);
+        assert_eq!(config.mutation.rules.len(), 1);
+        assert_eq!(
+            RepoConfig::default().mutation.strategy,
+            MutationStrategy::Llm
+        );
+    }
+
+    #[test]
+    fn test_load_mutation_parallelism() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("noctum.toml"),
+            "[mutation]\nparallelism = 4\n",
+        )
+        .unwrap();
+
+        let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
+        assert_eq!(config.mutation.parallelism, 4);
+        assert_eq!(RepoConfig::default().mutation.parallelism, 1);
+    }
+
+    #[test]
+    fn test_load_mutation_changed_only() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("noctum.toml"),
+            "[mutation]\nchanged_only = false\n",
+        )
+        .unwrap();
+
+        let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
+        assert!(!config.mutation.changed_only);
+        assert!(RepoConfig::default().mutation.changed_only);
+    }
+
+    #[test]
+    fn test_load_mutation_compile_fix() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("noctum.toml"),
+            "[mutation.compile_fix]\nmax_retries = 1\nfallback_to_operator = true\n",
+        )
+        .unwrap();
+
+        let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
+        let compile_fix = &config.mutation.compile_fix;
+        assert!(compile_fix.retry);
+        assert_eq!(compile_fix.max_retries, 1);
+        assert!(compile_fix.fallback_to_operator);
+        assert_eq!(
+            RepoConfig::default().mutation.compile_fix,
+            CompileFixConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_load_repository_watch() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("noctum.toml"),
+            "[repository]\nwatch = true\n",
+        )
+        .unwrap();
+
+        let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
+        assert!(config.repository.watch);
+        assert!(!RepoConfig::default().repository.watch);
+    }
+
+    #[test]
+    fn test_load_mutation_coverage() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("noctum.toml"),
+            "[mutation.coverage]\nenabled = true\nlcov_path = \"target/lcov.info\"\n",
+        )
+        .unwrap();
+
+        let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
+        let coverage = &config.mutation.coverage;
+        assert!(coverage.enabled);
+        assert_eq!(coverage.command, None);
+        assert_eq!(coverage.lcov_path, Some(PathBuf::from("target/lcov.info")));
+        assert_eq!(coverage.timeout_seconds, 1800);
+        assert!(!RepoConfig::default().mutation.coverage.enabled);
+    }
+
+    #[test]
+    fn test_load_issues_section() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("noctum.toml"),
+            "[issues]\nenabled = true\nprovider = \"gitlab\"\nproject = \"group/app\"\n",
+        )
+        .unwrap();
+
+        let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
+        assert!(config.issues.enabled);
+        assert_eq!(config.issues.provider, Some(IssueProvider::GitLab));
+        assert_eq!(config.issues.project.as_deref(), Some("group/app"));
+        assert!(!RepoConfig::default().issues.enabled);
+    }
+
+    #[test]
+    fn test_load_context_section() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("noctum.toml"),
+            "[context]\nenabled = true\n",
+        )
+        .unwrap();
+
+        let config = RepoConfig::load_unchecked(temp_dir.path()).unwrap();
+        assert!(config.context.enabled);
+        assert_eq!(config.context.max_tokens, 1500);
+        assert!(!RepoConfig::default().context.enabled);
+    }
 }
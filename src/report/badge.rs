@@ -0,0 +1,201 @@
+//! Status badges.
+//!
+//! Renders a repository's latest mutation score and findings count as flat,
+//! shields.io-style SVG badges, so a project README can embed live badges
+//! served by the local dashboard.
+
+use crate::db::{AnalysisFinding, MutationSummary};
+
+/// Badge colors, from best to worst
+const GREEN: &str = "#4c1";
+const YELLOW: &str = "#dfb317";
+const ORANGE: &str = "#fe7d37";
+const RED: &str = "#e05d44";
+const BLUE: &str = "#007ec6";
+const GREY: &str = "#9f9f9f";
+
+/// Height of a badge in pixels
+const HEIGHT: usize = 20;
+
+/// Horizontal padding around each half's text
+const PADDING: usize = 6;
+
+/// A two-part badge: a grey label and a colored message
+#[derive(Debug, Clone, PartialEq)]
+pub struct Badge {
+    pub label: String,
+    pub message: String,
+    pub color: &'static str,
+}
+
+impl Badge {
+    /// Render the badge as a standalone SVG document
+    pub fn svg(&self) -> String {
+        let label_width = text_width(&self.label) + 2 * PADDING;
+        let message_width = text_width(&self.message) + 2 * PADDING;
+        let width = label_width + message_width;
+        let label = escape(&self.label);
+        let message = escape(&self.message);
+        // Text is drawn at 10x scale for sub-pixel positioning
+        let label_x = label_width * 5;
+        let message_x = (label_width + message_width / 2) * 10;
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{HEIGHT}" role="img" aria-label="{label}: {message}"><title>{label}: {message}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="{HEIGHT}" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="{HEIGHT}" fill="#555"/><rect x="{label_width}" width="{message_width}" height="{HEIGHT}" fill="{color}"/><rect width="{width}" height="{HEIGHT}" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="110"><text x="{label_x}" y="150" fill="#010101" fill-opacity=".3" transform="scale(.1)">{label}</text><text x="{label_x}" y="140" transform="scale(.1)">{label}</text><text x="{message_x}" y="150" fill="#010101" fill-opacity=".3" transform="scale(.1)">{message}</text><text x="{message_x}" y="140" transform="scale(.1)">{message}</text></g></svg>"##,
+            color = self.color,
+        )
+    }
+}
+
+/// Badge of a mutation score (killed / (killed + survived))
+pub fn mutation_score(summary: &MutationSummary) -> Badge {
+    let testable = summary.killed + summary.survived;
+    let (message, color) = if testable == 0 {
+        ("unknown".to_string(), GREY)
+    } else {
+        let percent = summary.mutation_score() * 100.0;
+        let color = match percent {
+            p if p >= 80.0 => GREEN,
+            p if p >= 60.0 => YELLOW,
+            p if p >= 40.0 => ORANGE,
+            _ => RED,
+        };
+        (format!("{:.0}%", percent), color)
+    };
+
+    Badge {
+        label: "mutation score".to_string(),
+        message,
+        color,
+    }
+}
+
+/// Badge counting findings, only those of `severity` if given. The color
+/// follows the most severe finding counted.
+pub fn findings(findings: &[AnalysisFinding], severity: Option<&str>) -> Badge {
+    let counted: Vec<&AnalysisFinding> = findings
+        .iter()
+        .filter(|f| severity.is_none_or(|s| f.severity == s))
+        .collect();
+    let has = |severity: &str| counted.iter().any(|f| f.severity == severity);
+    let color = if counted.is_empty() {
+        GREEN
+    } else if has("error") {
+        RED
+    } else if has("warning") {
+        YELLOW
+    } else {
+        BLUE
+    };
+    let label = match severity {
+        Some("error") => "errors",
+        Some("warning") => "warnings",
+        _ => "findings",
+    };
+
+    Badge {
+        label: label.to_string(),
+        message: counted.len().to_string(),
+        color,
+    }
+}
+
+/// Approximate width of text in 11px Verdana, which is wider than most fonts
+/// a badge falls back to
+fn text_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c {
+            'i' | 'l' | 'j' | '.' | ',' | ':' | ';' | '!' | '|' | '\'' => 3,
+            'f' | 't' | 'r' | ' ' | '(' | ')' | '[' | ']' | '-' => 5,
+            'm' | 'w' | 'M' | 'W' | '%' => 10,
+            c if c.is_ascii_uppercase() => 8,
+            _ => 7,
+        })
+        .sum()
+}
+
+/// Escape text for use in SVG content and attributes
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(severity: &str) -> AnalysisFinding {
+        AnalysisFinding {
+            id: 1,
+            analysis_result_id: 1,
+            repository_id: 1,
+            file_path: "/repo/src/lib.rs".to_string(),
+            title: "Unchecked index".to_string(),
+            severity: severity.to_string(),
+            category: "bug".to_string(),
+            line_start: None,
+            line_end: None,
+            recommendation: String::new(),
+            fingerprint: None,
+            first_seen: None,
+            last_seen: None,
+            occurrences: 1,
+            resolved_at: None,
+        }
+    }
+
+    #[test]
+    fn test_mutation_score_badge() {
+        let summary = |killed, survived| MutationSummary {
+            total: killed + survived,
+            killed,
+            survived,
+            ..MutationSummary::default()
+        };
+
+        let badge = mutation_score(&summary(17, 3));
+        assert_eq!(badge.message, "85%");
+        assert_eq!(badge.color, GREEN);
+        assert_eq!(mutation_score(&summary(1, 2)).color, RED);
+
+        let badge = mutation_score(&summary(0, 0));
+        assert_eq!(badge.message, "unknown");
+        assert_eq!(badge.color, GREY);
+    }
+
+    #[test]
+    fn test_findings_badge() {
+        let all = vec![finding("warning"), finding("error"), finding("info")];
+
+        let badge = findings(&all, None);
+        assert_eq!(
+            (badge.label.as_str(), badge.message.as_str()),
+            ("findings", "3")
+        );
+        assert_eq!(badge.color, RED);
+
+        let badge = findings(&all, Some("warning"));
+        assert_eq!(
+            (badge.label.as_str(), badge.message.as_str()),
+            ("warnings", "1")
+        );
+        assert_eq!(badge.color, YELLOW);
+
+        assert_eq!(findings(&[], None).color, GREEN);
+    }
+
+    #[test]
+    fn test_svg_escapes_text() {
+        let svg = Badge {
+            label: "a<b".to_string(),
+            message: "\"x\" & y".to_string(),
+            color: BLUE,
+        }
+        .svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains("<title>a&lt;b: &quot;x&quot; &amp; y</title>"));
+        assert!(svg.contains(r##"fill="#007ec6""##));
+    }
+}
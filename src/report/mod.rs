@@ -0,0 +1,550 @@
+//! Report export.
+//!
+//! Renders a repository's latest results (architecture summary, diagrams,
+//! surviving mutations and per-file analyses) into a single self-contained
+//! document that can be attached to a pull request or shared outside the
+//! dashboard. Markdown reports embed diagrams as DOT or Mermaid source; HTML
+//! reports inline the pre-rendered SVGs and need no network access to view,
+//! except that Mermaid diagrams are drawn by Mermaid loaded from a CDN (their
+//! source is shown without it). Findings can also be exported as SARIF (see
+//! [`sarif`]) and summarized in README badges (see [`badge`]).
+
+pub mod badge;
+pub mod sarif;
+
+use crate::db::{AnalysisResult, Database, Diagram, MutationResult, MutationSummary, Repository};
+use crate::theme::{Page, Templates};
+use anyhow::{Context, Result};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser};
+use serde::Serialize;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Output format of a report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// MIME type of the rendered report
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    /// File extension of the rendered report
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "md" | "markdown" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            other => anyhow::bail!("Unknown report format '{}' (expected md or html)", other),
+        }
+    }
+}
+
+/// A surviving mutation, with its path relative to the repository root
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurvivedMutation {
+    pub file_path: String,
+    pub description: String,
+    pub reasoning: String,
+}
+
+/// A file analysis, with its path relative to the repository root
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileAnalysis {
+    pub file_path: String,
+    pub severity: Option<String>,
+    pub result: String,
+}
+
+/// Everything a report contains, loaded from the database
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub repository: Repository,
+    pub architecture_summary: Option<String>,
+    pub diagrams: Vec<Diagram>,
+    pub mutation_summary: MutationSummary,
+    pub survived: Vec<SurvivedMutation>,
+    pub files: Vec<FileAnalysis>,
+    /// Files with unused dependencies or exports
+    pub dead_weight: Vec<FileAnalysis>,
+    /// When the report was generated (RFC 3339)
+    pub generated_at: String,
+}
+
+impl Report {
+    /// Load the latest results of a repository
+    pub async fn load(db: &Database, repository_id: i64) -> Result<Self> {
+        let repository = db
+            .get_repository(repository_id)
+            .await?
+            .with_context(|| format!("Repository {} not found", repository_id))?;
+
+        let results = db.get_all_repository_results(repository_id).await?;
+        let mutations = db.get_mutation_results(repository_id).await?;
+        let mutation_summary = db.get_mutation_summary(repository_id).await?;
+        let diagrams = db.get_latest_diagrams(repository_id).await?;
+
+        Ok(Self::from_results(
+            repository,
+            results,
+            mutations,
+            mutation_summary,
+            diagrams,
+        ))
+    }
+
+    fn from_results(
+        repository: Repository,
+        results: Vec<AnalysisResult>,
+        mutations: Vec<MutationResult>,
+        mutation_summary: MutationSummary,
+        diagrams: Vec<Diagram>,
+    ) -> Self {
+        let architecture_summary = results
+            .iter()
+            .find(|r| r.analysis_type == "architecture_summary")
+            .map(|r| r.result.clone());
+
+        let mut files: Vec<FileAnalysis> = Vec::new();
+        let mut dead_weight: Vec<FileAnalysis> = Vec::new();
+        for r in results {
+            let analysis = FileAnalysis {
+                file_path: relative_path(&r.file_path, &repository.path),
+                severity: r.severity,
+                result: r.result,
+            };
+            match r.analysis_type.as_str() {
+                "code_understanding" => files.push(analysis),
+                // Files without dead code have no severity
+                "dead_code" if analysis.severity.is_some() => dead_weight.push(analysis),
+                _ => {}
+            }
+        }
+        files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        dead_weight.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        let mut survived: Vec<SurvivedMutation> = mutations
+            .into_iter()
+            .filter(|m| m.test_outcome == "survived")
+            .map(|m| SurvivedMutation {
+                file_path: relative_path(&m.file_path, &repository.path),
+                description: m.description,
+                reasoning: m.reasoning,
+            })
+            .collect();
+        survived.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        Self {
+            repository,
+            architecture_summary,
+            diagrams,
+            mutation_summary,
+            survived,
+            files,
+            dead_weight,
+            generated_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        }
+    }
+
+    /// Render the report in the given format
+    pub fn render(&self, format: ReportFormat, templates: &Templates) -> Result<String> {
+        match format {
+            ReportFormat::Markdown => Ok(self.to_markdown()),
+            ReportFormat::Html => self.to_html(templates),
+        }
+    }
+
+    /// Render the report as Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut out = self.header_markdown();
+        out.push_str(&self.architecture_markdown());
+
+        if !self.diagrams.is_empty() {
+            out.push_str("## Diagrams\n\n");
+            for diagram in &self.diagrams {
+                out.push_str(&format!(
+                    "### {}\n\n{}\n\n```{}\n{}\n```\n\n",
+                    diagram.title,
+                    diagram.description,
+                    diagram.format,
+                    diagram.dot_content.trim_end()
+                ));
+            }
+        }
+
+        out.push_str(&self.mutations_markdown());
+        out.push_str(&self.dead_weight_markdown());
+        out.push_str(&self.files_markdown());
+        out
+    }
+
+    /// Render the report as a standalone HTML document
+    pub fn to_html(&self, templates: &Templates) -> Result<String> {
+        let template = ReportTemplate {
+            title: format!("Noctum report: {}", self.repository.name),
+            intro_html: markdown_to_html(&format!(
+                "{}{}",
+                self.header_markdown(),
+                self.architecture_markdown()
+            )),
+            diagrams: &self.diagrams,
+            has_mermaid: self.diagrams.iter().any(|d| d.format == "mermaid"),
+            details_html: markdown_to_html(&format!(
+                "{}{}{}",
+                self.mutations_markdown(),
+                self.dead_weight_markdown(),
+                self.files_markdown()
+            )),
+        };
+        templates
+            .render_page(&template)
+            .context("Failed to render HTML report")
+    }
+
+    fn header_markdown(&self) -> String {
+        format!(
+            "# Noctum report: {}\n\nRepository: `{}`  \nGenerated: {}\n\n",
+            self.repository.name, self.repository.path, self.generated_at
+        )
+    }
+
+    fn architecture_markdown(&self) -> String {
+        match &self.architecture_summary {
+            Some(summary) => format!("## Architecture\n\n{}\n\n", demote_headings(summary, 2)),
+            None => "## Architecture\n\n_No architecture summary yet._\n\n".to_string(),
+        }
+    }
+
+    fn mutations_markdown(&self) -> String {
+        let summary = &self.mutation_summary;
+        if summary.total == 0 {
+            return "## Mutation Testing\n\n_No mutation test results yet._\n\n".to_string();
+        }
+
+        let mut out = format!(
+            "## Mutation Testing\n\n\
+             | Score | Killed | Survived | Timeout | Compile error |\n\
+             |---|---|---|---|---|\n\
+             | {:.1}% | {} | {} | {} | {} |\n\n",
+            summary.mutation_score() * 100.0,
+            summary.killed,
+            summary.survived,
+            summary.timeout,
+            summary.compile_error
+        );
+
+        if !self.survived.is_empty() {
+            out.push_str("### Surviving Mutations\n\n");
+            for mutant in &self.survived {
+                out.push_str(&format!(
+                    "- `{}`: {}\n",
+                    mutant.file_path,
+                    single_line(&mutant.description)
+                ));
+                if !mutant.reasoning.trim().is_empty() {
+                    out.push_str(&format!("  - {}\n", single_line(&mutant.reasoning)));
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn dead_weight_markdown(&self) -> String {
+        if self.dead_weight.is_empty() {
+            return String::new();
+        }
+
+        let mut out = "## Dead Weight\n\n".to_string();
+        for file in &self.dead_weight {
+            out.push_str(&format!("### `{}`\n\n", file.file_path));
+            out.push_str(file.result.trim());
+            out.push_str("\n\n");
+        }
+        out
+    }
+
+    fn files_markdown(&self) -> String {
+        if self.files.is_empty() {
+            return String::new();
+        }
+
+        let mut out = "## File Analyses\n\n".to_string();
+        for file in &self.files {
+            out.push_str(&format!("### `{}`\n\n", file.file_path));
+            if let Some(severity) = &file.severity {
+                out.push_str(&format!("Severity: **{}**\n\n", severity));
+            }
+            out.push_str(&demote_headings(&file.result, 3));
+            out.push_str("\n\n");
+        }
+        out
+    }
+}
+
+#[derive(Serialize)]
+struct ReportTemplate<'a> {
+    title: String,
+    intro_html: String,
+    diagrams: &'a [Diagram],
+    /// Whether to load Mermaid to draw the diagrams
+    has_mermaid: bool,
+    details_html: String,
+}
+
+impl Page for ReportTemplate<'_> {
+    const TEMPLATE: &'static str = "report.html";
+}
+
+/// Strip the repository prefix from a stored (absolute) file path
+fn relative_path(file_path: &str, repo_path: &str) -> String {
+    Path::new(file_path)
+        .strip_prefix(repo_path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| file_path.to_string())
+}
+
+/// Collapse text onto one line so it fits in a list item
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Push ATX headings down by `levels` so embedded LLM output nests under the
+/// report's own headings (fenced code blocks are left alone).
+fn demote_headings(markdown: &str, levels: usize) -> String {
+    let prefix = "#".repeat(levels);
+    let mut in_fence = false;
+    markdown
+        .trim()
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+            }
+            if !in_fence && line.starts_with('#') {
+                format!("{}{}", prefix, line)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render Markdown to HTML, escaping any raw HTML in the source.
+///
+/// Report content comes from LLM output, so embedded markup is shown as text
+/// rather than trusted.
+fn markdown_to_html(markdown: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let parser = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(CowStr::from(raw.into_string())),
+        other => other,
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, parser);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repository() -> Repository {
+        Repository {
+            id: 1,
+            path: "/repo".to_string(),
+            name: "myapp".to_string(),
+            enabled: true,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            updated_at: "2024-01-01 00:00:00".to_string(),
+            analysis_types: None,
+            sensitive: false,
+            remote_url: None,
+            branch: None,
+            tags: Vec::new(),
+            archived_at: None,
+            commands: Default::default(),
+        }
+    }
+
+    fn analysis(file_path: &str, analysis_type: &str, result: &str) -> AnalysisResult {
+        AnalysisResult {
+            id: 1,
+            repository_id: 1,
+            file_path: file_path.to_string(),
+            analysis_type: analysis_type.to_string(),
+            result: result.to_string(),
+            severity: None,
+            content_hash: None,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            issue_url: None,
+        }
+    }
+
+    fn mutation(file_path: &str, outcome: &str, description: &str) -> MutationResult {
+        MutationResult {
+            id: 1,
+            repository_id: 1,
+            file_path: file_path.to_string(),
+            description: description.to_string(),
+            reasoning: "Boundary not tested".to_string(),
+            replacements: Vec::new(),
+            test_outcome: outcome.to_string(),
+            killing_test: None,
+            test_output: None,
+            execution_time_ms: None,
+            content_hash: None,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            toolchain: None,
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            covered: None,
+            project: None,
+            issue_url: None,
+            status: "new".to_string(),
+        }
+    }
+
+    fn report() -> Report {
+        Report::from_results(
+            repository(),
+            vec![
+                analysis("/repo", "architecture_summary", "# Overview\nA web app."),
+                analysis("/repo/src/b.rs", "code_understanding", "## Purpose\nB file"),
+                analysis(
+                    "/repo/src/a.rs",
+                    "code_understanding",
+                    "A file <script>x</script>",
+                ),
+                AnalysisResult {
+                    severity: Some("warning".to_string()),
+                    ..analysis(
+                        "/repo/Cargo.toml",
+                        "dead_code",
+                        "- **Unused dependency `regex`** (line 8): Remove it",
+                    )
+                },
+                analysis("/repo/src/c.rs", "dead_code", "No dead code found."),
+            ],
+            vec![
+                mutation("/repo/src/a.rs", "survived", "Changed `>` to `>=`"),
+                mutation("/repo/src/a.rs", "killed", "Removed call"),
+            ],
+            MutationSummary {
+                total: 2,
+                killed: 1,
+                survived: 1,
+                timeout: 0,
+                compile_error: 0,
+                rejected: 0,
+                covered_survived: None,
+                untriaged: 0,
+            },
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn test_report_format_from_str() {
+        assert_eq!(
+            "md".parse::<ReportFormat>().unwrap(),
+            ReportFormat::Markdown
+        );
+        assert_eq!("html".parse::<ReportFormat>().unwrap(), ReportFormat::Html);
+        assert!("pdf".parse::<ReportFormat>().is_err());
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let markdown = report().to_markdown();
+
+        assert!(markdown.starts_with("# Noctum report: myapp\n"));
+        assert!(markdown.contains("## Architecture\n\n### Overview\nA web app."));
+        assert!(markdown.contains("| 50.0% | 1 | 1 | 0 | 0 |"));
+        assert!(markdown.contains("- `src/a.rs`: Changed `>` to `>=`\n  - Boundary not tested\n"));
+        assert!(!markdown.contains("Removed call"));
+        assert!(markdown.contains("#### Purpose\nB file"));
+        let a = markdown.find("### `src/a.rs`").unwrap();
+        let b = markdown.find("### `src/b.rs`").unwrap();
+        assert!(a < b);
+
+        let dead_weight = markdown.find("## Dead Weight\n\n### `Cargo.toml`").unwrap();
+        assert!(dead_weight < a);
+        assert!(markdown.contains("Unused dependency `regex`"));
+        assert!(!markdown.contains("src/c.rs"));
+    }
+
+    #[test]
+    fn test_html_report_escapes_raw_html() {
+        let html = report().to_html(&Templates::new(None).strict()).unwrap();
+
+        assert!(html.starts_with("<!doctype html>"));
+        assert!(html.contains("<title>Noctum report: myapp</title>"));
+        assert!(html.contains("&lt;script&gt;x&lt;/script&gt;"));
+        assert!(!html.contains("<script>x</script>"));
+        assert!(!html.contains("mermaid"));
+    }
+
+    #[test]
+    fn test_report_diagrams() {
+        let diagram = |format: &str, source: &str| Diagram {
+            id: 1,
+            repository_id: 1,
+            diagram_type: "data_flow".to_string(),
+            title: "Data Flow".to_string(),
+            description: "How data moves".to_string(),
+            format: format.to_string(),
+            dot_content: source.to_string(),
+            svg_content: if format == "dot" { "<svg></svg>" } else { "" }.to_string(),
+            content_hash: None,
+            created_at: "2024-01-01 00:00:00".to_string(),
+        };
+        let mut report = report();
+        report.diagrams = vec![
+            diagram("dot", "digraph { a -> b }"),
+            diagram("mermaid", "flowchart LR\n    a --> b\n"),
+        ];
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("```dot\ndigraph { a -> b }\n```"));
+        assert!(markdown.contains("```mermaid\nflowchart LR\n    a --> b\n```"));
+
+        let html = report.to_html(&Templates::new(None).strict()).unwrap();
+        assert!(html.contains("<div class=\"diagram-svg\"><svg></svg></div>"));
+        assert!(html.contains("<pre class=\"mermaid\">flowchart LR\n    a --&gt; b\n</pre>"));
+        assert!(html.contains("mermaid.esm.min.mjs"));
+    }
+
+    #[test]
+    fn test_demote_headings_skips_code_fences() {
+        let markdown = "# Title\n```sh\n# comment\n```\n## Sub";
+        assert_eq!(
+            demote_headings(markdown, 2),
+            "### Title\n```sh\n# comment\n```\n#### Sub"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! SARIF export.
+//!
+//! Converts a repository's structured findings into a SARIF 2.1.0 log, the
+//! format GitHub code scanning and most IDEs import. Each finding category
+//! becomes a rule, finding severities map onto SARIF levels, and locations
+//! are relative to the repository root (`%SRCROOT%`), so the log can be
+//! uploaded from a checkout anywhere.
+
+use super::relative_path;
+use crate::analyzer::findings::CATEGORIES;
+use crate::db::{AnalysisFinding, Database, Repository};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+/// SARIF version written
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Schema of the SARIF version written
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Base URI id locations are relative to
+const SRCROOT: &str = "%SRCROOT%";
+
+/// Load a repository's latest findings as a SARIF log
+pub async fn load(db: &Database, repository_id: i64) -> Result<Value> {
+    let repository = db
+        .get_repository(repository_id)
+        .await?
+        .with_context(|| format!("Repository {} not found", repository_id))?;
+    let findings = db.get_findings(repository_id).await?;
+    Ok(sarif_log(&repository, &findings))
+}
+
+/// SARIF level of a finding severity
+pub fn level(severity: &str) -> &'static str {
+    match severity {
+        "error" => "error",
+        "warning" => "warning",
+        _ => "note",
+    }
+}
+
+/// Build a SARIF log with one run holding the findings of a repository
+pub fn sarif_log(repository: &Repository, findings: &[AnalysisFinding]) -> Value {
+    let rules: Vec<Value> = CATEGORIES.iter().map(|category| rule(category)).collect();
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| result(repository, finding))
+        .collect();
+
+    json!({
+        "version": SARIF_VERSION,
+        "$schema": SARIF_SCHEMA,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "Noctum",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_HOMEPAGE"),
+                    "rules": rules,
+                }
+            },
+            "originalUriBaseIds": {
+                SRCROOT: { "uri": directory_uri(&repository.path) }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// Rule describing a finding category
+fn rule(category: &str) -> Value {
+    let name = category.replace('_', " ");
+    let mut tags = vec!["noctum"];
+    if category == "security" {
+        tags.push("security");
+    }
+    json!({
+        "id": category,
+        "name": name,
+        "shortDescription": { "text": format!("Noctum {} finding", name) },
+        "properties": { "tags": tags },
+    })
+}
+
+fn result(repository: &Repository, finding: &AnalysisFinding) -> Value {
+    // Findings are validated against CATEGORIES when saved
+    let rule_index = CATEGORIES
+        .iter()
+        .position(|c| *c == finding.category)
+        .unwrap_or(0);
+
+    let mut message = finding.title.trim().to_string();
+    if !finding.recommendation.trim().is_empty() {
+        message.push_str(&format!(
+            "\n\nRecommendation: {}",
+            finding.recommendation.trim()
+        ));
+    }
+
+    // GitHub code scanning rejects results without a start line, so findings
+    // about a whole file point at its first line
+    let start_line = finding.line_start.filter(|l| *l > 0).unwrap_or(1);
+    let mut region = json!({ "startLine": start_line });
+    if let Some(end_line) = finding.line_end.filter(|l| *l >= start_line) {
+        region["endLine"] = json!(end_line);
+    }
+
+    let mut result = json!({
+        "ruleId": finding.category,
+        "ruleIndex": rule_index,
+        "level": level(&finding.severity),
+        "message": { "text": message },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": {
+                    "uri": relative_path(&finding.file_path, &repository.path)
+                        .replace(std::path::MAIN_SEPARATOR, "/"),
+                    "uriBaseId": SRCROOT,
+                },
+                "region": region,
+            }
+        }],
+    });
+    // Lets code scanning track the finding across uploads as Noctum does
+    if let Some(fingerprint) = &finding.fingerprint {
+        result["partialFingerprints"] = json!({ "noctumFingerprint/v1": fingerprint });
+    }
+    result
+}
+
+/// `file://` URI of a directory, with the trailing slash SARIF requires
+fn directory_uri(path: &str) -> String {
+    let path = path.replace(std::path::MAIN_SEPARATOR, "/");
+    let path = path.trim_end_matches('/');
+    if path.starts_with('/') {
+        format!("file://{}/", path)
+    } else {
+        format!("file:///{}/", path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repository() -> Repository {
+        Repository {
+            id: 1,
+            path: "/repo".to_string(),
+            name: "myapp".to_string(),
+            enabled: true,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            updated_at: "2024-01-01 00:00:00".to_string(),
+            analysis_types: None,
+            sensitive: false,
+            remote_url: None,
+            branch: None,
+            tags: Vec::new(),
+            archived_at: None,
+            commands: Default::default(),
+        }
+    }
+
+    fn finding(
+        severity: &str,
+        category: &str,
+        line_start: Option<i64>,
+        line_end: Option<i64>,
+    ) -> AnalysisFinding {
+        AnalysisFinding {
+            id: 1,
+            analysis_result_id: 1,
+            repository_id: 1,
+            file_path: "/repo/src/db.rs".to_string(),
+            title: "SQL built from user input".to_string(),
+            severity: severity.to_string(),
+            category: category.to_string(),
+            line_start,
+            line_end,
+            recommendation: "Bind parameters".to_string(),
+            fingerprint: None,
+            first_seen: None,
+            last_seen: None,
+            occurrences: 1,
+            resolved_at: None,
+        }
+    }
+
+    #[test]
+    fn test_level() {
+        assert_eq!(level("error"), "error");
+        assert_eq!(level("warning"), "warning");
+        assert_eq!(level("info"), "note");
+    }
+
+    #[test]
+    fn test_sarif_log() {
+        let log = sarif_log(
+            &repository(),
+            &[
+                finding("error", "security", Some(12), Some(14)),
+                finding("info", "documentation", None, None),
+            ],
+        );
+
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "Noctum");
+        assert_eq!(
+            run["originalUriBaseIds"]["%SRCROOT%"]["uri"],
+            "file:///repo/"
+        );
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), CATEGORIES.len());
+
+        let security = &run["results"][0];
+        assert_eq!(security["ruleId"], "security");
+        assert_eq!(
+            rules[security["ruleIndex"].as_u64().unwrap() as usize]["id"],
+            "security"
+        );
+        assert_eq!(security["level"], "error");
+        assert_eq!(
+            security["message"]["text"],
+            "SQL built from user input\n\nRecommendation: Bind parameters"
+        );
+        let location = &security["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/db.rs");
+        assert_eq!(location["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+        assert_eq!(
+            location["region"],
+            json!({ "startLine": 12, "endLine": 14 })
+        );
+
+        let documentation = &run["results"][1];
+        assert_eq!(documentation["level"], "note");
+        assert_eq!(
+            documentation["locations"][0]["physicalLocation"]["region"],
+            json!({ "startLine": 1 })
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Local pre-review of uncommitted changes.
+//!
+//! `noctum review` collects the uncommitted diff of a repository (staged and
+//! unstaged changes against `HEAD`), enriches it with the stored code
+//! understanding summaries of the touched files, and asks an LLM for
+//! review-oriented findings which are printed to the terminal.
+
+use crate::analyzer::{respond_in, AnalysisType};
+use crate::config::Config;
+use crate::daemon::{
+    endpoints_for, find_available_endpoint, routing_audit_detail, truncate_at_char_boundary,
+    HealthMonitor,
+};
+use crate::db::{Database, Repository, AUDIT_SENSITIVE_ROUTING};
+use anyhow::{Context, Result};
+use std::path::Path;
+use tokio_util::sync::CancellationToken;
+
+/// Hash of git's empty tree, used as the diff base for repositories without commits.
+const EMPTY_TREE_HASH: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Maximum number of diff characters sent to the LLM.
+const MAX_DIFF_CHARS: usize = 60_000;
+
+/// Maximum number of summary characters sent to the LLM.
+const MAX_SUMMARY_CHARS: usize = 20_000;
+
+/// A stored file summary used as context for the review.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSummary {
+    /// Path of the file relative to the repository root.
+    pub file_path: String,
+    /// Latest code understanding result for the file.
+    pub summary: String,
+}
+
+/// Collect the uncommitted diff (staged and unstaged) of a git repository.
+pub async fn uncommitted_diff(repo_path: &Path) -> Result<String> {
+    let has_head = tokio::process::Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+        .current_dir(repo_path)
+        .output()
+        .await
+        .context("Failed to run git")?
+        .status
+        .success();
+
+    let base = if has_head { "HEAD" } else { EMPTY_TREE_HASH };
+
+    let output = tokio::process::Command::new("git")
+        .args(["diff", "--no-color", "--no-ext-diff", base])
+        .current_dir(repo_path)
+        .output()
+        .await
+        .context("Failed to run git diff")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git diff failed in {}: {}",
+            repo_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Extract the paths of files that still exist after the change from a unified diff.
+///
+/// Deleted files are skipped since there is nothing left to review in them.
+pub fn changed_files(diff: &str) -> Vec<String> {
+    diff.lines()
+        .filter_map(|line| line.strip_prefix("+++ b/"))
+        .map(|path| path.trim_end().to_string())
+        .collect()
+}
+
+/// Build the review prompt from the diff and the stored file summaries.
+pub fn review_prompt(diff: &str, summaries: &[FileSummary], output_language: &str) -> String {
+    let mut context = String::new();
+    for summary in summaries {
+        context.push_str(&format!(
+            "### {}\n{}\n\n",
+            summary.file_path, summary.summary
+        ));
+    }
+
+    let context_section = if context.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "Previously recorded summaries of the changed files (they describe the code BEFORE this change):\n\n{}\n",
+            truncate_at_char_boundary(&context, MAX_SUMMARY_CHARS)
+        )
+    };
+
+    format!(
+        "You are a senior engineer reviewing a colleague's uncommitted changes before they open a pull request.\n\n\
+         {}\
+         Diff to review:\n\n\
+         ```diff\n{}\n```\n\n\
+         Review ONLY the changed lines. For each finding provide:\n\
+         1. The file and approximate line\n\
+         2. The severity (error, warning or info)\n\
+         3. What is wrong and why it matters\n\
+         4. A concrete suggestion to fix it\n\n\
+         Focus on bugs, logic errors, missing error handling, security problems and changes that \
+         contradict the existing design of the file. Skip style nitpicks. \
+         If the changes look good, say so briefly.\n\n\
+         {}",
+        context_section,
+        truncate_at_char_boundary(diff, MAX_DIFF_CHARS),
+        respond_in(output_language, true)
+    )
+}
+
+/// Find the registered repository at `repo_path`, if any.
+async fn find_repository(db: &Database, repo_path: &Path) -> Result<Option<Repository>> {
+    Ok(db.get_repositories().await?.into_iter().find(|repo| {
+        Path::new(&repo.path)
+            .canonicalize()
+            .is_ok_and(|path| path == repo_path)
+    }))
+}
+
+/// Look up the stored code understanding summaries for the changed files.
+async fn load_summaries(
+    db: &Database,
+    repo: &Repository,
+    files: &[String],
+) -> Result<Vec<FileSummary>> {
+    let results = db
+        .get_repository_results(repo.id, &AnalysisType::CodeUnderstanding.to_string())
+        .await?;
+
+    let repo_root = Path::new(&repo.path);
+    Ok(files
+        .iter()
+        .filter_map(|file| {
+            let full_path = repo_root.join(file).to_string_lossy().to_string();
+            results
+                .iter()
+                .find(|result| result.file_path == full_path)
+                .map(|result| FileSummary {
+                    file_path: file.clone(),
+                    summary: result.result.clone(),
+                })
+        })
+        .collect())
+}
+
+/// Review the uncommitted changes of a repository.
+///
+/// Returns `None` when there is nothing to review.
+pub async fn review_repository(config: &Config, repo_path: &Path) -> Result<Option<String>> {
+    let repo_path = repo_path
+        .canonicalize()
+        .with_context(|| format!("Repository path not found: {}", repo_path.display()))?;
+
+    let diff = uncommitted_diff(&repo_path).await?;
+    if diff.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let files = changed_files(&diff);
+
+    // Summaries are optional context; a missing database or an unregistered
+    // repository simply means no context
+    let db = if config.database_path().is_none_or(|path| path.exists()) {
+        let db = Database::open(&config.database()).await?;
+        db.run_migrations().await?;
+        Some(db)
+    } else {
+        None
+    };
+    let repo = match &db {
+        Some(db) => find_repository(db, &repo_path).await?,
+        None => None,
+    };
+    let summaries = match (&db, &repo) {
+        (Some(db), Some(repo)) => load_summaries(db, repo, &files).await?,
+        _ => Vec::new(),
+    };
+
+    tracing::info!(
+        "Reviewing {} changed file(s) ({} with stored summaries)",
+        files.len(),
+        summaries.len()
+    );
+
+    let mut endpoints: Vec<_> = config
+        .endpoints
+        .iter()
+        .filter(|e| e.enabled)
+        .cloned()
+        .collect();
+
+    // A sensitive repository's diff only goes to local endpoints
+    if let (Some(db), Some(repo)) = (&db, &repo) {
+        if repo.sensitive {
+            let routed = endpoints_for(repo, &endpoints);
+            let detail = format!("Review: {}", routing_audit_detail(&endpoints, &routed));
+            db.record_audit(Some(repo.id), AUDIT_SENSITIVE_ROUTING, &detail)
+                .await?;
+            if routed.is_empty() {
+                anyhow::bail!(
+                    "{} is marked sensitive, but no local endpoint is enabled",
+                    repo.name
+                );
+            }
+            endpoints = routed;
+        }
+    }
+
+    // One-shot command: Ctrl+C ends the process, so nothing needs cancelling
+    let (client, endpoint_name) =
+        find_available_endpoint(&endpoints, &HealthMonitor::new(), &CancellationToken::new())
+            .await
+            .context("No available Ollama endpoints configured")?;
+    tracing::info!("Using endpoint {}", endpoint_name);
+
+    let review = client
+        .generate(&review_prompt(
+            &diff,
+            &summaries,
+            &config.general.output_language,
+        ))
+        .await
+        .context("Failed to generate review")?;
+
+    Ok(Some(review))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn init_repo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "--quiet"]);
+        git(dir.path(), &["config", "user.email", "test@example.com"]);
+        git(dir.path(), &["config", "user.name", "Test"]);
+        dir
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -1 +1 @@\n\
+                    -a\n\
+                    +b\n\
+                    diff --git a/old.rs b/old.rs\n\
+                    --- a/old.rs\n\
+                    +++ /dev/null\n\
+                    diff --git a/new.rs b/new.rs\n\
+                    --- /dev/null\n\
+                    +++ b/new.rs\n";
+
+        assert_eq!(changed_files(diff), vec!["src/lib.rs", "new.rs"]);
+    }
+
+    #[test]
+    fn test_changed_files_empty() {
+        assert!(changed_files("").is_empty());
+    }
+
+    #[test]
+    fn test_review_prompt_includes_diff_and_summaries() {
+        let summaries = vec![FileSummary {
+            file_path: "src/lib.rs".to_string(),
+            summary: "Parses config files".to_string(),
+        }];
+        let prompt = review_prompt("+let x = 1;", &summaries, "German");
+
+        assert!(prompt.contains("+let x = 1;"));
+        assert!(prompt.contains("### src/lib.rs"));
+        assert!(prompt.contains("Parses config files"));
+        assert!(prompt.contains("Respond only in German"));
+    }
+
+    #[test]
+    fn test_review_prompt_without_summaries() {
+        let prompt = review_prompt("+let x = 1;", &[], "English");
+        assert!(!prompt.contains("Previously recorded summaries"));
+    }
+
+    #[tokio::test]
+    async fn test_uncommitted_diff_includes_staged_and_unstaged() {
+        let dir = init_repo();
+        std::fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        std::fs::write(dir.path().join("b.txt"), "one\n").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "--quiet", "-m", "init"]);
+
+        std::fs::write(dir.path().join("a.txt"), "staged\n").unwrap();
+        git(dir.path(), &["add", "a.txt"]);
+        std::fs::write(dir.path().join("b.txt"), "unstaged\n").unwrap();
+
+        let diff = uncommitted_diff(dir.path()).await.unwrap();
+        assert_eq!(changed_files(&diff), vec!["a.txt", "b.txt"]);
+        assert!(diff.contains("+staged"));
+        assert!(diff.contains("+unstaged"));
+    }
+
+    #[tokio::test]
+    async fn test_uncommitted_diff_without_commits() {
+        let dir = init_repo();
+        std::fs::write(dir.path().join("a.txt"), "new\n").unwrap();
+        git(dir.path(), &["add", "a.txt"]);
+
+        let diff = uncommitted_diff(dir.path()).await.unwrap();
+        assert_eq!(changed_files(&diff), vec!["a.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_uncommitted_diff_clean_tree() {
+        let dir = init_repo();
+        std::fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "--quiet", "-m", "init"]);
+
+        let diff = uncommitted_diff(dir.path()).await.unwrap();
+        assert!(diff.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_uncommitted_diff_not_a_repo() {
+        let dir = TempDir::new().unwrap();
+        assert!(uncommitted_diff(dir.path()).await.is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! Semantic search across repositories.
+//!
+//! After each scan the daemon embeds the latest code understanding result of
+//! every file with an Ollama embedding model and stores the vector. A search
+//! embeds the query with the same model and ranks all stored vectors by cosine
+//! similarity, so "where do we parse config files" finds the right file even
+//! if it never uses those words. Each hit carries the file's findings.
+
+use crate::analyzer::{AnalysisType, OllamaClient};
+use crate::daemon::truncate_at_char_boundary;
+use crate::db::{AnalysisFinding, Database};
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+
+/// Maximum characters of a summary that are embedded
+const MAX_EMBED_CHARS: usize = 8_000;
+
+/// Characters of the summary shown with a hit
+const SNIPPET_CHARS: usize = 300;
+
+/// A file relevant to a search
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub repository_id: i64,
+    pub repository_name: String,
+    pub file_path: String,
+    /// Path relative to the repository root
+    pub relative_path: String,
+    /// Cosine similarity to the query (1 is identical)
+    pub score: f32,
+    /// Start of the file's summary
+    pub snippet: String,
+    /// Findings of the file's latest analysis, most severe first
+    pub findings: Vec<AnalysisFinding>,
+}
+
+/// Encode a vector for storage
+pub fn encode(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+/// Decode a stored vector
+pub fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+/// Cosine similarity of two vectors (0 if their lengths differ or either is zero)
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// Text embedded for a file: its path (which often says a lot) and summary
+fn embedding_text(file_path: &str, summary: &str) -> String {
+    format!(
+        "{}\n\n{}",
+        file_path,
+        truncate_at_char_boundary(summary, MAX_EMBED_CHARS)
+    )
+}
+
+/// Embed the files of a repository whose latest code understanding result
+/// hasn't been embedded with `model` yet. Returns the number of files embedded.
+pub async fn index_repository(
+    db: &Database,
+    client: &OllamaClient,
+    repository_id: i64,
+    model: &str,
+) -> Result<usize> {
+    let embedded = db.get_embedded_results(repository_id, model).await?;
+    let results = db
+        .get_repository_results(repository_id, &AnalysisType::CodeUnderstanding.to_string())
+        .await?;
+
+    let mut count = 0;
+    for result in results {
+        if embedded.get(&result.file_path) == Some(&result.id) {
+            continue;
+        }
+        let vector = client
+            .embed(&embedding_text(&result.file_path, &result.result))
+            .await?;
+        db.save_embedding(
+            repository_id,
+            &result.file_path,
+            result.id,
+            model,
+            &encode(&vector),
+        )
+        .await?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// The `limit` files most relevant to `query` in one repository, or across
+/// all of them
+pub async fn search(
+    db: &Database,
+    client: &OllamaClient,
+    model: &str,
+    query: &str,
+    repository_id: Option<i64>,
+    limit: usize,
+) -> Result<Vec<SearchHit>> {
+    let mut embeddings = db.get_embeddings(model).await?;
+    if let Some(repository_id) = repository_id {
+        embeddings.retain(|embedding| embedding.repository_id == repository_id);
+    }
+    if embeddings.is_empty() {
+        return Ok(Vec::new());
+    }
+    let query_vector = client.embed(query).await?;
+
+    let mut ranked: Vec<_> = embeddings
+        .into_iter()
+        .map(|embedding| (cosine(&query_vector, &decode(&embedding.vector)), embedding))
+        .collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    ranked.truncate(limit);
+
+    let mut hits = Vec::with_capacity(ranked.len());
+    for (score, embedding) in ranked {
+        let snippet = db
+            .get_analysis_result(embedding.analysis_result_id)
+            .await?
+            .map(|result| snippet(&result.result))
+            .unwrap_or_default();
+        let relative_path = Path::new(&embedding.file_path)
+            .strip_prefix(&embedding.repository_path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| embedding.file_path.clone());
+        hits.push(SearchHit {
+            relative_path,
+            repository_id: embedding.repository_id,
+            repository_name: embedding.repository_name,
+            file_path: embedding.file_path,
+            score,
+            snippet,
+            findings: db.get_result_findings(embedding.analysis_result_id).await?,
+        });
+    }
+    Ok(hits)
+}
+
+/// Start of a summary, cut at a character boundary
+fn snippet(summary: &str) -> String {
+    let cut = truncate_at_char_boundary(summary.trim(), SNIPPET_CHARS);
+    if cut.len() < summary.trim().len() {
+        format!("{}…", cut.trim_end())
+    } else {
+        cut.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Attribution;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_vector_encoding_round_trips() {
+        let vector = vec![0.25, -1.5, 3.0];
+        assert_eq!(decode(&encode(&vector)), vector);
+    }
+
+    #[test]
+    fn test_cosine() {
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine(&[1.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    async fn mock_embedding(server: &MockServer, text: &str, vector: [f32; 2]) {
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .and(body_partial_json(serde_json::json!({ "prompt": text })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "embedding": vector })),
+            )
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_index_and_search() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .unwrap();
+        db.run_migrations().await.unwrap();
+        let repo_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(repo_dir.path().join("noctum.toml"), "").unwrap();
+        let repo_id = db
+            .add_repository(&repo_dir.path().to_string_lossy(), "app")
+            .await
+            .unwrap();
+
+        let code_understanding = AnalysisType::CodeUnderstanding.to_string();
+        for (file, summary) in [("/app/config.rs", "Parses TOML"), ("/app/net.rs", "HTTP")] {
+            db.save_analysis_result(
+                repo_id,
+                file,
+                &code_understanding,
+                summary,
+                None,
+                None,
+                &Attribution::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let server = MockServer::start().await;
+        mock_embedding(&server, "/app/config.rs\n\nParses TOML", [1.0, 0.0]).await;
+        mock_embedding(&server, "/app/net.rs\n\nHTTP", [0.0, 1.0]).await;
+        mock_embedding(&server, "settings file", [0.9, 0.1]).await;
+        let client = OllamaClient::new(&server.uri(), "embed");
+
+        assert_eq!(
+            index_repository(&db, &client, repo_id, "embed")
+                .await
+                .unwrap(),
+            2
+        );
+        // Unchanged results aren't embedded again
+        assert_eq!(
+            index_repository(&db, &client, repo_id, "embed")
+                .await
+                .unwrap(),
+            0
+        );
+
+        let hits = search(&db, &client, "embed", "settings file", None, 10)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].file_path, "/app/config.rs");
+        assert_eq!(hits[0].repository_name, "app");
+        assert_eq!(hits[0].snippet, "Parses TOML");
+        assert!(hits[0].score > hits[1].score);
+
+        // Vectors of another model are not comparable
+        assert!(search(&db, &client, "other", "settings file", None, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+//! Repository snapshots.
+//!
+//! Every scan works on a temporary copy of the repository (see
+//! `copy_repo_to_temp` in the daemon), which used to mean copying every file,
+//! build artifacts included. A [`SnapshotPlan`] decides per repository which
+//! files go into the snapshot and how they get there, following the
+//! configured [`SnapshotStrategy`]: copy-on-write clones where the filesystem
+//! supports them, hard links, or copies of just the source and manifest
+//! files. With `respect_gitignore`, whatever git ignores is left out too.
+
+use crate::config::{SnapshotConfig, SnapshotStrategy};
+use crate::language::{Language, GRADLE_BUILD_FILES};
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Build, dependency and tool state directories of all supported languages
+const BUILD_DIRECTORIES: &[&str] = &[
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    "out",
+    ".next",
+    "coverage",
+    ".gradle",
+    ".git",
+];
+
+/// Manifests, lockfiles and build configuration kept by the `sources` strategy
+const MANIFEST_FILES: &[&str] = &[
+    "noctum.toml",
+    "Cargo.toml",
+    "Cargo.lock",
+    "rust-toolchain",
+    "rust-toolchain.toml",
+    "package.json",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "pnpm-workspace.yaml",
+    "bun.lockb",
+    ".npmrc",
+    "pom.xml",
+    "gradle.properties",
+    "gradlew",
+    "gradle-wrapper.properties",
+    "gradle-wrapper.jar",
+    "mvnw",
+    "maven-wrapper.properties",
+];
+
+/// Which files of a repository go into its snapshot, and how
+pub struct SnapshotPlan {
+    strategy: SnapshotStrategy,
+    /// Paths relative to the repository root that git ignores; directories
+    /// are listed once and skipped as a whole
+    git_ignored: HashSet<PathBuf>,
+}
+
+impl SnapshotPlan {
+    /// Plan the snapshot of the repository at `repo_path`. Listing the files
+    /// git ignores runs `git`; outside a git work tree nothing is ignored.
+    pub fn new(repo_path: &Path, config: &SnapshotConfig) -> Self {
+        let git_ignored = if config.respect_gitignore {
+            git_ignored_paths(repo_path).unwrap_or_else(|e| {
+                tracing::debug!(
+                    "Not applying ignore rules of {}: {}",
+                    repo_path.display(),
+                    e
+                );
+                HashSet::new()
+            })
+        } else {
+            HashSet::new()
+        };
+
+        Self {
+            strategy: config.strategy,
+            git_ignored,
+        }
+    }
+
+    /// Whether the directory at `relative` (to the repository root) is left
+    /// out of the snapshot, along with everything in it
+    pub fn skips_dir(&self, relative: &Path) -> bool {
+        self.git_ignored.contains(relative)
+            || (self.strategy == SnapshotStrategy::Sources && is_build_directory(relative))
+    }
+
+    /// Whether the file at `relative` (to the repository root) is left out
+    pub fn skips_file(&self, relative: &Path) -> bool {
+        self.git_ignored.contains(relative)
+            || (self.strategy == SnapshotStrategy::Sources && !is_source_or_manifest(relative))
+    }
+
+    /// Place the file `src` at `dest` in the snapshot; `relative` is its path
+    /// from the repository root
+    pub fn place(&self, src: &Path, dest: &Path, relative: &Path) -> io::Result<()> {
+        match self.strategy {
+            SnapshotStrategy::Auto => clone_or_copy(src, dest),
+            SnapshotStrategy::Copy | SnapshotStrategy::Sources => {
+                std::fs::copy(src, dest).map(|_| ())
+            }
+            // Build tools update their output in place, and rewrite stale
+            // lockfiles, which must not reach the repository itself
+            SnapshotStrategy::Hardlink if is_build_directory(relative) || is_manifest(relative) => {
+                clone_or_copy(src, dest)
+            }
+            SnapshotStrategy::Hardlink => std::fs::hard_link(src, dest).or_else(|e| {
+                // E.g. the snapshot is on another filesystem
+                tracing::debug!("Copying {:?} instead of linking it: {}", src, e);
+                std::fs::copy(src, dest).map(|_| ())
+            }),
+        }
+    }
+}
+
+/// Whether a path lies in (or is) a build or dependency directory
+fn is_build_directory(relative: &Path) -> bool {
+    relative.components().any(|component| {
+        matches!(component, std::path::Component::Normal(name)
+            if BUILD_DIRECTORIES.iter().any(|dir| name == *dir))
+    })
+}
+
+/// Whether a file is a manifest, lockfile or build file, which build tools
+/// may rewrite (e.g. cargo updating a stale `Cargo.lock`)
+fn is_manifest(relative: &Path) -> bool {
+    let name = relative
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    MANIFEST_FILES.contains(&name) || GRADLE_BUILD_FILES.contains(&name)
+}
+
+/// Whether the `sources` strategy keeps a file: code in a supported
+/// language, a manifest or build file, or documentation
+fn is_source_or_manifest(relative: &Path) -> bool {
+    let name = relative
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let extension = relative
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+
+    Language::all()
+        .iter()
+        .any(|language| language.file_extensions().contains(&extension))
+        || is_manifest(relative)
+        || Language::commands().any(|command| command.markers.contains(&name))
+        || (name.starts_with("tsconfig") && extension == "json")
+        || name == "jsconfig.json"
+        || name.to_lowercase().starts_with("readme")
+        || extension == "md"
+}
+
+/// Copy a file as a copy-on-write clone if the filesystem supports it, and
+/// byte for byte otherwise. (On macOS, `std::fs::copy` clones by itself.)
+pub fn clone_or_copy(src: &Path, dest: &Path) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    if reflink(src, dest).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(src, dest).map(|_| ())
+}
+
+/// Clone `src` to a new file at `dest` with the `FICLONE` ioctl (Btrfs, XFS)
+#[cfg(target_os = "linux")]
+fn reflink(src: &Path, dest: &Path) -> io::Result<()> {
+    let source = std::fs::File::open(src)?;
+    let target = std::fs::File::create(dest)?;
+    let cloned = rustix::fs::ioctl_ficlone(&target, &source)
+        .map_err(io::Error::from)
+        .and_then(|()| target.set_permissions(source.metadata()?.permissions()));
+    if cloned.is_err() {
+        drop(target);
+        let _ = std::fs::remove_file(dest);
+    }
+    cloned
+}
+
+/// Paths (relative to `repo_path`) of the untracked files and directories git
+/// ignores there
+fn git_ignored_paths(repo_path: &Path) -> anyhow::Result<HashSet<PathBuf>> {
+    let output = std::process::Command::new("git")
+        .args([
+            "ls-files",
+            "--others",
+            "--ignored",
+            "--exclude-standard",
+            "--directory",
+            "-z",
+        ])
+        .current_dir(repo_path)
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(output
+        .stdout
+        .split(|b| *b == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| {
+            // Directories are listed with a trailing slash
+            let path = String::from_utf8_lossy(path);
+            PathBuf::from(path.trim_end_matches('/'))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(strategy: SnapshotStrategy) -> SnapshotPlan {
+        SnapshotPlan {
+            strategy,
+            git_ignored: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_sources_strategy_keeps_sources_and_manifests() {
+        let sources = plan(SnapshotStrategy::Sources);
+        for kept in [
+            "src/main.rs",
+            "web/app.tsx",
+            "Cargo.lock",
+            "app/build.gradle.kts",
+            "tsconfig.build.json",
+            "docs/guide.md",
+            "README",
+        ] {
+            assert!(!sources.skips_file(Path::new(kept)), "{}", kept);
+        }
+        for skipped in ["assets/logo.png", "data.sqlite", ".env"] {
+            assert!(sources.skips_file(Path::new(skipped)), "{}", skipped);
+        }
+        assert!(sources.skips_dir(Path::new("target")));
+        assert!(sources.skips_dir(Path::new("web/node_modules")));
+        assert!(!sources.skips_dir(Path::new("src")));
+
+        // Other strategies keep everything
+        let copy = plan(SnapshotStrategy::Copy);
+        assert!(!copy.skips_file(Path::new("assets/logo.png")));
+        assert!(!copy.skips_dir(Path::new("target")));
+    }
+
+    #[test]
+    fn test_git_ignored_paths_are_skipped() {
+        let mut plan = plan(SnapshotStrategy::Auto);
+        plan.git_ignored.insert(PathBuf::from("target"));
+        plan.git_ignored.insert(PathBuf::from("src/generated.rs"));
+        assert!(plan.skips_dir(Path::new("target")));
+        assert!(plan.skips_file(Path::new("src/generated.rs")));
+        assert!(!plan.skips_file(Path::new("src/lib.rs")));
+    }
+
+    #[test]
+    fn test_hardlink_strategy_copies_build_directories() {
+        let src = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        std::fs::write(src.path().join("lib.rs"), "fn f() {}").unwrap();
+        std::fs::write(src.path().join("fingerprint"), "abc").unwrap();
+        std::fs::write(src.path().join("Cargo.lock"), "version = 3").unwrap();
+
+        let plan = plan(SnapshotStrategy::Hardlink);
+        plan.place(
+            &src.path().join("lib.rs"),
+            &dest.path().join("lib.rs"),
+            Path::new("lib.rs"),
+        )
+        .unwrap();
+        plan.place(
+            &src.path().join("fingerprint"),
+            &dest.path().join("fingerprint"),
+            Path::new("target/debug/fingerprint"),
+        )
+        .unwrap();
+        plan.place(
+            &src.path().join("Cargo.lock"),
+            &dest.path().join("Cargo.lock"),
+            Path::new("Cargo.lock"),
+        )
+        .unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let inode = |path: &Path| std::fs::metadata(path).unwrap().ino();
+            assert_eq!(
+                inode(&src.path().join("lib.rs")),
+                inode(&dest.path().join("lib.rs"))
+            );
+            assert_ne!(
+                inode(&src.path().join("fingerprint")),
+                inode(&dest.path().join("fingerprint"))
+            );
+            assert_ne!(
+                inode(&src.path().join("Cargo.lock")),
+                inode(&dest.path().join("Cargo.lock"))
+            );
+        }
+        assert_eq!(
+            std::fs::read_to_string(dest.path().join("fingerprint")).unwrap(),
+            "abc"
+        );
+    }
+
+    #[test]
+    fn test_clone_or_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), "content").unwrap();
+        clone_or_copy(&dir.path().join("a"), &dir.path().join("b")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("b")).unwrap(),
+            "content"
+        );
+    }
+
+    #[test]
+    fn test_git_ignored_paths() {
+        let repo = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(args)
+                .current_dir(repo.path())
+                .output()
+        };
+        if !git(&["init", "-q"]).is_ok_and(|o| o.status.success()) {
+            // git is not installed
+            return;
+        }
+        std::fs::write(repo.path().join(".gitignore"), "target/\n*.log\n").unwrap();
+        std::fs::create_dir_all(repo.path().join("target/debug")).unwrap();
+        std::fs::write(repo.path().join("target/debug/app"), "").unwrap();
+        std::fs::write(repo.path().join("build.log"), "").unwrap();
+        std::fs::write(repo.path().join("main.rs"), "").unwrap();
+
+        let ignored = git_ignored_paths(repo.path()).unwrap();
+        assert_eq!(
+            ignored,
+            HashSet::from([PathBuf::from("target"), PathBuf::from("build.log")])
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! HTML templates for the dashboard and reports.
+//!
+//! The built-in templates are embedded in the binary. When `web.templates_dir`
+//! is set, a file there with the same name as a built-in template (e.g.
+//! `base.html`) is used instead, so organizations can brand the dashboard and
+//! reports or add columns without recompiling. Templates use Jinja syntax and
+//! are loaded on every render, so edits show up on the next page load.
+
+use anyhow::{Context, Result};
+use minijinja::{Environment, ErrorKind};
+use rust_embed::Embed;
+use serde::Serialize;
+use std::path::{Component, Path};
+
+#[derive(Embed)]
+#[folder = "templates/"]
+struct BuiltinTemplates;
+
+/// A page rendered from a template, with its fields as the template context
+pub trait Page: Serialize {
+    /// Template file name, e.g. `repositories.html`
+    const TEMPLATE: &'static str;
+}
+
+/// Renders templates, preferring overrides from a directory
+pub struct Templates {
+    env: Environment<'static>,
+}
+
+impl Templates {
+    /// Templates from `override_dir` (if any), falling back to the built-in ones
+    pub fn new(override_dir: Option<&Path>) -> Self {
+        let override_dir = override_dir.map(Path::to_path_buf);
+        let mut env = Environment::new();
+        env.set_loader(move |name| load(override_dir.as_deref(), name));
+        Self { env }
+    }
+
+    /// Fail on undefined variables instead of rendering them as empty
+    #[cfg(test)]
+    pub fn strict(mut self) -> Self {
+        self.env
+            .set_undefined_behavior(minijinja::UndefinedBehavior::Strict);
+        self
+    }
+
+    /// Render a template with the given context
+    pub fn render<S: Serialize>(&self, name: &str, context: S) -> Result<String> {
+        self.env
+            .get_template(name)
+            .and_then(|template| template.render(context))
+            .with_context(|| format!("Failed to render template {}", name))
+    }
+
+    /// Render a page with its own template
+    pub fn render_page<P: Page>(&self, page: &P) -> Result<String> {
+        self.render(P::TEMPLATE, page)
+    }
+
+    /// Names of the built-in templates, for copying them as a starting point
+    pub fn builtin_names() -> Vec<String> {
+        BuiltinTemplates::iter()
+            .map(|name| name.into_owned())
+            .collect()
+    }
+
+    /// Source of a built-in template
+    pub fn builtin_source(name: &str) -> Option<String> {
+        BuiltinTemplates::get(name).map(|file| String::from_utf8_lossy(&file.data).into_owned())
+    }
+}
+
+/// Load a template from the override directory, or the built-in one
+fn load(override_dir: Option<&Path>, name: &str) -> Result<Option<String>, minijinja::Error> {
+    // Templates may only refer to files inside the templates directory
+    let relative = Path::new(name);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Ok(None);
+    }
+
+    if let Some(dir) = override_dir {
+        let path = dir.join(relative);
+        match std::fs::read_to_string(&path) {
+            Ok(source) => return Ok(Some(source)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(minijinja::Error::new(
+                    ErrorKind::InvalidOperation,
+                    format!("Failed to read template {}", path.display()),
+                )
+                .with_source(e))
+            }
+        }
+    }
+
+    Ok(Templates::builtin_source(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_builtin_templates_are_embedded() {
+        let names = Templates::builtin_names();
+        assert!(names.contains(&"base.html".to_string()));
+        assert!(names.contains(&"report.html".to_string()));
+    }
+
+    #[test]
+    fn test_override_dir_replaces_builtin_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("base.html"),
+            "<title>ACME</title>{% block content %}{% endblock %}",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("custom.html"),
+            "{% extends \"base.html\" %}{% block content %}{{ name }}{% endblock %}",
+        )
+        .unwrap();
+
+        let templates = Templates::new(Some(dir.path()));
+        assert_eq!(
+            templates
+                .render("custom.html", json!({"name": "<b>app</b>"}))
+                .unwrap(),
+            "<title>ACME</title>&lt;b&gt;app&lt;&#x2f;b&gt;"
+        );
+        // Templates without an override fall back to the built-in ones
+        let report = templates
+            .render(
+                "report.html",
+                json!({"title": "T", "intro_html": "", "diagrams": [], "details_html": ""}),
+            )
+            .unwrap();
+        assert!(report.contains("<title>T</title>"));
+    }
+
+    #[test]
+    fn test_templates_outside_the_directory_are_not_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("theme");
+        std::fs::create_dir(&nested).unwrap();
+        std::fs::write(dir.path().join("secret.html"), "secret").unwrap();
+
+        let templates = Templates::new(Some(&nested));
+        assert!(templates.render("../secret.html", json!({})).is_err());
+        assert!(templates.render("missing.html", json!({})).is_err());
+    }
+}
@@ -0,0 +1,479 @@
+//! Pinned toolchain detection for build and test commands.
+//!
+//! Repositories can pin the toolchain they need (e.g. `rust-toolchain.toml` asking
+//! for nightly, or `.nvmrc` asking for Node 18). Running their commands with
+//! whatever the daemon host defaults to produces false compile errors during
+//! mutation testing, so commands are wrapped to run with the pinned toolchain
+//! (`rustup run <toolchain> ...`, `volta run --node <version> ...` or
+//! `fnm exec --using=<version> ...`), and the toolchain used is recorded on the
+//! results.
+
+use std::path::Path;
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Runs shell commands with the toolchains pinned by a repository.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandRunner {
+    /// Program and arguments prepended to `sh -c <command>`
+    wrapper: Vec<String>,
+    /// Human-readable toolchains in use, e.g. `rust nightly-2024-06-01`
+    toolchains: Vec<String>,
+}
+
+impl CommandRunner {
+    /// Detect pinned toolchains for commands run in `dir`.
+    ///
+    /// Pin files are searched from `dir` up to (and including) `repo_root`, so a
+    /// project nested in a workspace picks up the workspace's pin.
+    pub fn detect(dir: &Path, repo_root: &Path) -> Self {
+        let mut runner = Self::default();
+
+        if let Some(toolchain) = find_rust_toolchain(dir, repo_root) {
+            if is_installed("rustup") {
+                runner.wrapper = vec!["rustup".to_string(), "run".to_string(), toolchain.clone()];
+                runner.toolchains.push(format!("rust {}", toolchain));
+            } else {
+                tracing::warn!(
+                    "Repository pins Rust toolchain '{}' but rustup is not installed; using the default toolchain",
+                    toolchain
+                );
+            }
+        }
+
+        if let Some(version) = find_node_version(dir, repo_root) {
+            if is_installed("volta") {
+                runner.wrapper.extend([
+                    "volta".to_string(),
+                    "run".to_string(),
+                    "--node".to_string(),
+                    version.clone(),
+                ]);
+                runner.toolchains.push(format!("node {}", version));
+            } else if is_installed("fnm") {
+                runner.wrapper.extend([
+                    "fnm".to_string(),
+                    "exec".to_string(),
+                    format!("--using={}", version),
+                ]);
+                runner.toolchains.push(format!("node {}", version));
+            } else {
+                tracing::warn!(
+                    "Repository pins Node version '{}' but neither volta nor fnm is installed; using the default Node",
+                    version
+                );
+            }
+        }
+
+        runner
+    }
+
+    /// Build a command that runs `command` through `sh -c` with the pinned toolchains.
+    ///
+    /// The command runs in its own process group so [`wait_with_output`] can kill
+    /// everything it started (e.g. the `cargo test` spawned by `sh`).
+    pub fn command(&self, command: &str) -> tokio::process::Command {
+        let mut cmd = match self.wrapper.split_first() {
+            Some((program, args)) => {
+                let mut cmd = tokio::process::Command::new(program);
+                cmd.args(args).arg("sh");
+                cmd
+            }
+            None => tokio::process::Command::new("sh"),
+        };
+        cmd.arg("-c").arg(command).kill_on_drop(true);
+        #[cfg(unix)]
+        cmd.process_group(0);
+        cmd
+    }
+
+    /// Description of the pinned toolchains, or None when using host defaults
+    pub fn toolchain(&self) -> Option<String> {
+        if self.toolchains.is_empty() {
+            None
+        } else {
+            Some(self.toolchains.join(", "))
+        }
+    }
+}
+
+/// How waiting for a command ended
+#[derive(Debug)]
+pub enum CommandWait {
+    Finished(std::io::Result<Output>),
+    TimedOut,
+    Cancelled,
+}
+
+/// Wait for a spawned command's output, killing its whole process group if it
+/// times out or `cancel` is cancelled.
+pub async fn wait_with_output(
+    child: tokio::process::Child,
+    timeout: Duration,
+    cancel: &CancellationToken,
+) -> CommandWait {
+    let pid = child.id();
+    let outcome = tokio::select! {
+        result = tokio::time::timeout(timeout, child.wait_with_output()) => match result {
+            Ok(output) => return CommandWait::Finished(output),
+            Err(_) => CommandWait::TimedOut,
+        },
+        _ = cancel.cancelled() => CommandWait::Cancelled,
+    };
+
+    // Dropping the child killed the shell; its own children are still running
+    if let Some(pid) = pid {
+        kill_process_group(pid);
+    }
+    outcome
+}
+
+#[cfg(unix)]
+fn kill_process_group(pgid: u32) {
+    let _ = std::process::Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", pgid)])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_pgid: u32) {}
+
+/// Find the Rust toolchain pinned by `rust-toolchain.toml` or the legacy `rust-toolchain` file.
+pub fn find_rust_toolchain(dir: &Path, repo_root: &Path) -> Option<String> {
+    for ancestor in dir.ancestors() {
+        for file in ["rust-toolchain.toml", "rust-toolchain"] {
+            if let Ok(content) = std::fs::read_to_string(ancestor.join(file)) {
+                if let Some(toolchain) = parse_rust_toolchain(&content) {
+                    return Some(toolchain);
+                }
+            }
+        }
+
+        if ancestor == repo_root {
+            break;
+        }
+    }
+    None
+}
+
+/// Parse the toolchain channel from a toolchain file.
+///
+/// Supports the TOML format (`[toolchain] channel = "..."`) and the legacy
+/// format containing just the channel name.
+pub fn parse_rust_toolchain(content: &str) -> Option<String> {
+    #[derive(serde::Deserialize)]
+    struct ToolchainFile {
+        toolchain: ToolchainSection,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct ToolchainSection {
+        channel: Option<String>,
+    }
+
+    if let Ok(file) = toml::from_str::<ToolchainFile>(content) {
+        return file.toolchain.channel.filter(|c| is_valid_channel(c));
+    }
+
+    let legacy = content.trim();
+    is_valid_channel(legacy).then(|| legacy.to_string())
+}
+
+/// Find the Node version pinned by `.nvmrc`, `.node-version` or `engines.node` in `package.json`.
+pub fn find_node_version(dir: &Path, repo_root: &Path) -> Option<String> {
+    for ancestor in dir.ancestors() {
+        for file in [".nvmrc", ".node-version"] {
+            if let Ok(content) = std::fs::read_to_string(ancestor.join(file)) {
+                if let Some(version) = parse_node_version_file(&content) {
+                    return Some(version);
+                }
+            }
+        }
+
+        if let Ok(content) = std::fs::read_to_string(ancestor.join("package.json")) {
+            if let Some(version) = parse_package_engines(&content) {
+                return Some(version);
+            }
+        }
+
+        if ancestor == repo_root {
+            break;
+        }
+    }
+    None
+}
+
+/// Parse a `.nvmrc`/`.node-version` file, e.g. `v18.17.0`, `20` or `lts/hydrogen`.
+pub fn parse_node_version_file(content: &str) -> Option<String> {
+    let version = content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))?;
+    let version = version.strip_prefix('v').unwrap_or(version);
+    is_valid_node_version(version).then(|| version.to_string())
+}
+
+/// Parse the major Node version from `engines.node` in `package.json`.
+///
+/// Ranges are reduced to the lowest major version of their lower bounds, so
+/// `>=18 <21`, `^18.2.0` and `18.x || 20.x` all select Node 18. Upper bounds
+/// (`<21`, `<= 20`) are ignored, so a range without a lower bound selects none.
+pub fn parse_package_engines(content: &str) -> Option<String> {
+    let package: serde_json::Value = serde_json::from_str(content).ok()?;
+    let range = package.get("engines")?.get("node")?.as_str()?;
+
+    let mut majors = Vec::new();
+    // Whether the previous comparator was a bare `<` or `<=` before the version
+    let mut upper_bound = false;
+    for comparator in range
+        .split(|c: char| c.is_whitespace() || c == '|' || c == ',')
+        .filter(|c| !c.is_empty())
+    {
+        let version = comparator.trim_start_matches(|c: char| !c.is_ascii_digit());
+        let operator = &comparator[..comparator.len() - version.len()];
+        let is_upper_bound = operator.starts_with('<') || (operator.is_empty() && upper_bound);
+        upper_bound = version.is_empty() && operator.starts_with('<');
+        if is_upper_bound {
+            continue;
+        }
+        if let Some(major) = version
+            .split('.')
+            .next()
+            .and_then(|m| m.parse::<u32>().ok())
+        {
+            majors.push(major);
+        }
+    }
+    majors.into_iter().min().map(|major| major.to_string())
+}
+
+/// Node versions are passed to volta/fnm, so only allow plain version names
+fn is_valid_node_version(version: &str) -> bool {
+    !version.is_empty()
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '/' | '*'))
+}
+
+/// Channel names are passed to rustup, so only allow plain toolchain names
+fn is_valid_channel(channel: &str) -> bool {
+    !channel.is_empty()
+        && channel
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+}
+
+/// Check whether a program is installed and runnable
+fn is_installed(program: &str) -> bool {
+    std::process::Command::new(program)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_rust_toolchain_toml() {
+        let content = r#"
+[toolchain]
+channel = "nightly-2024-06-01"
+components = ["rustfmt"]
+"#;
+        assert_eq!(
+            parse_rust_toolchain(content),
+            Some("nightly-2024-06-01".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_rust_toolchain_legacy() {
+        assert_eq!(parse_rust_toolchain("1.70.0\n"), Some("1.70.0".to_string()));
+    }
+
+    #[test]
+    fn test_parse_rust_toolchain_rejects_invalid() {
+        assert_eq!(parse_rust_toolchain(""), None);
+        assert_eq!(parse_rust_toolchain("stable; rm -rf /"), None);
+        assert_eq!(
+            parse_rust_toolchain("[toolchain]\npath = \"/opt/rust\""),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_rust_toolchain_in_ancestor() {
+        let repo = TempDir::new().unwrap();
+        let project = repo.path().join("crates").join("core");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(
+            repo.path().join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"nightly\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_rust_toolchain(&project, repo.path()),
+            Some("nightly".to_string())
+        );
+    }
+
+    #[test]
+    fn test_find_rust_toolchain_stops_at_repo_root() {
+        let outer = TempDir::new().unwrap();
+        std::fs::write(outer.path().join("rust-toolchain"), "nightly").unwrap();
+        let repo = outer.path().join("repo");
+        std::fs::create_dir_all(&repo).unwrap();
+
+        assert_eq!(find_rust_toolchain(&repo, &repo), None);
+    }
+
+    #[test]
+    fn test_parse_node_version_file() {
+        assert_eq!(
+            parse_node_version_file("v18.17.0\n"),
+            Some("18.17.0".to_string())
+        );
+        assert_eq!(
+            parse_node_version_file("lts/hydrogen"),
+            Some("lts/hydrogen".to_string())
+        );
+        assert_eq!(parse_node_version_file(""), None);
+        assert_eq!(parse_node_version_file("20; rm -rf /"), None);
+    }
+
+    #[test]
+    fn test_parse_package_engines() {
+        let package = |range: &str| format!(r#"{{"engines": {{"node": "{}"}}}}"#, range);
+        assert_eq!(
+            parse_package_engines(&package(">=18 <21")),
+            Some("18".to_string())
+        );
+        assert_eq!(
+            parse_package_engines(&package("^18.2.0")),
+            Some("18".to_string())
+        );
+        assert_eq!(
+            parse_package_engines(&package("20.x || 18.x")),
+            Some("18".to_string())
+        );
+        assert_eq!(
+            parse_package_engines(&package(">= 16 < 21")),
+            Some("16".to_string())
+        );
+        assert_eq!(
+            parse_package_engines(&package("<18 || >=20")),
+            Some("20".to_string())
+        );
+        assert_eq!(parse_package_engines(&package("<21")), None);
+        assert_eq!(parse_package_engines(&package("*")), None);
+        assert_eq!(parse_package_engines(r#"{"name": "app"}"#), None);
+    }
+
+    #[test]
+    fn test_find_node_version_prefers_nvmrc() {
+        let repo = TempDir::new().unwrap();
+        let project = repo.path().join("packages").join("web");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(
+            project.join("package.json"),
+            r#"{"engines": {"node": ">=16"}}"#,
+        )
+        .unwrap();
+        std::fs::write(project.join(".nvmrc"), "20\n").unwrap();
+
+        assert_eq!(
+            find_node_version(&project, repo.path()),
+            Some("20".to_string())
+        );
+
+        std::fs::remove_file(project.join(".nvmrc")).unwrap();
+        assert_eq!(
+            find_node_version(&project, repo.path()),
+            Some("16".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_default_runner_uses_sh() {
+        let runner = CommandRunner::default();
+        assert_eq!(runner.toolchain(), None);
+
+        let output = runner.command("echo hello").output().await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+    }
+
+    #[tokio::test]
+    async fn test_wait_with_output_finishes() {
+        let child = CommandRunner::default()
+            .command("echo done")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        match wait_with_output(child, Duration::from_secs(10), &CancellationToken::new()).await {
+            CommandWait::Finished(output) => {
+                assert_eq!(
+                    String::from_utf8_lossy(&output.unwrap().stdout).trim(),
+                    "done"
+                )
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_with_output_cancelled() {
+        let cancel = CancellationToken::new();
+        // A compound command keeps `sh` around as the parent of `sleep`
+        let child = CommandRunner::default()
+            .command("sleep 30; echo never")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceller.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let outcome = wait_with_output(child, Duration::from_secs(60), &cancel).await;
+        assert!(matches!(outcome, CommandWait::Cancelled));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_wait_with_output_timeout() {
+        let child = CommandRunner::default()
+            .command("sleep 30")
+            .spawn()
+            .unwrap();
+        let outcome =
+            wait_with_output(child, Duration::from_millis(50), &CancellationToken::new()).await;
+        assert!(matches!(outcome, CommandWait::TimedOut));
+    }
+
+    #[test]
+    fn test_wrapped_command() {
+        let runner = CommandRunner {
+            wrapper: vec!["rustup".into(), "run".into(), "nightly".into()],
+            toolchains: vec!["rust nightly".into()],
+        };
+        assert_eq!(runner.toolchain(), Some("rust nightly".to_string()));
+
+        let cmd = runner.command("cargo test");
+        let std_cmd = cmd.as_std();
+        assert_eq!(std_cmd.get_program(), "rustup");
+        let args: Vec<_> = std_cmd.get_args().collect();
+        assert_eq!(args, ["run", "nightly", "sh", "-c", "cargo test"]);
+    }
+}
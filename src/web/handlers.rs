@@ -1,35 +1,72 @@
 //! HTTP request handlers for the web dashboard and API.
 //!
-//! HTML handlers render Askama templates for the browser UI.
+//! HTML handlers render the page templates (see `crate::theme`) for the browser UI.
 //! API handlers return JSON for programmatic access and AJAX requests.
 
-use crate::analyzer::OllamaClient;
-use crate::config::{Config, OllamaEndpoint};
-use crate::db::{AnalysisResult, DaemonState, Database, Repository};
+use crate::analyzer::findings::SEVERITIES;
+use crate::analyzer::{text_diff, AnalysisType, OllamaClient};
+use crate::chat;
+use crate::config::{Config, EndpointLocation, EndpointTier, OllamaEndpoint, ScheduleWindow};
+use crate::daemon::{
+    analysis_coverage, schedule_paused_until, DaemonError, DaemonStatus, DashboardUpdate,
+    EndpointHealth, FILE_STATUS_BINARY, FILE_STATUS_GENERATED, FILE_STATUS_LOSSY,
+    FILE_STATUS_MINIFIED, FILE_STATUS_VENDORED,
+};
+use crate::db::{
+    normalize_tags, AnalysisResult, CommandOverrides, DaemonState, DailyUsage, Database, Diagram,
+    FileStatus, Issue, Job, JobCounts, MutationFilter, MutationReplacement, MutationResult,
+    Repository, UsageTotal, ANALYSIS_PASSES, AUDIT_ARCHIVE_CHANGED, AUDIT_COMMANDS_CHANGED,
+    AUDIT_PURGED, AUDIT_SENSITIVE_CHANGED, MUTATION_STATUSES,
+};
+use crate::diagram::DiagramType;
+use crate::duplication;
+use crate::issues::{matching_issues, IssueClient, IssueDraft, IssueSource};
+use crate::logs::LogFilter;
+use crate::onboarding;
+use crate::repo_config::RepoConfig;
+use crate::report::{badge, sarif, Report, ReportFormat};
+use crate::search;
 use crate::AppState;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::{Html, IntoResponse, Response},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Response,
+    },
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::Infallible;
 use std::path::Path as FilePath;
 use std::sync::Arc;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
 
 use super::templates::{
-    render_markdown, AnalysisResultView, MutationResultView, MutationResultsTemplate,
-    RepositoriesTemplate, RepositoryArchitectureTemplate, RepositoryDiagramsTemplate,
-    RepositoryFilesTemplate, SettingsTemplate,
+    render_markdown, AnalysisResultView, ChatMessageView, DuplicateClusterView, DuplicatePairView,
+    EndpointHealthView, FileHistoryTemplate, FindingView, FindingsTemplate, GlobalFindingView,
+    HistoryEntryView, HistoryTemplate, LogsTemplate, MutationResultView, MutationResultsTemplate,
+    RepositoriesTemplate, RepositoryArchitectureTemplate, RepositoryChatTemplate,
+    RepositoryDiagramsTemplate, RepositoryDuplicationTemplate, RepositoryFilesTemplate,
+    RepositoryView, ScanRunView, SearchTemplate, SettingsTemplate, SkippedFileView,
 };
-use askama::Template;
+use crate::theme::{Page, Templates};
+
+/// Templates honoring the configured `web.templates_dir`
+async fn page_templates(state: &AppState) -> Templates {
+    Templates::new(state.config.read().await.web.templates_dir.as_deref())
+}
 
-fn render_template<T: Template>(template: T) -> Response {
-    match template.render() {
+async fn render_template<P: Page>(state: &AppState, page: P) -> Response {
+    match page_templates(state).await.render_page(&page) {
         Ok(html) => Html(html).into_response(),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Template error: {}", e),
+            format!("Template error: {:#}", e),
         )
             .into_response(),
     }
@@ -46,544 +83,3469 @@ async fn get_repo_or_error(db: &Database, id: i64) -> Result<Repository, Respons
     }
 }
 
-pub async fn list_repositories(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let repositories = state.db.get_repositories().await.unwrap_or_default();
-    render_template(RepositoriesTemplate { repositories })
+/// Health scores shown in the trend of each repository
+const SCORE_TREND_LENGTH: i64 = 30;
+
+/// Query parameters for listing repositories
+#[derive(Deserialize, Debug, Default)]
+pub struct RepositoriesQuery {
+    /// Only repositories with this tag
+    pub tag: Option<String>,
+    /// List the archived repositories instead of the active ones
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// All repositories' tags, and the repositories with `tag` (all if None or empty)
+fn filter_by_tag(
+    repositories: Vec<Repository>,
+    tag: Option<&str>,
+) -> (Vec<Repository>, Vec<String>) {
+    let mut tags: Vec<String> = repositories
+        .iter()
+        .flat_map(|repo| repo.tags.iter().cloned())
+        .collect();
+    tags.sort();
+    tags.dedup();
+
+    let repositories = match tag.map(str::trim).filter(|tag| !tag.is_empty()) {
+        Some(tag) => {
+            let tag = tag.to_lowercase();
+            repositories
+                .into_iter()
+                .filter(|repo| repo.has_tag(&tag))
+                .collect()
+        }
+        None => repositories,
+    };
+    (repositories, tags)
+}
+
+pub async fn list_repositories(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RepositoriesQuery>,
+) -> impl IntoResponse {
+    let (archived, active): (Vec<Repository>, Vec<Repository>) = state
+        .db
+        .get_repositories()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .partition(Repository::archived);
+    let archived_count = archived.len();
+    let (repositories, tags) = filter_by_tag(
+        if params.archived { archived } else { active },
+        params.tag.as_deref(),
+    );
+    let mut scores = state
+        .db
+        .get_recent_scores(SCORE_TREND_LENGTH)
+        .await
+        .unwrap_or_default();
+    render_template(
+        &state,
+        RepositoriesTemplate {
+            repositories: repositories
+                .into_iter()
+                .map(|repo| {
+                    let trend = scores.remove(&repo.id).unwrap_or_default();
+                    RepositoryView::new(repo, trend)
+                })
+                .collect(),
+            analysis_passes: ANALYSIS_PASSES,
+            tags,
+            selected_tag: params.tag.filter(|tag| !tag.trim().is_empty()),
+            archived: params.archived,
+            archived_count,
+        },
+    )
+    .await
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct AddRepositoryRequest {
+    /// Local path, or a Git URL to clone
     path: String,
     name: String,
+    /// Branch to check out of a Git URL
+    #[serde(default)]
+    branch: Option<String>,
+    /// Run a quick scan now and hold the repository back from full scans
+    /// until its detected projects are confirmed (set by the dashboard form)
+    #[serde(default)]
+    onboarding: bool,
 }
 
 pub async fn add_repository(
     State(state): State<Arc<AppState>>,
     Json(req): Json<AddRepositoryRequest>,
 ) -> impl IntoResponse {
-    // Validate path exists and is a directory
-    let path = FilePath::new(&req.path);
-    if !path.exists() {
-        return (
+    let data_dir = state.config.read().await.data_dir();
+    let bad_request = |error: String| {
+        (
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({ "error": "Path does not exist" })),
+            Json(serde_json::json!({ "error": error })),
         )
-            .into_response();
+            .into_response()
+    };
+    let branch = req
+        .branch
+        .as_deref()
+        .map(str::trim)
+        .filter(|branch| !branch.is_empty());
+
+    let id = if crate::remote::is_git_url(&req.path) {
+        tracing::info!(
+            "Adding repository: name={}, url={}, branch={}",
+            req.name,
+            crate::remote::redact(&req.path),
+            branch.unwrap_or("default")
+        );
+        match crate::remote::add_repository(&state.db, &data_dir, &req.path, branch, &req.name)
+            .await
+        {
+            Ok((id, _)) => id,
+            Err(e) => {
+                tracing::warn!("Failed to add repository: {:#}", e);
+                return bad_request(format!("{:#}", e));
+            }
+        }
+    } else {
+        if branch.is_some() {
+            return bad_request("A branch can only be selected for a Git URL".to_string());
+        }
+        let canonical_path =
+            match crate::project::resolve_repository_path(FilePath::new(&req.path), &data_dir) {
+                Ok(path) => path.to_string_lossy().to_string(),
+                Err(e) => return bad_request(e.to_string()),
+            };
+
+        tracing::info!(
+            "Adding repository: name={}, path={} (canonicalized from {})",
+            req.name,
+            canonical_path,
+            req.path
+        );
+
+        match state.db.add_repository(&canonical_path, &req.name).await {
+            Ok(id) => id,
+            Err(e) => {
+                tracing::warn!("Failed to add repository: {}", e);
+                return bad_request("Failed to add repository".to_string());
+            }
+        }
+    };
+
+    tracing::info!("Repository added successfully: id={}", id);
+    if req.onboarding {
+        if let Err(e) = state.db.start_onboarding(id).await {
+            tracing::warn!("Failed to start onboarding of repository {}: {}", id, e);
+        } else if let Ok(Some(repo)) = state.db.get_repository(id).await {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let config = state.config.read().await.clone();
+                onboarding::run(&state.db, &config, state.daemon.health(), &repo).await;
+            });
+        }
+        return (StatusCode::CREATED, Json(serde_json::json!({ "id": id }))).into_response();
     }
-    if !path.is_dir() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({ "error": "Path must be a directory" })),
-        )
-            .into_response();
+    // If we're in the scheduled window, trigger a scan so the new repo is processed immediately
+    let in_window = schedule_paused_until(&state.db).await.is_none()
+        && state.config.read().await.schedule.is_in_window();
+    if in_window {
+        state.daemon.trigger_scan();
+        tracing::info!("Triggered scan for newly added repository (in scheduled window)");
     }
+    (StatusCode::CREATED, Json(serde_json::json!({ "id": id }))).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct ScanDirRequest {
+    /// Directory to search for repositories
+    path: String,
+    /// How many directory levels to search (default 2, at most 8)
+    #[serde(default)]
+    depth: Option<usize>,
+    /// Add the new repositories instead of only listing them
+    #[serde(default)]
+    register: bool,
+}
+
+/// A repository found by a directory scan
+#[derive(Serialize)]
+pub struct ScanDirEntry {
+    #[serde(flatten)]
+    repository: crate::project::FoundRepository,
+    /// "new", "added" (registered, possibly by this request) or
+    /// "unconfigured" (no noctum.toml, so it can't be added)
+    status: &'static str,
+    /// ID of the registered repository
+    id: Option<i64>,
+}
 
-    // Canonicalize the path to get an absolute, normalized path.
-    // This resolves symlinks, removes `.` and `..` components, and ensures
-    // we store a consistent path representation in the database.
-    let canonical_path = match path.canonicalize() {
-        Ok(p) => p.to_string_lossy().to_string(),
+/// API: Find the repositories in a directory, and add the new ones if asked
+pub async fn api_scan_dir(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ScanDirRequest>,
+) -> impl IntoResponse {
+    let data_dir = state.config.read().await.data_dir();
+    let depth = req.depth.unwrap_or(2).clamp(1, 8);
+    let dir = std::path::PathBuf::from(&req.path);
+    let found =
+        match tokio::task::spawn_blocking(move || crate::project::find_repositories(&dir, depth))
+            .await
+        {
+            Ok(Ok(found)) => found,
+            Ok(Err(e)) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "error": format!("{:#}", e) })),
+                )
+                    .into_response()
+            }
+            Err(e) => {
+                tracing::error!("Directory scan panicked: {}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": "Directory scan failed" })),
+                )
+                    .into_response();
+            }
+        };
+    let registered: HashMap<String, i64> = match state.db.get_repositories().await {
+        Ok(repos) => repos.into_iter().map(|repo| (repo.path, repo.id)).collect(),
         Err(e) => {
-            tracing::warn!("Failed to canonicalize path {}: {}", req.path, e);
+            tracing::error!("Failed to fetch repositories: {}", e);
             return (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": "Failed to resolve path" })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Database error" })),
             )
                 .into_response();
         }
     };
 
-    tracing::info!(
-        "Adding repository: name={}, path={} (canonicalized from {})",
-        req.name,
-        canonical_path,
-        req.path
-    );
-
-    match state.db.add_repository(&canonical_path, &req.name).await {
-        Ok(id) => {
-            tracing::info!("Repository added successfully: id={}", id);
-            // If we're in the scheduled window, trigger a scan so the new repo is processed immediately
-            let in_window = state.config.read().await.schedule.is_in_window();
-            if in_window {
-                state.daemon.trigger_scan();
-                tracing::info!("Triggered scan for newly added repository (in scheduled window)");
+    let mut entries = Vec::new();
+    let mut added = 0;
+    for repository in found {
+        let mut entry = match registered.get(&*repository.path.to_string_lossy()) {
+            Some(&id) => ScanDirEntry {
+                repository,
+                status: "added",
+                id: Some(id),
+            },
+            None if repository.configured => ScanDirEntry {
+                repository,
+                status: "new",
+                id: None,
+            },
+            None => ScanDirEntry {
+                repository,
+                status: "unconfigured",
+                id: None,
+            },
+        };
+        if req.register && entry.status == "new" {
+            let path = &entry.repository.path;
+            let result = match crate::project::resolve_repository_path(path, &data_dir) {
+                Ok(path) => {
+                    state
+                        .db
+                        .add_repository(&path.to_string_lossy(), &entry.repository.name)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(id) => {
+                    tracing::info!("Added repository {} from a directory scan", path.display());
+                    entry.status = "added";
+                    entry.id = Some(id);
+                    added += 1;
+                }
+                Err(e) => tracing::warn!("Failed to add repository {}: {:#}", path.display(), e),
             }
-            (StatusCode::CREATED, Json(serde_json::json!({ "id": id }))).into_response()
         }
+        entries.push(entry);
+    }
+
+    // As for a single repository, process the new ones right away in the window
+    if added > 0
+        && schedule_paused_until(&state.db).await.is_none()
+        && state.config.read().await.schedule.is_in_window()
+    {
+        state.daemon.trigger_scan();
+    }
+    (StatusCode::OK, Json(entries)).into_response()
+}
+
+/// API: Get the quick scan and confirmation state of a repository added from
+/// the dashboard
+pub async fn api_repository_onboarding(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match state.db.get_onboarding(id).await {
+        Ok(Some(onboarding)) => (StatusCode::OK, Json(onboarding)).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Repository has no onboarding" })),
+        )
+            .into_response(),
         Err(e) => {
-            tracing::warn!("Failed to add repository: {}", e);
+            tracing::error!("Failed to fetch onboarding of repository {}: {}", id, e);
             (
-                StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({ "error": "Failed to add repository" })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch onboarding" })),
             )
                 .into_response()
         }
     }
 }
 
-pub async fn delete_repository(
+/// API: Confirm the detected projects of a repository, including it in full
+/// scans. Triggers a scan if the scheduled window is open.
+pub async fn api_confirm_onboarding(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
 ) -> impl IntoResponse {
-    match state.db.delete_repository(id).await {
+    match state.db.confirm_onboarding(id).await {
         Ok(true) => {
-            tracing::info!("Deleted repository {}", id);
-            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+            tracing::info!("Confirmed repository {} for full scans", id);
+            let in_window = schedule_paused_until(&state.db).await.is_none()
+                && state.config.read().await.schedule.is_in_window();
+            if in_window {
+                state.daemon.trigger_scan();
+            }
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "success": true, "scan_triggered": in_window })),
+            )
+                .into_response()
         }
         Ok(false) => (
             StatusCode::NOT_FOUND,
-            Json(serde_json::json!({ "error": "Repository not found" })),
+            Json(serde_json::json!({ "error": "Repository is not awaiting confirmation" })),
         )
             .into_response(),
         Err(e) => {
-            tracing::error!("Failed to delete repository {}: {}", id, e);
+            tracing::error!("Failed to confirm repository {}: {}", id, e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to delete repository" })),
+                Json(serde_json::json!({ "error": "Failed to confirm repository" })),
             )
                 .into_response()
         }
     }
 }
 
-/// Legacy route - redirects to architecture tab
-pub async fn repository_results(Path(id): Path<i64>) -> impl IntoResponse {
-    axum::response::Redirect::permanent(&format!("/repositories/{}/architecture", id))
+/// Record an audit entry, logging instead of failing if it can't be written
+async fn audit(state: &AppState, id: i64, action: &str, detail: &str) {
+    tracing::info!("Repository {}: {}", id, detail);
+    if let Err(e) = state.db.record_audit(Some(id), action, detail).await {
+        tracing::warn!("Failed to record audit entry for repository {}: {}", id, e);
+    }
 }
 
-pub async fn repository_architecture(
+/// Archive a repository: it is no longer scanned or listed by default, but its
+/// results are kept. The response carries the token needed to purge it.
+pub async fn archive_repository(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
 ) -> impl IntoResponse {
-    let repository = match get_repo_or_error(&state.db, id).await {
-        Ok(repo) => repo,
-        Err(response) => return response,
-    };
+    match state.db.set_repository_archived(id, true).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Repository not found" })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to archive repository {}: {}", id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to archive repository" })),
+            )
+                .into_response();
+        }
+    }
 
-    let all_results = state
+    if let Err(e) = state.db.delete_pending_jobs(id).await {
+        tracing::warn!("Failed to drop pending jobs of repository {}: {}", id, e);
+    }
+    audit(
+        &state,
+        id,
+        AUDIT_ARCHIVE_CHANGED,
+        "Archived: scanning stopped, results kept",
+    )
+    .await;
+    let purge_token = state
         .db
-        .get_all_repository_results(id)
+        .get_repository(id)
         .await
-        .unwrap_or_default();
-
-    let architecture_summary = all_results
-        .into_iter()
-        .find(|r| r.analysis_type == "architecture_summary");
-
-    let architecture_summary_html = architecture_summary
-        .as_ref()
-        .map(|s| render_markdown(&s.result))
-        .unwrap_or_default();
-
-    render_template(RepositoryArchitectureTemplate {
-        repository,
-        architecture_summary,
-        architecture_summary_html,
-    })
+        .ok()
+        .flatten()
+        .and_then(|repo| repo.purge_token());
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "success": true, "purge_token": purge_token })),
+    )
+        .into_response()
 }
 
-pub async fn repository_files(
+/// Restore an archived repository, so it is scanned and listed again
+pub async fn restore_repository(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
 ) -> impl IntoResponse {
-    let repository = match get_repo_or_error(&state.db, id).await {
-        Ok(repo) => repo,
-        Err(response) => return response,
-    };
-
-    let all_results = state
-        .db
-        .get_all_repository_results(id)
-        .await
-        .unwrap_or_default();
-
-    let file_results: Vec<AnalysisResultView> = all_results
-        .into_iter()
-        .filter(|r| r.analysis_type == "code_understanding")
-        .map(|r| AnalysisResultView::from_result(r, &repository.path))
-        .collect();
+    match state.db.set_repository_archived(id, false).await {
+        Ok(true) => {
+            audit(
+                &state,
+                id,
+                AUDIT_ARCHIVE_CHANGED,
+                "Restored: scanning resumed",
+            )
+            .await;
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Repository not found" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to restore repository {}: {}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to restore repository" })),
+            )
+                .into_response()
+        }
+    }
+}
 
-    render_template(RepositoryFilesTemplate {
-        repository,
-        file_results,
-    })
+#[derive(Deserialize)]
+pub struct PurgeRequest {
+    /// The `purge_token` returned when the repository was archived
+    #[serde(default)]
+    confirm: String,
 }
 
-pub async fn mutation_results(
+/// Delete an archived repository and all of its results. Requires the
+/// repository's purge token, so nothing is deleted by accident.
+pub async fn purge_repository(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
+    Json(req): Json<PurgeRequest>,
 ) -> impl IntoResponse {
-    let repository = match get_repo_or_error(&state.db, id).await {
-        Ok(repo) => repo,
-        Err(response) => return response,
+    let repo = match state.db.get_repository(id).await {
+        Ok(Some(repo)) => repo,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Repository not found" })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch repository {}: {}", id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to purge repository" })),
+            )
+                .into_response();
+        }
     };
+    let Some(token) = repo.purge_token() else {
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "error": "Archive the repository before purging it" })),
+        )
+            .into_response();
+    };
+    if req.confirm.trim() != token {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Invalid confirmation token: pass the purge_token returned by the archive request"
+            })),
+        )
+            .into_response();
+    }
 
-    let raw_results = state.db.get_mutation_results(id).await.unwrap_or_default();
-    let summary = state.db.get_mutation_summary(id).await.unwrap_or_default();
-
-    let results: Vec<MutationResultView> = raw_results
-        .into_iter()
-        .map(|r| MutationResultView::from_result(r, &repository.path))
-        .collect();
-
-    let mutation_score_percent = format!("{:.1}", summary.mutation_score() * 100.0);
+    match state.db.delete_repository(id).await {
+        Ok(true) => {
+            let data_dir = state.config.read().await.data_dir();
+            crate::remote::remove_repository_checkout(&repo, &data_dir);
+            let detail = format!(
+                "Purged {} ({}) and all of its results",
+                repo.name, repo.path
+            );
+            audit(&state, id, AUDIT_PURGED, &detail).await;
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Repository not found" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to purge repository {}: {}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to purge repository" })),
+            )
+                .into_response()
+        }
+    }
+}
 
-    render_template(MutationResultsTemplate {
-        repository,
-        results,
-        summary,
-        mutation_score_percent,
-    })
+#[derive(Deserialize)]
+pub struct AnalysisTypesRequest {
+    analysis_types: Vec<String>,
 }
 
-pub async fn repository_diagrams(
+/// Select which analysis passes the daemon runs for a repository
+pub async fn update_repository_analysis_types(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
+    Json(req): Json<AnalysisTypesRequest>,
 ) -> impl IntoResponse {
-    let repository = match get_repo_or_error(&state.db, id).await {
-        Ok(repo) => repo,
-        Err(response) => return response,
-    };
-
-    let diagrams = state.db.get_latest_diagrams(id).await.unwrap_or_default();
-
-    render_template(RepositoryDiagramsTemplate {
-        repository,
-        diagrams,
-    })
-}
-
-pub async fn settings(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let config = state.config.read().await;
-    let endpoints = config.endpoints.clone();
-    let start_hour = config.schedule.start_hour;
-    let end_hour = config.schedule.end_hour;
-    let config_path = Config::default_config_path()
-        .map(|p| p.display().to_string())
-        .unwrap_or_else(|| "(unknown)".to_string());
+    if let Some(unknown) = req
+        .analysis_types
+        .iter()
+        .find(|pass| !ANALYSIS_PASSES.contains(&pass.as_str()))
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!(
+                    "Unknown analysis type '{}': expected some of {}",
+                    unknown,
+                    ANALYSIS_PASSES.join(", ")
+                )
+            })),
+        )
+            .into_response();
+    }
 
-    render_template(SettingsTemplate {
-        endpoints,
-        start_hour,
-        end_hour,
-        config_path,
-    })
+    let passes: Vec<&str> = ANALYSIS_PASSES
+        .iter()
+        .copied()
+        .filter(|pass| req.analysis_types.iter().any(|p| p == pass))
+        .collect();
+    match state.db.set_repository_analysis_types(id, &passes).await {
+        Ok(true) => {
+            tracing::info!("Set analysis types of repository {} to {:?}", id, passes);
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Repository not found" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(
+                "Failed to update analysis types of repository {}: {}",
+                id,
+                e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to update repository" })),
+            )
+                .into_response()
+        }
+    }
 }
 
-/// Add a new Ollama endpoint
 #[derive(Deserialize)]
-pub struct AddEndpointRequest {
-    name: String,
-    url: String,
-    model: String,
+pub struct SensitiveRequest {
+    sensitive: bool,
 }
 
-pub async fn add_endpoint(
+/// Mark a repository as sensitive, restricting its analysis to local endpoints
+pub async fn update_repository_sensitive(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<AddEndpointRequest>,
+    Path(id): Path<i64>,
+    Json(req): Json<SensitiveRequest>,
 ) -> impl IntoResponse {
-    let new_endpoint = OllamaEndpoint {
-        name: req.name,
-        url: req.url,
-        model: req.model,
-        enabled: true,
-    };
-
+    match state.db.set_repository_sensitive(id, req.sensitive).await {
+        Ok(true) => {
+            let detail = if req.sensitive {
+                "Marked sensitive: analysis restricted to local endpoints"
+            } else {
+                "Unmarked sensitive: analysis may use any endpoint"
+            };
+            audit(&state, id, AUDIT_SENSITIVE_CHANGED, detail).await;
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Repository not found" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(
+                "Failed to update sensitive flag of repository {}: {}",
+                id,
+                e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to update repository" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Replace the build and test command overrides of a repository
+pub async fn update_repository_commands(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(req): Json<CommandOverrides>,
+) -> impl IntoResponse {
+    let commands = match req.normalize() {
+        Ok(commands) => commands,
+        Err(error) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": error })),
+            )
+                .into_response()
+        }
+    };
+    match state.db.set_repository_commands(id, &commands).await {
+        Ok(true) => {
+            let detail = if commands.is_empty() {
+                "Cleared command overrides".to_string()
+            } else {
+                format!("Set command overrides: {}", commands.summary())
+            };
+            audit(&state, id, AUDIT_COMMANDS_CHANGED, &detail).await;
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "commands": commands })),
+            )
+                .into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Repository not found" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to update commands of repository {}: {}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to update repository" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TagsRequest {
+    tags: Vec<String>,
+}
+
+/// Replace the tags of a repository
+pub async fn update_repository_tags(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(req): Json<TagsRequest>,
+) -> impl IntoResponse {
+    let tags = match normalize_tags(&req.tags) {
+        Ok(tags) => tags,
+        Err(error) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": error })),
+            )
+                .into_response()
+        }
+    };
+    match state.db.set_repository_tags(id, &tags).await {
+        Ok(true) => (StatusCode::OK, Json(serde_json::json!({ "tags": tags }))).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Repository not found" })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to update tags of repository {}: {}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to update repository" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Legacy route - redirects to architecture tab
+pub async fn repository_results(Path(id): Path<i64>) -> impl IntoResponse {
+    axum::response::Redirect::permanent(&format!("/repositories/{}/architecture", id))
+}
+
+pub async fn repository_architecture(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+
+    let all_results = state
+        .db
+        .get_all_repository_results(id)
+        .await
+        .unwrap_or_default();
+
+    let architecture_summary = all_results
+        .into_iter()
+        .find(|r| r.analysis_type == "architecture_summary");
+
+    let architecture_summary_html = architecture_summary
+        .as_ref()
+        .map(|s| render_markdown(&s.result))
+        .unwrap_or_default();
+
+    let onboarding = state
+        .db
+        .get_onboarding(id)
+        .await
+        .ok()
+        .flatten()
+        .filter(|o| o.awaits_confirmation());
+
+    render_template(
+        &state,
+        RepositoryArchitectureTemplate {
+            repository,
+            architecture_summary,
+            architecture_summary_html,
+            onboarding,
+        },
+    )
+    .await
+}
+
+/// Recently resolved findings listed on the files page
+const RESOLVED_FINDINGS_SHOWN: i64 = 20;
+
+pub async fn repository_files(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+
+    let all_results = state
+        .db
+        .get_all_repository_results(id)
+        .await
+        .unwrap_or_default();
+
+    let statuses = state.db.get_file_statuses(id).await.unwrap_or_default();
+    let (mut file_results, skipped_files) = file_inventory(all_results, statuses, &repository.path);
+    let known_issues = state.db.get_issues(id).await.unwrap_or_default();
+    link_related_issues(&mut file_results, &known_issues);
+
+    let findings: Vec<FindingView> = state
+        .db
+        .get_findings(id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|f| FindingView::from_finding(f, &repository.path))
+        .collect();
+    let mut finding_categories: Vec<String> = findings.iter().map(|f| f.category.clone()).collect();
+    finding_categories.sort();
+    finding_categories.dedup();
+    let resolved_findings = state
+        .db
+        .get_resolved_findings(id, RESOLVED_FINDINGS_SHOWN)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|f| FindingView::from_finding(f, &repository.path))
+        .collect();
+
+    let stale_files = state.db.count_stale_files(id).await.unwrap_or_default();
+    let reanalysis_requested = state
+        .db
+        .get_reanalysis_requests(id)
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|path| relative_to_repo(path, &repository.path))
+        .collect();
+
+    render_template(
+        &state,
+        RepositoryFilesTemplate {
+            repository,
+            file_results,
+            skipped_files,
+            findings,
+            finding_categories,
+            resolved_findings,
+            reanalysis_requested,
+            stale_files,
+        },
+    )
+    .await
+}
+
+pub async fn mutation_results(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+
+    let raw_results = state.db.get_mutation_results(id).await.unwrap_or_default();
+    let summary = state.db.get_mutation_summary(id).await.unwrap_or_default();
+    let deferral = state.db.get_mutation_deferral(id).await.unwrap_or(None);
+
+    let mut test_suggestions = state.db.get_test_suggestions(id).await.unwrap_or_default();
+
+    let results: Vec<MutationResultView> = raw_results
+        .into_iter()
+        .map(|r| {
+            let suggestions = test_suggestions.remove(&r.id).unwrap_or_default();
+            MutationResultView {
+                test_suggestions: suggestions,
+                ..MutationResultView::from_result(r, &repository.path)
+            }
+        })
+        .collect();
+
+    let mutation_score_percent = format!("{:.1}", summary.mutation_score() * 100.0);
+
+    render_template(
+        &state,
+        MutationResultsTemplate {
+            repository,
+            results,
+            summary,
+            mutation_score_percent,
+            deferral,
+        },
+    )
+    .await
+}
+
+pub async fn repository_diagrams(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+
+    let diagrams = state.db.get_latest_diagrams(id).await.unwrap_or_default();
+    let mut versions = HashMap::new();
+    for diagram in &diagrams {
+        let history = state
+            .db
+            .get_diagram_versions(id, &diagram.diagram_type)
+            .await
+            .unwrap_or_default();
+        versions.insert(
+            diagram.diagram_type.clone(),
+            history.into_iter().map(|v| v.id).collect(),
+        );
+    }
+
+    render_template(
+        &state,
+        RepositoryDiagramsTemplate {
+            repository,
+            diagrams,
+            versions,
+        },
+    )
+    .await
+}
+
+/// API: Get the latest diagram of each type for a repository
+pub async fn api_repository_diagrams(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(response) = get_repo_or_error(&state.db, id).await {
+        return response;
+    }
+
+    match state.db.get_latest_diagrams(id).await {
+        Ok(diagrams) => Json(diagrams).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch diagrams of repository {}: {}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch diagrams" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// API: Get the saved versions of one diagram type for a repository, oldest first
+pub async fn api_diagram_versions(
+    State(state): State<Arc<AppState>>,
+    Path((id, diagram_type)): Path<(i64, DiagramType)>,
+) -> impl IntoResponse {
+    if let Err(response) = get_repo_or_error(&state.db, id).await {
+        return response;
+    }
+
+    match state
+        .db
+        .get_diagram_versions(id, diagram_type.as_str())
+        .await
+    {
+        Ok(versions) => Json(versions).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch {} diagram versions: {}", diagram_type, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch diagram versions" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Look up a diagram by ID, or return an error response
+async fn get_diagram_or_error(db: &Database, id: i64) -> Result<Diagram, Response> {
+    match db.get_diagram(id).await {
+        Ok(Some(diagram)) => Ok(diagram),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Diagram not found" })),
+        )
+            .into_response()),
+        Err(e) => {
+            tracing::error!("Failed to fetch diagram {}: {}", id, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch diagram" })),
+            )
+                .into_response())
+        }
+    }
+}
+
+/// API: Get one saved version of a diagram
+pub async fn api_diagram(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match get_diagram_or_error(&state.db, id).await {
+        Ok(diagram) => Json(diagram).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// Download file name of a diagram: `<repository>-<type>-<id>.<extension>`
+async fn diagram_file_name(db: &Database, diagram: &Diagram, extension: &str) -> String {
+    let repository = db
+        .get_repository(diagram.repository_id)
+        .await
+        .ok()
+        .flatten()
+        .map(|repo| {
+            repo.name
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_else(|| "diagram".to_string());
+    format!(
+        "{}-{}-{}.{}",
+        repository, diagram.diagram_type, diagram.id, extension
+    )
+}
+
+/// API: Download the source of a diagram, GraphViz DOT (`.dot`) or Mermaid (`.mmd`)
+pub async fn api_diagram_dot(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let diagram = match get_diagram_or_error(&state.db, id).await {
+        Ok(diagram) => diagram,
+        Err(response) => return response,
+    };
+
+    let (content_type, extension) = if diagram.format == "mermaid" {
+        ("text/plain; charset=utf-8", "mmd")
+    } else {
+        ("text/vnd.graphviz; charset=utf-8", "dot")
+    };
+    let file_name = diagram_file_name(&state.db, &diagram, extension).await;
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        diagram.dot_content,
+    )
+        .into_response()
+}
+
+/// API: Download the rendered SVG of a diagram. Mermaid diagrams are rendered
+/// in the browser, so the server has no SVG of them.
+pub async fn api_diagram_svg(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let diagram = match get_diagram_or_error(&state.db, id).await {
+        Ok(diagram) => diagram,
+        Err(response) => return response,
+    };
+    if diagram.svg_content.is_empty() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": "Diagram has no server-rendered SVG; Mermaid diagrams are rendered in the browser"
+            })),
+        )
+            .into_response();
+    }
+
+    let file_name = diagram_file_name(&state.db, &diagram, "svg").await;
+    (
+        [
+            (header::CONTENT_TYPE, "image/svg+xml".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        diagram.svg_content,
+    )
+        .into_response()
+}
+
+/// Seconds a regeneration request waits for the new diagram by default, and at most
+const DEFAULT_REGENERATE_WAIT_SECS: u64 = 60;
+const MAX_REGENERATE_WAIT_SECS: u64 = 600;
+
+/// How often a regeneration request checks for the new diagram
+const REGENERATE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Query parameters for the diagram regeneration API
+#[derive(Deserialize, Debug, Default)]
+pub struct RegenerateQuery {
+    /// Seconds to wait for the new diagram (0 returns right away)
+    pub wait: Option<u64>,
+}
+
+/// API: Generate a diagram again, even if its sources are unchanged. Triggers
+/// a scan and waits for the new diagram: 200 with the diagram (including its
+/// SVG) once it is saved, 202 if it is still queued when the wait is over.
+pub async fn api_regenerate_diagram(
+    State(state): State<Arc<AppState>>,
+    Path((id, diagram_type)): Path<(i64, DiagramType)>,
+    Query(params): Query<RegenerateQuery>,
+) -> impl IntoResponse {
+    if let Err(response) = get_repo_or_error(&state.db, id).await {
+        return response;
+    }
+    let internal_error = |e: anyhow::Error| {
+        tracing::error!("Failed to regenerate {} diagram: {:#}", diagram_type, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Failed to regenerate diagram" })),
+        )
+            .into_response()
+    };
+
+    let previous_id = match state.db.get_latest_diagram(id, diagram_type.as_str()).await {
+        Ok(diagram) => diagram.map_or(0, |d| d.id),
+        Err(e) => return internal_error(e),
+    };
+    if let Err(e) = state
+        .db
+        .request_diagram_regeneration(id, diagram_type.as_str())
+        .await
+    {
+        return internal_error(e);
+    }
+    tracing::info!("Regeneration of the {} diagram requested", diagram_type);
+    state.daemon.trigger_scan();
+
+    let wait = params
+        .wait
+        .unwrap_or(DEFAULT_REGENERATE_WAIT_SECS)
+        .min(MAX_REGENERATE_WAIT_SECS);
+    let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(wait);
+    loop {
+        match state.db.get_latest_diagram(id, diagram_type.as_str()).await {
+            Ok(Some(diagram)) if diagram.id > previous_id => return Json(diagram).into_response(),
+            Ok(_) => {}
+            Err(e) => return internal_error(e),
+        }
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            break;
+        }
+        tokio::time::sleep(REGENERATE_POLL_INTERVAL.min(deadline - now)).await;
+    }
+
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({ "queued": true, "diagram_type": diagram_type })),
+    )
+        .into_response()
+}
+
+/// Chat messages shown on the chat page and returned by the chat API
+const CHAT_HISTORY_LENGTH: i64 = 200;
+
+/// Longest question the chat API accepts, in characters
+const MAX_CHAT_MESSAGE_CHARS: usize = 4_000;
+
+pub async fn repository_chat(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+
+    let messages = state
+        .db
+        .get_chat_messages(id, CHAT_HISTORY_LENGTH)
+        .await
+        .unwrap_or_default();
+    let search_enabled = state.config.read().await.search.enabled;
+
+    render_template(
+        &state,
+        RepositoryChatTemplate {
+            repository,
+            messages: messages.into_iter().map(ChatMessageView::from).collect(),
+            search_enabled,
+        },
+    )
+    .await
+}
+
+/// Query parameters selecting a repository's chat
+#[derive(Deserialize, Debug)]
+pub struct ChatQuery {
+    pub repository_id: i64,
+}
+
+/// A question for the chat API
+#[derive(Deserialize, Debug)]
+pub struct ChatRequest {
+    pub repository_id: i64,
+    pub message: String,
+}
+
+/// API: The conversation about a repository, oldest message first
+pub async fn api_chat_history(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ChatQuery>,
+) -> impl IntoResponse {
+    if let Err(response) = get_repo_or_error(&state.db, query.repository_id).await {
+        return response;
+    }
+    match state
+        .db
+        .get_chat_messages(query.repository_id, CHAT_HISTORY_LENGTH)
+        .await
+    {
+        Ok(messages) => Json(
+            messages
+                .into_iter()
+                .map(ChatMessageView::from)
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch chat messages: {:#}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch chat messages" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// API: Ask a question about a repository. The answer is grounded in the
+/// repository's stored analyses and returned with the files it drew on.
+pub async fn api_chat(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ChatRequest>,
+) -> impl IntoResponse {
+    let repository = match get_repo_or_error(&state.db, request.repository_id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+    let question = request.message.trim();
+    if question.is_empty() || question.chars().count() > MAX_CHAT_MESSAGE_CHARS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!(
+                    "The message must have 1 to {} characters",
+                    MAX_CHAT_MESSAGE_CHARS
+                )
+            })),
+        )
+            .into_response();
+    }
+
+    let config = state.config.read().await.clone();
+    match chat::ask(
+        &state.db,
+        &config,
+        state.daemon.health(),
+        &repository,
+        question,
+    )
+    .await
+    {
+        Ok(answer) => Json(ChatMessageView::from(answer)).into_response(),
+        Err(e) => {
+            tracing::warn!("Chat about {} failed: {:#}", repository.name, e);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({ "error": format!("{:#}", e) })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// API: Delete the conversation about a repository
+pub async fn api_clear_chat(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ChatQuery>,
+) -> impl IntoResponse {
+    if let Err(response) = get_repo_or_error(&state.db, query.repository_id).await {
+        return response;
+    }
+    match state.db.clear_chat_messages(query.repository_id).await {
+        Ok(deleted) => Json(serde_json::json!({ "deleted": deleted })).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to clear chat messages: {:#}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to clear chat" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+pub async fn repository_duplication(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+
+    let clusters = state
+        .db
+        .get_duplicate_clusters(id)
+        .await
+        .unwrap_or_default();
+    let pairs = duplication::file_pairs(&clusters)
+        .into_iter()
+        .map(|pair| DuplicatePairView::from_pair(pair, &repository.path))
+        .collect();
+    let clusters = clusters
+        .into_iter()
+        .map(|cluster| DuplicateClusterView::from_cluster(cluster, &repository.path))
+        .collect();
+
+    render_template(
+        &state,
+        RepositoryDuplicationTemplate {
+            repository,
+            pairs,
+            clusters,
+        },
+    )
+    .await
+}
+
+pub async fn settings(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let config = state.config.read().await;
+    let endpoints = config.endpoints.clone();
+    let start_hour = config.schedule.start_hour;
+    let end_hour = config.schedule.end_hour;
+    let schedule_windows = config
+        .schedule
+        .windows
+        .iter()
+        .map(|w| w.describe())
+        .collect();
+    let mut config_path = Config::default_config_path()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "(unknown)".to_string());
+    if let Some(ref profile) = config.profile {
+        config_path.push_str(&format!(" (profile: {})", profile));
+    }
+
+    let health = state
+        .daemon
+        .endpoint_health(&endpoints)
+        .into_iter()
+        .map(EndpointHealthView::from_health)
+        .collect();
+    drop(config);
+
+    let paused_until = schedule_paused_until(&state.db).await.map(|until| {
+        until
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    });
+    let daily_usage = state
+        .db
+        .get_daily_usage(USAGE_DAYS)
+        .await
+        .unwrap_or_default();
+    let usage_totals = state
+        .db
+        .get_usage_totals(USAGE_DAYS)
+        .await
+        .unwrap_or_default();
+
+    render_template(
+        &state,
+        SettingsTemplate {
+            endpoints,
+            health,
+            daily_usage,
+            usage_totals,
+            start_hour,
+            end_hour,
+            schedule_windows,
+            paused_until,
+            config_path,
+        },
+    )
+    .await
+}
+
+/// Add a new Ollama endpoint
+#[derive(Deserialize)]
+pub struct AddEndpointRequest {
+    name: String,
+    url: String,
+    model: String,
+    #[serde(default)]
+    location: EndpointLocation,
+    #[serde(default)]
+    keep_alive: Option<String>,
+    #[serde(default)]
+    tier: EndpointTier,
+    #[serde(default)]
+    context_window: Option<u32>,
+}
+
+pub async fn add_endpoint(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<AddEndpointRequest>,
+) -> impl IntoResponse {
+    let new_endpoint = OllamaEndpoint {
+        location: req.location,
+        keep_alive: req.keep_alive,
+        tier: req.tier,
+        context_window: req.context_window,
+        ..OllamaEndpoint::new(req.name, req.url, req.model)
+    };
+
+    {
+        let mut config = state.config.write().await;
+        config.endpoints.push(new_endpoint);
+    }
+
+    tracing::info!("Added new Ollama endpoint");
+    (
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "success": true })),
+    )
+}
+
+/// Update an Ollama endpoint (by index)
+#[derive(Deserialize)]
+pub struct UpdateEndpointRequest {
+    name: String,
+    url: String,
+    model: String,
+    enabled: bool,
+    #[serde(default)]
+    location: EndpointLocation,
+    #[serde(default)]
+    keep_alive: Option<String>,
+    #[serde(default)]
+    tier: EndpointTier,
+    #[serde(default)]
+    context_window: Option<u32>,
+}
+
+pub async fn update_endpoint(
+    State(state): State<Arc<AppState>>,
+    Path(index): Path<usize>,
+    Json(req): Json<UpdateEndpointRequest>,
+) -> impl IntoResponse {
+    let mut config = state.config.write().await;
+
+    if index >= config.endpoints.len() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Endpoint not found" })),
+        )
+            .into_response();
+    }
+
+    // Request settings aren't edited in the dashboard, so they're kept
+    config.endpoints[index] = OllamaEndpoint {
+        name: req.name,
+        url: req.url,
+        model: req.model,
+        enabled: req.enabled,
+        location: req.location,
+        keep_alive: req.keep_alive,
+        tier: req.tier,
+        context_window: req.context_window,
+        ..config.endpoints[index].clone()
+    };
+
+    tracing::info!("Updated Ollama endpoint at index {}", index);
+    (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+}
+
+/// Delete an Ollama endpoint (by index)
+pub async fn delete_endpoint(
+    State(state): State<Arc<AppState>>,
+    Path(index): Path<usize>,
+) -> impl IntoResponse {
+    let mut config = state.config.write().await;
+
+    if index >= config.endpoints.len() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Endpoint not found" })),
+        )
+            .into_response();
+    }
+
+    config.endpoints.remove(index);
+
+    tracing::info!("Deleted Ollama endpoint at index {}", index);
+    (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+}
+
+/// API: Get all Ollama endpoints
+pub async fn api_endpoints(State(state): State<Arc<AppState>>) -> Json<Vec<OllamaEndpoint>> {
+    let config = state.config.read().await;
+    Json(config.endpoints.clone())
+}
+
+/// API: Get the tracked health of each configured endpoint
+pub async fn api_endpoints_health(State(state): State<Arc<AppState>>) -> Json<Vec<EndpointHealth>> {
+    let config = state.config.read().await;
+    Json(state.daemon.endpoint_health(&config.endpoints))
+}
+
+/// Days of token usage shown on the settings page (and the API default)
+const USAGE_DAYS: i64 = 7;
+
+#[derive(Deserialize)]
+pub struct UsageQuery {
+    /// Number of days to report, including today
+    pub days: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct UsageResponse {
+    pub days: i64,
+    pub daily: Vec<DailyUsage>,
+    pub totals: Vec<UsageTotal>,
+}
+
+/// API: Get LLM token usage per day and per endpoint, repository and analysis type
+pub async fn api_usage(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UsageQuery>,
+) -> impl IntoResponse {
+    let days = query.days.unwrap_or(USAGE_DAYS).clamp(1, 366);
+
+    match (
+        state.db.get_daily_usage(days).await,
+        state.db.get_usage_totals(days).await,
+    ) {
+        (Ok(daily), Ok(totals)) => Json(UsageResponse {
+            days,
+            daily,
+            totals,
+        })
+        .into_response(),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!("Failed to fetch usage: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch usage" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Audit log entries returned by default
+const AUDIT_LIMIT: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    pub limit: Option<i64>,
+}
+
+/// API: Get the most recent audit log entries
+pub async fn api_audit(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(AUDIT_LIMIT).clamp(1, 1000);
+    match state.db.get_audit_log(limit).await {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch audit log: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch audit log" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Processing cycles returned (and shown on the history page) by default
+const SCAN_RUNS_LIMIT: i64 = 50;
+
+#[derive(Deserialize)]
+pub struct ScanRunsQuery {
+    pub limit: Option<i64>,
+}
+
+/// API: Get the most recent processing cycles of the daemon, newest first
+pub async fn api_scan_runs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ScanRunsQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(SCAN_RUNS_LIMIT).clamp(1, 1000);
+    match state.db.get_scan_runs(limit).await {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch scan runs: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch scan runs" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct StatusResponse {
+    pub daemon_status: Option<DaemonState>,
+    /// Live state of the daemon loop (`waiting`, `processing`, `stopping` or `error`)
+    pub state: DaemonStatus,
+    /// Error that ended the most recent failed cycle since startup
+    pub last_error: Option<DaemonError>,
+    /// Whether processing is paused (see `/api/daemon/pause`)
+    pub paused: bool,
+    /// Expensive API requests waiting for the running one
+    pub request_queue_depth: usize,
+    pub version: &'static str,
+}
+
+/// API: Get daemon status
+pub async fn api_status(State(state): State<Arc<AppState>>) -> Json<StatusResponse> {
+    let daemon_status = state.db.get_daemon_status().await.ok();
+
+    Json(StatusResponse {
+        daemon_status,
+        state: state.daemon.status(),
+        last_error: state.daemon.last_error(),
+        paused: state.daemon.is_paused(),
+        request_queue_depth: state.expensive.queue_depth(),
+        version: env!("CARGO_PKG_VERSION"),
+    })
+}
+
+/// API: Get repositories
+pub async fn api_repositories(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RepositoriesQuery>,
+) -> Json<Vec<Repository>> {
+    let repositories = state
+        .db
+        .get_repositories()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|repo| repo.archived() == params.archived)
+        .collect();
+    let (repositories, _) = filter_by_tag(repositories, params.tag.as_deref());
+    Json(repositories)
+}
+
+/// Query parameters for the analysis results API
+#[derive(Deserialize, Debug, Default)]
+pub struct ResultsQuery {
+    /// Only results produced by this model
+    pub model: Option<String>,
+    /// Only purge results of this repository
+    pub repository_id: Option<i64>,
+}
+
+/// API: Get analysis results
+pub async fn api_results(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ResultsQuery>,
+) -> Json<Vec<AnalysisResult>> {
+    let model = params.model.as_deref().filter(|s| !s.is_empty());
+    let results = state
+        .db
+        .get_recent_results(100, model)
+        .await
+        .unwrap_or_default();
+    Json(results)
+}
+
+/// API: Delete all analysis and mutation results produced by a model, then
+/// trigger a scan so the affected files are regenerated
+pub async fn api_purge_results(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ResultsQuery>,
+) -> impl IntoResponse {
+    let Some(model) = params.model.as_deref().filter(|s| !s.is_empty()) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "The 'model' parameter is required" })),
+        )
+            .into_response();
+    };
+
+    match state
+        .db
+        .delete_results_by_model(model, params.repository_id)
+        .await
+    {
+        Ok(counts) => {
+            tracing::info!(
+                "Purged {} analysis and {} mutation results produced by {}",
+                counts.analysis_results,
+                counts.mutation_results,
+                model
+            );
+            state.daemon.trigger_scan();
+            Json(counts).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to purge results for model {}: {}", model, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to purge results" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// API: Delete the results of a repository's files that were deleted, which
+/// are hidden since a scan marked them stale
+pub async fn api_purge_stale_results(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(response) = get_repo_or_error(&state.db, id).await {
+        return response;
+    }
+    match state.db.purge_stale_results(id).await {
+        Ok(deleted) => {
+            tracing::info!("Purged {} stale results of repository {}", deleted, id);
+            Json(serde_json::json!({ "deleted": deleted })).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to purge stale results of repository {}: {}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to purge stale results" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct JobsResponse {
+    pub counts: JobCounts,
+    pub jobs: Vec<Job>,
+}
+
+/// API: Get the job queue (running and pending jobs first, then recently finished)
+pub async fn api_jobs(State(state): State<Arc<AppState>>) -> Json<JobsResponse> {
+    let counts = state.db.get_job_counts().await.unwrap_or_default();
+    let jobs = state.db.get_jobs(100).await.unwrap_or_default();
+    Json(JobsResponse { counts, jobs })
+}
+
+/// API: List running and pending jobs, in the order the daemon plans to run them
+pub async fn api_queue(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.db.get_queued_jobs().await {
+        Ok(jobs) => Json(jobs).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch queue: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch queue" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Request body for reprioritizing a queued job
+#[derive(Deserialize)]
+pub struct UpdateQueuedJobRequest {
+    /// Pending jobs with a higher priority run first (default 0)
+    pub priority: i64,
+}
+
+/// API: Remove a pending job from the queue
+pub async fn api_delete_queued_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match state.db.delete_pending_job(id).await {
+        Ok(true) => {
+            tracing::info!("Removed job {} from the queue", id);
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Ok(false) => queued_job_not_found(id),
+        Err(e) => {
+            tracing::error!("Failed to remove job {}: {}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to remove job" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// API: Change the priority of a pending job
+pub async fn api_update_queued_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(req): Json<UpdateQueuedJobRequest>,
+) -> impl IntoResponse {
+    match state.db.set_job_priority(id, req.priority).await {
+        Ok(true) => {
+            tracing::info!("Set priority of job {} to {}", id, req.priority);
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Ok(false) => queued_job_not_found(id),
+        Err(e) => {
+            tracing::error!("Failed to reprioritize job {}: {}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to update job" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Only pending jobs can be changed; running and finished ones are reported as
+/// not found in the queue
+fn queued_job_not_found(id: i64) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({ "error": format!("No pending job with ID {}", id) })),
+    )
+        .into_response()
+}
+
+/// API: List the named queries available at `/api/query/:name`
+pub async fn api_list_queries() -> Json<&'static [crate::query::QueryInfo]> {
+    Json(crate::query::QUERIES)
+}
+
+/// API: Run a named query, with parameters passed as query string
+pub async fn api_query(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(params): Query<BTreeMap<String, String>>,
+) -> impl IntoResponse {
+    let query = match crate::query::Query::parse(&name, &params) {
+        Ok(query) => query,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+
+    match query.run(&state.db).await {
+        Ok(rows) => Json(rows).into_response(),
+        Err(e) => {
+            tracing::error!("Query '{}' failed: {}", name, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Query failed" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// API: Stream live analysis progress as Server-Sent Events
+pub async fn api_events(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = BroadcastStream::new(state.daemon.subscribe_progress()).filter_map(|event| {
+        // Lagged subscribers skip missed events instead of disconnecting
+        let event = event.ok()?;
+        Event::default()
+            .event("progress")
+            .json_data(event)
+            .ok()
+            .map(Ok)
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Search hits returned by default
+const SEARCH_LIMIT: usize = 20;
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+/// API: Files across all repositories whose summaries are semantically
+/// closest to the query, with their findings
+pub async fn api_search(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> impl IntoResponse {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "Missing query" })),
+        )
+            .into_response();
+    }
+
+    let (search, endpoints) = {
+        let config = state.config.read().await;
+        (config.search.clone(), config.endpoints.clone())
+    };
+    if !search.enabled {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Semantic search is disabled; set `enabled = true` under [search]"
+            })),
+        )
+            .into_response();
+    }
+
+    // Only the query is sent, so any enabled endpoint will do
+    let limit = query.limit.unwrap_or(SEARCH_LIMIT).clamp(1, 100);
+    let mut last_error = None;
+    for endpoint in endpoints.iter().filter(|e| e.enabled) {
+        let client = OllamaClient::new(&endpoint.url, &search.embedding_model);
+        match search::search(&state.db, &client, &search.embedding_model, q, None, limit).await {
+            Ok(hits) => return Json(hits).into_response(),
+            Err(e) => {
+                tracing::warn!("Search via endpoint {} failed: {}", endpoint.name, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    match last_error {
+        Some(e) => {
+            tracing::error!("Search failed: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({ "error": "Search failed" })),
+            )
+                .into_response()
+        }
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "error": "No Ollama endpoints enabled" })),
+        )
+            .into_response(),
+    }
+}
+
+/// Semantic search page
+pub async fn search_page(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    render_template(&state, SearchTemplate {}).await
+}
+
+/// Findings listed across repositories by default
+const FINDINGS_LIMIT: i64 = 200;
+
+#[derive(Deserialize)]
+pub struct FindingsQuery {
+    /// "error" or "warning" (both if None or empty)
+    pub severity: Option<String>,
+    /// "severity" (most severe first, the default) or "recent" (newest first)
+    pub sort: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Open error and warning findings of all repositories that match the query
+async fn open_findings(
+    state: &AppState,
+    query: &FindingsQuery,
+) -> Result<Vec<GlobalFindingView>, Response> {
+    let severity = query.severity.as_deref().filter(|s| !s.is_empty());
+    if let Some(severity) = severity.filter(|s| !matches!(*s, "error" | "warning")) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("Unknown severity '{}': expected error or warning", severity)
+            })),
+        )
+            .into_response());
+    }
+    let recent_first = match query.sort.as_deref().filter(|s| !s.is_empty()) {
+        None | Some("severity") => false,
+        Some("recent") => true,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": format!("Unknown sort '{}': expected severity or recent", other)
+                })),
+            )
+                .into_response())
+        }
+    };
+    let limit = query.limit.unwrap_or(FINDINGS_LIMIT).clamp(1, 1000);
+
+    let database_error = |e: anyhow::Error| {
+        tracing::error!("Failed to fetch open findings: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Failed to fetch open findings" })),
+        )
+            .into_response()
+    };
+    let repositories: HashMap<i64, Repository> = state
+        .db
+        .get_repositories()
+        .await
+        .map_err(database_error)?
+        .into_iter()
+        .map(|repo| (repo.id, repo))
+        .collect();
+    let findings = state
+        .db
+        .get_open_findings(severity, recent_first, limit)
+        .await
+        .map_err(database_error)?;
+
+    Ok(findings
+        .into_iter()
+        .filter_map(|finding| {
+            let repo = repositories.get(&finding.repository_id)?;
+            Some(GlobalFindingView {
+                repository_id: repo.id,
+                repository_name: repo.name.clone(),
+                finding: FindingView::from_finding(finding, &repo.path),
+            })
+        })
+        .collect())
+}
+
+/// API: Open error and warning findings across all repositories, most
+/// severe or newest first
+pub async fn api_findings(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FindingsQuery>,
+) -> Response {
+    match open_findings(&state, &query).await {
+        Ok(findings) => Json(findings).into_response(),
+        Err(error) => error,
+    }
+}
+
+/// Findings page: the most urgent findings of all repositories
+pub async fn findings_page(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FindingsQuery>,
+) -> Response {
+    match open_findings(&state, &query).await {
+        Ok(findings) => {
+            render_template(
+                &state,
+                FindingsTemplate {
+                    findings,
+                    severity: query.severity.unwrap_or_default(),
+                    recent_first: query.sort.as_deref() == Some("recent"),
+                },
+            )
+            .await
+        }
+        Err(error) => error,
+    }
+}
+
+/// Log records sent before live ones by default
+const LOG_BACKLOG: usize = 200;
+
+#[derive(Deserialize)]
+pub struct LogStreamQuery {
+    /// Least severe level to stream (error, warn, info, debug or trace)
+    pub level: Option<String>,
+    /// Target prefix, e.g. `noctum::mutation`
+    pub target: Option<String>,
+    /// Number of earlier records to send first
+    pub backlog: Option<usize>,
+}
+
+/// API: Stream the daemon's log as Server-Sent Events, starting with the most
+/// recent records
+pub async fn api_logs_stream(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LogStreamQuery>,
+) -> Response {
+    let level = match query.level.as_deref().filter(|l| !l.is_empty()) {
+        Some(level) => match level.parse::<tracing::Level>() {
+            Ok(level) => Some(level),
+            Err(_) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "error": format!("Unknown level '{}'", level) })),
+                )
+                    .into_response()
+            }
+        },
+        None => None,
+    };
+    let filter = LogFilter {
+        level,
+        target: query.target.filter(|t| !t.is_empty()),
+    };
+
+    let (backlog, receiver) = state
+        .logs
+        .backlog_and_subscribe(&filter, query.backlog.unwrap_or(LOG_BACKLOG));
+    let live = BroadcastStream::new(receiver).filter_map(move |record| {
+        // Lagged subscribers skip missed records instead of disconnecting
+        let record = record.ok()?;
+        filter.matches(&record).then_some(record)
+    });
+    let events = tokio_stream::iter(backlog)
+        .chain(live)
+        .filter_map(|record| {
+            Event::default()
+                .event("log")
+                .json_data(record)
+                .ok()
+                .map(Ok::<_, Infallible>)
+        });
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Live daemon log page
+pub async fn logs_page(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    render_template(&state, LogsTemplate {}).await
+}
+
+/// History page: timeline of the daemon's processing cycles
+pub async fn history_page(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let runs = state
+        .db
+        .get_scan_runs(SCAN_RUNS_LIMIT)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(ScanRunView::from)
+        .collect();
+
+    render_template(&state, HistoryTemplate { runs }).await
+}
+
+/// Live dashboard updates over WebSocket: the current daemon status and pause
+/// state on connect, then every state change and newly saved result
+pub async fn ws_updates(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    // Browsers don't apply the same-origin policy to WebSockets, so refuse
+    // connections opened by pages of other sites
+    if !is_same_origin(&headers) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    // Subscribe before reading the status so no change falls in between
+    let updates = state.daemon.subscribe_updates();
+    let status = DashboardUpdate::Status {
+        state: state.daemon.status(),
+        task: state
+            .db
+            .get_daemon_status()
+            .await
+            .ok()
+            .and_then(|s| s.current_task),
+    };
+    let paused = DashboardUpdate::Paused {
+        paused: state.daemon.is_paused(),
+    };
+    ws.on_upgrade(move |socket| forward_updates(socket, vec![status, paused], updates))
+}
+
+/// Whether the request's `Origin` (if any) is the host it was sent to
+fn is_same_origin(headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN) else {
+        // Not sent by a browser
+        return true;
+    };
+    let host = headers.get(header::HOST).and_then(|h| h.to_str().ok());
+    let origin_host = origin
+        .to_str()
+        .ok()
+        .and_then(|o| o.split_once("://"))
+        .map(|(_, rest)| rest);
+    matches!((origin_host, host), (Some(o), Some(h)) if o.eq_ignore_ascii_case(h))
+}
+
+async fn forward_updates(
+    mut socket: WebSocket,
+    initial: Vec<DashboardUpdate>,
+    mut updates: tokio::sync::broadcast::Receiver<DashboardUpdate>,
+) {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let mut pending: std::collections::VecDeque<_> = initial.into();
+    loop {
+        while let Some(update) = pending.pop_front() {
+            let Ok(text) = serde_json::to_string(&update) else {
+                continue;
+            };
+            if socket.send(Message::Text(text)).await.is_err() {
+                return;
+            }
+        }
+
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => pending.push_back(update),
+                // Lagged subscribers skip missed updates instead of disconnecting
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+                // Pings are answered by axum; clients have nothing else to say
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// API: Test Ollama connection
+#[derive(Deserialize)]
+pub struct TestOllamaRequest {
+    url: String,
+}
+
+#[derive(Serialize)]
+pub struct TestOllamaResponse {
+    success: bool,
+    model_count: Option<usize>,
+    error: Option<String>,
+}
+
+pub async fn api_test_ollama(Json(req): Json<TestOllamaRequest>) -> Json<TestOllamaResponse> {
+    let client = OllamaClient::new(&req.url, "");
+
+    if !client.is_available().await {
+        return Json(TestOllamaResponse {
+            success: false,
+            model_count: None,
+            error: Some("Cannot connect to Ollama at the specified URL".to_string()),
+        });
+    }
+
+    match client.list_models().await {
+        Ok(models) => Json(TestOllamaResponse {
+            success: true,
+            model_count: Some(models.len()),
+            error: None,
+        }),
+        Err(e) => Json(TestOllamaResponse {
+            success: false,
+            model_count: None,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+/// Get current config as JSON
+#[derive(Serialize)]
+pub struct ConfigResponse {
+    pub start_hour: u8,
+    pub end_hour: u8,
+    pub check_interval_seconds: u64,
+}
+
+pub async fn api_get_config(State(state): State<Arc<AppState>>) -> Json<ConfigResponse> {
+    let config = state.config.read().await;
+    Json(ConfigResponse {
+        start_hour: config.schedule.start_hour,
+        end_hour: config.schedule.end_hour,
+        check_interval_seconds: config.schedule.check_interval_seconds,
+    })
+}
+
+/// Update config
+#[derive(Deserialize)]
+pub struct UpdateConfigRequest {
+    pub start_hour: u8,
+    pub end_hour: u8,
+}
+
+pub async fn api_update_config(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<UpdateConfigRequest>,
+) -> impl IntoResponse {
+    let start_hour = req.start_hour.min(23);
+    let end_hour = req.end_hour.min(23);
+
+    // Update in-memory config (daemon reads this directly each cycle)
     {
         let mut config = state.config.write().await;
-        config.endpoints.push(new_endpoint);
+        config.schedule.start_hour = start_hour;
+        config.schedule.end_hour = end_hour;
+    }
+
+    tracing::info!(
+        "Config updated: schedule = {:02}:00 - {:02}:00",
+        start_hour,
+        end_hour
+    );
+
+    (StatusCode::OK, Json(serde_json::json!({ "success": true })))
+}
+
+/// Schedule windows and pause state
+#[derive(Serialize)]
+pub struct ScheduleResponse {
+    /// Whether scheduled processing runs now (in a window and not paused)
+    pub active: bool,
+    /// Scheduled processing is paused until this time (RFC 3339)
+    pub paused_until: Option<String>,
+    pub start_hour: u8,
+    pub end_hour: u8,
+    pub windows: Vec<ScheduleWindow>,
+    /// Windows of repositories with these tags
+    pub tags: BTreeMap<String, Vec<ScheduleWindow>>,
+}
+
+/// API: Get the schedule and whether it is paused
+pub async fn api_schedule(State(state): State<Arc<AppState>>) -> Json<ScheduleResponse> {
+    let paused_until = schedule_paused_until(&state.db).await;
+    let config = state.config.read().await;
+    Json(ScheduleResponse {
+        active: paused_until.is_none() && config.schedule.is_any_open(),
+        paused_until: paused_until.map(|until| until.to_rfc3339()),
+        start_hour: config.schedule.start_hour,
+        end_hour: config.schedule.end_hour,
+        windows: config.schedule.windows.clone(),
+        tags: config.schedule.tags.clone(),
+    })
+}
+
+/// Request body for pausing the schedule
+#[derive(Deserialize)]
+pub struct PauseScheduleRequest {
+    /// Pause until this time (RFC 3339, e.g. `2025-01-06T08:00:00Z`)
+    pub until: String,
+}
+
+/// API: Pause scheduled processing until a time. Manual scans still run.
+pub async fn api_pause_schedule(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<PauseScheduleRequest>,
+) -> impl IntoResponse {
+    let until = match chrono::DateTime::parse_from_rfc3339(&req.until) {
+        Ok(until) if until > chrono::Utc::now() => until.with_timezone(&chrono::Utc),
+        Ok(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "'until' must be in the future" })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": format!("Invalid 'until' time: {}", e) })),
+            )
+                .into_response()
+        }
+    };
+
+    match state.db.set_paused_until(Some(&until.to_rfc3339())).await {
+        Ok(()) => {
+            tracing::info!("Schedule paused until {}", until.to_rfc3339());
+            Json(serde_json::json!({ "success": true, "paused_until": until.to_rfc3339() }))
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to pause schedule: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to pause schedule" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// API: Resume scheduled processing
+pub async fn api_resume_schedule(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.db.set_paused_until(None).await {
+        Ok(()) => {
+            tracing::info!("Schedule resumed");
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to resume schedule: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to resume schedule" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Save config to disk
+pub async fn api_save_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let config = state.config.read().await;
+
+    match config.save(None) {
+        Ok(()) => {
+            tracing::info!("Config saved to disk");
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to save config: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to save configuration" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Reload config from disk
+pub async fn api_reload_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let profile = state.config.read().await.profile.clone();
+    match Config::load(None, profile.as_deref()) {
+        Ok(new_config) => {
+            // Update shared config (daemon reads this directly each cycle)
+            {
+                let mut config = state.config.write().await;
+                *config = new_config;
+            }
+
+            tracing::info!("Config reloaded from disk");
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to reload config: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to reload configuration" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// API: Snapshot the database into the backup directory with SQLite's online
+/// backup API, so it is consistent while the daemon keeps writing
+pub async fn api_backup(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let (db_path, backup_dir) = {
+        let config = state.config.read().await;
+        (
+            crate::db::backup::database_file(&config),
+            config.backup_dir(),
+        )
+    };
+    let db_path = match db_path {
+        Ok(db_path) => db_path,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+
+    match crate::db::backup::create_backup(&db_path, &backup_dir).await {
+        Ok(path) => {
+            let bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            tracing::info!("Database backed up to {} via API", path.display());
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "path": path, "bytes": bytes })),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to back up the database: {:#}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to back up the database" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// API: Trigger an immediate scan
+pub async fn api_trigger_scan(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    state.daemon.trigger_scan();
+    tracing::info!("Scan triggered via API");
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "success": true, "message": "Scan triggered" })),
+    )
+}
+
+#[derive(Deserialize)]
+pub struct ReanalyzeRequest {
+    /// Absolute, or relative to the repository root
+    file_path: String,
+}
+
+/// API: Analyze a file again on a scan triggered right away, even though its
+/// content is unchanged
+pub async fn api_reanalyze_file(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(req): Json<ReanalyzeRequest>,
+) -> impl IntoResponse {
+    let repository = match state.db.get_repository(id).await {
+        Ok(Some(repo)) => repo,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Repository not found" })),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch repository {}: {}", id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch repository" })),
+            )
+                .into_response();
+        }
+    };
+
+    let Some(file_path) = repository_file(&repository.path, &req.file_path) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "Not a file of this repository" })),
+        )
+            .into_response();
+    };
+    let file_path = file_path.to_string_lossy().to_string();
+
+    match state.db.request_reanalysis(id, &file_path).await {
+        Ok(()) => {
+            tracing::info!("Reanalysis of {} requested", file_path);
+            state.daemon.trigger_scan();
+            Json(serde_json::json!({ "success": true, "file_path": file_path })).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to request reanalysis of {}: {}", file_path, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to request reanalysis" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Absolute path of an existing file given relative to the repository root (or
+/// absolute), if it lies within the repository
+fn repository_file(repo_path: &str, file_path: &str) -> Option<std::path::PathBuf> {
+    let repo_root = FilePath::new(repo_path);
+    let path = repo_root.join(file_path);
+    let escapes = path
+        .components()
+        .any(|c| matches!(c, std::path::Component::ParentDir));
+    (!escapes && path.starts_with(repo_root) && path.is_file()).then_some(path)
+}
+
+/// API: Stop all processing, even inside the schedule window, until resumed
+pub async fn api_pause_daemon(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    state.daemon.pause();
+    Json(serde_json::json!({ "success": true, "paused": true }))
+}
+
+/// API: Resume processing after a pause
+pub async fn api_resume_daemon(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    state.daemon.resume();
+    Json(serde_json::json!({ "success": true, "paused": false }))
+}
+
+/// A minimal mutation result for clipboard export
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SurvivedMutation {
+    pub file_path: String,
+    pub description: String,
+    pub test_outcome: String,
+    pub replacements: Vec<MutationReplacement>,
+}
+
+/// Combine code understanding results with WASM plugin findings (`plugin_*` analysis
+/// types), so each file shows a single entry. Files with only plugin findings get
+/// their own entry.
+fn merge_plugin_results(results: Vec<AnalysisResult>) -> Vec<AnalysisResult> {
+    let (mut merged, plugin_results): (Vec<_>, Vec<_>) = results
+        .into_iter()
+        .filter(|r| {
+            r.analysis_type == "code_understanding" || r.analysis_type.starts_with("plugin_")
+        })
+        .partition(|r| r.analysis_type == "code_understanding");
+
+    for plugin_result in plugin_results {
+        let section = format!(
+            "## Plugin: {}\n\n{}",
+            plugin_result.analysis_type.trim_start_matches("plugin_"),
+            plugin_result.result
+        );
+
+        match merged
+            .iter_mut()
+            .find(|r| r.file_path == plugin_result.file_path)
+        {
+            Some(existing) => {
+                existing.result = format!("{}\n\n{}", existing.result, section);
+            }
+            None => merged.push(AnalysisResult {
+                result: section,
+                ..plugin_result
+            }),
+        }
     }
 
-    tracing::info!("Added new Ollama endpoint");
-    (
-        StatusCode::CREATED,
-        Json(serde_json::json!({ "success": true })),
-    )
+    merged.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    merged
 }
 
-/// Update an Ollama endpoint (by index)
-#[derive(Deserialize)]
-pub struct UpdateEndpointRequest {
-    name: String,
-    url: String,
-    model: String,
-    enabled: bool,
+/// Build the file inventory: analyzed files (flagging lossily decoded ones) and
+/// files that were skipped as binary, minified, vendored or generated.
+fn file_inventory(
+    results: Vec<AnalysisResult>,
+    statuses: Vec<FileStatus>,
+    repo_path: &str,
+) -> (Vec<AnalysisResultView>, Vec<SkippedFileView>) {
+    let lossy: HashSet<&str> = statuses
+        .iter()
+        .filter(|s| s.status == FILE_STATUS_LOSSY)
+        .map(|s| s.file_path.as_str())
+        .collect();
+
+    let file_results = merge_plugin_results(results)
+        .into_iter()
+        .map(|r| {
+            let lossy_utf8 = lossy.contains(r.file_path.as_str());
+            AnalysisResultView {
+                lossy_utf8,
+                ..AnalysisResultView::from_result(r, repo_path)
+            }
+        })
+        .collect();
+
+    let skipped_files = statuses
+        .iter()
+        .filter_map(|s| {
+            let reason = match s.status.as_str() {
+                FILE_STATUS_BINARY => "binary",
+                FILE_STATUS_MINIFIED => "minified",
+                FILE_STATUS_VENDORED => "vendored",
+                FILE_STATUS_GENERATED => "generated",
+                _ => return None,
+            };
+            Some(SkippedFileView {
+                file_path: relative_to_repo(&s.file_path, repo_path),
+                reason: reason.to_string(),
+                updated_at: s.updated_at.clone(),
+            })
+        })
+        .collect();
+
+    (file_results, skipped_files)
 }
 
-pub async fn update_endpoint(
+/// Append links to the open issues each result refers to
+fn link_related_issues(results: &mut [AnalysisResultView], known_issues: &[Issue]) {
+    for result in results {
+        let links: Vec<String> = matching_issues(&result.result, known_issues)
+            .iter()
+            .map(|issue| format!("[#{} {}]({})", issue.number, issue.title, issue.url))
+            .collect();
+        if !links.is_empty() {
+            result
+                .result
+                .push_str(&format!("\n\n**Related issues:** {}", links.join(", ")));
+        }
+    }
+}
+
+/// Filter mutation results to only "survived" ones and convert to API format.
+/// This function is extracted for testability.
+fn filter_survived_mutations(
+    results: Vec<crate::db::MutationResult>,
+    repo_path: &str,
+) -> Vec<SurvivedMutation> {
+    results
+        .into_iter()
+        .filter(|r| r.test_outcome == "survived")
+        .map(|r| {
+            let relative_path = relative_to_repo(&r.file_path, repo_path);
+
+            SurvivedMutation {
+                file_path: relative_path,
+                description: r.description,
+                test_outcome: r.test_outcome,
+                replacements: r.replacements,
+            }
+        })
+        .collect()
+}
+
+/// API: Get survived mutations for a repository (for clipboard export)
+pub async fn api_survived_mutations(
     State(state): State<Arc<AppState>>,
-    Path(index): Path<usize>,
-    Json(req): Json<UpdateEndpointRequest>,
+    Path(id): Path<i64>,
 ) -> impl IntoResponse {
-    let mut config = state.config.write().await;
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
 
-    if index >= config.endpoints.len() {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({ "error": "Endpoint not found" })),
-        )
-            .into_response();
-    }
+    let raw_results = state.db.get_mutation_results(id).await.unwrap_or_default();
+    let survived = filter_survived_mutations(raw_results, &repository.path);
 
-    config.endpoints[index] = OllamaEndpoint {
-        name: req.name,
-        url: req.url,
-        model: req.model,
-        enabled: req.enabled,
-    };
+    Json(survived).into_response()
+}
 
-    tracing::info!("Updated Ollama endpoint at index {}", index);
-    (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+/// Maximum number of change-log entries returned by the API
+const MAX_CHANGELOG_ENTRIES: u32 = 500;
+
+/// Query parameters for the change log API
+#[derive(Deserialize, Debug, Default)]
+pub struct ChangelogQuery {
+    /// File path relative to the repository root
+    pub file: Option<String>,
 }
 
-/// Delete an Ollama endpoint (by index)
-pub async fn delete_endpoint(
+/// API: Get the delta analysis change log of a repository, optionally for one file
+pub async fn api_repository_changelog(
     State(state): State<Arc<AppState>>,
-    Path(index): Path<usize>,
+    Path(id): Path<i64>,
+    Query(params): Query<ChangelogQuery>,
 ) -> impl IntoResponse {
-    let mut config = state.config.write().await;
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
 
-    if index >= config.endpoints.len() {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({ "error": "Endpoint not found" })),
-        )
-            .into_response();
+    // Stored paths are absolute
+    let file_path = params
+        .file
+        .as_deref()
+        .filter(|s| !s.is_empty())
+        .map(|file| {
+            std::path::Path::new(&repository.path)
+                .join(file)
+                .to_string_lossy()
+                .to_string()
+        });
+
+    match state
+        .db
+        .get_changelog(id, file_path.as_deref(), MAX_CHANGELOG_ENTRIES)
+        .await
+    {
+        Ok(mut entries) => {
+            for entry in &mut entries {
+                entry.file_path = relative_to_repo(&entry.file_path, &repository.path);
+            }
+            Json(entries).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch change log: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch change log" })),
+            )
+                .into_response()
+        }
     }
+}
 
-    config.endpoints.remove(index);
+/// Maximum number of results shown in a file's history
+const MAX_HISTORY_ENTRIES: u32 = 200;
 
-    tracing::info!("Deleted Ollama endpoint at index {}", index);
-    (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+/// Query parameters for the result history API
+#[derive(Deserialize, Debug, Default)]
+pub struct HistoryQuery {
+    /// Repository of the file; the file path is resolved against its root
+    pub repository_id: Option<i64>,
+    /// Only include results of this analysis type
+    pub analysis_type: Option<String>,
 }
 
-/// API: Get all Ollama endpoints
-pub async fn api_endpoints(State(state): State<Arc<AppState>>) -> Json<Vec<OllamaEndpoint>> {
-    let config = state.config.read().await;
-    Json(config.endpoints.clone())
+/// An analysis of a file, with what changed since the previous analysis
+#[derive(Serialize)]
+pub struct ResultHistoryEntry {
+    #[serde(flatten)]
+    pub result: AnalysisResult,
+    /// Unified diff from the previous result of the same analysis type (None
+    /// for the first one, empty when the result didn't change)
+    pub diff: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct StatusResponse {
-    pub daemon_status: Option<DaemonState>,
-    pub version: &'static str,
+/// Pair each result (oldest first) with the diff from the previous result of
+/// the same analysis type
+fn result_history(results: Vec<AnalysisResult>) -> Vec<ResultHistoryEntry> {
+    let mut previous: HashMap<String, String> = HashMap::new();
+    results
+        .into_iter()
+        .map(|result| {
+            let diff = previous
+                .insert(result.analysis_type.clone(), result.result.clone())
+                .map(|old| text_diff(&old, &result.result));
+            ResultHistoryEntry { result, diff }
+        })
+        .collect()
 }
 
-/// API: Get daemon status
-pub async fn api_status(State(state): State<Arc<AppState>>) -> Json<StatusResponse> {
-    let daemon_status = state.db.get_daemon_status().await.ok();
+/// API: Get every stored analysis of a file, oldest first, with a diff between
+/// consecutive results. The file is the stored path, or a path relative to the
+/// repository when `repository_id` is given.
+pub async fn api_result_history(
+    State(state): State<Arc<AppState>>,
+    Path(file): Path<String>,
+    Query(params): Query<HistoryQuery>,
+) -> impl IntoResponse {
+    let file_path = match params.repository_id {
+        Some(id) => match get_repo_or_error(&state.db, id).await {
+            Ok(repository) => FilePath::new(&repository.path)
+                .join(&file)
+                .to_string_lossy()
+                .to_string(),
+            Err(response) => return response,
+        },
+        None => file,
+    };
+    let analysis_type = params.analysis_type.as_deref().filter(|s| !s.is_empty());
 
-    Json(StatusResponse {
-        daemon_status,
-        version: env!("CARGO_PKG_VERSION"),
-    })
+    match state
+        .db
+        .get_file_result_history(
+            params.repository_id,
+            &file_path,
+            analysis_type,
+            MAX_HISTORY_ENTRIES,
+        )
+        .await
+    {
+        Ok(results) if results.is_empty() => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "No results for this file" })),
+        )
+            .into_response(),
+        Ok(results) => Json(result_history(results)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch result history of {}: {}", file_path, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch result history" })),
+            )
+                .into_response()
+        }
+    }
 }
 
-/// API: Get repositories
-pub async fn api_repositories(State(state): State<Arc<AppState>>) -> Json<Vec<Repository>> {
-    let repositories = state.db.get_repositories().await.unwrap_or_default();
-    Json(repositories)
+/// Query parameters for the file history page
+#[derive(Deserialize, Debug, Default)]
+pub struct FileHistoryQuery {
+    /// File path relative to the repository root
+    pub file: Option<String>,
+    /// Only show results of this analysis type
+    pub analysis_type: Option<String>,
 }
 
-/// API: Get analysis results
-pub async fn api_results(State(state): State<Arc<AppState>>) -> Json<Vec<AnalysisResult>> {
-    let results = state.db.get_recent_results(100).await.unwrap_or_default();
-    Json(results)
-}
+/// Page: Every analysis of a file over time, newest first
+pub async fn file_history(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(params): Query<FileHistoryQuery>,
+) -> impl IntoResponse {
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+    let Some(file) = params.file.filter(|f| !f.is_empty()) else {
+        return (StatusCode::BAD_REQUEST, "The 'file' parameter is required").into_response();
+    };
+    let file_path = FilePath::new(&repository.path)
+        .join(&file)
+        .to_string_lossy()
+        .to_string();
 
-/// API: Test Ollama connection
-#[derive(Deserialize)]
-pub struct TestOllamaRequest {
-    url: String,
-}
+    let results = state
+        .db
+        .get_file_result_history(Some(id), &file_path, None, MAX_HISTORY_ENTRIES)
+        .await
+        .unwrap_or_default();
+    let mut analysis_types: Vec<String> = results.iter().map(|r| r.analysis_type.clone()).collect();
+    analysis_types.sort();
+    analysis_types.dedup();
 
-#[derive(Serialize)]
-pub struct TestOllamaResponse {
-    success: bool,
-    model_count: Option<usize>,
-    error: Option<String>,
+    let analysis_type = params.analysis_type.unwrap_or_default();
+    let entries = result_history(results)
+        .into_iter()
+        .rev()
+        .filter(|entry| analysis_type.is_empty() || entry.result.analysis_type == analysis_type)
+        .map(|entry| HistoryEntryView::from_entry(entry.result, entry.diff.as_deref()))
+        .collect();
+
+    render_template(
+        &state,
+        FileHistoryTemplate {
+            file_path: relative_to_repo(&file_path, &repository.path),
+            repository,
+            analysis_types,
+            analysis_type,
+            entries,
+        },
+    )
+    .await
 }
 
-pub async fn api_test_ollama(Json(req): Json<TestOllamaRequest>) -> Json<TestOllamaResponse> {
-    let client = OllamaClient::new(&req.url, "");
+/// Query parameters for the coverage API
+#[derive(Deserialize, Debug, Default)]
+pub struct CoverageQuery {
+    /// Per-file analysis type (defaults to code_understanding)
+    pub analysis_type: Option<String>,
+}
 
-    if !client.is_available().await {
-        return Json(TestOllamaResponse {
-            success: false,
-            model_count: None,
-            error: Some("Cannot connect to Ollama at the specified URL".to_string()),
-        });
-    }
+/// API: How many of a repository's source files have current analysis results
+pub async fn api_repository_coverage(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(params): Query<CoverageQuery>,
+) -> impl IntoResponse {
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+    let analysis_type = params
+        .analysis_type
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| AnalysisType::CodeUnderstanding.to_string());
 
-    match client.list_models().await {
-        Ok(models) => Json(TestOllamaResponse {
-            success: true,
-            model_count: Some(models.len()),
-            error: None,
-        }),
-        Err(e) => Json(TestOllamaResponse {
-            success: false,
-            model_count: None,
-            error: Some(e.to_string()),
-        }),
+    match analysis_coverage(&state.db, &repository, &analysis_type).await {
+        Ok(coverage) => Json(coverage).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to compute analysis coverage: {:#}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": format!("{:#}", e) })),
+            )
+                .into_response()
+        }
     }
 }
 
-/// Get current config as JSON
-#[derive(Serialize)]
-pub struct ConfigResponse {
-    pub start_hour: u8,
-    pub end_hour: u8,
-    pub check_interval_seconds: u64,
+/// API: The structured architecture model of a repository (components, their
+/// responsibilities and dependencies), or 404 before one was generated
+pub async fn api_repository_architecture(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let repository = match get_repo_or_error(&state.db, id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+
+    match state.db.get_architecture_model(repository.id).await {
+        Ok(Some(stored)) => Json(serde_json::json!({
+            "repository_id": stored.repository_id,
+            "generated_at": stored.created_at,
+            "endpoint": stored.endpoint,
+            "model": stored.llm_model,
+            "summary": stored.model.summary,
+            "components": stored.model.components,
+        }))
+        .into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": "No architecture model has been generated for this repository yet"
+            })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to fetch architecture model: {:#}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": format!("{:#}", e) })),
+            )
+                .into_response()
+        }
+    }
 }
 
-pub async fn api_get_config(State(state): State<Arc<AppState>>) -> Json<ConfigResponse> {
-    let config = state.config.read().await;
-    Json(ConfigResponse {
-        start_hour: config.schedule.start_hour,
-        end_hour: config.schedule.end_hour,
-        check_interval_seconds: config.schedule.check_interval_seconds,
-    })
+/// Query parameters for the create issue API
+#[derive(Deserialize, Debug, Default)]
+pub struct CreateIssueQuery {
+    /// What the ID refers to: `analysis` (default) or `mutation`
+    pub kind: Option<String>,
 }
 
-/// Update config
-#[derive(Deserialize)]
-pub struct UpdateConfigRequest {
-    pub start_hour: u8,
-    pub end_hour: u8,
+/// A record a tracker issue can be filed for
+enum Finding {
+    Analysis(AnalysisResult),
+    Mutation(MutationResult),
 }
 
-pub async fn api_update_config(
+/// API: File a tracker issue for an analysis finding or survived mutant
+pub async fn api_create_issue(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<UpdateConfigRequest>,
+    Path(id): Path<i64>,
+    Query(params): Query<CreateIssueQuery>,
 ) -> impl IntoResponse {
-    let start_hour = req.start_hour.min(23);
-    let end_hour = req.end_hour.min(23);
+    let error = |status: StatusCode, message: &str| {
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    };
 
-    // Update in-memory config (daemon reads this directly each cycle)
-    {
-        let mut config = state.config.write().await;
-        config.schedule.start_hour = start_hour;
-        config.schedule.end_hour = end_hour;
+    let finding = match params.kind.as_deref() {
+        None | Some("analysis") => state
+            .db
+            .get_analysis_result(id)
+            .await
+            .map(|r| r.map(Finding::Analysis)),
+        Some("mutation") => state
+            .db
+            .get_mutation_result(id)
+            .await
+            .map(|r| r.map(Finding::Mutation)),
+        Some(other) => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                &format!("Unknown kind '{}': expected analysis or mutation", other),
+            )
+        }
+    };
+    let finding = match finding {
+        Ok(Some(finding)) => finding,
+        Ok(None) => return error(StatusCode::NOT_FOUND, "Finding not found"),
+        Err(e) => {
+            tracing::error!("Failed to load finding {}: {}", id, e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to load finding");
+        }
+    };
+
+    let (repository_id, file_path, issue_url) = match &finding {
+        Finding::Analysis(r) => (r.repository_id, &r.file_path, &r.issue_url),
+        Finding::Mutation(r) if r.test_outcome != "survived" => {
+            return error(
+                StatusCode::BAD_REQUEST,
+                "Issues can only be created for survived mutations",
+            )
+        }
+        Finding::Mutation(r) => (r.repository_id, &r.file_path, &r.issue_url),
+    };
+    if let Some(url) = issue_url {
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "error": "An issue was already created", "url": url })),
+        )
+            .into_response();
     }
 
-    tracing::info!(
-        "Config updated: schedule = {:02}:00 - {:02}:00",
-        start_hour,
-        end_hour
-    );
+    let repository = match get_repo_or_error(&state.db, repository_id).await {
+        Ok(repo) => repo,
+        Err(response) => return response,
+    };
+    let repo_path = FilePath::new(&repository.path);
+    let settings = RepoConfig::load(repo_path).unwrap_or_default().issues;
+    let tracker = state.config.read().await.issues.clone();
+    let Some(source) = IssueSource::resolve(&settings, &tracker, repo_path).await else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "No issue tracker found: set [issues] provider and project in noctum.toml",
+        );
+    };
 
-    (StatusCode::OK, Json(serde_json::json!({ "success": true })))
+    let relative_path = relative_to_repo(file_path, &repository.path);
+    let draft = match &finding {
+        Finding::Analysis(r) => {
+            IssueDraft::from_analysis(&settings, &repository.name, &relative_path, r)
+        }
+        Finding::Mutation(r) => {
+            IssueDraft::from_mutation(&settings, &repository.name, &relative_path, r)
+        }
+    };
+
+    let created = match IssueClient::new(tracker)
+        .create_issue(&source, &draft)
+        .await
+    {
+        Ok(created) => created,
+        Err(e) => {
+            tracing::warn!("Failed to create issue in {}: {:#}", source.project, e);
+            return error(StatusCode::BAD_GATEWAY, &format!("{:#}", e));
+        }
+    };
+    tracing::info!("Created issue {} for {}", created.url, relative_path);
+
+    let saved = match &finding {
+        Finding::Analysis(_) => state.db.set_analysis_issue_url(id, &created.url).await,
+        Finding::Mutation(_) => state.db.set_mutation_issue_url(id, &created.url).await,
+    };
+    if let Err(e) = saved {
+        tracing::error!("Failed to record issue {}: {}", created.url, e);
+    }
+
+    (
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "number": created.number, "url": created.url })),
+    )
+        .into_response()
 }
 
-/// Save config to disk
-pub async fn api_save_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let config = state.config.read().await;
+/// Query parameters for the report API
+#[derive(Deserialize, Debug, Default)]
+pub struct ReportQuery {
+    /// Report format: md (default) or html
+    pub format: Option<String>,
+}
 
-    match config.save(None) {
-        Ok(()) => {
-            tracing::info!("Config saved to disk");
-            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+/// API: Export a repository's latest results as a Markdown or HTML report
+pub async fn api_repository_report(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(params): Query<ReportQuery>,
+) -> impl IntoResponse {
+    let format = match params
+        .format
+        .as_deref()
+        .unwrap_or("md")
+        .parse::<ReportFormat>()
+    {
+        Ok(format) => format,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
         }
+    };
+    if let Err(response) = get_repo_or_error(&state.db, id).await {
+        return response;
+    }
+
+    let templates = page_templates(&state).await;
+    let rendered = match Report::load(&state.db, id).await {
+        Ok(report) => report.render(format, &templates),
+        Err(e) => Err(e),
+    };
+    match rendered {
+        Ok(body) => (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "inline; filename=\"noctum-report-{}.{}\"",
+                        id,
+                        format.extension()
+                    ),
+                ),
+            ],
+            body,
+        )
+            .into_response(),
         Err(e) => {
-            tracing::error!("Failed to save config: {}", e);
+            tracing::error!("Failed to build report for repository {}: {}", id, e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to save configuration" })),
+                Json(serde_json::json!({ "error": "Failed to build report" })),
             )
                 .into_response()
         }
     }
 }
 
-/// Reload config from disk
-pub async fn api_reload_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    match Config::load(None) {
-        Ok(new_config) => {
-            // Update shared config (daemon reads this directly each cycle)
-            {
-                let mut config = state.config.write().await;
-                *config = new_config;
-            }
+/// API: Export a repository's latest findings as a SARIF 2.1.0 log
+pub async fn api_repository_sarif(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(response) = get_repo_or_error(&state.db, id).await {
+        return response;
+    }
 
-            tracing::info!("Config reloaded from disk");
-            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
-        }
+    match sarif::load(&state.db, id).await {
+        Ok(log) => (
+            [
+                (header::CONTENT_TYPE, "application/sarif+json".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("inline; filename=\"noctum-{}.sarif\"", id),
+                ),
+            ],
+            Json(log),
+        )
+            .into_response(),
         Err(e) => {
-            tracing::error!("Failed to reload config: {}", e);
+            tracing::error!("Failed to build SARIF log for repository {}: {}", id, e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({ "error": "Failed to reload configuration" })),
+                Json(serde_json::json!({ "error": "Failed to build SARIF log" })),
             )
                 .into_response()
         }
     }
 }
 
-/// API: Trigger an immediate scan
-pub async fn api_trigger_scan(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    state.daemon.trigger_scan();
-    tracing::info!("Scan triggered via API");
-    (
-        StatusCode::OK,
-        Json(serde_json::json!({ "success": true, "message": "Scan triggered" })),
-    )
+/// Query parameters for the badge API
+#[derive(Deserialize, Debug, Default)]
+pub struct BadgeQuery {
+    /// Only count findings of this severity (findings badge)
+    pub severity: Option<String>,
 }
 
-/// A minimal mutation result for clipboard export
+/// API: SVG badge of a repository's latest mutation score
+/// (`mutation-score.svg`) or findings count (`findings.svg`)
+pub async fn api_repository_badge(
+    State(state): State<Arc<AppState>>,
+    Path((id, name)): Path<(i64, String)>,
+    Query(params): Query<BadgeQuery>,
+) -> impl IntoResponse {
+    if let Err(response) = get_repo_or_error(&state.db, id).await {
+        return response;
+    }
+
+    let badge = match name.as_str() {
+        "mutation-score.svg" => state
+            .db
+            .get_mutation_summary(id)
+            .await
+            .map(|summary| badge::mutation_score(&summary)),
+        "findings.svg" => {
+            let severity = params.severity.as_deref().filter(|s| !s.is_empty());
+            if let Some(severity) = severity.filter(|s| !SEVERITIES.contains(s)) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": format!("Unknown severity '{}'", severity)
+                    })),
+                )
+                    .into_response();
+            }
+            state
+                .db
+                .get_findings(id)
+                .await
+                .map(|findings| badge::findings(&findings, severity))
+        }
+        _ => return (StatusCode::NOT_FOUND, "Unknown badge").into_response(),
+    };
+
+    match badge {
+        Ok(badge) => (
+            [
+                (header::CONTENT_TYPE, "image/svg+xml"),
+                // Badges follow the latest results, so caches must revalidate
+                (header::CACHE_CONTROL, "no-cache"),
+            ],
+            badge.svg(),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to build badge for repository {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// Default page size for the mutation results API
+const DEFAULT_MUTATIONS_PER_PAGE: u32 = 50;
+
+/// Maximum page size for the mutation results API
+const MAX_MUTATIONS_PER_PAGE: u32 = 500;
+
+/// Query parameters for the mutation results API
+#[derive(Deserialize, Debug, Default)]
+pub struct MutationsQuery {
+    pub outcome: Option<String>,
+    /// Substring of the file path
+    pub file: Option<String>,
+    /// Model that generated the mutation
+    pub model: Option<String>,
+    /// Triage state ('new', 'acknowledged', 'wont_fix' or 'fixed')
+    pub status: Option<String>,
+    /// Only mutations replacing a line in `line_start..=line_end`
+    /// (`line_end` defaults to `line_start`)
+    pub line_start: Option<i64>,
+    pub line_end: Option<i64>,
+    /// 1-indexed page number
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// A mutation result in API format, with the file path relative to the repository
 #[derive(Serialize, Debug, PartialEq)]
-pub struct SurvivedMutation {
+pub struct MutationApiResult {
+    pub id: i64,
     pub file_path: String,
     pub description: String,
+    pub reasoning: String,
     pub test_outcome: String,
-    pub replacements: serde_json::Value,
+    pub killing_test: Option<String>,
+    pub test_output: Option<String>,
+    pub execution_time_ms: Option<i32>,
+    pub toolchain: Option<String>,
+    pub endpoint: Option<String>,
+    pub model: Option<String>,
+    /// Time spent generating the file's mutations
+    pub duration_ms: Option<i64>,
+    pub replacements: Vec<MutationReplacement>,
+    pub created_at: String,
+    /// Tracker issue filed from this mutation
+    pub issue_url: Option<String>,
+    /// Triage state
+    pub status: String,
 }
 
-/// Filter mutation results to only "survived" ones and convert to API format.
-/// This function is extracted for testability.
-fn filter_survived_mutations(
-    results: Vec<crate::db::MutationResult>,
-    repo_path: &str,
-) -> Vec<SurvivedMutation> {
-    results
-        .into_iter()
-        .filter(|r| r.test_outcome == "survived")
-        .map(|r| {
-            let relative_path = r
-                .file_path
-                .strip_prefix(repo_path)
-                .map(|p| p.trim_start_matches('/'))
-                .unwrap_or(&r.file_path)
-                .to_string();
-
-            let replacements: serde_json::Value =
-                serde_json::from_str(&r.replacements_json).unwrap_or(serde_json::json!([]));
+impl MutationApiResult {
+    fn from_result(r: crate::db::MutationResult, repo_path: &str) -> Self {
+        Self {
+            id: r.id,
+            file_path: relative_to_repo(&r.file_path, repo_path),
+            replacements: r.replacements,
+            description: r.description,
+            reasoning: r.reasoning,
+            test_outcome: r.test_outcome,
+            killing_test: r.killing_test,
+            test_output: r.test_output,
+            execution_time_ms: r.execution_time_ms,
+            toolchain: r.toolchain,
+            endpoint: r.endpoint,
+            model: r.model,
+            duration_ms: r.duration_ms,
+            created_at: r.created_at,
+            issue_url: r.issue_url,
+            status: r.status,
+        }
+    }
+}
 
-            SurvivedMutation {
-                file_path: relative_path,
-                description: r.description,
-                test_outcome: r.test_outcome,
-                replacements,
-            }
-        })
-        .collect()
+/// One page of mutation results plus summary statistics for the repository
+#[derive(Serialize, Debug)]
+pub struct MutationsPage {
+    pub page: u32,
+    pub per_page: u32,
+    /// Number of results matching the filters
+    pub total: i64,
+    pub total_pages: i64,
+    /// Summary over all mutation results of the repository (unfiltered)
+    pub summary: crate::db::MutationSummary,
+    pub mutation_score: f64,
+    /// Mutation testing deferred to the next schedule window, if any
+    pub deferral: Option<crate::db::MutationDeferral>,
+    pub results: Vec<MutationApiResult>,
 }
 
-/// API: Get survived mutations for a repository (for clipboard export)
-pub async fn api_survived_mutations(
+/// API: Get mutation results for a repository, filtered and paginated
+pub async fn api_repository_mutations(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
+    Query(params): Query<MutationsQuery>,
 ) -> impl IntoResponse {
     let repository = match get_repo_or_error(&state.db, id).await {
         Ok(repo) => repo,
         Err(response) => return response,
     };
 
-    let raw_results = state.db.get_mutation_results(id).await.unwrap_or_default();
-    let survived = filter_survived_mutations(raw_results, &repository.path);
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params
+        .per_page
+        .unwrap_or(DEFAULT_MUTATIONS_PER_PAGE)
+        .clamp(1, MAX_MUTATIONS_PER_PAGE);
+    let offset = (page - 1).saturating_mul(per_page);
+
+    let non_empty = |s: Option<String>| s.filter(|s| !s.is_empty());
+    let filter = MutationFilter {
+        outcome: non_empty(params.outcome),
+        file: non_empty(params.file),
+        model: non_empty(params.model),
+        status: non_empty(params.status),
+        lines: params
+            .line_start
+            .map(|start| (start, params.line_end.unwrap_or(start))),
+    };
+    let (results, total) = match state
+        .db
+        .get_mutation_results_page(id, &filter, per_page, offset)
+        .await
+    {
+        Ok(page) => page,
+        Err(e) => {
+            tracing::error!("Failed to fetch mutation results: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to fetch mutation results" })),
+            )
+                .into_response();
+        }
+    };
+    let summary = state.db.get_mutation_summary(id).await.unwrap_or_default();
+    let deferral = state.db.get_mutation_deferral(id).await.unwrap_or(None);
+
+    Json(MutationsPage {
+        page,
+        per_page,
+        total,
+        total_pages: (total + per_page as i64 - 1) / per_page as i64,
+        mutation_score: summary.mutation_score(),
+        summary,
+        deferral,
+        results: results
+            .into_iter()
+            .map(|r| MutationApiResult::from_result(r, &repository.path))
+            .collect(),
+    })
+    .into_response()
+}
 
-    Json(survived).into_response()
+/// Request body for triaging a mutation result
+#[derive(Deserialize)]
+pub struct MutationStatusRequest {
+    /// 'new', 'acknowledged', 'wont_fix' or 'fixed'
+    pub status: String,
+}
+
+/// API: Set the triage state of a mutation result
+pub async fn api_set_mutation_status(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(req): Json<MutationStatusRequest>,
+) -> impl IntoResponse {
+    if !MUTATION_STATUSES.contains(&req.status.as_str()) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!(
+                    "Unknown status '{}': expected one of {}",
+                    req.status,
+                    MUTATION_STATUSES.join(", ")
+                )
+            })),
+        )
+            .into_response();
+    }
+
+    match state.db.set_mutation_status(id, &req.status).await {
+        Ok(true) => {
+            tracing::info!("Set status of mutation {} to {}", id, req.status);
+            (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": format!("No mutation with ID {}", id) })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to set status of mutation {}: {}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to update mutation" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Strip the repository prefix from a stored absolute file path
+fn relative_to_repo(file_path: &str, repo_path: &str) -> String {
+    file_path
+        .strip_prefix(repo_path)
+        .map(|p| p.trim_start_matches('/'))
+        .unwrap_or(file_path)
+        .to_string()
 }
 
 #[cfg(test)]
@@ -591,11 +3553,201 @@ mod tests {
     use super::*;
     use crate::db::MutationResult;
 
+    fn make_analysis_result(file_path: &str, analysis_type: &str, result: &str) -> AnalysisResult {
+        AnalysisResult {
+            id: 1,
+            repository_id: 1,
+            file_path: file_path.to_string(),
+            analysis_type: analysis_type.to_string(),
+            result: result.to_string(),
+            severity: None,
+            content_hash: None,
+            created_at: "2024-01-01".to_string(),
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            issue_url: None,
+        }
+    }
+
+    #[test]
+    fn test_is_same_origin() {
+        let headers = |origin: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::HOST, "localhost:8420".parse().unwrap());
+            if let Some(origin) = origin {
+                headers.insert(header::ORIGIN, origin.parse().unwrap());
+            }
+            headers
+        };
+
+        assert!(is_same_origin(&headers(None)));
+        assert!(is_same_origin(&headers(Some("http://localhost:8420"))));
+        assert!(is_same_origin(&headers(Some("https://LOCALHOST:8420"))));
+        assert!(!is_same_origin(&headers(Some("https://evil.example"))));
+        assert!(!is_same_origin(&headers(Some("http://localhost:9000"))));
+        assert!(!is_same_origin(&headers(Some("null"))));
+    }
+
+    #[test]
+    fn test_merge_plugin_results() {
+        let results = vec![
+            make_analysis_result("/repo/b.rs", "code_understanding", "B summary"),
+            make_analysis_result("/repo/b.rs", "plugin_license", "- **error**: no header"),
+            make_analysis_result("/repo/a.rs", "plugin_license", "No findings."),
+            make_analysis_result("/repo/b.rs", "architecture_analysis", "ignored"),
+        ];
+
+        let merged = merge_plugin_results(results);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].file_path, "/repo/a.rs");
+        assert_eq!(merged[0].result, "## Plugin: license\n\nNo findings.");
+        assert_eq!(merged[1].file_path, "/repo/b.rs");
+        assert_eq!(
+            merged[1].result,
+            "B summary\n\n## Plugin: license\n\n- **error**: no header"
+        );
+    }
+
+    #[test]
+    fn test_result_history_diffs_results_of_the_same_type() {
+        let results = vec![
+            make_analysis_result("/repo/a.rs", "code_understanding", "Parses input\n"),
+            make_analysis_result("/repo/a.rs", "plugin_license", "No findings."),
+            make_analysis_result(
+                "/repo/a.rs",
+                "code_understanding",
+                "Parses and validates input\n",
+            ),
+            make_analysis_result("/repo/a.rs", "plugin_license", "No findings."),
+        ];
+
+        let history = result_history(results);
+
+        assert_eq!(history[0].diff, None);
+        assert_eq!(history[1].diff, None);
+        let diff = history[2].diff.as_deref().unwrap();
+        assert!(diff.contains("-Parses input\n+Parses and validates input\n"));
+        assert_eq!(history[3].diff.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_file_inventory() {
+        let status = |file_path: &str, status: &str| FileStatus {
+            repository_id: 1,
+            file_path: file_path.to_string(),
+            status: status.to_string(),
+            updated_at: "2024-01-01".to_string(),
+        };
+        let results = vec![
+            make_analysis_result("/repo/a.rs", "code_understanding", "A"),
+            make_analysis_result("/repo/b.rs", "code_understanding", "B"),
+        ];
+        let statuses = vec![
+            status("/repo/a.rs", FILE_STATUS_LOSSY),
+            status("/repo/dist/app.min.js", FILE_STATUS_MINIFIED),
+            status("/repo/src/api.pb.rs", FILE_STATUS_GENERATED),
+        ];
+
+        let (files, skipped) = file_inventory(results, statuses, "/repo");
+
+        assert_eq!(files.len(), 2);
+        assert!(files[0].lossy_utf8);
+        assert!(!files[1].lossy_utf8);
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].file_path, "dist/app.min.js");
+        assert_eq!(skipped[0].reason, "minified");
+        assert_eq!(skipped[1].reason, "generated");
+    }
+
+    #[test]
+    fn test_filter_by_tag() {
+        let repo = |id: i64, tags: &[&str]| Repository {
+            id,
+            path: format!("/repo{}", id),
+            name: format!("repo{}", id),
+            enabled: true,
+            created_at: String::new(),
+            updated_at: String::new(),
+            analysis_types: None,
+            sensitive: false,
+            remote_url: None,
+            branch: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            archived_at: None,
+            commands: Default::default(),
+        };
+        let repositories = vec![
+            repo(1, &["work"]),
+            repo(2, &["personal", "work"]),
+            repo(3, &[]),
+        ];
+
+        let (filtered, tags) = filter_by_tag(repositories.clone(), Some("Work"));
+        assert_eq!(filtered.iter().map(|r| r.id).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(tags, ["personal", "work"]);
+
+        let (filtered, _) = filter_by_tag(repositories.clone(), Some(""));
+        assert_eq!(filtered.len(), 3);
+        let (filtered, _) = filter_by_tag(repositories, Some("other"));
+        assert!(filtered.is_empty());
+    }
+
+    #[test]
+    fn test_repository_file() {
+        let repo = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(repo.path().join("src")).unwrap();
+        std::fs::write(repo.path().join("src/lib.rs"), "").unwrap();
+        let root = repo.path().to_string_lossy();
+        let lib = repo.path().join("src/lib.rs");
+
+        assert_eq!(repository_file(&root, "src/lib.rs"), Some(lib.clone()));
+        assert_eq!(repository_file(&root, &lib.to_string_lossy()), Some(lib));
+        assert_eq!(repository_file(&root, "src/missing.rs"), None);
+        assert_eq!(repository_file(&root, "src"), None);
+        assert_eq!(repository_file(&root, "src/../src/lib.rs"), None);
+        assert_eq!(repository_file(&root, "/etc/hostname"), None);
+    }
+
+    #[test]
+    fn test_link_related_issues() {
+        let known_issues = vec![Issue {
+            repository_id: 1,
+            number: 12,
+            title: "Parser panics on empty input".to_string(),
+            url: "https://github.com/acme/app/issues/12".to_string(),
+            labels: String::new(),
+            fetched_at: String::new(),
+        }];
+        let mut results = vec![
+            AnalysisResultView::from_result(
+                make_analysis_result(
+                    "/repo/a.rs",
+                    "code_understanding",
+                    "Parser panics on empty input",
+                ),
+                "/repo",
+            ),
+            AnalysisResultView::from_result(
+                make_analysis_result("/repo/b.rs", "code_understanding", "Looks fine"),
+                "/repo",
+            ),
+        ];
+
+        link_related_issues(&mut results, &known_issues);
+
+        assert!(results[0].result.ends_with(
+            "**Related issues:** [#12 Parser panics on empty input](https://github.com/acme/app/issues/12)"
+        ));
+        assert_eq!(results[1].result, "Looks fine");
+    }
+
     fn make_mutation_result(
         file_path: &str,
         description: &str,
         test_outcome: &str,
-        replacements_json: &str,
+        replacements: Vec<MutationReplacement>,
     ) -> MutationResult {
         MutationResult {
             id: 1,
@@ -603,22 +3755,30 @@ mod tests {
             file_path: file_path.to_string(),
             description: description.to_string(),
             reasoning: "test reasoning".to_string(),
-            replacements_json: replacements_json.to_string(),
+            replacements,
             test_outcome: test_outcome.to_string(),
             killing_test: None,
             test_output: None,
             execution_time_ms: None,
             content_hash: None,
             created_at: "2024-01-01".to_string(),
+            toolchain: None,
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            covered: None,
+            project: None,
+            issue_url: None,
+            status: "new".to_string(),
         }
     }
 
     #[test]
     fn test_filter_survived_mutations_filters_correctly() {
         let results = vec![
-            make_mutation_result("/repo/src/main.rs", "desc1", "survived", "[]"),
-            make_mutation_result("/repo/src/lib.rs", "desc2", "killed", "[]"),
-            make_mutation_result("/repo/src/test.rs", "desc3", "survived", "[]"),
+            make_mutation_result("/repo/src/main.rs", "desc1", "survived", vec![]),
+            make_mutation_result("/repo/src/lib.rs", "desc2", "killed", vec![]),
+            make_mutation_result("/repo/src/test.rs", "desc3", "survived", vec![]),
         ];
 
         let survived = filter_survived_mutations(results, "/repo");
@@ -633,7 +3793,7 @@ mod tests {
             "/home/user/project/src/main.rs",
             "desc",
             "survived",
-            "[]",
+            vec![],
         )];
 
         let survived = filter_survived_mutations(results, "/home/user/project");
@@ -643,20 +3803,46 @@ mod tests {
     }
 
     #[test]
-    fn test_filter_survived_mutations_parses_replacements() {
-        let replacements = r#"[{"line": 10, "find": "x > 0", "replace": "x >= 0"}]"#;
+    fn test_mutation_api_result_from_result() {
+        let result = make_mutation_result(
+            "/repo/src/lib.rs",
+            "Changed > to >=",
+            "killed",
+            vec![MutationReplacement {
+                line_number: 3,
+                find: "a > b".to_string(),
+                replace: "a >= b".to_string(),
+                original_line: "    a > b".to_string(),
+            }],
+        );
+
+        let api = MutationApiResult::from_result(result, "/repo");
+        assert_eq!(api.file_path, "src/lib.rs");
+        assert_eq!(api.test_outcome, "killed");
+        let json = serde_json::to_value(&api).unwrap();
+        assert_eq!(json["replacements"][0]["replace"], "a >= b");
+        assert_eq!(json["replacements"][0]["line_number"], 3);
+    }
+
+    #[test]
+    fn test_filter_survived_mutations_keeps_replacements() {
+        let replacements = vec![MutationReplacement {
+            line_number: 10,
+            find: "x > 0".to_string(),
+            replace: "x >= 0".to_string(),
+            original_line: String::new(),
+        }];
         let results = vec![make_mutation_result(
             "/repo/file.rs",
             "Changed > to >=",
             "survived",
-            replacements,
+            replacements.clone(),
         )];
 
         let survived = filter_survived_mutations(results, "/repo");
 
         assert_eq!(survived.len(), 1);
-        assert!(survived[0].replacements.is_array());
-        assert_eq!(survived[0].replacements.as_array().unwrap().len(), 1);
+        assert_eq!(survived[0].replacements, replacements);
     }
 
     #[test]
@@ -669,8 +3855,8 @@ mod tests {
     #[test]
     fn test_filter_survived_mutations_no_survived() {
         let results = vec![
-            make_mutation_result("/repo/a.rs", "desc1", "killed", "[]"),
-            make_mutation_result("/repo/b.rs", "desc2", "timeout", "[]"),
+            make_mutation_result("/repo/a.rs", "desc1", "killed", vec![]),
+            make_mutation_result("/repo/b.rs", "desc2", "timeout", vec![]),
         ];
 
         let survived = filter_survived_mutations(results, "/repo");
@@ -0,0 +1,246 @@
+//! Rate limiting and queueing of expensive API requests.
+//!
+//! Triggering a scan, regenerating a diagram or testing an Ollama connection
+//! starts far more work than serving a page, so these endpoints share one
+//! [`ExpensiveLimit`] layer. Requests beyond the configured rate, or arriving
+//! while the queue is full, are rejected with `429 Too Many Requests` and a
+//! `Retry-After` header; admitted requests run one at a time, in order.
+
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::future::BoxFuture;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tower::{Layer, Service, ServiceExt};
+
+/// How long clients are asked to wait when the queue is full
+const QUEUE_FULL_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// Shared limit on the expensive endpoints: a token bucket refilled at
+/// `requests_per_minute`, and a queue of at most `queue_size` requests
+/// waiting for the running one.
+#[derive(Clone)]
+pub struct ExpensiveLimit {
+    inner: Arc<LimitState>,
+}
+
+struct LimitState {
+    bucket: Mutex<TokenBucket>,
+    /// Held by the request that is running
+    running: Semaphore,
+    queue_size: usize,
+    /// Requests admitted and not finished, running or waiting
+    admitted: AtomicUsize,
+}
+
+/// Allows bursts of up to `capacity` requests, then one per `interval`.
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    interval: Duration,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32, now: Instant) -> Self {
+        let per_minute = per_minute.max(1);
+        Self {
+            capacity: per_minute as f64,
+            tokens: per_minute as f64,
+            interval: Duration::from_secs(60) / per_minute,
+            refilled_at: now,
+        }
+    }
+
+    /// Take a token, or return how long until one is available
+    fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() / self.interval.as_secs_f64()).min(self.capacity);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(self.interval.mul_f64(1.0 - self.tokens))
+        }
+    }
+}
+
+/// An admitted request, counted until dropped
+struct Admission(Arc<LimitState>);
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        self.0.admitted.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ExpensiveLimit {
+    pub fn new(requests_per_minute: u32, queue_size: usize) -> Self {
+        Self {
+            inner: Arc::new(LimitState {
+                bucket: Mutex::new(TokenBucket::new(requests_per_minute, Instant::now())),
+                running: Semaphore::new(1),
+                queue_size,
+                admitted: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Requests waiting for the running one to finish
+    pub fn queue_depth(&self) -> usize {
+        let running = 1 - self.inner.running.available_permits();
+        self.inner
+            .admitted
+            .load(Ordering::SeqCst)
+            .saturating_sub(running)
+    }
+
+    /// Admit a request, or return how long the client should wait
+    fn admit(&self, now: Instant) -> Result<Admission, Duration> {
+        // The bucket lock also serializes admission, so the queue can't overfill
+        let mut bucket = self
+            .inner
+            .bucket
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if self.inner.admitted.load(Ordering::SeqCst) > self.inner.queue_size {
+            return Err(QUEUE_FULL_RETRY_AFTER);
+        }
+        bucket.take(now)?;
+        self.inner.admitted.fetch_add(1, Ordering::SeqCst);
+        Ok(Admission(self.inner.clone()))
+    }
+}
+
+impl<S> Layer<S> for ExpensiveLimit {
+    type Service = ExpensiveLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ExpensiveLimitService {
+            limit: self.clone(),
+            inner,
+        }
+    }
+}
+
+/// Service applying an [`ExpensiveLimit`] to the requests of a route
+#[derive(Clone)]
+pub struct ExpensiveLimitService<S> {
+    limit: ExpensiveLimit,
+    inner: S,
+}
+
+impl<S> Service<Request> for ExpensiveLimitService<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Readiness of the inner service is awaited in `call`
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let limit = self.limit.clone();
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            let _admission = match limit.admit(Instant::now()) {
+                Ok(admission) => admission,
+                Err(retry_after) => return Ok(too_many_requests(retry_after)),
+            };
+            // The semaphore is never closed
+            let _running = limit.inner.running.acquire().await;
+            inner.oneshot(request).await
+        })
+    }
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    // Retry-After is in whole seconds; round up so retrying on time succeeds
+    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(serde_json::json!({
+            "error": "Too many expensive requests, try again later",
+            "retry_after_seconds": seconds,
+        })),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, seconds.into());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::service_fn;
+
+    #[test]
+    fn test_token_bucket_refills_over_time() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, start);
+
+        assert!(bucket.take(start).is_ok());
+        assert!(bucket.take(start).is_ok());
+        assert_eq!(bucket.take(start), Err(Duration::from_secs(30)));
+        assert_eq!(
+            bucket.take(start + Duration::from_secs(20)),
+            Err(Duration::from_secs(10))
+        );
+        assert!(bucket.take(start + Duration::from_secs(30)).is_ok());
+    }
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let limit = ExpensiveLimit::new(60, 1);
+        let now = Instant::now();
+
+        let first = limit.admit(now).unwrap();
+        let _second = limit.admit(now).unwrap();
+        assert_eq!(limit.queue_depth(), 2);
+        assert_eq!(limit.admit(now).err(), Some(QUEUE_FULL_RETRY_AFTER));
+
+        drop(first);
+        assert_eq!(limit.queue_depth(), 1);
+        assert!(limit.admit(now).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_with_retry_after() {
+        let limit = ExpensiveLimit::new(1, 4);
+        let service = limit.layer(service_fn(|_: Request| async {
+            Ok::<_, Infallible>(StatusCode::OK.into_response())
+        }));
+
+        let response = service.clone().oneshot(Request::new(Body::empty())).await;
+        assert_eq!(response.unwrap().status(), StatusCode::OK);
+
+        let response = service.oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((59..=60).contains(&retry_after));
+        assert_eq!(limit.queue_depth(), 0);
+    }
+}
@@ -4,8 +4,11 @@
 //! and a JSON API for configuration and triggering scans.
 
 mod handlers;
+mod limit;
 mod templates;
 
+pub use limit::ExpensiveLimit;
+
 use crate::AppState;
 use axum::{
     body::Body,
@@ -75,13 +78,57 @@ async fn validate_host(request: Request, next: Next) -> Result<Response, StatusC
 pub async fn start_server(state: Arc<AppState>, host: &str, port: u16) -> anyhow::Result<()> {
     // Only enforce host validation when binding to localhost
     let is_localhost = matches!(host, "127.0.0.1" | "localhost" | "::1");
+    let expensive = state.expensive.clone();
 
     let app = Router::new()
         // Repositories (default page)
         .route("/", get(handlers::list_repositories))
         .route("/repositories", get(handlers::list_repositories))
         .route("/repositories", post(handlers::add_repository))
-        .route("/repositories/:id", delete(handlers::delete_repository))
+        .route(
+            "/api/repositories/scan-dir",
+            post(handlers::api_scan_dir).layer(expensive.clone()),
+        )
+        .route(
+            "/api/repositories/:id/archive",
+            post(handlers::archive_repository),
+        )
+        .route(
+            "/api/repositories/:id/restore",
+            post(handlers::restore_repository),
+        )
+        .route(
+            "/api/repositories/:id/purge",
+            post(handlers::purge_repository),
+        )
+        .route(
+            "/api/repositories/:id/stale-results",
+            delete(handlers::api_purge_stale_results),
+        )
+        .route(
+            "/api/repositories/:id/onboarding",
+            get(handlers::api_repository_onboarding),
+        )
+        .route(
+            "/api/repositories/:id/onboarding/confirm",
+            post(handlers::api_confirm_onboarding),
+        )
+        .route(
+            "/repositories/:id/analysis-types",
+            post(handlers::update_repository_analysis_types),
+        )
+        .route(
+            "/repositories/:id/sensitive",
+            post(handlers::update_repository_sensitive),
+        )
+        .route(
+            "/repositories/:id/tags",
+            post(handlers::update_repository_tags),
+        )
+        .route(
+            "/repositories/:id/commands",
+            post(handlers::update_repository_commands),
+        )
         .route(
             "/repositories/:id/results",
             get(handlers::repository_results),
@@ -99,8 +146,18 @@ pub async fn start_server(state: Arc<AppState>, host: &str, port: u16) -> anyhow
             "/repositories/:id/diagrams",
             get(handlers::repository_diagrams),
         )
+        .route(
+            "/repositories/:id/duplication",
+            get(handlers::repository_duplication),
+        )
+        .route("/repositories/:id/chat", get(handlers::repository_chat))
+        .route("/repositories/:id/history", get(handlers::file_history))
         // Settings / Endpoints
         .route("/settings", get(handlers::settings))
+        .route("/search", get(handlers::search_page))
+        .route("/findings", get(handlers::findings_page))
+        .route("/logs", get(handlers::logs_page))
+        .route("/history", get(handlers::history_page))
         .route("/endpoints", post(handlers::add_endpoint))
         .route("/endpoints/:id", post(handlers::update_endpoint))
         .route("/endpoints/:id", delete(handlers::delete_endpoint))
@@ -108,20 +165,115 @@ pub async fn start_server(state: Arc<AppState>, host: &str, port: u16) -> anyhow
         .route("/api/status", get(handlers::api_status))
         .route("/api/repositories", get(handlers::api_repositories))
         .route("/api/results", get(handlers::api_results))
+        .route("/api/results", delete(handlers::api_purge_results))
+        .route(
+            "/api/results/:file/history",
+            get(handlers::api_result_history),
+        )
         .route("/api/endpoints", get(handlers::api_endpoints))
-        .route("/api/test-ollama", post(handlers::api_test_ollama))
+        .route("/api/endpoints/health", get(handlers::api_endpoints_health))
+        .route("/api/usage", get(handlers::api_usage))
+        .route("/api/audit", get(handlers::api_audit))
+        .route("/api/runs", get(handlers::api_scan_runs))
+        .route("/api/jobs", get(handlers::api_jobs))
+        .route("/api/queue", get(handlers::api_queue))
+        .route("/api/queue/:id", post(handlers::api_update_queued_job))
+        .route("/api/queue/:id", delete(handlers::api_delete_queued_job))
+        .route("/api/events", get(handlers::api_events))
+        .route("/api/logs/stream", get(handlers::api_logs_stream))
+        .route("/api/search", get(handlers::api_search))
+        .route("/api/findings", get(handlers::api_findings))
+        .route("/api/chat", get(handlers::api_chat_history))
+        .route("/api/chat", post(handlers::api_chat))
+        .route("/api/chat", delete(handlers::api_clear_chat))
+        .route("/ws", get(handlers::ws_updates))
+        .route("/api/query", get(handlers::api_list_queries))
+        .route("/api/query/:name", get(handlers::api_query))
+        .route(
+            "/api/test-ollama",
+            post(handlers::api_test_ollama).layer(expensive.clone()),
+        )
         // Config API
         .route("/api/config", get(handlers::api_get_config))
         .route("/api/config", post(handlers::api_update_config))
         .route("/api/config/save", post(handlers::api_save_config))
         .route("/api/config/reload", post(handlers::api_reload_config))
+        // Schedule API
+        .route("/api/schedule", get(handlers::api_schedule))
+        .route("/api/schedule/pause", post(handlers::api_pause_schedule))
+        .route("/api/schedule/pause", delete(handlers::api_resume_schedule))
+        // Admin API
+        .route(
+            "/api/admin/backup",
+            post(handlers::api_backup).layer(expensive.clone()),
+        )
         // Scan API
-        .route("/api/scan/trigger", post(handlers::api_trigger_scan))
+        .route(
+            "/api/scan/trigger",
+            post(handlers::api_trigger_scan).layer(expensive.clone()),
+        )
+        .route("/api/daemon/pause", post(handlers::api_pause_daemon))
+        .route("/api/daemon/resume", post(handlers::api_resume_daemon))
+        .route(
+            "/api/repositories/:id/files/reanalyze",
+            post(handlers::api_reanalyze_file),
+        )
+        .route(
+            "/api/repositories/:id/changelog",
+            get(handlers::api_repository_changelog),
+        )
+        .route(
+            "/api/repositories/:id/report",
+            get(handlers::api_repository_report),
+        )
+        .route(
+            "/api/repositories/:id/sarif",
+            get(handlers::api_repository_sarif),
+        )
+        .route(
+            "/api/repositories/:id/badge/:badge",
+            get(handlers::api_repository_badge),
+        )
+        .route(
+            "/api/repositories/:id/coverage",
+            get(handlers::api_repository_coverage),
+        )
+        .route(
+            "/api/repositories/:id/architecture",
+            get(handlers::api_repository_architecture),
+        )
+        .route(
+            "/api/repositories/:id/diagrams",
+            get(handlers::api_repository_diagrams),
+        )
+        .route(
+            "/api/repositories/:id/diagrams/:type/versions",
+            get(handlers::api_diagram_versions),
+        )
+        .route("/api/diagrams/:id", get(handlers::api_diagram))
+        .route("/api/diagrams/:id/dot", get(handlers::api_diagram_dot))
+        .route("/api/diagrams/:id/svg", get(handlers::api_diagram_svg))
+        .route(
+            "/api/repositories/:id/diagrams/:type/regenerate",
+            post(handlers::api_regenerate_diagram).layer(expensive),
+        )
+        .route(
+            "/api/findings/:id/create-issue",
+            post(handlers::api_create_issue),
+        )
         // Mutations API
+        .route(
+            "/api/repositories/:id/mutations",
+            get(handlers::api_repository_mutations),
+        )
         .route(
             "/api/repositories/:id/mutations/survived",
             get(handlers::api_survived_mutations),
         )
+        .route(
+            "/api/mutations/:id/status",
+            post(handlers::api_set_mutation_status),
+        )
         // Static files (embedded in binary)
         .route("/static/*path", get(serve_static))
         // State
@@ -4,10 +4,17 @@
 //! database models for display (e.g., converting absolute paths to relative).
 
 use crate::config::OllamaEndpoint;
-use crate::db::{AnalysisResult, Diagram, MutationResult, MutationSummary, Repository};
-use askama::Template;
+use crate::daemon::EndpointHealth;
+use crate::db::{
+    AnalysisFinding, AnalysisResult, ChatMessage, DailyUsage, Diagram, DuplicateCluster,
+    HealthScore, MutationDeferral, MutationResult, MutationSummary, MutationTestSuggestion,
+    Onboarding, Repository, ScanRun, UsageTotal,
+};
+use crate::duplication::FilePair;
+use crate::theme::Page;
 use pulldown_cmark::{html, Options, Parser};
 use serde::Serialize;
+use std::collections::HashMap;
 
 /// Render markdown to HTML
 pub fn render_markdown(s: &str) -> String {
@@ -22,21 +29,245 @@ pub fn render_markdown(s: &str) -> String {
     html_output
 }
 
-#[derive(Template)]
-#[template(path = "repositories.html")]
+#[derive(Serialize)]
 pub struct RepositoriesTemplate {
-    pub repositories: Vec<Repository>,
+    pub repositories: Vec<RepositoryView>,
+    /// Every pass that can be toggled per repository
+    pub analysis_passes: &'static [&'static str],
+    /// Tags of all repositories, for the filter
+    pub tags: Vec<String>,
+    /// Only repositories with this tag are shown
+    pub selected_tag: Option<String>,
+    /// The archived repositories are shown instead of the active ones
+    pub archived: bool,
+    /// Number of archived repositories
+    pub archived_count: usize,
 }
 
-#[derive(Template)]
-#[template(path = "settings.html")]
+/// A repository row with its selected analysis passes and health trend
+#[derive(Serialize)]
+pub struct RepositoryView {
+    #[serde(flatten)]
+    pub repository: Repository,
+    pub passes: Vec<&'static str>,
+    /// Path, or Git URL and branch of a remote repository
+    pub source: String,
+    /// The latest health score and its components
+    pub score: Option<HealthScore>,
+    /// Change of the score since the oldest one shown in the trend
+    pub score_change: Option<f64>,
+    /// `points` of an SVG polyline of the recent scores
+    pub sparkline: Option<String>,
+    /// Confirmation token for purging an archived repository
+    pub purge_token: Option<String>,
+}
+
+/// Width and height of the score sparkline
+pub const SPARKLINE_SIZE: (f64, f64) = (100.0, 24.0);
+
+impl RepositoryView {
+    /// `scores` are the repository's recent health scores, oldest first
+    pub fn new(repository: Repository, scores: Vec<HealthScore>) -> Self {
+        let values: Vec<f64> = scores.iter().map(|s| s.score).collect();
+        let score_change = match (values.first(), values.last()) {
+            (Some(first), Some(last)) if values.len() > 1 => {
+                Some(((last - first) * 10.0).round() / 10.0)
+            }
+            _ => None,
+        };
+        Self {
+            passes: repository.analysis_passes(),
+            source: crate::remote::describe_source(&repository),
+            purge_token: repository.purge_token(),
+            repository,
+            score: scores.into_iter().last(),
+            score_change,
+            sparkline: sparkline(&values),
+        }
+    }
+}
+
+/// Polyline points of scores from 0 to 100 spread over [`SPARKLINE_SIZE`];
+/// a single score is drawn as a flat line
+fn sparkline(scores: &[f64]) -> Option<String> {
+    let (width, height) = SPARKLINE_SIZE;
+    let y = |score: f64| height - score.clamp(0.0, 100.0) / 100.0 * height;
+    let points: Vec<(f64, f64)> = match scores {
+        [] => return None,
+        [score] => vec![(0.0, y(*score)), (width, y(*score))],
+        _ => {
+            let step = width / (scores.len() - 1) as f64;
+            scores
+                .iter()
+                .enumerate()
+                .map(|(i, score)| (i as f64 * step, y(*score)))
+                .collect()
+        }
+    };
+    Some(
+        points
+            .iter()
+            .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+impl Page for RepositoriesTemplate {
+    const TEMPLATE: &'static str = "repositories.html";
+}
+
+#[derive(Serialize)]
 pub struct SettingsTemplate {
     pub endpoints: Vec<OllamaEndpoint>,
+    pub health: Vec<EndpointHealthView>,
+    /// Token usage per day over the last week
+    pub daily_usage: Vec<DailyUsage>,
+    /// Token usage over the last week per endpoint, repository and analysis type
+    pub usage_totals: Vec<UsageTotal>,
     pub start_hour: u8,
     pub end_hour: u8,
+    /// Descriptions of the named schedule windows, which replace the hour range
+    pub schedule_windows: Vec<String>,
+    /// Local time scheduled processing is paused until
+    pub paused_until: Option<String>,
     pub config_path: String,
 }
 
+impl Page for SettingsTemplate {
+    const TEMPLATE: &'static str = "settings.html";
+}
+
+/// Semantic search; results are fetched by the page itself
+#[derive(Serialize)]
+pub struct SearchTemplate {}
+
+impl Page for SearchTemplate {
+    const TEMPLATE: &'static str = "search.html";
+}
+
+/// Open error and warning findings of all repositories
+#[derive(Serialize)]
+pub struct FindingsTemplate {
+    pub findings: Vec<GlobalFindingView>,
+    /// Severity the findings are limited to (empty for errors and warnings)
+    pub severity: String,
+    /// List the newest findings first instead of the most severe
+    pub recent_first: bool,
+}
+
+impl Page for FindingsTemplate {
+    const TEMPLATE: &'static str = "findings.html";
+}
+
+/// A finding with the repository it was reported in
+#[derive(Serialize)]
+pub struct GlobalFindingView {
+    pub repository_id: i64,
+    pub repository_name: String,
+    #[serde(flatten)]
+    pub finding: FindingView,
+}
+
+/// Live daemon log; records are streamed in by the page itself
+#[derive(Serialize)]
+pub struct LogsTemplate {}
+
+impl Page for LogsTemplate {
+    const TEMPLATE: &'static str = "logs.html";
+}
+
+/// Timeline of the daemon's processing cycles
+#[derive(Serialize)]
+pub struct HistoryTemplate {
+    pub runs: Vec<ScanRunView>,
+}
+
+impl Page for HistoryTemplate {
+    const TEMPLATE: &'static str = "history.html";
+}
+
+/// A processing cycle with its times formatted for display
+#[derive(Serialize)]
+pub struct ScanRunView {
+    #[serde(flatten)]
+    pub run: ScanRun,
+    /// Local start time, e.g. "2025-01-01 02:00"
+    pub started: String,
+    /// How long the cycle took, e.g. "1h 05m" (None while running or if it was
+    /// interrupted)
+    pub duration: Option<String>,
+}
+
+impl From<ScanRun> for ScanRunView {
+    fn from(run: ScanRun) -> Self {
+        let parse = |s: &str| chrono::DateTime::parse_from_rfc3339(s).ok();
+        let started_at = parse(&run.started_at);
+        let started = started_at
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|| run.started_at.clone());
+        let duration = started_at
+            .zip(run.finished_at.as_deref().and_then(parse))
+            .map(|(start, end)| {
+                let seconds = (end - start).num_seconds().max(0);
+                match seconds {
+                    s if s < 60 => format!("{}s", s),
+                    s if s < 3600 => format!("{}m {:02}s", s / 60, s % 60),
+                    s => format!("{}h {:02}m", s / 3600, s % 3600 / 60),
+                }
+            });
+        Self {
+            run,
+            started,
+            duration,
+        }
+    }
+}
+
+/// Endpoint health formatted for the settings page
+#[derive(Clone, Serialize)]
+pub struct EndpointHealthView {
+    pub name: String,
+    pub status: String,
+    pub latency: String,
+    pub model_loaded: String,
+    pub consecutive_failures: u32,
+    /// Last error, plus when the endpoint is retried if its circuit is open
+    pub detail: String,
+    pub last_checked: String,
+}
+
+impl EndpointHealthView {
+    pub fn from_health(health: EndpointHealth) -> Self {
+        let mut detail = health.last_error.unwrap_or_default();
+        if let Some(seconds) = health.retry_in_seconds {
+            detail = format!("Retrying in {}s. {}", seconds, detail);
+        }
+
+        Self {
+            name: health.name,
+            status: health.status.replace('_', "-"),
+            latency: health
+                .latency_ms
+                .map(|ms| format!("{} ms", ms))
+                .unwrap_or_else(|| "-".to_string()),
+            model_loaded: match health.model_loaded {
+                Some(true) => "Yes",
+                Some(false) => "No",
+                None => "-",
+            }
+            .to_string(),
+            consecutive_failures: health.consecutive_failures,
+            detail,
+            last_checked: health.last_checked.unwrap_or_else(|| "never".to_string()),
+        }
+    }
+}
+
 /// An analysis result with a relative file path for display
 #[derive(Clone, Serialize)]
 pub struct AnalysisResultView {
@@ -48,6 +279,8 @@ pub struct AnalysisResultView {
     pub severity: Option<String>,
     pub content_hash: Option<String>,
     pub created_at: String,
+    /// The file wasn't valid UTF-8 and was analyzed after lossy decoding
+    pub lossy_utf8: bool,
 }
 
 impl AnalysisResultView {
@@ -69,23 +302,189 @@ impl AnalysisResultView {
             severity: result.severity,
             content_hash: result.content_hash,
             created_at: result.created_at,
+            lossy_utf8: false,
         }
     }
 }
 
-#[derive(Template)]
-#[template(path = "repository_architecture.html")]
+/// A file that was skipped instead of analyzed, for the file inventory
+#[derive(Clone, Serialize)]
+pub struct SkippedFileView {
+    /// File path relative to the repository root
+    pub file_path: String,
+    /// Human-readable reason, e.g. "minified"
+    pub reason: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize)]
 pub struct RepositoryArchitectureTemplate {
     pub repository: Repository,
     pub architecture_summary: Option<AnalysisResult>,
     pub architecture_summary_html: String,
+    /// Quick scan of a repository awaiting confirmation
+    pub onboarding: Option<Onboarding>,
+}
+
+impl Page for RepositoryArchitectureTemplate {
+    const TEMPLATE: &'static str = "repository_architecture.html";
 }
 
-#[derive(Template)]
-#[template(path = "repository_files.html")]
+#[derive(Serialize)]
 pub struct RepositoryFilesTemplate {
     pub repository: Repository,
     pub file_results: Vec<AnalysisResultView>,
+    pub skipped_files: Vec<SkippedFileView>,
+    pub findings: Vec<FindingView>,
+    /// Categories present in `findings`, for the category filter
+    pub finding_categories: Vec<String>,
+    /// Findings the latest scans stopped reporting, most recent first
+    pub resolved_findings: Vec<FindingView>,
+    /// Files (relative paths) waiting to be analyzed again by the next scan
+    pub reanalysis_requested: Vec<String>,
+    /// Number of deleted files whose results are kept, hidden, until purged
+    pub stale_files: i64,
+}
+
+impl Page for RepositoryFilesTemplate {
+    const TEMPLATE: &'static str = "repository_files.html";
+}
+
+/// A structured finding with a relative file path for display
+#[derive(Clone, Serialize)]
+pub struct FindingView {
+    pub file_path: String,
+    pub title: String,
+    pub severity: String,
+    pub category: String,
+    /// "12" or "12-15" (empty if the finding isn't tied to lines)
+    pub lines: String,
+    pub recommendation: String,
+    /// Date the finding was first reported (empty if unknown)
+    pub first_seen: String,
+    /// Number of scans that reported the finding
+    pub occurrences: i64,
+    /// Date the finding stopped being reported (empty while open)
+    pub resolved_at: String,
+}
+
+impl FindingView {
+    /// Create a view from a finding, stripping the repo path from file_path
+    pub fn from_finding(finding: AnalysisFinding, repo_path: &str) -> Self {
+        let file_path = finding
+            .file_path
+            .strip_prefix(repo_path)
+            .map(|p| p.trim_start_matches('/'))
+            .unwrap_or(&finding.file_path)
+            .to_string();
+        let lines = match (finding.line_start, finding.line_end) {
+            (Some(start), Some(end)) if end > start => format!("{}-{}", start, end),
+            (Some(start), _) => start.to_string(),
+            _ => String::new(),
+        };
+
+        Self {
+            file_path,
+            title: finding.title,
+            severity: finding.severity,
+            category: finding.category,
+            lines,
+            recommendation: finding.recommendation,
+            first_seen: date_of(finding.first_seen.as_deref()),
+            occurrences: finding.occurrences,
+            resolved_at: date_of(finding.resolved_at.as_deref()),
+        }
+    }
+}
+
+/// Date part of an RFC 3339 timestamp
+fn date_of(timestamp: Option<&str>) -> String {
+    timestamp
+        .map(|t| t.get(..10).unwrap_or(t).to_string())
+        .unwrap_or_default()
+}
+
+/// One analysis of a file on the history page
+#[derive(Clone, Serialize)]
+pub struct HistoryEntryView {
+    pub analysis_type: String,
+    pub created_at: String,
+    pub severity: Option<String>,
+    pub model: Option<String>,
+    pub result_html: String,
+    /// No earlier result of this analysis type
+    pub first: bool,
+    /// Lines of the diff from the previous result (empty when unchanged)
+    pub diff_lines: Vec<DiffLineView>,
+}
+
+/// A line of a unified diff, classified for styling
+#[derive(Clone, Serialize)]
+pub struct DiffLineView {
+    /// "added", "removed", "hunk" or "context"
+    pub kind: &'static str,
+    pub text: String,
+}
+
+impl HistoryEntryView {
+    /// Create a view from a result and the unified diff from its predecessor
+    pub fn from_entry(result: AnalysisResult, diff: Option<&str>) -> Self {
+        let diff_lines = diff
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.starts_with('\\'))
+            .map(|line| {
+                let (kind, text) = if line.starts_with("@@") {
+                    ("hunk", line)
+                } else if let Some(text) = line.strip_prefix('+') {
+                    ("added", text)
+                } else if let Some(text) = line.strip_prefix('-') {
+                    ("removed", text)
+                } else {
+                    ("context", line.strip_prefix(' ').unwrap_or(line))
+                };
+                DiffLineView {
+                    kind,
+                    text: text.to_string(),
+                }
+            })
+            .collect();
+
+        Self {
+            analysis_type: result.analysis_type,
+            created_at: result.created_at,
+            severity: result.severity,
+            model: result.model,
+            result_html: render_markdown(&result.result),
+            first: diff.is_none(),
+            diff_lines,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct FileHistoryTemplate {
+    pub repository: Repository,
+    /// File path relative to the repository root
+    pub file_path: String,
+    /// Analysis types the file has results for, for the filter
+    pub analysis_types: Vec<String>,
+    /// Selected analysis type (empty for all)
+    pub analysis_type: String,
+    /// Results, newest first
+    pub entries: Vec<HistoryEntryView>,
+}
+
+impl Page for FileHistoryTemplate {
+    const TEMPLATE: &'static str = "file_history.html";
+}
+
+/// One replaced line of a mutation, before and after
+#[derive(Clone, Serialize)]
+pub struct ReplacementView {
+    pub line_number: i64,
+    pub original: String,
+    pub mutated: String,
 }
 
 /// A mutation result with a relative file path for display
@@ -96,13 +495,20 @@ pub struct MutationResultView {
     pub file_path: String,
     pub description: String,
     pub reasoning: String,
-    pub replacements_json: String,
+    pub replacements: Vec<ReplacementView>,
     pub test_outcome: String,
     pub killing_test: Option<String>,
     pub test_output: Option<String>,
     pub execution_time_ms: Option<i32>,
     pub content_hash: Option<String>,
     pub created_at: String,
+    pub toolchain: Option<String>,
+    /// Project whose tests ran the mutation
+    pub project: Option<String>,
+    /// Triage state
+    pub status: String,
+    /// Test cases suggested to kill a surviving mutation
+    pub test_suggestions: Vec<MutationTestSuggestion>,
 }
 
 impl MutationResultView {
@@ -121,36 +527,235 @@ impl MutationResultView {
             file_path: relative_path,
             description: result.description,
             reasoning: result.reasoning,
-            replacements_json: result.replacements_json,
+            replacements: result
+                .replacements
+                .iter()
+                .map(|r| ReplacementView {
+                    line_number: r.line_number,
+                    original: r.original().to_string(),
+                    mutated: r.mutated(),
+                })
+                .collect(),
             test_outcome: result.test_outcome,
             killing_test: result.killing_test,
             test_output: result.test_output,
             execution_time_ms: result.execution_time_ms,
             content_hash: result.content_hash,
             created_at: result.created_at,
+            toolchain: result.toolchain,
+            project: result.project,
+            status: result.status,
+            test_suggestions: Vec::new(),
         }
     }
 }
 
-#[derive(Template)]
-#[template(path = "mutation_results.html")]
+#[derive(Serialize)]
 pub struct MutationResultsTemplate {
     pub repository: Repository,
     pub results: Vec<MutationResultView>,
     pub summary: MutationSummary,
     pub mutation_score_percent: String,
+    /// Files left for the next schedule window
+    pub deferral: Option<MutationDeferral>,
+}
+
+impl Page for MutationResultsTemplate {
+    const TEMPLATE: &'static str = "mutation_results.html";
 }
 
-#[derive(Template)]
-#[template(path = "repository_diagrams.html")]
+#[derive(Serialize)]
 pub struct RepositoryDiagramsTemplate {
     pub repository: Repository,
     pub diagrams: Vec<Diagram>,
+    /// IDs of the saved versions of each diagram type, oldest first
+    pub versions: HashMap<String, Vec<i64>>,
+}
+
+impl Page for RepositoryDiagramsTemplate {
+    const TEMPLATE: &'static str = "repository_diagrams.html";
+}
+
+#[derive(Serialize)]
+pub struct RepositoryChatTemplate {
+    pub repository: Repository,
+    pub messages: Vec<ChatMessageView>,
+    /// Whether relevant files are found by semantic search (or by keywords)
+    pub search_enabled: bool,
+}
+
+impl Page for RepositoryChatTemplate {
+    const TEMPLATE: &'static str = "repository_chat.html";
+}
+
+/// A chat message with its Markdown rendered
+#[derive(Serialize)]
+pub struct ChatMessageView {
+    #[serde(flatten)]
+    pub message: ChatMessage,
+    pub content_html: String,
+}
+
+impl From<ChatMessage> for ChatMessageView {
+    fn from(message: ChatMessage) -> Self {
+        Self {
+            content_html: render_markdown(&message.content),
+            message,
+        }
+    }
+}
+
+/// A pair of files sharing code, with paths relative to the repository
+#[derive(Clone, Serialize)]
+pub struct DuplicatePairView {
+    pub file_a: String,
+    pub file_b: String,
+    pub duplicated_lines: i64,
+    /// Similarity as a percentage with one decimal
+    pub similarity_percent: String,
+    pub blocks: usize,
+}
+
+impl DuplicatePairView {
+    pub fn from_pair(pair: FilePair, repo_path: &str) -> Self {
+        let relative = |path: &str| {
+            path.strip_prefix(repo_path)
+                .map(|p| p.trim_start_matches('/'))
+                .unwrap_or(path)
+                .to_string()
+        };
+        Self {
+            file_a: relative(&pair.file_a),
+            file_b: relative(&pair.file_b),
+            duplicated_lines: pair.duplicated_lines,
+            similarity_percent: format!("{:.1}", pair.similarity * 100.0),
+            blocks: pair.blocks,
+        }
+    }
+}
+
+/// One occurrence of a duplicated block
+#[derive(Clone, Serialize)]
+pub struct DuplicateLocationView {
+    pub file_path: String,
+    /// Line range, e.g. "12-30"
+    pub lines: String,
+}
+
+/// A duplicated block and where it occurs
+#[derive(Clone, Serialize)]
+pub struct DuplicateClusterView {
+    pub line_count: i64,
+    pub locations: Vec<DuplicateLocationView>,
+}
+
+impl DuplicateClusterView {
+    /// Create a view from a cluster, stripping the repo path from file paths
+    pub fn from_cluster(cluster: DuplicateCluster, repo_path: &str) -> Self {
+        Self {
+            line_count: cluster.line_count,
+            locations: cluster
+                .locations
+                .into_iter()
+                .map(|location| DuplicateLocationView {
+                    file_path: location
+                        .file_path
+                        .strip_prefix(repo_path)
+                        .map(|p| p.trim_start_matches('/'))
+                        .unwrap_or(&location.file_path)
+                        .to_string(),
+                    lines: format!("{}-{}", location.line_start, location.line_end),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct RepositoryDuplicationTemplate {
+    pub repository: Repository,
+    /// Files sharing code, most similar first
+    pub pairs: Vec<DuplicatePairView>,
+    /// Duplicated blocks, largest first
+    pub clusters: Vec<DuplicateClusterView>,
+}
+
+impl Page for RepositoryDuplicationTemplate {
+    const TEMPLATE: &'static str = "repository_duplication.html";
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::ANALYSIS_PASSES;
+
+    #[test]
+    fn test_finding_view_from_finding() {
+        let finding = AnalysisFinding {
+            id: 1,
+            analysis_result_id: 2,
+            repository_id: 3,
+            file_path: "/repo/src/lib.rs".to_string(),
+            title: "Unchecked index".to_string(),
+            severity: "warning".to_string(),
+            category: "bug".to_string(),
+            line_start: Some(12),
+            line_end: Some(15),
+            recommendation: "Use get()".to_string(),
+            fingerprint: Some("abc".to_string()),
+            first_seen: Some("2025-01-02T03:04:05.678Z".to_string()),
+            last_seen: Some("2025-02-02T03:04:05.678Z".to_string()),
+            occurrences: 3,
+            resolved_at: None,
+        };
+        let view = FindingView::from_finding(finding.clone(), "/repo");
+        assert_eq!(view.file_path, "src/lib.rs");
+        assert_eq!(view.lines, "12-15");
+        assert_eq!(view.first_seen, "2025-01-02");
+        assert_eq!(view.resolved_at, "");
+
+        let single = FindingView::from_finding(
+            AnalysisFinding {
+                line_end: Some(12),
+                ..finding.clone()
+            },
+            "/repo",
+        );
+        assert_eq!(single.lines, "12");
+
+        let unlocated = FindingView::from_finding(
+            AnalysisFinding {
+                line_start: None,
+                line_end: None,
+                ..finding
+            },
+            "/repo",
+        );
+        assert_eq!(unlocated.lines, "");
+    }
+
+    fn score(score: f64) -> HealthScore {
+        HealthScore {
+            id: 1,
+            repository_id: 1,
+            scan_run_id: None,
+            score,
+            kill_rate: Some(0.5),
+            finding_score: None,
+            doc_coverage: None,
+            created_at: "2025-01-01".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[]), None);
+        assert_eq!(sparkline(&[50.0]).unwrap(), "0.0,12.0 100.0,12.0");
+        assert_eq!(
+            sparkline(&[0.0, 100.0, 75.0]).unwrap(),
+            "0.0,24.0 50.0,0.0 100.0,6.0"
+        );
+    }
 
     #[test]
     fn test_render_markdown_basic() {
@@ -213,6 +818,10 @@ mod tests {
             severity: Some("info".to_string()),
             content_hash: Some("hash".to_string()),
             created_at: "2025-01-01".to_string(),
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            issue_url: None,
         };
 
         let view = AnalysisResultView::from_result(result, "/repo/path");
@@ -230,6 +839,10 @@ mod tests {
             severity: None,
             content_hash: None,
             created_at: "2025-01-01".to_string(),
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            issue_url: None,
         };
 
         let view = AnalysisResultView::from_result(result, "/repo/path");
@@ -247,6 +860,10 @@ mod tests {
             severity: None,
             content_hash: None,
             created_at: "2025-01-01".to_string(),
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            issue_url: None,
         };
 
         let view = AnalysisResultView::from_result(result, "/repo/path");
@@ -261,13 +878,21 @@ mod tests {
             file_path: "/repo/path/src/main.rs".to_string(),
             description: "test".to_string(),
             reasoning: "reason".to_string(),
-            replacements_json: "{}".to_string(),
+            replacements: Vec::new(),
             test_outcome: "killed".to_string(),
             killing_test: Some("test_foo".to_string()),
             test_output: Some("output".to_string()),
             execution_time_ms: Some(100),
             content_hash: Some("hash".to_string()),
             created_at: "2025-01-01".to_string(),
+            toolchain: None,
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            covered: None,
+            project: None,
+            issue_url: None,
+            status: "new".to_string(),
         };
 
         let view = MutationResultView::from_result(result, "/repo/path");
@@ -282,16 +907,461 @@ mod tests {
             file_path: "/other/path/src/main.rs".to_string(),
             description: "test".to_string(),
             reasoning: "reason".to_string(),
-            replacements_json: "{}".to_string(),
+            replacements: Vec::new(),
             test_outcome: "killed".to_string(),
             killing_test: None,
             test_output: None,
             execution_time_ms: None,
             content_hash: None,
             created_at: "2025-01-01".to_string(),
+            toolchain: None,
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            covered: None,
+            project: None,
+            issue_url: None,
+            status: "new".to_string(),
         };
 
         let view = MutationResultView::from_result(result, "/repo/path");
         assert_eq!(view.file_path, "/other/path/src/main.rs");
     }
+
+    #[test]
+    fn test_pages_render_with_builtin_templates() {
+        let templates = crate::theme::Templates::new(None).strict();
+        let repository = Repository {
+            id: 1,
+            path: "/repo".to_string(),
+            name: "app".to_string(),
+            enabled: true,
+            created_at: "2025-01-01".to_string(),
+            updated_at: "2025-01-01".to_string(),
+            analysis_types: None,
+            sensitive: false,
+            remote_url: Some("https://github.com/a/app.git".to_string()),
+            branch: Some("main".to_string()),
+            tags: Vec::new(),
+            archived_at: None,
+            commands: Default::default(),
+        };
+        let analysis = AnalysisResult {
+            id: 1,
+            repository_id: 1,
+            file_path: "/repo/src/lib.rs".to_string(),
+            analysis_type: "code_understanding".to_string(),
+            result: "Summary".to_string(),
+            severity: Some("info".to_string()),
+            content_hash: None,
+            created_at: "2025-01-01".to_string(),
+            endpoint: None,
+            model: None,
+            duration_ms: None,
+            issue_url: None,
+        };
+        let mutation = |test_outcome: &str, toolchain: Option<&str>| MutationResultView {
+            id: 1,
+            repository_id: 1,
+            file_path: "src/lib.rs".to_string(),
+            description: "Changed > to >=".to_string(),
+            reasoning: "Boundary".to_string(),
+            replacements: vec![ReplacementView {
+                line_number: 3,
+                original: "if a > b {".to_string(),
+                mutated: "if a >= b {".to_string(),
+            }],
+            test_outcome: test_outcome.to_string(),
+            killing_test: toolchain.map(|_| "tests::boundary".to_string()),
+            test_output: Some("output".to_string()),
+            execution_time_ms: None,
+            content_hash: None,
+            created_at: "2025-01-01".to_string(),
+            toolchain: toolchain.map(str::to_string),
+            project: toolchain.map(|_| "noctum-core".to_string()),
+            status: "new".to_string(),
+            test_suggestions: vec![MutationTestSuggestion {
+                mutation_result_id: 1,
+                name: "max_prefers_first_on_tie".to_string(),
+                scenario: "Call with equal values".to_string(),
+                assertion: "assert_eq!(max(2, 2), 2);".to_string(),
+            }],
+        };
+        let diagram = Diagram {
+            id: 1,
+            repository_id: 1,
+            diagram_type: "system_architecture".to_string(),
+            title: "System".to_string(),
+            description: "Overview".to_string(),
+            format: "dot".to_string(),
+            dot_content: "digraph {}".to_string(),
+            svg_content: "<svg></svg>".to_string(),
+            content_hash: None,
+            created_at: "2025-01-01".to_string(),
+        };
+
+        let html = templates
+            .render_page(&RepositoriesTemplate {
+                repositories: vec![
+                    RepositoryView::new(repository.clone(), Vec::new()),
+                    RepositoryView::new(
+                        repository.clone(),
+                        vec![score(40.0), score(47.8), score(62.5)],
+                    ),
+                ],
+                analysis_passes: ANALYSIS_PASSES,
+                tags: vec!["personal".to_string(), "work".to_string()],
+                selected_tag: Some("work".to_string()),
+                archived: false,
+                archived_count: 2,
+            })
+            .unwrap();
+        assert!(html.contains("app"));
+        assert!(html.contains("/repositories?tag=personal"));
+        assert!(html.contains("Archived (2)"));
+        assert!(html.contains("archiveRepository(1, 'app')"));
+        assert!(html.contains(r#"points="0.0,14.4 50.0,12.5 100.0,9.0""#));
+        assert!(html.contains("62.5"));
+        assert!(html.contains("+22.5"));
+        assert!(html.contains("Kill rate: 50%, findings: n/a"));
+
+        let html = templates
+            .render_page(&RepositoryArchitectureTemplate {
+                repository: repository.clone(),
+                architecture_summary: Some(analysis.clone()),
+                architecture_summary_html: "<p>Layered</p>".to_string(),
+                onboarding: None,
+            })
+            .unwrap();
+        assert!(html.contains("<p>Layered</p>"));
+        let html = templates
+            .render_page(&RepositoryArchitectureTemplate {
+                repository: repository.clone(),
+                architecture_summary: None,
+                architecture_summary_html: String::new(),
+                onboarding: Some(Onboarding {
+                    repository_id: 1,
+                    status: "ready".to_string(),
+                    quick_scan: crate::onboarding::QuickScan {
+                        projects: vec![crate::onboarding::DetectedProject {
+                            name: "myapp-api".to_string(),
+                            path: "api".to_string(),
+                            language: "Rust".to_string(),
+                            kind: "workspace member".to_string(),
+                            files: 12,
+                        }],
+                        languages: vec![crate::onboarding::LanguageCount {
+                            language: "Rust".to_string(),
+                            files: 12,
+                        }],
+                        file_count: 12,
+                        readme_summary: Some("An API server.".to_string()),
+                    },
+                    error: None,
+                    created_at: "2025-01-01".to_string(),
+                    updated_at: "2025-01-01".to_string(),
+                }),
+            })
+            .unwrap();
+        assert!(html.contains("myapp-api"));
+        assert!(html.contains("An API server."));
+        assert!(html.contains("Confirm and Scan"));
+
+        let html = templates
+            .render_page(&RepositoryFilesTemplate {
+                repository: repository.clone(),
+                file_results: vec![AnalysisResultView::from_result(analysis.clone(), "/repo")],
+                skipped_files: vec![SkippedFileView {
+                    file_path: "dist/app.min.js".to_string(),
+                    reason: "minified".to_string(),
+                    updated_at: "2025-01-01".to_string(),
+                }],
+                findings: vec![FindingView {
+                    file_path: "src/lib.rs".to_string(),
+                    title: "Unchecked index".to_string(),
+                    severity: "warning".to_string(),
+                    category: "error_handling".to_string(),
+                    lines: "3-4".to_string(),
+                    recommendation: "Use get()".to_string(),
+                    first_seen: "2025-01-01".to_string(),
+                    occurrences: 3,
+                    resolved_at: String::new(),
+                }],
+                finding_categories: vec!["error_handling".to_string()],
+                resolved_findings: vec![FindingView {
+                    file_path: "src/main.rs".to_string(),
+                    title: "Unused result".to_string(),
+                    severity: "info".to_string(),
+                    category: "bug".to_string(),
+                    lines: String::new(),
+                    recommendation: String::new(),
+                    first_seen: "2025-01-01".to_string(),
+                    occurrences: 1,
+                    resolved_at: "2025-02-01".to_string(),
+                }],
+                reanalysis_requested: vec!["src/lib.rs".to_string()],
+                stale_files: 2,
+            })
+            .unwrap();
+        assert!(html.contains("error handling"));
+        assert!(html.contains("2 deleted files still"));
+        assert!(html.contains("Queued"));
+        assert!(html.contains("lib.rs:3-4"));
+        assert!(html.contains("Reported by 3 scans since 2025-01-01"));
+        assert!(html.contains("Resolved 2025-02-01"));
+
+        let html = templates
+            .render_page(&FileHistoryTemplate {
+                repository: repository.clone(),
+                file_path: "src/lib.rs".to_string(),
+                analysis_types: vec![
+                    "code_understanding".to_string(),
+                    "plugin_license".to_string(),
+                ],
+                analysis_type: "code_understanding".to_string(),
+                entries: vec![
+                    HistoryEntryView::from_entry(
+                        analysis.clone(),
+                        Some("@@ -1 +1 @@\n-Parses input\n+Parses and validates input\n"),
+                    ),
+                    HistoryEntryView::from_entry(analysis.clone(), Some("")),
+                    HistoryEntryView::from_entry(analysis.clone(), None),
+                ],
+            })
+            .unwrap();
+        assert!(html.contains("<div class=\"diff-line added\">Parses and validates input</div>"));
+        assert!(html.contains("Unchanged from the previous analysis"));
+        assert!(html.contains("First analysis"));
+        assert!(html.contains("<option value=\"code_understanding\" selected>"));
+
+        let html = templates
+            .render_page(&MutationResultsTemplate {
+                repository: repository.clone(),
+                results: vec![
+                    mutation("survived", Some("1.80")),
+                    mutation("rejected", None),
+                ],
+                summary: MutationSummary {
+                    covered_survived: Some(1),
+                    ..MutationSummary::default()
+                },
+                mutation_score_percent: "50".to_string(),
+                deferral: Some(MutationDeferral {
+                    repository_id: repository.id,
+                    pending_files: 4,
+                    estimated_seconds: 5400,
+                    remaining_window_seconds: 600,
+                    deferred_at: "2025-01-01 05:50:00".to_string(),
+                }),
+            })
+            .unwrap();
+        assert!(html.contains("Covered but Survived"));
+        assert!(html.contains("4 file(s) (~90 min) deferred"));
+        assert!(html.contains("tests::boundary"));
+        assert!(html.contains("<code>noctum-core</code>"));
+        assert!(html.contains("<code>max_prefers_first_on_tie</code>: Call with equal values"));
+        assert!(html.contains("<span>if a &gt;= b {</span>"));
+        assert!(html.contains("onchange=\"setStatus(1, this)\""));
+        assert!(html.contains("Untriaged"));
+
+        let html = templates
+            .render_page(&RepositoryDiagramsTemplate {
+                repository: repository.clone(),
+                diagrams: vec![
+                    diagram.clone(),
+                    Diagram {
+                        format: "mermaid".to_string(),
+                        dot_content: "flowchart LR\n    a --> b".to_string(),
+                        svg_content: String::new(),
+                        ..diagram.clone()
+                    },
+                ],
+                versions: HashMap::from([(diagram.diagram_type.clone(), vec![3, diagram.id])]),
+            })
+            .unwrap();
+        assert!(html.contains("<svg></svg>"));
+        assert!(html.contains(&format!("data-versions=\"3,{}\"", diagram.id)));
+        assert!(html.contains(&format!("href=\"/api/diagrams/{}/dot\"", diagram.id)));
+        assert!(html.contains("<pre class=\"mermaid\">flowchart LR\n    a --&gt; b</pre>"));
+        assert!(html.contains("View Mermaid Source"));
+
+        let html = templates
+            .render_page(&RepositoryChatTemplate {
+                repository: repository.clone(),
+                messages: vec![ChatMessageView::from(ChatMessage {
+                    id: 1,
+                    repository_id: 1,
+                    role: "assistant".to_string(),
+                    content: "Auth is in `src/auth.rs`".to_string(),
+                    sources: vec!["src/auth.rs".to_string()],
+                    created_at: "2025-01-01".to_string(),
+                })],
+                search_enabled: false,
+            })
+            .unwrap();
+        assert!(html.contains("Auth is in <code>src/auth.rs</code>"));
+        assert!(html.contains("chat-sources"));
+        assert!(html.contains("matching words"));
+
+        let html = templates
+            .render_page(&RepositoryChatTemplate {
+                repository: repository.clone(),
+                messages: Vec::new(),
+                search_enabled: true,
+            })
+            .unwrap();
+        assert!(html.contains("where is auth handled?"));
+
+        let html = templates
+            .render_page(&RepositoryDuplicationTemplate {
+                repository: repository.clone(),
+                pairs: Vec::new(),
+                clusters: Vec::new(),
+            })
+            .unwrap();
+        assert!(html.contains("No duplicated code found"));
+
+        let cluster = DuplicateCluster {
+            id: 1,
+            repository_id: 1,
+            fingerprint: "abc".to_string(),
+            line_count: 8,
+            locations: ["/repo/a.rs", "/repo/b.rs"]
+                .iter()
+                .map(|path| crate::db::DuplicateLocation {
+                    file_path: path.to_string(),
+                    line_start: 3,
+                    line_end: 12,
+                    file_lines: 40,
+                })
+                .collect(),
+            created_at: "2024-01-01 00:00:00".to_string(),
+        };
+        let html = templates
+            .render_page(&RepositoryDuplicationTemplate {
+                repository,
+                pairs: crate::duplication::file_pairs(std::slice::from_ref(&cluster))
+                    .into_iter()
+                    .map(|pair| DuplicatePairView::from_pair(pair, "/repo"))
+                    .collect(),
+                clusters: vec![DuplicateClusterView::from_cluster(cluster, "/repo")],
+            })
+            .unwrap();
+        assert!(html.contains("<code>b.rs</code>"));
+        assert!(html.contains("20.0%"));
+        assert!(html.contains("a.rs:3-12"));
+
+        let html = templates.render_page(&SearchTemplate {}).unwrap();
+        assert!(html.contains("/api/search"));
+
+        let html = templates
+            .render_page(&FindingsTemplate {
+                findings: vec![],
+                severity: String::new(),
+                recent_first: false,
+            })
+            .unwrap();
+        assert!(html.contains("No open errors or warnings"));
+
+        let finding = AnalysisFinding {
+            id: 1,
+            analysis_result_id: 1,
+            repository_id: 3,
+            file_path: "/repo/src/db.rs".to_string(),
+            title: "SQL injection".to_string(),
+            severity: "error".to_string(),
+            category: "security".to_string(),
+            line_start: Some(12),
+            line_end: None,
+            recommendation: "Bind the parameter".to_string(),
+            fingerprint: None,
+            first_seen: Some("2025-01-02T03:04:05.000Z".to_string()),
+            last_seen: None,
+            occurrences: 1,
+            resolved_at: None,
+        };
+        let html = templates
+            .render_page(&FindingsTemplate {
+                findings: vec![GlobalFindingView {
+                    repository_id: 3,
+                    repository_name: "myapp".to_string(),
+                    finding: FindingView::from_finding(finding, "/repo"),
+                }],
+                severity: "error".to_string(),
+                recent_first: true,
+            })
+            .unwrap();
+        assert!(html.contains("SQL injection"));
+        assert!(html.contains("data-repository=\"3\""));
+        assert!(html.contains("db.rs:12"));
+        assert!(html.contains("<option value=\"error\" selected>"));
+
+        let html = templates.render_page(&LogsTemplate {}).unwrap();
+        assert!(html.contains("/api/logs/stream"));
+
+        let html = templates
+            .render_page(&HistoryTemplate { runs: vec![] })
+            .unwrap();
+        assert!(html.contains("No processing cycles recorded yet"));
+
+        let run = ScanRun {
+            id: 1,
+            trigger: "scheduled".to_string(),
+            status: "failed".to_string(),
+            started_at: "2025-01-01T02:00:00.000Z".to_string(),
+            finished_at: Some("2025-01-01T03:05:09.000Z".to_string()),
+            repositories_processed: 2,
+            files_analyzed: 41,
+            mutations_run: 12,
+            errors: 1,
+            last_error: Some("myapp: build failed".to_string()),
+        };
+        let view = ScanRunView::from(run);
+        assert_eq!(view.duration.as_deref(), Some("1h 05m"));
+        let html = templates
+            .render_page(&HistoryTemplate { runs: vec![view] })
+            .unwrap();
+        assert!(html.contains("run-failed"));
+        assert!(html.contains("took 1h 05m"));
+        assert!(html.contains("41 files analyzed"));
+        assert!(html.contains("myapp: build failed"));
+
+        let html = templates
+            .render_page(&SettingsTemplate {
+                endpoints: vec![OllamaEndpoint::new(
+                    "gpu".to_string(),
+                    "http://localhost:11434".to_string(),
+                    "llama3".to_string(),
+                )],
+                health: vec![],
+                daily_usage: vec![DailyUsage {
+                    day: "2025-01-01".to_string(),
+                    requests: 1,
+                    prompt_tokens: 10,
+                    completion_tokens: 5,
+                    retries: 1,
+                    rejected: 0,
+                }],
+                usage_totals: vec![UsageTotal {
+                    endpoint: "gpu".to_string(),
+                    repository_id: 1,
+                    repository_name: None,
+                    analysis_type: "code_understanding".to_string(),
+                    requests: 1,
+                    prompt_tokens: 10,
+                    completion_tokens: 5,
+                    retries: 1,
+                    rejected: 0,
+                }],
+                start_hour: 22,
+                end_hour: 6,
+                schedule_windows: vec!["weekends Sat/Sun 00:00 - 24:00".to_string()],
+                paused_until: Some("2025-01-02 08:00".to_string()),
+                config_path: "/etc/noctum.toml".to_string(),
+            })
+            .unwrap();
+        assert!(html.contains("(deleted)"));
+        assert!(html.contains("weekends Sat"));
+        assert!(html.contains("Paused until 2025-01-02 08:00"));
+    }
 }